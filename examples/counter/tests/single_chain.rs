@@ -5,7 +5,7 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use linera_sdk::test::{QueryOutcome, TestValidator};
+use linera_sdk::test::{ProfileMetric, QueryOutcome, TestValidator};
 
 /// Test setting a counter and testing its coherency across microchains.
 ///
@@ -35,3 +35,41 @@ async fn single_chain_test() {
     let state_value = response["value"].as_u64().expect("Failed to get the u64");
     assert_eq!(state_value, final_value);
 }
+
+/// Test profiling an increment of the counter without adding the block.
+///
+/// The fuel of the block is attributed to the functions of the contract under the
+/// `execute_operation` entry point, and the counter keeps its value.
+#[tokio::test(flavor = "multi_thread")]
+async fn profile_test() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<counter::CounterAbi, (), u64>().await;
+    let mut chain = validator.new_chain().await;
+
+    let initial_state = 42u64;
+    let application_id = chain
+        .create_application(module_id, (), initial_state, vec![])
+        .await;
+
+    let profile = chain
+        .profile_block(|block| {
+            block.with_operation(application_id, 15u64);
+        })
+        .await;
+
+    let frame = format!("{}::execute_operation", application_id.forget_abi());
+    assert!(profile.total().fuel > 0);
+    assert!(profile
+        .folded(ProfileMetric::Fuel)
+        .to_string()
+        .lines()
+        .all(|line| line.starts_with(&frame)));
+    assert!(profile.stacks.contains_key(&vec![
+        frame,
+        "linera:app/contract-entrypoints#execute-operation".to_owned(),
+    ]));
+
+    let QueryOutcome { response, .. } =
+        chain.graphql_query(application_id, "query { value }").await;
+    assert_eq!(response["value"].as_u64(), Some(initial_state));
+}
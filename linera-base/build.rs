@@ -9,6 +9,7 @@ fn main() {
         with_reqwest: { feature = "reqwest" },
         with_testing: { any(test, feature = "test") },
         with_revm: { any(test, feature = "revm") },
+        with_hd: { feature = "hd" },
 
         // the old version of `getrandom` we pin here is available on all targets, but
         // using it will panic if no suitable source of entropy is found
@@ -0,0 +1,282 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hierarchical deterministic derivation of account keys from a BIP-39 mnemonic.
+//!
+//! Ed25519 keys are derived following [SLIP-10], which only supports hardened derivation,
+//! and secp256k1 keys following [BIP-32]. EVM keys use the standard Ethereum path
+//! `m/44'/60'/0'/0/{index}`, so that they match the accounts of other Ethereum wallets
+//! restored from the same mnemonic.
+//!
+//! [SLIP-10]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//! [BIP-32]: https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+
+use std::{fmt, str::FromStr};
+
+use ed25519_dalek as dalek;
+use hmac::{Hmac, Mac as _};
+use k256::{ecdsa::SigningKey, elliptic_curve::PrimeField as _, FieldBytes, NonZeroScalar, Scalar};
+use sha2::Sha512;
+use zeroize::Zeroizing;
+
+use super::{
+    ed25519::Ed25519SecretKey,
+    secp256k1::{evm::EvmSecretKey, Secp256k1SecretKey},
+    AccountSecretKey, CryptoError, SignatureScheme,
+};
+
+/// The coin type used in the derivation paths of Linera keys.
+pub const LINERA_COIN_TYPE: u32 = 0x4c4e;
+/// The coin type used in the derivation paths of Ethereum keys.
+pub const ETHEREUM_COIN_TYPE: u32 = 60;
+
+/// The length of the entropy of generated mnemonics, in bytes. This gives 24 words.
+const ENTROPY_LENGTH: usize = 32;
+/// The offset of hardened child indices.
+const HARDENED: u32 = 1 << 31;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// A BIP-39 mnemonic phrase, from which any number of account keys can be derived.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic(bip39::Mnemonic);
+
+impl Mnemonic {
+    /// Generates a new 24-word mnemonic from the given RNG.
+    #[cfg(with_getrandom)]
+    pub fn generate_from<R: super::CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut entropy = Zeroizing::new([0u8; ENTROPY_LENGTH]);
+        rng.fill_bytes(entropy.as_mut_slice());
+        let mnemonic = bip39::Mnemonic::from_entropy(entropy.as_slice())
+            .expect("32 bytes is a valid entropy length");
+        Mnemonic(mnemonic)
+    }
+
+    /// Returns the number of words of this mnemonic.
+    pub fn word_count(&self) -> usize {
+        self.0.word_count()
+    }
+
+    /// Derives the secret key with the given `index` for the signature `scheme`.
+    ///
+    /// The derivation paths are:
+    /// * Ed25519: `m/44'/{LINERA_COIN_TYPE}'/{index}'/0'/0'`,
+    /// * secp256k1: `m/44'/{LINERA_COIN_TYPE}'/0'/0/{index}`,
+    /// * EVM secp256k1: `m/44'/60'/0'/0/{index}`.
+    pub fn derive_key(
+        &self,
+        scheme: SignatureScheme,
+        index: u32,
+    ) -> Result<AccountSecretKey, CryptoError> {
+        if index >= HARDENED {
+            return Err(CryptoError::InvalidDerivationIndex(index));
+        }
+        let seed = Zeroizing::new(self.0.to_seed(""));
+        Ok(match scheme {
+            SignatureScheme::Ed25519 => {
+                let path = [
+                    44 | HARDENED,
+                    LINERA_COIN_TYPE | HARDENED,
+                    index | HARDENED,
+                    HARDENED,
+                    HARDENED,
+                ];
+                let secret = derive_ed25519(seed.as_slice(), &path);
+                AccountSecretKey::Ed25519(Ed25519SecretKey(dalek::SigningKey::from_bytes(&secret)))
+            }
+            SignatureScheme::Secp256k1 => {
+                let path = [
+                    44 | HARDENED,
+                    LINERA_COIN_TYPE | HARDENED,
+                    HARDENED,
+                    0,
+                    index,
+                ];
+                let key = derive_secp256k1(seed.as_slice(), &path)?;
+                AccountSecretKey::Secp256k1(Secp256k1SecretKey(key))
+            }
+            SignatureScheme::EvmSecp256k1 => {
+                let path = [
+                    44 | HARDENED,
+                    ETHEREUM_COIN_TYPE | HARDENED,
+                    HARDENED,
+                    0,
+                    index,
+                ];
+                let key = derive_secp256k1(seed.as_slice(), &path)?;
+                AccountSecretKey::EvmSecp256k1(EvmSecretKey(key))
+            }
+        })
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted for Mnemonic>")
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl FromStr for Mnemonic {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mnemonic = bip39::Mnemonic::parse(s)
+            .map_err(|error| CryptoError::InvalidMnemonic(error.to_string()))?;
+        Ok(Mnemonic(mnemonic))
+    }
+}
+
+/// Computes HMAC-SHA512 of the concatenation of `parts`, with the given `key`.
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    let mut output = Zeroizing::new([0u8; 64]);
+    output.copy_from_slice(&mac.finalize().into_bytes());
+    output
+}
+
+/// Splits the output of HMAC-SHA512 into a key and a chain code.
+fn split(output: &[u8; 64]) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut chain_code = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&output[..32]);
+    chain_code.copy_from_slice(&output[32..]);
+    (key, chain_code)
+}
+
+/// Derives an Ed25519 secret key along a path of hardened indices, following SLIP-10.
+fn derive_ed25519(seed: &[u8], path: &[u32]) -> Zeroizing<[u8; 32]> {
+    let (mut key, mut chain_code) = split(&hmac_sha512(b"ed25519 seed", &[seed]));
+    for index in path {
+        debug_assert!(
+            index & HARDENED != 0,
+            "Ed25519 only supports hardened derivation"
+        );
+        let output = hmac_sha512(
+            chain_code.as_slice(),
+            &[&[0u8], key.as_slice(), &index.to_be_bytes()],
+        );
+        (key, chain_code) = split(&output);
+    }
+    key
+}
+
+/// Derives a secp256k1 secret key along a path, following BIP-32.
+fn derive_secp256k1(seed: &[u8], path: &[u32]) -> Result<SigningKey, CryptoError> {
+    let (key, mut chain_code) = split(&hmac_sha512(b"Bitcoin seed", &[seed]));
+    let mut key = SigningKey::from_slice(key.as_slice()).map_err(CryptoError::Secp256k1Error)?;
+    for index in path {
+        let output = if index & HARDENED != 0 {
+            hmac_sha512(
+                chain_code.as_slice(),
+                &[&[0u8], &key.to_bytes(), &index.to_be_bytes()],
+            )
+        } else {
+            let public_key = key.verifying_key().to_encoded_point(true);
+            hmac_sha512(
+                chain_code.as_slice(),
+                &[public_key.as_bytes(), &index.to_be_bytes()],
+            )
+        };
+        let (tweak, next_chain_code) = split(&output);
+        let tweak = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::clone_from_slice(
+            tweak.as_slice(),
+        )))
+        .ok_or(CryptoError::InvalidDerivedKey)?;
+        let scalar = Option::<NonZeroScalar>::from(NonZeroScalar::new(
+            tweak + key.as_nonzero_scalar().as_ref(),
+        ))
+        .ok_or(CryptoError::InvalidDerivedKey)?;
+        key = SigningKey::from(scalar);
+        chain_code = next_chain_code;
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::CryptoRng;
+
+    /// The first test vector of SLIP-10 for Ed25519.
+    #[test]
+    fn test_slip10_ed25519_vector() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = derive_ed25519(&seed, &[]);
+        assert_eq!(
+            hex::encode(key.as_slice()),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        let key = derive_ed25519(&seed, &[HARDENED]);
+        assert_eq!(
+            hex::encode(key.as_slice()),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+    }
+
+    /// The first test vector of BIP-32.
+    #[test]
+    fn test_bip32_secp256k1_vector() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let key = derive_secp256k1(&seed, &[]).unwrap();
+        assert_eq!(
+            hex::encode(key.to_bytes()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        let key = derive_secp256k1(&seed, &[HARDENED, 1]).unwrap();
+        assert_eq!(
+            hex::encode(key.to_bytes()),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+    }
+
+    /// EVM keys must match the accounts of other Ethereum wallets.
+    #[test]
+    fn test_evm_derivation_matches_ethereum_wallets() {
+        let mnemonic: Mnemonic = "test test test test test test test test test test test junk"
+            .parse()
+            .unwrap();
+        let AccountSecretKey::EvmSecp256k1(key) = mnemonic
+            .derive_key(SignatureScheme::EvmSecp256k1, 0)
+            .unwrap()
+        else {
+            panic!("unexpected key type");
+        };
+        assert_eq!(
+            hex::encode(key.0.to_bytes()),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+    }
+
+    #[test]
+    fn test_derivation_is_deterministic() {
+        let mut rng: Box<dyn CryptoRng> = Some(0).into();
+        let mnemonic = Mnemonic::generate_from(&mut *rng);
+        assert_eq!(mnemonic.word_count(), 24);
+        let restored: Mnemonic = mnemonic.to_string().parse().unwrap();
+        for scheme in [
+            SignatureScheme::Ed25519,
+            SignatureScheme::Secp256k1,
+            SignatureScheme::EvmSecp256k1,
+        ] {
+            let key0 = mnemonic.derive_key(scheme, 0).unwrap();
+            let key1 = mnemonic.derive_key(scheme, 1).unwrap();
+            assert_ne!(key0.public(), key1.public());
+            assert_eq!(
+                key0.public(),
+                restored.derive_key(scheme, 0).unwrap().public()
+            );
+        }
+        assert!(mnemonic
+            .derive_key(SignatureScheme::Ed25519, HARDENED)
+            .is_err());
+    }
+}
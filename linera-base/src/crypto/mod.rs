@@ -6,6 +6,8 @@
 
 mod ed25519;
 mod hash;
+#[cfg(with_hd)]
+pub mod hd;
 #[allow(dead_code)]
 mod secp256k1;
 use std::{fmt::Display, io, num::ParseIntError, str::FromStr};
@@ -42,6 +44,19 @@ pub enum SignatureScheme {
     EvmSecp256k1,
 }
 
+impl FromStr for SignatureScheme {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(SignatureScheme::Ed25519),
+            "secp256k1" => Ok(SignatureScheme::Secp256k1),
+            "evm-secp256k1" | "evm" => Ok(SignatureScheme::EvmSecp256k1),
+            _ => Err(CryptoError::UnknownSignatureScheme(s.to_string())),
+        }
+    }
+}
+
 /// The public key of a chain owner.
 /// The corresponding private key is allowed to propose blocks
 /// on the chain and transfer account's tokens.
@@ -285,6 +300,14 @@ pub enum CryptoError {
     PublicKeyParseError(bcs::Error),
     #[error("could not parse signature: {0}")]
     SignatureParseError(bcs::Error),
+    #[error("unknown signature scheme: {0}")]
+    UnknownSignatureScheme(String),
+    #[error("invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("derivation index {0} is out of range")]
+    InvalidDerivationIndex(u32),
+    #[error("key derivation produced an invalid key")]
+    InvalidDerivedKey,
 }
 
 #[cfg(with_getrandom)]
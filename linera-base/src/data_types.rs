@@ -262,6 +262,39 @@ impl Display for Timestamp {
     }
 }
 
+/// When a timer set by an application becomes due.
+#[derive(
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Copy,
+    Clone,
+    Hash,
+    Debug,
+    Serialize,
+    Deserialize,
+    WitType,
+    WitLoad,
+    WitStore,
+)]
+pub enum TimerDeadline {
+    /// The timer is due in blocks with at least this timestamp.
+    Timestamp(Timestamp),
+    /// The timer is due in blocks with at least this height.
+    BlockHeight(BlockHeight),
+}
+
+impl TimerDeadline {
+    /// Returns whether the timer is due in a block with the given height and timestamp.
+    pub fn is_due(&self, height: BlockHeight, timestamp: Timestamp) -> bool {
+        match self {
+            TimerDeadline::Timestamp(deadline) => *deadline <= timestamp,
+            TimerDeadline::BlockHeight(deadline) => *deadline <= height,
+        }
+    }
+}
+
 /// Resources that an application may spend during the execution of transaction or an
 /// application call.
 #[derive(
@@ -827,6 +860,10 @@ pub struct ApplicationPermissions {
     #[graphql(default)]
     #[debug(skip_if = Option::is_none)]
     pub make_http_requests: Option<Vec<ApplicationId>>,
+    /// These applications can be upgraded to new bytecode by the owners of this chain.
+    #[graphql(default)]
+    #[debug(skip_if = Vec::is_empty)]
+    pub upgrade_applications: Vec<ApplicationId>,
 }
 
 impl ApplicationPermissions {
@@ -840,6 +877,7 @@ impl ApplicationPermissions {
             change_application_permissions: vec![app_id],
             call_service_as_oracle: Some(vec![app_id]),
             make_http_requests: Some(vec![app_id]),
+            upgrade_applications: Vec::new(),
         }
     }
 
@@ -878,6 +916,11 @@ impl ApplicationPermissions {
             .map(|app_ids| app_ids.contains(app_id))
             .unwrap_or(true)
     }
+
+    /// Returns whether the owners of this chain can upgrade the given application.
+    pub fn can_upgrade_application(&self, app_id: &ApplicationId) -> bool {
+        self.upgrade_applications.contains(app_id)
+    }
 }
 
 /// A record of a single oracle response.
@@ -13,7 +13,7 @@ use std::{
 #[cfg(with_revm)]
 use alloy_primitives::{Address, B256};
 use anyhow::{anyhow, Context};
-use async_graphql::SimpleObject;
+use async_graphql::{InputObject, SimpleObject};
 use custom_debug_derive::Debug;
 use linera_witty::{WitLoad, WitStore, WitType};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -497,7 +497,9 @@ where
     WitStore,
     WitType,
     SimpleObject,
+    InputObject,
 )]
+#[graphql(input_name = "StreamIdInput")]
 pub struct StreamId {
     /// The application that can add events to this stream.
     pub application_id: GenericApplicationId,
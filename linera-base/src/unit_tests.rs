@@ -5,6 +5,7 @@
 
 use std::fmt::Debug;
 
+use alloy_primitives::{Address, Bytes, B256};
 use linera_witty::{Layout, WitLoad, WitStore};
 use test_case::test_case;
 
@@ -16,7 +17,7 @@ use crate::{
         ModuleId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
-    vm::VmRuntime,
+    vm::{EvmLog, EvmLogFilter, VmRuntime, EVM_LOG_VERSION},
 };
 
 /// Test roundtrip of types used in the WIT interface.
@@ -165,3 +166,44 @@ fn chain_ownership_test_case() -> ChainOwnership {
         },
     }
 }
+
+/// Tests that EVM logs survive an encoding roundtrip and are matched like `eth_getLogs` does.
+#[test]
+fn test_evm_log_filter() {
+    let log = EvmLog {
+        origin: "operation".to_string(),
+        address: Address::with_last_byte(1),
+        topics: vec![B256::with_last_byte(10), B256::with_last_byte(11)],
+        data: Bytes::from(vec![1, 2, 3]),
+    };
+    let bytes = log.to_event_value().unwrap();
+    assert_eq!(bytes[0], EVM_LOG_VERSION);
+    assert_eq!(EvmLog::from_event_value(&bytes).unwrap(), Some(log.clone()));
+    // Logs encoded without a version are not decoded.
+    let old_bytes = bcs::to_bytes(&log).unwrap();
+    assert_eq!(EvmLog::from_event_value(&old_bytes).unwrap(), None);
+    assert!(EvmLog::from_event_value(&bytes[..bytes.len() - 1]).is_err());
+
+    assert!(EvmLogFilter::default().matches(&log));
+    let filter = EvmLogFilter {
+        addresses: vec![Address::with_last_byte(2)],
+        topics: Vec::new(),
+    };
+    assert!(!filter.matches(&log));
+    let filter = EvmLogFilter {
+        addresses: vec![Address::with_last_byte(1), Address::with_last_byte(2)],
+        topics: vec![None, Some(vec![B256::with_last_byte(11)])],
+    };
+    assert!(filter.matches(&log));
+    let filter = EvmLogFilter {
+        addresses: Vec::new(),
+        topics: vec![Some(vec![B256::with_last_byte(11)])],
+    };
+    assert!(!filter.matches(&log));
+    // A log without a topic at a position of the filter never matches.
+    let filter = EvmLogFilter {
+        addresses: Vec::new(),
+        topics: vec![None, None, None],
+    };
+    assert!(!filter.matches(&log));
+}
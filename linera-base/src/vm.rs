@@ -5,12 +5,15 @@
 
 use std::str::FromStr;
 
+use alloy_primitives::{Address, Bytes, B256};
 use async_graphql::scalar;
 use derive_more::Display;
 use linera_witty::{WitLoad, WitStore, WitType};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::identifiers::StreamName;
+
 #[derive(
     Clone,
     Copy,
@@ -64,4 +67,79 @@ pub enum EvmQuery {
     Query(Vec<u8>),
     /// A request to schedule an operation that can mutate the application state.
     Mutation(Vec<u8>),
+    /// A read-only execution of the operation, returning the gas it used.
+    EstimateGas(Vec<u8>),
+}
+
+/// Returns the name of the stream where an EVM application emits the logs of its contract, as
+/// [`EvmLog`] values encoded with [`EvmLog::to_event_value`].
+pub fn evm_log_stream_name() -> StreamName {
+    StreamName(bcs::to_bytes("ethereum_event").expect("a string can be serialized"))
+}
+
+/// A log emitted by an EVM contract, as stored in the events of its application.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvmLog {
+    /// Whether the log was emitted when deploying the contract (`deploy`) or when executing
+    /// an operation (`operation`).
+    pub origin: String,
+    /// The address of the contract that emitted the log. The contract of the application has
+    /// the address of the application.
+    pub address: Address,
+    /// The indexed topics of the log. The first one is usually the hash of the event
+    /// signature.
+    pub topics: Vec<B256>,
+    /// The non-indexed data of the log.
+    pub data: Bytes,
+}
+
+/// The version of the encoding of [`EvmLog`] events, written as their first byte.
+///
+/// The first logs were written without a version: their first byte is the length of their
+/// origin, e.g. 9 for `operation`, so it never equals 1.
+pub const EVM_LOG_VERSION: u8 = 1;
+
+impl EvmLog {
+    /// Encodes the log as the value of an event: the [`EVM_LOG_VERSION`] followed by the BCS
+    /// bytes of the log.
+    pub fn to_event_value(&self) -> Result<Vec<u8>, bcs::Error> {
+        let mut bytes = vec![EVM_LOG_VERSION];
+        bytes.extend(bcs::to_bytes(self)?);
+        Ok(bytes)
+    }
+
+    /// Decodes the value of an event. Returns `None` if it has another version of the encoding,
+    /// e.g. if it was written before the encoding had a version.
+    pub fn from_event_value(bytes: &[u8]) -> Result<Option<Self>, bcs::Error> {
+        match bytes.split_first() {
+            Some((&EVM_LOG_VERSION, bytes)) => bcs::from_bytes(bytes).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A filter on EVM logs, with the semantics of `eth_getLogs`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvmLogFilter {
+    /// The accepted addresses, or all of them if empty.
+    pub addresses: Vec<Address>,
+    /// The accepted values of the topics at each position. `None` or an empty list accepts
+    /// any value, but the log must still have a topic at that position.
+    pub topics: Vec<Option<Vec<B256>>>,
+}
+
+impl EvmLogFilter {
+    /// Returns whether the log passes the filter.
+    pub fn matches(&self, log: &EvmLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics.iter().enumerate().all(|(position, accepted)| {
+            log.topics.get(position).is_some_and(|topic| {
+                accepted
+                    .as_ref()
+                    .is_none_or(|accepted| accepted.is_empty() || accepted.contains(topic))
+            })
+        })
+    }
 }
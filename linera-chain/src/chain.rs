@@ -25,8 +25,9 @@ use linera_base::{
 };
 use linera_execution::{
     committee::Committee, system::OpenChainConfig, ExecutionRuntimeContext, ExecutionStateView,
-    Message, MessageContext, Operation, OperationContext, OutgoingMessage, Query, QueryContext,
-    QueryOutcome, ResourceController, ResourceTracker, ServiceRuntimeEndpoint, TransactionTracker,
+    ExecutionTrace, Message, MessageContext, Operation, OperationContext, OutgoingMessage, Query,
+    QueryContext, QueryOutcome, ResourceController, ResourceProfile, ResourceTracker,
+    ServiceRuntimeEndpoint, TransactionTracker,
 };
 use linera_views::{
     bucket_queue_view::BucketQueueView,
@@ -207,6 +208,28 @@ impl BundleInInbox {
 // of 100 seems reasonable for the storing of the data.
 const TIMESTAMPBUNDLE_BUCKET_SIZE: usize = 100;
 
+/// What to record while executing a block, besides its outcome.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExecutionOptions {
+    /// Whether to trace the execution of each transaction.
+    pub trace: bool,
+    /// Whether to profile the resources used by the applications.
+    pub profile: bool,
+    /// Whether to return the resources used by the block.
+    pub resources: bool,
+}
+
+/// What was recorded while executing a block, as requested by its [`ExecutionOptions`].
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionReport {
+    /// The traces of the execution of each transaction.
+    pub traces: Option<Vec<ExecutionTrace>>,
+    /// The profile of the resources used by the applications.
+    pub profile: Option<ResourceProfile>,
+    /// The resources used by the block.
+    pub resources: Option<ResourceTracker>,
+}
+
 /// A view accessing the state of a chain.
 #[derive(Debug, RootView, ClonableView, SimpleObject)]
 #[graphql(cache_control(no_cache))]
@@ -713,6 +736,8 @@ where
 
     /// Executes a block: first the incoming messages, then the main operation.
     /// Does not update chain state other than the execution state.
+    ///
+    /// The `options` select what is recorded in the returned [`ExecutionReport`].
     #[expect(clippy::too_many_arguments)]
     pub async fn execute_block_inner(
         chain: &mut ExecutionStateView<C>,
@@ -723,9 +748,11 @@ where
         round: Option<u32>,
         published_blobs: &[Blob],
         replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
+        options: ExecutionOptions,
     ) -> Result<
         (
             BlockExecutionOutcome,
+            ExecutionReport,
             Vec<(ChannelFullName, ChainId)>,
             Vec<(ChannelFullName, ChainId)>,
         ),
@@ -797,6 +824,11 @@ where
         let mut operation_results = Vec::new();
         let mut subscribe = Vec::new();
         let mut unsubscribe = Vec::new();
+        let mut report = ExecutionReport {
+            traces: options.trace.then(Vec::new),
+            profile: options.profile.then(ResourceProfile::default),
+            resources: None,
+        };
         for (txn_index, transaction) in block.transactions() {
             let chain_execution_context = match transaction {
                 Transaction::ReceiveMessages(_) => ChainExecutionContext::IncomingBundle(txn_index),
@@ -814,6 +846,12 @@ where
                 next_application_index,
                 maybe_responses,
             );
+            if options.trace {
+                txn_tracker = txn_tracker.with_trace();
+            }
+            if options.profile {
+                txn_tracker = txn_tracker.with_profile();
+            }
             match transaction {
                 Transaction::ReceiveMessages(incoming_bundle) => {
                     resource_controller
@@ -868,6 +906,12 @@ where
                 .with_execution_context(chain_execution_context)?;
             next_message_index = txn_outcome.next_message_index;
             next_application_index = txn_outcome.next_application_index;
+            if let (Some(traces), Some(trace)) = (&mut report.traces, txn_outcome.trace) {
+                traces.push(trace);
+            }
+            if let (Some(profile), Some(txn_profile)) = (&mut report.profile, txn_outcome.profile) {
+                profile.merge(txn_profile);
+            }
 
             subscribe.extend(txn_outcome.subscribe);
             unsubscribe.extend(txn_outcome.unsubscribe);
@@ -955,6 +999,9 @@ where
 
         #[cfg(with_metrics)]
         Self::track_block_metrics(&resource_controller.tracker);
+        if options.resources {
+            report.resources = Some(resource_controller.tracker);
+        }
 
         let state_hash = {
             #[cfg(with_metrics)]
@@ -972,7 +1019,7 @@ where
             operation_results,
         };
 
-        Ok((outcome, subscribe, unsubscribe))
+        Ok((outcome, report, subscribe, unsubscribe))
     }
 
     /// Executes a block: first the incoming messages, then the main operation.
@@ -992,7 +1039,33 @@ where
         ),
         ChainError,
     > {
-        Self::execute_block_inner(
+        let (outcome, _, subscribe, unsubscribe) = Self::execute_block_inner(
+            &mut self.execution_state,
+            &self.confirmed_log,
+            &self.previous_message_blocks,
+            block,
+            local_time,
+            round,
+            published_blobs,
+            replaying_oracle_responses,
+            ExecutionOptions::default(),
+        )
+        .await?;
+        Ok((outcome, subscribe, unsubscribe))
+    }
+
+    /// Executes a block like [`Self::execute_block`], and returns what the `options` ask to
+    /// record about its execution.
+    pub async fn execute_block_with_options(
+        &mut self,
+        block: &ProposedBlock,
+        local_time: Timestamp,
+        round: Option<u32>,
+        published_blobs: &[Blob],
+        replaying_oracle_responses: Option<Vec<Vec<OracleResponse>>>,
+        options: ExecutionOptions,
+    ) -> Result<(BlockExecutionOutcome, ExecutionReport), ChainError> {
+        let (outcome, report, _, _) = Self::execute_block_inner(
             &mut self.execution_state,
             &self.confirmed_log,
             &self.previous_message_blocks,
@@ -1001,8 +1074,10 @@ where
             round,
             published_blobs,
             replaying_oracle_responses,
+            options,
         )
-        .await
+        .await?;
+        Ok((outcome, report))
     }
 
     /// Applies an execution outcome to the chain, updating the outboxes, state hash and chain
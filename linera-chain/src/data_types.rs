@@ -610,27 +610,22 @@ pub struct ProposalContent {
     pub outcome: Option<BlockExecutionOutcome>,
 }
 
-impl BlockProposal {
-    pub fn new_initial(round: Round, block: ProposedBlock, secret: &AccountSecretKey) -> Self {
-        let content = ProposalContent {
-            round,
+impl ProposalContent {
+    /// Returns the content of a proposal for a new `block` in the given `round`.
+    pub fn new_initial(round: Round, block: ProposedBlock) -> Self {
+        ProposalContent {
             block,
+            round,
             outcome: None,
-        };
-        let signature = secret.sign(&content);
-        Self {
-            content,
-            public_key: secret.public(),
-            signature,
-            validated_block_certificate: None,
         }
     }
 
+    /// Returns the content of a proposal retrying the block of the given certificate in a
+    /// later `round`, together with the lite version of that certificate.
     pub fn new_retry(
         round: Round,
         validated_block_certificate: ValidatedBlockCertificate,
-        secret: &AccountSecretKey,
-    ) -> Self {
+    ) -> (Self, LiteCertificate<'static>) {
         let lite_cert = validated_block_certificate.lite_certificate().cloned();
         let block = validated_block_certificate.into_inner().into_inner();
         let (block, outcome) = block.into_proposal();
@@ -639,12 +634,40 @@ impl BlockProposal {
             round,
             outcome: Some(outcome),
         };
+        (content, lite_cert)
+    }
+}
+
+impl BlockProposal {
+    pub fn new_initial(round: Round, block: ProposedBlock, secret: &AccountSecretKey) -> Self {
+        let content = ProposalContent::new_initial(round, block);
+        let signature = secret.sign(&content);
+        Self::from_signed_content(content, secret.public(), signature, None)
+    }
+
+    pub fn new_retry(
+        round: Round,
+        validated_block_certificate: ValidatedBlockCertificate,
+        secret: &AccountSecretKey,
+    ) -> Self {
+        let (content, lite_cert) = ProposalContent::new_retry(round, validated_block_certificate);
         let signature = secret.sign(&content);
+        Self::from_signed_content(content, secret.public(), signature, Some(lite_cert))
+    }
+
+    /// Assembles a proposal from content that was signed separately, e.g. by an external
+    /// signer. The signature is not checked.
+    pub fn from_signed_content(
+        content: ProposalContent,
+        public_key: AccountPublicKey,
+        signature: AccountSignature,
+        validated_block_certificate: Option<LiteCertificate<'static>>,
+    ) -> Self {
         Self {
             content,
-            public_key: secret.public(),
+            public_key,
             signature,
-            validated_block_certificate: Some(lite_cert),
+            validated_block_certificate,
         }
     }
 
@@ -21,7 +21,7 @@ mod pending_blobs;
 #[cfg(with_testing)]
 pub mod test;
 
-pub use chain::ChainStateView;
+pub use chain::{ChainStateView, ExecutionOptions, ExecutionReport};
 use data_types::{MessageBundle, Origin, PostedMessage};
 use linera_base::{
     bcs,
@@ -13,6 +13,7 @@ use linera_base::{
 use linera_chain::{
     data_types::{BlockProposal, ProposedBlock},
     types::ConfirmedBlock,
+    ExecutionOptions,
 };
 use linera_core::{client::ChainClient, local_node::LocalNodeClient};
 use linera_execution::{
@@ -538,7 +539,12 @@ where
                 timestamp: chain_client.timestamp().max(Timestamp::now()),
             };
             let block = local_node
-                .stage_block_execution(proposed_block.clone(), None, Vec::new())
+                .stage_block_execution(
+                    proposed_block.clone(),
+                    None,
+                    Vec::new(),
+                    ExecutionOptions::default(),
+                )
                 .await
                 .map_err(BenchmarkError::LocalNode)?
                 .0;
@@ -21,7 +21,7 @@ use linera_base::{
     task::NonBlockingFuture,
 };
 use linera_core::{
-    client::{AbortOnDrop, ChainClient, ChainClientError},
+    client::{AbortOnDrop, ChainClient, ChainClientError, MessagePolicy},
     node::{NotificationStream, ValidatorNodeProvider},
     worker::{Notification, Reason},
 };
@@ -72,6 +72,9 @@ pub trait ClientContext: 'static {
 
     fn make_chain_client(&self, chain_id: ChainId) -> Result<ContextChainClient<Self>, Error>;
 
+    /// Returns the policy for handling incoming messages, shared by the chain clients.
+    fn message_policy(&self) -> &MessagePolicy;
+
     async fn update_wallet_for_new_chain(
         &mut self,
         chain_id: ChainId,
@@ -104,7 +107,8 @@ struct ListeningClient<C: ClientContext> {
     join_handle: NonBlockingFuture<()>,
     /// The stream of notifications from the local node.
     notification_stream: Arc<Mutex<NotificationStream>>,
-    /// This is only `< u64::MAX` when the client is waiting for a timeout to process the inbox.
+    /// This is only `< u64::MAX` when the client is waiting for a round timeout or for a timer
+    /// to process the inbox.
     timeout: Timestamp,
 }
 
@@ -197,7 +201,10 @@ impl<C: ClientContext> ChainListener<C> {
                 self.update_wallet(notification.chain_id).await?;
                 self.add_new_chains(*hash).await?;
                 self.process_new_events(notification.chain_id).await?;
+                self.process_timers(notification.chain_id).await?;
             }
+            // New events are processed with the block that added them.
+            Reason::NewEvents { .. } => {}
         }
         Self::sleep(self.config.delay_after_ms).await;
         Ok(())
@@ -261,6 +268,24 @@ impl<C: ClientContext> ChainListener<C> {
         Ok(())
     }
 
+    /// Processes the inbox of a chain if some of its timers are due, and otherwise makes sure
+    /// that we wake up when the next one becomes due.
+    async fn process_timers(&mut self, chain_id: ChainId) -> Result<(), Error> {
+        if self.config.skip_process_inbox {
+            return Ok(());
+        }
+        let Some(listening_client) = self.listening.get_mut(&chain_id) else {
+            return Ok(());
+        };
+        if listening_client.client.has_due_timers().await? {
+            return self.maybe_process_inbox(chain_id).await;
+        }
+        if let Some(timestamp) = listening_client.client.next_timer_timestamp().await? {
+            listening_client.timeout = listening_client.timeout.min(timestamp);
+        }
+        Ok(())
+    }
+
     /// Start listening for notifications about the given chain.
     async fn listen(&mut self, chain_id: ChainId) -> Result<(), Error> {
         if self.listening.contains_key(&chain_id) {
@@ -369,7 +394,14 @@ impl<C: ClientContext> ChainListener<C> {
         {
             Err(ChainClientError::CannotFindKeyForChain(_)) => {}
             Err(error) => warn!(%error, "Failed to process inbox."),
-            Ok((certs, None)) => info!("Done processing inbox. {} blocks created.", certs.len()),
+            Ok((certs, None)) => {
+                info!("Done processing inbox. {} blocks created.", certs.len());
+                match listening_client.client.next_timer_timestamp().await {
+                    Ok(Some(timestamp)) => listening_client.timeout = timestamp,
+                    Ok(None) => {}
+                    Err(error) => warn!(%error, "Failed to read the timers of the chain."),
+                }
+            }
             Ok((certs, Some(new_timeout))) => {
                 info!(
                     "{} blocks created. Will try processing the inbox later based \
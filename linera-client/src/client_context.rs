@@ -3,13 +3,14 @@
 
 #[cfg(with_testing)]
 use std::num::NonZeroUsize;
-use std::{collections::HashSet, sync::Arc};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures::Future;
 use linera_base::{
     crypto::{AccountSecretKey, CryptoHash, ValidatorPublicKey},
     data_types::{BlockHeight, Timestamp},
+    ensure,
     identifiers::{Account, AccountOwner, ChainId, MessageId},
     ownership::ChainOwnership,
     time::{Duration, Instant},
@@ -21,6 +22,7 @@ use linera_core::{
     join_set_ext::JoinSet,
     node::{CrossChainMessageDelivery, ValidatorNodeProvider},
     remote_node::RemoteNode,
+    signer::{InMemorySigner, Signer, SignerError},
     JoinSetExt,
 };
 use linera_execution::{Message, SystemMessage};
@@ -60,6 +62,8 @@ use {
 use crate::persistent::{LocalPersist as Persist, LocalPersistExt as _};
 #[cfg(not(web))]
 use crate::persistent::{Persist, PersistExt as _};
+#[cfg(unix)]
+use crate::signer::UnixSocketSigner;
 use crate::{
     chain_listener,
     client_options::{ChainOwnershipConfig, ClientContextOptions},
@@ -80,8 +84,11 @@ where
     pub retry_delay: Duration,
     pub max_retries: u32,
     pub chain_listeners: JoinSet,
-    pub blanket_message_policy: BlanketMessagePolicy,
-    pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    /// The policy for handling incoming messages, shared by all the chain clients.
+    pub message_policy: MessagePolicy,
+    /// The signer holding the keys of the wallet, unless blocks are signed by an external
+    /// signer.
+    pub wallet_signer: Option<Arc<InMemorySigner>>,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -102,6 +109,10 @@ where
         self.make_chain_client(chain_id)
     }
 
+    fn message_policy(&self) -> &MessagePolicy {
+        &self.message_policy
+    }
+
     async fn update_wallet_for_new_chain(
         &mut self,
         chain_id: ChainId,
@@ -158,6 +169,7 @@ where
             1 => format!("Client node for {:.8}", chain_ids[0]),
             n => format!("Client node for {:.8} and {} others", chain_ids[0], n - 1),
         };
+        let (signer, wallet_signer) = Self::make_signer(&options);
         let client = Client::new(
             node_provider,
             storage,
@@ -167,8 +179,10 @@ where
             chain_ids,
             name,
             options.max_loaded_chains,
+            options.retention_config.clone().into(),
             options.grace_period,
             options.blob_download_timeout,
+            signer,
         );
 
         ClientContext {
@@ -179,14 +193,41 @@ where
             retry_delay: options.retry_delay,
             max_retries: options.max_retries,
             chain_listeners: JoinSet::default(),
-            blanket_message_policy: options.blanket_message_policy,
-            restrict_chain_ids_to: options.restrict_chain_ids_to,
+            message_policy: MessagePolicy::new(
+                options.blanket_message_policy,
+                options.restrict_chain_ids_to,
+            )
+            .with_rules(
+                options
+                    .message_rules
+                    .map(|rules| rules.0)
+                    .unwrap_or_default(),
+            ),
+            wallet_signer,
+        }
+    }
+
+    /// Returns the signer to use for block proposals, and the in-memory signer to load the
+    /// wallet's keys into, if any.
+    fn make_signer(
+        #[cfg_attr(not(unix), allow(unused_variables))] options: &ClientContextOptions,
+    ) -> (Arc<dyn Signer>, Option<Arc<InMemorySigner>>) {
+        #[cfg(unix)]
+        if let Some(path) = &options.signer_socket {
+            info!(
+                "Signing blocks with the signer daemon at {}",
+                path.display()
+            );
+            return (Arc::new(UnixSocketSigner::new(path)), None);
         }
+        let wallet_signer = Arc::new(InMemorySigner::default());
+        (wallet_signer.clone(), Some(wallet_signer))
     }
 
     #[cfg(with_testing)]
     pub fn new_test_client_context(storage: S, wallet: W) -> Self {
         use linera_core::DEFAULT_GRACE_PERIOD;
+        use linera_storage::RetentionPolicy;
 
         let send_recv_timeout = Duration::from_millis(4000);
         let retry_delay = Duration::from_millis(1000);
@@ -200,6 +241,7 @@ where
         };
         let node_provider = NodeProvider::new(node_options);
         let delivery = CrossChainMessageDelivery::new(true);
+        let wallet_signer = Arc::new(InMemorySigner::default());
         let chain_ids = wallet.chain_ids();
         let name = match chain_ids.len() {
             0 => "Client node".to_string(),
@@ -215,8 +257,10 @@ where
             chain_ids,
             name,
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
+            RetentionPolicy::archival(),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            wallet_signer.clone(),
         );
 
         ClientContext {
@@ -227,8 +271,8 @@ where
             retry_delay,
             max_retries,
             chain_listeners: JoinSet::default(),
-            blanket_message_policy: BlanketMessagePolicy::Accept,
-            restrict_chain_ids_to: None,
+            message_policy: MessagePolicy::new(BlanketMessagePolicy::Accept, None),
+            wallet_signer: Some(wallet_signer),
         }
     }
 
@@ -254,10 +298,13 @@ where
             }
             None => return Err(error::Inner::NonexistentChain(chain_id).into()),
         };
-        let known_key_pairs = chain.key_pair.into_iter().collect();
+        let known_owners = match chain.key_pair {
+            Some(key_pair) => vec![self.add_wallet_key(key_pair)],
+            None => chain.owner.into_iter().collect(),
+        };
         Ok(self.make_chain_client_internal(
             chain_id,
-            known_key_pairs,
+            known_owners,
             chain.block_hash,
             chain.timestamp,
             chain.next_block_height,
@@ -268,7 +315,7 @@ where
     fn make_chain_client_internal(
         &self,
         chain_id: ChainId,
-        known_key_pairs: Vec<AccountSecretKey>,
+        known_owners: Vec<AccountOwner>,
         block_hash: Option<CryptoHash>,
         timestamp: Timestamp,
         next_block_height: BlockHeight,
@@ -276,20 +323,26 @@ where
     ) -> ChainClient<NodeProvider, S> {
         let mut chain_client = self.client.create_chain_client(
             chain_id,
-            known_key_pairs,
+            known_owners,
             self.wallet.genesis_admin_chain(),
             block_hash,
             timestamp,
             next_block_height,
             pending_proposal,
         );
-        chain_client.options_mut().message_policy = MessagePolicy::new(
-            self.blanket_message_policy,
-            self.restrict_chain_ids_to.clone(),
-        );
+        chain_client.options_mut().message_policy = self.message_policy.clone();
         chain_client
     }
 
+    /// Makes a key of the wallet available to the in-memory signer, if we use one, and
+    /// returns its owner.
+    fn add_wallet_key(&self, key_pair: AccountSecretKey) -> AccountOwner {
+        match &self.wallet_signer {
+            Some(wallet_signer) => wallet_signer.add_key(key_pair),
+            None => AccountOwner::from(key_pair.public()),
+        }
+    }
+
     pub fn make_node_provider(&self) -> NodeProvider {
         NodeProvider::new(self.make_node_options())
     }
@@ -340,6 +393,7 @@ where
                 w.insert(UserChain {
                     chain_id,
                     key_pair: key_pair.as_ref().map(|kp| kp.copy()),
+                    owner: key_pair.as_ref().map(|kp| kp.public().into()),
                     block_hash: None,
                     timestamp,
                     next_block_height: BlockHeight::ZERO,
@@ -464,8 +518,22 @@ where
             return Err(error::Inner::ChainOwnership.into());
         }
 
+        let timestamp = block.header.timestamp;
+        if self.wallet_signer.is_none() && self.wallet.key_pair_for_owner(&owner).is_none() {
+            // The key is held by the external signer: we only need to remember the owner.
+            ensure!(
+                self.client.signer().public_key(&owner).await?.is_some(),
+                error::Inner::Signer(SignerError::MissingKey(owner))
+            );
+            let user_chain = UserChain {
+                owner: Some(owner),
+                ..UserChain::make_other(chain_id, timestamp)
+            };
+            self.mutate_wallet(|w| w.insert(user_chain)).await?;
+            return Ok(());
+        }
         self.wallet_mut()
-            .mutate(|w| w.assign_new_chain_to_owner(owner, chain_id, block.header.timestamp))
+            .mutate(|w| w.assign_new_chain_to_owner(owner, chain_id, timestamp))
             .await
             .map_err(|e| error::Inner::Persistence(Box::new(e)))?
             .context("assigning new chain")?;
@@ -840,7 +908,7 @@ where
 
         let mut key_pairs = Vec::new();
         for _ in (0..num_chains_to_create).step_by(operations_per_block) {
-            key_pairs.push(self.wallet.generate_key_pair());
+            key_pairs.push(self.wallet.generate_key_pair()?);
         }
         let mut key_pairs_iter = key_pairs.into_iter();
         let admin_id = self.wallet.genesis_admin_chain();
@@ -871,7 +939,7 @@ where
 
                 let chain_client = self.make_chain_client_internal(
                     chain_id,
-                    vec![key_pair.copy()],
+                    vec![self.add_wallet_key(key_pair.copy())],
                     None,
                     certificate.block().header.timestamp,
                     BlockHeight::ZERO,
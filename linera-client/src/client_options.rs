@@ -4,13 +4,17 @@
 use std::{collections::HashSet, fmt, iter, num::NonZeroUsize, path::PathBuf};
 
 use linera_base::{
-    data_types::{ApplicationPermissions, TimeDelta},
+    data_types::{ApplicationPermissions, TimeDelta, Timestamp},
     identifiers::{AccountOwner, ApplicationId, ChainId},
     ownership::{ChainOwnership, TimeoutConfig},
     time::Duration,
 };
-use linera_core::{client::BlanketMessagePolicy, DEFAULT_GRACE_PERIOD};
+use linera_core::{
+    client::{BlanketMessagePolicy, MessageRules},
+    DEFAULT_GRACE_PERIOD,
+};
 use linera_execution::ResourceControlPolicy;
+use linera_storage::RetentionPolicy;
 
 #[cfg(any(with_indexed_db, not(with_persist)))]
 use crate::{config::WalletState, wallet::Wallet};
@@ -98,6 +102,14 @@ pub struct ClientContextOptions {
     #[arg(long, value_parser = util::parse_chain_set)]
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
 
+    /// Rules for handling incoming messages, as a JSON list, checked in order before the
+    /// blanket policy. The first rule matching a message bundle decides whether it is
+    /// accepted, rejected or deferred, e.g.
+    /// `[{"origins": ["<chain ID>"], "kinds": ["Tracked"], "action": "Reject"}]`. Rules can
+    /// also match on `application_id`, `min_grant` and `max_grant`.
+    #[arg(long)]
+    pub message_rules: Option<MessageRules>,
+
     /// An additional delay, after reaching a quorum, to wait for additional validator signatures,
     /// as a fraction of time taken to reach quorum.
     #[arg(long, default_value_t = DEFAULT_GRACE_PERIOD)]
@@ -110,6 +122,15 @@ pub struct ClientContextOptions {
         value_parser = util::parse_millis
     )]
     pub blob_download_timeout: Duration,
+
+    #[command(flatten)]
+    pub retention_config: RetentionConfig,
+
+    /// Sign block proposals with the signer daemon listening on this Unix socket, instead
+    /// of the keys stored in the wallet.
+    #[cfg(unix)]
+    #[arg(long)]
+    pub signer_socket: Option<PathBuf>,
 }
 
 #[cfg(with_indexed_db)]
@@ -259,6 +280,9 @@ pub struct ApplicationPermissionsConfig {
     /// API.
     #[arg(long)]
     pub make_http_requests: Option<Vec<ApplicationId>>,
+    /// These applications can be upgraded to new bytecode by the owners of the current chain.
+    #[arg(long)]
+    pub upgrade_applications: Option<Vec<ApplicationId>>,
 }
 
 impl From<ApplicationPermissionsConfig> for ApplicationPermissions {
@@ -272,7 +296,35 @@ impl From<ApplicationPermissionsConfig> for ApplicationPermissions {
                 .unwrap_or_default(),
             call_service_as_oracle: config.call_service_as_oracle,
             make_http_requests: config.make_http_requests,
+            upgrade_applications: config.upgrade_applications.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct RetentionConfig {
+    /// Prune the confirmed blocks and certificates of each chain, except the ones at
+    /// the last this many heights. By default, the full history is kept.
+    #[arg(long)]
+    pub keep_last_blocks: Option<u64>,
+
+    /// Prune the confirmed blocks and certificates of each chain with a timestamp before
+    /// this RFC 3339 date, e.g. `2025-01-31T12:00:00Z`. If used together with
+    /// `--keep-last-blocks`, a block is kept if either option keeps it.
+    #[arg(long, value_parser = util::parse_timestamp)]
+    pub keep_blocks_since: Option<Timestamp>,
+}
+
+impl From<RetentionConfig> for RetentionPolicy {
+    fn from(config: RetentionConfig) -> RetentionPolicy {
+        let mut policy = RetentionPolicy::archival();
+        if let Some(heights) = config.keep_last_blocks {
+            policy = policy.with_keep_last_heights(heights);
+        }
+        if let Some(timestamp) = config.keep_blocks_since {
+            policy = policy.with_keep_since(timestamp);
         }
+        policy
     }
 }
 
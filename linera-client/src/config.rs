@@ -10,7 +10,7 @@ use std::{
 use linera_base::{
     crypto::{
         AccountPublicKey, AccountSecretKey, BcsSignable, CryptoHash, CryptoRng, Ed25519SecretKey,
-        ValidatorPublicKey, ValidatorSecretKey,
+        SignatureScheme, ValidatorPublicKey, ValidatorSecretKey,
     },
     data_types::{Amount, Timestamp},
     identifiers::{ChainDescription, ChainId},
@@ -19,7 +19,9 @@ use linera_execution::{
     committee::{Committee, ValidatorState},
     ResourceControlPolicy,
 };
-use linera_rpc::config::{ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig};
+use linera_rpc::config::{
+    TlsCertificatesConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+};
 use linera_storage::Storage;
 use serde::{Deserialize, Serialize};
 
@@ -61,6 +63,10 @@ pub struct ValidatorServerConfig {
     pub validator: ValidatorConfig,
     pub validator_secret: ValidatorSecretKey,
     pub internal_network: ValidatorInternalNetworkConfig,
+    /// The certificate of the proxy's public endpoint, if it uses TLS. By default, the
+    /// self-signed certificate embedded in the binary is used.
+    #[serde(default)]
+    pub public_tls: Option<TlsCertificatesConfig>,
 }
 
 #[cfg(web)]
@@ -111,6 +117,21 @@ impl<W: Persist<Target = Wallet>> WalletState<W> {
             .await
             .map_err(|e| Error::Persistence(Box::new(e)))
     }
+
+    /// Generates a new key pair. If the wallet was created from a mnemonic, the key is
+    /// derived from it, so that it can be restored from the mnemonic alone. The index of the
+    /// derived key is saved the next time the wallet is persisted.
+    pub fn generate_key_pair(&mut self) -> Result<AccountSecretKey, crate::Error> {
+        if self.wallet.has_mnemonic() {
+            return self
+                .wallet
+                .as_mut()
+                .derive_next_key_pair(SignatureScheme::Ed25519);
+        }
+        Ok(AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(
+            &mut self.prng,
+        )))
+    }
 }
 
 impl<W: Deref> Deref for WalletState<W> {
@@ -179,10 +200,6 @@ impl<W: Deref<Target = Wallet>> WalletState<W> {
             wallet,
         }
     }
-
-    pub fn generate_key_pair(&mut self) -> AccountSecretKey {
-        AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(&mut self.prng))
-    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
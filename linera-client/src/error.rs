@@ -14,6 +14,8 @@ pub(crate) enum Inner {
     Chain(#[from] linera_chain::ChainError),
     #[error("chain client error: {0}")]
     ChainClient(#[from] linera_core::client::ChainClientError),
+    #[error("signer error: {0}")]
+    Signer(#[from] linera_core::signer::SignerError),
     #[error("options error: {0}")]
     Options(#[from] crate::client_options::Error),
     #[error("persistence error: {0}")]
@@ -36,6 +38,18 @@ pub(crate) enum Inner {
     InvalidOpenMessage(Option<Box<linera_execution::Message>>),
     #[error("incorrect chain ownership")]
     ChainOwnership,
+    #[error("keystore error: {0}")]
+    Keystore(#[from] crate::keystore::Error),
+    #[error("the wallet is locked: unlock it with its passphrase first")]
+    LockedWallet,
+    #[error("the wallet is not encrypted")]
+    UnencryptedWallet,
+    #[error("cryptographic error: {0}")]
+    Crypto(#[from] linera_base::crypto::CryptoError),
+    #[error("the wallet was not created from a mnemonic")]
+    NoMnemonic,
+    #[error("a wallet created from a mnemonic must be encrypted with a passphrase")]
+    UnencryptedMnemonic,
     #[cfg(feature = "benchmark")]
     #[error("Benchmark error: {0}")]
     Benchmark(#[from] BenchmarkError),
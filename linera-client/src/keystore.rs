@@ -0,0 +1,202 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passphrase-based encryption of the secret keys held in a [`Wallet`](crate::wallet::Wallet).
+//!
+//! The secret keys are serialized and encrypted with XChaCha20-Poly1305, using a key
+//! derived from the passphrase with Argon2id. The parameters of the key derivation are
+//! stored next to the ciphertext, so that they can be strengthened in the future without
+//! breaking existing wallets.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead as _, KeyInit as _},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use linera_base::{
+    crypto::{AccountSecretKey, CryptoRng},
+    identifiers::ChainId,
+};
+use rand::RngCore as _;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// The version of the keystore format produced by this crate.
+const KEYSTORE_VERSION: u32 = 1;
+/// The length of the symmetric key, in bytes.
+const KEY_LENGTH: usize = 32;
+/// The length of the random salt used for key derivation, in bytes.
+const SALT_LENGTH: usize = 16;
+/// The length of an XChaCha20-Poly1305 nonce, in bytes.
+const NONCE_LENGTH: usize = 24;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unsupported keystore version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("key derivation failed: {0}")]
+    KeyDerivation(argon2::Error),
+    #[error("failed to encrypt the secret keys")]
+    Encryption,
+    #[error("wrong passphrase or corrupted keystore")]
+    Decryption,
+    #[error("invalid nonce length: {0}")]
+    InvalidNonceLength(usize),
+    #[error("invalid keystore key length: {0}")]
+    InvalidKeyLength(usize),
+    #[error("invalid hexadecimal encoding: {0}")]
+    Hex(#[from] hex::FromHexError),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The parameters of the Argon2id key derivation function.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// The hex-encoded random salt.
+    pub salt: String,
+    /// The memory cost, in KiB.
+    pub memory_cost: u32,
+    /// The number of passes over the memory.
+    pub time_cost: u32,
+    /// The degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Creates parameters with a fresh random salt and the recommended costs.
+    pub fn generate<R: CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        rng.fill_bytes(&mut salt);
+        Self {
+            salt: hex::encode(salt),
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+
+    /// Derives the key protecting a keystore from the given `passphrase`.
+    pub fn derive_key(&self, passphrase: &str) -> Result<KeystoreKey, Error> {
+        let salt = hex::decode(&self.salt)?;
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LENGTH),
+        )
+        .map_err(Error::KeyDerivation)?;
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut_slice())
+            .map_err(Error::KeyDerivation)?;
+        Ok(KeystoreKey(key))
+    }
+}
+
+/// A symmetric key derived from a wallet passphrase.
+///
+/// It can be exported with [`KeystoreKey::to_session_string`] so that the expensive key
+/// derivation only runs once per session.
+#[derive(Clone)]
+pub struct KeystoreKey(Zeroizing<[u8; KEY_LENGTH]>);
+
+impl KeystoreKey {
+    /// Returns the hex encoding of this key, to be passed back to [`KeystoreKey::from_str`].
+    pub fn to_session_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(hex::encode(self.0.as_slice()))
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(Key::from_slice(self.0.as_slice()))
+    }
+}
+
+impl fmt::Debug for KeystoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeystoreKey(..)")
+    }
+}
+
+impl FromStr for KeystoreKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = Zeroizing::new(hex::decode(s.trim())?);
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        if bytes.len() != KEY_LENGTH {
+            return Err(Error::InvalidKeyLength(bytes.len()));
+        }
+        key.copy_from_slice(&bytes);
+        Ok(KeystoreKey(key))
+    }
+}
+
+/// The secret keys of a wallet, as stored inside an [`EncryptedKeystore`].
+#[derive(Default, Serialize, Deserialize)]
+pub struct SecretKeys {
+    /// The key pairs of the chains we own.
+    pub chains: BTreeMap<ChainId, AccountSecretKey>,
+    /// The key pairs that are not assigned to any chain yet.
+    pub unassigned: Vec<AccountSecretKey>,
+    /// The mnemonic phrase the keys are derived from, if any.
+    #[serde(default)]
+    pub mnemonic: Option<Zeroizing<String>>,
+}
+
+/// Secret keys encrypted under a passphrase.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncryptedKeystore {
+    /// The version of the keystore format.
+    pub version: u32,
+    /// The parameters used to derive the encryption key from the passphrase.
+    pub kdf: KdfParams,
+    /// The hex-encoded nonce.
+    pub nonce: String,
+    /// The hex-encoded ciphertext, including the authentication tag.
+    pub ciphertext: String,
+}
+
+impl EncryptedKeystore {
+    /// Encrypts the `keys` with the given `key`, derived from `kdf`.
+    pub fn seal<R: CryptoRng + ?Sized>(
+        keys: &SecretKeys,
+        kdf: KdfParams,
+        key: &KeystoreKey,
+        rng: &mut R,
+    ) -> Result<Self, Error> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+        let plaintext = Zeroizing::new(serde_json::to_vec(keys)?);
+        let ciphertext = key
+            .cipher()
+            .encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+            .map_err(|_| Error::Encryption)?;
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            kdf,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the secret keys. Fails if `key` was not derived from the right passphrase.
+    pub fn open(&self, key: &KeystoreKey) -> Result<SecretKeys, Error> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(Error::UnsupportedVersion(self.version));
+        }
+        let nonce = hex::decode(&self.nonce)?;
+        if nonce.len() != NONCE_LENGTH {
+            return Err(Error::InvalidNonceLength(nonce.len()));
+        }
+        let ciphertext = hex::decode(&self.ciphertext)?;
+        let plaintext = Zeroizing::new(
+            key.cipher()
+                .decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| Error::Decryption)?,
+        );
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
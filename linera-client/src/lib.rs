@@ -11,7 +11,10 @@ pub mod client_context;
 pub mod client_options;
 pub mod config;
 mod error;
+pub mod keystore;
 pub mod persistent;
+#[cfg(unix)]
+pub mod signer;
 pub mod util;
 pub mod wallet;
 
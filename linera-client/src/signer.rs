@@ -0,0 +1,192 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A [`Signer`] that delegates to a signer daemon listening on a Unix socket, and the
+//! daemon itself.
+//!
+//! Each connection carries a single BCS-encoded [`SignerRequest`], followed by the
+//! BCS-encoded [`SignerResponse`] once the client has closed its write half.
+
+use std::{os::unix::fs::PermissionsExt as _, path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use linera_base::{
+    crypto::{AccountPublicKey, AccountSignature},
+    identifiers::AccountOwner,
+};
+use linera_chain::data_types::ProposalContent;
+use linera_core::signer::{Signer, SignerError};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _},
+    net::{UnixListener, UnixStream},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// The maximum size of a request accepted by the signer daemon.
+const MAX_REQUEST_SIZE: u64 = 16 << 20;
+
+/// A request sent to the signer daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SignerRequest {
+    /// Returns the public key of an owner, if the daemon holds its secret key.
+    PublicKey { owner: AccountOwner },
+    /// Signs the content of a block proposal with the key of an owner.
+    SignProposal {
+        owner: AccountOwner,
+        content: Box<ProposalContent>,
+    },
+}
+
+/// A response of the signer daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SignerResponse {
+    PublicKey(Option<AccountPublicKey>),
+    Signature(AccountSignature),
+    Error(String),
+}
+
+/// A [`Signer`] that forwards every request to a signer daemon over a Unix socket.
+#[derive(Clone, Debug)]
+pub struct UnixSocketSigner {
+    path: PathBuf,
+}
+
+impl UnixSocketSigner {
+    /// Creates a signer that connects to the daemon listening at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    async fn request(&self, request: &SignerRequest) -> Result<SignerResponse, SignerError> {
+        let mut stream = UnixStream::connect(&self.path)
+            .await
+            .map_err(communication_error)?;
+        let bytes = bcs::to_bytes(request).map_err(communication_error)?;
+        stream
+            .write_all(&bytes)
+            .await
+            .map_err(communication_error)?;
+        stream.shutdown().await.map_err(communication_error)?;
+        let mut bytes = Vec::new();
+        stream
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(communication_error)?;
+        bcs::from_bytes(&bytes).map_err(communication_error)
+    }
+}
+
+fn communication_error(error: impl std::fmt::Display) -> SignerError {
+    SignerError::Communication(error.to_string())
+}
+
+#[async_trait]
+impl Signer for UnixSocketSigner {
+    async fn public_key(
+        &self,
+        owner: &AccountOwner,
+    ) -> Result<Option<AccountPublicKey>, SignerError> {
+        let request = SignerRequest::PublicKey { owner: *owner };
+        match self.request(&request).await? {
+            SignerResponse::PublicKey(public_key) => Ok(public_key),
+            SignerResponse::Error(error) => Err(SignerError::Refused(error)),
+            SignerResponse::Signature(_) => Err(SignerError::Communication(
+                "unexpected response from the signer daemon".to_string(),
+            )),
+        }
+    }
+
+    async fn sign_proposal(
+        &self,
+        owner: &AccountOwner,
+        content: &ProposalContent,
+    ) -> Result<AccountSignature, SignerError> {
+        let request = SignerRequest::SignProposal {
+            owner: *owner,
+            content: Box::new(content.clone()),
+        };
+        match self.request(&request).await? {
+            SignerResponse::Signature(signature) => Ok(signature),
+            SignerResponse::Error(error) => Err(SignerError::Refused(error)),
+            SignerResponse::PublicKey(_) => Err(SignerError::Communication(
+                "unexpected response from the signer daemon".to_string(),
+            )),
+        }
+    }
+}
+
+/// A daemon serving the requests of [`UnixSocketSigner`]s.
+pub struct SignerDaemon {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl SignerDaemon {
+    /// Starts listening at `path`. Any file already there is replaced, and the socket is
+    /// only made accessible to the current user.
+    pub fn bind(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(Self { path, listener })
+    }
+
+    /// Serves requests using `signer` until `cancellation_token` is cancelled.
+    pub async fn run(
+        self,
+        signer: Arc<dyn Signer>,
+        cancellation_token: CancellationToken,
+    ) -> std::io::Result<()> {
+        info!("Signer daemon listening on {}", self.path.display());
+        loop {
+            let stream = tokio::select! {
+                result = self.listener.accept() => result?.0,
+                () = cancellation_token.cancelled() => break,
+            };
+            let signer = signer.clone();
+            tokio::spawn(async move {
+                if let Err(error) = handle_connection(stream, &*signer).await {
+                    warn!("Failed to handle a signer request: {error}");
+                }
+            });
+        }
+        std::fs::remove_file(&self.path)
+    }
+}
+
+async fn handle_connection(mut stream: UnixStream, signer: &dyn Signer) -> std::io::Result<()> {
+    let mut bytes = Vec::new();
+    (&mut stream)
+        .take(MAX_REQUEST_SIZE)
+        .read_to_end(&mut bytes)
+        .await?;
+    let response = match bcs::from_bytes::<SignerRequest>(&bytes) {
+        Ok(request) => {
+            debug!("Handling signer request {request:?}");
+            handle_request(request, signer).await
+        }
+        Err(error) => SignerResponse::Error(format!("invalid request: {error}")),
+    };
+    let bytes = bcs::to_bytes(&response).map_err(std::io::Error::other)?;
+    stream.write_all(&bytes).await?;
+    stream.shutdown().await
+}
+
+async fn handle_request(request: SignerRequest, signer: &dyn Signer) -> SignerResponse {
+    let result = match request {
+        SignerRequest::PublicKey { owner } => signer
+            .public_key(&owner)
+            .await
+            .map(SignerResponse::PublicKey),
+        SignerRequest::SignProposal { owner, content } => signer
+            .sign_proposal(&owner, &content)
+            .await
+            .map(SignerResponse::Signature),
+    };
+    result.unwrap_or_else(|error| SignerResponse::Error(error.to_string()))
+}
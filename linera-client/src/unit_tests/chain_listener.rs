@@ -14,13 +14,14 @@ use linera_base::{
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_core::{
-    client::{ChainClient, Client},
+    client::{BlanketMessagePolicy, ChainClient, Client, MessagePolicy},
     node::CrossChainMessageDelivery,
+    signer::InMemorySigner,
     test_utils::{MemoryStorageBuilder, NodeProvider, StorageBuilder as _, TestBuilder},
     DEFAULT_GRACE_PERIOD,
 };
 use linera_execution::system::Recipient;
-use linera_storage::{DbStorage, TestClock};
+use linera_storage::{DbStorage, RetentionPolicy, TestClock};
 use linera_views::memory::MemoryStore;
 use rand::SeedableRng as _;
 use tokio_util::sync::CancellationToken;
@@ -38,6 +39,8 @@ type TestProvider = NodeProvider<TestStorage>;
 struct ClientContext {
     wallet: Wallet,
     client: Arc<Client<TestProvider, TestStorage>>,
+    signer: Arc<InMemorySigner>,
+    message_policy: MessagePolicy,
 }
 
 #[cfg_attr(not(web), async_trait)]
@@ -58,21 +61,27 @@ impl chain_listener::ClientContext for ClientContext {
             .wallet
             .get(chain_id)
             .unwrap_or_else(|| panic!("Unknown chain: {}", chain_id));
-        let known_key_pairs = chain
+        let known_owners = chain
             .key_pair
             .as_ref()
-            .map(|kp| kp.copy())
+            .map(|kp| self.signer.add_key(kp.copy()))
             .into_iter()
             .collect();
-        Ok(self.client.create_chain_client(
+        let mut chain_client = self.client.create_chain_client(
             chain_id,
-            known_key_pairs,
+            known_owners,
             self.wallet.genesis_admin_chain(),
             chain.block_hash,
             chain.timestamp,
             chain.next_block_height,
             chain.pending_proposal.clone(),
-        ))
+        );
+        chain_client.options_mut().message_policy = self.message_policy.clone();
+        Ok(chain_client)
+    }
+
+    fn message_policy(&self) -> &MessagePolicy {
+        &self.message_policy
     }
 
     async fn update_wallet_for_new_chain(
@@ -85,6 +94,7 @@ impl chain_listener::ClientContext for ClientContext {
             self.wallet.insert(UserChain {
                 chain_id,
                 key_pair: key_pair.as_ref().map(|kp| kp.copy()),
+                owner: key_pair.as_ref().map(|kp| kp.public().into()),
                 block_hash: None,
                 timestamp,
                 next_block_height: BlockHeight::ZERO,
@@ -122,6 +132,7 @@ async fn test_chain_listener() -> anyhow::Result<()> {
     let genesis_config = make_genesis_config(&builder);
     let storage = builder.make_storage().await?;
     let delivery = CrossChainMessageDelivery::NonBlocking;
+    let signer = Arc::new(InMemorySigner::default());
     let mut context = ClientContext {
         wallet: Wallet::new(genesis_config, Some(37)),
         client: Arc::new(Client::new(
@@ -133,9 +144,13 @@ async fn test_chain_listener() -> anyhow::Result<()> {
             [chain_id0],
             format!("Client node for {:.8}", chain_id0),
             NonZeroUsize::new(20).expect("Chain worker LRU cache size must be non-zero"),
+            RetentionPolicy::archival(),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            signer.clone(),
         )),
+        signer,
+        message_policy: MessagePolicy::new(BlanketMessagePolicy::Accept, None),
     };
     let key_pair = AccountSecretKey::Secp256k1(Secp256k1SecretKey::generate_from(&mut rng));
     let owner = key_pair.public().into();
@@ -0,0 +1,61 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use linera_base::{
+    crypto::{AccountSecretKey, Ed25519SecretKey},
+    data_types::{BlockHeight, Epoch, Round, Timestamp},
+    identifiers::{AccountOwner, ChainId},
+};
+use linera_chain::data_types::{BlockProposal, ProposalContent, ProposedBlock};
+use linera_core::signer::{InMemorySigner, Signer as _};
+use rand::{rngs::StdRng, SeedableRng as _};
+use tokio_util::sync::CancellationToken;
+
+use crate::signer::{SignerDaemon, UnixSocketSigner};
+
+/// Tests that a client can obtain public keys and proposal signatures from a signer daemon.
+#[test_log::test(tokio::test)]
+async fn test_unix_socket_signer() -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let key_pair = AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(&mut rng));
+    let public_key = key_pair.public();
+    let owner = AccountOwner::from(public_key);
+    let other_key_pair = AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(&mut rng));
+    let other_owner = AccountOwner::from(other_key_pair.public());
+
+    let tmp_dir = tempfile::tempdir()?;
+    let socket = tmp_dir.path().join("signer.sock");
+    let daemon = SignerDaemon::bind(&socket)?;
+    let cancellation_token = CancellationToken::new();
+    let handle = tokio::spawn(daemon.run(
+        Arc::new(InMemorySigner::new([key_pair])),
+        cancellation_token.clone(),
+    ));
+
+    let signer = UnixSocketSigner::new(&socket);
+    assert_eq!(signer.public_key(&owner).await?, Some(public_key));
+    assert_eq!(signer.public_key(&other_owner).await?, None);
+
+    let block = ProposedBlock {
+        chain_id: ChainId::root(0),
+        epoch: Epoch::ZERO,
+        incoming_bundles: vec![],
+        operations: vec![],
+        height: BlockHeight::ZERO,
+        timestamp: Timestamp::from(0),
+        authenticated_signer: Some(owner),
+        previous_block_hash: None,
+    };
+    let content = ProposalContent::new_initial(Round::Fast, block);
+    let signature = signer.sign_proposal(&owner, &content).await?;
+    let proposal = BlockProposal::from_signed_content(content.clone(), public_key, signature, None);
+    proposal.check_signature()?;
+    assert!(signer.sign_proposal(&other_owner, &content).await.is_err());
+
+    cancellation_token.cancel();
+    handle.await??;
+    assert!(!socket.exists());
+    Ok(())
+}
@@ -3,9 +3,9 @@
 
 use anyhow::anyhow;
 use linera_base::{
-    crypto::{AccountSecretKey, Ed25519SecretKey},
-    data_types::{Amount, Blob, BlockHeight, Epoch},
-    identifiers::{ChainDescription, ChainId},
+    crypto::{hd::Mnemonic, AccountSecretKey, Ed25519SecretKey, SignatureScheme},
+    data_types::{Amount, Blob, BlockHeight, Epoch, Timestamp},
+    identifiers::{AccountOwner, ChainDescription, ChainId},
 };
 use linera_chain::data_types::ProposedBlock;
 use linera_core::{
@@ -18,6 +18,7 @@ use super::util::make_genesis_config;
 use crate::{
     client_context::ClientContext,
     config::WalletState,
+    persistent::{self, PersistExt as _},
     wallet::{UserChain, Wallet},
 };
 
@@ -73,3 +74,122 @@ async fn test_save_wallet_with_pending_blobs() -> anyhow::Result<()> {
     context.save_wallet().await?;
     Ok(())
 }
+
+/// Tests that the chains of wallets saved before owners were recorded get their owner from
+/// their key pair, and that forgetting the keys of a chain keeps its owner.
+#[test_log::test(tokio::test)]
+async fn test_user_chain_owners() -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1).await?;
+    let key_pair = AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(&mut rng));
+    let owner = AccountOwner::from(key_pair.public());
+    let chain = UserChain::make_initial(key_pair, ChainDescription::Root(0), Timestamp::from(0));
+    let mut value = serde_json::to_value(&chain)?;
+    value.as_object_mut().unwrap().remove("owner");
+    let chain = serde_json::from_value::<UserChain>(value)?;
+    assert_eq!(chain.owner, Some(owner));
+
+    let mut wallet = Wallet::new(make_genesis_config(&builder), None);
+    wallet.insert(chain);
+    wallet.forget_keys(&ChainId::root(0))?;
+    let chain = wallet.get(ChainId::root(0)).unwrap();
+    assert!(chain.key_pair.is_none());
+    assert_eq!(chain.owner, Some(owner));
+    Ok(())
+}
+
+/// Tests that an encrypted wallet does not contain its secret keys in the clear, and that
+/// they can only be recovered with the right passphrase.
+#[test_log::test(tokio::test)]
+async fn test_encrypted_wallet() -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1).await?;
+    let genesis_config = make_genesis_config(&builder);
+
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+    let key_pair = AccountSecretKey::Ed25519(Ed25519SecretKey::generate_from(&mut rng));
+    let owner = AccountOwner::from(key_pair.public());
+    let secret = serde_json::to_value(&key_pair)?["Ed25519"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    {
+        let mut wallet =
+            WalletState::create_from_file(&wallet_path, Wallet::new(genesis_config, Some(37)))?;
+        wallet
+            .add_chains(Some(UserChain::make_initial(
+                key_pair,
+                ChainDescription::Root(0),
+                Timestamp::from(0),
+            )))
+            .await?;
+        wallet
+            .mutate(|w| w.set_passphrase("passphrase", &mut rng))
+            .await??;
+    }
+    assert!(!fs_err::read_to_string(&wallet_path)?.contains(&secret));
+
+    let mut wallet = persistent::File::<Wallet>::read(&wallet_path)?;
+    assert!(wallet.is_locked());
+    assert!(wallet.owned_chain_ids().is_empty());
+    assert_eq!(wallet.get(ChainId::root(0)).unwrap().owner, Some(owner));
+    assert!(wallet.unlock_with_passphrase("wrong passphrase").is_err());
+    wallet.unlock_with_passphrase("passphrase")?;
+    assert!(!wallet.is_locked());
+    assert_eq!(wallet.owned_chain_ids(), vec![ChainId::root(0)]);
+    assert!(wallet.key_pair_for_owner(&owner).is_some());
+    Ok(())
+}
+
+/// Tests that the keys of a wallet created from a mnemonic can be restored from it, and
+/// that the mnemonic is only ever stored encrypted together with the secret keys.
+#[test_log::test(tokio::test)]
+async fn test_mnemonic_wallet() -> anyhow::Result<()> {
+    let mut rng = StdRng::seed_from_u64(42);
+    let builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 1).await?;
+    let genesis_config = make_genesis_config(&builder);
+    let mnemonic = Mnemonic::generate_from(&mut rng);
+    let phrase = mnemonic.to_string();
+
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_path = tmp_dir.path().join("wallet.json");
+    let owners = {
+        let mut wallet = WalletState::create_from_file(
+            &wallet_path,
+            Wallet::new(genesis_config.clone(), Some(37)),
+        )?;
+        assert!(wallet.mutate(|w| w.set_mnemonic(&mnemonic)).await?.is_err());
+        wallet
+            .mutate(|w| w.set_passphrase("passphrase", &mut rng))
+            .await??;
+        wallet.mutate(|w| w.set_mnemonic(&mnemonic)).await??;
+        let mut owners = Vec::new();
+        for _ in 0..2 {
+            let key_pair = wallet.generate_key_pair()?;
+            owners.push(AccountOwner::from(key_pair.public()));
+            wallet
+                .mutate(|w| w.add_unassigned_key_pair(key_pair))
+                .await?;
+        }
+        assert!(wallet.mutate(|w| w.remove_passphrase()).await?.is_err());
+        owners
+    };
+    assert!(!fs_err::read_to_string(&wallet_path)?.contains(&phrase));
+
+    let mut wallet = persistent::File::<Wallet>::read(&wallet_path)?;
+    assert!(!wallet.has_mnemonic());
+    wallet.unlock_with_passphrase("passphrase")?;
+    assert!(wallet.has_mnemonic());
+    let key_pair = wallet.derive_next_key_pair(SignatureScheme::Ed25519)?;
+    assert!(!owners.contains(&AccountOwner::from(key_pair.public())));
+
+    let mut restored = Wallet::new(genesis_config, None);
+    restored.set_passphrase("other passphrase", &mut rng)?;
+    restored.set_mnemonic(&phrase.parse::<Mnemonic>()?)?;
+    for (index, owner) in owners.iter().enumerate() {
+        let key_pair = restored.derive_key_pair(SignatureScheme::Ed25519, index as u32)?;
+        assert_eq!(AccountOwner::from(key_pair.public()), *owner);
+    }
+    Ok(())
+}
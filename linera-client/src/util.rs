@@ -32,6 +32,15 @@ pub fn parse_chain_set(s: &str) -> Result<HashSet<ChainId>, CryptoError> {
     }
 }
 
+/// Parses an RFC 3339 date and time, e.g. `2025-01-31T12:00:00Z`, as a [`Timestamp`].
+pub fn parse_timestamp(s: &str) -> Result<Timestamp, String> {
+    let date_time =
+        chrono::DateTime::<chrono::Utc>::from_str(s).map_err(|error| error.to_string())?;
+    let micros = u64::try_from(date_time.timestamp_micros())
+        .map_err(|_| format!("{date_time} is before the Unix epoch"))?;
+    Ok(Timestamp::from(micros))
+}
+
 pub fn parse_ascii_alphanumeric_string(s: &str) -> Result<String, &'static str> {
     if s.chars().all(|x| x.is_ascii_alphanumeric()) {
         Ok(s.to_string())
@@ -45,7 +54,7 @@ pub async fn wait_for_next_round(stream: &mut NotificationStream, timeout: Round
     let mut stream = stream.filter(|notification| match &notification.reason {
         Reason::NewBlock { height, .. } => *height >= timeout.next_block_height,
         Reason::NewRound { round, .. } => *round > timeout.current_round,
-        Reason::NewIncomingBundle { .. } => false,
+        Reason::NewIncomingBundle { .. } | Reason::NewEvents { .. } => false,
     });
     future::select(
         Box::pin(stream.next()),
@@ -2,12 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, HashMap},
     iter::IntoIterator,
 };
 
 use linera_base::{
-    crypto::{AccountSecretKey, CryptoHash, CryptoRng},
+    crypto::{hd::Mnemonic, AccountSecretKey, CryptoError, CryptoHash, CryptoRng, SignatureScheme},
     data_types::{BlockHeight, Timestamp},
     ensure,
     identifiers::{AccountOwner, ChainDescription, ChainId},
@@ -18,17 +19,109 @@ use linera_core::{
 };
 use linera_storage::Storage;
 use rand::Rng as _;
-use serde::{Deserialize, Serialize};
+use serde::{ser::Error as _, Deserialize, Serialize, Serializer};
+use zeroize::Zeroizing;
 
-use crate::{config::GenesisConfig, error, Error};
+use crate::{
+    config::GenesisConfig,
+    error,
+    keystore::{EncryptedKeystore, KdfParams, KeystoreKey, SecretKeys},
+    Error,
+};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Deserialize)]
 pub struct Wallet {
     pub chains: BTreeMap<ChainId, UserChain>,
     pub unassigned_key_pairs: HashMap<AccountOwner, AccountSecretKey>,
     pub default: Option<ChainId>,
     pub genesis_config: GenesisConfig,
     pub testing_prng_seed: Option<u64>,
+    /// The mnemonic phrase from which the keys of this wallet are derived, if any. It is
+    /// only ever stored in the encrypted keystore, so it is known once the wallet is unlocked.
+    #[serde(skip)]
+    mnemonic: Option<Zeroizing<String>>,
+    /// The index of the next key to derive from `mnemonic`.
+    #[serde(default)]
+    next_key_index: u32,
+    /// The passphrase-encrypted secret keys, if the wallet is encrypted. In that case, the
+    /// secret keys are not stored anywhere else in the serialized wallet.
+    #[serde(default)]
+    keystore: Option<EncryptedKeystore>,
+    /// The key protecting `keystore`, once the wallet has been unlocked.
+    #[serde(skip)]
+    keystore_key: Option<KeystoreKey>,
+}
+
+/// The serialized form of a [`Wallet`].
+#[derive(Serialize)]
+struct WalletRepr<'a> {
+    chains: BTreeMap<ChainId, Cow<'a, UserChain>>,
+    unassigned_key_pairs: &'a HashMap<AccountOwner, AccountSecretKey>,
+    default: Option<ChainId>,
+    genesis_config: &'a GenesisConfig,
+    testing_prng_seed: Option<u64>,
+    next_key_index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keystore: Option<Cow<'a, EncryptedKeystore>>,
+}
+
+impl Serialize for Wallet {
+    /// Serializes the wallet. If the wallet is encrypted, the secret keys are re-encrypted
+    /// with a fresh nonce and left out of the plaintext part of the wallet.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let keystore = match (&self.keystore, &self.keystore_key) {
+            (None, _) => None,
+            (Some(keystore), None) => {
+                if self.has_secret_keys() {
+                    return Err(S::Error::custom(
+                        "cannot save new secret keys in a locked wallet",
+                    ));
+                }
+                Some(Cow::Borrowed(keystore))
+            }
+            (Some(keystore), Some(key)) => {
+                let mut rng = Box::<dyn CryptoRng>::from(None);
+                let keystore = EncryptedKeystore::seal(
+                    &self.secret_keys(),
+                    keystore.kdf.clone(),
+                    key,
+                    &mut rng,
+                )
+                .map_err(S::Error::custom)?;
+                Some(Cow::Owned(keystore))
+            }
+        };
+        let no_key_pairs = HashMap::new();
+        let chains = self
+            .chains
+            .iter()
+            .map(|(chain_id, chain)| {
+                let chain = if keystore.is_some() && chain.key_pair.is_some() {
+                    Cow::Owned(UserChain {
+                        key_pair: None,
+                        ..chain.clone()
+                    })
+                } else {
+                    Cow::Borrowed(chain)
+                };
+                (*chain_id, chain)
+            })
+            .collect();
+        WalletRepr {
+            chains,
+            unassigned_key_pairs: if keystore.is_some() {
+                &no_key_pairs
+            } else {
+                &self.unassigned_key_pairs
+            },
+            default: self.default,
+            genesis_config: &self.genesis_config,
+            testing_prng_seed: self.testing_prng_seed,
+            next_key_index: self.next_key_index,
+            keystore,
+        }
+        .serialize(serializer)
+    }
 }
 
 impl Extend<UserChain> for Wallet {
@@ -53,6 +146,10 @@ impl Wallet {
             default: None,
             genesis_config,
             testing_prng_seed,
+            mnemonic: None,
+            next_key_index: 0,
+            keystore: None,
+            keystore_key: None,
         }
     }
 
@@ -68,7 +165,11 @@ impl Wallet {
         self.chains.insert(chain.chain_id, chain);
     }
 
+    /// Forgets the key pair of a chain. Its owner is kept, so that blocks can still be
+    /// signed with an external signer.
     pub fn forget_keys(&mut self, chain_id: &ChainId) -> Result<AccountSecretKey, Error> {
+        // Otherwise the keys would come back from the keystore when unlocking.
+        ensure!(!self.is_locked(), error::Inner::LockedWallet);
         let chain = self
             .chains
             .get_mut(chain_id)
@@ -133,6 +234,68 @@ impl Wallet {
             .map(|key_pair| key_pair.copy())
     }
 
+    /// Returns the secret keys of all the chains we own, and the unassigned ones.
+    pub fn key_pairs(&self) -> impl Iterator<Item = AccountSecretKey> + '_ {
+        self.chains
+            .values()
+            .filter_map(|user_chain| user_chain.key_pair.as_ref())
+            .chain(self.unassigned_key_pairs.values())
+            .map(AccountSecretKey::copy)
+    }
+
+    /// Returns whether the keys of this wallet are derived from a mnemonic. This is only
+    /// known once the wallet is unlocked.
+    pub fn has_mnemonic(&self) -> bool {
+        self.mnemonic.is_some()
+    }
+
+    /// Sets the mnemonic from which the keys of this wallet are derived. The wallet must
+    /// be encrypted, so that the mnemonic is never saved in the clear.
+    pub fn set_mnemonic(&mut self, mnemonic: &Mnemonic) -> Result<(), Error> {
+        ensure!(!self.is_locked(), error::Inner::LockedWallet);
+        ensure!(self.is_encrypted(), error::Inner::UnencryptedMnemonic);
+        self.mnemonic = Some(Zeroizing::new(mnemonic.to_string()));
+        Ok(())
+    }
+
+    /// Derives the key pair with the given `index` from the mnemonic of this wallet. Keys
+    /// derived afterwards with [`Wallet::derive_next_key_pair`] will use higher indices.
+    pub fn derive_key_pair(
+        &mut self,
+        scheme: SignatureScheme,
+        index: u32,
+    ) -> Result<AccountSecretKey, Error> {
+        let key_pair = self.mnemonic()?.derive_key(scheme, index)?;
+        self.next_key_index = self.next_key_index.max(index.saturating_add(1));
+        Ok(key_pair)
+    }
+
+    /// Derives a new key pair from the mnemonic of this wallet, using the next unused index.
+    pub fn derive_next_key_pair(
+        &mut self,
+        scheme: SignatureScheme,
+    ) -> Result<AccountSecretKey, Error> {
+        let mnemonic = self.mnemonic()?;
+        loop {
+            let index = self.next_key_index;
+            match mnemonic.derive_key(scheme, index) {
+                Ok(key_pair) => {
+                    self.next_key_index = index + 1;
+                    return Ok(key_pair);
+                }
+                // As specified by BIP-32, indices that don't yield a valid key are skipped.
+                Err(CryptoError::InvalidDerivedKey) => self.next_key_index = index + 1,
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+
+    fn mnemonic(&self) -> Result<Mnemonic, Error> {
+        ensure!(!self.is_locked(), error::Inner::LockedWallet);
+        let mnemonic = self.mnemonic.as_ref().ok_or(error::Inner::NoMnemonic)?;
+        Ok(mnemonic.parse()?)
+    }
+
     pub fn assign_new_chain_to_owner(
         &mut self,
         owner: AccountOwner,
@@ -146,6 +309,7 @@ impl Wallet {
         let user_chain = UserChain {
             chain_id,
             key_pair: Some(key_pair),
+            owner: Some(owner),
             block_hash: None,
             timestamp,
             next_block_height: BlockHeight(0),
@@ -169,12 +333,14 @@ impl Wallet {
         P: ValidatorNodeProvider + Sync + 'static,
         S: Storage + Clone + Send + Sync + 'static,
     {
-        let key_pair = chain_client.key_pair().await.map(|k| k.copy()).ok();
+        let owner = chain_client.identity().await.ok();
+        let key_pair = owner.and_then(|owner| self.key_pair_for_owner(&owner));
         let state = chain_client.state();
         self.chains.insert(
             chain_client.chain_id(),
             UserChain {
                 chain_id: chain_client.chain_id(),
+                owner,
                 key_pair,
                 block_hash: state.block_hash(),
                 next_block_height: state.next_block_height(),
@@ -184,6 +350,118 @@ impl Wallet {
         );
     }
 
+    /// Returns whether the secret keys of this wallet are encrypted with a passphrase.
+    pub fn is_encrypted(&self) -> bool {
+        self.keystore.is_some()
+    }
+
+    /// Returns whether this wallet is encrypted and its secret keys have not been decrypted.
+    pub fn is_locked(&self) -> bool {
+        self.keystore.is_some() && self.keystore_key.is_none()
+    }
+
+    /// Derives the key protecting the secret keys of this wallet from a `passphrase`,
+    /// checking that the passphrase is correct.
+    pub fn derive_keystore_key(&self, passphrase: &str) -> Result<KeystoreKey, Error> {
+        let keystore = self
+            .keystore
+            .as_ref()
+            .ok_or(error::Inner::UnencryptedWallet)?;
+        let key = keystore.kdf.derive_key(passphrase)?;
+        keystore.open(&key)?;
+        Ok(key)
+    }
+
+    /// Decrypts the secret keys of this wallet, making them available for signing.
+    pub fn unlock(&mut self, key: KeystoreKey) -> Result<(), Error> {
+        let keystore = self
+            .keystore
+            .as_ref()
+            .ok_or(error::Inner::UnencryptedWallet)?;
+        if self.keystore_key.is_some() {
+            return Ok(());
+        }
+        let SecretKeys {
+            chains,
+            unassigned,
+            mnemonic,
+        } = keystore.open(&key)?;
+        for (chain_id, key_pair) in chains {
+            // Keys of chains forgotten while the wallet was locked are dropped.
+            if let Some(chain) = self.chains.get_mut(&chain_id) {
+                chain.key_pair = Some(key_pair);
+            }
+        }
+        for key_pair in unassigned {
+            self.add_unassigned_key_pair(key_pair);
+        }
+        self.mnemonic = mnemonic;
+        self.keystore_key = Some(key);
+        Ok(())
+    }
+
+    /// Decrypts the secret keys of this wallet using its `passphrase`.
+    pub fn unlock_with_passphrase(&mut self, passphrase: &str) -> Result<(), Error> {
+        let key = self.derive_keystore_key(passphrase)?;
+        self.unlock(key)
+    }
+
+    /// Encrypts the secret keys of this wallet with a new `passphrase`, replacing the
+    /// previous one if any. The wallet must not be locked.
+    pub fn set_passphrase<R: CryptoRng + ?Sized>(
+        &mut self,
+        passphrase: &str,
+        rng: &mut R,
+    ) -> Result<(), Error> {
+        ensure!(!self.is_locked(), error::Inner::LockedWallet);
+        let kdf = KdfParams::generate(rng);
+        let key = kdf.derive_key(passphrase)?;
+        self.keystore = Some(EncryptedKeystore::seal(
+            &self.secret_keys(),
+            kdf,
+            &key,
+            rng,
+        )?);
+        self.keystore_key = Some(key);
+        Ok(())
+    }
+
+    /// Removes the passphrase protection: the secret keys will be saved in the clear again.
+    /// The wallet must not be locked, nor be derived from a mnemonic.
+    pub fn remove_passphrase(&mut self) -> Result<(), Error> {
+        ensure!(!self.is_locked(), error::Inner::LockedWallet);
+        ensure!(self.is_encrypted(), error::Inner::UnencryptedWallet);
+        ensure!(self.mnemonic.is_none(), error::Inner::UnencryptedMnemonic);
+        self.keystore = None;
+        self.keystore_key = None;
+        Ok(())
+    }
+
+    fn has_secret_keys(&self) -> bool {
+        self.mnemonic.is_some()
+            || !self.unassigned_key_pairs.is_empty()
+            || self.chains.values().any(|chain| chain.key_pair.is_some())
+    }
+
+    fn secret_keys(&self) -> SecretKeys {
+        SecretKeys {
+            chains: self
+                .chains
+                .iter()
+                .filter_map(|(chain_id, chain)| {
+                    let key_pair = chain.key_pair.as_ref()?;
+                    Some((*chain_id, key_pair.copy()))
+                })
+                .collect(),
+            unassigned: self
+                .unassigned_key_pairs
+                .values()
+                .map(AccountSecretKey::copy)
+                .collect(),
+            mnemonic: self.mnemonic.clone(),
+        }
+    }
+
     pub fn genesis_admin_chain(&self) -> ChainId {
         self.genesis_config.admin_id
     }
@@ -204,20 +482,58 @@ impl Wallet {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(from = "UserChainRepr")]
 pub struct UserChain {
     pub chain_id: ChainId,
     pub key_pair: Option<AccountSecretKey>,
+    /// The owner we sign blocks as, if we own the chain. It is kept when `key_pair` is
+    /// forgotten or only stored encrypted, so that an external signer can still be used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<AccountOwner>,
     pub block_hash: Option<CryptoHash>,
     pub timestamp: Timestamp,
     pub next_block_height: BlockHeight,
     pub pending_proposal: Option<PendingProposal>,
 }
 
+/// The serialized form of a [`UserChain`]. Wallets written before owners were recorded
+/// only have the key pair: the owner is derived from it when loading them.
+#[derive(Deserialize)]
+struct UserChainRepr {
+    chain_id: ChainId,
+    key_pair: Option<AccountSecretKey>,
+    #[serde(default)]
+    owner: Option<AccountOwner>,
+    block_hash: Option<CryptoHash>,
+    timestamp: Timestamp,
+    next_block_height: BlockHeight,
+    pending_proposal: Option<PendingProposal>,
+}
+
+impl From<UserChainRepr> for UserChain {
+    fn from(chain: UserChainRepr) -> Self {
+        let owner = chain.owner.or_else(|| {
+            let key_pair = chain.key_pair.as_ref()?;
+            Some(AccountOwner::from(key_pair.public()))
+        });
+        Self {
+            chain_id: chain.chain_id,
+            key_pair: chain.key_pair,
+            owner,
+            block_hash: chain.block_hash,
+            timestamp: chain.timestamp,
+            next_block_height: chain.next_block_height,
+            pending_proposal: chain.pending_proposal,
+        }
+    }
+}
+
 impl Clone for UserChain {
     fn clone(&self) -> Self {
         Self {
             chain_id: self.chain_id,
             key_pair: self.key_pair.as_ref().map(AccountSecretKey::copy),
+            owner: self.owner,
             block_hash: self.block_hash,
             timestamp: self.timestamp,
             next_block_height: self.next_block_height,
@@ -235,6 +551,7 @@ impl UserChain {
     ) -> Self {
         Self {
             chain_id: description.into(),
+            owner: Some(AccountOwner::from(key_pair.public())),
             key_pair: Some(key_pair),
             block_hash: None,
             timestamp,
@@ -249,6 +566,7 @@ impl UserChain {
         Self {
             chain_id,
             key_pair: None,
+            owner: None,
             block_hash: None,
             timestamp,
             next_block_height: BlockHeight::ZERO,
@@ -19,7 +19,7 @@ use linera_base::{
 use linera_chain::{
     data_types::{BlockProposal, MessageBundle, Origin, ProposedBlock, Target},
     types::{Block, ConfirmedBlockCertificate, TimeoutCertificate, ValidatedBlockCertificate},
-    ChainStateView,
+    ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{
     ExecutionStateView, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint,
@@ -87,8 +87,9 @@ where
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
+        options: ExecutionOptions,
         #[debug(skip)]
-        callback: oneshot::Sender<Result<(Block, ChainInfoResponse), WorkerError>>,
+        callback: oneshot::Sender<Result<(Block, ChainInfoResponse, ExecutionReport), WorkerError>>,
     },
 
     /// Process a leader timeout issued for this multi-owner chain.
@@ -357,11 +358,12 @@ where
                 block,
                 round,
                 published_blobs,
+                options,
                 callback,
             } => callback
                 .send(
                     self.worker
-                        .stage_block_execution(block, round, &published_blobs)
+                        .stage_block_execution(block, round, &published_blobs, options)
                         .await,
                 )
                 .is_ok(),
@@ -6,6 +6,11 @@
 use std::sync::Arc;
 
 use linera_base::{crypto::ValidatorSecretKey, time::Duration};
+use linera_storage::RetentionPolicy;
+
+/// The number of blocks after which a chain is pruned again, unless the retention policy is
+/// archival.
+pub const PRUNING_INTERVAL: u64 = 100;
 
 /// Configuration parameters for the [`ChainWorkerState`][`super::state::ChainWorkerState`].
 #[derive(Clone, Default)]
@@ -22,6 +27,8 @@ pub struct ChainWorkerConfig {
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
     /// will wait until that timestamp before voting.
     pub grace_period: Duration,
+    /// Which confirmed blocks and certificates are kept in storage.
+    pub retention_policy: RetentionPolicy,
 }
 
 impl ChainWorkerConfig {
@@ -13,6 +13,6 @@ pub(super) use self::delivery_notifier::DeliveryNotifier;
 pub(crate) use self::state::CrossChainUpdateHelper;
 pub use self::{
     actor::{ChainWorkerActor, ChainWorkerRequest},
-    config::ChainWorkerConfig,
+    config::{ChainWorkerConfig, PRUNING_INTERVAL},
     state::ChainWorkerState,
 };
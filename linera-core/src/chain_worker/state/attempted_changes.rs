@@ -10,7 +10,7 @@ use linera_base::{
     crypto::ValidatorPublicKey,
     data_types::{Blob, BlockHeight, Epoch, Timestamp},
     ensure,
-    identifiers::{AccountOwner, ChainId},
+    identifiers::{AccountOwner, ChainId, StreamId},
 };
 use linera_chain::{
     data_types::{
@@ -31,6 +31,7 @@ use tracing::{debug, instrument, trace, warn};
 
 use super::{check_block_epoch, ChainWorkerConfig, ChainWorkerState};
 use crate::{
+    chain_worker::PRUNING_INTERVAL,
     data_types::ChainInfoResponse,
     worker::{NetworkActions, Notification, Reason, WorkerError},
 };
@@ -408,9 +409,36 @@ where
             chain_id,
             reason: Reason::NewBlock { height, hash },
         });
+        let mut new_events = BTreeMap::<StreamId, Vec<u32>>::new();
+        for event in certificate.block().body.events.iter().flatten() {
+            new_events
+                .entry(event.stream_id.clone())
+                .or_default()
+                .push(event.index);
+        }
+        actions
+            .notifications
+            .extend(
+                new_events
+                    .into_iter()
+                    .map(|(stream_id, indices)| Notification {
+                        chain_id,
+                        reason: Reason::NewEvents { stream_id, indices },
+                    }),
+            );
         // Persist chain.
         self.save().await?;
 
+        let retention_policy = self.state.config.retention_policy;
+        if !retention_policy.is_archival() && height.0 % PRUNING_INTERVAL == 0 {
+            let storage = self.state.storage.clone();
+            linera_base::task::spawn(async move {
+                if let Err(error) = storage.prune_chain(chain_id, &retention_policy).await {
+                    warn!("Failed to prune chain {chain_id:.8}: {error}");
+                }
+            });
+        }
+
         self.state
             .block_values
             .insert(Cow::Owned(certificate.into_inner().into_inner()));
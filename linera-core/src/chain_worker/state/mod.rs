@@ -25,7 +25,7 @@ use linera_chain::{
     },
     manager,
     types::{Block, ConfirmedBlockCertificate, TimeoutCertificate, ValidatedBlockCertificate},
-    ChainError, ChainStateView,
+    ChainError, ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{
     ExecutionStateView, Message, Query, QueryContext, QueryOutcome, ServiceRuntimeEndpoint,
@@ -183,18 +183,19 @@ where
             .await
     }
 
-    /// Executes a block without persisting any changes to the state.
+    /// Executes a block without persisting any changes to the state, and returns what the
+    /// `options` ask to record about its execution.
     pub(super) async fn stage_block_execution(
         &mut self,
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: &[Blob],
-    ) -> Result<(Block, ChainInfoResponse), WorkerError> {
-        let (block, response) = ChainWorkerStateWithTemporaryChanges::new(self)
+        options: ExecutionOptions,
+    ) -> Result<(Block, ChainInfoResponse, ExecutionReport), WorkerError> {
+        ChainWorkerStateWithTemporaryChanges::new(self)
+            .await
+            .stage_block_execution(block, round, published_blobs, options)
             .await
-            .stage_block_execution(block, round, published_blobs)
-            .await?;
-        Ok((block, response))
     }
 
     /// Processes a leader timeout issued for this multi-owner chain.
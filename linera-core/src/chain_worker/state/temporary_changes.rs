@@ -15,6 +15,7 @@ use linera_chain::{
     },
     manager,
     types::Block,
+    ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{Query, QueryOutcome};
 use linera_storage::{Clock as _, Storage};
@@ -119,20 +120,22 @@ where
         Ok(response)
     }
 
-    /// Executes a block without persisting any changes to the state.
+    /// Executes a block without persisting any changes to the state, and returns what the
+    /// `options` ask to record about its execution.
     pub(super) async fn stage_block_execution(
         &mut self,
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: &[Blob],
-    ) -> Result<(Block, ChainInfoResponse), WorkerError> {
+        options: ExecutionOptions,
+    ) -> Result<(Block, ChainInfoResponse, ExecutionReport), WorkerError> {
         let local_time = self.0.storage.clock().current_time();
         let signer = block.authenticated_signer;
         let (_, committee) = self.0.chain.current_committee()?;
         block.check_proposal_size(committee.policy().maximum_block_proposal_size)?;
 
-        let outcome = self
-            .execute_block(&block, local_time, round, published_blobs)
+        let (outcome, report) = self
+            .execute_block(&block, local_time, round, published_blobs, options)
             .await?;
 
         let mut response = ChainInfoResponse::new(&self.0.chain, None);
@@ -147,7 +150,7 @@ where
                 .await?;
         }
 
-        Ok((outcome.with(block), response))
+        Ok((outcome.with(block), response, report))
     }
 
     /// Validates a proposal's signatures; returns `manager::Outcome::Skip` if we already voted
@@ -219,8 +222,15 @@ where
         let outcome = if let Some(outcome) = outcome {
             outcome.clone()
         } else {
-            self.execute_block(block, local_time, round.multi_leader(), published_blobs)
-                .await?
+            self.execute_block(
+                block,
+                local_time,
+                round.multi_leader(),
+                published_blobs,
+                ExecutionOptions::default(),
+            )
+            .await?
+            .0
         };
 
         ensure!(
@@ -299,6 +309,7 @@ where
             };
             let keys = chain.confirmed_log.read(start..end).await?;
             info.requested_sent_certificate_hashes = keys;
+            info.pruned_height = self.0.storage.pruned_height(chain.chain_id()).await?;
         }
         if let Some(start) = query.request_received_log_excluding_first_n {
             let start = usize::try_from(start).map_err(|_| ArithmeticError::Overflow)?;
@@ -310,20 +321,29 @@ where
         Ok(ChainInfoResponse::new(info, self.0.config.key_pair()))
     }
 
-    /// Executes a block, caches the result, and returns the outcome.
+    /// Executes a block, caches the result, and returns the outcome and what the `options`
+    /// ask to record about its execution.
     async fn execute_block(
         &mut self,
         block: &ProposedBlock,
         local_time: Timestamp,
         round: Option<u32>,
         published_blobs: &[Blob],
-    ) -> Result<BlockExecutionOutcome, WorkerError> {
-        let (outcome, subscribe, unsubscribe) =
-            Box::pin(
-                self.0
-                    .chain
-                    .execute_block(block, local_time, round, published_blobs, None),
-            )
+        options: ExecutionOptions,
+    ) -> Result<(BlockExecutionOutcome, ExecutionReport), WorkerError> {
+        let chain = &mut self.0.chain;
+        let (outcome, report, subscribe, unsubscribe) =
+            Box::pin(ChainStateView::execute_block_inner(
+                &mut chain.execution_state,
+                &chain.confirmed_log,
+                &chain.previous_message_blocks,
+                block,
+                local_time,
+                round,
+                published_blobs,
+                None,
+                options,
+            ))
             .await?;
         if subscribe.is_empty() && unsubscribe.is_empty() {
             self.0.execution_state_cache.insert_owned(
@@ -331,7 +351,7 @@ where
                 self.0.chain.execution_state.clone_unchecked()?,
             );
         }
-        Ok(outcome)
+        Ok((outcome, report))
     }
 }
 
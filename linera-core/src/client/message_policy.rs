@@ -0,0 +1,252 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Policies for automatically handling incoming messages.
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use async_graphql::{Enum, InputObject, SimpleObject};
+use custom_debug_derive::Debug;
+use linera_base::{
+    data_types::Amount,
+    identifiers::{ApplicationId, ChainId, GenericApplicationId},
+};
+use linera_chain::data_types::{IncomingBundle, MessageAction, PostedMessage};
+use linera_execution::MessageKind;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+/// A predicate on the incoming messages of an application, supplied by the user.
+pub type MessagePredicate = Arc<dyn Fn(&PostedMessage) -> bool + Send + Sync>;
+
+/// Policies for automatically handling incoming messages.
+#[derive(Clone, Debug)]
+pub struct MessagePolicy {
+    /// The blanket policy applied to all messages that don't match any rule.
+    blanket: BlanketMessagePolicy,
+    /// A collection of chains which restrict the origin of messages to be
+    /// accepted. `Option::None` means that messages from all chains are accepted. An empty
+    /// `HashSet` denotes that messages from no chains are accepted.
+    restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    /// The rules checked in order before the blanket policy. They are shared by all the
+    /// clones of the policy, so that updating them affects every chain client using it.
+    rules: Arc<RwLock<Vec<MessageRule>>>,
+    /// The predicates checked by the rules with `predicate` set, by application.
+    #[debug(skip)]
+    predicates: HashMap<ApplicationId, MessagePredicate>,
+}
+
+#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+pub enum BlanketMessagePolicy {
+    /// Automatically accept all incoming messages. Reject them only if execution fails.
+    Accept,
+    /// Automatically reject tracked messages, ignore or skip untracked messages, but accept
+    /// protected ones.
+    Reject,
+    /// Don't include any messages in blocks, and don't make any decision whether to accept or
+    /// reject.
+    Ignore,
+}
+
+/// A rule for handling incoming message bundles.
+///
+/// A bundle matches the rule if it comes from one of the `origins`, and its messages satisfy
+/// all the other conditions that are set: all of them for rules that accept the bundle, since
+/// it is executed as a whole, and at least one of them for rules that reject or defer it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "MessageRuleInput")]
+pub struct MessageRule {
+    /// The chains the bundle must come from. Any chain if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origins: Option<Vec<ChainId>>,
+    /// The application the message must belong to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub application_id: Option<GenericApplicationId>,
+    /// The kinds the message must have, e.g. tracked, protected or bouncing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kinds: Option<Vec<MessageKind>>,
+    /// The minimal grant of the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_grant: Option<Amount>,
+    /// The maximal grant of the message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_grant: Option<Amount>,
+    /// Whether the message must satisfy the predicate supplied for its application. The
+    /// messages of applications without a predicate don't.
+    #[serde(default)]
+    pub predicate: bool,
+    /// What to do with the matching bundles.
+    pub action: MessageRuleAction,
+}
+
+/// What to do with the incoming bundles that match a [`MessageRule`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum MessageRuleAction {
+    /// Accept the bundle. Reject it only if execution fails.
+    Accept,
+    /// Reject the bundle if it is tracked, skip it if it can be skipped, and otherwise accept
+    /// it, since protected messages cannot be rejected.
+    Reject,
+    /// Leave the bundle, and the later ones from the same origin, in the inbox for now.
+    Defer,
+}
+
+/// A list of [`MessageRule`]s in JSON, e.g. as given on the command line.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MessageRules(pub Vec<MessageRule>);
+
+impl FromStr for MessageRules {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl MessageRule {
+    /// Returns whether the `bundle` matches this rule, given the user-supplied `predicates`.
+    fn matches(
+        &self,
+        bundle: &IncomingBundle,
+        predicates: &HashMap<ApplicationId, MessagePredicate>,
+    ) -> bool {
+        if self
+            .origins
+            .as_ref()
+            .is_some_and(|origins| !origins.contains(&bundle.origin.sender))
+        {
+            return false;
+        }
+        let mut messages = bundle.bundle.messages.iter();
+        match self.action {
+            MessageRuleAction::Accept => {
+                messages.all(|message| self.matches_message(message, predicates))
+            }
+            MessageRuleAction::Reject | MessageRuleAction::Defer => {
+                messages.any(|message| self.matches_message(message, predicates))
+            }
+        }
+    }
+
+    fn matches_message(
+        &self,
+        message: &PostedMessage,
+        predicates: &HashMap<ApplicationId, MessagePredicate>,
+    ) -> bool {
+        let application_id = message.message.application_id();
+        self.application_id.is_none_or(|id| id == application_id)
+            && self
+                .kinds
+                .as_ref()
+                .is_none_or(|kinds| kinds.contains(&message.kind))
+            && self.min_grant.is_none_or(|min| message.grant >= min)
+            && self.max_grant.is_none_or(|max| message.grant <= max)
+            && (!self.predicate
+                || application_id
+                    .user_application_id()
+                    .and_then(|id| predicates.get(id))
+                    .is_some_and(|predicate| predicate(message)))
+    }
+}
+
+impl MessagePolicy {
+    pub fn new(
+        blanket: BlanketMessagePolicy,
+        restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    ) -> Self {
+        Self {
+            blanket,
+            restrict_chain_ids_to,
+            rules: Arc::default(),
+            predicates: HashMap::new(),
+        }
+    }
+
+    /// Returns the policy with the given rules, checked in order before the blanket policy.
+    pub fn with_rules(mut self, rules: Vec<MessageRule>) -> Self {
+        self.rules = Arc::new(RwLock::new(rules));
+        self
+    }
+
+    /// Returns the policy with the `predicate` for the messages of `application_id`, checked
+    /// by the rules with `predicate` set.
+    pub fn with_predicate(
+        mut self,
+        application_id: ApplicationId,
+        predicate: impl Fn(&PostedMessage) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicates.insert(application_id, Arc::new(predicate));
+        self
+    }
+
+    /// Returns the current rules.
+    pub fn rules(&self) -> Vec<MessageRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Replaces the rules, for this policy and all its clones.
+    pub fn set_rules(&self, rules: Vec<MessageRule>) {
+        *self.rules.write().unwrap() = rules;
+    }
+
+    /// Selects the pending `bundles` to include in the next block, in inbox order, and sets
+    /// their actions.
+    #[instrument(level = "trace", skip_all)]
+    pub(crate) fn apply(
+        &self,
+        bundles: Vec<IncomingBundle>,
+    ) -> impl Iterator<Item = IncomingBundle> + '_ {
+        let rules = self.rules();
+        let mut deferred_origins = HashSet::new();
+        bundles.into_iter().filter_map(move |mut bundle| {
+            if deferred_origins.contains(&bundle.origin)
+                || self
+                    .restrict_chain_ids_to
+                    .as_ref()
+                    .is_some_and(|chains| !chains.contains(&bundle.origin.sender))
+            {
+                return None;
+            }
+            let action = rules
+                .iter()
+                .find(|rule| rule.matches(&bundle, &self.predicates))
+                .map_or_else(|| self.blanket_action(), |rule| rule.action);
+            match action {
+                MessageRuleAction::Accept => {}
+                MessageRuleAction::Reject => {
+                    if bundle.bundle.is_skippable() {
+                        return None;
+                    } else if bundle.bundle.is_tracked() {
+                        bundle.action = MessageAction::Reject;
+                    }
+                }
+                MessageRuleAction::Defer => {
+                    // Later bundles from the same inbox cannot be received before this one.
+                    deferred_origins.insert(bundle.origin.clone());
+                    return None;
+                }
+            }
+            Some(bundle)
+        })
+    }
+
+    fn blanket_action(&self) -> MessageRuleAction {
+        match self.blanket {
+            BlanketMessagePolicy::Accept => MessageRuleAction::Accept,
+            BlanketMessagePolicy::Reject => MessageRuleAction::Reject,
+            BlanketMessagePolicy::Ignore => MessageRuleAction::Defer,
+        }
+    }
+
+    /// Returns whether no messages are handled at all.
+    #[instrument(level = "trace", skip(self))]
+    pub(crate) fn is_ignore(&self) -> bool {
+        matches!(self.blanket, BlanketMessagePolicy::Ignore)
+            && self.rules.read().unwrap().is_empty()
+    }
+}
@@ -26,7 +26,7 @@ use futures::{
 use linera_base::prometheus_util::MeasureLatency as _;
 use linera_base::{
     abi::Abi,
-    crypto::{AccountPublicKey, AccountSecretKey, CryptoHash, ValidatorPublicKey},
+    crypto::{AccountPublicKey, CryptoHash, ValidatorPublicKey},
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, Blob, BlobContent, BlockHeight, Epoch,
         Round, Timestamp,
@@ -42,24 +42,26 @@ use linera_base::{
 use linera_base::{data_types::Bytecode, vm::VmRuntime};
 use linera_chain::{
     data_types::{
-        BlockProposal, ChainAndHeight, IncomingBundle, LiteVote, MessageAction, ProposedBlock,
+        BlockProposal, ChainAndHeight, IncomingBundle, LiteVote, MessageAction, ProposalContent,
+        ProposedBlock,
     },
     manager::LockingBlock,
     types::{
         Block, CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate,
         LiteCertificate, Timeout, TimeoutCertificate, ValidatedBlock, ValidatedBlockCertificate,
     },
-    ChainError, ChainExecutionContext, ChainStateView,
+    ChainError, ChainExecutionContext, ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{
     committee::Committee,
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemOperation, EPOCH_STREAM_NAME,
-        OPEN_CHAIN_MESSAGE_INDEX, REMOVED_EPOCH_STREAM_NAME,
+        application_upgrade_stream_id, AdminOperation, OpenChainConfig, Recipient, SystemOperation,
+        EPOCH_STREAM_NAME, OPEN_CHAIN_MESSAGE_INDEX, REMOVED_EPOCH_STREAM_NAME,
     },
-    ExecutionError, Operation, Query, QueryOutcome, QueryResponse, SystemQuery, SystemResponse,
+    ExecutionError, Operation, Query, QueryOutcome, QueryResponse, ResourceProfile, SystemQuery,
+    SystemResponse,
 };
-use linera_storage::{Clock as _, Storage};
+use linera_storage::{Clock as _, RetentionPolicy, Storage};
 use linera_views::views::ViewError;
 use rand::prelude::SliceRandom as _;
 use serde::{Deserialize, Serialize};
@@ -70,7 +72,8 @@ use tracing::{debug, error, info, instrument, warn, Instrument as _};
 
 use crate::{
     data_types::{
-        BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse, ClientOutcome, RoundTimeout,
+        BatchPrecondition, BlockHeightRange, ChainInfo, ChainInfoQuery, ChainInfoResponse,
+        ClientOutcome, DryRunOutcome, OperationBatch, RoundTimeout,
     },
     local_node::{LocalNodeClient, LocalNodeError},
     node::{
@@ -79,14 +82,20 @@ use crate::{
     },
     notifier::ChannelNotifier,
     remote_node::RemoteNode,
+    signer::{Signer, SignerError},
     updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
     worker::{Notification, ProcessableCertificate, Reason, WorkerError, WorkerState},
 };
+pub use message_policy::{
+    BlanketMessagePolicy, MessagePolicy, MessagePredicate, MessageRule, MessageRuleAction,
+    MessageRules,
+};
 
 mod chain_client_state;
 #[cfg(test)]
 #[path = "../unit_tests/client_tests.rs"]
 mod client_tests;
+mod message_policy;
 
 #[cfg(with_metrics)]
 mod metrics {
@@ -175,6 +184,8 @@ where
     max_loaded_chains: NonZeroUsize,
     /// The delay when downloading a blob, after which we try a second validator.
     blob_download_timeout: Duration,
+    /// The signer holding the keys of the chain owners we act on behalf of.
+    signer: Arc<dyn Signer>,
 }
 
 impl<P, S: Storage + Clone> Client<P, S> {
@@ -190,8 +201,10 @@ impl<P, S: Storage + Clone> Client<P, S> {
         tracked_chains: impl IntoIterator<Item = ChainId>,
         name: impl Into<String>,
         max_loaded_chains: NonZeroUsize,
+        retention_policy: RetentionPolicy,
         grace_period: f64,
         blob_download_timeout: Duration,
+        signer: Arc<dyn Signer>,
     ) -> Self {
         let tracked_chains = Arc::new(RwLock::new(tracked_chains.into_iter().collect()));
         let state = WorkerState::new_for_client(
@@ -202,7 +215,8 @@ impl<P, S: Storage + Clone> Client<P, S> {
         )
         .with_long_lived_services(long_lived_services)
         .with_allow_inactive_chains(true)
-        .with_allow_messages_from_deprecated_epochs(true);
+        .with_allow_messages_from_deprecated_epochs(true)
+        .with_retention_policy(retention_policy);
         let local_node = LocalNodeClient::new(state);
 
         Self {
@@ -218,6 +232,7 @@ impl<P, S: Storage + Clone> Client<P, S> {
             storage,
             max_loaded_chains,
             blob_download_timeout,
+            signer,
         }
     }
 
@@ -227,6 +242,12 @@ impl<P, S: Storage + Clone> Client<P, S> {
         &self.storage
     }
 
+    /// Returns the signer used to sign block proposals.
+    #[instrument(level = "trace", skip(self))]
+    pub fn signer(&self) -> &Arc<dyn Signer> {
+        &self.signer
+    }
+
     /// Returns a reference to the [`LocalNodeClient`] of the client.
     #[instrument(level = "trace", skip(self))]
     pub fn local_node(&self) -> &LocalNodeClient<S> {
@@ -248,7 +269,7 @@ impl<P, S: Storage + Clone> Client<P, S> {
     pub fn create_chain_client(
         self: &Arc<Self>,
         chain_id: ChainId,
-        known_key_pairs: Vec<AccountSecretKey>,
+        known_owners: Vec<AccountOwner>,
         admin_id: ChainId,
         block_hash: Option<CryptoHash>,
         timestamp: Timestamp,
@@ -259,7 +280,7 @@ impl<P, S: Storage + Clone> Client<P, S> {
         // the arguments: If they were read from the wallet file, they might be stale.
         if let dashmap::mapref::entry::Entry::Vacant(e) = self.chains.entry(chain_id) {
             e.insert(ChainClientState::new(
-                known_key_pairs,
+                known_owners,
                 block_hash,
                 timestamp,
                 next_block_height,
@@ -405,67 +426,6 @@ where
     }
 }
 
-/// Policies for automatically handling incoming messages.
-#[derive(Clone, Debug)]
-pub struct MessagePolicy {
-    /// The blanket policy applied to all messages.
-    blanket: BlanketMessagePolicy,
-    /// A collection of chains which restrict the origin of messages to be
-    /// accepted. `Option::None` means that messages from all chains are accepted. An empty
-    /// `HashSet` denotes that messages from no chains are accepted.
-    restrict_chain_ids_to: Option<HashSet<ChainId>>,
-}
-
-#[derive(Copy, Clone, Debug, clap::ValueEnum)]
-pub enum BlanketMessagePolicy {
-    /// Automatically accept all incoming messages. Reject them only if execution fails.
-    Accept,
-    /// Automatically reject tracked messages, ignore or skip untracked messages, but accept
-    /// protected ones.
-    Reject,
-    /// Don't include any messages in blocks, and don't make any decision whether to accept or
-    /// reject.
-    Ignore,
-}
-
-impl MessagePolicy {
-    pub fn new(
-        blanket: BlanketMessagePolicy,
-        restrict_chain_ids_to: Option<HashSet<ChainId>>,
-    ) -> Self {
-        Self {
-            blanket,
-            restrict_chain_ids_to,
-        }
-    }
-
-    #[instrument(level = "trace", skip(self))]
-    fn must_handle(&self, bundle: &mut IncomingBundle) -> bool {
-        if self.is_reject() {
-            if bundle.bundle.is_skippable() {
-                return false;
-            } else if bundle.bundle.is_tracked() {
-                bundle.action = MessageAction::Reject;
-            }
-        }
-        let sender = bundle.origin.sender;
-        match &self.restrict_chain_ids_to {
-            None => true,
-            Some(chains) => chains.contains(&sender),
-        }
-    }
-
-    #[instrument(level = "trace", skip(self))]
-    fn is_ignore(&self) -> bool {
-        matches!(self.blanket, BlanketMessagePolicy::Ignore)
-    }
-
-    #[instrument(level = "trace", skip(self))]
-    fn is_reject(&self) -> bool {
-        matches!(self.blanket, BlanketMessagePolicy::Reject)
-    }
-}
-
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct ChainClientOptions {
@@ -584,6 +544,15 @@ pub enum ChainClientError {
 
     #[error(transparent)]
     BcsError(#[from] bcs::Error),
+
+    #[error("Signer error: {0}")]
+    Signer(#[from] SignerError),
+
+    #[error("The precondition {precondition:?} of the batch does not hold: found {actual}")]
+    BatchPreconditionFailed {
+        precondition: BatchPrecondition,
+        actual: String,
+    },
 }
 
 impl From<Infallible> for ChainClientError {
@@ -853,14 +822,11 @@ where
             }
         }
 
-        Ok(pending_message_bundles
+        // The `OpenChain` bundle is always included.
+        let open_chain_bundle = rearranged.then(|| pending_message_bundles.remove(0));
+        Ok(open_chain_bundle
             .into_iter()
-            .filter_map(|mut bundle| {
-                self.options
-                    .message_policy
-                    .must_handle(&mut bundle)
-                    .then_some(bundle)
-            })
+            .chain(self.options.message_policy.apply(pending_message_bundles))
             .take(self.options.max_pending_message_bundles)
             .collect())
     }
@@ -953,8 +919,8 @@ where
             .ok_or(LocalNodeError::InactiveChain(self.chain_id))
     }
 
-    /// Obtains the identity of the current owner of the chain. Returns an error if we have the
-    /// private key for more than one identity.
+    /// Obtains the identity of the current owner of the chain. Returns an error if our signer
+    /// holds the private key for more than one identity.
     #[instrument(level = "trace")]
     pub async fn identity(&self) -> Result<AccountOwner, ChainClientError> {
         let manager = self.chain_info().await?.manager;
@@ -967,7 +933,7 @@ where
             .ownership
             .all_owners()
             .chain(&manager.leader)
-            .filter(|owner| state.known_key_pairs().contains_key(owner));
+            .filter(|owner| state.known_owners().contains(owner));
         let Some(identity) = our_identities.next() else {
             return Err(ChainClientError::CannotFindKeyForChain(self.chain_id));
         };
@@ -978,22 +944,37 @@ where
         Ok(*identity)
     }
 
-    /// Obtains the key pair associated to the current identity.
-    #[instrument(level = "trace")]
-    pub async fn key_pair(&self) -> Result<AccountSecretKey, ChainClientError> {
-        let id = self.identity().await?;
-        Ok(self
-            .state()
-            .known_key_pairs()
-            .get(&id)
-            .expect("key should be known at this point")
-            .copy())
-    }
-
     /// Obtains the public key associated to the current identity.
     #[instrument(level = "trace")]
     pub async fn public_key(&self) -> Result<AccountPublicKey, ChainClientError> {
-        Ok(self.key_pair().await?.public())
+        let owner = self.identity().await?;
+        self.client
+            .signer
+            .public_key(&owner)
+            .await?
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))
+    }
+
+    /// Asks our signer to sign the given proposal content on behalf of `owner`.
+    #[instrument(level = "trace", skip(content, validated_block_certificate))]
+    async fn sign_proposal(
+        &self,
+        owner: &AccountOwner,
+        content: ProposalContent,
+        validated_block_certificate: Option<LiteCertificate<'static>>,
+    ) -> Result<BlockProposal, ChainClientError> {
+        let signer = &self.client.signer;
+        let public_key = signer
+            .public_key(owner)
+            .await?
+            .ok_or(ChainClientError::CannotFindKeyForChain(self.chain_id))?;
+        let signature = signer.sign_proposal(owner, &content).await?;
+        Ok(BlockProposal::from_signed_content(
+            content,
+            public_key,
+            signature,
+            validated_block_certificate,
+        ))
     }
 
     /// Prepares the chain for the next operation, i.e. makes sure we have synchronized it up to
@@ -1751,9 +1732,23 @@ where
             return Ok(());
         }
 
-        let certificates: Vec<ConfirmedBlockCertificate> = remote_node
-            .download_certificates(info.requested_sent_certificate_hashes)
-            .await?;
+        let mut hashes = info.requested_sent_certificate_hashes;
+        if let Some(pruned_height) = info
+            .pruned_height
+            .filter(|height| *height > local_info.next_block_height)
+        {
+            // This validator pruned some of the certificates we are missing. Look for those on
+            // the other validators, and download only the remaining ones from this validator.
+            let nodes = self.validator_nodes().await?;
+            self.client
+                .download_certificates(&nodes, chain_id, pruned_height)
+                .await?;
+            let pruned_count =
+                usize::try_from(pruned_height.try_sub(local_info.next_block_height)?)?;
+            hashes.drain(..pruned_count.min(hashes.len()));
+        }
+        let certificates: Vec<ConfirmedBlockCertificate> =
+            remote_node.download_certificates(hashes).await?;
 
         if !certificates.is_empty()
             && self
@@ -1965,8 +1960,14 @@ where
     ) -> Result<(Block, ChainInfoResponse), ChainClientError> {
         loop {
             let result = self
-                .stage_block_execution(block.clone(), round, published_blobs.clone())
-                .await;
+                .stage_block_execution(
+                    block.clone(),
+                    round,
+                    published_blobs.clone(),
+                    ExecutionOptions::default(),
+                )
+                .await
+                .map(|(block, response, _)| (block, response));
             if let Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
                 WorkerError::ChainError(chain_error),
             ))) = &result
@@ -2007,12 +2008,13 @@ where
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
-    ) -> Result<(Block, ChainInfoResponse), ChainClientError> {
+        options: ExecutionOptions,
+    ) -> Result<(Block, ChainInfoResponse, ExecutionReport), ChainClientError> {
         loop {
             let result = self
                 .client
                 .local_node
-                .stage_block_execution(block.clone(), round, published_blobs.clone())
+                .stage_block_execution(block.clone(), round, published_blobs.clone(), options)
                 .await;
             if let Err(LocalNodeError::BlobsNotFound(blob_ids)) = &result {
                 self.receive_certificates_for_blobs(blob_ids.clone())
@@ -2049,6 +2051,77 @@ where
         }
     }
 
+    /// Executes a batch of operations atomically in a single block, if all the preconditions
+    /// of the batch hold for the state of the chain before that block.
+    ///
+    /// The preconditions are checked again whenever the block has to be retried because
+    /// another block was committed first.
+    #[instrument(level = "trace", skip(batch, blobs))]
+    pub async fn execute_batch(
+        &self,
+        batch: OperationBatch,
+        blobs: Vec<Blob>,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        let OperationBatch {
+            preconditions,
+            operations,
+        } = batch;
+        loop {
+            // TODO(#2066): Remove boxing once the call-stack is shallower
+            match Box::pin(self.execute_block_with_preconditions(
+                operations.clone(),
+                blobs.clone(),
+                &preconditions,
+            ))
+            .await?
+            {
+                ExecuteBlockOutcome::Executed(certificate) => {
+                    return Ok(ClientOutcome::Committed(certificate));
+                }
+                ExecuteBlockOutcome::WaitForTimeout(timeout) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+                ExecuteBlockOutcome::Conflict(certificate) => {
+                    info!(
+                        height = %certificate.block().header.height,
+                        "Another block was committed; checking the preconditions again."
+                    );
+                }
+            };
+        }
+    }
+
+    /// Returns an error if one of the preconditions of a batch does not hold for the local
+    /// state of the chain.
+    async fn check_batch_preconditions(
+        &self,
+        preconditions: &[BatchPrecondition],
+    ) -> Result<(), ChainClientError> {
+        for precondition in preconditions {
+            let actual = match precondition {
+                BatchPrecondition::BlockHeight(height) => {
+                    let next_block_height = self.next_block_height();
+                    if next_block_height == *height {
+                        continue;
+                    }
+                    next_block_height.to_string()
+                }
+                BatchPrecondition::Balance { owner, amount } => {
+                    let balance = self.local_owner_balance(*owner).await?;
+                    if balance == *amount {
+                        continue;
+                    }
+                    balance.to_string()
+                }
+            };
+            return Err(ChainClientError::BatchPreconditionFailed {
+                precondition: precondition.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
     /// Executes an operation.
     pub async fn execute_operation(
         &self,
@@ -2058,6 +2131,106 @@ where
             .await
     }
 
+    /// Executes a list of operations in a new block against the local state of the chain,
+    /// without signing or committing it, and returns the profile of the resources used by the
+    /// applications.
+    ///
+    /// The block is authenticated by `authenticated_signer`, which does not have to be one of
+    /// our keys.
+    #[instrument(level = "trace", skip(operations, blobs))]
+    pub async fn profile_operations(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+        authenticated_signer: Option<AccountOwner>,
+    ) -> Result<ResourceProfile, ChainClientError> {
+        let block = self
+            .unsigned_block(operations, authenticated_signer)
+            .await?;
+        let options = ExecutionOptions {
+            profile: true,
+            ..ExecutionOptions::default()
+        };
+        let (_, _, report) = self
+            .stage_block_execution(block, None, blobs, options)
+            .await?;
+        Ok(report.profile.unwrap_or_default())
+    }
+
+    /// Executes a list of operations in a new block on top of the local chain state, without
+    /// signing or committing it, and returns the block's messages, events, resources and
+    /// fees, or the reason why its execution failed.
+    ///
+    /// The block is authenticated by `authenticated_signer`, which does not have to be one of
+    /// our keys.
+    #[instrument(level = "trace", skip(operations, blobs))]
+    pub async fn dry_run_operations(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+        authenticated_signer: Option<AccountOwner>,
+    ) -> Result<DryRunOutcome, ChainClientError> {
+        let block = self
+            .unsigned_block(operations, authenticated_signer)
+            .await?;
+        let policy = self.local_committee().await?.policy().clone();
+        let options = ExecutionOptions {
+            resources: true,
+            ..ExecutionOptions::default()
+        };
+        let (block, _, report) = match self
+            .stage_block_execution(block, None, blobs, options)
+            .await
+        {
+            Err(ChainClientError::LocalNodeError(LocalNodeError::WorkerError(
+                WorkerError::ChainError(error),
+            ))) => {
+                return Ok(DryRunOutcome {
+                    outcome: None,
+                    resources: None,
+                    fees: None,
+                    error: Some(error.to_string()),
+                });
+            }
+            result => result?,
+        };
+        let resources = report.resources.unwrap_or_default();
+        let (_, outcome) = block.into_proposal();
+        Ok(DryRunOutcome {
+            outcome: Some(outcome),
+            fees: Some(policy.total_fees(&resources)?),
+            resources: Some(resources),
+            error: None,
+        })
+    }
+
+    /// Returns a block with the given operations and no incoming messages, to be executed on
+    /// top of the local chain state without being signed.
+    async fn unsigned_block(
+        &self,
+        operations: Vec<Operation>,
+        authenticated_signer: Option<AccountOwner>,
+    ) -> Result<ProposedBlock, ChainClientError> {
+        let (previous_block_hash, height, timestamp) = {
+            let state = self.state();
+            (
+                state.block_hash(),
+                state.next_block_height(),
+                self.next_timestamp(&[], state.timestamp()),
+            )
+        };
+        Ok(ProposedBlock {
+            epoch: self.epoch().await?,
+            chain_id: self.chain_id,
+            incoming_bundles: Vec::new(),
+            operations,
+            previous_block_hash,
+            height,
+            authenticated_signer,
+            timestamp,
+        })
+    }
+
     /// Executes a new block.
     ///
     /// This must be preceded by a call to `prepare_chain()`.
@@ -2066,6 +2239,18 @@ where
         &self,
         operations: Vec<Operation>,
         blobs: Vec<Blob>,
+    ) -> Result<ExecuteBlockOutcome, ChainClientError> {
+        self.execute_block_with_preconditions(operations, blobs, &[])
+            .await
+    }
+
+    /// Executes a list of operations in a new block, like [`Self::execute_block`], unless one
+    /// of the `preconditions` does not hold for the state of the chain before that block.
+    async fn execute_block_with_preconditions(
+        &self,
+        operations: Vec<Operation>,
+        blobs: Vec<Blob>,
+        preconditions: &[BatchPrecondition],
     ) -> Result<ExecuteBlockOutcome, ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::EXECUTE_BLOCK_LATENCY.measure_latency();
@@ -2082,6 +2267,7 @@ where
             ClientOutcome::Committed(None) => {}
         }
 
+        self.check_batch_preconditions(preconditions).await?;
         let incoming_bundles = self.pending_message_bundles().await?;
         let identity = self.identity().await?;
         let confirmed_value = self
@@ -2496,7 +2682,12 @@ where
                             ChainClientError::InternalError("Missing local locking blobs")
                         })?;
                     let block = self
-                        .stage_block_execution(proposed_block, None, blobs.clone())
+                        .stage_block_execution(
+                            proposed_block,
+                            None,
+                            blobs.clone(),
+                            ExecutionOptions::default(),
+                        )
                         .await?
                         .0;
                     (block, blobs)
@@ -2513,8 +2704,13 @@ where
                 Either::Left(round) => round.multi_leader(),
                 Either::Right(_) => None,
             };
-            let (block, _) = self
-                .stage_block_execution(proposed_block, round, pending_proposal.blobs.clone())
+            let (block, _, _) = self
+                .stage_block_execution(
+                    proposed_block,
+                    round,
+                    pending_proposal.blobs.clone(),
+                    ExecutionOptions::default(),
+                )
                 .await?;
             (block, pending_proposal.blobs)
         } else {
@@ -2536,22 +2732,29 @@ where
         let already_handled_locally = info
             .manager
             .already_handled_proposal(round, &proposed_block);
-        let key_pair = self.key_pair().await?;
         // Create the final block proposal.
-        let proposal = if let Some(locking) = info.manager.requested_locking {
-            Box::new(match *locking {
-                LockingBlock::Regular(cert) => BlockProposal::new_retry(round, cert, &key_pair),
-                LockingBlock::Fast(proposal) => {
-                    BlockProposal::new_initial(round, proposal.content.block, &key_pair)
+        let (content, validated_block_certificate) =
+            if let Some(locking) = info.manager.requested_locking {
+                match *locking {
+                    LockingBlock::Regular(cert) => {
+                        let (content, lite_cert) = ProposalContent::new_retry(round, cert);
+                        (content, Some(lite_cert))
+                    }
+                    LockingBlock::Fast(proposal) => (
+                        ProposalContent::new_initial(round, proposal.content.block),
+                        None,
+                    ),
                 }
-            })
-        } else {
-            Box::new(BlockProposal::new_initial(
-                round,
-                proposed_block.clone(),
-                &key_pair,
-            ))
-        };
+            } else {
+                (
+                    ProposalContent::new_initial(round, proposed_block.clone()),
+                    None,
+                )
+            };
+        let proposal = Box::new(
+            self.sign_proposal(&identity, content, validated_block_certificate)
+                .await?,
+        );
         if !already_handled_locally {
             // Check the final block proposal. This will be cheaper after #1401.
             if let Err(err) = local_node.handle_block_proposal(*proposal.clone()).await {
@@ -2709,14 +2912,19 @@ where
             .await
     }
 
-    /// Rotates the key of the chain.
-    #[instrument(level = "trace", skip(key_pair))]
-    pub async fn rotate_key_pair(
+    /// Rotates the key of the chain. Our signer must hold the new secret key.
+    #[instrument(level = "trace")]
+    pub async fn rotate_key(
         &self,
-        key_pair: AccountSecretKey,
+        new_public_key: AccountPublicKey,
     ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
-        let new_public_key = self.state_mut().insert_known_key_pair(key_pair);
-        self.transfer_ownership(new_public_key.into()).await
+        let new_owner = AccountOwner::from(new_public_key);
+        ensure!(
+            self.client.signer.public_key(&new_owner).await? == Some(new_public_key),
+            ChainClientError::Signer(SignerError::MissingKey(new_owner))
+        );
+        self.state_mut().insert_known_owner(new_owner);
+        self.transfer_ownership(new_owner).await
     }
 
     /// Transfers ownership of the chain to a single super owner.
@@ -2804,6 +3012,22 @@ where
         .await
     }
 
+    /// Upgrades an application created by this chain to the code of another module, and runs
+    /// its migration, if the chain's application permissions allow it. Other chains apply the
+    /// upgrade when they process their inbox.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn upgrade_application(
+        &self,
+        application_id: ApplicationId,
+        module_id: ModuleId,
+    ) -> Result<ClientOutcome<ConfirmedBlockCertificate>, ChainClientError> {
+        self.execute_operation(SystemOperation::UpgradeApplication {
+            application_id,
+            module_id,
+        })
+        .await
+    }
+
     /// Opens a new chain with a derived UID.
     #[instrument(level = "trace", skip(self))]
     pub async fn open_chain(
@@ -3056,7 +3280,11 @@ where
         let mut certificates = Vec::new();
         loop {
             let incoming_bundles = self.pending_message_bundles().await?;
-            let block_operations = epoch_change_ops.next().into_iter().collect::<Vec<_>>();
+            let mut block_operations = epoch_change_ops.next().into_iter().collect::<Vec<_>>();
+            if block_operations.is_empty() {
+                block_operations.extend(self.collect_application_upgrades().await?);
+                block_operations.extend(self.collect_due_timers().await?);
+            }
             if incoming_bundles.is_empty() && block_operations.is_empty() {
                 return Ok((certificates, None));
             }
@@ -3111,6 +3339,79 @@ where
         Ok(epoch_change_ops)
     }
 
+    /// Returns operations to process the pending upgrades of the applications used on this
+    /// chain, published by their creator chains, in order.
+    async fn collect_application_upgrades(&self) -> Result<Vec<Operation>, ChainClientError> {
+        let mut next_upgrades = Vec::new();
+        {
+            let chain = self.chain_state_view().await?;
+            let system = &chain.execution_state.system;
+            for application_id in chain.execution_state.users.indices().await? {
+                let count = system.application_upgrade_count(&application_id).await?;
+                next_upgrades.push((application_id, count));
+            }
+        }
+        let mut upgrade_ops = Vec::new();
+        for (application_id, mut index) in next_upgrades {
+            let description = self
+                .client
+                .local_node
+                .describe_application(self.chain_id, application_id)
+                .await?;
+            if description.creator_chain_id == self.chain_id {
+                continue;
+            }
+            let stream_id = application_upgrade_stream_id(application_id);
+            while self
+                .has_event(EventId {
+                    chain_id: description.creator_chain_id,
+                    stream_id: stream_id.clone(),
+                    index,
+                })
+                .await?
+            {
+                upgrade_ops.push(Operation::system(
+                    SystemOperation::ProcessApplicationUpgrade(application_id),
+                ));
+                index = index.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+            }
+        }
+        Ok(upgrade_ops)
+    }
+
+    /// Returns an operation to fire the timers that are due in the next block, if there are
+    /// any.
+    async fn collect_due_timers(&self) -> Result<Option<Operation>, ChainClientError> {
+        let height = self.next_block_height();
+        let timestamp = self.storage_client().clock().current_time();
+        let chain = self.chain_state_view().await?;
+        let timer_ids = chain
+            .execution_state
+            .system
+            .due_timer_ids(height, timestamp)
+            .await?;
+        if timer_ids.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Operation::system(SystemOperation::FireTimers(
+            timer_ids,
+        ))))
+    }
+
+    /// Returns the earliest timestamp at which a timer set on this chain becomes due, if any
+    /// timer has a timestamp as its deadline.
+    #[instrument(level = "trace")]
+    pub async fn next_timer_timestamp(&self) -> Result<Option<Timestamp>, ChainClientError> {
+        let chain = self.chain_state_view().await?;
+        Ok(chain.execution_state.system.next_timer_timestamp().await?)
+    }
+
+    /// Returns whether some timers set on this chain are due in the next block.
+    #[instrument(level = "trace")]
+    pub async fn has_due_timers(&self) -> Result<bool, ChainClientError> {
+        Ok(self.collect_due_timers().await?.is_some())
+    }
+
     /// Returns whether the system event on the admin chain with the given stream name and key
     /// exists in storage.
     async fn has_admin_event(
@@ -3118,11 +3419,16 @@ where
         stream_name: &[u8],
         index: u32,
     ) -> Result<bool, ChainClientError> {
-        let event_id = EventId {
+        self.has_event(EventId {
             chain_id: self.admin_id,
             stream_id: StreamId::system(stream_name),
             index,
-        };
+        })
+        .await
+    }
+
+    /// Returns whether the event with the given ID exists in storage.
+    async fn has_event(&self, event_id: EventId) -> Result<bool, ChainClientError> {
         match self.client.storage.read_event(event_id).await {
             Ok(_) => Ok(true),
             Err(ViewError::EventsNotFound(_)) => Ok(false),
@@ -3311,6 +3617,8 @@ where
                     error!("Fail to synchronize new block after notification");
                 }
             }
+            // The block that added the events is notified separately.
+            Reason::NewEvents { .. } => {}
         }
     }
 
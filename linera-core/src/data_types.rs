@@ -4,6 +4,7 @@
 
 use std::{collections::BTreeMap, ops::Not};
 
+use async_graphql::SimpleObject;
 use custom_debug_derive::Debug;
 use linera_base::{
     crypto::{
@@ -11,14 +12,15 @@ use linera_base::{
         ValidatorSignature,
     },
     data_types::{Amount, BlockHeight, Epoch, Round, Timestamp},
+    doc_scalar,
     identifiers::{AccountOwner, ChainDescription, ChainId},
 };
 use linera_chain::{
-    data_types::{ChainAndHeight, IncomingBundle, Medium, MessageBundle},
+    data_types::{BlockExecutionOutcome, ChainAndHeight, IncomingBundle, Medium, MessageBundle},
     manager::ChainManagerInfo,
     ChainStateView,
 };
-use linera_execution::{committee::Committee, ExecutionRuntimeContext};
+use linera_execution::{committee::Committee, ExecutionRuntimeContext, Operation, ResourceTracker};
 use linera_storage::ChainRuntimeContext;
 use linera_views::context::Context;
 use serde::{Deserialize, Serialize};
@@ -196,6 +198,10 @@ pub struct ChainInfo {
     /// The response to `request_received_certificates_excluding_first_n`
     #[debug(skip_if = Vec::is_empty)]
     pub requested_received_log: Vec<ChainAndHeight>,
+    /// The height below which this node pruned the certificates of the chain, if any. Only
+    /// set together with `requested_sent_certificate_hashes`.
+    #[debug(skip_if = Option::is_none)]
+    pub pruned_height: Option<BlockHeight>,
 }
 
 impl ChainInfo {
@@ -286,6 +292,7 @@ where
             requested_sent_certificate_hashes: Vec::new(),
             count_received_log: view.received_log.count(),
             requested_received_log: Vec::new(),
+            pruned_height: None,
         }
     }
 }
@@ -329,6 +336,51 @@ pub enum ClientOutcome<T> {
     WaitForTimeout(RoundTimeout),
 }
 
+/// The outcome of executing a list of operations in a block that is neither signed nor
+/// committed.
+#[derive(Clone, Debug, Serialize, Deserialize, SimpleObject)]
+pub struct DryRunOutcome {
+    /// The messages, events and operation results of the block, if it executed successfully.
+    #[debug(skip_if = Option::is_none)]
+    pub outcome: Option<BlockExecutionOutcome>,
+    /// The resources used by the block, if it executed successfully.
+    #[debug(skip_if = Option::is_none)]
+    pub resources: Option<ResourceTracker>,
+    /// The fees that the block would be charged, if it executed successfully.
+    #[debug(skip_if = Option::is_none)]
+    pub fees: Option<Amount>,
+    /// Why the execution of the block failed, if it did.
+    #[debug(skip_if = Option::is_none)]
+    pub error: Option<String>,
+}
+
+/// A condition on the state of a chain before a block, that must hold for a batch of
+/// operations to be executed in that block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BatchPrecondition {
+    /// The block must have this height.
+    BlockHeight(BlockHeight),
+    /// The balance of the account must be exactly this amount. The balance of the chain
+    /// itself is the one of [`AccountOwner::CHAIN`].
+    Balance { owner: AccountOwner, amount: Amount },
+}
+
+doc_scalar!(
+    BatchPrecondition,
+    "A condition on the state of a chain that must hold for a batch of operations to be executed"
+);
+
+/// Operations to be executed atomically in a single block, if all the preconditions hold.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationBatch {
+    /// The conditions that must hold before the block.
+    #[serde(default)]
+    #[debug(skip_if = Vec::is_empty)]
+    pub preconditions: Vec<BatchPrecondition>,
+    /// The system and user operations of the block, in order.
+    pub operations: Vec<Operation>,
+}
+
 #[derive(Debug)]
 pub struct RoundTimeout {
     pub timestamp: Timestamp,
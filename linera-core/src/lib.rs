@@ -15,6 +15,8 @@ pub mod local_node;
 pub mod node;
 pub mod notifier;
 pub mod remote_node;
+pub mod replay;
+pub mod signer;
 #[cfg(with_testing)]
 #[path = "unit_tests/test_utils.rs"]
 pub mod test_utils;
@@ -16,7 +16,7 @@ use linera_base::{
 use linera_chain::{
     data_types::{BlockProposal, ProposedBlock},
     types::{Block, ConfirmedBlockCertificate, GenericCertificate, LiteCertificate},
-    ChainStateView,
+    ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{Query, QueryOutcome};
 use linera_storage::Storage;
@@ -171,17 +171,20 @@ impl<S> LocalNodeClient<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
+    /// Executes a block without committing it, and returns what the `options` ask to record
+    /// about its execution.
     #[instrument(level = "trace", skip_all)]
     pub async fn stage_block_execution(
         &self,
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
-    ) -> Result<(Block, ChainInfoResponse), LocalNodeError> {
+        options: ExecutionOptions,
+    ) -> Result<(Block, ChainInfoResponse, ExecutionReport), LocalNodeError> {
         Ok(self
             .node
             .state
-            .stage_block_execution(block, round, published_blobs)
+            .stage_block_execution(block, round, published_blobs, options)
             .await?)
     }
 
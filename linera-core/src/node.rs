@@ -259,6 +259,15 @@ pub enum NodeError {
     EmptyBlobsNotFound,
     #[error("Local error handling validator response")]
     ResponseHandlingError { error: String },
+
+    #[error(
+        "Certificate {hash} of chain {chain_id} at height {height} was pruned by the validator"
+    )]
+    CertificatePruned {
+        hash: CryptoHash,
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
 }
 
 impl From<tonic::Status> for NodeError {
@@ -290,6 +299,15 @@ impl From<ViewError> for NodeError {
     fn from(error: ViewError) -> Self {
         match error {
             ViewError::BlobsNotFound(blob_ids) => Self::BlobsNotFound(blob_ids),
+            ViewError::CertificatePruned {
+                hash,
+                chain_id,
+                height,
+            } => Self::CertificatePruned {
+                hash,
+                chain_id,
+                height,
+            },
             error => Self::ViewError {
                 error: error.to_string(),
             },
@@ -349,6 +367,7 @@ impl From<WorkerError> for NodeError {
             WorkerError::ChainError(error) => (*error).into(),
             WorkerError::MissingCertificateValue => Self::MissingCertificateValue,
             WorkerError::BlobsNotFound(blob_ids) => Self::BlobsNotFound(blob_ids),
+            WorkerError::ViewError(error @ ViewError::CertificatePruned { .. }) => error.into(),
             error => Self::WorkerError {
                 error: error.to_string(),
             },
@@ -176,10 +176,26 @@ impl<N: ValidatorNode> RemoteNode<N> {
         let query = ChainInfoQuery::new(chain_id).with_sent_certificate_hashes_in_range(range);
         match self.handle_chain_info_query(query).await {
             Ok(info) => {
-                let certificates = self
+                if let Some(pruned_height) = info.pruned_height.filter(|height| *height > start) {
+                    tracing::info!(
+                        name = ?self.public_key, ?chain_id, %pruned_height,
+                        "Validator pruned the requested certificates; trying another one"
+                    );
+                    return Ok(None);
+                }
+                let certificates = match self
                     .node
                     .download_certificates(info.requested_sent_certificate_hashes)
-                    .await?
+                    .await
+                {
+                    Ok(certificates) => certificates,
+                    Err(error @ NodeError::CertificatePruned { .. }) => {
+                        tracing::info!("Failed to download certificates: {error}");
+                        return Ok(None);
+                    }
+                    Err(error) => return Err(error),
+                };
+                let certificates = certificates
                     .into_iter()
                     .map(|c| {
                         ConfirmedBlockCertificate::try_from(c)
@@ -0,0 +1,343 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays of confirmed blocks, to find out why validators disagree about their execution.
+//!
+//! A block is replayed by executing all the earlier blocks of its chain, from the first one,
+//! in a separate storage, and then executing the block itself with tracing enabled. The
+//! outcome of the replay is compared with the outcome recorded in the block. Blocks of chains
+//! whose earlier blocks were pruned cannot be replayed.
+
+use std::{collections::BTreeMap, fmt::Debug};
+
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Blob, BlockHeight, OracleResponse},
+    identifiers::BlobId,
+};
+use linera_chain::{
+    data_types::BlockExecutionOutcome,
+    types::{CertificateValue as _, ConfirmedBlock},
+    ChainError, ChainStateView, ExecutionOptions,
+};
+use linera_execution::ExecutionTrace;
+use linera_storage::Storage;
+use linera_views::views::{RootView as _, ViewError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+/// An error while replaying a block.
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
+    #[error(transparent)]
+    ChainError(#[from] Box<ChainError>),
+    #[error("The blob {0} used by the chain is missing from the storage")]
+    MissingBlob(BlobId),
+    #[error(
+        "The earlier block {hash} at height {height} has a different outcome when replayed; \
+        replay it first"
+    )]
+    EarlierBlockDiverged {
+        hash: CryptoHash,
+        height: BlockHeight,
+    },
+    #[error(
+        "The block {hash} at height {height} was pruned from the storage, but replaying a \
+        block requires all the blocks of its chain up to it"
+    )]
+    BlockPruned {
+        hash: CryptoHash,
+        height: BlockHeight,
+    },
+}
+
+impl From<ChainError> for ReplayError {
+    fn from(error: ChainError) -> Self {
+        Box::new(error).into()
+    }
+}
+
+/// The replay of a block.
+#[derive(Debug, Clone)]
+pub struct BlockReplay {
+    /// The outcome recorded in the block.
+    pub recorded: BlockExecutionOutcome,
+    /// The outcome of the replay.
+    pub replayed: BlockExecutionOutcome,
+    /// The traces of the execution of the block's transactions during the replay.
+    pub traces: Vec<ExecutionTrace>,
+}
+
+/// A difference between the recorded and the replayed outcome of a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutcomeDifference {
+    /// The field of the outcome that differs.
+    pub field: String,
+    /// The index of the transaction, if the field has one entry per transaction.
+    pub transaction_index: Option<usize>,
+    /// The recorded value.
+    pub recorded: String,
+    /// The replayed value.
+    pub replayed: String,
+}
+
+impl BlockReplay {
+    /// Returns whether the replay has the recorded outcome.
+    pub fn matches(&self) -> bool {
+        self.recorded == self.replayed
+    }
+
+    /// Returns the differences between the recorded and the replayed outcome.
+    pub fn differences(&self) -> Vec<OutcomeDifference> {
+        let BlockExecutionOutcome {
+            messages,
+            previous_message_blocks,
+            state_hash,
+            oracle_responses,
+            events,
+            blobs,
+            operation_results,
+        } = &self.replayed;
+        let recorded = &self.recorded;
+        let mut differences = Vec::new();
+        per_transaction(&mut differences, "messages", &recorded.messages, messages);
+        compare(
+            &mut differences,
+            "previous_message_blocks",
+            None,
+            &recorded.previous_message_blocks,
+            previous_message_blocks,
+        );
+        compare(
+            &mut differences,
+            "state_hash",
+            None,
+            &recorded.state_hash,
+            state_hash,
+        );
+        per_transaction(
+            &mut differences,
+            "oracle_responses",
+            &recorded.oracle_responses,
+            oracle_responses,
+        );
+        per_transaction(&mut differences, "events", &recorded.events, events);
+        per_transaction(&mut differences, "blobs", &recorded.blobs, blobs);
+        per_transaction(
+            &mut differences,
+            "operation_results",
+            &recorded.operation_results,
+            operation_results,
+        );
+        differences
+    }
+}
+
+/// Records a difference if the values are not equal.
+fn compare<T: Debug + PartialEq>(
+    differences: &mut Vec<OutcomeDifference>,
+    field: &str,
+    transaction_index: Option<usize>,
+    recorded: &T,
+    replayed: &T,
+) {
+    if recorded != replayed {
+        differences.push(OutcomeDifference {
+            field: field.to_string(),
+            transaction_index,
+            recorded: format!("{recorded:?}"),
+            replayed: format!("{replayed:?}"),
+        });
+    }
+}
+
+/// Records the differences of a field with one entry per transaction.
+fn per_transaction<T: Debug + PartialEq>(
+    differences: &mut Vec<OutcomeDifference>,
+    field: &str,
+    recorded: &[T],
+    replayed: &[T],
+) {
+    if recorded.len() != replayed.len() {
+        compare(differences, field, None, &recorded, &replayed);
+        return;
+    }
+    for (index, (recorded, replayed)) in recorded.iter().zip(replayed).enumerate() {
+        compare(differences, field, Some(index), recorded, replayed);
+    }
+}
+
+/// Replays the confirmed block with the given hash, read from the `source` storage.
+///
+/// The earlier blocks of the chain are executed in the `target` storage, which must contain
+/// the genesis state of the network but not the chain's blocks. The blocks, the blobs and
+/// the events they use are read from the `source` storage.
+pub async fn replay_block<S, T>(
+    source: &S,
+    target: &T,
+    hash: CryptoHash,
+) -> Result<BlockReplay, ReplayError>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    T: Storage + Clone + Send + Sync + 'static,
+{
+    let block = read_confirmed_block(source, hash).await?;
+    let mut earlier_blocks = Vec::new();
+    let mut previous_hash = block.block().header.previous_block_hash;
+    while let Some(hash) = previous_hash {
+        let earlier_block = read_confirmed_block(source, hash).await?;
+        previous_hash = earlier_block.block().header.previous_block_hash;
+        earlier_blocks.push(earlier_block);
+    }
+
+    let mut chain = target.load_chain(block.chain_id()).await?;
+    for earlier_block in earlier_blocks.iter().rev() {
+        debug!(
+            "Executing block {} at height {}",
+            earlier_block.hash(),
+            earlier_block.height()
+        );
+        let (recorded, replayed, _) =
+            execute_block(source, target, &mut chain, earlier_block, false).await?;
+        if recorded != replayed {
+            return Err(ReplayError::EarlierBlockDiverged {
+                hash: earlier_block.hash(),
+                height: earlier_block.height(),
+            });
+        }
+    }
+    let (recorded, replayed, traces) =
+        execute_block(source, target, &mut chain, &block, true).await?;
+    Ok(BlockReplay {
+        recorded,
+        replayed,
+        traces,
+    })
+}
+
+/// Reads a confirmed block, and reports it if it was pruned.
+async fn read_confirmed_block<S: Storage>(
+    storage: &S,
+    hash: CryptoHash,
+) -> Result<ConfirmedBlock, ReplayError> {
+    storage
+        .read_confirmed_block(hash)
+        .await
+        .map_err(|error| match error {
+            ViewError::CertificatePruned { hash, height, .. } => {
+                ReplayError::BlockPruned { hash, height }
+            }
+            error => error.into(),
+        })
+}
+
+/// Executes a confirmed block on its chain, and returns the recorded outcome, the new
+/// outcome, and the traces of the execution if requested. The block is then applied to the
+/// chain, unless it is traced.
+async fn execute_block<S, T>(
+    source: &S,
+    target: &T,
+    chain: &mut ChainStateView<T::Context>,
+    block: &ConfirmedBlock,
+    trace: bool,
+) -> Result<
+    (
+        BlockExecutionOutcome,
+        BlockExecutionOutcome,
+        Vec<ExecutionTrace>,
+    ),
+    ReplayError,
+>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    T: Storage + Clone + Send + Sync + 'static,
+{
+    let published_blobs = copy_dependencies(source, target, block).await?;
+    let local_time = block.block().header.timestamp;
+    if chain.tip_state.get().is_first_block() && chain.is_child() {
+        chain
+            .execute_init_message_from(block.block(), local_time)
+            .await?;
+    }
+    let oracle_responses = Some(block.block().body.oracle_responses.clone());
+    let (proposed_block, recorded) = block.block().clone().into_proposal();
+    if trace {
+        let options = ExecutionOptions {
+            trace: true,
+            ..ExecutionOptions::default()
+        };
+        let (replayed, report) = chain
+            .execute_block_with_options(
+                &proposed_block,
+                local_time,
+                None,
+                &published_blobs,
+                oracle_responses,
+                options,
+            )
+            .await?;
+        return Ok((recorded, replayed, report.traces.unwrap_or_default()));
+    }
+    let (replayed, subscribe, unsubscribe) = chain
+        .execute_block(
+            &proposed_block,
+            local_time,
+            None,
+            &published_blobs,
+            oracle_responses,
+        )
+        .await?;
+    chain.process_unsubscribes(unsubscribe).await?;
+    chain.apply_confirmed_block(block, local_time).await?;
+    chain.process_subscribes(subscribe).await?;
+    chain.save().await?;
+    Ok((recorded, replayed, Vec::new()))
+}
+
+/// Copies the blobs and the events that a block uses from the `source` to the `target`
+/// storage, and returns the blobs that it publishes.
+async fn copy_dependencies<S, T>(
+    source: &S,
+    target: &T,
+    block: &ConfirmedBlock,
+) -> Result<Vec<Blob>, ReplayError>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+    T: Storage + Clone + Send + Sync + 'static,
+{
+    let block = block.block();
+    let created_blobs = block.iter_created_blobs().collect::<BTreeMap<_, _>>();
+    let blob_ids = block
+        .required_blob_ids()
+        .into_iter()
+        .filter(|blob_id| !created_blobs.contains_key(blob_id))
+        .collect::<Vec<_>>();
+    let mut blobs = BTreeMap::new();
+    for (blob_id, blob) in blob_ids.iter().zip(source.read_blobs(&blob_ids).await?) {
+        let blob = blob.ok_or(ReplayError::MissingBlob(*blob_id))?;
+        blobs.insert(*blob_id, blob);
+    }
+    blobs.extend(created_blobs);
+    target
+        .write_blobs(&blobs.values().cloned().collect::<Vec<_>>())
+        .await?;
+    let events = block
+        .body
+        .oracle_responses
+        .iter()
+        .flatten()
+        .filter_map(|response| match response {
+            OracleResponse::Event(event_id, value) => Some((event_id.clone(), value.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    target.write_events(events).await?;
+    Ok(block
+        .published_blob_ids()
+        .into_iter()
+        .filter_map(|blob_id| blobs.remove(&blob_id))
+        .collect())
+}
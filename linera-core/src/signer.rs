@@ -0,0 +1,116 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Abstraction over the holder of the secret keys used to sign block proposals.
+//!
+//! The chain client never needs direct access to a secret key: it only asks a [`Signer`]
+//! for the public key of an owner and for signatures of [`ProposalContent`]s. This allows
+//! the keys to be held in memory, or by a separate process or device.
+
+use std::{collections::BTreeMap, sync::RwLock};
+
+use async_trait::async_trait;
+use linera_base::{
+    crypto::{AccountPublicKey, AccountSecretKey, AccountSignature},
+    identifiers::AccountOwner,
+};
+use linera_chain::data_types::ProposalContent;
+use thiserror::Error;
+
+/// An error returned by a [`Signer`].
+#[derive(Debug, Error)]
+pub enum SignerError {
+    #[error("No key available for owner {0}")]
+    MissingKey(AccountOwner),
+
+    #[error("The signer refused to sign: {0}")]
+    Refused(String),
+
+    #[error("Failed to communicate with the signer: {0}")]
+    Communication(String),
+}
+
+/// Produces signatures on behalf of chain owners.
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+pub trait Signer: Send + Sync {
+    /// Returns the public key of the given `owner`, or `None` if this signer does not hold
+    /// the corresponding secret key.
+    async fn public_key(
+        &self,
+        owner: &AccountOwner,
+    ) -> Result<Option<AccountPublicKey>, SignerError>;
+
+    /// Signs the content of a block proposal with the key of the given `owner`.
+    async fn sign_proposal(
+        &self,
+        owner: &AccountOwner,
+        content: &ProposalContent,
+    ) -> Result<AccountSignature, SignerError>;
+}
+
+/// A [`Signer`] holding secret keys in memory.
+#[derive(Default)]
+pub struct InMemorySigner {
+    keys: RwLock<BTreeMap<AccountOwner, AccountSecretKey>>,
+}
+
+impl InMemorySigner {
+    /// Creates a signer holding the given secret keys.
+    pub fn new(keys: impl IntoIterator<Item = AccountSecretKey>) -> Self {
+        let keys = keys
+            .into_iter()
+            .map(|key| (AccountOwner::from(key.public()), key))
+            .collect();
+        Self {
+            keys: RwLock::new(keys),
+        }
+    }
+
+    /// Adds a secret key to this signer, and returns the owner it corresponds to.
+    pub fn add_key(&self, key: AccountSecretKey) -> AccountOwner {
+        let owner = AccountOwner::from(key.public());
+        self.keys
+            .write()
+            .expect("Panics should not happen while holding a lock to the keys")
+            .insert(owner, key);
+        owner
+    }
+
+    /// Returns whether this signer holds the secret key of the given `owner`.
+    pub fn contains_key(&self, owner: &AccountOwner) -> bool {
+        self.keys
+            .read()
+            .expect("Panics should not happen while holding a lock to the keys")
+            .contains_key(owner)
+    }
+}
+
+#[cfg_attr(not(web), async_trait)]
+#[cfg_attr(web, async_trait(?Send))]
+impl Signer for InMemorySigner {
+    async fn public_key(
+        &self,
+        owner: &AccountOwner,
+    ) -> Result<Option<AccountPublicKey>, SignerError> {
+        Ok(self
+            .keys
+            .read()
+            .expect("Panics should not happen while holding a lock to the keys")
+            .get(owner)
+            .map(AccountSecretKey::public))
+    }
+
+    async fn sign_proposal(
+        &self,
+        owner: &AccountOwner,
+        content: &ProposalContent,
+    ) -> Result<AccountSignature, SignerError> {
+        let keys = self
+            .keys
+            .read()
+            .expect("Panics should not happen while holding a lock to the keys");
+        let key = keys.get(owner).ok_or(SignerError::MissingKey(*owner))?;
+        Ok(key.sign(content))
+    }
+}
@@ -9,7 +9,7 @@ mod wasm;
 use assert_matches::assert_matches;
 use futures::StreamExt;
 use linera_base::{
-    crypto::AccountSecretKey,
+    crypto::{AccountSecretKey, CryptoHash},
     data_types::*,
     identifiers::{Account, AccountOwner, ChainId, MessageId},
     ownership::{ChainOwnership, TimeoutConfig},
@@ -26,8 +26,8 @@ use linera_execution::{
     ExecutionError, Message, MessageKind, Operation, QueryOutcome, ResourceControlPolicy,
     SystemMessage, SystemQuery, SystemResponse,
 };
-use linera_storage::{DbStorage, TestClock};
-use linera_views::memory::MemoryStore;
+use linera_storage::{DbStorage, RetentionPolicy, Storage as _, TestClock};
+use linera_views::{memory::MemoryStore, views::ViewError};
 use rand::Rng;
 use test_case::test_case;
 use test_helpers::{
@@ -46,14 +46,16 @@ use crate::test_utils::ServiceStorageBuilder;
 use crate::{
     client::{
         BlanketMessagePolicy, ChainClient, ChainClientError, ClientOutcome, MessageAction,
-        MessagePolicy,
+        MessagePolicy, MessageRule, MessageRuleAction,
     },
+    data_types::{BatchPrecondition, OperationBatch},
     local_node::LocalNodeError,
     node::{
         CrossChainMessageDelivery,
         NodeError::{self, ClientIoError},
         ValidatorNode,
     },
+    signer::SignerError,
     test_utils::{FaultType, MemoryStorageBuilder, NodeProvider, StorageBuilder, TestBuilder},
     updater::CommunicationError,
     worker::{Notification, Reason, WorkerError},
@@ -260,7 +262,7 @@ where
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
 #[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
 #[test_log::test(tokio::test)]
-async fn test_rotate_key_pair<B>(storage_builder: B) -> anyhow::Result<()>
+async fn test_rotate_key<B>(storage_builder: B) -> anyhow::Result<()>
 where
     B: StorageBuilder,
 {
@@ -269,8 +271,15 @@ where
         .with_policy(ResourceControlPolicy::fuel_and_block());
     let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
     let new_key_pair = AccountSecretKey::generate();
-    let new_owner = AccountOwner::from(new_key_pair.public());
-    let certificate = sender.rotate_key_pair(new_key_pair).await.unwrap().unwrap();
+    let new_public_key = new_key_pair.public();
+    let new_owner = AccountOwner::from(new_public_key);
+    // The signer must hold the new key before we can rotate to it.
+    assert_matches!(
+        sender.rotate_key(new_public_key).await,
+        Err(ChainClientError::Signer(SignerError::MissingKey(owner))) if owner == new_owner
+    );
+    builder.signer.add_key(new_key_pair);
+    let certificate = sender.rotate_key(new_public_key).await.unwrap().unwrap();
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
     assert!(sender.pending_proposal().is_none());
     assert_eq!(sender.identity().await.unwrap(), new_owner);
@@ -294,6 +303,108 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_download_pruned_certificates_from_archival_validator<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+    let chain_id = sender.chain_id();
+    for _ in 0..5 {
+        sender
+            .burn(AccountOwner::CHAIN, Amount::ONE)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    let block_hash = sender.block_hash().unwrap();
+
+    // Only the last validator is archival.
+    let policy = RetentionPolicy::archival().with_keep_last_heights(2);
+    for index in 0..3 {
+        let storage = builder.validator_storage(index);
+        assert_eq!(
+            storage.prune_chain(chain_id, &policy).await?,
+            Some(BlockHeight::from(3))
+        );
+        assert_eq!(
+            storage.pruned_height(chain_id).await?,
+            Some(BlockHeight::from(3))
+        );
+        // Pruning again does nothing.
+        assert_eq!(
+            storage.prune_chain(chain_id, &policy).await?,
+            Some(BlockHeight::from(3))
+        );
+        let blocks = storage
+            .read_confirmed_blocks_downward(block_hash, 10)
+            .await?;
+        assert_eq!(blocks.len(), 2);
+        let first_hash = blocks[1].block().header.previous_block_hash.unwrap();
+        assert_matches!(
+            storage.read_certificate(first_hash).await,
+            Err(ViewError::CertificatePruned { hash, chain_id: id, height })
+                if hash == first_hash && id == chain_id && height == BlockHeight::from(2)
+        );
+        assert_matches!(
+            storage.read_certificates(vec![block_hash, first_hash]).await,
+            Err(ViewError::CertificatePruned { height, .. }) if height == BlockHeight::from(2)
+        );
+        assert_matches!(
+            storage.read_confirmed_block(first_hash).await,
+            Err(ViewError::CertificatePruned { height, .. }) if height == BlockHeight::from(2)
+        );
+        assert_matches!(
+            NodeError::from(storage.read_certificate(first_hash).await.unwrap_err()),
+            NodeError::CertificatePruned { height, .. } if height == BlockHeight::from(2)
+        );
+    }
+    let storage = builder.validator_storage(3);
+    assert_eq!(storage.pruned_height(chain_id).await?, None);
+    assert_eq!(
+        storage
+            .read_confirmed_blocks_downward(block_hash, 10)
+            .await?
+            .len(),
+        5
+    );
+
+    // A new client downloads the full history from the archival validator.
+    let client = builder
+        .make_client(
+            chain_id,
+            AccountSecretKey::generate(),
+            Some(block_hash),
+            BlockHeight::from(5),
+        )
+        .await?;
+    client.synchronize_until(BlockHeight::from(5)).await?;
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(5));
+
+    // Synchronizing with the pruned validators, a client gets the pruned blocks from the
+    // archival one and the remaining ones from the pruned validators themselves.
+    let client = builder
+        .make_client(
+            chain_id,
+            AccountSecretKey::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    let info = client.synchronize_chain_state(chain_id).await?;
+    assert_eq!(info.next_block_height, BlockHeight::from(5));
+    assert_eq!(client.local_balance().await?, Amount::from_tokens(5));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -314,10 +425,7 @@ where
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
     assert!(sender.pending_proposal().is_none());
     assert_matches!(
-        sender
-            .key_pair()
-            .await
-            .map(|kp| AccountSecretKey::public(&kp)), // AccountSecretKey isn't Debug; using PublicKey.
+        sender.public_key().await,
         Err(ChainClientError::CannotFindKeyForChain(_))
     );
     assert_eq!(
@@ -363,7 +471,7 @@ where
         .unwrap();
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
     assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+    assert!(sender.public_key().await.is_ok());
     assert_eq!(
         builder
             .check_that_validators_have_certificate(sender.chain_id, BlockHeight::ZERO, 3)
@@ -492,7 +600,7 @@ where
 
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
     assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+    assert!(sender.public_key().await.is_ok());
     // Make a client to try the new chain.
     let new_id = ChainId::child(message_id);
     let client = builder
@@ -552,7 +660,7 @@ where
     assert_eq!(sender.next_block_height(), BlockHeight::from(1));
     assert_eq!(parent.next_block_height(), BlockHeight::from(1));
     assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+    assert!(sender.public_key().await.is_ok());
     assert_matches!(
         certificate.block().body.operations[open_chain_message_id.index as usize]
             .as_system_operation(),
@@ -643,7 +751,7 @@ where
     assert_eq!(new_id, new_id2);
     assert_eq!(sender.next_block_height(), BlockHeight::from(2));
     assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+    assert!(sender.public_key().await.is_ok());
     assert_matches!(
         certificate.block().body.operations[open_chain_message_id.index as usize]
             .as_system_operation(),
@@ -717,7 +825,7 @@ where
         .unwrap();
     assert_eq!(sender.next_block_height(), BlockHeight::from(2));
     assert!(sender.pending_proposal().is_none());
-    assert!(sender.key_pair().await.is_ok());
+    assert!(sender.public_key().await.is_ok());
     // Make a client to try the new chain.
     let client = builder
         .make_client(new_id, new_key_pair, None, BlockHeight::ZERO)
@@ -773,7 +881,7 @@ where
     );
     assert_eq!(client1.next_block_height(), BlockHeight::from(1));
     assert!(client1.pending_proposal().is_none());
-    assert!(client1.key_pair().await.is_ok());
+    assert!(client1.public_key().await.is_ok());
     assert_eq!(
         builder
             .check_that_validators_have_certificate(client1.chain_id, BlockHeight::ZERO, 3)
@@ -1167,7 +1275,7 @@ where
     admin.stage_new_committee(committee).await.unwrap();
     assert_eq!(admin.next_block_height(), BlockHeight::from(5));
     assert!(admin.pending_proposal().is_none());
-    assert!(admin.key_pair().await.is_ok());
+    assert!(admin.public_key().await.is_ok());
     assert_eq!(admin.epoch().await.unwrap(), Epoch::from(2));
 
     // Sending money from the admin chain is supported.
@@ -1265,6 +1373,139 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_dry_run_operations<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::fuel_and_block());
+    let sender = builder.add_root_chain(1, Amount::from_tokens(3)).await?;
+    let burn = |amount| {
+        Operation::from(SystemOperation::Transfer {
+            owner: AccountOwner::CHAIN,
+            recipient: Recipient::Burn,
+            amount,
+        })
+    };
+
+    let owner = sender.identity().await?;
+    let outcome = sender
+        .dry_run_operations(vec![burn(Amount::from_tokens(1))], vec![], Some(owner))
+        .await?;
+    assert_eq!(outcome.error, None);
+    assert_eq!(outcome.fees, Some(Amount::from_millis(1)));
+    let resources = outcome.resources.unwrap();
+    assert_eq!(resources.blocks, 1);
+    assert_eq!(resources.operations, 1);
+    assert_eq!(outcome.outcome.unwrap().operation_results.len(), 1);
+
+    // A block that would fail is not an error, and nothing is committed.
+    let outcome = sender
+        .dry_run_operations(vec![burn(Amount::from_tokens(4))], vec![], Some(owner))
+        .await?;
+    assert!(outcome.error.is_some());
+    assert_eq!(outcome.fees, None);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(3));
+    assert_eq!(
+        sender.chain_info().await?.next_block_height,
+        BlockHeight::ZERO
+    );
+
+    // A client without any key for the chain can dry-run blocks on behalf of its owners.
+    let observer = builder
+        .make_client(
+            sender.chain_id(),
+            AccountSecretKey::generate(),
+            None,
+            BlockHeight::ZERO,
+        )
+        .await?;
+    assert!(observer.identity().await.is_err());
+    let outcome = observer
+        .dry_run_operations(vec![burn(Amount::from_tokens(1))], vec![], Some(owner))
+        .await?;
+    assert_eq!(outcome.error, None);
+    assert_eq!(outcome.fees, Some(Amount::from_millis(1)));
+    // Without a signer, only unauthenticated operations succeed.
+    let outcome = observer
+        .dry_run_operations(vec![burn(Amount::from_tokens(1))], vec![], None)
+        .await?;
+    assert!(outcome.error.is_some());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_execute_batch<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let sender = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let receiver = builder.add_root_chain(2, Amount::ZERO).await?;
+    let transfer = |amount| {
+        Operation::from(SystemOperation::Transfer {
+            owner: AccountOwner::CHAIN,
+            recipient: Recipient::chain(receiver.chain_id()),
+            amount,
+        })
+    };
+
+    // A precondition that does not hold aborts the whole batch.
+    let batch = OperationBatch {
+        preconditions: vec![
+            BatchPrecondition::BlockHeight(BlockHeight::ZERO),
+            BatchPrecondition::Balance {
+                owner: AccountOwner::CHAIN,
+                amount: Amount::from_tokens(3),
+            },
+        ],
+        operations: vec![transfer(Amount::ONE), transfer(Amount::ONE)],
+    };
+    assert_matches!(
+        sender.execute_batch(batch, vec![]).await,
+        Err(ChainClientError::BatchPreconditionFailed {
+            precondition: BatchPrecondition::Balance { .. },
+            ..
+        })
+    );
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(4));
+
+    // Otherwise all the operations are executed in a single block.
+    let batch = OperationBatch {
+        preconditions: vec![
+            BatchPrecondition::BlockHeight(BlockHeight::ZERO),
+            BatchPrecondition::Balance {
+                owner: AccountOwner::CHAIN,
+                amount: Amount::from_tokens(4),
+            },
+        ],
+        operations: vec![transfer(Amount::ONE), transfer(Amount::ONE)],
+    };
+    let certificate = sender.execute_batch(batch, vec![]).await?.unwrap();
+    assert_eq!(certificate.block().body.operations.len(), 2);
+    assert_eq!(sender.local_balance().await?, Amount::from_tokens(2));
+
+    // A batch for a height that was already used fails.
+    let batch = OperationBatch {
+        preconditions: vec![BatchPrecondition::BlockHeight(BlockHeight::ZERO)],
+        operations: vec![transfer(Amount::ONE)],
+    };
+    assert_matches!(
+        sender.execute_batch(batch, vec![]).await,
+        Err(ChainClientError::BatchPreconditionFailed {
+            precondition: BatchPrecondition::BlockHeight(_),
+            ..
+        })
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -2254,6 +2495,141 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_message_policy_rules<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::only_fuel());
+    let sender1 = builder.add_root_chain(1, Amount::from_tokens(4)).await?;
+    let sender2 = builder.add_root_chain(2, Amount::from_tokens(4)).await?;
+    let mut receiver = builder.add_root_chain(3, Amount::ZERO).await?;
+    let recipient = Recipient::chain(receiver.chain_id());
+    for sender in [&sender1, &sender2] {
+        let cert = sender
+            .transfer(AccountOwner::CHAIN, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+
+    // Messages from the first sender are deferred, and tracked messages from the second one
+    // are rejected.
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None)
+        .with_rules(vec![
+            MessageRule {
+                origins: Some(vec![sender1.chain_id()]),
+                application_id: None,
+                kinds: None,
+                min_grant: None,
+                max_grant: None,
+                predicate: false,
+                action: MessageRuleAction::Defer,
+            },
+            MessageRule {
+                origins: Some(vec![sender2.chain_id()]),
+                application_id: None,
+                kinds: Some(vec![MessageKind::Tracked]),
+                min_grant: None,
+                max_grant: None,
+                predicate: false,
+                action: MessageRuleAction::Reject,
+            },
+        ]);
+    let certs = receiver.process_inbox().await?.0;
+    assert_eq!(certs.len(), 1);
+    assert_eq!(receiver.local_balance().await.unwrap(), Amount::ZERO);
+    sender2
+        .receive_certificate_and_update_validators(certs.into_iter().next().unwrap())
+        .await?;
+    // The message bounces.
+    assert_eq!(sender2.process_inbox().await?.0.len(), 1);
+    assert_eq!(
+        sender2.local_balance().await.unwrap(),
+        Amount::from_tokens(4)
+    );
+
+    // Without the rules, the deferred message is accepted.
+    receiver.options().message_policy.set_rules(Vec::new());
+    assert_eq!(receiver.process_inbox().await?.0.len(), 1);
+    assert_eq!(receiver.local_balance().await.unwrap(), Amount::ONE);
+
+    Ok(())
+}
+
+/// Tests that a rule accepts the bundles whose messages all match it, while rules that reject
+/// or defer bundles apply as soon as one of their messages matches.
+#[test]
+fn test_message_rules_match_bundles() {
+    let posted = |index: u32, kind: MessageKind| PostedMessage {
+        authenticated_signer: None,
+        grant: Amount::ZERO,
+        refund_grant_to: None,
+        kind,
+        index,
+        message: Message::System(SystemMessage::Credit {
+            target: AccountOwner::CHAIN,
+            amount: Amount::ONE,
+            source: AccountOwner::CHAIN,
+        }),
+    };
+    let bundle = IncomingBundle {
+        origin: Origin {
+            sender: ChainId::root(1),
+            medium: Medium::Direct,
+        },
+        bundle: MessageBundle {
+            height: BlockHeight::ZERO,
+            timestamp: Timestamp::from(0),
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            transaction_index: 0,
+            messages: vec![
+                posted(0, MessageKind::Tracked),
+                posted(1, MessageKind::Simple),
+            ],
+        },
+        action: MessageAction::Accept,
+    };
+    let rule = |kinds: Vec<MessageKind>, action: MessageRuleAction| MessageRule {
+        origins: None,
+        application_id: None,
+        kinds: Some(kinds),
+        min_grant: None,
+        max_grant: None,
+        predicate: false,
+        action,
+    };
+    let actions = |rule: MessageRule| {
+        MessagePolicy::new(BlanketMessagePolicy::Reject, None)
+            .with_rules(vec![rule])
+            .apply(vec![bundle.clone()])
+            .map(|bundle| bundle.action)
+            .collect::<Vec<_>>()
+    };
+
+    // Not all messages are tracked: the blanket policy rejects the bundle.
+    assert_eq!(
+        actions(rule(vec![MessageKind::Tracked], MessageRuleAction::Accept)),
+        [MessageAction::Reject]
+    );
+    assert_eq!(
+        actions(rule(
+            vec![MessageKind::Tracked, MessageKind::Simple],
+            MessageRuleAction::Accept
+        )),
+        [MessageAction::Accept]
+    );
+    // One message is simple: the bundle is deferred.
+    assert!(actions(rule(vec![MessageKind::Simple], MessageRuleAction::Defer)).is_empty());
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -30,7 +30,7 @@ use linera_chain::{
     },
 };
 use linera_execution::{committee::Committee, ResourceControlPolicy, WasmRuntime};
-use linera_storage::{DbStorage, Storage, TestClock};
+use linera_storage::{DbStorage, RetentionPolicy, Storage, TestClock};
 #[cfg(all(not(target_arch = "wasm32"), feature = "storage-service"))]
 use linera_storage_service::client::ServiceStoreClient;
 use linera_version::VersionInfo;
@@ -57,6 +57,7 @@ use crate::{
         ValidatorNodeProvider,
     },
     notifier::ChannelNotifier,
+    signer::InMemorySigner,
     updater::DEFAULT_GRACE_PERIOD,
     worker::{NetworkActions, Notification, ProcessableCertificate, WorkerState},
 };
@@ -663,6 +664,8 @@ pub struct TestBuilder<B: StorageBuilder> {
     validator_clients: Vec<LocalValidatorClient<B::Storage>>,
     validator_storages: HashMap<ValidatorPublicKey, B::Storage>,
     chain_client_storages: Vec<B::Storage>,
+    /// The signer holding the keys of all the chain clients created by this builder.
+    pub signer: Arc<InMemorySigner>,
 }
 
 #[async_trait]
@@ -772,6 +775,7 @@ where
             validator_clients,
             validator_storages,
             chain_client_storages: Vec::new(),
+            signer: Arc::new(InMemorySigner::default()),
         })
     }
 
@@ -882,6 +886,11 @@ where
         self.validator_clients.iter().cloned().collect()
     }
 
+    /// Returns the storage of the validator with the given index.
+    pub fn validator_storage(&self, index: usize) -> &B::Storage {
+        &self.validator_storages[&self.validator_clients[index].name()]
+    }
+
     pub fn node(&mut self, index: usize) -> &mut LocalValidatorClient<B::Storage> {
         &mut self.validator_clients[index]
     }
@@ -909,6 +918,7 @@ where
         let storage = self.make_storage().await?;
         self.chain_client_storages.push(storage.clone());
         let provider = self.make_node_provider();
+        let owner = self.signer.add_key(key_pair);
         let builder = Arc::new(Client::new(
             provider,
             storage,
@@ -918,12 +928,14 @@ where
             [chain_id],
             format!("Client node for {:.8}", chain_id),
             NonZeroUsize::new(20).expect("Chain worker limit should not be zero"),
+            RetentionPolicy::archival(),
             DEFAULT_GRACE_PERIOD,
             Duration::from_secs(1),
+            self.signer.clone(),
         ));
         Ok(builder.create_chain_client(
             chain_id,
-            vec![key_pair],
+            vec![owner],
             self.admin_id,
             block_hash,
             Timestamp::from(0),
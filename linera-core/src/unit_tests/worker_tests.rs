@@ -39,7 +39,7 @@ use linera_chain::{
         CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate, Timeout,
         ValidatedBlock,
     },
-    ChainError, ChainExecutionContext,
+    ChainError, ChainExecutionContext, ExecutionOptions,
 };
 use linera_execution::{
     committee::Committee,
@@ -47,12 +47,14 @@ use linera_execution::{
         AdminOperation, OpenChainConfig, Recipient, SystemMessage, SystemOperation,
         EPOCH_STREAM_NAME as NEW_EPOCH_STREAM_NAME, REMOVED_EPOCH_STREAM_NAME,
     },
-    test_utils::{ExpectedCall, RegisterMockApplication, SystemExecutionState},
-    ExecutionError, Message, MessageKind, OutgoingMessage, Query, QueryContext, QueryOutcome,
-    QueryResponse, SystemQuery, SystemResponse,
+    test_utils::{ExpectedCall, MockApplication, RegisterMockApplication, SystemExecutionState},
+    BaseRuntime as _, ContractRuntime as _, ExecutionError, Message, MessageKind, Operation,
+    OutgoingMessage, Query, QueryContext, QueryOutcome, QueryResponse, StorageRead, StorageWrite,
+    SystemQuery, SystemResponse, TraceStep,
 };
-use linera_storage::{DbStorage, Storage, TestClock};
+use linera_storage::{DbStorage, RetentionPolicy, Storage, TestClock};
 use linera_views::{
+    batch::Batch,
     memory::MemoryStore,
     random::generate_test_namespace,
     store::TestKeyValueStore as _,
@@ -70,10 +72,11 @@ use crate::test_utils::ScyllaDbStorageBuilder;
 use crate::{
     chain_worker::CrossChainUpdateHelper,
     data_types::*,
+    replay::ReplayError,
     test_utils::{MemoryStorageBuilder, StorageBuilder},
     worker::{
-        Notification,
-        Reason::{self, NewBlock, NewIncomingBundle},
+        ChainAssignment, Notification,
+        Reason::{self, NewBlock, NewEvents, NewIncomingBundle},
         WorkerError, WorkerState,
     },
 };
@@ -2034,6 +2037,254 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_replay_block<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let sender_key_pair = AccountSecretKey::generate();
+    let chains = vec![
+        (
+            ChainDescription::Root(1),
+            AccountOwner::from(sender_key_pair.public()),
+            Amount::from_tokens(5),
+        ),
+        (ChainDescription::Root(2), AccountOwner::CHAIN, Amount::ZERO),
+    ];
+    let (committee, worker) =
+        init_worker_with_chains(storage_builder.build().await?, chains.clone()).await;
+    let certificate0 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(2),
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate0.clone(), &())
+        .await?;
+    let certificate1 = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(3),
+        Vec::new(),
+        &committee,
+        Amount::ZERO,
+        &worker,
+        Some(&certificate0),
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate1.clone(), &())
+        .await?;
+
+    // The replay starts from the same genesis state, in a separate storage.
+    let target = storage_builder.build().await?;
+    for (description, owner, balance) in chains.clone() {
+        target
+            .create_chain(
+                committee.clone(),
+                ChainId::root(0),
+                description,
+                owner,
+                balance,
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+    let replay = crate::replay::replay_block(&worker.storage, &target, certificate1.hash()).await?;
+    assert!(replay.matches());
+    assert!(replay.differences().is_empty());
+    assert_eq!(replay.traces.len(), 1);
+    assert_eq!(replay.traces[0].transaction_index, 0);
+
+    // A block executing a user application is traced step by step.
+    let chain_id = ChainId::root(1);
+    let expect_calls = |application: &MockApplication| {
+        application.expect_call(ExpectedCall::execute_operation(
+            |runtime, _context, operation| {
+                assert_eq!(runtime.read_value_bytes(b"key".to_vec())?, None);
+                let mut batch = Batch::new();
+                batch.put_key_value_bytes(b"key".to_vec(), operation);
+                runtime.write_batch(batch)?;
+                Ok(vec![])
+            },
+        ));
+        application.expect_call(ExpectedCall::default_finalize());
+    };
+    let (application_id, application, _) = worker
+        .storage
+        .load_chain(chain_id)
+        .await?
+        .execution_state
+        .register_mock_application(0)
+        .await?;
+    expect_calls(&application);
+    let proposed_block = make_child_block(certificate1.value())
+        .with_operation(Operation::User {
+            application_id,
+            bytes: b"value".to_vec(),
+        })
+        .with_authenticated_signer(Some(sender_key_pair.public().into()));
+    let (block, _, _) = worker
+        .stage_block_execution(proposed_block, None, vec![], ExecutionOptions::default())
+        .await?;
+    let certificate2 = make_certificate(&committee, &worker, ConfirmedBlock::new(block));
+    // The staged execution state is reused: the application is not called again.
+    worker
+        .fully_handle_certificate_with_notifications(certificate2.clone(), &())
+        .await?;
+
+    let target = storage_builder.build().await?;
+    for (description, owner, balance) in chains.clone() {
+        target
+            .create_chain(
+                committee.clone(),
+                ChainId::root(0),
+                description,
+                owner,
+                balance,
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+    let (_, target_application, _) = target
+        .load_chain(chain_id)
+        .await?
+        .execution_state
+        .register_mock_application(0)
+        .await?;
+    expect_calls(&target_application);
+    let replay = crate::replay::replay_block(&worker.storage, &target, certificate2.hash()).await?;
+    assert!(replay.matches());
+    assert_eq!(replay.traces.len(), 1);
+    let steps = &replay.traces[0].steps;
+    let position = |step: &TraceStep| {
+        steps
+            .iter()
+            .position(|candidate| candidate == step)
+            .unwrap_or_else(|| panic!("missing step {step:?} in {steps:#?}"))
+    };
+    let execute_operation = position(&TraceStep::EntryPoint {
+        application_id,
+        entry_point: "execute_operation".to_string(),
+    });
+    let read = position(&TraceStep::StorageRead {
+        application_id,
+        read: StorageRead::ReadValue(b"key".to_vec()),
+    });
+    let write = position(&TraceStep::StorageWrite {
+        application_id,
+        writes: vec![StorageWrite::Put {
+            key: b"key".to_vec(),
+            value_len: 5,
+        }],
+    });
+    let finalize = position(&TraceStep::EntryPoint {
+        application_id,
+        entry_point: "finalize".to_string(),
+    });
+    assert!(execute_operation < read && read < write && write < finalize);
+    application.assert_no_more_expected_calls();
+    target_application.assert_no_more_expected_calls();
+
+    // Blocks can't be replayed once the earlier blocks of their chain are pruned.
+    let policy = RetentionPolicy::archival().with_keep_last_heights(1);
+    worker.storage.prune_chain(chain_id, &policy).await?;
+    assert_matches!(
+        crate::replay::replay_block(&worker.storage, &target, certificate2.hash()).await,
+        Err(ReplayError::BlockPruned { hash, height })
+            if hash == certificate1.hash() && height == BlockHeight::from(1)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_replay_corrupted_block<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let sender_key_pair = AccountSecretKey::generate();
+    let chains = vec![
+        (
+            ChainDescription::Root(1),
+            AccountOwner::from(sender_key_pair.public()),
+            Amount::from_tokens(5),
+        ),
+        (ChainDescription::Root(2), AccountOwner::CHAIN, Amount::ZERO),
+    ];
+    let (committee, worker) =
+        init_worker_with_chains(storage_builder.build().await?, chains.clone()).await;
+    let certificate = make_simple_transfer_certificate(
+        ChainDescription::Root(1),
+        &sender_key_pair,
+        ChainId::root(2),
+        Amount::from_tokens(2),
+        Vec::new(),
+        &committee,
+        Amount::from_tokens(3),
+        &worker,
+        None,
+    )
+    .await;
+    worker
+        .fully_handle_certificate_with_notifications(certificate.clone(), &())
+        .await?;
+
+    // A block whose stored outcome differs from its execution, e.g. due to a corrupted
+    // storage or a faulty validator.
+    let (proposed_block, mut outcome) = certificate.block().clone().into_proposal();
+    let recorded_messages = outcome.messages[0].clone();
+    assert!(!recorded_messages.is_empty());
+    outcome.messages[0].clear();
+    outcome.state_hash = CryptoHash::test_hash("corrupted state");
+    let corrupted_certificate = make_certificate(
+        &committee,
+        &worker,
+        ConfirmedBlock::new(outcome.with(proposed_block)),
+    );
+    worker
+        .storage
+        .write_blobs_and_certificate(&[], &corrupted_certificate)
+        .await?;
+
+    let target = storage_builder.build().await?;
+    for (description, owner, balance) in chains {
+        target
+            .create_chain(
+                committee.clone(),
+                ChainId::root(0),
+                description,
+                owner,
+                balance,
+                Timestamp::from(0),
+            )
+            .await?;
+    }
+    let replay =
+        crate::replay::replay_block(&worker.storage, &target, corrupted_certificate.hash()).await?;
+    assert!(!replay.matches());
+    let differences = replay.differences();
+    assert_eq!(
+        differences
+            .iter()
+            .map(|difference| (difference.field.as_str(), difference.transaction_index))
+            .collect::<Vec<_>>(),
+        vec![("messages", Some(0)), ("state_hash", None)]
+    );
+    assert_eq!(differences[0].recorded, "[]");
+    assert_eq!(differences[0].replayed, format!("{recorded_messages:?}"));
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -2477,9 +2728,18 @@ where
             ),
         ),
     );
+    let notifications = Arc::new(Mutex::new(Vec::new()));
     worker
-        .fully_handle_certificate_with_notifications(certificate1.clone(), &())
+        .fully_handle_certificate_with_notifications(certificate1.clone(), &notifications)
         .await?;
+    // Subscribers learn about the new epoch event.
+    assert!(notifications.lock().unwrap().contains(&Notification {
+        chain_id: admin_id,
+        reason: NewEvents {
+            stream_id: event_id.stream_id.clone(),
+            indices: vec![event_id.index],
+        },
+    }));
     {
         // The child is active and has not migrated yet.
         let user_chain = worker.chain_state_view(user_id).await?;
@@ -3198,8 +3458,8 @@ where
             timeout_config: TimeoutConfig::default(),
         })
         .with_authenticated_signer(Some(owner0));
-    let (block0, _) = worker
-        .stage_block_execution(proposed_block0, None, vec![])
+    let (block0, _, _) = worker
+        .stage_block_execution(proposed_block0, None, vec![], ExecutionOptions::default())
         .await?;
     let value0 = ConfirmedBlock::new(block0);
     let certificate0 = make_certificate(&committee, &worker, value0.clone());
@@ -3249,8 +3509,13 @@ where
 
     // Now owner 0 can propose a block, but owner 1 can't.
     let proposed_block1 = make_child_block(&value0.clone());
-    let (block1, _) = worker
-        .stage_block_execution(proposed_block1.clone(), None, vec![])
+    let (block1, _, _) = worker
+        .stage_block_execution(
+            proposed_block1.clone(),
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
     let proposal1_wrong_owner = proposed_block1
         .clone()
@@ -3291,8 +3556,13 @@ where
     let amount = Amount::from_tokens(1);
     let proposed_block2 =
         make_child_block(&value0.clone()).with_simple_transfer(ChainId::root(1), amount);
-    let (block2, _) = worker
-        .stage_block_execution(proposed_block2.clone(), None, vec![])
+    let (block2, _, _) = worker
+        .stage_block_execution(
+            proposed_block2.clone(),
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
 
     // Since round 3 is already over, the validator won't vote for a validated block from round 3.
@@ -3407,8 +3677,8 @@ where
                 ..TimeoutConfig::default()
             },
         });
-    let (block0, _) = worker
-        .stage_block_execution(proposed_block0, None, vec![])
+    let (block0, _, _) = worker
+        .stage_block_execution(proposed_block0, None, vec![], ExecutionOptions::default())
         .await?;
     let value0 = ConfirmedBlock::new(block0);
     let certificate0 = make_certificate(&committee, &worker, value0.clone());
@@ -3498,8 +3768,13 @@ where
                 ..TimeoutConfig::default()
             },
         });
-    let (change_ownership_block, _) = worker
-        .stage_block_execution(change_ownership_block, None, vec![])
+    let (change_ownership_block, _, _) = worker
+        .stage_block_execution(
+            change_ownership_block,
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
     let change_ownership_value = ConfirmedBlock::new(change_ownership_block);
     let change_ownership_certificate =
@@ -3522,8 +3797,13 @@ where
     // Without the transfer, a random key pair can propose a block.
     let proposal = make_child_block(&change_ownership_value)
         .into_proposal_with_round(&AccountSecretKey::generate(), Round::MultiLeader(0));
-    let (block, _) = worker
-        .stage_block_execution(proposal.content.block.clone(), None, vec![])
+    let (block, _, _) = worker
+        .stage_block_execution(
+            proposal.content.block.clone(),
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
     let value = ConfirmedBlock::new(block);
     let (response, _) = worker.handle_block_proposal(proposal).await?;
@@ -3563,8 +3843,8 @@ where
                 ..TimeoutConfig::default()
             },
         });
-    let (block0, _) = worker
-        .stage_block_execution(proposed_block0, None, vec![])
+    let (block0, _, _) = worker
+        .stage_block_execution(proposed_block0, None, vec![], ExecutionOptions::default())
         .await?;
     let value0 = ConfirmedBlock::new(block0);
     let certificate0 = make_certificate(&committee, &worker, value0.clone());
@@ -3581,8 +3861,13 @@ where
     let proposal1 = proposed_block1
         .clone()
         .into_proposal_with_round(&key_pairs[0], Round::Fast);
-    let (block1, _) = worker
-        .stage_block_execution(proposed_block1.clone(), None, vec![])
+    let (block1, _, _) = worker
+        .stage_block_execution(
+            proposed_block1.clone(),
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
     let value1 = ConfirmedBlock::new(block1);
     let (response, _) = worker.handle_block_proposal(proposal1).await?;
@@ -3629,8 +3914,13 @@ where
     worker.handle_block_proposal(proposal3).await?;
 
     // A validated block certificate from a later round can override the locked fast block.
-    let (block2, _) = worker
-        .stage_block_execution(proposed_block2.clone(), None, vec![])
+    let (block2, _, _) = worker
+        .stage_block_execution(
+            proposed_block2.clone(),
+            None,
+            vec![],
+            ExecutionOptions::default(),
+        )
         .await?;
     let value2 = ValidatedBlock::new(block2.clone());
     let certificate2 =
@@ -3688,8 +3978,8 @@ where
     let proposed_block = make_first_block(chain_id)
         .with_simple_transfer(chain_id, Amount::ONE)
         .with_authenticated_signer(Some(key_pair.public().into()));
-    let (block, _) = worker
-        .stage_block_execution(proposed_block, None, vec![])
+    let (block, _, _) = worker
+        .stage_block_execution(proposed_block, None, vec![], ExecutionOptions::default())
         .await?;
     let value = ConfirmedBlock::new(block);
     let certificate = make_certificate(&committee, &worker, value);
@@ -4000,3 +4290,66 @@ where
 
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_chain_assignment_and_eviction<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock().clone();
+    let chain_id = ChainId::root(1);
+    let (_, worker) = init_worker_with_chain(
+        storage,
+        ChainDescription::Root(1),
+        AccountOwner::CHAIN,
+        Amount::ZERO,
+    )
+    .await;
+    // The chain is handed over to this worker until time 1000, and moved away at time 2000.
+    let worker = worker.with_chain_assignment(move |_, now| {
+        if now < Timestamp::from(1000) {
+            ChainAssignment::HandedOverUntil(Timestamp::from(1000))
+        } else if now < Timestamp::from(2000) {
+            ChainAssignment::Served
+        } else {
+            ChainAssignment::NotServed
+        }
+    });
+
+    // Requests wait for the end of the handover.
+    let request = tokio::spawn({
+        let worker = worker.clone();
+        async move { worker.chain_state_view(chain_id).await.map(drop) }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!request.is_finished());
+    clock.set(Timestamp::from(1000));
+    request.await??;
+    assert!(worker.chain_workers.lock().unwrap().contains(&chain_id));
+
+    // Once the chain is moved away, requests are rejected, and the chain is evicted when the
+    // requests in flight are complete.
+    let endpoint = worker.get_chain_worker_endpoint(chain_id).await?;
+    clock.set(Timestamp::from(2000));
+    assert_matches!(
+        worker.chain_state_view(chain_id).await,
+        Err(WorkerError::ChainNotServed(id)) if id == chain_id
+    );
+    let eviction = tokio::spawn({
+        let worker = worker.clone();
+        async move { worker.evict_chain(chain_id).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!eviction.is_finished());
+    assert!(!worker.chain_workers.lock().unwrap().contains(&chain_id));
+    drop(endpoint);
+    eviction.await?;
+    assert_matches!(
+        worker.get_chain_worker_endpoint(chain_id).await,
+        Err(WorkerError::ChainNotServed(_))
+    );
+
+    Ok(())
+}
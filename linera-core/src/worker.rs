@@ -14,11 +14,11 @@ use linera_base::{
     crypto::{CryptoError, CryptoHash, ValidatorPublicKey, ValidatorSecretKey},
     data_types::{
         ApplicationDescription, ArithmeticError, Blob, BlockHeight, DecompressionError, Epoch,
-        Round,
+        Round, Timestamp,
     },
-    doc_scalar,
+    doc_scalar, ensure,
     hashed::Hashed,
-    identifiers::{AccountOwner, ApplicationId, BlobId, ChainId},
+    identifiers::{AccountOwner, ApplicationId, BlobId, ChainId, StreamId},
     time::timer::{sleep, timeout},
 };
 #[cfg(with_testing)]
@@ -31,10 +31,10 @@ use linera_chain::{
         Block, CertificateValue, ConfirmedBlock, ConfirmedBlockCertificate, GenericCertificate,
         LiteCertificate, Timeout, TimeoutCertificate, ValidatedBlock, ValidatedBlockCertificate,
     },
-    ChainError, ChainStateView,
+    ChainError, ChainStateView, ExecutionOptions, ExecutionReport,
 };
 use linera_execution::{ExecutionError, ExecutionStateView, Query, QueryOutcome};
-use linera_storage::Storage;
+use linera_storage::{Clock as _, RetentionPolicy, Storage};
 use linera_views::views::ViewError;
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
@@ -143,6 +143,10 @@ pub enum Reason {
         height: BlockHeight,
         round: Round,
     },
+    NewEvents {
+        stream_id: StreamId,
+        indices: Vec<u32>,
+    },
 }
 
 /// Error type for worker operations.
@@ -212,6 +216,8 @@ pub enum WorkerError {
     InvalidBlockProposal(String),
     #[error("The worker is too busy to handle new chains")]
     FullChainWorkerCache,
+    #[error("Chain {0} is not served by this worker")]
+    ChainNotServed(ChainId),
     #[error("Failed to join spawned worker task")]
     JoinError,
     #[error("Blob was not required by any pending block")]
@@ -273,6 +279,21 @@ impl WorkerError {
     }
 }
 
+/// Whether a worker serves a chain, e.g. according to the assignment of chains to the shards
+/// of a validator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainAssignment {
+    /// The worker serves the chain.
+    Served,
+    /// The chain is being handed over to this worker, which serves it from the given time on.
+    HandedOverUntil(Timestamp),
+    /// Another worker serves the chain.
+    NotServed,
+}
+
+/// Returns the [`ChainAssignment`] of a chain at the given time.
+pub type ChainAssignmentFn = Arc<dyn Fn(ChainId, Timestamp) -> ChainAssignment + Send + Sync>;
+
 /// State of a worker in a validator or a local node.
 pub struct WorkerState<StorageClient>
 where
@@ -294,7 +315,9 @@ where
     /// The set of spawned [`ChainWorkerActor`] tasks.
     chain_worker_tasks: Arc<Mutex<JoinSet>>,
     /// The cache of running [`ChainWorkerActor`]s.
-    chain_workers: Arc<Mutex<LruCache<ChainId, ChainActorEndpoint<StorageClient>>>>,
+    chain_workers: Arc<Mutex<LruCache<ChainId, ChainActor<StorageClient>>>>,
+    /// The chains this worker serves. All of them if `None`.
+    chain_assignment: Option<ChainAssignmentFn>,
 }
 
 impl<StorageClient> Clone for WorkerState<StorageClient>
@@ -312,6 +335,7 @@ where
             delivery_notifiers: self.delivery_notifiers.clone(),
             chain_worker_tasks: self.chain_worker_tasks.clone(),
             chain_workers: self.chain_workers.clone(),
+            chain_assignment: self.chain_assignment.clone(),
         }
     }
 }
@@ -322,6 +346,20 @@ type ChainActorEndpoint<StorageClient> = mpsc::UnboundedSender<(
     tracing::Span,
 )>;
 
+/// A running [`ChainWorkerActor`] in the cache.
+struct ChainActor<StorageClient>
+where
+    StorageClient: Storage,
+{
+    /// The endpoint to send requests to the actor.
+    endpoint: ChainActorEndpoint<StorageClient>,
+    /// Completes once the actor has stopped, i.e. once all its endpoints are dropped.
+    stopped: oneshot::Receiver<()>,
+}
+
+/// How long [`WorkerState::evict_chain`] waits for the requests in flight for a chain.
+const CHAIN_EVICTION_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub(crate) type DeliveryNotifiers = HashMap<ChainId, DeliveryNotifier>;
 
 impl<StorageClient> WorkerState<StorageClient>
@@ -345,6 +383,7 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_assignment: None,
         }
     }
 
@@ -365,6 +404,7 @@ where
             delivery_notifiers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
+            chain_assignment: None,
         }
     }
 
@@ -397,6 +437,18 @@ where
         self
     }
 
+    /// Returns an instance that only serves the chains assigned to it by `chain_assignment`.
+    /// Requests for other chains fail, and requests for chains being handed over to this
+    /// worker wait until the handover is complete.
+    #[instrument(level = "trace", skip(self, chain_assignment))]
+    pub fn with_chain_assignment(
+        mut self,
+        chain_assignment: impl Fn(ChainId, Timestamp) -> ChainAssignment + Send + Sync + 'static,
+    ) -> Self {
+        self.chain_assignment = Some(Arc::new(chain_assignment));
+        self
+    }
+
     /// Returns an instance with the specified grace period, in microseconds.
     ///
     /// Blocks with a timestamp this far in the future will still be accepted, but the validator
@@ -407,6 +459,16 @@ where
         self
     }
 
+    /// Returns an instance with the specified retention policy.
+    ///
+    /// Unless the policy is archival, the old blocks of each chain are pruned every
+    /// [`PRUNING_INTERVAL`](crate::chain_worker::PRUNING_INTERVAL) blocks.
+    #[instrument(level = "trace", skip(self))]
+    pub fn with_retention_policy(mut self, retention_policy: RetentionPolicy) -> Self {
+        self.chain_worker_config.retention_policy = retention_policy;
+        self
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn nickname(&self) -> &str {
         &self.nickname
@@ -427,6 +489,60 @@ where
         &self.storage
     }
 
+    /// Drops the chain's worker from the cache once the requests it is handling are complete,
+    /// e.g. because the chain was moved to another shard. Its state is loaded again from the
+    /// storage the next time it is needed here.
+    ///
+    /// The chain assignment must be updated first, so that no new requests for the chain are
+    /// accepted in the meantime. The worker is dropped from the cache anyway if its requests
+    /// are not complete after [`CHAIN_EVICTION_TIMEOUT`].
+    #[instrument(level = "trace", skip(self))]
+    pub async fn evict_chain(&self, chain_id: ChainId) {
+        loop {
+            let stopped = {
+                let mut chain_workers = self.chain_workers.lock().unwrap();
+                let Some(actor) = chain_workers.pop(&chain_id) else {
+                    return;
+                };
+                self.clean_up_finished_chain_workers(&chain_workers);
+                actor.stopped
+            };
+            // Each request holds an endpoint until it gets its response, and the actor stops
+            // once they are all dropped.
+            if timeout(CHAIN_EVICTION_TIMEOUT, stopped).await.is_err() {
+                warn!(
+                    %chain_id,
+                    "Timed out waiting for the requests in flight before evicting the chain"
+                );
+                return;
+            }
+        }
+    }
+
+    /// Returns the [`ChainAssignment`] of the chain to this worker at the current time.
+    fn chain_assignment(&self, chain_id: ChainId) -> ChainAssignment {
+        match &self.chain_assignment {
+            Some(chain_assignment) => {
+                chain_assignment(chain_id, self.storage.clock().current_time())
+            }
+            None => ChainAssignment::Served,
+        }
+    }
+
+    /// Waits until the chain is handed over to this worker, if it is being handed over, and
+    /// returns an error if another worker serves it.
+    async fn wait_for_chain_assignment(&self, chain_id: ChainId) -> Result<(), WorkerError> {
+        loop {
+            match self.chain_assignment(chain_id) {
+                ChainAssignment::Served => return Ok(()),
+                ChainAssignment::HandedOverUntil(timestamp) => {
+                    self.storage.clock().sleep_until(timestamp).await;
+                }
+                ChainAssignment::NotServed => return Err(WorkerError::ChainNotServed(chain_id)),
+            }
+        }
+    }
+
     #[instrument(level = "trace", skip(self, key_pair))]
     #[cfg(test)]
     pub(crate) async fn with_key_pair(mut self, key_pair: Option<Arc<ValidatorSecretKey>>) -> Self {
@@ -535,19 +651,22 @@ where
         .unwrap_or_else(|_| Err(WorkerError::JoinError))
     }
 
-    /// Tries to execute a block proposal without any verification other than block execution.
+    /// Tries to execute a block proposal without any verification other than block execution,
+    /// and returns what the `options` ask to record about its execution.
     #[instrument(level = "trace", skip(self, block))]
     pub async fn stage_block_execution(
         &self,
         block: ProposedBlock,
         round: Option<u32>,
         published_blobs: Vec<Blob>,
-    ) -> Result<(Block, ChainInfoResponse), WorkerError> {
+        options: ExecutionOptions,
+    ) -> Result<(Block, ChainInfoResponse, ExecutionReport), WorkerError> {
         self.query_chain_worker(block.chain_id, move |callback| {
             ChainWorkerRequest::StageBlockExecution {
                 block,
                 round,
                 published_blobs,
+                options,
                 callback,
             }
         })
@@ -718,19 +837,20 @@ where
         &self,
         chain_id: ChainId,
     ) -> Result<ChainActorEndpoint<StorageClient>, WorkerError> {
-        let (sender, new_receiver) = timeout(Duration::from_secs(3), async move {
+        self.wait_for_chain_assignment(chain_id).await?;
+        let (sender, new_actor) = timeout(Duration::from_secs(3), async move {
             loop {
-                match self.try_get_chain_worker_endpoint(chain_id) {
-                    Some(endpoint) => break endpoint,
+                match self.try_get_chain_worker_endpoint(chain_id)? {
+                    Some(endpoint) => break Ok(endpoint),
                     None => sleep(Duration::from_millis(250)).await,
                 }
                 warn!("No chain worker candidates found for eviction, retrying...");
             }
         })
         .await
-        .map_err(|_| WorkerError::FullChainWorkerCache)?;
+        .map_err(|_| WorkerError::FullChainWorkerCache)??;
 
-        if let Some(receiver) = new_receiver {
+        if let Some((receiver, stopped)) = new_actor {
             let delivery_notifier = self
                 .delivery_notifiers
                 .lock()
@@ -753,14 +873,18 @@ where
             self.chain_worker_tasks
                 .lock()
                 .unwrap()
-                .spawn_task(actor_task);
+                .spawn_task(async move {
+                    actor_task.await;
+                    drop(stopped);
+                });
         }
 
         Ok(sender)
     }
 
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, attempting to create one
-    /// and add it to the cache if needed.
+    /// and add it to the cache if needed. For a new actor, also returns the receiver of its
+    /// requests and a sender to drop once it has stopped.
     ///
     /// Returns [`None`] if the cache is full and no candidate for eviction was found.
     #[instrument(level = "trace", skip(self))]
@@ -768,22 +892,37 @@ where
     fn try_get_chain_worker_endpoint(
         &self,
         chain_id: ChainId,
-    ) -> Option<(
-        ChainActorEndpoint<StorageClient>,
-        Option<
-            mpsc::UnboundedReceiver<(ChainWorkerRequest<StorageClient::Context>, tracing::Span)>,
-        >,
-    )> {
+    ) -> Result<
+        Option<(
+            ChainActorEndpoint<StorageClient>,
+            Option<(
+                mpsc::UnboundedReceiver<(
+                    ChainWorkerRequest<StorageClient::Context>,
+                    tracing::Span,
+                )>,
+                oneshot::Sender<()>,
+            )>,
+        )>,
+        WorkerError,
+    > {
         let mut chain_workers = self.chain_workers.lock().unwrap();
+        // Checked with the lock held, so that `evict_chain` doesn't miss a new endpoint.
+        ensure!(
+            self.chain_assignment(chain_id) == ChainAssignment::Served,
+            WorkerError::ChainNotServed(chain_id)
+        );
 
-        if let Some(endpoint) = chain_workers.get(&chain_id) {
-            Some((endpoint.clone(), None))
+        if let Some(actor) = chain_workers.get(&chain_id) {
+            Ok(Some((actor.endpoint.clone(), None)))
         } else {
             if chain_workers.len() >= usize::from(chain_workers.cap()) {
-                let (chain_to_evict, _) = chain_workers
+                let Some((chain_to_evict, _)) = chain_workers
                     .iter()
                     .rev()
-                    .find(|(_, candidate_endpoint)| candidate_endpoint.strong_count() <= 1)?;
+                    .find(|(_, candidate)| candidate.endpoint.strong_count() <= 1)
+                else {
+                    return Ok(None);
+                };
                 let chain_to_evict = *chain_to_evict;
 
                 chain_workers.pop(&chain_to_evict);
@@ -791,16 +930,23 @@ where
             }
 
             let (sender, receiver) = mpsc::unbounded_channel();
-            chain_workers.push(chain_id, sender.clone());
+            let (stopped_sender, stopped) = oneshot::channel();
+            chain_workers.push(
+                chain_id,
+                ChainActor {
+                    endpoint: sender.clone(),
+                    stopped,
+                },
+            );
 
-            Some((sender, Some(receiver)))
+            Ok(Some((sender, Some((receiver, stopped_sender)))))
         }
     }
 
     /// Cleans up any finished chain workers and their delivery notifiers.
     fn clean_up_finished_chain_workers(
         &self,
-        active_chain_workers: &LruCache<ChainId, ChainActorEndpoint<StorageClient>>,
+        active_chain_workers: &LruCache<ChainId, ChainActor<StorageClient>>,
     ) {
         self.chain_worker_tasks
             .lock()
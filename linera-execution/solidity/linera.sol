@@ -5,24 +5,117 @@ pragma solidity ^0.8.0;
 // Precompile keys:
 // 0: try_call_application
 // 1: try_query_application
+// 2: chain_id
+// 3: application_creator_chain_id
+// 4: block_height
+// 5: read_system_timestamp
+// 6: read_chain_balance
+// 7: read_owner_balance
+// 8: read_data_blob
+// 9: authenticated_signer
+// 10: send_message
+// 11: transfer
+// 12: emit
+// 13: read_event
+// 14: subscribe_to_events
+// 15: unsubscribe_from_events
+//
+// The arguments of `try_call_application` and `try_query_application` follow the key
+// packed, and those of the other functions follow it ABI-encoded.
+//
+// Account owners are passed as `bytes`: empty for the chain itself, 1 byte for the reserved
+// owners, 20 bytes for an EVM address and 32 bytes for other owners. Amounts are in
+// attotokens.
+//
+// The calls with effects outside of the EVM, i.e. calling applications, sending messages,
+// transferring tokens, emitting events and (un)subscribing, are not undone if the calling
+// frame reverts. They can only be made by the top-level call of a contract, not e.g. by an
+// external call of the contract to itself, whose revert could be caught.
+//
+// Each call is charged gas: 100, plus 3 per 32-byte word of its input and output, plus 2100
+// for reading the state of the chain, 20000 for changing it or sending a message, and 2600
+// for calling another application.
 
 library Linera {
-  function try_call_application(bytes32 universal_address, bytes memory operation) internal returns (bytes memory) {
-    address precompile = address(0x0b);
-    bytes1 input1 = bytes1(uint8(0));
-    bytes memory input2 = abi.encodePacked(input1, universal_address, operation);
-    (bool success, bytes memory output) = precompile.call(input2);
+  address constant PRECOMPILE = address(0x0b);
+
+  function call_precompile(uint8 key, bytes memory arguments) private returns (bytes memory) {
+    bytes memory input = abi.encodePacked(bytes1(key), arguments);
+    (bool success, bytes memory output) = PRECOMPILE.call(input);
     require(success);
     return output;
   }
 
-  function try_query_application(bytes32 universal_address, bytes memory argument) internal returns (bytes memory) {
-    address precompile = address(0x0b);
-    bytes1 input1 = bytes1(uint8(1));
-    bytes memory input2 = abi.encodePacked(input1, universal_address, argument);
-    (bool success, bytes memory output) = precompile.call(input2);
+  function view_precompile(uint8 key, bytes memory arguments) private view returns (bytes memory) {
+    bytes memory input = abi.encodePacked(bytes1(key), arguments);
+    (bool success, bytes memory output) = PRECOMPILE.staticcall(input);
     require(success);
     return output;
   }
 
+  function try_call_application(bytes32 universal_address, bytes memory operation) internal returns (bytes memory) {
+    return call_precompile(0, abi.encodePacked(universal_address, operation));
+  }
+
+  function try_query_application(bytes32 universal_address, bytes memory argument) internal returns (bytes memory) {
+    return call_precompile(1, abi.encodePacked(universal_address, argument));
+  }
+
+  function chain_id() internal view returns (bytes32) {
+    return abi.decode(view_precompile(2, ""), (bytes32));
+  }
+
+  function application_creator_chain_id() internal view returns (bytes32) {
+    return abi.decode(view_precompile(3, ""), (bytes32));
+  }
+
+  function block_height() internal view returns (uint64) {
+    return abi.decode(view_precompile(4, ""), (uint64));
+  }
+
+  function read_system_timestamp() internal view returns (uint64) {
+    return abi.decode(view_precompile(5, ""), (uint64));
+  }
+
+  function read_chain_balance() internal view returns (uint256) {
+    return abi.decode(view_precompile(6, ""), (uint256));
+  }
+
+  function read_owner_balance(bytes memory owner) internal view returns (uint256) {
+    return abi.decode(view_precompile(7, abi.encode(owner)), (uint256));
+  }
+
+  function read_data_blob(bytes32 hash) internal view returns (bytes memory) {
+    return abi.decode(view_precompile(8, abi.encode(hash)), (bytes));
+  }
+
+  // Returns whether the block has an authenticated signer, and the signer.
+  function authenticated_signer() internal view returns (bool, bytes memory) {
+    return abi.decode(view_precompile(9, ""), (bool, bytes));
+  }
+
+  function send_message(bytes32 destination_chain_id, bool authenticated, bool is_tracked, bytes memory message) internal {
+    call_precompile(10, abi.encode(destination_chain_id, authenticated, is_tracked, message));
+  }
+
+  function transfer(bytes memory source, bytes32 destination_chain_id, bytes memory destination_owner, uint256 amount) internal {
+    call_precompile(11, abi.encode(source, destination_chain_id, destination_owner, amount));
+  }
+
+  // Returns the index of the new event in the stream.
+  function emit_event(bytes memory stream_name, bytes memory value) internal returns (uint32) {
+    return abi.decode(call_precompile(12, abi.encode(stream_name, value)), (uint32));
+  }
+
+  function read_event(bytes32 chain, bytes memory stream_name, uint32 index) internal returns (bytes memory) {
+    return abi.decode(call_precompile(13, abi.encode(chain, stream_name, index)), (bytes));
+  }
+
+  function subscribe_to_events(bytes32 chain, bytes32 application_id, bytes memory stream_name) internal {
+    call_precompile(14, abi.encode(chain, application_id, stream_name));
+  }
+
+  function unsubscribe_from_events(bytes32 chain, bytes32 application_id, bytes memory stream_name) internal {
+    call_precompile(15, abi.encode(chain, application_id, stream_name));
+  }
 }
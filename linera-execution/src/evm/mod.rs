@@ -34,4 +34,22 @@ pub enum EvmExecutionError {
     },
     #[error("The operation was halted")]
     Halt { gas_used: u64, reason: HaltReason },
+    #[error("{name} is not available to the EVM {runtime} precompile")]
+    PrecompileUnavailable {
+        name: &'static str,
+        runtime: &'static str,
+    },
+    #[error("The input of a precompile call is too short")]
+    PrecompileInputTooShort,
+    #[error("Failed to decode the arguments of a precompile call: {0}")]
+    PrecompileArguments(#[from] alloy_sol_types::Error),
+    #[error("An account owner cannot be encoded in {0} bytes")]
+    InvalidAccountOwner(usize),
+    #[error("The amount {0} does not fit in 128 bits")]
+    AmountOverflow(alloy::primitives::U256),
+    #[error(
+        "Calls with effects outside of the EVM can only be made by the top-level call frame, \
+         since reverting the other frames would not undo them"
+    )]
+    EffectsInRevertibleFrame,
 }
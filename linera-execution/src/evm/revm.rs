@@ -6,12 +6,14 @@
 use core::ops::Range;
 use std::{convert::TryFrom, sync::Arc};
 
-use alloy::primitives::Address;
+use alloy::primitives::{Address, B256, U256};
+use alloy_sol_types::SolValue;
 use linera_base::{
-    data_types::Bytecode,
+    crypto::CryptoHash,
+    data_types::{Amount, Bytecode, Resources, SendMessageRequest},
     ensure,
-    identifiers::{ApplicationId, StreamName},
-    vm::EvmQuery,
+    identifiers::{Account, AccountOwner, ApplicationId, ChainId, Destination, StreamName},
+    vm::{evm_log_stream_name, EvmLog, EvmQuery},
 };
 use num_enum::TryFromPrimitive;
 use revm::{
@@ -21,8 +23,8 @@ use revm::{
 use revm_interpreter::{CallInputs, CallOutcome, Gas, InstructionResult, InterpreterResult};
 use revm_precompile::PrecompileResult;
 use revm_primitives::{
-    address, ExecutionResult, Log, Output, PrecompileErrors, PrecompileOutput, SuccessReason,
-    TxKind,
+    address, ExecutionResult, Log, Output, PrecompileError, PrecompileErrors, PrecompileOutput,
+    SuccessReason, TxKind,
 };
 #[cfg(with_metrics)]
 use {
@@ -34,10 +36,10 @@ use {
 };
 
 use crate::{
-    evm::database::DatabaseRuntime, ContractRuntime, ContractSyncRuntimeHandle, EvmExecutionError,
-    EvmRuntime, ExecutionError, FinalizeContext, MessageContext, OperationContext, QueryContext,
-    ServiceRuntime, ServiceSyncRuntimeHandle, UserContract, UserContractInstance,
-    UserContractModule, UserService, UserServiceInstance, UserServiceModule,
+    evm::database::DatabaseRuntime, BaseRuntime, ContractRuntime, ContractSyncRuntimeHandle,
+    EvmExecutionError, EvmRuntime, ExecutionError, FinalizeContext, MessageContext,
+    OperationContext, QueryContext, ServiceRuntime, ServiceSyncRuntimeHandle, UserContract,
+    UserContractInstance, UserContractModule, UserService, UserServiceInstance, UserServiceModule,
 };
 
 /// This is the selector of the `execute_message` that should be called
@@ -239,6 +241,9 @@ fn address_to_user_application_id(address: Address) -> ApplicationId {
     u8_slice_to_application_id(&vec)
 }
 
+/// The Linera functionalities available from the precompile. The first byte of the input
+/// is the tag. The arguments of `TryCallApplication` and `TryQueryApplication` follow it
+/// packed, and those of the other calls follow it ABI-encoded.
 #[repr(u8)]
 #[derive(TryFromPrimitive)]
 enum PrecompileTag {
@@ -246,6 +251,364 @@ enum PrecompileTag {
     TryCallApplication,
     /// Key prefix for the try_query_application
     TryQueryApplication,
+    /// Key prefix for the chain_id
+    ChainId,
+    /// Key prefix for the application_creator_chain_id
+    ApplicationCreatorChainId,
+    /// Key prefix for the block_height
+    BlockHeight,
+    /// Key prefix for the read_system_timestamp
+    ReadSystemTimestamp,
+    /// Key prefix for the read_chain_balance
+    ReadChainBalance,
+    /// Key prefix for the read_owner_balance
+    ReadOwnerBalance,
+    /// Key prefix for the read_data_blob
+    ReadDataBlob,
+    /// Key prefix for the authenticated_signer
+    AuthenticatedSigner,
+    /// Key prefix for the send_message
+    SendMessage,
+    /// Key prefix for the transfer
+    Transfer,
+    /// Key prefix for the emit
+    Emit,
+    /// Key prefix for the read_event
+    ReadEvent,
+    /// Key prefix for the subscribe_to_events
+    SubscribeToEvents,
+    /// Key prefix for the unsubscribe_from_events
+    UnsubscribeFromEvents,
+}
+
+/// The gas charged for every precompile call, as for a call to a warm account.
+const PRECOMPILE_BASE_GAS: u64 = 100;
+
+/// The gas charged for each 32-byte word of the input and of the output of a precompile call.
+const PRECOMPILE_WORD_GAS: u64 = 3;
+
+/// The additional gas charged for reading the state of the chain, as for a cold storage
+/// read.
+const PRECOMPILE_READ_GAS: u64 = 2_100;
+
+/// The additional gas charged for changing the state of the chain or sending a message, as
+/// for setting a storage slot.
+const PRECOMPILE_WRITE_GAS: u64 = 20_000;
+
+/// The additional gas charged for calling another application, as for a call to a cold
+/// account. The called application is charged for its own execution.
+const PRECOMPILE_CALL_GAS: u64 = 2_600;
+
+/// The depth of the call frame of a contract's transaction. If it reverts, the whole
+/// operation fails, but the deeper frames can revert without failing it, and that does not
+/// undo the effects of their calls outside of the EVM.
+const TOP_LEVEL_FRAME_DEPTH: usize = 1;
+
+impl PrecompileTag {
+    /// Returns the gas charged for a call, besides the size of its input and output.
+    fn gas_cost(&self) -> u64 {
+        let additional_gas = match self {
+            PrecompileTag::ChainId
+            | PrecompileTag::ApplicationCreatorChainId
+            | PrecompileTag::BlockHeight
+            | PrecompileTag::ReadSystemTimestamp
+            | PrecompileTag::AuthenticatedSigner => 0,
+            PrecompileTag::ReadChainBalance
+            | PrecompileTag::ReadOwnerBalance
+            | PrecompileTag::ReadDataBlob
+            | PrecompileTag::ReadEvent => PRECOMPILE_READ_GAS,
+            PrecompileTag::SendMessage
+            | PrecompileTag::Transfer
+            | PrecompileTag::Emit
+            | PrecompileTag::SubscribeToEvents
+            | PrecompileTag::UnsubscribeFromEvents => PRECOMPILE_WRITE_GAS,
+            PrecompileTag::TryCallApplication | PrecompileTag::TryQueryApplication => {
+                PRECOMPILE_CALL_GAS
+            }
+        };
+        PRECOMPILE_BASE_GAS + additional_gas
+    }
+
+    /// Returns whether the call has effects outside of the EVM, which reverting its frame
+    /// would not undo.
+    fn has_effects(&self) -> bool {
+        match self {
+            PrecompileTag::TryCallApplication
+            | PrecompileTag::SendMessage
+            | PrecompileTag::Transfer
+            | PrecompileTag::Emit
+            | PrecompileTag::SubscribeToEvents
+            | PrecompileTag::UnsubscribeFromEvents => true,
+            PrecompileTag::TryQueryApplication
+            | PrecompileTag::ChainId
+            | PrecompileTag::ApplicationCreatorChainId
+            | PrecompileTag::BlockHeight
+            | PrecompileTag::ReadSystemTimestamp
+            | PrecompileTag::ReadChainBalance
+            | PrecompileTag::ReadOwnerBalance
+            | PrecompileTag::ReadDataBlob
+            | PrecompileTag::AuthenticatedSigner
+            | PrecompileTag::ReadEvent => false,
+        }
+    }
+}
+
+/// Returns the gas charged for `length` bytes of input or output.
+fn precompile_words_gas(length: usize) -> u64 {
+    (length as u64).div_ceil(32) * PRECOMPILE_WORD_GAS
+}
+
+/// Splits the input of a precompile call into its tag and its arguments.
+fn precompile_tag(input: &[u8]) -> Result<(PrecompileTag, &[u8]), PrecompileErrors> {
+    let (tag, arguments) = input.split_first().ok_or_else(|| PrecompileErrors::Fatal {
+        msg: EvmExecutionError::PrecompileInputTooShort.to_string(),
+    })?;
+    let tag = PrecompileTag::try_from(*tag).map_err(|error| PrecompileErrors::Fatal {
+        msg: format!("{error} when trying to convert tag={tag}"),
+    })?;
+    Ok((tag, arguments))
+}
+
+/// Splits the packed arguments of `TryCallApplication` and `TryQueryApplication` into the
+/// target application and its argument.
+fn split_application_id(arguments: &[u8]) -> Result<(ApplicationId, Vec<u8>), ExecutionError> {
+    let (target, argument) = arguments
+        .split_at_checked(32)
+        .ok_or(EvmExecutionError::PrecompileInputTooShort)?;
+    Ok((u8_slice_to_application_id(target), argument.to_vec()))
+}
+
+/// Runs a precompile call with `execute`, charging its gas within `gas_limit`.
+fn run_precompile(
+    input: &[u8],
+    gas_limit: u64,
+    execute: impl FnOnce(PrecompileTag, &[u8]) -> Result<Vec<u8>, ExecutionError>,
+) -> PrecompileResult {
+    let (tag, arguments) = precompile_tag(input)?;
+    let gas_used = tag.gas_cost() + precompile_words_gas(input.len());
+    if gas_used > gas_limit {
+        return Err(PrecompileErrors::Error(PrecompileError::OutOfGas));
+    }
+    let output = execute(tag, arguments).map_err(|error| PrecompileErrors::Fatal {
+        msg: format!("{}", error),
+    })?;
+    let gas_used = gas_used + precompile_words_gas(output.len());
+    if gas_used > gas_limit {
+        // The call may have had effects outside of the EVM, e.g. sent messages, which
+        // reverting the EVM call frame would not undo.
+        return Err(PrecompileErrors::Fatal {
+            msg: "Out of gas for the output of a precompile call".to_string(),
+        });
+    }
+    let bytes = Bytes::from(output);
+    Ok(PrecompileOutput { gas_used, bytes })
+}
+
+/// Decodes an account owner: the chain itself is empty, a reserved owner is its single
+/// byte, and the other owners are their 20 or 32 bytes.
+fn decode_owner(bytes: &[u8]) -> Result<AccountOwner, ExecutionError> {
+    Ok(match bytes.len() {
+        0 => AccountOwner::CHAIN,
+        1 => AccountOwner::Reserved(bytes[0]),
+        20 => AccountOwner::Address20(bytes.try_into().expect("the length was checked")),
+        32 => AccountOwner::Address32(CryptoHash::try_from(bytes).expect("the length was checked")),
+        length => return Err(EvmExecutionError::InvalidAccountOwner(length).into()),
+    })
+}
+
+/// Encodes an account owner, as expected by [`decode_owner`].
+fn encode_owner(owner: AccountOwner) -> Vec<u8> {
+    match owner {
+        AccountOwner::CHAIN => Vec::new(),
+        AccountOwner::Reserved(byte) => vec![byte],
+        AccountOwner::Address20(address) => address.to_vec(),
+        AccountOwner::Address32(hash) => hash.as_bytes().to_vec(),
+    }
+}
+
+fn decode_hash(bytes: B256) -> CryptoHash {
+    CryptoHash::try_from(bytes.as_slice()).expect("a B256 has the size of a hash")
+}
+
+fn decode_chain_id(bytes: B256) -> ChainId {
+    ChainId(decode_hash(bytes))
+}
+
+fn encode_chain_id(chain_id: ChainId) -> Vec<u8> {
+    (*chain_id.0.as_bytes()).abi_encode()
+}
+
+fn decode_amount(amount: U256) -> Result<Amount, ExecutionError> {
+    let attos = u128::try_from(amount).map_err(|_| EvmExecutionError::AmountOverflow(amount))?;
+    Ok(Amount::from(attos))
+}
+
+fn encode_amount(amount: Amount) -> Vec<u8> {
+    U256::from(u128::from(amount)).abi_encode()
+}
+
+/// Executes the precompile calls that are available to both contracts and services.
+/// Returns `None` if the call is not one of them.
+fn base_runtime_call<Runtime: BaseRuntime>(
+    tag: &PrecompileTag,
+    arguments: &[u8],
+    runtime: &mut Runtime,
+) -> Result<Option<Vec<u8>>, ExecutionError> {
+    let output = match tag {
+        PrecompileTag::ChainId => encode_chain_id(runtime.chain_id()?),
+        PrecompileTag::ApplicationCreatorChainId => {
+            encode_chain_id(runtime.application_creator_chain_id()?)
+        }
+        PrecompileTag::BlockHeight => runtime.block_height()?.0.abi_encode(),
+        PrecompileTag::ReadSystemTimestamp => {
+            runtime.read_system_timestamp()?.micros().abi_encode()
+        }
+        PrecompileTag::ReadChainBalance => encode_amount(runtime.read_chain_balance()?),
+        PrecompileTag::ReadOwnerBalance => {
+            let owner = Bytes::abi_decode(arguments, true).map_err(EvmExecutionError::from)?;
+            let owner = decode_owner(&owner)?;
+            encode_amount(runtime.read_owner_balance(owner)?)
+        }
+        PrecompileTag::ReadDataBlob => {
+            let hash = B256::abi_decode(arguments, true).map_err(EvmExecutionError::from)?;
+            Bytes::from(runtime.read_data_blob(&decode_hash(hash))?).abi_encode()
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(output))
+}
+
+/// Executes the precompile calls that are only available to contracts.
+fn contract_runtime_call<Runtime: ContractRuntime>(
+    tag: PrecompileTag,
+    arguments: &[u8],
+    runtime: &mut Runtime,
+) -> Result<Vec<u8>, ExecutionError> {
+    if let Some(output) = base_runtime_call(&tag, arguments, runtime)? {
+        return Ok(output);
+    }
+    let output = match tag {
+        PrecompileTag::TryCallApplication => {
+            let (target, argument) = split_application_id(arguments)?;
+            let authenticated = true;
+            runtime.try_call_application(authenticated, target, argument)?
+        }
+        PrecompileTag::AuthenticatedSigner => {
+            // Whether there is a signer, since the chain itself is encoded as empty bytes.
+            let signer = runtime.authenticated_signer()?;
+            let owner = Bytes::from(signer.map(encode_owner).unwrap_or_default());
+            (signer.is_some(), owner).abi_encode_params()
+        }
+        PrecompileTag::SendMessage => {
+            let (destination, authenticated, is_tracked, message) =
+                <(B256, bool, bool, Bytes)>::abi_decode_params(arguments, true)
+                    .map_err(EvmExecutionError::from)?;
+            runtime.send_message(SendMessageRequest {
+                destination: Destination::Recipient(decode_chain_id(destination)),
+                authenticated,
+                is_tracked,
+                grant: Resources::default(),
+                message: message.to_vec(),
+            })?;
+            Vec::new()
+        }
+        PrecompileTag::Transfer => {
+            let (source, chain_id, owner, amount) =
+                <(Bytes, B256, Bytes, U256)>::abi_decode_params(arguments, true)
+                    .map_err(EvmExecutionError::from)?;
+            let destination = Account {
+                chain_id: decode_chain_id(chain_id),
+                owner: decode_owner(&owner)?,
+            };
+            runtime.transfer(decode_owner(&source)?, destination, decode_amount(amount)?)?;
+            Vec::new()
+        }
+        PrecompileTag::Emit => {
+            let (stream_name, value) = <(Bytes, Bytes)>::abi_decode_params(arguments, true)
+                .map_err(EvmExecutionError::from)?;
+            let stream_name = StreamName(stream_name.to_vec());
+            runtime.emit(stream_name, value.to_vec())?.abi_encode()
+        }
+        PrecompileTag::ReadEvent => {
+            let (chain_id, stream_name, index) =
+                <(B256, Bytes, u32)>::abi_decode_params(arguments, true)
+                    .map_err(EvmExecutionError::from)?;
+            let stream_name = StreamName(stream_name.to_vec());
+            let value = runtime.read_event(decode_chain_id(chain_id), stream_name, index)?;
+            Bytes::from(value).abi_encode()
+        }
+        PrecompileTag::SubscribeToEvents | PrecompileTag::UnsubscribeFromEvents => {
+            let (chain_id, application_id, stream_name) =
+                <(B256, B256, Bytes)>::abi_decode_params(arguments, true)
+                    .map_err(EvmExecutionError::from)?;
+            let chain_id = decode_chain_id(chain_id);
+            let application_id = u8_slice_to_application_id(application_id.as_slice());
+            let stream_name = StreamName(stream_name.to_vec());
+            if matches!(tag, PrecompileTag::SubscribeToEvents) {
+                runtime.subscribe_to_events(chain_id, application_id, stream_name)?;
+            } else {
+                runtime.unsubscribe_from_events(chain_id, application_id, stream_name)?;
+            }
+            Vec::new()
+        }
+        PrecompileTag::TryQueryApplication => {
+            return Err(EvmExecutionError::PrecompileUnavailable {
+                name: "try_query_application",
+                runtime: "contract",
+            }
+            .into());
+        }
+        PrecompileTag::ChainId
+        | PrecompileTag::ApplicationCreatorChainId
+        | PrecompileTag::BlockHeight
+        | PrecompileTag::ReadSystemTimestamp
+        | PrecompileTag::ReadChainBalance
+        | PrecompileTag::ReadOwnerBalance
+        | PrecompileTag::ReadDataBlob => unreachable!("handled by `base_runtime_call`"),
+    };
+    Ok(output)
+}
+
+/// Executes the precompile calls that are available to services.
+fn service_runtime_call<Runtime: ServiceRuntime>(
+    tag: PrecompileTag,
+    arguments: &[u8],
+    runtime: &mut Runtime,
+) -> Result<Vec<u8>, ExecutionError> {
+    if let Some(output) = base_runtime_call(&tag, arguments, runtime)? {
+        return Ok(output);
+    }
+    match tag {
+        PrecompileTag::TryQueryApplication => {
+            let (target, argument) = split_application_id(arguments)?;
+            runtime.try_query_application(target, argument)
+        }
+        PrecompileTag::TryCallApplication => Err(unavailable_in_service("try_call_application")),
+        PrecompileTag::AuthenticatedSigner => Err(unavailable_in_service("authenticated_signer")),
+        PrecompileTag::SendMessage => Err(unavailable_in_service("send_message")),
+        PrecompileTag::Transfer => Err(unavailable_in_service("transfer")),
+        PrecompileTag::Emit => Err(unavailable_in_service("emit")),
+        PrecompileTag::ReadEvent => Err(unavailable_in_service("read_event")),
+        PrecompileTag::SubscribeToEvents => Err(unavailable_in_service("subscribe_to_events")),
+        PrecompileTag::UnsubscribeFromEvents => {
+            Err(unavailable_in_service("unsubscribe_from_events"))
+        }
+        PrecompileTag::ChainId
+        | PrecompileTag::ApplicationCreatorChainId
+        | PrecompileTag::BlockHeight
+        | PrecompileTag::ReadSystemTimestamp
+        | PrecompileTag::ReadChainBalance
+        | PrecompileTag::ReadOwnerBalance
+        | PrecompileTag::ReadDataBlob => unreachable!("handled by `base_runtime_call`"),
+    }
+}
+
+fn unavailable_in_service(name: &'static str) -> ExecutionError {
+    EvmExecutionError::PrecompileUnavailable {
+        name,
+        runtime: "service",
+    }
+    .into()
 }
 
 struct GeneralContractCall;
@@ -257,42 +620,24 @@ impl<Runtime: ContractRuntime>
     fn call(
         &self,
         input: &Bytes,
-        _gas_limit: u64,
+        gas_limit: u64,
         context: &mut InnerEvmContext<WrapDatabaseRef<&mut DatabaseRuntime<Runtime>>>,
     ) -> PrecompileResult {
-        let vec = input.to_vec();
-        let tag = vec[0];
-        let tag = PrecompileTag::try_from(tag).map_err(|error| PrecompileErrors::Fatal {
-            msg: format!("{error} when trying to convert tag={tag}"),
-        })?;
-        match tag {
-            PrecompileTag::TryCallApplication => {
-                let target = u8_slice_to_application_id(&vec[1..33]);
-                let argument = vec[33..].to_vec();
-                let result = {
-                    let authenticated = true;
-                    let mut runtime = context
-                        .db
-                        .0
-                        .runtime
-                        .lock()
-                        .expect("The lock should be possible");
-                    runtime.try_call_application(authenticated, target, argument)
-                }
-                .map_err(|error| PrecompileErrors::Fatal {
-                    msg: format!("{}", error),
-                })?;
-                // We do not know how much gas was used.
-                let gas_used = 0;
-                let bytes = Bytes::copy_from_slice(&result);
-                let result = PrecompileOutput { gas_used, bytes };
-                Ok(result)
-            }
-            PrecompileTag::TryQueryApplication => Err(PrecompileErrors::Fatal {
-                msg: "try_query_application is not available in the GeneralContractCall"
-                    .to_string(),
-            }),
-        }
+        // The precompile runs in a frame of its own, above the one calling it.
+        let caller_depth = context.journaled_state.depth - 1;
+        run_precompile(input, gas_limit, |tag, arguments| {
+            ensure!(
+                !tag.has_effects() || caller_depth == TOP_LEVEL_FRAME_DEPTH,
+                EvmExecutionError::EffectsInRevertibleFrame
+            );
+            let mut runtime = context
+                .db
+                .0
+                .runtime
+                .lock()
+                .expect("The lock should be possible");
+            contract_runtime_call(tag, arguments, &mut *runtime)
+        })
     }
 }
 
@@ -305,40 +650,18 @@ impl<Runtime: ServiceRuntime>
     fn call(
         &self,
         input: &Bytes,
-        _gas_limit: u64,
+        gas_limit: u64,
         context: &mut InnerEvmContext<WrapDatabaseRef<&mut DatabaseRuntime<Runtime>>>,
     ) -> PrecompileResult {
-        let vec = input.to_vec();
-        let tag = vec[0];
-        let tag = PrecompileTag::try_from(tag).map_err(|error| PrecompileErrors::Fatal {
-            msg: format!("{error} when trying to convert tag={tag}"),
-        })?;
-        match tag {
-            PrecompileTag::TryCallApplication => Err(PrecompileErrors::Fatal {
-                msg: "try_call_application is not available in the GeneralServiceCall".to_string(),
-            }),
-            PrecompileTag::TryQueryApplication => {
-                let target = u8_slice_to_application_id(&vec[1..33]);
-                let argument = vec[33..].to_vec();
-                let result = {
-                    let mut runtime = context
-                        .db
-                        .0
-                        .runtime
-                        .lock()
-                        .expect("The lock should be possible");
-                    runtime.try_query_application(target, argument)
-                }
-                .map_err(|error| PrecompileErrors::Fatal {
-                    msg: format!("{}", error),
-                })?;
-                // We do not know how much gas was used.
-                let gas_used = 0;
-                let bytes = Bytes::copy_from_slice(&result);
-                let result = PrecompileOutput { gas_used, bytes };
-                Ok(result)
-            }
-        }
+        run_precompile(input, gas_limit, |tag, arguments| {
+            let mut runtime = context
+                .db
+                .0
+                .runtime
+                .lock()
+                .expect("The lock should be possible");
+            service_runtime_call(tag, arguments, &mut *runtime)
+        })
     }
 }
 
@@ -386,7 +709,7 @@ impl<Runtime: ContractRuntime> Inspector<WrapDatabaseRef<&mut DatabaseRuntime<Ru
 impl<Runtime: ContractRuntime> CallInterceptorContract<Runtime> {
     fn call_or_fail(
         &mut self,
-        _context: &mut EvmContext<WrapDatabaseRef<&mut DatabaseRuntime<Runtime>>>,
+        context: &mut EvmContext<WrapDatabaseRef<&mut DatabaseRuntime<Runtime>>>,
         inputs: &mut CallInputs,
     ) -> Result<Option<CallOutcome>, ExecutionError> {
         let contract_address = Address::ZERO.create(0);
@@ -394,6 +717,11 @@ impl<Runtime: ContractRuntime> CallInterceptorContract<Runtime> {
         {
             return Ok(None);
         }
+        // The call is intercepted before its frame is created.
+        ensure!(
+            context.journaled_state.depth == TOP_LEVEL_FRAME_DEPTH,
+            EvmExecutionError::EffectsInRevertibleFrame
+        );
         let vec = inputs.input.to_vec();
         let target = address_to_user_application_id(inputs.target_address);
         let mut argument: Vec<u8> = INTERPRETER_RESULT_SELECTOR.to_vec();
@@ -479,6 +807,7 @@ enum Choice {
 
 struct ExecutionResultSuccess {
     reason: SuccessReason,
+    gas_used: u64,
     logs: Vec<Log>,
     output: Output,
 }
@@ -528,6 +857,11 @@ where
         Ok(())
     }
 
+    fn migrate(&mut self, _context: OperationContext) -> Result<(), ExecutionError> {
+        // EVM applications cannot be upgraded.
+        Ok(())
+    }
+
     fn execute_operation(
         &mut self,
         _context: OperationContext,
@@ -565,12 +899,13 @@ fn process_execution_result(
     match result {
         ExecutionResult::Success {
             reason,
-            gas_used: _,
+            gas_used,
             gas_refunded: _,
             logs,
             output,
         } => Ok(ExecutionResultSuccess {
             reason,
+            gas_used,
             logs,
             output,
         }),
@@ -599,6 +934,10 @@ where
         ch: Choice,
         vec: &[u8],
     ) -> Result<ExecutionResultSuccess, ExecutionError> {
+        let function = match ch {
+            Choice::Create => "constructor".to_string(),
+            Choice::Call => format!("0x{}", hex::encode(vec.get(..4).unwrap_or_default())),
+        };
         let (kind, tx_data) = match ch {
             Choice::Create => (TxKind::Create, Bytes::copy_from_slice(vec)),
             Choice::Call => {
@@ -638,18 +977,35 @@ where
             let error = EvmExecutionError::TransactCommitError(error);
             ExecutionError::EvmError(error)
         })?;
-        process_execution_result(result)
+        drop(evm);
+        let result = process_execution_result(result)?;
+        let mut runtime = self.db.runtime.lock().expect("The lock should be possible");
+        runtime.profile_fuel(&function, result.gas_used)?;
+        Ok(result)
     }
 
+    /// Emits the logs of a transaction as [`EvmLog`] events. The logs of the contract of the
+    /// application are given the address of the application.
     fn write_logs(&mut self, logs: Vec<Log>, origin: &str) -> Result<(), ExecutionError> {
-        // TODO(#3758): Extracting Ethereum events from the Linera events.
         if !logs.is_empty() {
             let mut runtime = self.db.runtime.lock().expect("The lock should be possible");
-            let stream_name = bcs::to_bytes("ethereum_event")?;
-            let stream_name = StreamName(stream_name);
-            for log in &logs {
-                let value = bcs::to_bytes(&(origin, log))?;
-                runtime.emit(stream_name.clone(), value)?;
+            let application_address = runtime.application_id()?.evm_address();
+            let contract_address = Address::ZERO.create(0);
+            let stream_name = evm_log_stream_name();
+            for log in logs {
+                let address = if log.address == contract_address {
+                    application_address
+                } else {
+                    log.address
+                };
+                let (topics, data) = log.data.split();
+                let log = EvmLog {
+                    origin: origin.to_string(),
+                    address,
+                    topics,
+                    data,
+                };
+                runtime.emit(stream_name.clone(), log.to_event_value()?)?;
             }
         }
         Ok(())
@@ -687,6 +1043,10 @@ where
                 runtime.schedule_operation(operation)?;
                 return Ok(Vec::new());
             }
+            EvmQuery::EstimateGas(operation) => {
+                let result = self.transact_tx_data(&operation)?;
+                return Ok(serde_json::to_vec(&result.gas_used)?);
+            }
         };
 
         ensure_message_length(query.len(), 4)?;
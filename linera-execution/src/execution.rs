@@ -12,8 +12,9 @@ use linera_views::{
     context::Context,
     key_value_store_view::KeyValueStoreView,
     map_view::MapView,
+    proof::{ProofPath, ViewProof},
     reentrant_collection_view::HashedReentrantCollectionView,
-    views::{ClonableView, View},
+    views::{ClonableView, HashableFields as _, View, ViewError},
 };
 use linera_views_derive::CryptoHashView;
 #[cfg(with_testing)]
@@ -32,7 +33,7 @@ use crate::{
     ApplicationId, ContractSyncRuntime, ExecutionError, ExecutionRuntimeConfig,
     ExecutionRuntimeContext, Message, MessageContext, MessageKind, Operation, OperationContext,
     OutgoingMessage, Query, QueryContext, QueryOutcome, ServiceSyncRuntime, SystemMessage,
-    TransactionTracker,
+    SystemOperation, TransactionTracker,
 };
 
 /// A view accessing the execution state of a chain.
@@ -134,6 +135,7 @@ where
 
 pub enum UserAction {
     Instantiate(OperationContext, Vec<u8>),
+    Migrate(OperationContext),
     Operation(OperationContext, Vec<u8>),
     Message(MessageContext, Vec<u8>),
 }
@@ -143,6 +145,7 @@ impl UserAction {
         use UserAction::*;
         match self {
             Instantiate(context, _) => context.authenticated_signer,
+            Migrate(context) => context.authenticated_signer,
             Operation(context, _) => context.authenticated_signer,
             Message(context, _) => context.authenticated_signer,
         }
@@ -151,6 +154,7 @@ impl UserAction {
     pub(crate) fn height(&self) -> BlockHeight {
         match self {
             UserAction::Instantiate(context, _) => context.height,
+            UserAction::Migrate(context) => context.height,
             UserAction::Operation(context, _) => context.height,
             UserAction::Message(context, _) => context.height,
         }
@@ -159,6 +163,7 @@ impl UserAction {
     pub(crate) fn round(&self) -> Option<u32> {
         match self {
             UserAction::Instantiate(context, _) => context.round,
+            UserAction::Migrate(context) => context.round,
             UserAction::Operation(context, _) => context.round,
             UserAction::Message(context, _) => context.round,
         }
@@ -263,6 +268,13 @@ where
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match operation {
             Operation::System(op) => {
+                let upgraded_application = match &*op {
+                    SystemOperation::UpgradeApplication { application_id, .. }
+                    | SystemOperation::ProcessApplicationUpgrade(application_id) => {
+                        Some(*application_id)
+                    }
+                    _ => None,
+                };
                 let new_application = self
                     .system
                     .execute_operation(context, *op, txn_tracker, resource_controller)
@@ -279,6 +291,17 @@ where
                     )
                     .await?;
                 }
+                if let Some(application_id) = upgraded_application {
+                    self.run_user_action(
+                        application_id,
+                        UserAction::Migrate(context),
+                        context.refund_grant_to(),
+                        None,
+                        txn_tracker,
+                        resource_controller,
+                    )
+                    .await?;
+                }
             }
             Operation::User {
                 application_id,
@@ -495,4 +518,49 @@ where
         }
         Ok(applications)
     }
+
+    /// Returns a proof of the balance of `owner`, under the hash of this view. It can be
+    /// checked against the state hash of a block with
+    /// `ViewProof::verify_crypto_hash(&path, "ExecutionStateViewHash", &state_hash)`, where
+    /// `path` is given by [`Self::balance_proof_path`].
+    pub async fn balance_proof(&self, owner: &AccountOwner) -> Result<ViewProof, ViewError> {
+        let proof = self.system.balance_proof(owner).await?;
+        Ok(proof.in_field(Self::field_index("system")?, self.field_hashes().await?))
+    }
+
+    /// Returns the location of the balance of `owner` in this view.
+    pub fn balance_proof_path(owner: &AccountOwner) -> Result<ProofPath, ViewError> {
+        Ok(SystemExecutionStateView::<C>::balance_proof_path(owner)?
+            .in_field(Self::field_index("system")?))
+    }
+
+    /// Returns a proof of the value at `key` in the state of an application, or of its
+    /// absence, under the hash of this view.
+    pub async fn application_state_proof(
+        &self,
+        application_id: ApplicationId,
+        key: &[u8],
+    ) -> Result<ViewProof, ViewError> {
+        let Some(state) = self.users.try_load_entry(&application_id).await? else {
+            return Err(ViewError::not_found(
+                "application state for",
+                application_id,
+            ));
+        };
+        let proof = state.proof(key).await?;
+        drop(state);
+        let proof = self.users.extend_proof(&application_id, proof).await?;
+        Ok(proof.in_field(Self::field_index("users")?, self.field_hashes().await?))
+    }
+
+    /// Returns the location of the value at `key` in the state of an application, in this
+    /// view.
+    pub fn application_state_proof_path(
+        application_id: ApplicationId,
+        key: &[u8],
+    ) -> Result<ProofPath, ViewError> {
+        Ok(ProofPath::entry(key.to_vec())
+            .in_collection_entry(bcs::to_bytes(&application_id)?)
+            .in_field(Self::field_index("users")?))
+    }
 }
@@ -16,7 +16,8 @@ use linera_base::prometheus_util::{
 };
 use linera_base::{
     data_types::{
-        Amount, ApplicationPermissions, ArithmeticError, BlobContent, BlockHeight, Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, BlobContent, BlockHeight, TimerDeadline,
+        Timestamp,
     },
     ensure, hex_debug, hex_vec_debug, http,
     identifiers::{Account, AccountOwner, BlobId, BlobType, ChainId, EventId, MessageId, StreamId},
@@ -32,7 +33,7 @@ use crate::{
     system::{CreateApplicationResult, OpenChainConfig, Recipient},
     util::RespondExt,
     ApplicationDescription, ApplicationId, ExecutionError, ExecutionRuntimeContext,
-    ExecutionStateView, ModuleId, OutgoingMessage, ResourceController, TransactionTracker,
+    ExecutionStateView, ModuleId, OutgoingMessage, ResourceController, Timer, TransactionTracker,
     UserContractCode, UserServiceCode,
 };
 
@@ -84,10 +85,11 @@ where
                     .await?
             }
         };
+        let code_description = self.system.code_description(&description).await?;
         let code = self
             .context()
             .extra()
-            .get_user_contract(&description)
+            .get_user_contract(&code_description)
             .await?;
         Ok((code, description))
     }
@@ -110,10 +112,11 @@ where
             }
             None => self.system.describe_application(id, txn_tracker).await?,
         };
+        let code_description = self.system.code_description(&description).await?;
         let code = self
             .context()
             .extra()
-            .get_user_service(&description)
+            .get_user_service(&code_description)
             .await?;
         Ok((code, description))
     }
@@ -487,6 +490,41 @@ where
                 callback.respond(());
             }
 
+            SetTimer {
+                application_id,
+                deadline,
+                payload,
+                callback,
+            } => {
+                let timer_id = *self.system.next_timer_id.get();
+                self.system
+                    .next_timer_id
+                    .set(timer_id.checked_add(1).ok_or(ArithmeticError::Overflow)?);
+                let timer = Timer {
+                    application_id,
+                    deadline,
+                    payload,
+                };
+                self.system.add_timer(timer_id, timer).await?;
+                callback.respond(timer_id);
+            }
+
+            CancelTimer {
+                application_id,
+                timer_id,
+                callback,
+            } => {
+                let timer = self.system.timers.get(&timer_id).await?;
+                if timer.is_none() {
+                    callback.respond(Ok(false));
+                } else if timer.is_some_and(|timer| timer.application_id != application_id) {
+                    callback.respond(Err(ExecutionError::UnauthorizedApplication(application_id)));
+                } else {
+                    self.system.remove_timer(timer_id).await?;
+                    callback.respond(Ok(true));
+                }
+            }
+
             GetApplicationPermissions { callback } => {
                 let app_permissions = self.system.application_permissions.get();
                 callback.respond(app_permissions.clone());
@@ -770,6 +808,22 @@ pub enum ExecutionRequest {
         callback: Sender<()>,
     },
 
+    SetTimer {
+        application_id: ApplicationId,
+        deadline: TimerDeadline,
+        #[debug(with = hex_debug)]
+        payload: Vec<u8>,
+        #[debug(skip)]
+        callback: Sender<u64>,
+    },
+
+    CancelTimer {
+        application_id: ApplicationId,
+        timer_id: u64,
+        #[debug(skip)]
+        callback: Sender<Result<bool, ExecutionError>>,
+    },
+
     GetApplicationPermissions {
         #[debug(skip)]
         callback: Sender<ApplicationPermissions>,
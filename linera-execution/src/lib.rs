@@ -12,11 +12,13 @@ mod execution;
 mod execution_state_actor;
 mod graphql;
 mod policy;
+mod profile;
 mod resources;
 mod runtime;
 pub mod system;
 #[cfg(with_testing)]
 pub mod test_utils;
+mod trace;
 mod transaction_tracker;
 mod util;
 mod wasm;
@@ -35,7 +37,7 @@ use linera_base::{
     crypto::{BcsHashable, CryptoHash},
     data_types::{
         Amount, ApplicationDescription, ApplicationPermissions, ArithmeticError, Blob, BlockHeight,
-        DecompressionError, Epoch, Resources, SendMessageRequest, Timestamp,
+        DecompressionError, Epoch, Resources, SendMessageRequest, TimerDeadline, Timestamp,
     },
     doc_scalar, hex_debug, http,
     identifiers::{
@@ -55,6 +57,10 @@ use crate::evm::EvmExecutionError;
 use crate::runtime::ContractSyncRuntime;
 #[cfg(all(with_testing, with_wasm_runtime))]
 pub use crate::wasm::test as wasm_test;
+#[cfg(all(with_wasm_runtime, not(web)))]
+pub use crate::wasm::{
+    enable_module_disk_cache, ModuleDiskCacheConfig, DEFAULT_MAX_DISK_CACHE_SIZE,
+};
 #[cfg(with_wasm_runtime)]
 pub use crate::wasm::{
     BaseRuntimeApi, ContractEntrypoints, ContractRuntimeApi, RuntimeApiData, ServiceEntrypoints,
@@ -64,6 +70,7 @@ pub use crate::{
     execution::{ExecutionStateView, ServiceRuntimeEndpoint},
     execution_state_actor::ExecutionRequest,
     policy::ResourceControlPolicy,
+    profile::{FoldedStacks, InvalidProfileMetric, ProfileMetric, ResourceProfile, ResourceUsage},
     resources::{ResourceController, ResourceTracker},
     runtime::{
         ContractSyncRuntimeHandle, ServiceRuntimeRequest, ServiceSyncRuntime,
@@ -71,7 +78,9 @@ pub use crate::{
     },
     system::{
         SystemExecutionStateView, SystemMessage, SystemOperation, SystemQuery, SystemResponse,
+        Timer,
     },
+    trace::{ExecutionTrace, StorageRead, StorageWrite, TraceStep},
     transaction_tracker::{TransactionOutcome, TransactionTracker},
 };
 
@@ -102,6 +111,15 @@ pub trait UserContractModule: dyn_clone::DynClone + Any + task::Post + Send + Sy
         &self,
         runtime: ContractSyncRuntimeHandle,
     ) -> Result<UserContractInstance, ExecutionError>;
+
+    /// Instantiates the contract for an execution that is profiled, which may attribute the
+    /// resources it uses in more detail.
+    fn instantiate_profiled(
+        &self,
+        runtime: ContractSyncRuntimeHandle,
+    ) -> Result<UserContractInstance, ExecutionError> {
+        self.instantiate(runtime)
+    }
 }
 
 impl<T: UserContractModule + Send + Sync + 'static> From<T> for UserContractCode {
@@ -141,8 +159,13 @@ impl UserContractCode {
     fn instantiate(
         &self,
         runtime: ContractSyncRuntimeHandle,
+        profiled: bool,
     ) -> Result<UserContractInstance, ExecutionError> {
-        self.0.instantiate(runtime)
+        if profiled {
+            self.0.instantiate_profiled(runtime)
+        } else {
+            self.0.instantiate(runtime)
+        }
     }
 }
 
@@ -311,6 +334,19 @@ pub enum ExecutionError {
     InvalidCommitteeEpoch { expected: Epoch, provided: Epoch },
     #[error("Failed to remove committee")]
     InvalidCommitteeRemoval,
+    #[error("Timer {0} does not exist")]
+    TimerNotFound(u64),
+    #[error("Timer {0} is not due yet")]
+    TimerNotDue(u64),
+    #[error("Timer payload exceeds the size limit of {limit} bytes, having {size} bytes")]
+    TimerPayloadTooLarge { limit: u64, size: u64 },
+    #[error(
+        "Application {0} can only be upgraded by the chain that created it, if its application \
+         permissions allow it"
+    )]
+    UnauthorizedApplicationUpgrade(ApplicationId),
+    #[error("Application {0} cannot be upgraded: only Wasm applications can be upgraded")]
+    UnsupportedApplicationUpgrade(ApplicationId),
     #[error("Amount overflow")]
     AmountOverflow,
     #[error("Amount underflow")]
@@ -349,6 +385,9 @@ pub trait UserContract {
         argument: Vec<u8>,
     ) -> Result<(), ExecutionError>;
 
+    /// Migrates the application state after the application was upgraded to new code.
+    fn migrate(&mut self, context: OperationContext) -> Result<(), ExecutionError>;
+
     /// Applies an operation from the current block.
     fn execute_operation(
         &mut self,
@@ -696,6 +735,14 @@ pub trait ContractRuntime: BaseRuntime {
     /// Consumes some of the execution fuel.
     fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError>;
 
+    /// Consumes some of the execution fuel in a `function` of the current application, to
+    /// which it is attributed if the execution is profiled.
+    fn consume_fuel_in(&mut self, function: &str, fuel: u64) -> Result<(), ExecutionError>;
+
+    /// Attributes some fuel that is not consumed with [`Self::consume_fuel`] to a `function`
+    /// of the current application, if the execution is profiled. Nothing is charged.
+    fn profile_fuel(&mut self, function: &str, fuel: u64) -> Result<(), ExecutionError>;
+
     /// Schedules a message to be sent.
     fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError>;
 
@@ -759,6 +806,18 @@ pub trait ContractRuntime: BaseRuntime {
         stream_name: StreamName,
     ) -> Result<(), ExecutionError>;
 
+    /// Sets a timer that sends `payload` as a message to this application on the current
+    /// chain once the `deadline` is reached. Returns the ID of the timer.
+    fn set_timer(
+        &mut self,
+        deadline: TimerDeadline,
+        payload: Vec<u8>,
+    ) -> Result<u64, ExecutionError>;
+
+    /// Cancels a timer set by this application. Returns `false` if the timer does not exist
+    /// anymore, e.g. because it already fired.
+    fn cancel_timer(&mut self, timer_id: u64) -> Result<bool, ExecutionError>;
+
     /// Queries a service.
     fn query_service(
         &mut self,
@@ -1367,3 +1426,7 @@ doc_scalar!(
     "A message to be sent and possibly executed in the receiver's block."
 );
 doc_scalar!(MessageKind, "The kind of outgoing message being sent");
+doc_scalar!(
+    ResourceTracker,
+    "The resources used by the execution of a block or a transaction"
+);
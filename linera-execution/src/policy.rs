@@ -13,7 +13,7 @@ use linera_base::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::ExecutionError;
+use crate::{ExecutionError, ResourceTracker};
 
 /// A collection of prices and limits associated with block execution.
 #[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject)]
@@ -53,6 +53,8 @@ pub struct ResourceControlPolicy {
     pub service_as_oracle_query: Amount,
     /// The price for a performing an HTTP request.
     pub http_request: Amount,
+    /// The price for setting a timer.
+    pub timer: Amount,
 
     // TODO(#1538): Cap the number of transactions per block and the total size of their
     // arguments.
@@ -79,6 +81,8 @@ pub struct ResourceControlPolicy {
     pub maximum_oracle_response_bytes: u64,
     /// The maximum size in bytes of a received HTTP response.
     pub maximum_http_response_bytes: u64,
+    /// The maximum size in bytes of the payload of a timer.
+    pub maximum_timer_payload_bytes: u64,
     /// The maximum amount of time allowed to wait for an HTTP response.
     pub http_request_timeout_ms: u64,
     /// The list of hosts that contracts and services can send HTTP requests to.
@@ -105,6 +109,7 @@ impl fmt::Display for ResourceControlPolicy {
             message_byte,
             service_as_oracle_query,
             http_request,
+            timer,
             maximum_fuel_per_block,
             maximum_service_oracle_execution_ms,
             maximum_block_size,
@@ -116,6 +121,7 @@ impl fmt::Display for ResourceControlPolicy {
             maximum_bytes_written_per_block,
             maximum_oracle_response_bytes,
             maximum_http_response_bytes,
+            maximum_timer_payload_bytes,
             http_request_allow_list,
             http_request_timeout_ms,
         } = self;
@@ -139,6 +145,7 @@ impl fmt::Display for ResourceControlPolicy {
             {message:.2} per outgoing messages\n\
             {message_byte:.2} per byte in the argument of an outgoing messages\n\
             {http_request:.2} per HTTP request performed\n\
+            {timer:.2} per timer set\n\
             {maximum_fuel_per_block} maximum fuel per block\n\
             {maximum_service_oracle_execution_ms} ms maximum service-as-oracle execution time per \
                 block\n\
@@ -151,6 +158,7 @@ impl fmt::Display for ResourceControlPolicy {
             {maximum_bytes_written_per_block} maximum number of bytes written per block\n\
             {maximum_oracle_response_bytes} maximum number of bytes of an oracle response\n\
             {maximum_http_response_bytes} maximum number of bytes of an HTTP response\n\
+            {maximum_timer_payload_bytes} maximum number of bytes of a timer payload\n\
             {http_request_timeout_ms} ms timeout for HTTP requests\n\
             HTTP hosts allowed for contracts and services: {http_request_allow_list:#?}\n",
         )?;
@@ -187,6 +195,7 @@ impl ResourceControlPolicy {
             message_byte: Amount::ZERO,
             service_as_oracle_query: Amount::ZERO,
             http_request: Amount::ZERO,
+            timer: Amount::ZERO,
             maximum_fuel_per_block: u64::MAX,
             maximum_service_oracle_execution_ms: u64::MAX,
             maximum_block_size: u64::MAX,
@@ -198,6 +207,7 @@ impl ResourceControlPolicy {
             maximum_bytes_written_per_block: u64::MAX,
             maximum_oracle_response_bytes: u64::MAX,
             maximum_http_response_bytes: u64::MAX,
+            maximum_timer_payload_bytes: u64::MAX,
             http_request_timeout_ms: u64::MAX,
             http_request_allow_list: BTreeSet::new(),
         }
@@ -243,6 +253,7 @@ impl ResourceControlPolicy {
             message: Amount::from_attos(10),
             message_byte: Amount::from_attos(1),
             http_request: Amount::from_micros(1),
+            timer: Amount::from_micros(1),
             ..Self::no_fees()
         }
     }
@@ -267,6 +278,7 @@ impl ResourceControlPolicy {
             message: Amount::from_micros(10),
             service_as_oracle_query: Amount::from_millis(10),
             http_request: Amount::from_micros(50),
+            timer: Amount::from_micros(10),
             maximum_fuel_per_block: 100_000_000,
             maximum_service_oracle_execution_ms: 10_000,
             maximum_block_size: 1_000_000,
@@ -278,6 +290,7 @@ impl ResourceControlPolicy {
             maximum_bytes_written_per_block: 10_000_000,
             maximum_oracle_response_bytes: 10_000,
             maximum_http_response_bytes: 10_000,
+            maximum_timer_payload_bytes: 10_000,
             http_request_timeout_ms: 20_000,
             http_request_allow_list: BTreeSet::new(),
         }
@@ -317,6 +330,43 @@ impl ResourceControlPolicy {
         Ok(amount)
     }
 
+    /// Returns the fees charged for the resources recorded by a [`ResourceTracker`]. Grants
+    /// are not fees and are not included.
+    pub fn total_fees(&self, tracker: &ResourceTracker) -> Result<Amount, ArithmeticError> {
+        let mut amount = self.block.try_mul(u128::from(tracker.blocks))?;
+        amount.try_add_assign(self.operation.try_mul(u128::from(tracker.operations))?)?;
+        amount.try_add_assign(self.operation_bytes_price(tracker.operation_bytes)?)?;
+        amount.try_add_assign(self.message.try_mul(u128::from(tracker.messages))?)?;
+        amount.try_add_assign(self.message_bytes_price(tracker.message_bytes)?)?;
+        amount.try_add_assign(self.fuel_price(tracker.fuel)?)?;
+        amount.try_add_assign(self.read_operations_price(tracker.read_operations)?)?;
+        amount.try_add_assign(self.write_operations_price(tracker.write_operations)?)?;
+        amount.try_add_assign(self.bytes_read_price(tracker.bytes_read)?)?;
+        amount.try_add_assign(self.bytes_written_price(tracker.bytes_written)?)?;
+        amount.try_add_assign(
+            self.blob_read
+                .try_mul(u128::from(tracker.blobs_read))?
+                .try_add(
+                    self.blob_byte_read
+                        .try_mul(u128::from(tracker.blob_bytes_read))?,
+                )?,
+        )?;
+        amount.try_add_assign(
+            self.blob_published
+                .try_mul(u128::from(tracker.blobs_published))?
+                .try_add(
+                    self.blob_byte_published
+                        .try_mul(u128::from(tracker.blob_bytes_published))?,
+                )?,
+        )?;
+        amount.try_add_assign(self.http_requests_price(tracker.http_requests)?)?;
+        amount.try_add_assign(
+            self.service_as_oracle_queries_price(tracker.service_oracle_queries)?,
+        )?;
+        amount.try_add_assign(self.timers_price(tracker.timers)?)?;
+        Ok(amount)
+    }
+
     pub(crate) fn operation_bytes_price(&self, size: u64) -> Result<Amount, ArithmeticError> {
         self.operation_byte.try_mul(size as u128)
     }
@@ -371,6 +421,10 @@ impl ResourceControlPolicy {
         self.http_request.try_mul(count as u128)
     }
 
+    pub(crate) fn timers_price(&self, count: u32) -> Result<Amount, ArithmeticError> {
+        self.timer.try_mul(count as u128)
+    }
+
     pub(crate) fn fuel_price(&self, fuel: u64) -> Result<Amount, ArithmeticError> {
         self.fuel_unit.try_mul(u128::from(fuel))
     }
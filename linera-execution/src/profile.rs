@@ -0,0 +1,293 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Profiles of the resources used by user applications.
+//!
+//! When enabled in a [`TransactionTracker`][crate::TransactionTracker], the fuel, the storage
+//! operations and the bytes read and written are attributed to the call stack of the
+//! applications that used them. Each frame of a stack is an entry point of an application,
+//! e.g. `execute_operation`, and the leaves are the storage functions of the runtime and the
+//! functions of the contracts. Wasm contracts are executed with a variant of their fuel
+//! metering that reports the Wasm function consuming the fuel, to which it is attributed,
+//! named after its export or its debug name. The gas used by an EVM contract is attributed to
+//! the selector of the function that it executes.
+//!
+//! Profiles can be written in the "folded stacks" format read by flamegraph tools such as
+//! `inferno-flamegraph` and `flamegraph.pl`.
+
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use derive_more::Display;
+use linera_base::identifiers::ApplicationId;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::ResourceTracker;
+
+/// The resources attributed to a call stack.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// The fuel consumed, or the gas used by EVM contracts.
+    pub fuel: u64,
+    /// The number of read operations.
+    pub read_operations: u64,
+    /// The number of write operations.
+    pub write_operations: u64,
+    /// The number of bytes read.
+    pub bytes_read: u64,
+    /// The number of bytes written.
+    pub bytes_written: u64,
+}
+
+impl ResourceUsage {
+    /// Returns the resources used between two states of a [`ResourceTracker`].
+    fn between(before: &ResourceTracker, after: &ResourceTracker) -> Self {
+        ResourceUsage {
+            fuel: after.fuel.saturating_sub(before.fuel),
+            read_operations: u64::from(
+                after.read_operations.saturating_sub(before.read_operations),
+            ),
+            write_operations: u64::from(
+                after
+                    .write_operations
+                    .saturating_sub(before.write_operations),
+            ),
+            bytes_read: after.bytes_read.saturating_sub(before.bytes_read),
+            bytes_written: after.bytes_written.saturating_sub(before.bytes_written),
+        }
+    }
+
+    /// Returns whether no resources were used.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the value of a metric.
+    pub fn get(&self, metric: ProfileMetric) -> u64 {
+        match metric {
+            ProfileMetric::Fuel => self.fuel,
+            ProfileMetric::ReadOperations => self.read_operations,
+            ProfileMetric::WriteOperations => self.write_operations,
+            ProfileMetric::BytesRead => self.bytes_read,
+            ProfileMetric::BytesWritten => self.bytes_written,
+        }
+    }
+
+    /// Adds the resources of `other` to these ones.
+    pub fn add_assign(&mut self, other: &Self) {
+        self.fuel = self.fuel.saturating_add(other.fuel);
+        self.read_operations = self.read_operations.saturating_add(other.read_operations);
+        self.write_operations = self.write_operations.saturating_add(other.write_operations);
+        self.bytes_read = self.bytes_read.saturating_add(other.bytes_read);
+        self.bytes_written = self.bytes_written.saturating_add(other.bytes_written);
+    }
+}
+
+/// A resource that a [`ResourceProfile`] can be rendered for.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum ProfileMetric {
+    #[display("fuel")]
+    Fuel,
+    #[display("read-operations")]
+    ReadOperations,
+    #[display("write-operations")]
+    WriteOperations,
+    #[display("bytes-read")]
+    BytesRead,
+    #[display("bytes-written")]
+    BytesWritten,
+}
+
+impl ProfileMetric {
+    /// All the metrics of a profile.
+    pub const ALL: [ProfileMetric; 5] = [
+        ProfileMetric::Fuel,
+        ProfileMetric::ReadOperations,
+        ProfileMetric::WriteOperations,
+        ProfileMetric::BytesRead,
+        ProfileMetric::BytesWritten,
+    ];
+}
+
+impl FromStr for ProfileMetric {
+    type Err = InvalidProfileMetric;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        ProfileMetric::ALL
+            .into_iter()
+            .find(|metric| metric.to_string() == string)
+            .ok_or_else(|| InvalidProfileMetric(string.to_owned()))
+    }
+}
+
+/// Attempts to parse an invalid [`ProfileMetric`] from a string.
+#[derive(Clone, Debug, Error)]
+#[error("{0:?} is not a valid profile metric")]
+pub struct InvalidProfileMetric(String);
+
+/// The resources used by applications, by call stack.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceProfile {
+    /// The resources used by each call stack, from the outermost frame.
+    pub stacks: BTreeMap<Vec<String>, ResourceUsage>,
+}
+
+impl ResourceProfile {
+    /// Attributes some resources to a call stack.
+    pub fn record(&mut self, stack: Vec<String>, usage: &ResourceUsage) {
+        self.stacks.entry(stack).or_default().add_assign(usage);
+    }
+
+    /// Adds the resources of another profile to this one.
+    pub fn merge(&mut self, other: ResourceProfile) {
+        for (stack, usage) in other.stacks {
+            self.record(stack, &usage);
+        }
+    }
+
+    /// Returns the total resources of the profile.
+    pub fn total(&self) -> ResourceUsage {
+        let mut total = ResourceUsage::default();
+        for usage in self.stacks.values() {
+            total.add_assign(usage);
+        }
+        total
+    }
+
+    /// Returns an object that displays the profile in the folded stacks format of flamegraph
+    /// tools, with one line per call stack that used some of the `metric`.
+    pub fn folded(&self, metric: ProfileMetric) -> FoldedStacks<'_> {
+        FoldedStacks {
+            profile: self,
+            metric,
+        }
+    }
+
+    /// Writes the profile in the folded stacks format to a file.
+    #[cfg(with_fs)]
+    pub fn write_folded(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        metric: ProfileMetric,
+    ) -> std::io::Result<()> {
+        std::fs::write(path, self.folded(metric).to_string())
+    }
+}
+
+/// A [`ResourceProfile`] in the folded stacks format of flamegraph tools.
+pub struct FoldedStacks<'a> {
+    profile: &'a ResourceProfile,
+    metric: ProfileMetric,
+}
+
+impl fmt::Display for FoldedStacks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (stack, usage) in &self.profile.stacks {
+            let value = usage.get(self.metric);
+            if value > 0 {
+                writeln!(f, "{} {value}", stack.join(";"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [`ResourceProfile`] while a transaction executes.
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    profile: ResourceProfile,
+    /// The frames of the current call stack.
+    stack: Vec<String>,
+    /// The state of the resource tracker when resources were last attributed.
+    last: ResourceTracker,
+}
+
+impl Profiler {
+    /// Returns the name of the frame of an application's entry point.
+    pub(crate) fn frame(application_id: ApplicationId, entry_point: &str) -> String {
+        format!("{application_id}::{entry_point}")
+    }
+
+    /// Attributes the resources used so far to the current stack, then enters a new frame.
+    pub(crate) fn push(&mut self, frame: String, tracker: &ResourceTracker) {
+        self.record(tracker, None);
+        self.stack.push(frame);
+    }
+
+    /// Attributes the resources used so far to the current stack, then leaves its last frame.
+    pub(crate) fn pop(&mut self, tracker: &ResourceTracker) {
+        self.record(tracker, None);
+        self.stack.pop();
+    }
+
+    /// Attributes the resources used since the last call to the current stack, followed by
+    /// `leaf` if any. Resources used outside of applications are ignored.
+    pub(crate) fn record(&mut self, tracker: &ResourceTracker, leaf: Option<&str>) {
+        let usage = ResourceUsage::between(&self.last, tracker);
+        self.last = *tracker;
+        if self.stack.is_empty() || usage.is_empty() {
+            return;
+        }
+        let mut stack = self.stack.clone();
+        stack.extend(leaf.map(str::to_owned));
+        self.profile.record(stack, &usage);
+    }
+
+    /// Attributes some resources that are not tracked by the resource tracker to the current
+    /// stack, followed by `leaf`.
+    pub(crate) fn record_untracked(&mut self, leaf: &str, usage: &ResourceUsage) {
+        if self.stack.is_empty() {
+            return;
+        }
+        let mut stack = self.stack.clone();
+        stack.push(leaf.to_owned());
+        self.profile.record(stack, usage);
+    }
+
+    /// Returns the profile recorded so far.
+    pub(crate) fn into_profile(self) -> ResourceProfile {
+        self.profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler_attributes_resources_to_stacks() {
+        let application_id = ApplicationId::default();
+        let frame = Profiler::frame(application_id, "execute_operation");
+        let mut tracker = ResourceTracker {
+            fuel: 100,
+            ..ResourceTracker::default()
+        };
+        let mut profiler = Profiler::default();
+        profiler.push(frame.clone(), &tracker);
+        tracker.fuel += 30;
+        profiler.record(&tracker, None);
+        tracker.read_operations += 1;
+        tracker.bytes_read += 8;
+        profiler.record(&tracker, Some("read_value_bytes"));
+        tracker.fuel += 12;
+        profiler.pop(&tracker);
+        // Resources used outside of applications are not attributed.
+        tracker.fuel += 1000;
+        profiler.record(&tracker, None);
+
+        let profile = profiler.into_profile();
+        assert_eq!(profile.total().fuel, 42);
+        assert_eq!(
+            profile.folded(ProfileMetric::Fuel).to_string(),
+            format!("{frame} 42\n")
+        );
+        assert_eq!(
+            profile.folded(ProfileMetric::BytesRead).to_string(),
+            format!("{frame};read_value_bytes 8\n")
+        );
+        assert_eq!(
+            "read-operations".parse::<ProfileMetric>().unwrap(),
+            ProfileMetric::ReadOperations
+        );
+    }
+}
@@ -12,7 +12,7 @@ use linera_base::{
     identifiers::AccountOwner,
 };
 use linera_views::{context::Context, views::ViewError};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{ExecutionError, Message, Operation, ResourceControlPolicy, SystemExecutionStateView};
 
@@ -27,7 +27,7 @@ pub struct ResourceController<Account = Amount, Tracker = ResourceTracker> {
 }
 
 /// The resources used so far by an execution process.
-#[derive(Copy, Debug, Clone, Default)]
+#[derive(Copy, Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceTracker {
     /// The number of blocks created.
     pub blocks: u32,
@@ -67,6 +67,8 @@ pub struct ResourceTracker {
     pub service_oracle_queries: u32,
     /// The time spent executing services as oracles.
     pub service_oracle_execution: Duration,
+    /// The number of timers set.
+    pub timers: u32,
     /// The amount allocated to message grants.
     pub grants: Amount,
 }
@@ -207,6 +209,27 @@ where
         self.update_balance(self.policy.http_request)
     }
 
+    /// Tracks a timer set by an application, with a payload of `payload_size` bytes that is
+    /// written to storage until the timer fires.
+    pub fn track_timer(&mut self, payload_size: u64) -> Result<(), ExecutionError> {
+        let limit = self.policy.maximum_timer_payload_bytes;
+        ensure!(
+            payload_size <= limit,
+            ExecutionError::TimerPayloadTooLarge {
+                limit,
+                size: payload_size,
+            }
+        );
+        self.tracker.as_mut().timers = self
+            .tracker
+            .as_ref()
+            .timers
+            .checked_add(1)
+            .ok_or(ArithmeticError::Overflow)?;
+        self.update_balance(self.policy.timer)?;
+        self.track_bytes_written(payload_size)
+    }
+
     /// Tracks a number of fuel units used.
     pub(crate) fn track_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
         self.tracker.as_mut().fuel = self
@@ -14,7 +14,7 @@ use linera_base::{
     crypto::CryptoHash,
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, BlockHeight, OracleResponse,
-        SendMessageRequest, Timestamp,
+        SendMessageRequest, TimerDeadline, Timestamp,
     },
     ensure, http,
     identifiers::{
@@ -29,14 +29,15 @@ use oneshot::Receiver;
 use crate::{
     execution::UserAction,
     execution_state_actor::{ExecutionRequest, ExecutionStateSender},
+    profile::Profiler,
     resources::ResourceController,
     system::CreateApplicationResult,
     util::{ReceiverExt, UnboundedSenderExt},
     ApplicationDescription, ApplicationId, BaseRuntime, ContractRuntime, ExecutionError,
     FinalizeContext, Message, MessageContext, MessageKind, ModuleId, Operation, OperationContext,
-    OutgoingMessage, QueryContext, QueryOutcome, ServiceRuntime, TransactionTracker,
-    UserContractCode, UserContractInstance, UserServiceCode, UserServiceInstance,
-    MAX_STREAM_NAME_LEN,
+    OutgoingMessage, QueryContext, QueryOutcome, ResourceUsage, ServiceRuntime, StorageRead,
+    StorageWrite, TraceStep, TransactionTracker, UserContractCode, UserContractInstance,
+    UserServiceCode, UserServiceInstance, MAX_STREAM_NAME_LEN,
 };
 
 #[cfg(test)]
@@ -357,6 +358,67 @@ impl<UserInstance> SyncRuntimeInternal<UserInstance> {
         status
     }
 
+    /// Records a call of a runtime function by the current application, if the execution is
+    /// traced.
+    fn trace_call(&mut self, function: &str) {
+        if let Some(application) = self.call_stack.last() {
+            let application_id = application.id;
+            self.transaction_tracker.trace(|| TraceStep::RuntimeCall {
+                application_id,
+                function: function.to_string(),
+            });
+        }
+    }
+
+    /// Records a storage read of the current application, if the execution is traced.
+    fn trace_read(&mut self, read: impl FnOnce() -> StorageRead) {
+        let application_id = self.current_application().id;
+        self.transaction_tracker.trace(|| TraceStep::StorageRead {
+            application_id,
+            read: read(),
+        });
+    }
+
+    /// Attributes the resources used since the last call to the current call stack, followed
+    /// by `leaf` if any, if the execution is profiled.
+    fn profile(&mut self, leaf: Option<&str>) {
+        if let Some(profiler) = self.transaction_tracker.profiler() {
+            profiler.record(&self.resource_controller.tracker, leaf);
+        }
+    }
+
+    /// Tracks some resources with `track`, and attributes exactly them to the current call
+    /// stack followed by `leaf`, if the execution is profiled. The resources used before are
+    /// attributed to the current call stack itself.
+    fn track_in_leaf(
+        &mut self,
+        leaf: &str,
+        track: impl FnOnce(&mut ResourceController) -> Result<(), ExecutionError>,
+    ) -> Result<(), ExecutionError> {
+        self.profile(None);
+        let result = track(&mut self.resource_controller);
+        self.profile(Some(leaf));
+        result
+    }
+
+    /// Enters the entry point of an application in the profiled call stack, if the execution
+    /// is profiled.
+    fn profile_enter(&mut self, application_id: ApplicationId, entry_point: &str) {
+        if let Some(profiler) = self.transaction_tracker.profiler() {
+            profiler.push(
+                Profiler::frame(application_id, entry_point),
+                &self.resource_controller.tracker,
+            );
+        }
+    }
+
+    /// Leaves the last entry point of the profiled call stack, if the execution is profiled.
+    fn profile_leave(&mut self) {
+        if let Some(profiler) = self.transaction_tracker.profiler() {
+            profiler.pop(&self.resource_controller.tracker);
+        }
+    }
+
     /// Ensures that a call to `application_id` is not-reentrant.
     ///
     /// Returns an error if there already is an entry for `application_id` in the call stack.
@@ -401,7 +463,8 @@ impl SyncRuntimeInternal<UserContractInstance> {
                     .recv_response()?;
                 self.transaction_tracker = txn_tracker_moved;
 
-                let instance = code.instantiate(this)?;
+                let profiled = self.transaction_tracker.is_profiled();
+                let instance = code.instantiate(this, profiled)?;
 
                 self.applications_to_finalize.push(id);
                 Ok(entry
@@ -434,6 +497,12 @@ impl SyncRuntimeInternal<UserContractInstance> {
 
         let caller = self.current_application();
         let caller_id = caller.id;
+        self.transaction_tracker
+            .trace(|| TraceStep::CallApplication {
+                caller_id,
+                callee_id,
+                authenticated,
+            });
         let caller_signer = caller.signer;
         // Make the call to user code.
         let authenticated_signer = match caller_signer {
@@ -456,11 +525,13 @@ impl SyncRuntimeInternal<UserContractInstance> {
             // Allow further nested calls to be authenticated if this one is.
             signer: authenticated_signer,
         });
+        self.profile_enter(callee_id, "execute_operation");
         Ok((application.instance, callee_context))
     }
 
     /// Cleans up the runtime after the execution of a call to a different contract.
     fn finish_call(&mut self) -> Result<(), ExecutionError> {
+        self.profile_leave();
         self.pop_application();
         Ok(())
     }
@@ -588,18 +659,22 @@ where
     type FindKeyValuesByPrefix = u32;
 
     fn chain_id(&mut self) -> Result<ChainId, ExecutionError> {
+        self.inner().trace_call("chain_id");
         Ok(self.inner().chain_id)
     }
 
     fn block_height(&mut self) -> Result<BlockHeight, ExecutionError> {
+        self.inner().trace_call("block_height");
         Ok(self.inner().height)
     }
 
     fn application_id(&mut self) -> Result<ApplicationId, ExecutionError> {
+        self.inner().trace_call("application_id");
         Ok(self.inner().current_application().id)
     }
 
     fn application_creator_chain_id(&mut self) -> Result<ChainId, ExecutionError> {
+        self.inner().trace_call("application_creator_chain_id");
         Ok(self
             .inner()
             .current_application()
@@ -608,6 +683,7 @@ where
     }
 
     fn application_parameters(&mut self) -> Result<Vec<u8>, ExecutionError> {
+        self.inner().trace_call("application_parameters");
         Ok(self
             .inner()
             .current_application()
@@ -617,6 +693,7 @@ where
     }
 
     fn read_system_timestamp(&mut self) -> Result<Timestamp, ExecutionError> {
+        self.inner().trace_call("read_system_timestamp");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::SystemTimestamp { callback })?
@@ -624,6 +701,7 @@ where
     }
 
     fn read_chain_balance(&mut self) -> Result<Amount, ExecutionError> {
+        self.inner().trace_call("read_chain_balance");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::ChainBalance { callback })?
@@ -631,6 +709,7 @@ where
     }
 
     fn read_owner_balance(&mut self, owner: AccountOwner) -> Result<Amount, ExecutionError> {
+        self.inner().trace_call("read_owner_balance");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::OwnerBalance { owner, callback })?
@@ -638,6 +717,7 @@ where
     }
 
     fn read_owner_balances(&mut self) -> Result<Vec<(AccountOwner, Amount)>, ExecutionError> {
+        self.inner().trace_call("read_owner_balances");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::OwnerBalances { callback })?
@@ -645,6 +725,7 @@ where
     }
 
     fn read_balance_owners(&mut self) -> Result<Vec<AccountOwner>, ExecutionError> {
+        self.inner().trace_call("read_balance_owners");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::BalanceOwners { callback })?
@@ -652,6 +733,7 @@ where
     }
 
     fn chain_ownership(&mut self) -> Result<ChainOwnership, ExecutionError> {
+        self.inner().trace_call("chain_ownership");
         self.inner()
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::ChainOwnership { callback })?
@@ -661,7 +743,10 @@ where
     fn contains_key_new(&mut self, key: Vec<u8>) -> Result<Self::ContainsKey, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("contains_key", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::ContainsKey(key.clone()));
         let receiver = this
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ContainsKey { id, key, callback })?;
@@ -683,7 +768,10 @@ where
     ) -> Result<Self::ContainsKeys, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("contains_keys", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::ContainsKeys(keys.clone()));
         let receiver = this
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ContainsKeys { id, keys, callback })?;
@@ -708,7 +796,10 @@ where
     ) -> Result<Self::ReadMultiValuesBytes, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("read_multi_values_bytes", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::ReadMultiValues(keys.clone()));
         let receiver = this.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::ReadMultiValuesBytes { id, keys, callback }
         })?;
@@ -724,12 +815,12 @@ where
         let id = this.current_application().id;
         let state = this.view_user_states.entry(id).or_default();
         let values = state.read_multi_values_queries.wait(*promise)?;
-        for value in &values {
-            if let Some(value) = &value {
-                this.resource_controller
-                    .track_bytes_read(value.len() as u64)?;
+        this.track_in_leaf("read_multi_values_bytes", |controller| {
+            for value in values.iter().flatten() {
+                controller.track_bytes_read(value.len() as u64)?;
             }
-        }
+            Ok(())
+        })?;
         Ok(values)
     }
 
@@ -739,7 +830,10 @@ where
     ) -> Result<Self::ReadValueBytes, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("read_value_bytes", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::ReadValue(key.clone()));
         let receiver = this
             .execution_state_sender
             .send_request(move |callback| ExecutionRequest::ReadValueBytes { id, key, callback })?;
@@ -757,10 +851,12 @@ where
             let state = this.view_user_states.entry(id).or_default();
             state.read_value_queries.wait(*promise)?
         };
-        if let Some(value) = &value {
-            this.resource_controller
-                .track_bytes_read(value.len() as u64)?;
-        }
+        this.track_in_leaf("read_value_bytes", |controller| {
+            if let Some(value) = &value {
+                controller.track_bytes_read(value.len() as u64)?;
+            }
+            Ok(())
+        })?;
         Ok(value)
     }
 
@@ -770,7 +866,10 @@ where
     ) -> Result<Self::FindKeysByPrefix, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("find_keys_by_prefix", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::FindKeysByPrefix(key_prefix.clone()));
         let receiver = this.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::FindKeysByPrefix {
                 id,
@@ -796,8 +895,9 @@ where
         for key in &keys {
             read_size += key.len();
         }
-        this.resource_controller
-            .track_bytes_read(read_size as u64)?;
+        this.track_in_leaf("find_keys_by_prefix", |controller| {
+            controller.track_bytes_read(read_size as u64)
+        })?;
         Ok(keys)
     }
 
@@ -807,7 +907,10 @@ where
     ) -> Result<Self::FindKeyValuesByPrefix, ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
-        this.resource_controller.track_read_operations(1)?;
+        this.track_in_leaf("find_key_values_by_prefix", |controller| {
+            controller.track_read_operations(1)
+        })?;
+        this.trace_read(|| StorageRead::FindKeyValuesByPrefix(key_prefix.clone()));
         let receiver = this.execution_state_sender.send_request(move |callback| {
             ExecutionRequest::FindKeyValuesByPrefix {
                 id,
@@ -831,8 +934,9 @@ where
         for (key, value) in &key_values {
             read_size += key.len() + value.len();
         }
-        this.resource_controller
-            .track_bytes_read(read_size as u64)?;
+        this.track_in_leaf("find_key_values_by_prefix", |controller| {
+            controller.track_bytes_read(read_size as u64)
+        })?;
         Ok(key_values)
     }
 
@@ -841,6 +945,7 @@ where
         request: http::Request,
     ) -> Result<http::Response, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("perform_http_request");
         let app_permissions = this
             .execution_state_sender
             .send_request(|callback| ExecutionRequest::GetApplicationPermissions { callback })?
@@ -877,6 +982,7 @@ where
 
     fn assert_before(&mut self, timestamp: Timestamp) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("assert_before");
         if !this
             .transaction_tracker
             .replay_oracle_response(OracleResponse::Assert)?
@@ -896,6 +1002,7 @@ where
 
     fn read_data_blob(&mut self, hash: &CryptoHash) -> Result<Vec<u8>, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("read_data_blob");
         let blob_id = BlobId::new(*hash, BlobType::Data);
         let (blob_content, is_new) = this
             .execution_state_sender
@@ -910,6 +1017,7 @@ where
 
     fn assert_data_blob_exists(&mut self, hash: &CryptoHash) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("assert_data_blob_exists");
         let blob_id = BlobId::new(*hash, BlobType::Data);
         let is_new = this
             .execution_state_sender
@@ -989,10 +1097,11 @@ impl ContractSyncRuntime {
             .expect("contracts shouldn't be preloaded while the runtime is being dropped");
         let runtime_handle = this.clone();
         let mut this_guard = this.inner();
+        let profiled = this_guard.transaction_tracker.is_profiled();
 
         if let hash_map::Entry::Vacant(entry) = this_guard.loaded_applications.entry(id) {
             entry.insert(LoadedApplication::new(
-                code.instantiate(runtime_handle)?,
+                code.instantiate(runtime_handle, profiled)?,
                 description,
             ));
             this_guard.applications_to_finalize.push(id);
@@ -1044,10 +1153,17 @@ impl ContractSyncRuntimeHandle {
         }
 
         let signer = action.signer();
+        let entry_point = match &action {
+            UserAction::Instantiate(..) => "instantiate",
+            UserAction::Migrate(..) => "migrate",
+            UserAction::Operation(..) => "execute_operation",
+            UserAction::Message(..) => "execute_message",
+        };
         let closure = move |code: &mut UserContractInstance| match action {
             UserAction::Instantiate(context, argument) => {
                 code.instantiate(context, argument).map(|()| None)
             }
+            UserAction::Migrate(context) => code.migrate(context).map(|()| None),
             UserAction::Operation(context, operation) => {
                 code.execute_operation(context, operation).map(Option::Some)
             }
@@ -1056,7 +1172,7 @@ impl ContractSyncRuntimeHandle {
             }
         };
 
-        let result = self.execute(application_id, signer, closure)?;
+        let result = self.execute(application_id, signer, entry_point, closure)?;
         self.finalize(finalize_context)?;
         Ok(result)
     }
@@ -1070,9 +1186,12 @@ impl ContractSyncRuntimeHandle {
         self.inner().is_finalizing = true;
 
         for application in applications {
-            self.execute(application, context.authenticated_signer, |contract| {
-                contract.finalize(context).map(|_| None)
-            })?;
+            self.execute(
+                application,
+                context.authenticated_signer,
+                "finalize",
+                |contract| contract.finalize(context).map(|_| None),
+            )?;
             self.inner().loaded_applications.remove(&application);
         }
 
@@ -1084,11 +1203,16 @@ impl ContractSyncRuntimeHandle {
         &mut self,
         application_id: ApplicationId,
         signer: Option<AccountOwner>,
+        entry_point: &str,
         closure: impl FnOnce(&mut UserContractInstance) -> Result<Option<Vec<u8>>, ExecutionError>,
     ) -> Result<Option<Vec<u8>>, ExecutionError> {
         let contract = {
             let mut runtime = self.inner();
             let application = runtime.load_contract_instance(self.clone(), application_id)?;
+            runtime.transaction_tracker.trace(|| TraceStep::EntryPoint {
+                application_id,
+                entry_point: entry_point.to_string(),
+            });
 
             let status = ApplicationStatus {
                 caller_id: None,
@@ -1098,6 +1222,7 @@ impl ContractSyncRuntimeHandle {
             };
 
             runtime.push_application(status);
+            runtime.profile_enter(application_id, entry_point);
 
             application
         };
@@ -1110,6 +1235,7 @@ impl ContractSyncRuntimeHandle {
         )?;
 
         let mut runtime = self.inner();
+        runtime.profile_leave();
         let application_status = runtime.pop_application();
         assert_eq!(application_status.caller_id, None);
         assert_eq!(application_status.id, application_id);
@@ -1123,14 +1249,17 @@ impl ContractSyncRuntimeHandle {
 
 impl ContractRuntime for ContractSyncRuntimeHandle {
     fn authenticated_signer(&mut self) -> Result<Option<AccountOwner>, ExecutionError> {
+        self.inner().trace_call("authenticated_signer");
         Ok(self.inner().authenticated_signer)
     }
 
     fn message_id(&mut self) -> Result<Option<MessageId>, ExecutionError> {
+        self.inner().trace_call("message_id");
         Ok(self.inner().executing_message.map(|metadata| metadata.id))
     }
 
     fn message_is_bouncing(&mut self) -> Result<Option<bool>, ExecutionError> {
+        self.inner().trace_call("message_is_bouncing");
         Ok(self
             .inner()
             .executing_message
@@ -1138,7 +1267,8 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
     }
 
     fn authenticated_caller_id(&mut self) -> Result<Option<ApplicationId>, ExecutionError> {
-        let this = self.inner();
+        let mut this = self.inner();
+        this.trace_call("authenticated_caller_id");
         if this.call_stack.len() <= 1 {
             return Ok(None);
         }
@@ -1146,16 +1276,49 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
     }
 
     fn remaining_fuel(&mut self) -> Result<u64, ExecutionError> {
+        self.inner().trace_call("remaining_fuel");
         Ok(self.inner().resource_controller.remaining_fuel())
     }
 
     fn consume_fuel(&mut self, fuel: u64) -> Result<(), ExecutionError> {
         let mut this = self.inner();
-        this.resource_controller.track_fuel(fuel)
+        let application_id = this.current_application().id;
+        this.transaction_tracker.trace(|| TraceStep::Fuel {
+            application_id,
+            fuel,
+        });
+        this.resource_controller.track_fuel(fuel)?;
+        this.profile(None);
+        Ok(())
+    }
+
+    fn consume_fuel_in(&mut self, function: &str, fuel: u64) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        let application_id = this.current_application().id;
+        this.transaction_tracker.trace(|| TraceStep::Fuel {
+            application_id,
+            fuel,
+        });
+        this.track_in_leaf(function, |controller| controller.track_fuel(fuel))
+    }
+
+    fn profile_fuel(&mut self, function: &str, fuel: u64) -> Result<(), ExecutionError> {
+        let mut this = self.inner();
+        if let Some(profiler) = this.transaction_tracker.profiler() {
+            profiler.record_untracked(
+                function,
+                &ResourceUsage {
+                    fuel,
+                    ..ResourceUsage::default()
+                },
+            );
+        }
+        Ok(())
     }
 
     fn send_message(&mut self, message: SendMessageRequest<Vec<u8>>) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("send_message");
         let application = this.current_application();
         let application_id = application.id;
         let authenticated_signer = application.signer;
@@ -1194,6 +1357,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
 
     fn subscribe(&mut self, chain: ChainId, name: ChannelName) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("subscribe");
         let application_id = this.current_application().id;
         let full_name = ChannelFullName::new(name, application_id);
         this.transaction_tracker.subscribe(full_name, chain);
@@ -1203,6 +1367,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
 
     fn unsubscribe(&mut self, chain: ChainId, name: ChannelName) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("unsubscribe");
         let application_id = this.current_application().id;
         let full_name = ChannelFullName::new(name, application_id);
         this.transaction_tracker.unsubscribe(full_name, chain);
@@ -1217,6 +1382,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         amount: Amount,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("transfer");
         let current_application = this.current_application();
         let application_id = current_application.id;
         let signer = current_application.signer;
@@ -1245,6 +1411,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         amount: Amount,
     ) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("claim");
         let current_application = this.current_application();
         let application_id = current_application.id;
         let signer = current_application.signer;
@@ -1279,13 +1446,17 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
             .expect("Applications should not have reentrant calls")
             .execute_operation(context, argument)?;
 
-        self.inner().finish_call()?;
+        let mut this = self.inner();
+        this.finish_call()?;
+        this.transaction_tracker
+            .trace(|| TraceStep::ReturnFromApplication { callee_id });
 
         Ok(value)
     }
 
     fn emit(&mut self, stream_name: StreamName, value: Vec<u8>) -> Result<u32, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("emit");
         ensure!(
             stream_name.0.len() <= MAX_STREAM_NAME_LEN,
             ExecutionError::StreamNameTooLong
@@ -1316,6 +1487,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         index: u32,
     ) -> Result<Vec<u8>, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("read_event");
         ensure!(
             stream_name.0.len() <= MAX_STREAM_NAME_LEN,
             ExecutionError::StreamNameTooLong
@@ -1351,7 +1523,8 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         application_id: ApplicationId,
         stream_name: StreamName,
     ) -> Result<(), ExecutionError> {
-        let this = self.inner();
+        let mut this = self.inner();
+        this.trace_call("subscribe_to_events");
         ensure!(
             stream_name.0.len() <= MAX_STREAM_NAME_LEN,
             ExecutionError::StreamNameTooLong
@@ -1378,7 +1551,8 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         application_id: ApplicationId,
         stream_name: StreamName,
     ) -> Result<(), ExecutionError> {
-        let this = self.inner();
+        let mut this = self.inner();
+        this.trace_call("unsubscribe_from_events");
         ensure!(
             stream_name.0.len() <= MAX_STREAM_NAME_LEN,
             ExecutionError::StreamNameTooLong
@@ -1399,12 +1573,45 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         Ok(())
     }
 
+    fn set_timer(
+        &mut self,
+        deadline: TimerDeadline,
+        payload: Vec<u8>,
+    ) -> Result<u64, ExecutionError> {
+        let mut this = self.inner();
+        this.trace_call("set_timer");
+        this.resource_controller.track_timer(payload.len() as u64)?;
+        let application_id = this.current_application().id;
+        this.execution_state_sender
+            .send_request(|callback| ExecutionRequest::SetTimer {
+                application_id,
+                deadline,
+                payload,
+                callback,
+            })?
+            .recv_response()
+    }
+
+    fn cancel_timer(&mut self, timer_id: u64) -> Result<bool, ExecutionError> {
+        let mut this = self.inner();
+        this.trace_call("cancel_timer");
+        let application_id = this.current_application().id;
+        this.execution_state_sender
+            .send_request(|callback| ExecutionRequest::CancelTimer {
+                application_id,
+                timer_id,
+                callback,
+            })?
+            .recv_response()?
+    }
+
     fn query_service(
         &mut self,
         application_id: ApplicationId,
         query: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("query_service");
 
         let app_permissions = this
             .execution_state_sender
@@ -1441,6 +1648,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         balance: Amount,
     ) -> Result<(MessageId, ChainId), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("open_chain");
         let message_id = MessageId {
             chain_id: this.chain_id,
             height: this.height,
@@ -1463,7 +1671,8 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
     }
 
     fn close_chain(&mut self) -> Result<(), ExecutionError> {
-        let this = self.inner();
+        let mut this = self.inner();
+        this.trace_call("close_chain");
         let application_id = this.current_application().id;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::CloseChain {
@@ -1477,7 +1686,8 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         &mut self,
         application_permissions: ApplicationPermissions,
     ) -> Result<(), ExecutionError> {
-        let this = self.inner();
+        let mut this = self.inner();
+        this.trace_call("change_application_permissions");
         let application_id = this.current_application().id;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::ChangeApplicationPermissions {
@@ -1495,6 +1705,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
         argument: Vec<u8>,
         required_application_ids: Vec<ApplicationId>,
     ) -> Result<ApplicationId, ExecutionError> {
+        self.inner().trace_call("create_application");
         let chain_id = self.inner().chain_id;
         let block_height = self.block_height()?;
 
@@ -1533,6 +1744,7 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
 
     fn validation_round(&mut self) -> Result<Option<u32>, ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("validation_round");
         let round =
             if let Some(response) = this.transaction_tracker.next_replayed_oracle_response()? {
                 match response {
@@ -1550,16 +1762,20 @@ impl ContractRuntime for ContractSyncRuntimeHandle {
     fn write_batch(&mut self, batch: Batch) -> Result<(), ExecutionError> {
         let mut this = self.inner();
         let id = this.current_application().id;
+        this.transaction_tracker.trace(|| TraceStep::StorageWrite {
+            application_id: id,
+            writes: StorageWrite::from_batch(&batch),
+        });
         let state = this.view_user_states.entry(id).or_default();
         state.force_all_pending_queries()?;
-        this.resource_controller.track_write_operations(
-            batch
-                .num_operations()
-                .try_into()
-                .map_err(|_| ExecutionError::from(ArithmeticError::Overflow))?,
-        )?;
-        this.resource_controller
-            .track_bytes_written(batch.size() as u64)?;
+        let write_operations = batch
+            .num_operations()
+            .try_into()
+            .map_err(|_| ExecutionError::from(ArithmeticError::Overflow))?;
+        this.track_in_leaf("write_batch", |controller| {
+            controller.track_write_operations(write_operations)?;
+            controller.track_bytes_written(batch.size() as u64)
+        })?;
         this.execution_state_sender
             .send_request(|callback| ExecutionRequest::WriteBatch {
                 id,
@@ -1699,6 +1915,7 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
         queried_id: ApplicationId,
         argument: Vec<u8>,
     ) -> Result<Vec<u8>, ExecutionError> {
+        self.inner().trace_call("try_query_application");
         let (query_context, service) = {
             let mut this = self.inner();
 
@@ -1728,6 +1945,7 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
 
     fn schedule_operation(&mut self, operation: Vec<u8>) -> Result<(), ExecutionError> {
         let mut this = self.inner();
+        this.trace_call("schedule_operation");
         let application_id = this.current_application().id;
 
         this.scheduled_operations.push(Operation::User {
@@ -1739,6 +1957,7 @@ impl ServiceRuntime for ServiceSyncRuntimeHandle {
     }
 
     fn check_execution_time(&mut self) -> Result<(), ExecutionError> {
+        self.inner().trace_call("check_execution_time");
         if let Some(deadline) = self.inner().deadline {
             if Instant::now() >= deadline {
                 return Err(ExecutionError::MaximumServiceOracleExecutionTimeExceeded);
@@ -17,8 +17,8 @@ use custom_debug_derive::Debug;
 use linera_base::{
     crypto::CryptoHash,
     data_types::{
-        Amount, ApplicationPermissions, Blob, BlobContent, BlockHeight, Epoch, OracleResponse,
-        Timestamp,
+        Amount, ApplicationPermissions, ArithmeticError, Blob, BlobContent, BlockHeight, Epoch,
+        OracleResponse, TimerDeadline, Timestamp,
     },
     ensure, hex_debug,
     identifiers::{
@@ -26,13 +26,15 @@ use linera_base::{
         ModuleId, StreamId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
+    vm::VmRuntime,
 };
 use linera_views::{
     context::Context,
-    map_view::{HashedMapView, MapView},
+    map_view::{CustomMapView, HashedMapView, MapView},
+    proof::{ProofPath, ViewProof},
     register_view::HashedRegisterView,
     set_view::HashedSetView,
-    views::{ClonableView, HashableView, View, ViewError},
+    views::{ClonableView, HashableFields as _, HashableView, View, ViewError},
 };
 use serde::{Deserialize, Serialize};
 #[cfg(with_metrics)]
@@ -42,8 +44,8 @@ use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCoun
 use crate::test_utils::SystemExecutionState;
 use crate::{
     committee::Committee, ApplicationDescription, ApplicationId, ExecutionError,
-    ExecutionRuntimeContext, MessageContext, MessageKind, OperationContext, OutgoingMessage,
-    QueryContext, QueryOutcome, ResourceController, TransactionTracker,
+    ExecutionRuntimeContext, Message, MessageContext, MessageKind, OperationContext,
+    OutgoingMessage, QueryContext, QueryOutcome, ResourceController, TransactionTracker,
 };
 
 /// The relative index of the `OpenChain` message created by the `OpenChain` operation.
@@ -52,6 +54,9 @@ pub static OPEN_CHAIN_MESSAGE_INDEX: u32 = 0;
 pub static EPOCH_STREAM_NAME: &[u8] = &[0];
 /// The event stream name for removed epochs.
 pub static REMOVED_EPOCH_STREAM_NAME: &[u8] = &[1];
+/// The prefix of the event stream names for application upgrades, followed by the hash of
+/// the application's description. The stream is on the application's creator chain.
+pub static APPLICATION_UPGRADE_STREAM_PREFIX: &[u8] = &[2];
 
 /// The number of times the [`SystemOperation::OpenChain`] was executed.
 #[cfg(with_metrics)]
@@ -93,6 +98,38 @@ pub struct SystemExecutionStateView<C> {
     pub used_blobs: HashedSetView<C, BlobId>,
     /// The event stream subscriptions of applications on this chain.
     pub event_subscriptions: MapView<C, (ChainId, StreamId), EventSubscriptions>,
+    /// The ID of the next timer set by an application on this chain.
+    pub next_timer_id: HashedRegisterView<C, u64>,
+    /// The timers set by applications on this chain that have not fired yet, by ID.
+    pub timers: MapView<C, u64, Timer>,
+    /// The IDs of the timers with a timestamp as deadline, by timestamp in microseconds.
+    pub timestamp_timers: CustomMapView<C, u64, BTreeSet<u64>>,
+    /// The IDs of the timers with a block height as deadline, by block height.
+    pub height_timers: CustomMapView<C, u64, BTreeSet<u64>>,
+    /// The latest upgrades of applications processed by this chain.
+    pub application_upgrades: MapView<C, ApplicationId, ApplicationUpgrade>,
+}
+
+/// The upgrades of an application that a chain has processed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApplicationUpgrade {
+    /// The number of upgrades processed, i.e. the index of the next upgrade event.
+    pub count: u32,
+    /// The module the application was last upgraded to.
+    pub module_id: ModuleId,
+}
+
+/// Returns the ID of the event stream, on the creator chain of an application, with the
+/// modules the application is upgraded to.
+pub fn application_upgrade_stream_id(application_id: ApplicationId) -> StreamId {
+    let mut name = APPLICATION_UPGRADE_STREAM_PREFIX.to_vec();
+    name.extend_from_slice(
+        application_id
+            .application_description_hash
+            .as_bytes()
+            .as_slice(),
+    );
+    StreamId::system(name)
 }
 
 /// The applications subscribing to a particular stream, and the next event index.
@@ -105,6 +142,18 @@ pub struct EventSubscriptions {
     pub applications: BTreeSet<ApplicationId>,
 }
 
+/// A timer set by an application, to receive a message once it is due.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timer {
+    /// The application that set the timer and receives its payload.
+    pub application_id: ApplicationId,
+    /// When the timer becomes due.
+    pub deadline: TimerDeadline,
+    /// The message sent to the application when the timer fires.
+    #[debug(with = "hex_debug")]
+    pub payload: Vec<u8>,
+}
+
 /// The configuration for a new chain.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct OpenChainConfig {
@@ -186,6 +235,22 @@ pub enum SystemOperation {
     ProcessRemovedEpoch(Epoch),
     /// Updates the event stream trackers.
     UpdateStreams(Vec<(ChainId, StreamId, u32)>),
+    /// Fires timers that are due, sending their payloads as messages to their applications
+    /// on this chain.
+    FireTimers(Vec<u64>),
+    /// Upgrades an application to the code of a new module, and runs its migration. This is
+    /// only allowed on the chain that created the application, for the applications listed in
+    /// its [`ApplicationPermissions`]. The upgrade is published as an event, and other chains
+    /// apply it by executing [`SystemOperation::ProcessApplicationUpgrade`]. The application
+    /// keeps its ID and its state. Messages to the application that are still in flight are
+    /// executed by the new code.
+    UpgradeApplication {
+        application_id: ApplicationId,
+        module_id: ModuleId,
+    },
+    /// Processes the next upgrade event of an application from its creator chain, and runs
+    /// its migration on this chain.
+    ProcessApplicationUpgrade(ApplicationId),
 }
 
 /// Operations that are only allowed on the admin chain.
@@ -318,6 +383,107 @@ where
         Some((*epoch, committee))
     }
 
+    /// Returns a proof of the balance of `owner`, under the hash of this view.
+    pub async fn balance_proof(&self, owner: &AccountOwner) -> Result<ViewProof, ViewError> {
+        let (index, proof) = if *owner == AccountOwner::CHAIN {
+            (Self::field_index("balance")?, self.balance.proof()?)
+        } else {
+            (
+                Self::field_index("balances")?,
+                self.balances.proof(owner).await?,
+            )
+        };
+        Ok(proof.in_field(index, self.field_hashes().await?))
+    }
+
+    /// Returns the location of the balance of `owner` in this view, to check the proofs
+    /// returned by [`Self::balance_proof`].
+    pub fn balance_proof_path(owner: &AccountOwner) -> Result<ProofPath, ViewError> {
+        Ok(if *owner == AccountOwner::CHAIN {
+            ProofPath::register().in_field(Self::field_index("balance")?)
+        } else {
+            ProofPath::entry(bcs::to_bytes(owner)?).in_field(Self::field_index("balances")?)
+        })
+    }
+
+    /// Adds a timer, and indexes it by its deadline.
+    pub async fn add_timer(&mut self, timer_id: u64, timer: Timer) -> Result<(), ViewError> {
+        let (timers, key) = self.timers_by_deadline(timer.deadline);
+        timers.get_mut_or_default(&key).await?.insert(timer_id);
+        self.timers.insert(&timer_id, timer)
+    }
+
+    /// Removes a timer and returns it, if it exists.
+    pub async fn remove_timer(&mut self, timer_id: u64) -> Result<Option<Timer>, ViewError> {
+        let Some(timer) = self.timers.get(&timer_id).await? else {
+            return Ok(None);
+        };
+        self.timers.remove(&timer_id)?;
+        let (timers, key) = self.timers_by_deadline(timer.deadline);
+        if let Some(timer_ids) = timers.get_mut(&key).await? {
+            timer_ids.remove(&timer_id);
+            if timer_ids.is_empty() {
+                timers.remove(&key)?;
+            }
+        }
+        Ok(Some(timer))
+    }
+
+    /// Returns the IDs of the timers that are due in a block with the given height and
+    /// timestamp, in increasing order. Only the entries of the due deadlines are read.
+    pub async fn due_timer_ids(
+        &self,
+        height: BlockHeight,
+        timestamp: Timestamp,
+    ) -> Result<Vec<u64>, ViewError> {
+        let mut timer_ids = Vec::new();
+        for (timers, limit) in [
+            (&self.timestamp_timers, timestamp.micros()),
+            (&self.height_timers, height.0),
+        ] {
+            let mut due_keys = Vec::new();
+            timers
+                .for_each_index_while(|key| {
+                    if key > limit {
+                        return Ok(false);
+                    }
+                    due_keys.push(key);
+                    Ok(true)
+                })
+                .await?;
+            for key in due_keys {
+                timer_ids.extend(timers.get(&key).await?.into_iter().flatten());
+            }
+        }
+        timer_ids.sort_unstable();
+        Ok(timer_ids)
+    }
+
+    /// Returns the earliest timestamp at which a timer becomes due, if any timer has a
+    /// timestamp as its deadline.
+    pub async fn next_timer_timestamp(&self) -> Result<Option<Timestamp>, ViewError> {
+        let mut next = None;
+        self.timestamp_timers
+            .for_each_index_while(|micros| {
+                next = Some(Timestamp::from(micros));
+                Ok(false)
+            })
+            .await?;
+        Ok(next)
+    }
+
+    /// Returns the index of the timers with the same kind of deadline, and the key of
+    /// `deadline` in it.
+    fn timers_by_deadline(
+        &mut self,
+        deadline: TimerDeadline,
+    ) -> (&mut CustomMapView<C, u64, BTreeSet<u64>>, u64) {
+        match deadline {
+            TimerDeadline::Timestamp(timestamp) => (&mut self.timestamp_timers, timestamp.micros()),
+            TimerDeadline::BlockHeight(height) => (&mut self.height_timers, height.0),
+        }
+    }
+
     /// Executes the sender's side of an operation and returns a list of actions to be
     /// taken.
     pub async fn execute_operation(
@@ -472,17 +638,8 @@ where
                     stream_id: StreamId::system(EPOCH_STREAM_NAME),
                     index: epoch.0,
                 };
-                let bytes = match txn_tracker.next_replayed_oracle_response()? {
-                    None => self.context().extra().get_event(event_id.clone()).await?,
-                    Some(OracleResponse::Event(recorded_event_id, bytes))
-                        if recorded_event_id == event_id =>
-                    {
-                        bytes
-                    }
-                    Some(_) => return Err(ExecutionError::OracleResponseMismatch),
-                };
+                let bytes = self.read_event(event_id, txn_tracker).await?;
                 let blob_id = BlobId::new(bcs::from_bytes(&bytes)?, BlobType::Committee);
-                txn_tracker.add_oracle_response(OracleResponse::Event(event_id, bytes));
                 let committee = bcs::from_bytes(self.read_blob_content(blob_id).await?.bytes())?;
                 self.blob_used(Some(txn_tracker), blob_id).await?;
                 self.committees.get_mut().insert(epoch, committee);
@@ -502,16 +659,7 @@ where
                     stream_id: StreamId::system(REMOVED_EPOCH_STREAM_NAME),
                     index: epoch.0,
                 };
-                let bytes = match txn_tracker.next_replayed_oracle_response()? {
-                    None => self.context().extra().get_event(event_id.clone()).await?,
-                    Some(OracleResponse::Event(recorded_event_id, bytes))
-                        if recorded_event_id == event_id =>
-                    {
-                        bytes
-                    }
-                    Some(_) => return Err(ExecutionError::OracleResponseMismatch),
-                };
-                txn_tracker.add_oracle_response(OracleResponse::Event(event_id, bytes));
+                self.read_event(event_id, txn_tracker).await?;
             }
             UpdateStreams(streams) => {
                 for (chain_id, stream_id, next_index) in streams {
@@ -522,11 +670,128 @@ where
                     subscriptions.next_index = subscriptions.next_index.max(next_index);
                 }
             }
+            FireTimers(timer_ids) => {
+                let timestamp = *self.timestamp.get();
+                for timer_id in timer_ids {
+                    let timer = self
+                        .timers
+                        .get(&timer_id)
+                        .await?
+                        .ok_or(ExecutionError::TimerNotFound(timer_id))?;
+                    ensure!(
+                        timer.deadline.is_due(context.height, timestamp),
+                        ExecutionError::TimerNotDue(timer_id)
+                    );
+                    self.remove_timer(timer_id).await?;
+                    let message = Message::User {
+                        application_id: timer.application_id,
+                        bytes: timer.payload,
+                    };
+                    txn_tracker
+                        .add_outgoing_message(OutgoingMessage::new(context.chain_id, message))?;
+                }
+            }
+            UpgradeApplication {
+                application_id,
+                module_id,
+            } => {
+                ensure!(
+                    self.application_permissions
+                        .get()
+                        .can_upgrade_application(&application_id),
+                    ExecutionError::UnauthorizedApplicationUpgrade(application_id)
+                );
+                let description = self
+                    .describe_application(application_id, Some(txn_tracker))
+                    .await?;
+                ensure!(
+                    description.creator_chain_id == context.chain_id,
+                    ExecutionError::UnauthorizedApplicationUpgrade(application_id)
+                );
+                // EVM contracts keep their deployed code in their own state.
+                ensure!(
+                    module_id.vm_runtime == VmRuntime::Wasm
+                        && description.module_id.vm_runtime == VmRuntime::Wasm,
+                    ExecutionError::UnsupportedApplicationUpgrade(application_id)
+                );
+                let count = self.application_upgrade_count(&application_id).await?;
+                txn_tracker.add_event(
+                    application_upgrade_stream_id(application_id),
+                    count,
+                    bcs::to_bytes(&module_id)?,
+                );
+                self.apply_application_upgrade(application_id, count, module_id, txn_tracker)
+                    .await?;
+            }
+            ProcessApplicationUpgrade(application_id) => {
+                let description = self
+                    .describe_application(application_id, Some(txn_tracker))
+                    .await?;
+                let count = self.application_upgrade_count(&application_id).await?;
+                let event_id = EventId {
+                    chain_id: description.creator_chain_id,
+                    stream_id: application_upgrade_stream_id(application_id),
+                    index: count,
+                };
+                let bytes = self.read_event(event_id, txn_tracker).await?;
+                let module_id = bcs::from_bytes(&bytes)?;
+                self.apply_application_upgrade(application_id, count, module_id, txn_tracker)
+                    .await?;
+            }
         }
 
         Ok(new_application)
     }
 
+    /// Reads an event from another chain, or the recorded one if the block is being replayed,
+    /// and records it as an oracle response.
+    async fn read_event(
+        &self,
+        event_id: EventId,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        let bytes = match txn_tracker.next_replayed_oracle_response()? {
+            None => self.context().extra().get_event(event_id.clone()).await?,
+            Some(OracleResponse::Event(recorded_event_id, bytes))
+                if recorded_event_id == event_id =>
+            {
+                bytes
+            }
+            Some(_) => return Err(ExecutionError::OracleResponseMismatch),
+        };
+        txn_tracker.add_oracle_response(OracleResponse::Event(event_id, bytes.clone()));
+        Ok(bytes)
+    }
+
+    /// Returns the number of upgrades of the application processed by this chain.
+    pub async fn application_upgrade_count(
+        &self,
+        application_id: &ApplicationId,
+    ) -> Result<u32, ViewError> {
+        Ok(self
+            .application_upgrades
+            .get(application_id)
+            .await?
+            .map_or(0, |upgrade| upgrade.count))
+    }
+
+    /// Records the upgrade with the given index of an application to `module_id`.
+    async fn apply_application_upgrade(
+        &mut self,
+        application_id: ApplicationId,
+        index: u32,
+        module_id: ModuleId,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<(), ExecutionError> {
+        for blob_id in self.check_bytecode_blobs(&module_id).await? {
+            self.blob_used(Some(txn_tracker), blob_id).await?;
+        }
+        let count = index.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+        self.application_upgrades
+            .insert(&application_id, ApplicationUpgrade { count, module_id })?;
+        Ok(())
+    }
+
     /// Returns an error if the `provided` epoch is not exactly one higher than the chain's current
     /// epoch.
     fn check_next_epoch(&self, provided: Epoch) -> Result<(), ExecutionError> {
@@ -849,6 +1114,20 @@ where
         Ok(description)
     }
 
+    /// Returns the description of the code to run for an application on this chain: the
+    /// application's own description, or the module it was upgraded to.
+    pub async fn code_description(
+        &self,
+        description: &ApplicationDescription,
+    ) -> Result<ApplicationDescription, ExecutionError> {
+        let application_id = ApplicationId::from(description);
+        let mut code_description = description.clone();
+        if let Some(upgrade) = self.application_upgrades.get(&application_id).await? {
+            code_description.module_id = upgrade.module_id;
+        }
+        Ok(code_description)
+    }
+
     /// Retrieves the recursive dependencies of applications and applies a topological sort.
     pub async fn find_dependencies(
         &mut self,
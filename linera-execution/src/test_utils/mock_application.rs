@@ -128,6 +128,11 @@ type InstantiateHandler = Box<
         + Send
         + Sync,
 >;
+type MigrateHandler = Box<
+    dyn FnOnce(&mut ContractSyncRuntimeHandle, OperationContext) -> Result<(), ExecutionError>
+        + Send
+        + Sync,
+>;
 type ExecuteOperationHandler = Box<
     dyn FnOnce(
             &mut ContractSyncRuntimeHandle,
@@ -166,6 +171,8 @@ type HandleQueryHandler = Box<
 pub enum ExpectedCall {
     /// An expected call to [`UserContract::instantiate`].
     Instantiate(#[debug(skip)] InstantiateHandler),
+    /// An expected call to [`UserContract::migrate`].
+    Migrate(#[debug(skip)] MigrateHandler),
     /// An expected call to [`UserContract::execute_operation`].
     ExecuteOperation(#[debug(skip)] ExecuteOperationHandler),
     /// An expected call to [`UserContract::execute_message`].
@@ -180,6 +187,7 @@ impl Display for ExpectedCall {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         let name = match self {
             ExpectedCall::Instantiate(_) => "instantiate",
+            ExpectedCall::Migrate(_) => "migrate",
             ExpectedCall::ExecuteOperation(_) => "execute_operation",
             ExpectedCall::ExecuteMessage(_) => "execute_message",
             ExpectedCall::Finalize(_) => "finalize",
@@ -206,6 +214,17 @@ impl ExpectedCall {
         ExpectedCall::Instantiate(Box::new(handler))
     }
 
+    /// Creates an [`ExpectedCall`] to the [`MockApplicationInstance`]'s
+    /// [`UserContract::migrate`] implementation, which is handled by the provided `handler`.
+    pub fn migrate(
+        handler: impl FnOnce(&mut ContractSyncRuntimeHandle, OperationContext) -> Result<(), ExecutionError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        ExpectedCall::Migrate(Box::new(handler))
+    }
+
     /// Creates an [`ExpectedCall`] to the [`MockApplicationInstance`]'s
     /// [`UserContract::execute_operation`] implementation, which is handled by the provided
     /// `handler`.
@@ -316,6 +335,16 @@ impl UserContract for MockApplicationInstance<ContractSyncRuntimeHandle> {
         }
     }
 
+    fn migrate(&mut self, context: OperationContext) -> Result<(), ExecutionError> {
+        match self.next_expected_call() {
+            Some(ExpectedCall::Migrate(handler)) => handler(&mut self.runtime, context),
+            Some(unexpected_call) => {
+                panic!("Expected a call to `migrate`, got a call to `{unexpected_call}` instead.")
+            }
+            None => panic!("Unexpected call to `migrate`"),
+        }
+    }
+
     fn execute_operation(
         &mut self,
         context: OperationContext,
@@ -0,0 +1,103 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Traces of the execution of transactions.
+//!
+//! When enabled in a [`TransactionTracker`][crate::TransactionTracker], every step of the
+//! execution that can make it diverge between validators is recorded: the calls to the
+//! runtime, the storage reads and writes, the oracle responses, the fuel and the calls between
+//! applications. Comparing the traces of two executions of the same block shows where they
+//! started to differ.
+
+use linera_base::{data_types::OracleResponse, identifiers::ApplicationId};
+use linera_views::batch::{Batch, WriteOperation};
+use serde::{Deserialize, Serialize};
+
+/// The steps of the execution of a transaction, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionTrace {
+    /// The index of the transaction in the block.
+    pub transaction_index: u32,
+    /// The steps of the execution.
+    pub steps: Vec<TraceStep>,
+}
+
+/// A step of the execution of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceStep {
+    /// The execution of an entry point of a contract started: `instantiate`, `migrate`,
+    /// `execute_operation`, `execute_message` or `finalize`.
+    EntryPoint {
+        application_id: ApplicationId,
+        entry_point: String,
+    },
+    /// An application called a function of the runtime.
+    RuntimeCall {
+        application_id: ApplicationId,
+        function: String,
+    },
+    /// An application requested a read of its storage.
+    StorageRead {
+        application_id: ApplicationId,
+        read: StorageRead,
+    },
+    /// An application wrote a batch to its storage.
+    StorageWrite {
+        application_id: ApplicationId,
+        writes: Vec<StorageWrite>,
+    },
+    /// An oracle response was obtained, or replayed from the block.
+    OracleResponse(OracleResponse),
+    /// An application consumed fuel. Consecutive consumptions are combined.
+    Fuel {
+        application_id: ApplicationId,
+        fuel: u64,
+    },
+    /// An application called another one.
+    CallApplication {
+        caller_id: ApplicationId,
+        callee_id: ApplicationId,
+        authenticated: bool,
+    },
+    /// A call to another application returned.
+    ReturnFromApplication { callee_id: ApplicationId },
+}
+
+/// The keys of a storage read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageRead {
+    ContainsKey(Vec<u8>),
+    ContainsKeys(Vec<Vec<u8>>),
+    ReadValue(Vec<u8>),
+    ReadMultiValues(Vec<Vec<u8>>),
+    FindKeysByPrefix(Vec<u8>),
+    FindKeyValuesByPrefix(Vec<u8>),
+}
+
+/// A storage write. Only the size of the written values is recorded.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StorageWrite {
+    Delete { key: Vec<u8> },
+    DeletePrefix { key_prefix: Vec<u8> },
+    Put { key: Vec<u8>, value_len: usize },
+}
+
+impl StorageWrite {
+    /// Returns the writes of a batch.
+    pub fn from_batch(batch: &Batch) -> Vec<Self> {
+        batch
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                WriteOperation::Delete { key } => StorageWrite::Delete { key: key.clone() },
+                WriteOperation::DeletePrefix { key_prefix } => StorageWrite::DeletePrefix {
+                    key_prefix: key_prefix.clone(),
+                },
+                WriteOperation::Put { key, value } => StorageWrite::Put {
+                    key: key.clone(),
+                    value_len: value.len(),
+                },
+            })
+            .collect()
+    }
+}
@@ -10,7 +10,9 @@ use linera_base::{
     identifiers::{BlobId, ChainId, ChannelFullName, StreamId},
 };
 
-use crate::{ExecutionError, OutgoingMessage};
+use crate::{
+    profile::Profiler, ExecutionError, ExecutionTrace, OutgoingMessage, ResourceProfile, TraceStep,
+};
 
 /// Tracks oracle responses and execution outcomes of an ongoing transaction execution, as well
 /// as replayed oracle responses.
@@ -38,6 +40,12 @@ pub struct TransactionTracker {
     unsubscribe: Vec<(ChannelFullName, ChainId)>,
     /// Operation result.
     operation_result: Option<Vec<u8>>,
+    /// The steps of the execution, if they are recorded.
+    #[debug(skip_if = Option::is_none)]
+    trace: Option<Vec<TraceStep>>,
+    /// The profile of the resources used by applications, if it is recorded.
+    #[debug(skip_if = Option::is_none)]
+    profiler: Option<Profiler>,
 }
 
 /// The [`TransactionTracker`] contents after a transaction has finished.
@@ -59,6 +67,12 @@ pub struct TransactionOutcome {
     pub unsubscribe: Vec<(ChannelFullName, ChainId)>,
     /// Operation result.
     pub operation_result: Vec<u8>,
+    /// The trace of the execution, if it was recorded.
+    #[debug(skip_if = Option::is_none)]
+    pub trace: Option<ExecutionTrace>,
+    /// The profile of the resources used by applications, if it was recorded.
+    #[debug(skip_if = Option::is_none)]
+    pub profile: Option<ResourceProfile>,
 }
 
 impl TransactionTracker {
@@ -84,6 +98,49 @@ impl TransactionTracker {
         self
     }
 
+    /// Records a trace of the execution.
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    /// Records a profile of the resources used by applications.
+    pub fn with_profile(mut self) -> Self {
+        self.profiler = Some(Profiler::default());
+        self
+    }
+
+    /// Returns whether the execution is profiled.
+    pub(crate) fn is_profiled(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    /// Returns the profiler, if the execution is profiled.
+    pub(crate) fn profiler(&mut self) -> Option<&mut Profiler> {
+        self.profiler.as_mut()
+    }
+
+    /// Records a step of the execution, if the execution is traced. Consecutive fuel
+    /// consumptions of the same application are recorded as a single step.
+    pub fn trace(&mut self, step: impl FnOnce() -> TraceStep) {
+        let Some(trace) = &mut self.trace else {
+            return;
+        };
+        match (trace.last_mut(), step()) {
+            (
+                Some(TraceStep::Fuel {
+                    application_id,
+                    fuel,
+                }),
+                TraceStep::Fuel {
+                    application_id: new_application_id,
+                    fuel: new_fuel,
+                },
+            ) if *application_id == new_application_id => *fuel = fuel.saturating_add(new_fuel),
+            (_, step) => trace.push(step),
+        }
+    }
+
     pub fn local_time(&self) -> Timestamp {
         self.local_time
     }
@@ -153,6 +210,7 @@ impl TransactionTracker {
     }
 
     pub fn add_oracle_response(&mut self, oracle_response: OracleResponse) {
+        self.trace(|| TraceStep::OracleResponse(oracle_response.clone()));
         self.oracle_responses.push(oracle_response);
     }
 
@@ -204,7 +262,7 @@ impl TransactionTracker {
             oracle_responses,
             outgoing_messages,
             local_time: _,
-            transaction_index: _,
+            transaction_index,
             next_message_index,
             next_application_index,
             events,
@@ -212,6 +270,8 @@ impl TransactionTracker {
             subscribe,
             unsubscribe,
             operation_result,
+            trace,
+            profiler,
         } = self;
         if let Some(mut responses) = replaying_oracle_responses {
             ensure!(
@@ -229,6 +289,11 @@ impl TransactionTracker {
             subscribe,
             unsubscribe,
             operation_result: operation_result.unwrap_or_default(),
+            trace: trace.map(|steps| ExecutionTrace {
+                transaction_index,
+                steps,
+            }),
+            profile: profiler.map(Profiler::into_profile),
         })
     }
 }
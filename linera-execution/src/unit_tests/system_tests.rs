@@ -145,3 +145,120 @@ async fn empty_accounts_are_removed() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that only due timers can be fired, and that they send their payload to their
+/// application on the current chain.
+#[tokio::test]
+async fn fire_timers() -> anyhow::Result<()> {
+    let (mut view, context) = new_view_and_context().await;
+    let application_id = ApplicationId::default();
+    let due = Timer {
+        application_id,
+        deadline: TimerDeadline::BlockHeight(context.height),
+        payload: b"due".to_vec(),
+    };
+    let not_due = Timer {
+        application_id,
+        deadline: TimerDeadline::Timestamp(Timestamp::from(1_000)),
+        payload: b"not due".to_vec(),
+    };
+    view.system.add_timer(0, due).await?;
+    view.system.add_timer(1, not_due).await?;
+
+    let mut txn_tracker = TransactionTracker::default();
+    view.system
+        .execute_operation(
+            context,
+            SystemOperation::FireTimers(vec![0]),
+            &mut txn_tracker,
+            &mut ResourceController::default(),
+        )
+        .await?;
+    assert_eq!(view.system.timers.indices().await?, vec![1]);
+    assert!(view.system.height_timers.indices().await?.is_empty());
+    let outcome = txn_tracker.into_outcome()?;
+    assert_eq!(
+        outcome.outgoing_messages,
+        vec![OutgoingMessage::new(
+            context.chain_id,
+            Message::User {
+                application_id,
+                bytes: b"due".to_vec(),
+            }
+        )]
+    );
+
+    for (timer_id, expected_error) in [
+        (1, ExecutionError::TimerNotDue(1)),
+        (0, ExecutionError::TimerNotFound(0)),
+    ] {
+        let result = view
+            .system
+            .execute_operation(
+                context,
+                SystemOperation::FireTimers(vec![timer_id]),
+                &mut TransactionTracker::default(),
+                &mut ResourceController::default(),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().to_string(), expected_error.to_string());
+    }
+
+    Ok(())
+}
+
+/// Tests that the timers are indexed by deadline, and that only the due ones are returned.
+#[tokio::test]
+async fn due_timers() -> anyhow::Result<()> {
+    let (mut view, _context) = new_view_and_context().await;
+    let deadlines = [
+        TimerDeadline::Timestamp(Timestamp::from(3_000)),
+        TimerDeadline::BlockHeight(BlockHeight(9)),
+        TimerDeadline::Timestamp(Timestamp::from(1_000)),
+        TimerDeadline::BlockHeight(BlockHeight(5)),
+        TimerDeadline::Timestamp(Timestamp::from(2_000)),
+        TimerDeadline::Timestamp(Timestamp::from(1_000)),
+    ];
+    for (timer_id, deadline) in (0..).zip(deadlines) {
+        let timer = Timer {
+            application_id: ApplicationId::default(),
+            deadline,
+            payload: vec![],
+        };
+        view.system.add_timer(timer_id, timer).await?;
+    }
+
+    let system = &mut view.system;
+    assert_eq!(
+        system.next_timer_timestamp().await?,
+        Some(Timestamp::from(1_000))
+    );
+    assert_eq!(
+        system
+            .due_timer_ids(BlockHeight(7), Timestamp::from(2_000))
+            .await?,
+        vec![2, 3, 4, 5]
+    );
+    assert!(system
+        .due_timer_ids(BlockHeight(4), Timestamp::from(999))
+        .await?
+        .is_empty());
+
+    for timer_id in [2, 5] {
+        assert!(system.remove_timer(timer_id).await?.is_some());
+    }
+    assert!(system.remove_timer(2).await?.is_none());
+    assert_eq!(system.timestamp_timers.indices().await?, vec![2_000, 3_000]);
+    assert_eq!(
+        system.next_timer_timestamp().await?,
+        Some(Timestamp::from(2_000))
+    );
+    assert_eq!(
+        system
+            .due_timer_ids(BlockHeight(9), Timestamp::from(3_000))
+            .await?,
+        vec![0, 1, 3, 4]
+    );
+
+    Ok(())
+}
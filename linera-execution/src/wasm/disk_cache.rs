@@ -0,0 +1,377 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An optional on-disk cache of compiled WebAssembly modules.
+//!
+//! Compiling a module is slow, so that the in-memory [`ModuleCache`][super::module_cache] is
+//! empty and every module must be compiled again when a validator restarts. When enabled, the
+//! disk cache keeps the artifacts produced by the runtimes, indexed by the hash of the bytecode
+//! and by everything else that determines the compiled code: the runtime, the version and
+//! configuration of its engine, and the fuel metering rules. Each artifact is stored with its
+//! hash, and discarded if it does not match. The least recently used artifacts are removed when
+//! the total size of the cache exceeds its maximum.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::SystemTime,
+};
+
+use linera_base::{
+    crypto::{BcsHashable, CryptoHash},
+    data_types::Bytecode,
+};
+use serde::{Deserialize, Serialize};
+
+use super::METERING_VERSION;
+
+/// The extension of the files storing artifacts.
+const ARTIFACT_EXTENSION: &str = "module";
+
+/// The default maximum total size of the artifacts on disk.
+pub const DEFAULT_MAX_DISK_CACHE_SIZE: u64 = 1024 /* MiB */ * 1024 /* KiB */ * 1024 /* bytes */;
+
+/// The disk cache, if enabled.
+static DISK_CACHE: OnceLock<ModuleDiskCache> = OnceLock::new();
+
+/// The number of artifact files written by this process, to name their temporary files.
+static TEMPORARY_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// The configuration of the on-disk cache of compiled modules.
+#[derive(Clone, Debug)]
+pub struct ModuleDiskCacheConfig {
+    /// The directory storing the artifacts.
+    pub directory: PathBuf,
+    /// The maximum total size of the artifacts, in bytes.
+    pub max_size: u64,
+}
+
+/// Enables the on-disk cache of compiled modules for the rest of the process. It can only be
+/// enabled once.
+pub fn enable_module_disk_cache(config: ModuleDiskCacheConfig) -> io::Result<()> {
+    fs::create_dir_all(&config.directory)?;
+    let cache = ModuleDiskCache {
+        directory: config.directory,
+        max_size: config.max_size,
+        eviction: Mutex::new(()),
+    };
+    DISK_CACHE.set(cache).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the module disk cache is already enabled",
+        )
+    })
+}
+
+/// Whether a module is a contract or a service.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) enum ModuleKind {
+    Contract,
+    Service,
+}
+
+/// Everything that determines the artifact compiled from a bytecode.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ArtifactKey {
+    /// The hash of the bytecode.
+    bytecode: CryptoHash,
+    /// Whether the module is a contract or a service.
+    kind: ModuleKind,
+    /// The name of the runtime.
+    runtime: &'static str,
+    /// The version and configuration of the engine compiling the module.
+    engine: String,
+    /// The version of the fuel metering rules, if the bytecode is metered.
+    metering: Option<u32>,
+}
+
+impl BcsHashable<'_> for ArtifactKey {}
+
+impl ArtifactKey {
+    /// Creates the key of the artifact compiled from `bytecode` by the given runtime and
+    /// engine. Contract bytecodes are metered.
+    pub fn new(
+        bytecode: &Bytecode,
+        kind: ModuleKind,
+        runtime: &'static str,
+        engine: String,
+    ) -> Self {
+        let metering = match kind {
+            ModuleKind::Contract => Some(METERING_VERSION),
+            ModuleKind::Service => None,
+        };
+        ArtifactKey {
+            bytecode: CryptoHash::new(&HashedBytes(bytecode.as_ref())),
+            kind,
+            runtime,
+            engine,
+            metering,
+        }
+    }
+}
+
+/// A [`Hasher`][std::hash::Hasher] for the engine configurations that only implement
+/// [`Hash`][std::hash::Hash]. Unlike the hashers of the standard library, its result does not
+/// change with the Rust version.
+#[cfg(with_wasmtime)]
+#[derive(Default)]
+pub(crate) struct StableHasher(Vec<u8>);
+
+#[cfg(with_wasmtime)]
+impl StableHasher {
+    /// Returns the hash of the bytes written so far.
+    pub fn digest(&self) -> CryptoHash {
+        CryptoHash::new(&HashedBytes(&self.0))
+    }
+}
+
+#[cfg(with_wasmtime)]
+impl std::hash::Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.digest();
+        u64::from_le_bytes(digest.as_bytes()[..8].try_into().unwrap())
+    }
+}
+
+/// Bytes to hash.
+#[derive(Serialize, Deserialize)]
+struct HashedBytes<'a>(#[serde(with = "serde_bytes")] &'a [u8]);
+
+impl<'a> BcsHashable<'a> for HashedBytes<'a> {}
+
+/// The content of an artifact file.
+#[derive(Serialize, Deserialize)]
+struct ArtifactFile {
+    /// The hash of the [`ArtifactKey`].
+    key: CryptoHash,
+    /// The hash of the artifact.
+    hash: CryptoHash,
+    /// The artifact produced by the runtime.
+    #[serde(with = "serde_bytes")]
+    artifact: Vec<u8>,
+}
+
+/// The on-disk cache of compiled modules.
+struct ModuleDiskCache {
+    directory: PathBuf,
+    max_size: u64,
+    /// Held while removing artifacts.
+    eviction: Mutex<()>,
+}
+
+/// Returns the module compiled from a bytecode, using the disk cache if it is enabled.
+///
+/// The artifact is loaded with `deserialize` if the cache has one for `key`. Otherwise, the
+/// module is built with `compile`, and its artifact produced by `serialize` is cached.
+/// Failures of the cache are logged, and fall back to compiling the module.
+pub(crate) fn load_or_compile<Module>(
+    key: impl FnOnce() -> ArtifactKey,
+    compile: impl FnOnce() -> anyhow::Result<Module>,
+    serialize: impl FnOnce(&Module) -> anyhow::Result<Vec<u8>>,
+    deserialize: impl FnOnce(&[u8]) -> anyhow::Result<Module>,
+) -> anyhow::Result<Module> {
+    let Some(cache) = DISK_CACHE.get() else {
+        return compile();
+    };
+    let key = CryptoHash::new(&key());
+    if let Some(artifact) = cache.load(&key) {
+        match deserialize(&artifact) {
+            Ok(module) => return Ok(module),
+            Err(error) => {
+                tracing::warn!(%key, %error, "Failed to load a cached Wasm module");
+                cache.remove(&key);
+            }
+        }
+    }
+    let module = compile()?;
+    match serialize(&module) {
+        Ok(artifact) => cache.store(&key, artifact),
+        Err(error) => tracing::warn!(%key, %error, "Failed to serialize a compiled Wasm module"),
+    }
+    Ok(module)
+}
+
+impl ModuleDiskCache {
+    /// Returns the path of the artifact with the given key.
+    fn path(&self, key: &CryptoHash) -> PathBuf {
+        self.directory
+            .join(format!("{key}"))
+            .with_extension(ARTIFACT_EXTENSION)
+    }
+
+    /// Returns the stored artifact with the given key, if it is intact.
+    fn load(&self, key: &CryptoHash) -> Option<Vec<u8>> {
+        let path = self.path(key);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return None,
+            Err(error) => {
+                tracing::warn!(%key, %error, "Failed to read a cached Wasm module");
+                return None;
+            }
+        };
+        let file = bcs::from_bytes::<ArtifactFile>(&bytes).ok().filter(|file| {
+            file.key == *key && file.hash == CryptoHash::new(&HashedBytes(&file.artifact))
+        });
+        let Some(file) = file else {
+            tracing::warn!(%key, "Removing a corrupted cached Wasm module");
+            self.remove(key);
+            return None;
+        };
+        // The modification time orders the artifacts for eviction.
+        if let Err(error) = touch(&path) {
+            tracing::debug!(%key, %error, "Failed to update the time of a cached Wasm module");
+        }
+        Some(file.artifact)
+    }
+
+    /// Stores an artifact, then evicts the least recently used ones if the cache is too large.
+    fn store(&self, key: &CryptoHash, artifact: Vec<u8>) {
+        let file = ArtifactFile {
+            key: *key,
+            hash: CryptoHash::new(&HashedBytes(&artifact)),
+            artifact,
+        };
+        if let Err(error) = self.write(key, &file) {
+            tracing::warn!(%key, %error, "Failed to cache a compiled Wasm module");
+            return;
+        }
+        if let Err(error) = self.evict() {
+            tracing::warn!(%error, "Failed to evict cached Wasm modules");
+        }
+    }
+
+    /// Writes an artifact file, atomically.
+    fn write(&self, key: &CryptoHash, file: &ArtifactFile) -> anyhow::Result<()> {
+        let path = self.path(key);
+        // Threads of the same process may compile the same module at once.
+        let index = TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed);
+        let temporary_path = path.with_extension(format!("tmp-{}-{index}", std::process::id()));
+        fs::write(&temporary_path, bcs::to_bytes(file)?)?;
+        fs::rename(&temporary_path, &path)?;
+        Ok(())
+    }
+
+    /// Removes an artifact.
+    fn remove(&self, key: &CryptoHash) {
+        if let Err(error) = fs::remove_file(self.path(key)) {
+            if error.kind() != io::ErrorKind::NotFound {
+                tracing::warn!(%key, %error, "Failed to remove a cached Wasm module");
+            }
+        }
+    }
+
+    /// Removes the least recently used artifacts until their total size fits in the cache.
+    fn evict(&self) -> io::Result<()> {
+        let _guard = self.eviction.lock().unwrap();
+        let mut artifacts = Vec::new();
+        let mut total_size = 0;
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != ARTIFACT_EXTENSION)
+            {
+                continue;
+            }
+            // Another process may have removed the artifact in the meantime.
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            total_size += metadata.len();
+            artifacts.push((metadata.modified()?, metadata.len(), path));
+        }
+        artifacts.sort();
+        for (_, size, path) in artifacts {
+            if total_size <= self.max_size {
+                break;
+            }
+            match fs::remove_file(&path) {
+                Ok(()) => total_size -= size,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => total_size -= size,
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the modification time of a file to now.
+fn touch(path: &Path) -> io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(directory: &Path, max_size: u64) -> ModuleDiskCache {
+        ModuleDiskCache {
+            directory: directory.to_path_buf(),
+            max_size,
+            eviction: Mutex::new(()),
+        }
+    }
+
+    fn key(bytes: &[u8], engine: &str) -> CryptoHash {
+        let bytecode = Bytecode::new(bytes.to_vec());
+        let key = ArtifactKey::new(&bytecode, ModuleKind::Contract, "test", engine.to_string());
+        CryptoHash::new(&key)
+    }
+
+    #[test]
+    fn test_artifacts_are_keyed_and_checked() {
+        let directory = tempfile::tempdir().unwrap();
+        let cache = cache(directory.path(), DEFAULT_MAX_DISK_CACHE_SIZE);
+        let key = key(b"bytecode", "engine");
+        assert_ne!(key, self::key(b"bytecode", "other engine"));
+        assert_eq!(cache.load(&key), None);
+        cache.store(&key, b"artifact".to_vec());
+        assert_eq!(cache.load(&key), Some(b"artifact".to_vec()));
+
+        // A corrupted artifact is discarded.
+        let path = cache.path(&key);
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&path, bytes).unwrap();
+        assert_eq!(cache.load(&key), None);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_least_recently_used_artifacts_are_evicted() {
+        let directory = tempfile::tempdir().unwrap();
+        let cache = cache(directory.path(), 250);
+        let keys = [
+            key(b"a", "engine"),
+            key(b"b", "engine"),
+            key(b"c", "engine"),
+        ];
+        for key in &keys[..2] {
+            cache.store(key, vec![0; 100]);
+        }
+        // Make the first artifact the most recently used one.
+        let past = SystemTime::now() - std::time::Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(cache.path(&keys[1]))
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        cache.store(&keys[2], vec![0; 100]);
+        assert!(cache.load(&keys[0]).is_some());
+        assert!(cache.load(&keys[1]).is_none());
+        assert!(cache.load(&keys[2]).is_some());
+    }
+}
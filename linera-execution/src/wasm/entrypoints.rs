@@ -9,6 +9,7 @@ use linera_witty::wit_import;
 #[wit_import(package = "linera:app")]
 pub trait ContractEntrypoints {
     fn instantiate(argument: Vec<u8>);
+    fn migrate();
     fn execute_operation(operation: Vec<u8>) -> Vec<u8>;
     fn execute_message(message: Vec<u8>);
     fn finalize();
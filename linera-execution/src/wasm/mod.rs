@@ -10,6 +10,8 @@
 
 #![cfg(with_wasm_runtime)]
 
+#[cfg(not(web))]
+mod disk_cache;
 mod entrypoints;
 mod module_cache;
 #[macro_use]
@@ -19,9 +21,19 @@ mod wasmer;
 #[cfg(with_wasmtime)]
 mod wasmtime;
 
+use std::sync::{Arc, OnceLock};
+
 use linera_base::data_types::Bytecode;
 use thiserror::Error;
-use wasm_instrument::{gas_metering, parity_wasm};
+use wasm_instrument::{
+    gas_metering,
+    parity_wasm::{
+        self,
+        elements::{
+            External, FunctionType, ImportCountType, Instruction, Internal, Module, Type, ValueType,
+        },
+    },
+};
 #[cfg(with_wasmer)]
 use wasmer::{WasmerContractInstance, WasmerServiceInstance};
 #[cfg(with_wasmtime)]
@@ -35,6 +47,10 @@ use {
     std::sync::LazyLock,
 };
 
+#[cfg(not(web))]
+pub use self::disk_cache::{
+    enable_module_disk_cache, ModuleDiskCacheConfig, DEFAULT_MAX_DISK_CACHE_SIZE,
+};
 pub use self::{
     entrypoints::{ContractEntrypoints, ServiceEntrypoints},
     runtime_api::{BaseRuntimeApi, ContractRuntimeApi, RuntimeApiData, ServiceRuntimeApi},
@@ -71,9 +87,13 @@ pub enum WasmContractModule {
     Wasmer {
         engine: ::wasmer::Engine,
         module: ::wasmer::Module,
+        profiled: Arc<ProfiledModule<(::wasmer::Engine, ::wasmer::Module)>>,
     },
     #[cfg(with_wasmtime)]
-    Wasmtime { module: ::wasmtime::Module },
+    Wasmtime {
+        module: ::wasmtime::Module,
+        profiled: Arc<ProfiledModule<::wasmtime::Module>>,
+    },
 }
 
 impl WasmContractModule {
@@ -82,12 +102,13 @@ impl WasmContractModule {
         contract_bytecode: Bytecode,
         runtime: WasmRuntime,
     ) -> Result<Self, WasmExecutionError> {
+        let profiled = Arc::new(ProfiledModule::new(contract_bytecode.clone()));
         let contract_bytecode = add_metering(contract_bytecode)?;
         match runtime {
             #[cfg(with_wasmer)]
-            WasmRuntime::Wasmer => Self::from_wasmer(contract_bytecode).await,
+            WasmRuntime::Wasmer => Self::from_wasmer(contract_bytecode, profiled).await,
             #[cfg(with_wasmtime)]
-            WasmRuntime::Wasmtime => Self::from_wasmtime(contract_bytecode).await,
+            WasmRuntime::Wasmtime => Self::from_wasmtime(contract_bytecode, profiled).await,
         }
     }
 
@@ -108,29 +129,131 @@ impl WasmContractModule {
     }
 }
 
-impl UserContractModule for WasmContractModule {
-    fn instantiate(
+impl WasmContractModule {
+    /// Prepares an instance of the contract, using its [`ProfiledModule`] if the execution is
+    /// `profiled` and the module can be profiled.
+    fn prepare(
         &self,
         runtime: ContractSyncRuntimeHandle,
+        profiled: bool,
     ) -> Result<UserContractInstance, ExecutionError> {
         #[cfg(with_metrics)]
         let _instantiation_latency = CONTRACT_INSTANTIATION_LATENCY.measure_latency();
 
         let instance: UserContractInstance = match self {
             #[cfg(with_wasmtime)]
-            WasmContractModule::Wasmtime { module } => {
-                Box::new(WasmtimeContractInstance::prepare(module, runtime)?)
+            WasmContractModule::Wasmtime {
+                module,
+                profiled: profiled_module,
+            } => {
+                let profiled_module = if profiled {
+                    profiled_module.get_or_compile(Self::compile_profiled_with_wasmtime)?
+                } else {
+                    None
+                };
+                let (module, function_names) = match profiled_module {
+                    Some((module, function_names)) => (module, function_names.clone()),
+                    None => (module, Arc::default()),
+                };
+                Box::new(WasmtimeContractInstance::prepare(
+                    module,
+                    function_names,
+                    runtime,
+                )?)
             }
             #[cfg(with_wasmer)]
-            WasmContractModule::Wasmer { engine, module } => Box::new(
-                WasmerContractInstance::prepare(engine.clone(), module, runtime)?,
-            ),
+            WasmContractModule::Wasmer {
+                engine,
+                module,
+                profiled: profiled_module,
+            } => {
+                let profiled_module = if profiled {
+                    profiled_module.get_or_compile(Self::compile_profiled_with_wasmer)?
+                } else {
+                    None
+                };
+                let (engine, module, function_names) = match profiled_module {
+                    Some(((engine, module), function_names)) => {
+                        (engine, module, function_names.clone())
+                    }
+                    None => (engine, module, Arc::default()),
+                };
+                Box::new(WasmerContractInstance::prepare(
+                    engine.clone(),
+                    module,
+                    function_names,
+                    runtime,
+                )?)
+            }
         };
 
         Ok(instance)
     }
 }
 
+impl UserContractModule for WasmContractModule {
+    fn instantiate(
+        &self,
+        runtime: ContractSyncRuntimeHandle,
+    ) -> Result<UserContractInstance, ExecutionError> {
+        self.prepare(runtime, false)
+    }
+
+    fn instantiate_profiled(
+        &self,
+        runtime: ContractSyncRuntimeHandle,
+    ) -> Result<UserContractInstance, ExecutionError> {
+        self.prepare(runtime, true)
+    }
+}
+
+/// The variant of a contract module whose metering reports the functions that consume the
+/// fuel, so that the fuel can be attributed to them. It is only compiled the first time that
+/// the contract is executed with profiling.
+pub struct ProfiledModule<Module> {
+    /// The bytecode of the contract before metering, if it is known.
+    bytecode: Option<Bytecode>,
+    /// The compiled module, and the names of its functions by position in its code section.
+    compiled: OnceLock<(Module, Arc<[String]>)>,
+}
+
+impl<Module> Default for ProfiledModule<Module> {
+    fn default() -> Self {
+        ProfiledModule {
+            bytecode: None,
+            compiled: OnceLock::new(),
+        }
+    }
+}
+
+impl<Module> ProfiledModule<Module> {
+    /// Creates a [`ProfiledModule`] to compile from the contract's `bytecode` when needed.
+    fn new(bytecode: Bytecode) -> Self {
+        ProfiledModule {
+            bytecode: Some(bytecode),
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// Returns the compiled module and the names of its functions, instrumenting the bytecode
+    /// and compiling it with `compile` the first time. Returns `None` if the bytecode is
+    /// unknown.
+    fn get_or_compile(
+        &self,
+        compile: impl FnOnce(Bytecode) -> Result<Module, WasmExecutionError>,
+    ) -> Result<Option<&(Module, Arc<[String]>)>, WasmExecutionError> {
+        if let Some(compiled) = self.compiled.get() {
+            return Ok(Some(compiled));
+        }
+        let Some(bytecode) = &self.bytecode else {
+            return Ok(None);
+        };
+        let (bytecode, function_names) = add_profiled_metering(bytecode.clone())?;
+        let module = compile(bytecode)?;
+        Ok(Some(self.compiled.get_or_init(|| (module, function_names))))
+    }
+}
+
 /// A user service in a compiled WebAssembly module.
 #[derive(Clone)]
 pub enum WasmServiceModule {
@@ -194,8 +317,44 @@ impl UserServiceModule for WasmServiceModule {
     }
 }
 
+/// The version of the fuel metering rules applied by [`add_metering`]. It must be increased
+/// whenever the rules change, so that modules compiled with older rules are not reused.
+pub(crate) const METERING_VERSION: u32 = 1;
+
+/// The host function called by the metering of [`add_profiled_metering`], with the position of
+/// the metered function in the code section and the fuel that it consumes.
+const PROFILED_METERING_FUNCTION: &str = "consume-fuel-in";
+
 /// Instrument the [`Bytecode`] to add fuel metering.
 pub fn add_metering(bytecode: Bytecode) -> Result<Bytecode, WasmExecutionError> {
+    let instrumented_module = inject_metering(parity_wasm::deserialize_buffer(&bytecode.bytes)?)?;
+
+    Ok(Bytecode::new(instrumented_module.into_bytes()?))
+}
+
+/// Instrument the [`Bytecode`] to add fuel metering that reports the function consuming the
+/// fuel, with its position in the code section, so that the fuel can be attributed to it when
+/// the execution is profiled. Returns the instrumented bytecode and the names of the functions,
+/// by position.
+fn add_profiled_metering(
+    bytecode: Bytecode,
+) -> Result<(Bytecode, Arc<[String]>), WasmExecutionError> {
+    let module = parity_wasm::deserialize_buffer::<Module>(&bytecode.bytes)?;
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+    let function_names = function_names(&module);
+    // The metering function is imported after the functions that the module imports.
+    let metering_function = module.import_count(ImportCountType::Function) as u32;
+    let mut instrumented_module = inject_metering(module)?;
+    report_metered_functions(&mut instrumented_module, metering_function)?;
+
+    Ok((
+        Bytecode::new(instrumented_module.into_bytes()?),
+        function_names,
+    ))
+}
+
+/// Injects the fuel metering into a `module`, calling the `consume-fuel` host function.
+fn inject_metering(module: Module) -> Result<Module, WasmExecutionError> {
     struct WasmtimeRules;
 
     impl gas_metering::Rules for WasmtimeRules {
@@ -224,17 +383,96 @@ pub fn add_metering(bytecode: Bytecode) -> Result<Bytecode, WasmExecutionError>
         }
     }
 
-    let instrumented_module = gas_metering::inject(
-        parity_wasm::deserialize_buffer(&bytecode.bytes)?,
+    gas_metering::inject(
+        module,
         gas_metering::host_function::Injector::new(
             "linera:app/contract-runtime-api",
             "consume-fuel",
         ),
         &WasmtimeRules,
     )
-    .map_err(|_| WasmExecutionError::InstrumentModule)?;
+    .map_err(|_| WasmExecutionError::InstrumentModule)
+}
 
-    Ok(Bytecode::new(instrumented_module.into_bytes()?))
+/// Returns the names of the functions defined by a module, by position in its code section:
+/// their export name if they are exported, otherwise their debug name if the module has one.
+fn function_names(module: &Module) -> Arc<[String]> {
+    let imported_functions = module.import_count(ImportCountType::Function) as u32;
+    let defined_functions = module
+        .function_section()
+        .map_or(0, |section| section.entries().len()) as u32;
+    let debug_names = module
+        .names_section()
+        .and_then(|section| section.functions())
+        .map(|subsection| subsection.names());
+    (imported_functions..imported_functions + defined_functions)
+        .map(|index| {
+            let export = module.export_section().and_then(|section| {
+                section
+                    .entries()
+                    .iter()
+                    .find(|entry| *entry.internal() == Internal::Function(index))
+            });
+            if let Some(export) = export {
+                export.field().to_owned()
+            } else if let Some(name) = debug_names.and_then(|names| names.get(index)) {
+                name.clone()
+            } else {
+                format!("function[{index}]")
+            }
+        })
+        .collect()
+}
+
+/// Replaces the `consume-fuel` function imported by the metering with the
+/// [`PROFILED_METERING_FUNCTION`], and passes it the position of the metered function before
+/// the fuel.
+fn report_metered_functions(
+    module: &mut Module,
+    metering_function: u32,
+) -> Result<(), WasmExecutionError> {
+    let types = module
+        .type_section_mut()
+        .ok_or(WasmExecutionError::InstrumentModule)?
+        .types_mut();
+    types.push(Type::Function(FunctionType::new(
+        vec![ValueType::I32, ValueType::I64],
+        vec![],
+    )));
+    let signature = (types.len() - 1) as u32;
+
+    let import = module
+        .import_section_mut()
+        .ok_or(WasmExecutionError::InstrumentModule)?
+        .entries_mut()
+        .iter_mut()
+        .filter(|entry| matches!(entry.external(), External::Function(_)))
+        .nth(metering_function as usize)
+        .ok_or(WasmExecutionError::InstrumentModule)?;
+    *import.field_mut() = PROFILED_METERING_FUNCTION.to_owned();
+    *import.external_mut() = External::Function(signature);
+
+    let Some(code) = module.code_section_mut() else {
+        return Ok(());
+    };
+    for (position, body) in code.bodies_mut().iter_mut().enumerate() {
+        let instructions = body.code_mut().elements_mut();
+        let mut metered = Vec::with_capacity(instructions.len());
+        for instruction in instructions.drain(..) {
+            if instruction == Instruction::Call(metering_function) {
+                // The metering pushes the fuel just before the call.
+                let fuel = metered.pop();
+                if !matches!(fuel, Some(Instruction::I64Const(_))) {
+                    return Err(WasmExecutionError::InstrumentModule);
+                }
+                metered.push(Instruction::I32Const(position as i32));
+                metered.extend(fuel);
+            }
+            metered.push(instruction);
+        }
+        *instructions = metered;
+    }
+    Ok(())
 }
 
 #[cfg(web)]
@@ -278,6 +516,7 @@ const _: () = {
                     Ok(Self::Wasmer {
                         module: value.try_into()?,
                         engine: Default::default(),
+                        profiled: Arc::default(),
                     })
                 } else {
                     Err(value)
@@ -290,7 +529,7 @@ const _: () = {
         fn from(module: WasmContractModule) -> JsValue {
             match module {
                 #[cfg(with_wasmer)]
-                WasmContractModule::Wasmer { module, engine: _ } => {
+                WasmContractModule::Wasmer { module, .. } => {
                     ::wasmer::Module::clone(&module).into()
                 }
             }
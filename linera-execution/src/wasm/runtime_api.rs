@@ -1,11 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{any::Any, collections::HashMap, marker::PhantomData};
+use std::{any::Any, collections::HashMap, marker::PhantomData, sync::Arc};
 
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Amount, ApplicationPermissions, BlockHeight, SendMessageRequest, Timestamp},
+    data_types::{
+        Amount, ApplicationPermissions, BlockHeight, SendMessageRequest, TimerDeadline, Timestamp,
+    },
     http,
     identifiers::{
         Account, AccountOwner, ApplicationId, ChainId, ChannelName, MessageId, StreamName,
@@ -24,6 +26,8 @@ pub struct RuntimeApiData<Runtime> {
     runtime: Runtime,
     active_promises: HashMap<u32, Box<dyn Any + Send + Sync>>,
     promise_counter: u32,
+    /// The names of the functions of the module, by position in its code section.
+    function_names: Arc<[String]>,
 }
 
 impl<Runtime> RuntimeApiData<Runtime> {
@@ -33,9 +37,17 @@ impl<Runtime> RuntimeApiData<Runtime> {
             runtime,
             active_promises: HashMap::new(),
             promise_counter: 0,
+            function_names: Arc::default(),
         }
     }
 
+    /// Returns the [`RuntimeApiData`] with the names of the functions of the module, by
+    /// position in its code section, to which the fuel they consume is attributed.
+    pub fn with_function_names(mut self, function_names: Arc<[String]>) -> Self {
+        self.function_names = function_names;
+        self
+    }
+
     /// Returns a mutable reference the system API `Runtime`.
     pub fn runtime_mut(&mut self) -> &mut Runtime {
         &mut self.runtime
@@ -625,6 +637,29 @@ where
             .map_err(|error| RuntimeError::Custom(error.into()))
     }
 
+    /// Sets a timer that sends `payload` as a message to this application once the `deadline`
+    /// is reached, and returns the ID of the timer.
+    fn set_timer(
+        caller: &mut Caller,
+        deadline: TimerDeadline,
+        payload: Vec<u8>,
+    ) -> Result<u64, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .set_timer(deadline, payload)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
+    /// Cancels a timer set by this application, and returns whether it had not fired yet.
+    fn cancel_timer(caller: &mut Caller, timer_id: u64) -> Result<bool, RuntimeError> {
+        caller
+            .user_data_mut()
+            .runtime
+            .cancel_timer(timer_id)
+            .map_err(|error| RuntimeError::Custom(error.into()))
+    }
+
     /// Queries a service and returns the response.
     fn query_service(
         caller: &mut Caller,
@@ -650,6 +685,25 @@ where
             .map_err(|e| RuntimeError::Custom(e.into()))
     }
 
+    /// Consumes some fuel in the function at the given position of the code section.
+    ///
+    /// This is called instead of `consume_fuel` by the metering instrumentation of each
+    /// function when the execution is profiled.
+    fn consume_fuel_in(
+        caller: &mut Caller,
+        function_index: u32,
+        fuel: u64,
+    ) -> Result<(), RuntimeError> {
+        let data = caller.user_data_mut();
+        let name = data
+            .function_names
+            .get(function_index as usize)
+            .map_or("unknown", String::as_str);
+        data.runtime
+            .consume_fuel_in(name, fuel)
+            .map_err(|e| RuntimeError::Custom(e.into()))
+    }
+
     /// Returns the round in which this block was validated.
     fn validation_round(caller: &mut Caller) -> Result<Option<u32>, RuntimeError> {
         caller
@@ -3,7 +3,10 @@
 
 //! Code specific to the usage of the [Wasmer](https://wasmer.io/) runtime.
 
-use std::{marker::Unpin, sync::LazyLock};
+use std::{
+    marker::Unpin,
+    sync::{Arc, LazyLock},
+};
 
 use linera_base::data_types::Bytecode;
 use linera_witty::{
@@ -11,11 +14,15 @@ use linera_witty::{
     ExportTo,
 };
 use tokio::sync::Mutex;
+#[cfg(not(web))]
+use wasmer::sys::NativeEngineExt as _;
 
+#[cfg(not(web))]
+use super::disk_cache::{self, ArtifactKey, ModuleKind};
 use super::{
     module_cache::ModuleCache,
     runtime_api::{BaseRuntimeApi, ContractRuntimeApi, RuntimeApiData, ServiceRuntimeApi},
-    ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
+    ContractEntrypoints, ProfiledModule, ServiceEntrypoints, WasmExecutionError,
 };
 use crate::{
     wasm::{WasmContractModule, WasmServiceModule},
@@ -56,15 +63,33 @@ pub struct WasmerServiceInstance<Runtime> {
 }
 
 impl WasmContractModule {
-    /// Creates a new [`WasmContractModule`] using Wasmer with the provided bytecode files.
-    pub async fn from_wasmer(contract_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
+    /// Creates a new [`WasmContractModule`] using Wasmer with the provided bytecode files, and
+    /// the variant of the module used for profiling.
+    pub async fn from_wasmer(
+        contract_bytecode: Bytecode,
+        profiled: Arc<ProfiledModule<(wasmer::Engine, wasmer::Module)>>,
+    ) -> Result<Self, WasmExecutionError> {
         let mut contract_cache = CONTRACT_CACHE.lock().await;
         let (engine, module) = contract_cache
             .get_or_insert_with(contract_bytecode, CachedContractModule::new)
             .map_err(WasmExecutionError::LoadContractModule)?
             .create_execution_instance()
             .map_err(WasmExecutionError::LoadContractModule)?;
-        Ok(WasmContractModule::Wasmer { engine, module })
+        Ok(WasmContractModule::Wasmer {
+            engine,
+            module,
+            profiled,
+        })
+    }
+
+    /// Compiles the profiled variant of a contract module with Wasmer. It is not kept in the
+    /// in-memory cache of contract modules.
+    pub(crate) fn compile_profiled_with_wasmer(
+        contract_bytecode: Bytecode,
+    ) -> Result<(wasmer::Engine, wasmer::Module), WasmExecutionError> {
+        CachedContractModule::new(contract_bytecode)
+            .and_then(|module| module.create_execution_instance())
+            .map_err(WasmExecutionError::LoadContractModule)
     }
 }
 
@@ -76,9 +101,10 @@ where
     pub fn prepare(
         contract_engine: wasmer::Engine,
         contract_module: &wasmer::Module,
+        function_names: Arc<[String]>,
         runtime: Runtime,
     ) -> Result<Self, WasmExecutionError> {
-        let system_api_data = RuntimeApiData::new(runtime);
+        let system_api_data = RuntimeApiData::new(runtime).with_function_names(function_names);
         let mut instance_builder = InstanceBuilder::new(contract_engine, system_api_data);
 
         BaseRuntimeApi::export_to(&mut instance_builder)?;
@@ -96,6 +122,12 @@ impl WasmServiceModule {
         let mut service_cache = SERVICE_CACHE.lock().await;
         let module = service_cache
             .get_or_insert_with(service_bytecode, |bytecode| {
+                #[cfg(not(web))]
+                {
+                    compile_module(&SERVICE_ENGINE, &bytecode, ModuleKind::Service, "cranelift")
+                }
+
+                #[cfg(web)]
                 wasmer::Module::new(&*SERVICE_ENGINE, bytecode).map_err(anyhow::Error::from)
             })
             .map_err(WasmExecutionError::LoadServiceModule)?;
@@ -139,6 +171,13 @@ where
         Ok(())
     }
 
+    fn migrate(&mut self, _context: OperationContext) -> Result<(), ExecutionError> {
+        ContractEntrypoints::new(&mut self.instance)
+            .migrate()
+            .map_err(WasmExecutionError::from)?;
+        Ok(())
+    }
+
     fn execute_operation(
         &mut self,
         _context: OperationContext,
@@ -204,7 +243,19 @@ pub struct CachedContractModule(wasmer::Module);
 impl CachedContractModule {
     /// Creates a new [`CachedContractModule`] by compiling a `contract_bytecode`.
     pub fn new(contract_bytecode: Bytecode) -> Result<Self, anyhow::Error> {
-        let module = wasmer::Module::new(&Self::create_compilation_engine(), contract_bytecode)?;
+        let engine = Self::create_compilation_engine();
+
+        #[cfg(not(web))]
+        let module = compile_module(
+            &engine,
+            &contract_bytecode,
+            ModuleKind::Contract,
+            "singlepass with canonicalized NaNs",
+        )?;
+
+        #[cfg(web)]
+        let module = wasmer::Module::new(&engine, contract_bytecode)?;
+
         Ok(CachedContractModule(module))
     }
 
@@ -241,3 +292,32 @@ impl CachedContractModule {
         }
     }
 }
+
+/// Compiles a module with `engine`, or loads it from the disk cache if it is enabled.
+#[cfg(not(web))]
+fn compile_module(
+    engine: &wasmer::Engine,
+    bytecode: &Bytecode,
+    kind: ModuleKind,
+    compiler: &str,
+) -> Result<wasmer::Module, anyhow::Error> {
+    disk_cache::load_or_compile(
+        || {
+            // The compiler settings are not part of the engine's identifier.
+            let target = engine.target();
+            let engine = format!(
+                "{} {} {} {:?} {compiler}",
+                wasmer::VERSION,
+                engine.deterministic_id(),
+                target.triple(),
+                target.cpu_features(),
+            );
+            ArtifactKey::new(bytecode, kind, "wasmer", engine)
+        },
+        || Ok(wasmer::Module::new(engine, bytecode)?),
+        |module| Ok(module.serialize()?.to_vec()),
+        // SAFETY: The artifact was serialized by the same version of Wasmer, and the disk
+        // cache checked its integrity.
+        |artifact| Ok(unsafe { wasmer::Module::deserialize(engine, artifact.to_vec()) }?),
+    )
+}
@@ -3,7 +3,10 @@
 
 //! Code specific to the usage of the [Wasmtime](https://wasmtime.dev/) runtime.
 
-use std::sync::LazyLock;
+use std::{
+    hash::Hash as _,
+    sync::{Arc, LazyLock},
+};
 
 use linera_base::data_types::Bytecode;
 use linera_witty::{wasmtime::EntrypointInstance, ExportTo};
@@ -11,9 +14,10 @@ use tokio::sync::Mutex;
 use wasmtime::{Config, Engine, Linker, Module, Store};
 
 use super::{
+    disk_cache::{self, ArtifactKey, ModuleKind, StableHasher},
     module_cache::ModuleCache,
     runtime_api::{BaseRuntimeApi, ContractRuntimeApi, RuntimeApiData, ServiceRuntimeApi},
-    ContractEntrypoints, ServiceEntrypoints, WasmExecutionError,
+    ContractEntrypoints, ProfiledModule, ServiceEntrypoints, WasmExecutionError,
 };
 use crate::{
     wasm::{WasmContractModule, WasmServiceModule},
@@ -38,6 +42,29 @@ static CONTRACT_CACHE: LazyLock<Mutex<ModuleCache<Module>>> = LazyLock::new(Mute
 /// A cache of compiled service modules.
 static SERVICE_CACHE: LazyLock<Mutex<ModuleCache<Module>>> = LazyLock::new(Mutex::default);
 
+/// Compiles a module with `engine`, or loads it from the disk cache if it is enabled.
+fn compile_module(
+    engine: &Engine,
+    bytecode: &Bytecode,
+    kind: ModuleKind,
+) -> Result<Module, anyhow::Error> {
+    disk_cache::load_or_compile(
+        || {
+            // The version, settings and target features that Wasmtime checks when loading
+            // an artifact.
+            let mut hasher = StableHasher::default();
+            engine.precompile_compatibility_hash().hash(&mut hasher);
+            let engine = hasher.digest().to_string();
+            ArtifactKey::new(bytecode, kind, "wasmtime", engine)
+        },
+        || Module::new(engine, bytecode),
+        Module::serialize,
+        // SAFETY: The artifact was serialized by a compatible engine, and the disk cache
+        // checked its integrity.
+        |artifact| unsafe { Module::deserialize(engine, artifact) },
+    )
+}
+
 /// Type representing a running [Wasmtime](https://wasmtime.dev/) contract.
 ///
 /// The runtime has a lifetime so that it does not outlive the trait object used to export the
@@ -57,15 +84,28 @@ pub struct WasmtimeServiceInstance<Runtime> {
 }
 
 impl WasmContractModule {
-    /// Creates a new [`WasmContractModule`] using Wasmtime with the provided bytecode files.
-    pub async fn from_wasmtime(contract_bytecode: Bytecode) -> Result<Self, WasmExecutionError> {
+    /// Creates a new [`WasmContractModule`] using Wasmtime with the provided bytecode files, and
+    /// the variant of the module used for profiling.
+    pub async fn from_wasmtime(
+        contract_bytecode: Bytecode,
+        profiled: Arc<ProfiledModule<Module>>,
+    ) -> Result<Self, WasmExecutionError> {
         let mut contract_cache = CONTRACT_CACHE.lock().await;
         let module = contract_cache
             .get_or_insert_with(contract_bytecode, |bytecode| {
-                Module::new(&CONTRACT_ENGINE, bytecode)
+                compile_module(&CONTRACT_ENGINE, &bytecode, ModuleKind::Contract)
             })
             .map_err(WasmExecutionError::LoadContractModule)?;
-        Ok(WasmContractModule::Wasmtime { module })
+        Ok(WasmContractModule::Wasmtime { module, profiled })
+    }
+
+    /// Compiles the profiled variant of a contract module with Wasmtime. It is not kept in
+    /// the in-memory cache of contract modules.
+    pub(crate) fn compile_profiled_with_wasmtime(
+        contract_bytecode: Bytecode,
+    ) -> Result<Module, WasmExecutionError> {
+        compile_module(&CONTRACT_ENGINE, &contract_bytecode, ModuleKind::Contract)
+            .map_err(WasmExecutionError::LoadContractModule)
     }
 }
 
@@ -74,13 +114,17 @@ where
     Runtime: ContractRuntime + 'static,
 {
     /// Prepares a runtime instance to call into the Wasm contract.
-    pub fn prepare(contract_module: &Module, runtime: Runtime) -> Result<Self, WasmExecutionError> {
+    pub fn prepare(
+        contract_module: &Module,
+        function_names: Arc<[String]>,
+        runtime: Runtime,
+    ) -> Result<Self, WasmExecutionError> {
         let mut linker = Linker::new(&CONTRACT_ENGINE);
 
         BaseRuntimeApi::export_to(&mut linker)?;
         ContractRuntimeApi::export_to(&mut linker)?;
 
-        let user_data = RuntimeApiData::new(runtime);
+        let user_data = RuntimeApiData::new(runtime).with_function_names(function_names);
         let mut store = Store::new(&CONTRACT_ENGINE, user_data);
         let instance = linker
             .instantiate(&mut store, contract_module)
@@ -98,7 +142,7 @@ impl WasmServiceModule {
         let mut service_cache = SERVICE_CACHE.lock().await;
         let module = service_cache
             .get_or_insert_with(service_bytecode, |bytecode| {
-                Module::new(&SERVICE_ENGINE, bytecode)
+                compile_module(&SERVICE_ENGINE, &bytecode, ModuleKind::Service)
             })
             .map_err(WasmExecutionError::LoadServiceModule)?;
         Ok(WasmServiceModule::Wasmtime { module })
@@ -143,6 +187,13 @@ where
         Ok(())
     }
 
+    fn migrate(&mut self, _context: OperationContext) -> Result<(), ExecutionError> {
+        ContractEntrypoints::new(&mut self.instance)
+            .migrate()
+            .map_err(WasmExecutionError::from)?;
+        Ok(())
+    }
+
     fn execute_operation(
         &mut self,
         _context: OperationContext,
@@ -5,6 +5,7 @@
 
 use std::{
     collections::{BTreeMap, BTreeSet},
+    sync::Arc,
     vec,
 };
 
@@ -13,7 +14,7 @@ use linera_base::{
     crypto::CryptoHash,
     data_types::{
         Amount, ApplicationDescription, ApplicationPermissions, Blob, BlockHeight,
-        CompressedBytecode, OracleResponse,
+        CompressedBytecode, OracleResponse, TimerDeadline,
     },
     http,
     identifiers::{Account, AccountOwner, ApplicationId, ChainDescription, ChainId, ModuleId},
@@ -26,8 +27,8 @@ use linera_execution::{
         ExpectedCall, RegisterMockApplication, SystemExecutionState,
     },
     BaseRuntime, ContractRuntime, ExecutionError, Message, MessageContext, Operation,
-    OperationContext, ResourceController, SystemExecutionStateView, TestExecutionRuntimeContext,
-    TransactionOutcome, TransactionTracker,
+    OperationContext, ResourceControlPolicy, ResourceController, SystemExecutionStateView,
+    TestExecutionRuntimeContext, TransactionOutcome, TransactionTracker,
 };
 use linera_views::context::MemoryContext;
 use test_case::{test_case, test_matrix};
@@ -942,3 +943,130 @@ async fn test_perform_http_request(authorized_apps: Option<Vec<()>>) -> Result<(
 
     Ok(())
 }
+
+/// Tests that the payload of a timer is charged as written bytes, and limited in size.
+#[test_case(10 => matches Ok(_); "when at the limit")]
+#[test_case(11 => matches Err(ExecutionError::TimerPayloadTooLarge { limit: 10, size: 11 }); "when too large")]
+#[test_log::test(tokio::test)]
+async fn test_set_timer_payload(payload_size: usize) -> Result<(), ExecutionError> {
+    let mut view = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        balance: Amount::from_tokens(1),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+
+    let (application_id, application, blobs) = view.register_mock_application(0).await.unwrap();
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let deadline = TimerDeadline::BlockHeight(BlockHeight(1));
+            runtime.set_timer(deadline, vec![0; payload_size])?;
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = create_dummy_operation_context();
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy {
+            byte_written: Amount::from_attos(1),
+            maximum_timer_payload_bytes: 10,
+            ..ResourceControlPolicy::default()
+        }),
+        ..ResourceController::default()
+    };
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+
+    view.execute_operation(
+        context,
+        operation,
+        &mut TransactionTracker::new_replaying_blobs(blobs),
+        &mut controller,
+    )
+    .await?;
+
+    assert_eq!(controller.tracker.bytes_written, payload_size as u64);
+    assert_eq!(
+        *view.system.balance.get(),
+        Amount::from_tokens(1).saturating_sub(Amount::from_attos(payload_size as u128))
+    );
+    Ok(())
+}
+
+/// Tests that an application can cancel its own timers, but not the ones of other
+/// applications.
+#[test_log::test(tokio::test)]
+async fn test_cancel_timer() -> anyhow::Result<()> {
+    let mut view = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..SystemExecutionState::default()
+    }
+    .into_view()
+    .await;
+
+    let (application_id, application, blobs) = view.register_mock_application(0).await?;
+    let (other_application_id, other_application, other_blobs) =
+        view.register_mock_application(1).await?;
+
+    application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            let first = runtime.set_timer(TimerDeadline::BlockHeight(BlockHeight(1)), vec![1])?;
+            let second = runtime.set_timer(TimerDeadline::BlockHeight(BlockHeight(1)), vec![2])?;
+            assert!(runtime.cancel_timer(first)?);
+            assert!(!runtime.cancel_timer(first)?);
+            assert_eq!(second, first + 1);
+            Ok(vec![])
+        },
+    ));
+    application.expect_call(ExpectedCall::default_finalize());
+
+    let context = create_dummy_operation_context();
+    view.execute_operation(
+        context,
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new_replaying_blobs(blobs),
+        &mut ResourceController::default(),
+    )
+    .await?;
+    // The first timer has ID 0 and was cancelled.
+    let second = 1;
+    assert_eq!(view.system.timers.indices().await?, vec![second]);
+    assert_eq!(
+        view.system.height_timers.index_values().await?,
+        vec![(1, BTreeSet::from([second]))]
+    );
+
+    other_application.expect_call(ExpectedCall::execute_operation(
+        move |runtime, _context, _operation| {
+            runtime.cancel_timer(second)?;
+            Ok(vec![])
+        },
+    ));
+
+    let result = view
+        .execute_operation(
+            context,
+            Operation::User {
+                application_id: other_application_id,
+                bytes: vec![],
+            },
+            &mut TransactionTracker::new_replaying_blobs(other_blobs),
+            &mut ResourceController::default(),
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::UnauthorizedApplication(id)) if id == other_application_id
+    );
+    assert_eq!(view.system.timers.indices().await?, vec![second]);
+
+    Ok(())
+}
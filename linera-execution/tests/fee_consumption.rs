@@ -9,7 +9,7 @@ use std::{collections::BTreeSet, sync::Arc, vec};
 
 use linera_base::{
     crypto::{AccountPublicKey, CryptoHash},
-    data_types::{Amount, BlockHeight, OracleResponse},
+    data_types::{Amount, BlockHeight, OracleResponse, TimerDeadline},
     http,
     identifiers::{Account, AccountOwner, ChainDescription, ChainId, MessageId},
 };
@@ -165,6 +165,13 @@ use test_case::test_case;
     Some(Amount::from_tokens(1_000));
     "with three HTTP requests"
 )]
+#[test_case(
+    vec![FeeSpend::Timer(vec![]), FeeSpend::Timer(vec![1, 2, 3])],
+    Amount::from_tokens(2),
+    Some(Amount::from_tokens(1)),
+    Some(Amount::from_tokens(1_000));
+    "with two timers"
+)]
 #[test_case(
     vec![
         FeeSpend::Fuel(11),
@@ -215,6 +222,7 @@ async fn test_fee_consumption(
         message_byte: Amount::from_tokens(31),
         service_as_oracle_query: Amount::from_millis(37),
         http_request: Amount::from_tokens(41),
+        timer: Amount::from_tokens(109),
         maximum_fuel_per_block: 4_868_145_137,
         maximum_block_size: 43,
         maximum_service_oracle_execution_ms: 47,
@@ -226,6 +234,7 @@ async fn test_fee_consumption(
         maximum_bytes_written_per_block: 73,
         maximum_oracle_response_bytes: 79,
         maximum_http_response_bytes: 83,
+        maximum_timer_payload_bytes: 113,
         http_request_timeout_ms: 89,
         blob_read: Amount::from_tokens(97),
         blob_published: Amount::from_tokens(101),
@@ -302,6 +311,10 @@ async fn test_fee_consumption(
 
     let txn_outcome = txn_tracker.into_outcome()?;
     assert!(txn_outcome.outgoing_messages.is_empty());
+    assert_eq!(
+        controller.policy.total_fees(&controller.tracker)?,
+        consumed_fees
+    );
 
     match initial_grant {
         None => {
@@ -366,13 +379,15 @@ pub enum FeeSpend {
     QueryServiceOracle,
     /// Performs an HTTP request.
     HttpRequest,
+    /// Sets a timer with a payload.
+    Timer(Vec<u8>),
 }
 
 impl FeeSpend {
     /// Returns the [`OracleResponse`]s necessary for executing this runtime operation.
     pub fn expected_oracle_responses(&self) -> Vec<OracleResponse> {
         match self {
-            FeeSpend::Fuel(_) | FeeSpend::Read(_, _) => vec![],
+            FeeSpend::Fuel(_) | FeeSpend::Read(_, _) | FeeSpend::Timer(_) => vec![],
             FeeSpend::QueryServiceOracle => {
                 vec![OracleResponse::Service(vec![])]
             }
@@ -394,6 +409,9 @@ impl FeeSpend {
             }
             FeeSpend::QueryServiceOracle => policy.service_as_oracle_query,
             FeeSpend::HttpRequest => policy.http_request,
+            FeeSpend::Timer(payload) => policy
+                .timer
+                .saturating_add(policy.byte_written.saturating_mul(payload.len() as u128)),
         }
     }
 
@@ -416,6 +434,10 @@ impl FeeSpend {
                 runtime.perform_http_request(http::Request::get("http://dummy.url"))?;
                 Ok(())
             }
+            FeeSpend::Timer(payload) => {
+                runtime.set_timer(TimerDeadline::BlockHeight(BlockHeight(1)), payload)?;
+                Ok(())
+            }
         }
     }
 }
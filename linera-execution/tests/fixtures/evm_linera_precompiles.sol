@@ -0,0 +1,60 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+import "./linera.sol";
+
+contract LineraPrecompiles {
+  event ValueEmitted(uint32 indexed index, bytes value);
+
+  function send_to(bytes32 destination, bytes memory message) external {
+    Linera.send_message(destination, false, false, message);
+  }
+
+  // Sends the message from a frame whose revert is caught.
+  function send_in_subcall(bytes32 destination, bytes memory message) external {
+    try this.send_to(destination, message) {} catch {}
+  }
+
+  function emit_value(bytes memory value) external returns (uint32) {
+    uint32 index = Linera.emit_event("numbers", value);
+    emit ValueEmitted(index, value);
+    return index;
+  }
+
+  function current_chain() external view returns (bytes32) {
+    return Linera.chain_id();
+  }
+
+  function chain_balance() external view returns (uint256) {
+    return Linera.read_chain_balance();
+  }
+
+  function owner_balance(bytes memory owner) external view returns (uint256) {
+    return Linera.read_owner_balance(owner);
+  }
+
+  function signer() external view returns (bool, bytes memory) {
+    return Linera.authenticated_signer();
+  }
+
+  function transfer_to(bytes memory source, bytes32 chain, bytes memory owner, uint256 amount) external {
+    Linera.transfer(source, chain, owner, amount);
+  }
+
+  function event_value(bytes32 chain, uint32 index) external returns (bytes memory) {
+    return Linera.read_event(chain, "numbers", index);
+  }
+
+  function subscribe(bytes32 chain, bytes32 application) external {
+    Linera.subscribe_to_events(chain, application, "numbers");
+  }
+
+  function unsubscribe(bytes32 chain, bytes32 application) external {
+    Linera.unsubscribe_from_events(chain, application, "numbers");
+  }
+
+  function data_blob(bytes32 hash) external view returns (bytes memory) {
+    return Linera.read_data_blob(hash);
+  }
+}
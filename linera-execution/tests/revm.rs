@@ -5,11 +5,16 @@
 
 use std::sync::Arc;
 
+use alloy::primitives::{keccak256, Bytes, B256, U256};
 use alloy_sol_types::{sol, SolCall, SolValue};
 use linera_base::{
-    data_types::{Amount, Blob, BlockHeight, Timestamp},
-    identifiers::ChainDescription,
-    vm::EvmQuery,
+    crypto::CryptoHash,
+    data_types::{Amount, Blob, BlockHeight, OracleResponse, Timestamp},
+    identifiers::{
+        AccountOwner, ApplicationId, ChainDescription, ChainId, Destination, EventId,
+        GenericApplicationId, StreamId, StreamName,
+    },
+    vm::{evm_log_stream_name, EvmLog, EvmQuery},
 };
 use linera_execution::{
     evm::revm::{EvmContractModule, EvmServiceModule},
@@ -18,11 +23,15 @@ use linera_execution::{
         solidity::{load_solidity_example, read_evm_u64_entry},
         SystemExecutionState,
     },
-    ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, OperationContext, Query,
-    QueryContext, QueryResponse, ResourceControlPolicy, ResourceController, ResourceTracker,
-    TransactionTracker,
+    ExecutionRuntimeConfig, ExecutionRuntimeContext, ExecutionStateView, Message, Operation,
+    OperationContext, Query, QueryContext, QueryResponse, ResourceControlPolicy,
+    ResourceController, ResourceTracker, SystemMessage, TestExecutionRuntimeContext,
+    TransactionOutcome, TransactionTracker,
+};
+use linera_views::{
+    context::{Context as _, MemoryContext},
+    views::View,
 };
-use linera_views::{context::Context as _, views::View};
 
 #[tokio::test]
 async fn test_fuel_for_counter_revm_application() -> anyhow::Result<()> {
@@ -149,3 +158,392 @@ async fn test_fuel_for_counter_revm_application() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+sol! {
+    function send_to(bytes32 destination, bytes message);
+    function send_in_subcall(bytes32 destination, bytes message);
+    function emit_value(bytes value);
+    function current_chain();
+    function chain_balance();
+    function owner_balance(bytes owner);
+    function signer();
+    function transfer_to(bytes source, bytes32 chain, bytes owner, uint256 amount);
+    function event_value(bytes32 chain, uint32 index);
+    function subscribe(bytes32 chain, bytes32 application);
+    function unsubscribe(bytes32 chain, bytes32 application);
+    function data_blob(bytes32 hash);
+}
+
+type TestExecutionStateView = ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>;
+
+/// Creates the application of `evm_linera_precompiles.sol` on a chain with 3 tokens.
+async fn create_precompiles_application() -> anyhow::Result<(TestExecutionStateView, ApplicationId)>
+{
+    let module = load_solidity_example("tests/fixtures/evm_linera_precompiles.sol")?;
+    let instantiation_argument = serde_json::to_string(&Vec::<u8>::new())?.into_bytes();
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let chain_id = app_desc.creator_chain_id;
+    let mut view = state
+        .into_view_with(chain_id, ExecutionRuntimeConfig::default())
+        .await;
+    let app_id = From::from(&app_desc);
+
+    let contract = EvmContractModule::Revm {
+        module: module.clone(),
+    };
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.clone().into());
+    let service = EvmServiceModule::Revm { module };
+    view.context()
+        .extra()
+        .user_services()
+        .insert(app_id, service.into());
+
+    view.simulate_instantiation(
+        contract.into(),
+        Timestamp::from(2),
+        app_desc,
+        instantiation_argument,
+        contract_blob,
+        service_blob,
+    )
+    .await?;
+    *view.system.balance.get_mut() = Amount::from_tokens(3);
+    Ok((view, app_id))
+}
+
+/// Executes the `operations` of the application in one transaction, signed by `signer`.
+async fn execute_operations(
+    view: &mut TestExecutionStateView,
+    app_id: ApplicationId,
+    signer: Option<AccountOwner>,
+    operations: impl IntoIterator<Item = Vec<u8>>,
+) -> anyhow::Result<TransactionOutcome> {
+    let operation_context = OperationContext {
+        chain_id: view.context().extra().chain_id(),
+        height: BlockHeight(0),
+        round: Some(0),
+        index: Some(0),
+        authenticated_signer: signer,
+        authenticated_caller_id: None,
+    };
+    let mut controller = ResourceController::default();
+    let mut txn_tracker = TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None);
+    for bytes in operations {
+        let operation = Operation::User {
+            application_id: app_id,
+            bytes,
+        };
+        view.execute_operation(
+            operation_context,
+            operation,
+            &mut txn_tracker,
+            &mut controller,
+        )
+        .await?;
+    }
+    Ok(txn_tracker.into_outcome()?)
+}
+
+/// Runs the `query` on the service of the application, and returns the ABI-encoded output.
+async fn query_application(
+    view: &mut TestExecutionStateView,
+    app_id: ApplicationId,
+    query: EvmQuery,
+) -> anyhow::Result<Vec<u8>> {
+    let query_context = QueryContext {
+        chain_id: view.context().extra().chain_id(),
+        next_block_height: BlockHeight(0),
+        local_time: Timestamp::from(0),
+    };
+    let query = Query::User {
+        application_id: app_id,
+        bytes: serde_json::to_vec(&query)?,
+    };
+    let result = view.query_application(query_context, query, None).await?;
+    let QueryResponse::User(result) = result.response else {
+        anyhow::bail!("Wrong QueryResponse result");
+    };
+    Ok(result)
+}
+
+#[tokio::test]
+async fn test_linera_precompiles_in_revm_application() -> anyhow::Result<()> {
+    let (mut view, app_id) = create_precompiles_application().await?;
+    let chain_id = view.context().extra().chain_id();
+
+    // Messages and events are recorded in the transaction outcome.
+    let recipient = ChainId(CryptoHash::test_hash("recipient"));
+    let operations = [
+        send_toCall {
+            destination: *recipient.0.as_bytes(),
+            message: b"hello".to_vec().into(),
+        }
+        .abi_encode(),
+        emit_valueCall {
+            value: b"42".to_vec().into(),
+        }
+        .abi_encode(),
+    ];
+    let outcome = execute_operations(&mut view, app_id, None, operations).await?;
+    assert_eq!(outcome.outgoing_messages.len(), 1);
+
+    // A message could not be taken back if the frame sending it reverted.
+    let operation = send_in_subcallCall {
+        destination: *recipient.0.as_bytes(),
+        message: b"hello".to_vec().into(),
+    }
+    .abi_encode();
+    let error = execute_operations(&mut view, app_id, None, [operation])
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("top-level call frame"));
+    let message = &outcome.outgoing_messages[0];
+    assert_eq!(message.destination, Destination::Recipient(recipient));
+    assert_eq!(
+        message.message,
+        Message::User {
+            application_id: app_id,
+            bytes: b"hello".to_vec(),
+        }
+    );
+    let stream_id = StreamId {
+        application_id: GenericApplicationId::User(app_id),
+        stream_name: StreamName(b"numbers".to_vec()),
+    };
+    assert!(outcome
+        .events
+        .iter()
+        .any(|event| event.stream_id == stream_id && event.value == b"42"));
+
+    // The Solidity event is decoded from the EVM logs, with the address of the application.
+    let log_stream_id = StreamId {
+        application_id: GenericApplicationId::User(app_id),
+        stream_name: evm_log_stream_name(),
+    };
+    let logs = outcome
+        .events
+        .iter()
+        .filter(|event| event.stream_id == log_stream_id)
+        .map(|event| EvmLog::from_event_value(&event.value))
+        .collect::<Result<Option<Vec<_>>, _>>()?
+        .expect("the logs should have the current version");
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].origin, "operation");
+    assert_eq!(logs[0].address, app_id.evm_address());
+    assert_eq!(
+        logs[0].topics,
+        vec![keccak256("ValueEmitted(uint32,bytes)"), B256::ZERO]
+    );
+    assert_eq!(
+        logs[0].data.to_vec(),
+        (Bytes::from(b"42".to_vec()),).abi_encode_params()
+    );
+
+    // The chain ID and balance can be read by the service.
+    let output = query_application(
+        &mut view,
+        app_id,
+        EvmQuery::Query(current_chainCall {}.abi_encode()),
+    )
+    .await?;
+    let output = serde_json::from_slice::<Vec<u8>>(&output)?;
+    assert_eq!(B256::abi_decode(&output, true)?, *chain_id.0.as_bytes());
+    let output = query_application(
+        &mut view,
+        app_id,
+        EvmQuery::Query(chain_balanceCall {}.abi_encode()),
+    )
+    .await?;
+    let output = serde_json::from_slice::<Vec<u8>>(&output)?;
+    assert_eq!(
+        U256::abi_decode(&output, true)?,
+        U256::from(u128::from(Amount::from_tokens(3)))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transfers_and_balances_in_revm_application() -> anyhow::Result<()> {
+    let (mut view, app_id) = create_precompiles_application().await?;
+    let owner = AccountOwner::Address20([7; 20]);
+    let encoded_owner = Bytes::from(owner_bytes(owner));
+    view.system
+        .balances
+        .insert(&owner, Amount::from_tokens(5))?;
+
+    // The authenticated signer is encoded like the other owners, and its absence differs
+    // from the chain itself.
+    for (signer, expected) in [
+        (Some(owner), (true, encoded_owner.clone())),
+        (Some(AccountOwner::CHAIN), (true, Bytes::new())),
+        (None, (false, Bytes::new())),
+    ] {
+        let outcome =
+            execute_operations(&mut view, app_id, signer, [signerCall {}.abi_encode()]).await?;
+        assert_eq!(
+            <(bool, Bytes)>::abi_decode_params(&outcome.operation_result, true)?,
+            expected
+        );
+    }
+
+    // The signer can transfer its own tokens.
+    let recipient = ChainId(CryptoHash::test_hash("recipient"));
+    let recipient_owner = AccountOwner::Reserved(3);
+    let amount = Amount::from_tokens(2);
+    let transfer = transfer_toCall {
+        source: encoded_owner.clone(),
+        chain: *recipient.0.as_bytes(),
+        owner: owner_bytes(recipient_owner).into(),
+        amount: U256::from(u128::from(amount)),
+    }
+    .abi_encode();
+    assert!(
+        execute_operations(&mut view, app_id, None, [transfer.clone()])
+            .await
+            .is_err()
+    );
+    let outcome = execute_operations(&mut view, app_id, Some(owner), [transfer]).await?;
+    assert_eq!(outcome.outgoing_messages.len(), 1);
+    let message = &outcome.outgoing_messages[0];
+    assert_eq!(message.destination, Destination::Recipient(recipient));
+    assert_eq!(
+        message.message,
+        Message::System(SystemMessage::Credit {
+            amount,
+            source: owner,
+            target: recipient_owner,
+        })
+    );
+
+    let output = query_application(
+        &mut view,
+        app_id,
+        EvmQuery::Query(
+            owner_balanceCall {
+                owner: encoded_owner,
+            }
+            .abi_encode(),
+        ),
+    )
+    .await?;
+    let output = serde_json::from_slice::<Vec<u8>>(&output)?;
+    assert_eq!(
+        U256::abi_decode(&output, true)?,
+        U256::from(u128::from(Amount::from_tokens(3)))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_events_and_blobs_in_revm_application() -> anyhow::Result<()> {
+    let (mut view, app_id) = create_precompiles_application().await?;
+    let other_chain = ChainId(CryptoHash::test_hash("other chain"));
+    let stream_id = StreamId {
+        application_id: GenericApplicationId::User(app_id),
+        stream_name: StreamName(b"numbers".to_vec()),
+    };
+
+    // Events of other chains are read through an oracle.
+    let event_id = EventId {
+        chain_id: other_chain,
+        stream_id: stream_id.clone(),
+        index: 4,
+    };
+    view.context()
+        .extra()
+        .add_events([(event_id.clone(), b"event".to_vec())])
+        .await?;
+    let operation = event_valueCall {
+        chain: *other_chain.0.as_bytes(),
+        index: 4,
+    }
+    .abi_encode();
+    let outcome = execute_operations(&mut view, app_id, None, [operation]).await?;
+    assert_eq!(
+        Bytes::abi_decode(&outcome.operation_result, true)?,
+        Bytes::from(b"event".to_vec())
+    );
+    assert!(outcome
+        .oracle_responses
+        .contains(&OracleResponse::Event(event_id, b"event".to_vec())));
+
+    // Subscriptions to streams are recorded in the system state.
+    let subscription = (other_chain, stream_id);
+    let operation = subscribeCall {
+        chain: *other_chain.0.as_bytes(),
+        application: app_id.bytes32(),
+    }
+    .abi_encode();
+    execute_operations(&mut view, app_id, None, [operation]).await?;
+    let subscriptions = view.system.event_subscriptions.get(&subscription).await?;
+    assert!(subscriptions.is_some_and(|subscriptions| subscriptions.applications.contains(&app_id)));
+    let operation = unsubscribeCall {
+        chain: *other_chain.0.as_bytes(),
+        application: app_id.bytes32(),
+    }
+    .abi_encode();
+    execute_operations(&mut view, app_id, None, [operation]).await?;
+    assert!(view
+        .system
+        .event_subscriptions
+        .get(&subscription)
+        .await?
+        .is_none());
+
+    // Data blobs are read through an oracle by contracts, and directly by services.
+    let blob = Blob::new_data(b"blob".to_vec());
+    view.context().extra().add_blobs([blob.clone()]).await?;
+    let query = data_blobCall {
+        hash: *blob.id().hash.as_bytes(),
+    }
+    .abi_encode();
+    let outcome = execute_operations(&mut view, app_id, None, [query.clone()]).await?;
+    assert_eq!(
+        Bytes::abi_decode(&outcome.operation_result, true)?,
+        Bytes::from(b"blob".to_vec())
+    );
+    assert!(outcome
+        .oracle_responses
+        .contains(&OracleResponse::Blob(blob.id())));
+    let output = query_application(&mut view, app_id, EvmQuery::Query(query)).await?;
+    let output = serde_json::from_slice::<Vec<u8>>(&output)?;
+    assert_eq!(
+        Bytes::abi_decode(&output, true)?,
+        Bytes::from(b"blob".to_vec())
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gas_of_revm_precompiles() -> anyhow::Result<()> {
+    let (mut view, app_id) = create_precompiles_application().await?;
+    let mut gas = Vec::new();
+    for operation in [
+        current_chainCall {}.abi_encode(),
+        chain_balanceCall {}.abi_encode(),
+    ] {
+        let output = query_application(&mut view, app_id, EvmQuery::EstimateGas(operation)).await?;
+        gas.push(serde_json::from_slice::<u64>(&output)?);
+    }
+    // Both functions make one precompile call and decode 32 bytes, but reading the balance is
+    // charged as a storage read.
+    assert!(gas[1] > gas[0] + 2_000, "{gas:?}");
+    Ok(())
+}
+
+/// Encodes an account owner as expected by the precompiles.
+fn owner_bytes(owner: AccountOwner) -> Vec<u8> {
+    match owner {
+        AccountOwner::CHAIN => Vec::new(),
+        AccountOwner::Reserved(byte) => vec![byte],
+        AccountOwner::Address20(address) => address.to_vec(),
+        AccountOwner::Address32(hash) => hash.as_bytes().to_vec(),
+    }
+}
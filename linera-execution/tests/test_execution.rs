@@ -7,27 +7,34 @@ use std::{collections::BTreeMap, vec};
 
 use assert_matches::assert_matches;
 use linera_base::{
-    crypto::{AccountPublicKey, ValidatorPublicKey},
+    crypto::{AccountPublicKey, CryptoHash, ValidatorPublicKey},
     data_types::{
-        Amount, ApplicationPermissions, Blob, BlockHeight, Epoch, Resources, SendMessageRequest,
-        Timestamp,
+        Amount, ApplicationDescription, ApplicationPermissions, Blob, BlockHeight, Epoch, Event,
+        OracleResponse, Resources, SendMessageRequest, Timestamp,
+    },
+    identifiers::{
+        Account, AccountOwner, ApplicationId, ChainDescription, ChainId, Destination, EventId,
+        MessageId, ModuleId,
     },
-    identifiers::{Account, AccountOwner, ChainDescription, ChainId, Destination, MessageId},
     ownership::ChainOwnership,
 };
 use linera_execution::{
     committee::Committee,
-    system::SystemMessage,
+    system::{application_upgrade_stream_id, ApplicationUpgrade, SystemMessage},
     test_utils::{
         blob_oracle_responses, create_dummy_message_context, create_dummy_operation_context,
-        create_dummy_user_application_registrations, ExpectedCall, RegisterMockApplication,
-        SystemExecutionState,
+        create_dummy_user_application_description, create_dummy_user_application_registrations,
+        ExpectedCall, MockApplication, RegisterMockApplication, SystemExecutionState,
     },
     BaseRuntime, ContractRuntime, ExecutionError, ExecutionRuntimeContext, Message, Operation,
     OperationContext, OutgoingMessage, Query, QueryContext, QueryOutcome, QueryResponse,
-    ResourceController, SystemOperation, TransactionTracker,
+    ResourceController, SystemOperation, TraceStep, TransactionTracker,
+};
+use linera_views::{
+    batch::Batch,
+    context::Context,
+    views::{View, ViewError},
 };
-use linera_views::{batch::Batch, context::Context, views::View};
 use test_case::test_case;
 
 #[tokio::test]
@@ -1351,6 +1358,226 @@ async fn test_close_chain() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Registers the application of `create_dummy_user_application_description(0)`, and the
+/// code of the module it is upgraded to. Returns the application ID, the new module ID, and
+/// the mocks for the old and the new code.
+async fn register_upgradable_application(
+    view: &mut impl RegisterMockApplication,
+) -> anyhow::Result<(ApplicationId, ModuleId, MockApplication, MockApplication)> {
+    let (description, contract_blob, service_blob) = create_dummy_user_application_description(0);
+    let (application_id, application) = view
+        .register_mock_application_with(description.clone(), contract_blob, service_blob)
+        .await?;
+
+    // The new code is registered for the upgraded description, under the same application ID.
+    let (new_description, new_contract_blob, new_service_blob) =
+        create_dummy_user_application_description(1);
+    let module_id = new_description.module_id;
+    let (_, new_application) = view
+        .register_mock_application_with(
+            ApplicationDescription {
+                module_id,
+                ..description
+            },
+            new_contract_blob,
+            new_service_blob,
+        )
+        .await?;
+    Ok((application_id, module_id, application, new_application))
+}
+
+#[tokio::test]
+async fn test_upgrade_application() -> anyhow::Result<()> {
+    // The dummy application is created on `ChainId::root(1)`.
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(1));
+    let mut view = state.into_view().await;
+    let (application_id, module_id, application, new_application) =
+        register_upgradable_application(&mut view).await?;
+    let (description, contract_blob, service_blob) = create_dummy_user_application_description(0);
+    let blobs = [
+        Blob::new_application_description(&description).id(),
+        contract_blob.id(),
+        service_blob.id(),
+    ];
+    let (_, new_contract_blob, new_service_blob) = create_dummy_user_application_description(1);
+    let new_blobs = [new_contract_blob.id(), new_service_blob.id()];
+
+    let context = OperationContext {
+        chain_id: ChainId::root(1),
+        ..create_dummy_operation_context()
+    };
+    let mut controller = ResourceController::default();
+    application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    application.expect_call(ExpectedCall::default_finalize());
+    let operation = Operation::User {
+        application_id,
+        bytes: vec![],
+    };
+    view.execute_operation(
+        context,
+        operation.clone(),
+        &mut TransactionTracker::new_replaying_blobs(blobs),
+        &mut controller,
+    )
+    .await?;
+
+    // The application can only be upgraded once the chain's permissions allow it.
+    let upgrade = SystemOperation::UpgradeApplication {
+        application_id,
+        module_id,
+    };
+    let result = view
+        .execute_operation(
+            context,
+            upgrade.clone().into(),
+            &mut TransactionTracker::new_replaying(Vec::new()),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::UnauthorizedApplicationUpgrade(id)) if id == application_id
+    );
+
+    // The creator chain upgrades the application, and publishes the upgrade as an event.
+    view.system
+        .application_permissions
+        .set(ApplicationPermissions {
+            upgrade_applications: vec![application_id],
+            ..ApplicationPermissions::default()
+        });
+    new_application.expect_call(ExpectedCall::migrate(|_, _| Ok(())));
+    new_application.expect_call(ExpectedCall::default_finalize());
+    let mut txn_tracker = TransactionTracker::new_replaying_blobs(new_blobs);
+    view.execute_operation(context, upgrade.into(), &mut txn_tracker, &mut controller)
+        .await?;
+    let upgrade_event = Event {
+        stream_id: application_upgrade_stream_id(application_id),
+        index: 0,
+        value: bcs::to_bytes(&module_id)?,
+    };
+    assert_eq!(txn_tracker.into_outcome()?.events, vec![upgrade_event]);
+    assert_eq!(
+        view.system
+            .application_upgrades
+            .get(&application_id)
+            .await?,
+        Some(ApplicationUpgrade {
+            count: 1,
+            module_id
+        })
+    );
+
+    // Operations and messages for the application are now executed by the new code.
+    new_application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    new_application.expect_call(ExpectedCall::default_finalize());
+    view.execute_operation(
+        context,
+        operation,
+        &mut TransactionTracker::new_replaying(Vec::new()),
+        &mut controller,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_upgrade_application_from_other_chain() -> anyhow::Result<()> {
+    // The dummy application is created on `ChainId::root(1)`, not on this chain.
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    let mut view = state.into_view().await;
+    let (application_id, module_id, _application, new_application) =
+        register_upgradable_application(&mut view).await?;
+    let context = create_dummy_operation_context();
+    let mut controller = ResourceController::default();
+
+    // Other chains cannot upgrade the application, even if their permissions allow it.
+    view.system
+        .application_permissions
+        .set(ApplicationPermissions {
+            upgrade_applications: vec![application_id],
+            ..ApplicationPermissions::default()
+        });
+    let upgrade = SystemOperation::UpgradeApplication {
+        application_id,
+        module_id,
+    };
+    let result = view
+        .execute_operation(
+            context,
+            upgrade.into(),
+            &mut TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::UnauthorizedApplicationUpgrade(id)) if id == application_id
+    );
+
+    // Without an upgrade event from the creator chain, there is nothing to process.
+    let process_upgrade =
+        Operation::system(SystemOperation::ProcessApplicationUpgrade(application_id));
+    let result = view
+        .execute_operation(
+            context,
+            process_upgrade.clone(),
+            &mut TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None),
+            &mut controller,
+        )
+        .await;
+    assert_matches!(
+        result,
+        Err(ExecutionError::ViewError(ViewError::EventsNotFound(_)))
+    );
+
+    // Once the creator chain has upgraded the application, this chain follows.
+    let event_id = EventId {
+        chain_id: ChainId::root(1),
+        stream_id: application_upgrade_stream_id(application_id),
+        index: 0,
+    };
+    let event_value = bcs::to_bytes(&module_id)?;
+    view.context()
+        .extra()
+        .add_events([(event_id.clone(), event_value.clone())])
+        .await?;
+    new_application.expect_call(ExpectedCall::migrate(|_, _| Ok(())));
+    new_application.expect_call(ExpectedCall::default_finalize());
+    let mut txn_tracker = TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None);
+    view.execute_operation(context, process_upgrade, &mut txn_tracker, &mut controller)
+        .await?;
+    assert!(txn_tracker
+        .into_outcome()?
+        .oracle_responses
+        .contains(&OracleResponse::Event(event_id, event_value)));
+    assert_eq!(
+        view.system
+            .application_upgrade_count(&application_id)
+            .await?,
+        1
+    );
+
+    // The old code is not called anymore.
+    new_application.expect_call(ExpectedCall::execute_operation(|_, _, _| Ok(vec![])));
+    new_application.expect_call(ExpectedCall::default_finalize());
+    view.execute_operation(
+        context,
+        Operation::User {
+            application_id,
+            bytes: vec![],
+        },
+        &mut TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None),
+        &mut controller,
+    )
+    .await?;
+
+    Ok(())
+}
+
 /// Tests an application attempting to transfer the tokens in the chain's balance while executing
 /// messages.
 #[test_case(
@@ -1434,3 +1661,37 @@ async fn test_message_receipt_spending_chain_balance(
 
     Ok(execution_result)
 }
+
+#[test]
+fn test_consecutive_fuel_steps_are_combined() -> anyhow::Result<()> {
+    let first_id = ApplicationId::new(CryptoHash::test_hash("first"));
+    let second_id = ApplicationId::new(CryptoHash::test_hash("second"));
+    let fuel = |application_id, fuel| TraceStep::Fuel {
+        application_id,
+        fuel,
+    };
+    let response = TraceStep::OracleResponse(OracleResponse::Assert);
+    let mut txn_tracker = TransactionTracker::new(Timestamp::from(0), 0, 0, 0, None).with_trace();
+    for step in [
+        fuel(first_id, 1),
+        fuel(first_id, 2),
+        fuel(second_id, 3),
+        fuel(first_id, 4),
+        response.clone(),
+        fuel(first_id, 5),
+    ] {
+        txn_tracker.trace(|| step);
+    }
+    let trace = txn_tracker.into_outcome()?.trace.unwrap();
+    assert_eq!(
+        trace.steps,
+        vec![
+            fuel(first_id, 3),
+            fuel(second_id, 3),
+            fuel(first_id, 4),
+            response,
+            fuel(first_id, 5),
+        ]
+    );
+    Ok(())
+}
@@ -3,6 +3,7 @@
 
 #![allow(clippy::field_reassign_with_default)]
 
+use assert_matches::assert_matches;
 use linera_base::{
     crypto::{AccountSecretKey, CryptoHash},
     data_types::{Amount, BlockHeight, Timestamp},
@@ -10,10 +11,14 @@ use linera_base::{
     ownership::ChainOwnership,
 };
 use linera_execution::{
-    system::Recipient, test_utils::SystemExecutionState, Message, MessageContext, Operation,
-    OperationContext, Query, QueryContext, QueryOutcome, QueryResponse, ResourceController,
-    SystemMessage, SystemOperation, SystemQuery, SystemResponse, TransactionTracker,
+    system::Recipient, test_utils::SystemExecutionState, ExecutionStateView, Message,
+    MessageContext, Operation, OperationContext, Query, QueryContext, QueryOutcome, QueryResponse,
+    ResourceController, SystemMessage, SystemOperation, SystemQuery, SystemResponse,
+    TestExecutionRuntimeContext, TransactionTracker,
 };
+use linera_views::{context::MemoryContext, proof::ProofError, views::CryptoHashView as _};
+
+type TestExecutionStateView = ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>;
 
 #[tokio::test]
 async fn test_simple_system_operation() -> anyhow::Result<()> {
@@ -127,3 +132,44 @@ async fn test_simple_system_query() -> anyhow::Result<()> {
     assert!(operations.is_empty());
     Ok(())
 }
+
+#[tokio::test]
+async fn test_balance_proofs() -> anyhow::Result<()> {
+    let owner = AccountOwner::from(AccountSecretKey::generate().public());
+    let mut state = SystemExecutionState::default();
+    state.description = Some(ChainDescription::Root(0));
+    state.balance = Amount::from_tokens(4);
+    state.balances.insert(owner, Amount::from_tokens(2));
+    let view = state.into_view().await;
+    let state_hash = view.crypto_hash().await?;
+    let other_owner = AccountOwner::from(AccountSecretKey::generate().public());
+    for (account, expected) in [
+        (AccountOwner::CHAIN, Some(Amount::from_tokens(4))),
+        (owner, Some(Amount::from_tokens(2))),
+        (other_owner, None),
+    ] {
+        let proof = view.balance_proof(&account).await?;
+        let path = TestExecutionStateView::balance_proof_path(&account)?;
+        proof.verify_crypto_hash(&path, "ExecutionStateViewHash", &state_hash)?;
+        assert_eq!(proof.value::<Amount>()?, expected);
+    }
+    let proof = view.balance_proof(&owner).await?;
+    let path = TestExecutionStateView::balance_proof_path(&owner)?;
+    assert!(proof
+        .verify_crypto_hash(
+            &path,
+            "ExecutionStateViewHash",
+            &CryptoHash::test_hash("other")
+        )
+        .is_err());
+
+    // A valid proof about another account, or about the chain balance, is rejected.
+    for account in [other_owner, AccountOwner::CHAIN] {
+        let path = TestExecutionStateView::balance_proof_path(&account)?;
+        assert_matches!(
+            proof.verify_crypto_hash(&path, "ExecutionStateViewHash", &state_hash),
+            Err(ProofError::PathMismatch { .. })
+        );
+    }
+    Ok(())
+}
@@ -11,8 +11,8 @@ use linera_base::{
 };
 use linera_execution::{
     test_utils::{create_dummy_user_application_description, SystemExecutionState},
-    ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, OperationContext, Query,
-    QueryContext, QueryOutcome, QueryResponse, ResourceControlPolicy, ResourceController,
+    ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, OperationContext, ProfileMetric,
+    Query, QueryContext, QueryOutcome, QueryResponse, ResourceControlPolicy, ResourceController,
     ResourceTracker, TransactionTracker, WasmContractModule, WasmRuntime, WasmServiceModule,
 };
 use linera_views::{context::Context as _, views::View};
@@ -147,3 +147,107 @@ async fn test_fuel_for_counter_wasm_application(
     assert!(operations.is_empty());
     Ok(())
 }
+
+/// Test that the fuel consumed by the "counter" example application is attributed to the
+/// functions of its Wasm module, and its storage reads to the runtime functions.
+#[cfg_attr(with_wasmer, test_case(WasmRuntime::Wasmer; "wasmer"))]
+#[cfg_attr(with_wasmtime, test_case(WasmRuntime::Wasmtime; "wasmtime"))]
+#[test_log::test(tokio::test(flavor = "multi_thread"))]
+async fn test_profile_of_counter_wasm_application(wasm_runtime: WasmRuntime) -> anyhow::Result<()> {
+    let state = SystemExecutionState {
+        description: Some(ChainDescription::Root(0)),
+        ..Default::default()
+    };
+    let mut view = state
+        .into_view_with(ChainId::root(0), ExecutionRuntimeConfig::default())
+        .await;
+    let (app_desc, contract_blob, service_blob) = create_dummy_user_application_description(1);
+    let app_id = From::from(&app_desc);
+    let blob_ids = vec![
+        Blob::new_application_description(&app_desc).id(),
+        contract_blob.id(),
+        service_blob.id(),
+    ];
+
+    let contract =
+        WasmContractModule::from_file("tests/fixtures/counter_contract.wasm", wasm_runtime).await?;
+    view.context()
+        .extra()
+        .user_contracts()
+        .insert(app_id, contract.into());
+    view.context()
+        .extra()
+        .add_blobs([
+            contract_blob,
+            service_blob,
+            Blob::new_application_description(&app_desc),
+        ])
+        .await?;
+
+    let context = OperationContext {
+        chain_id: ChainId::root(0),
+        height: BlockHeight(0),
+        round: Some(0),
+        index: Some(0),
+        authenticated_signer: None,
+        authenticated_caller_id: None,
+    };
+    *view.system.balance.get_mut() = Amount::from_tokens(1);
+    let mut controller = ResourceController {
+        policy: Arc::new(ResourceControlPolicy::default()),
+        tracker: ResourceTracker::default(),
+        account: None,
+    };
+    let mut txn_tracker = TransactionTracker::new_replaying_blobs(blob_ids).with_profile();
+    view.execute_operation(
+        context,
+        Operation::user_without_abi(app_id, &7_u64).unwrap(),
+        &mut txn_tracker,
+        &mut controller,
+    )
+    .await?;
+    let profile = txn_tracker.into_outcome()?.profile.unwrap();
+
+    assert_eq!(profile.total().fuel, controller.tracker.fuel);
+    let frame = format!("{app_id}::execute_operation");
+    let entry_point = vec![
+        frame.clone(),
+        "linera:app/contract-entrypoints#execute-operation".to_owned(),
+    ];
+    assert!(profile.stacks[&entry_point].fuel > 0);
+    let functions = profile
+        .folded(ProfileMetric::Fuel)
+        .to_string()
+        .lines()
+        .count();
+    assert!(
+        functions > 1,
+        "the fuel should be spread over several functions"
+    );
+    let read_operations = profile
+        .stacks
+        .iter()
+        .filter(|(stack, _)| stack.first() == Some(&frame))
+        .map(|(_, usage)| usage.read_operations)
+        .sum::<u64>();
+    assert!(read_operations > 0);
+    let storage_functions = [
+        "contains_key",
+        "contains_keys",
+        "read_multi_values_bytes",
+        "read_value_bytes",
+        "find_keys_by_prefix",
+        "find_key_values_by_prefix",
+        "write_batch",
+    ];
+    for (stack, usage) in &profile.stacks {
+        // Only the resources used by the storage functions are attributed to them.
+        if stack
+            .last()
+            .is_some_and(|leaf| storage_functions.contains(&leaf.as_str()))
+        {
+            assert_eq!(usage.fuel, 0, "{stack:?}");
+        }
+    }
+    Ok(())
+}
@@ -14,7 +14,7 @@ use linera_base::{
 };
 use linera_client::{chain_listener, wallet::Wallet};
 use linera_core::{
-    client::ChainClient,
+    client::{ChainClient, MessagePolicy},
     test_utils::{FaultType, MemoryStorageBuilder, NodeProvider, StorageBuilder as _, TestBuilder},
 };
 use linera_storage::{DbStorage, TestClock};
@@ -47,6 +47,10 @@ impl chain_listener::ClientContext for ClientContext {
         Ok(self.client.clone())
     }
 
+    fn message_policy(&self) -> &MessagePolicy {
+        &self.client.options().message_policy
+    }
+
     async fn update_wallet_for_new_chain(
         &mut self,
         _: ChainId,
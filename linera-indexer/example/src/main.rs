@@ -1,10 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! An example of an indexer with the operations plugin.
+//! An example of an indexer with the operations and EVM logs plugins.
 
 use linera_indexer::{common::IndexerError, plugin::Plugin, rocks_db::RocksDbRunner};
-use linera_indexer_plugins::operations::OperationsPlugin;
+use linera_indexer_plugins::{evm_logs::EvmLogsPlugin, operations::OperationsPlugin};
 
 #[tokio::main]
 async fn main() -> Result<(), IndexerError> {
@@ -20,5 +20,8 @@ async fn main() -> Result<(), IndexerError> {
     runner
         .add_plugin(OperationsPlugin::load(runner.store.clone()).await?)
         .await?;
+    runner
+        .add_plugin(EvmLogsPlugin::load(runner.store.clone()).await?)
+        .await?;
     runner.run().await
 }
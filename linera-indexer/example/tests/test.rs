@@ -115,8 +115,8 @@ async fn test_end_to_end_operations_indexer(config: impl LineraNetConfig) {
         .plugins;
     assert_eq!(
         plugins,
-        vec!["operations"],
-        "Indexer plugin 'operations' not loaded",
+        vec!["evm_logs", "operations"],
+        "Indexer plugins 'evm_logs' and 'operations' not loaded",
     );
 
     // making a few transfers
@@ -48,6 +48,8 @@ pub enum IndexerError {
     InvalidCertificateValue(CryptoHash),
     #[error("Clone with root key error")]
     CloneWithRootKeyError,
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
 
     #[cfg(feature = "rocksdb")]
     #[error(transparent)]
@@ -55,6 +55,9 @@ impl RocksDbRunner {
             max_cache_size: config.client.max_cache_size,
             max_entry_size: config.client.max_entry_size,
             max_cache_entries: config.client.max_cache_entries,
+            cache_missing_keys: false,
+            entry_ttl: None,
+            invalidation_channel: None,
         };
         let common_config = CommonStoreConfig {
             max_concurrent_queries: config.client.max_concurrent_queries,
@@ -0,0 +1,253 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{str::FromStr, sync::Arc};
+
+use alloy_primitives::{Address, B256};
+use async_graphql::{ComplexObject, SimpleObject};
+use axum::Router;
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::BlockHeight,
+    identifiers::{ApplicationId, ChainId, GenericApplicationId},
+    vm::{evm_log_stream_name, EvmLog, EvmLogFilter},
+};
+use linera_chain::types::{CertificateValue as _, ConfirmedBlock};
+use linera_indexer::{
+    common::IndexerError,
+    plugin::{load, route, sdl, Plugin},
+};
+use linera_views::{
+    context::{Context, ViewContext},
+    map_view::MapView,
+    store::KeyValueStore,
+    views::RootView,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+/// The maximal number of blocks searched by a single query.
+const MAX_BLOCK_RANGE: u64 = 1000;
+
+/// An EVM log emitted in a block of a chain.
+#[derive(Deserialize, Serialize, Clone, SimpleObject, Debug)]
+#[graphql(complex)]
+pub struct ChainEvmLog {
+    chain_id: ChainId,
+    height: BlockHeight,
+    block: CryptoHash,
+    /// The index of the transaction of the block that emitted the log
+    transaction_index: u32,
+    /// The index of the log in the event stream of the application
+    event_index: u32,
+    application_id: ApplicationId,
+    #[graphql(skip)]
+    log: EvmLog,
+}
+
+#[ComplexObject]
+impl ChainEvmLog {
+    /// Whether the log was emitted when deploying the contract or executing an operation
+    async fn origin(&self) -> &str {
+        &self.log.origin
+    }
+
+    /// The address of the contract that emitted the log, in hexadecimal
+    async fn address(&self) -> String {
+        self.log.address.to_string()
+    }
+
+    /// The topics of the log, in hexadecimal
+    async fn topics(&self) -> Vec<String> {
+        self.log.topics.iter().map(B256::to_string).collect()
+    }
+
+    /// The data of the log, in hexadecimal
+    async fn data(&self) -> String {
+        self.log.data.to_string()
+    }
+}
+
+#[derive(RootView)]
+pub struct EvmLogs<C> {
+    /// The height of the last block registered for each chain
+    last: MapView<C, ChainId, BlockHeight>,
+    /// The EVM logs of the blocks that have some, indexed by chain ID and height
+    logs: MapView<C, (ChainId, BlockHeight), Vec<ChainEvmLog>>,
+}
+
+/// Implements helper functions on the `RootView`
+impl<C> EvmLogs<C>
+where
+    C: Context + Send + Sync + 'static + Clone,
+{
+    /// Registers the EVM logs of a block, unless the block was already registered
+    async fn register_block(&mut self, value: &ConfirmedBlock) -> Result<(), IndexerError> {
+        let chain_id = value.chain_id();
+        let height = value.height();
+        if self
+            .last
+            .get(&chain_id)
+            .await?
+            .is_some_and(|last| last >= height)
+        {
+            return Ok(());
+        }
+        let stream_name = evm_log_stream_name();
+        let mut logs = Vec::new();
+        for (transaction_index, events) in value.block().body.events.iter().enumerate() {
+            for event in events {
+                let GenericApplicationId::User(application_id) = event.stream_id.application_id
+                else {
+                    continue;
+                };
+                if event.stream_id.stream_name != stream_name {
+                    continue;
+                }
+                let log = match EvmLog::from_event_value(&event.value) {
+                    Ok(Some(log)) => log,
+                    // The logs of an older format are not indexed.
+                    Ok(None) => continue,
+                    Err(error) => {
+                        warn!(
+                            "failed to decode an EVM log of {application_id} in block {}: {error}",
+                            value.hash()
+                        );
+                        continue;
+                    }
+                };
+                logs.push(ChainEvmLog {
+                    chain_id,
+                    height,
+                    block: value.hash(),
+                    transaction_index: transaction_index as u32,
+                    event_index: event.index,
+                    application_id,
+                    log,
+                });
+            }
+        }
+        if !logs.is_empty() {
+            info!(
+                "register {} EVM logs for {chain_id:?} at height {height}",
+                logs.len()
+            );
+            self.logs.insert(&(chain_id, height), logs)?;
+        }
+        Ok(self.last.insert(&chain_id, height)?)
+    }
+}
+
+#[derive(Clone)]
+pub struct EvmLogsPlugin<C>(Arc<Mutex<EvmLogs<C>>>);
+
+static NAME: &str = "evm_logs";
+
+/// Implements `Plugin`
+#[async_trait::async_trait]
+impl<S> Plugin<S> for EvmLogsPlugin<ViewContext<(), S>>
+where
+    S: KeyValueStore + Clone + Send + Sync + 'static,
+    S::Error: From<bcs::Error> + Send + Sync + std::error::Error + 'static,
+{
+    fn name(&self) -> String {
+        NAME.to_string()
+    }
+
+    async fn load(store: S) -> Result<Self, IndexerError>
+    where
+        Self: Sized,
+    {
+        Ok(Self(load(store, NAME).await?))
+    }
+
+    async fn register(&self, value: &ConfirmedBlock) -> Result<(), IndexerError> {
+        let mut plugin = self.0.lock().await;
+        plugin.register_block(value).await?;
+        Ok(plugin.save().await?)
+    }
+
+    fn sdl(&self) -> String {
+        sdl(self.clone())
+    }
+
+    fn route(&self, app: Router) -> Router {
+        route(&self.name(), self.clone(), app)
+    }
+}
+
+/// Implements `ObjectType`
+#[async_graphql::Object(cache_control(no_cache))]
+impl<C> EvmLogsPlugin<C>
+where
+    C: Context + Send + Sync + 'static + Clone,
+{
+    /// Gets the EVM logs of a chain between two heights, included, like `eth_getLogs`.
+    /// The heights default to the last block registered. Logs can be filtered by
+    /// application, by the hexadecimal addresses of the contracts and by the hexadecimal
+    /// topics accepted at each position, where `null` accepts any topic
+    pub async fn logs(
+        &self,
+        chain_id: ChainId,
+        from_height: Option<BlockHeight>,
+        to_height: Option<BlockHeight>,
+        application_id: Option<ApplicationId>,
+        addresses: Option<Vec<String>>,
+        topics: Option<Vec<Option<Vec<String>>>>,
+    ) -> Result<Vec<ChainEvmLog>, IndexerError> {
+        let filter = EvmLogFilter {
+            addresses: parse_all::<Address>(addresses.unwrap_or_default())?,
+            topics: topics
+                .unwrap_or_default()
+                .into_iter()
+                .map(|topics| topics.map(parse_all::<B256>).transpose())
+                .collect::<Result<_, _>>()?,
+        };
+        let plugin = self.0.lock().await;
+        let Some(last) = plugin.last.get(&chain_id).await? else {
+            return Ok(Vec::new());
+        };
+        let from = from_height.unwrap_or(last);
+        let to = to_height.unwrap_or(last).min(last);
+        if from > to {
+            return Ok(Vec::new());
+        }
+        if to.0 - from.0 >= MAX_BLOCK_RANGE {
+            return Err(IndexerError::InvalidQuery(format!(
+                "at most {MAX_BLOCK_RANGE} blocks can be searched at once"
+            )));
+        }
+        let mut result = Vec::new();
+        for height in from.0..=to.0 {
+            let Some(logs) = plugin.logs.get(&(chain_id, BlockHeight(height))).await? else {
+                continue;
+            };
+            result.extend(logs.into_iter().filter(|log| {
+                application_id.is_none_or(|id| id == log.application_id) && filter.matches(&log.log)
+            }));
+        }
+        Ok(result)
+    }
+
+    /// Gets the height of the last block registered for a chain
+    pub async fn last(&self, chain_id: ChainId) -> Result<Option<BlockHeight>, IndexerError> {
+        let plugin = self.0.lock().await;
+        Ok(plugin.last.get(&chain_id).await?)
+    }
+}
+
+/// Parses hexadecimal values of a query.
+fn parse_all<T: FromStr>(values: Vec<String>) -> Result<Vec<T>, IndexerError>
+where
+    T::Err: std::fmt::Display,
+{
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|error| IndexerError::InvalidQuery(format!("{value}: {error}")))
+        })
+        .collect()
+}
@@ -3,4 +3,5 @@
 
 //! Plugins for Linera indexer.
 
+pub mod evm_logs;
 pub mod operations;
@@ -1,10 +1,17 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::ffi::OsString;
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
 
 use clap::Parser;
-use linera_base::{crypto::ValidatorPublicKey, identifiers::ChainId};
+use linera_base::{crypto::ValidatorPublicKey, data_types::Timestamp, identifiers::ChainId};
+use linera_core::worker::ChainAssignment;
 use serde::{Deserialize, Serialize};
 
 #[cfg(with_simple_network)]
@@ -93,6 +100,132 @@ impl ShardConfig {
     }
 }
 
+/// The chains that are explicitly assigned to a shard, regardless of their hash, e.g. to give
+/// a hot chain its own shard.
+///
+/// Clones share the same table, so that the assignment of a running validator can be changed
+/// with [`ShardAssignment::update`] without restarting it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "ShardOverrides", into = "ShardOverrides")]
+pub struct ShardAssignment(Arc<RwLock<ShardOverrides>>);
+
+/// The serialized form of a [`ShardAssignment`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ShardOverrides {
+    #[serde(default)]
+    overrides: BTreeMap<ChainId, ShardId>,
+    /// The chains that were moved to another shard, with the time until which no shard
+    /// serves them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    handovers: BTreeMap<ChainId, Timestamp>,
+}
+
+impl From<ShardOverrides> for ShardAssignment {
+    fn from(table: ShardOverrides) -> Self {
+        ShardAssignment(Arc::new(RwLock::new(table)))
+    }
+}
+
+impl From<ShardAssignment> for ShardOverrides {
+    fn from(assignment: ShardAssignment) -> Self {
+        assignment.0.read().unwrap().clone()
+    }
+}
+
+impl PartialEq for ShardAssignment {
+    fn eq(&self, other: &Self) -> bool {
+        *self.0.read().unwrap() == *other.0.read().unwrap()
+    }
+}
+
+impl Eq for ShardAssignment {}
+
+impl ShardAssignment {
+    /// Returns the chains that are explicitly assigned to a shard.
+    pub fn overrides(&self) -> BTreeMap<ChainId, ShardId> {
+        self.0.read().unwrap().overrides.clone()
+    }
+
+    /// Returns the shard that the chain is explicitly assigned to, if any.
+    pub fn get(&self, chain_id: &ChainId) -> Option<ShardId> {
+        self.0.read().unwrap().overrides.get(chain_id).copied()
+    }
+
+    /// Assigns the chain to the given shard, or removes its explicit assignment if `shard_id`
+    /// is `None`.
+    pub fn set(&self, chain_id: ChainId, shard_id: Option<ShardId>) {
+        let overrides = &mut self.0.write().unwrap().overrides;
+        match shard_id {
+            Some(shard_id) => overrides.insert(chain_id, shard_id),
+            None => overrides.remove(&chain_id),
+        };
+    }
+
+    /// Assigns the chain like [`ShardAssignment::set`], but lets no shard serve it before
+    /// `until`: this gives the shard that served it so far the time to learn about the move
+    /// and to complete its requests for the chain. Handovers that are over at `now` are
+    /// forgotten.
+    pub fn hand_over(
+        &self,
+        chain_id: ChainId,
+        shard_id: Option<ShardId>,
+        now: Timestamp,
+        until: Timestamp,
+    ) {
+        self.set(chain_id, shard_id);
+        let handovers = &mut self.0.write().unwrap().handovers;
+        handovers.retain(|_, end| *end > now);
+        handovers.insert(chain_id, until);
+    }
+
+    /// Returns the time until which no shard serves the chain, if it is later than `now`.
+    pub fn handover_end(&self, chain_id: &ChainId, now: Timestamp) -> Option<Timestamp> {
+        self.0
+            .read()
+            .unwrap()
+            .handovers
+            .get(chain_id)
+            .copied()
+            .filter(|end| *end > now)
+    }
+
+    /// Removes the explicit assignments to shards that are not in `0..num_shards`, and
+    /// returns the chains that they were about.
+    pub fn retain_shards(&self, num_shards: usize) -> Vec<ChainId> {
+        let mut removed = Vec::new();
+        self.0
+            .write()
+            .unwrap()
+            .overrides
+            .retain(|chain_id, shard_id| {
+                let keep = *shard_id < num_shards;
+                if !keep {
+                    removed.push(*chain_id);
+                }
+                keep
+            });
+        removed
+    }
+
+    /// Replaces the explicit assignments and the handovers with the ones of `other`, and
+    /// returns the chains whose assignment changed.
+    pub fn update(&self, other: &ShardAssignment) -> Vec<ChainId> {
+        let new_table = ShardOverrides::from(other.clone());
+        let mut table = self.0.write().unwrap();
+        let changed = table
+            .overrides
+            .keys()
+            .chain(new_table.overrides.keys())
+            .filter(|chain_id| table.overrides.get(chain_id) != new_table.overrides.get(chain_id))
+            .copied()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        *table = new_table;
+        changed
+    }
+}
+
 /// The network protocol.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NetworkProtocol {
@@ -107,7 +240,33 @@ pub enum TlsConfig {
     Tls,
 }
 
+/// The files with the certificates used to secure connections with TLS.
+///
+/// The files are read again while the validator is running, so that the certificates can be
+/// renewed without restarting it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsCertificatesConfig {
+    /// The PEM file with the certificate chain to present to peers.
+    pub certificate_path: PathBuf,
+    /// The PEM file with the private key of the certificate.
+    pub private_key_path: PathBuf,
+    /// The PEM file with the certificate authorities to trust instead of the public ones. If
+    /// set, servers only accept clients that present a certificate signed by one of them
+    /// (mutual TLS).
+    #[serde(default)]
+    pub ca_certificate_path: Option<PathBuf>,
+}
+
 impl NetworkProtocol {
+    /// Returns whether connections with this protocol are secured with TLS.
+    pub fn uses_tls(&self) -> bool {
+        match self {
+            #[cfg(with_simple_network)]
+            NetworkProtocol::Simple(transport) => *transport == simple::TransportProtocol::Tls,
+            NetworkProtocol::Grpc(tls) => *tls == TlsConfig::Tls,
+        }
+    }
+
     fn scheme(&self) -> &'static str {
         match self {
             #[cfg(with_simple_network)]
@@ -133,9 +292,17 @@ pub struct ValidatorInternalNetworkPreConfig<P> {
     pub public_key: ValidatorPublicKey,
     /// The network protocol to use for all shards.
     pub protocol: P,
-    /// The available shards. Each chain UID is mapped to a unique shard in the vector in
-    /// a static way.
+    /// The available shards. Each chain UID is mapped to a unique shard in the vector by
+    /// rendezvous hashing, unless the shard assignment says otherwise.
     pub shards: Vec<ShardConfig>,
+    /// The chains that are explicitly assigned to a shard.
+    #[serde(default)]
+    pub shard_assignment: ShardAssignment,
+    /// The certificates of the proxy and the shards, if the internal protocol uses TLS.
+    /// Every node presents its certificate to the others, so the shards can be run on an
+    /// untrusted network when a CA certificate is set.
+    #[serde(default)]
+    pub tls: Option<TlsCertificatesConfig>,
     /// The host name of the proxy on the internal network (IP or hostname).
     pub host: String,
     /// The port the proxy listens on the internal network.
@@ -150,6 +317,8 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
             public_key: self.public_key,
             protocol,
             shards: self.shards.clone(),
+            shard_assignment: self.shard_assignment.clone(),
+            tls: self.tls.clone(),
             host: self.host.clone(),
             port: self.port,
             metrics_port: self.metrics_port,
@@ -161,6 +330,10 @@ impl ValidatorInternalNetworkConfig {
     pub fn proxy_address(&self) -> String {
         format!("{}://{}:{}", self.protocol.scheme(), self.host, self.port)
     }
+
+    pub fn shard_address(&self, shard: &ShardConfig) -> String {
+        format!("{}://{}:{}", self.protocol.scheme(), shard.host, shard.port)
+    }
 }
 
 impl ValidatorPublicNetworkConfig {
@@ -223,7 +396,7 @@ where
         let parts = s.split(':').collect::<Vec<_>>();
         anyhow::ensure!(
             parts.len() == 3,
-            "Expecting format `(tcp|udp|grpc|grpcs):host:port`"
+            "Expecting format `(tcp|udp|tls|grpc|grpcs):host:port`"
         );
         let protocol = parts[0].parse().map_err(|s| anyhow::anyhow!("{}", s))?;
         let host = parts[1].to_owned();
@@ -253,14 +426,48 @@ impl std::str::FromStr for NetworkProtocol {
 }
 
 impl<P> ValidatorInternalNetworkPreConfig<P> {
-    /// Static shard assignment
+    /// Returns the shard that the chain is assigned to.
+    ///
+    /// Unless the chain is explicitly assigned to a shard, this is the shard with the highest
+    /// score for the chain (rendezvous hashing). Shards are identified by their address, so
+    /// adding or removing a shard only moves the chains that are assigned to it.
     pub fn get_shard_id(&self, chain_id: ChainId) -> ShardId {
-        use std::hash::{Hash, Hasher};
-        let mut s = std::collections::hash_map::DefaultHasher::new();
-        // Use the validator public key to randomise shard assignment.
-        self.public_key.hash(&mut s);
-        chain_id.hash(&mut s);
-        (s.finish() as ShardId) % self.shards.len()
+        if let Some(shard_id) = self.shard_assignment.get(&chain_id) {
+            if shard_id < self.shards.len() {
+                return shard_id;
+            }
+        }
+        self.shards
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, shard)| {
+                let mut s = std::collections::hash_map::DefaultHasher::new();
+                // Use the validator public key to randomise shard assignment.
+                self.public_key.hash(&mut s);
+                chain_id.hash(&mut s);
+                shard.host.hash(&mut s);
+                shard.port.hash(&mut s);
+                s.finish()
+            })
+            .map(|(shard_id, _)| shard_id)
+            .expect("validators have at least one shard")
+    }
+
+    /// Returns whether the shard serves the chain at the given time. A chain that was moved
+    /// is not served by any shard until its handover is over.
+    pub fn chain_assignment(
+        &self,
+        shard_id: ShardId,
+        chain_id: ChainId,
+        now: Timestamp,
+    ) -> ChainAssignment {
+        if self.get_shard_id(chain_id) != shard_id {
+            return ChainAssignment::NotServed;
+        }
+        match self.shard_assignment.handover_end(&chain_id, now) {
+            Some(end) => ChainAssignment::HandedOverUntil(end),
+            None => ChainAssignment::Served,
+        }
     }
 
     pub fn shard(&self, shard_id: ShardId) -> &ShardConfig {
@@ -273,6 +480,116 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
     }
 }
 
+#[cfg(test)]
+fn test_network_config(num_shards: u16) -> ValidatorInternalNetworkPreConfig<()> {
+    ValidatorInternalNetworkPreConfig {
+        public_key: ValidatorPublicKey::test_key(0),
+        protocol: (),
+        shards: (0..num_shards)
+            .map(|index| ShardConfig {
+                host: format!("shard-{index}"),
+                port: 19100 + index,
+                metrics_port: None,
+            })
+            .collect(),
+        shard_assignment: ShardAssignment::default(),
+        tls: None,
+        host: "proxy".to_string(),
+        port: 19000,
+        metrics_port: 21100,
+    }
+}
+
+#[test]
+fn adding_a_shard_only_moves_chains_to_it() {
+    let mut config = test_network_config(4);
+    let chain_ids = (0..200).map(ChainId::root).collect::<Vec<_>>();
+    let before = chain_ids
+        .iter()
+        .map(|chain_id| config.get_shard_id(*chain_id))
+        .collect::<Vec<_>>();
+    config.shards.push(ShardConfig {
+        host: "shard-4".to_string(),
+        port: 19104,
+        metrics_port: None,
+    });
+    let mut moved = 0;
+    for (chain_id, shard_id) in chain_ids.iter().zip(before) {
+        let new_shard_id = config.get_shard_id(*chain_id);
+        if new_shard_id != shard_id {
+            assert_eq!(new_shard_id, 4);
+            moved += 1;
+        }
+    }
+    assert!(moved > 0 && moved < 100, "{moved} chains moved");
+}
+
+#[test]
+fn shard_assignment_overrides() {
+    let config = test_network_config(3);
+    let chain_id = ChainId::root(0);
+    let shard_id = config.get_shard_id(chain_id);
+    let other_shard_id = (shard_id + 1) % 3;
+
+    // Clones of the configuration share the assignment.
+    let clone = config.clone();
+    let new_assignment = ShardAssignment::default();
+    new_assignment.set(chain_id, Some(other_shard_id));
+    assert_eq!(
+        config.shard_assignment.update(&new_assignment),
+        vec![chain_id]
+    );
+    assert_eq!(clone.get_shard_id(chain_id), other_shard_id);
+    assert!(config.shard_assignment.update(&new_assignment).is_empty());
+
+    // Assignments to missing shards are ignored.
+    config.shard_assignment.set(chain_id, Some(7));
+    assert_eq!(config.get_shard_id(chain_id), shard_id);
+    assert_eq!(config.shard_assignment.retain_shards(3), vec![chain_id]);
+}
+
+#[test]
+fn shard_assignment_handover() {
+    let config = test_network_config(3);
+    let chain_id = ChainId::root(0);
+    let shard_id = config.get_shard_id(chain_id);
+    let other_shard_id = (shard_id + 1) % 3;
+    let now = Timestamp::from(1_000);
+    let until = Timestamp::from(2_000);
+    assert_eq!(
+        config.chain_assignment(shard_id, chain_id, now),
+        ChainAssignment::Served
+    );
+
+    // The previous shard stops serving the chain at once, the new one after the handover.
+    config
+        .shard_assignment
+        .hand_over(chain_id, Some(other_shard_id), now, until);
+    assert_eq!(
+        config.chain_assignment(shard_id, chain_id, now),
+        ChainAssignment::NotServed
+    );
+    assert_eq!(
+        config.chain_assignment(other_shard_id, chain_id, now),
+        ChainAssignment::HandedOverUntil(until)
+    );
+    assert_eq!(
+        config.chain_assignment(other_shard_id, chain_id, until),
+        ChainAssignment::Served
+    );
+
+    // Handovers are forgotten once they are over.
+    config
+        .shard_assignment
+        .hand_over(ChainId::root(1), None, until, Timestamp::from(3_000));
+    assert_eq!(
+        config
+            .shard_assignment
+            .handover_end(&chain_id, Timestamp::from(0)),
+        None
+    );
+}
+
 #[test]
 fn cross_chain_config_to_args() {
     let config = CrossChainConfig::default();
@@ -265,6 +265,7 @@ impl ValidatorNode for GrpcClient {
         let mut retry_count = 0;
         let subscription_request = SubscriptionRequest {
             chain_ids: chains.into_iter().map(|chain| chain.into()).collect(),
+            with_events: true,
         };
         let mut client = self.client.clone();
 
@@ -304,10 +305,14 @@ impl ValidatorNode for GrpcClient {
         // terminates after unexpected or fatal errors.
         let notification_stream = endlessly_retrying_notification_stream
             .map(|result| {
-                Option::<Notification>::try_from(result?).map_err(|err| {
-                    let message = format!("Could not deserialize notification: {}", err);
-                    tonic::Status::new(Code::Internal, message)
-                })
+                // Notifications that we cannot decode, e.g. of a kind added by a newer
+                // validator, are skipped.
+                let notification =
+                    Option::<Notification>::try_from(result?).unwrap_or_else(|err| {
+                        warn!("Could not deserialize notification: {}", err);
+                        None
+                    });
+                Ok::<_, Status>(notification)
             })
             .take_while(move |result| {
                 let Err(status) = result else {
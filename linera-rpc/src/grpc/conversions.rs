@@ -1057,6 +1057,7 @@ pub mod tests {
             requested_sent_certificate_hashes: vec![],
             count_received_log: 0,
             requested_received_log: vec![],
+            pruned_height: None,
         });
 
         let chain_info_response_none = ChainInfoResponse {
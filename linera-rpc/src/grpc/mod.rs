@@ -36,6 +36,10 @@ pub enum GrpcError {
     #[cfg(with_server)]
     #[error(transparent)]
     Reflection(#[from] tonic_reflection::server::Error),
+
+    #[cfg(with_server)]
+    #[error("failed to listen for connections: {0}")]
+    Listen(#[from] std::io::Error),
 }
 
 const MEBIBYTE: usize = 1024 * 1024;
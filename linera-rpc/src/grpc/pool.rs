@@ -5,12 +5,15 @@ use dashmap::DashMap;
 use linera_base::time::Duration;
 
 use super::{transport, GrpcError};
+#[cfg(not(web))]
+use crate::tls::TlsCertificates;
 
 /// A pool of transport channels to be used by gRPC.
 #[derive(Clone, Default)]
 pub struct GrpcConnectionPool {
     options: transport::Options,
-    channels: DashMap<String, transport::Channel>,
+    /// The channels, with the generation of the certificates they were created with.
+    channels: DashMap<String, (u64, transport::Channel)>,
 }
 
 impl GrpcConnectionPool {
@@ -31,14 +34,60 @@ impl GrpcConnectionPool {
         self
     }
 
+    #[cfg(not(web))]
+    pub fn with_certificates(mut self, certificates: Option<TlsCertificates>) -> Self {
+        self.options.certificates = certificates;
+        self
+    }
+
     /// Obtains a channel for the current address. Either clones an existing one (thereby
     /// reusing the connection), or creates one if needed. New channels do not create a
-    /// connection immediately.
+    /// connection immediately. Channels created before the certificates were renewed are
+    /// replaced.
     pub fn channel(&self, address: String) -> Result<transport::Channel, GrpcError> {
-        Ok(self
+        let generation = self.options.certificates_generation();
+        let mut entry = self
             .channels
             .entry(address.clone())
-            .or_try_insert_with(|| transport::create_channel(address, &self.options))?
-            .clone())
+            .or_try_insert_with(|| {
+                Ok::<_, GrpcError>((
+                    generation,
+                    transport::create_channel(address.clone(), &self.options)?,
+                ))
+            })?;
+        if entry.0 != generation {
+            *entry = (
+                generation,
+                transport::create_channel(address, &self.options)?,
+            );
+        }
+        Ok(entry.1.clone())
+    }
+}
+
+#[cfg(all(test, not(web)))]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::tls::test_utils::TestAuthority;
+
+    #[tokio::test]
+    async fn channels_are_replaced_when_certificates_are_renewed() {
+        let authority = TestAuthority::new("authority");
+        let directory = TempDir::new().unwrap();
+        let certificates =
+            TlsCertificates::load(authority.write_certificates(directory.path(), &authority))
+                .unwrap();
+        let pool = GrpcConnectionPool::default().with_certificates(Some(certificates.clone()));
+        let address = "https://localhost:9000".to_owned();
+        pool.channel(address.clone()).unwrap();
+        pool.channel(address.clone()).unwrap();
+        assert_eq!(pool.channels.get(&address).unwrap().0, 0);
+
+        authority.write_certificates(directory.path(), &authority);
+        assert!(certificates.reload().unwrap());
+        pool.channel(address.clone()).unwrap();
+        assert_eq!(pool.channels.get(&address).unwrap().0, 1);
     }
 }
@@ -23,7 +23,7 @@ use linera_core::{
 };
 use linera_storage::Storage;
 use rand::Rng;
-use tokio::{sync::oneshot, task::JoinSet};
+use tokio::{net::TcpListener, sync::oneshot, task::JoinSet};
 use tokio_util::sync::CancellationToken;
 use tonic::{Request, Response, Status};
 use tower::{builder::ServiceBuilder, Layer, Service};
@@ -50,6 +50,7 @@ use super::{
 };
 use crate::{
     config::{CrossChainConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig},
+    tls::TlsCertificates,
     HandleConfirmedCertificateRequest, HandleLiteCertRequest, HandleTimeoutCertificateRequest,
     HandleValidatedCertificateRequest,
 };
@@ -200,6 +201,7 @@ where
         internal_network: ValidatorInternalNetworkConfig,
         cross_chain_config: CrossChainConfig,
         notification_config: NotificationConfig,
+        certificates: Option<TlsCertificates>,
         shutdown_signal: CancellationToken,
         join_set: &mut JoinSet<()>,
     ) -> GrpcServerHandle {
@@ -228,6 +230,7 @@ where
                 cross_chain_config.sender_failure_rate,
                 cross_chain_config.max_concurrent_tasks,
                 shard_id,
+                certificates.clone(),
                 cross_chain_receiver,
             )
         });
@@ -240,6 +243,7 @@ where
             Self::forward_notifications(
                 state.nickname().to_string(),
                 internal_network.proxy_address(),
+                certificates.clone(),
                 notification_receiver,
             )
         });
@@ -269,7 +273,7 @@ where
                 .set_serving::<ValidatorWorkerServer<Self>>()
                 .await;
 
-            tonic::transport::Server::builder()
+            let router = tonic::transport::Server::builder()
                 .layer(
                     ServiceBuilder::new()
                         .layer(GrpcPrometheusMetricsMiddlewareLayer)
@@ -277,9 +281,24 @@ where
                 )
                 .add_service(health_service)
                 .add_service(reflection_service)
-                .add_service(worker_node)
-                .serve_with_shutdown(server_address, shutdown_signal.cancelled_owned())
-                .await?;
+                .add_service(worker_node);
+
+            match certificates {
+                Some(certificates) => {
+                    let listener = TcpListener::bind(server_address).await?;
+                    router
+                        .serve_with_incoming_shutdown(
+                            certificates.grpc_incoming(listener),
+                            shutdown_signal.cancelled_owned(),
+                        )
+                        .await?
+                }
+                None => {
+                    router
+                        .serve_with_shutdown(server_address, shutdown_signal.cancelled_owned())
+                        .await?
+                }
+            }
 
             Ok(())
         });
@@ -289,20 +308,22 @@ where
 
     /// Continuously waits for receiver to receive a notification which is then sent to
     /// the proxy.
-    #[instrument(skip(receiver))]
+    #[instrument(skip(certificates, receiver))]
     async fn forward_notifications(
         nickname: String,
         proxy_address: String,
+        certificates: Option<TlsCertificates>,
         mut receiver: Receiver<Notification>,
     ) {
-        let channel = tonic::transport::Channel::from_shared(proxy_address.clone())
-            .expect("Proxy URI should be valid")
-            .connect_lazy();
-        let mut client = NotifierServiceClient::new(channel)
-            .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
-            .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
+        let pool = GrpcConnectionPool::default().with_certificates(certificates);
 
         while let Some(notification) = receiver.next().await {
+            let channel = pool
+                .channel(proxy_address.clone())
+                .expect("Proxy URI should be valid");
+            let mut client = NotifierServiceClient::new(channel)
+                .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
+                .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
             let notification: api::Notification = match notification.clone().try_into() {
                 Ok(notification) => notification,
                 Err(error) => {
@@ -370,15 +391,16 @@ where
         cross_chain_sender_failure_rate: f32,
         cross_chain_max_concurrent_tasks: usize,
         this_shard: ShardId,
+        certificates: Option<TlsCertificates>,
         receiver: mpsc::Receiver<(linera_core::data_types::CrossChainRequest, ShardId)>,
     ) {
-        let pool = GrpcConnectionPool::default();
+        let pool = GrpcConnectionPool::default().with_certificates(certificates);
         let max_concurrent_tasks = Some(cross_chain_max_concurrent_tasks);
 
         receiver
             .for_each_concurrent(max_concurrent_tasks, |(cross_chain_request, shard_id)| {
                 let shard = network.shard(shard_id);
-                let remote_address = shard.http_address();
+                let remote_address = network.shard_address(shard);
 
                 let pool = pool.clone();
                 let nickname = nickname.clone();
@@ -1,12 +1,18 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(not(web))]
+use crate::tls::TlsCertificates;
 use crate::NodeOptions;
 
 #[derive(Clone, Debug, Default)]
 pub struct Options {
     pub connect_timeout: Option<linera_base::time::Duration>,
     pub timeout: Option<linera_base::time::Duration>,
+    /// The certificates to present to TLS servers. Without them, the public certificate
+    /// authorities are trusted.
+    #[cfg(not(web))]
+    pub certificates: Option<TlsCertificates>,
 }
 
 impl From<&'_ NodeOptions> for Options {
@@ -14,10 +20,23 @@ impl From<&'_ NodeOptions> for Options {
         Self {
             connect_timeout: Some(node_options.send_timeout),
             timeout: Some(node_options.recv_timeout),
+            #[cfg(not(web))]
+            certificates: None,
         }
     }
 }
 
+impl Options {
+    /// Returns the generation of the certificates that new channels are created with.
+    pub(crate) fn certificates_generation(&self) -> u64 {
+        #[cfg(not(web))]
+        if let Some(certificates) = &self.certificates {
+            return certificates.generation();
+        }
+        0
+    }
+}
+
 cfg_if::cfg_if! {
     if #[cfg(web)] {
         pub use tonic_web_wasm_client::{Client as Channel, Error};
@@ -33,8 +52,12 @@ cfg_if::cfg_if! {
             address: String,
             options: &Options,
         ) -> Result<Channel, Error> {
+            let tls_config = match &options.certificates {
+                Some(certificates) => certificates.grpc_client_config(),
+                None => tonic::transport::channel::ClientTlsConfig::default().with_webpki_roots(),
+            };
             let mut endpoint = tonic::transport::Endpoint::from_shared(address)?
-                .tls_config(tonic::transport::channel::ClientTlsConfig::default().with_webpki_roots())?;
+                .tls_config(tls_config)?;
 
             if let Some(timeout) = options.connect_timeout {
                 endpoint = endpoint.connect_timeout(timeout);
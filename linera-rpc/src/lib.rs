@@ -18,6 +18,8 @@ mod message;
 pub mod simple;
 
 pub mod grpc;
+#[cfg(not(web))]
+pub mod tls;
 
 pub use client::Client;
 pub use message::RpcMessage;
@@ -51,8 +51,11 @@ impl SimpleClient {
     }
 
     async fn send_recv_internal(&self, message: RpcMessage) -> Result<RpcMessage, codec::Error> {
-        let address = format!("{}:{}", self.network.host, self.network.port);
-        let mut stream = self.network.protocol.connect(address).await?;
+        let mut stream = self
+            .network
+            .protocol
+            .connect(&self.network.host, self.network.port, None)
+            .await?;
         // Send message
         timer::timeout(self.send_timeout, stream.send(message))
             .await
@@ -18,6 +18,7 @@ use tracing::{debug, error, info, instrument, warn};
 use super::transport::{MessageHandler, ServerHandle, TransportProtocol};
 use crate::{
     config::{CrossChainConfig, ShardId, ValidatorInternalNetworkPreConfig},
+    tls::TlsCertificates,
     RpcMessage,
 };
 
@@ -32,6 +33,7 @@ where
     state: WorkerState<S>,
     shard_id: ShardId,
     cross_chain_config: CrossChainConfig,
+    certificates: Option<TlsCertificates>,
     // Stats
     packets_processed: u64,
     user_errors: u64,
@@ -48,6 +50,7 @@ where
         state: WorkerState<S>,
         shard_id: ShardId,
         cross_chain_config: CrossChainConfig,
+        certificates: Option<TlsCertificates>,
     ) -> Self {
         Self {
             network,
@@ -56,6 +59,7 @@ where
             state,
             shard_id,
             cross_chain_config,
+            certificates,
             packets_processed: 0,
             user_errors: 0,
         }
@@ -83,11 +87,12 @@ where
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
         this_shard: ShardId,
+        certificates: Option<TlsCertificates>,
         mut receiver: mpsc::Receiver<(RpcMessage, ShardId)>,
     ) {
         let mut pool = network
             .protocol
-            .make_outgoing_connection_pool()
+            .make_outgoing_connection_pool(certificates)
             .await
             .expect("Initialization should not fail");
 
@@ -163,16 +168,18 @@ where
             Duration::from_millis(self.cross_chain_config.sender_delay_ms),
             self.cross_chain_config.sender_failure_rate,
             self.shard_id,
+            self.certificates.clone(),
             cross_chain_receiver,
         ));
 
         let protocol = self.network.protocol;
+        let certificates = self.certificates.clone();
         let state = RunningServerState {
             server: self,
             cross_chain_sender,
         };
         // Launch server for the appropriate protocol.
-        protocol.spawn_server(address, state, shutdown_signal, join_set)
+        protocol.spawn_server(address, state, certificates, shutdown_signal, join_set)
     }
 }
 
@@ -18,11 +18,13 @@ use tokio::{
     sync::Mutex,
     task::JoinSet,
 };
-use tokio_util::{codec::Framed, sync::CancellationToken, udp::UdpFramed};
+use tokio_rustls::{client, server};
+use tokio_util::{codec::Framed, either::Either, sync::CancellationToken, udp::UdpFramed};
 use tracing::{error, warn};
 
 use crate::{
     simple::{codec, codec::Codec},
+    tls::{self, TlsCertificates},
     RpcMessage,
 };
 
@@ -32,11 +34,19 @@ pub const DEFAULT_MAX_DATAGRAM_SIZE: &str = "65507";
 /// Number of tasks to spawn before attempting to reap some finished tasks to prevent memory leaks.
 const REAP_TASKS_THRESHOLD: usize = 100;
 
+/// An outgoing TCP connection, possibly secured with TLS.
+type TcpClientStream = Either<TcpStream, client::TlsStream<TcpStream>>;
+
+/// An incoming TCP connection, possibly secured with TLS.
+type TcpServerStream = Either<TcpStream, server::TlsStream<TcpStream>>;
+
 // Supported transport protocols.
 #[derive(clap::ValueEnum, Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TransportProtocol {
     Udp,
     Tcp,
+    /// TCP secured with TLS.
+    Tls,
 }
 
 impl std::str::FromStr for TransportProtocol {
@@ -58,6 +68,7 @@ impl TransportProtocol {
         match self {
             TransportProtocol::Udp => "udp",
             TransportProtocol::Tcp => "tcp",
+            TransportProtocol::Tls => "tls",
         }
     }
 }
@@ -114,11 +125,16 @@ impl<T> Transport for T where
 
 impl TransportProtocol {
     /// Creates a transport for this protocol.
+    ///
+    /// With TLS, the connection presents the given certificates if any, and otherwise
+    /// trusts the public certificate authorities.
     pub async fn connect(
         self,
-        address: impl ToSocketAddrs,
+        host: &str,
+        port: u16,
+        certificates: Option<&TlsCertificates>,
     ) -> Result<impl Transport, std::io::Error> {
-        let mut addresses = lookup_host(address)
+        let mut addresses = lookup_host((host, port))
             .await
             .expect("Invalid address to connect to");
         let address = addresses
@@ -137,43 +153,80 @@ impl TransportProtocol {
             TransportProtocol::Tcp => {
                 let stream = TcpStream::connect(address).await?;
 
-                Framed::new(stream, Codec).right_stream()
+                Framed::new(Either::Left(stream), Codec).right_stream()
+            }
+            TransportProtocol::Tls => {
+                let stream = TcpStream::connect(address).await?;
+                let stream = connect_tls(host, stream, certificates).await?;
+
+                Framed::new(Either::Right(stream), Codec).right_stream()
             }
         };
 
         Ok(stream)
     }
 
-    /// Creates a [`ConnectionPool`] for this protocol.
+    /// Creates a [`ConnectionPool`] for this protocol. With TLS, the connections present the
+    /// given certificates if any.
     pub async fn make_outgoing_connection_pool(
         self,
+        certificates: Option<TlsCertificates>,
     ) -> Result<Box<dyn ConnectionPool>, std::io::Error> {
         let pool: Box<dyn ConnectionPool> = match self {
             Self::Udp => Box::new(UdpConnectionPool::new().await?),
-            Self::Tcp => Box::new(TcpConnectionPool::new().await?),
+            Self::Tcp => Box::new(TcpConnectionPool::new(false, None).await?),
+            Self::Tls => Box::new(TcpConnectionPool::new(true, certificates).await?),
         };
         Ok(pool)
     }
 
     /// Runs a server for this protocol and the given message handler.
+    ///
+    /// Serving TLS requires the server's `certificates`; they are ignored by the other
+    /// protocols.
     pub fn spawn_server<S>(
         self,
         address: impl ToSocketAddrs + Send + 'static,
         state: S,
+        certificates: Option<TlsCertificates>,
         shutdown_signal: CancellationToken,
         join_set: &mut JoinSet<()>,
     ) -> ServerHandle
     where
         S: MessageHandler + Send + 'static,
     {
-        let handle = match self {
-            Self::Udp => join_set.spawn_task(UdpServer::run(address, state, shutdown_signal)),
-            Self::Tcp => join_set.spawn_task(TcpServer::run(address, state, shutdown_signal)),
+        let handle = match (self, certificates) {
+            (Self::Udp, _) => join_set.spawn_task(UdpServer::run(address, state, shutdown_signal)),
+            (Self::Tcp, _) => {
+                join_set.spawn_task(TcpServer::run(address, state, None, shutdown_signal))
+            }
+            (Self::Tls, Some(certificates)) => join_set.spawn_task(TcpServer::run(
+                address,
+                state,
+                Some(certificates),
+                shutdown_signal,
+            )),
+            (Self::Tls, None) => join_set.spawn_task(future::ready(Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "serving TLS requires certificates",
+            )))),
         };
         ServerHandle { handle }
     }
 }
 
+/// Performs the client side of the TLS handshake with `host`.
+async fn connect_tls(
+    host: &str,
+    stream: TcpStream,
+    certificates: Option<&TlsCertificates>,
+) -> Result<client::TlsStream<TcpStream>, io::Error> {
+    match certificates {
+        Some(certificates) => certificates.connect(host, stream).await,
+        None => tls::connect_public(host, stream).await,
+    }
+}
+
 /// An implementation of [`ConnectionPool`] based on UDP.
 struct UdpConnectionPool {
     transport: UdpFramed<Codec>,
@@ -313,26 +366,47 @@ where
     }
 }
 
-/// An implementation of [`ConnectionPool`] based on TCP.
+/// An implementation of [`ConnectionPool`] based on TCP, possibly secured with TLS.
 struct TcpConnectionPool {
-    streams: HashMap<String, Framed<TcpStream, Codec>>,
+    /// The open connections, with the generation of the certificates they were opened with.
+    streams: HashMap<String, (u64, Framed<TcpClientStream, Codec>)>,
+    use_tls: bool,
+    certificates: Option<TlsCertificates>,
 }
 
 impl TcpConnectionPool {
-    async fn new() -> Result<Self, std::io::Error> {
+    async fn new(
+        use_tls: bool,
+        certificates: Option<TlsCertificates>,
+    ) -> Result<Self, std::io::Error> {
         let streams = HashMap::new();
-        Ok(Self { streams })
+        Ok(Self {
+            streams,
+            use_tls,
+            certificates,
+        })
     }
 
     async fn get_stream(
         &mut self,
         address: &str,
-    ) -> Result<&mut Framed<TcpStream, Codec>, io::Error> {
-        if !self.streams.contains_key(address) {
-            match TcpStream::connect(address).await {
-                Ok(s) => {
-                    self.streams
-                        .insert(address.to_string(), Framed::new(s, Codec));
+    ) -> Result<&mut Framed<TcpClientStream, Codec>, io::Error> {
+        // Connections opened with certificates that were since renewed are opened again.
+        let generation = self
+            .certificates
+            .as_ref()
+            .map_or(0, TlsCertificates::generation);
+        if self
+            .streams
+            .get(address)
+            .is_none_or(|(stream_generation, _)| *stream_generation != generation)
+        {
+            match self.open_stream(address).await {
+                Ok(stream) => {
+                    self.streams.insert(
+                        address.to_string(),
+                        (generation, Framed::new(stream, Codec)),
+                    );
                 }
                 Err(error) => {
                     error!("Failed to open connection to {}: {}", address, error);
@@ -340,7 +414,19 @@ impl TcpConnectionPool {
                 }
             };
         };
-        Ok(self.streams.get_mut(address).unwrap())
+        Ok(&mut self.streams.get_mut(address).unwrap().1)
+    }
+
+    async fn open_stream(&self, address: &str) -> Result<TcpClientStream, io::Error> {
+        let stream = TcpStream::connect(address).await?;
+        if !self.use_tls {
+            return Ok(Either::Left(stream));
+        }
+        let host = address
+            .rsplit_once(':')
+            .map_or(address, |(host, _port)| host);
+        let stream = connect_tls(host, stream, self.certificates.as_ref()).await?;
+        Ok(Either::Right(stream))
     }
 }
 
@@ -361,9 +447,9 @@ impl ConnectionPool for TcpConnectionPool {
     }
 }
 
-/// Server implementation for TCP.
+/// Server implementation for TCP, possibly secured with TLS.
 pub struct TcpServer<State> {
-    connection: Framed<TcpStream, Codec>,
+    connection: Framed<TcpServerStream, Codec>,
     handler: State,
     shutdown_signal: CancellationToken,
 }
@@ -375,10 +461,11 @@ where
     /// Runs the TCP server implementation.
     ///
     /// Listens for connections and spawns a task with a new [`TcpServer`] instance to serve that
-    /// client.
+    /// client. If `certificates` are provided, the connections are secured with TLS.
     pub async fn run(
         address: impl ToSocketAddrs,
         handler: State,
+        certificates: Option<TlsCertificates>,
         shutdown_signal: CancellationToken,
     ) -> Result<(), std::io::Error> {
         let listener = TcpListener::bind(address).await?;
@@ -401,12 +488,24 @@ where
                 }
                 maybe_socket = accept_stream.next() => match maybe_socket {
                     Some(Ok(socket)) => {
-                        let server = TcpServer::new_connection(
-                            socket,
-                            handler.clone(),
-                            connection_shutdown_signal.clone(),
-                        );
-                        join_set.spawn_task(server.serve());
+                        let handler = handler.clone();
+                        let connection_shutdown_signal = connection_shutdown_signal.clone();
+                        let certificates = certificates.clone();
+                        join_set.spawn_task(async move {
+                            let stream = match certificates {
+                                None => Either::Left(socket),
+                                Some(certificates) => match certificates.accept(socket).await {
+                                    Ok(stream) => Either::Right(stream),
+                                    Err(error) => {
+                                        warn!("TLS handshake failed: {error}");
+                                        return;
+                                    }
+                                },
+                            };
+                            TcpServer::new_connection(stream, handler, connection_shutdown_signal)
+                                .serve()
+                                .await
+                        });
                         reap_countdown -= 1;
                     }
                     Some(Err(error)) => {
@@ -427,9 +526,9 @@ where
     }
 
     /// Creates a new [`TcpServer`] to serve a single connection established on the provided
-    /// [`TcpStream`].
+    /// stream.
     fn new_connection(
-        tcp_stream: TcpStream,
+        tcp_stream: TcpServerStream,
         handler: State,
         shutdown_signal: CancellationToken,
     ) -> Self {
@@ -447,8 +546,11 @@ where
                 _ = self.shutdown_signal.cancelled() => {
                     let mut tcp_stream = self.connection.into_inner();
                     if let Err(error) = tcp_stream.shutdown().await {
-                        let peer = tcp_stream
-                            .peer_addr()
+                        let peer = match &tcp_stream {
+                            Either::Left(stream) => stream.peer_addr(),
+                            Either::Right(stream) => stream.get_ref().0.peer_addr(),
+                        };
+                        let peer = peer
                             .map(|address| address.to_string())
                             .unwrap_or_else(|_| "an unknown peer".to_owned());
                         warn!("Failed to close connection to {peer}: {error:?}");
@@ -491,3 +593,73 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tempfile::TempDir;
+    use tokio_rustls::rustls::pki_types::CertificateDer;
+
+    use super::*;
+    use crate::tls::test_utils::TestAuthority;
+
+    /// Accepts a connection on `listener`, and returns it with the client's certificate.
+    async fn accept(
+        listener: &TcpListener,
+        certificates: &TlsCertificates,
+    ) -> (server::TlsStream<TcpStream>, CertificateDer<'static>) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let stream = certificates.accept(stream).await.unwrap();
+        let certificate = stream.get_ref().1.peer_certificates().unwrap()[0].clone();
+        (stream, certificate)
+    }
+
+    #[tokio::test]
+    async fn tls_connections_are_opened_again_when_certificates_are_renewed() {
+        let authority = TestAuthority::new("authority");
+        let (server_directory, client_directory) =
+            (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let server = TlsCertificates::load(
+            authority.write_certificates(server_directory.path(), &authority),
+        )
+        .unwrap();
+        let client = TlsCertificates::load(
+            authority.write_certificates(client_directory.path(), &authority),
+        )
+        .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("localhost:{}", listener.local_addr().unwrap().port());
+        let mut pool = TcpConnectionPool::new(true, Some(client.clone()))
+            .await
+            .unwrap();
+
+        let (accepted, sent) = tokio::join!(
+            accept(&listener, &server),
+            pool.send_message_to(RpcMessage::VersionInfoQuery, &address)
+        );
+        sent.unwrap();
+        let (_first_stream, first_certificate) = accepted;
+
+        // The connection is reused while the certificates are unchanged.
+        pool.send_message_to(RpcMessage::VersionInfoQuery, &address)
+            .await
+            .unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), listener.accept())
+                .await
+                .is_err()
+        );
+
+        // After the client's certificate is renewed, a new connection presents it.
+        authority.write_certificates(client_directory.path(), &authority);
+        assert!(client.reload().unwrap());
+        let (accepted, sent) = tokio::join!(
+            accept(&listener, &server),
+            pool.send_message_to(RpcMessage::VersionInfoQuery, &address)
+        );
+        sent.unwrap();
+        let (_second_stream, second_certificate) = accepted;
+        assert_ne!(first_certificate, second_certificate);
+    }
+}
@@ -0,0 +1,616 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! TLS for the connections to the proxy and between the proxy and the shards of a validator.
+//!
+//! The certificates are read from the files of a [`TlsCertificatesConfig`]. They can be read
+//! again with [`TlsCertificates::reload`] while the validator is running, e.g. after they
+//! were renewed: new connections then use the new certificates, while the established ones
+//! are kept.
+
+use std::{
+    fmt, future, io,
+    path::Path,
+    sync::{Arc, LazyLock, RwLock},
+    time::Duration,
+};
+
+use futures::{stream, Stream, StreamExt as _};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{
+    client,
+    rustls::{
+        self,
+        crypto::{ring, CryptoProvider},
+        pki_types::{CertificateDer, ServerName},
+        server::WebPkiClientVerifier,
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    server, TlsAcceptor, TlsConnector,
+};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+use tracing::debug;
+
+use crate::config::TlsCertificatesConfig;
+
+/// How long a peer has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many TLS handshakes of incoming gRPC connections can be in progress at once.
+const MAX_CONCURRENT_HANDSHAKES: usize = 64;
+
+/// The protocol negotiated by gRPC connections.
+const ALPN_H2: &[u8] = b"h2";
+
+/// An error while loading TLS certificates.
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("failed to read {path:?}: {error}")]
+    Read { path: String, error: io::Error },
+    #[error("invalid PEM data in {0}")]
+    InvalidPem(String),
+    #[error("no certificate found in {0}")]
+    MissingCertificate(String),
+    #[error("no private key found in {0}")]
+    MissingPrivateKey(String),
+    #[error(transparent)]
+    Rustls(#[from] rustls::Error),
+    #[error(transparent)]
+    ClientVerifier(#[from] rustls::server::VerifierBuilderError),
+}
+
+/// The TLS certificates of a node of a validator, which can be reloaded from their files.
+///
+/// Clones share the same certificates.
+#[derive(Clone)]
+pub struct TlsCertificates {
+    config: Option<Arc<TlsCertificatesConfig>>,
+    loaded: Arc<RwLock<Arc<LoadedCertificates>>>,
+}
+
+/// The contents of the PEM files of a [`TlsCertificatesConfig`].
+#[derive(PartialEq, Eq)]
+struct PemFiles {
+    certificate: Vec<u8>,
+    private_key: Vec<u8>,
+    ca_certificate: Option<Vec<u8>>,
+}
+
+/// The TLS configurations built from a version of the certificates.
+struct LoadedCertificates {
+    files: PemFiles,
+    generation: u64,
+    server: Arc<ServerConfig>,
+    grpc_server: Arc<ServerConfig>,
+    client: Arc<ClientConfig>,
+}
+
+impl fmt::Debug for TlsCertificates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsCertificates")
+            .field("config", &self.config)
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+impl TlsCertificates {
+    /// Reads the certificates from the files of `config`.
+    pub fn load(config: TlsCertificatesConfig) -> Result<Self, TlsError> {
+        let loaded = LoadedCertificates::new(PemFiles::read(&config)?, 0)?;
+        Ok(Self {
+            config: Some(Arc::new(config)),
+            loaded: Arc::new(RwLock::new(Arc::new(loaded))),
+        })
+    }
+
+    /// Returns the self-signed certificate that is embedded in the binary, for servers
+    /// whose certificates are not configured.
+    pub fn embedded() -> Self {
+        let files = PemFiles {
+            certificate: crate::CERT_PEM.as_bytes().to_vec(),
+            private_key: crate::KEY_PEM.as_bytes().to_vec(),
+            ca_certificate: None,
+        };
+        let loaded =
+            LoadedCertificates::new(files, 0).expect("the embedded certificate should be valid");
+        Self {
+            config: None,
+            loaded: Arc::new(RwLock::new(Arc::new(loaded))),
+        }
+    }
+
+    /// Reads the certificates from their files again. Returns whether they changed.
+    ///
+    /// If the new files are invalid, the previous certificates are kept.
+    pub fn reload(&self) -> Result<bool, TlsError> {
+        let Some(config) = &self.config else {
+            return Ok(false);
+        };
+        let files = PemFiles::read(config)?;
+        let current = self.current();
+        if files == current.files {
+            return Ok(false);
+        }
+        let loaded = LoadedCertificates::new(files, current.generation + 1)?;
+        *self.loaded.write().unwrap() = Arc::new(loaded);
+        Ok(true)
+    }
+
+    /// Returns how many times the certificates changed, so that connections can be opened
+    /// again with the new ones.
+    pub fn generation(&self) -> u64 {
+        self.current().generation
+    }
+
+    /// Returns whether the servers require the clients to present a certificate signed by
+    /// the configured CA.
+    pub fn authenticates_clients(&self) -> bool {
+        self.current().files.ca_certificate.is_some()
+    }
+
+    /// Performs the server side of the TLS handshake on an incoming connection.
+    pub async fn accept(&self, stream: TcpStream) -> io::Result<server::TlsStream<TcpStream>> {
+        let acceptor = TlsAcceptor::from(self.current().server.clone());
+        with_handshake_timeout(acceptor.accept(stream)).await
+    }
+
+    /// Returns the TLS connections accepted by `listener`, for a gRPC server.
+    ///
+    /// Connections whose handshake fails are dropped.
+    pub fn grpc_incoming(
+        &self,
+        listener: TcpListener,
+    ) -> impl Stream<Item = io::Result<server::TlsStream<TcpStream>>> + Send + 'static {
+        let certificates = self.clone();
+        stream::unfold(listener, |listener| async move {
+            let result = listener.accept().await;
+            Some((result, listener))
+        })
+        .map(move |result| {
+            let acceptor = TlsAcceptor::from(certificates.current().grpc_server.clone());
+            async move {
+                let (stream, address) = match result {
+                    Ok(accepted) => accepted,
+                    Err(error) => return Some(Err(error)),
+                };
+                match with_handshake_timeout(acceptor.accept(stream)).await {
+                    Ok(stream) => Some(Ok(stream)),
+                    Err(error) => {
+                        debug!("TLS handshake with {address} failed: {error}");
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_HANDSHAKES)
+        .filter_map(future::ready)
+    }
+
+    /// Performs the client side of the TLS handshake with the server `server_name`,
+    /// presenting our certificate.
+    pub async fn connect(
+        &self,
+        server_name: &str,
+        stream: TcpStream,
+    ) -> io::Result<client::TlsStream<TcpStream>> {
+        connect_with(self.current().client.clone(), server_name, stream).await
+    }
+
+    /// Returns the configuration of gRPC channels that present our certificate.
+    pub fn grpc_client_config(&self) -> ClientTlsConfig {
+        let loaded = self.current();
+        let config = ClientTlsConfig::new().identity(Identity::from_pem(
+            &loaded.files.certificate,
+            &loaded.files.private_key,
+        ));
+        match &loaded.files.ca_certificate {
+            Some(ca_certificate) => config.ca_certificate(Certificate::from_pem(ca_certificate)),
+            None => config.with_webpki_roots(),
+        }
+    }
+
+    fn current(&self) -> Arc<LoadedCertificates> {
+        self.loaded.read().unwrap().clone()
+    }
+}
+
+/// Performs the client side of the TLS handshake with the server `server_name`, trusting the
+/// public certificate authorities and without presenting a certificate.
+pub async fn connect_public(
+    server_name: &str,
+    stream: TcpStream,
+) -> io::Result<client::TlsStream<TcpStream>> {
+    static CONFIG: LazyLock<Arc<ClientConfig>> = LazyLock::new(|| {
+        let config = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .expect("the default protocol versions should be supported")
+            .with_root_certificates(public_roots())
+            .with_no_client_auth();
+        Arc::new(config)
+    });
+    connect_with(CONFIG.clone(), server_name, stream).await
+}
+
+async fn connect_with(
+    config: Arc<ClientConfig>,
+    server_name: &str,
+    stream: TcpStream,
+) -> io::Result<client::TlsStream<TcpStream>> {
+    let server_name = ServerName::try_from(server_name.to_owned())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    with_handshake_timeout(TlsConnector::from(config).connect(server_name, stream)).await
+}
+
+async fn with_handshake_timeout<T>(
+    handshake: impl future::Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+fn public_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    roots
+}
+
+impl PemFiles {
+    fn read(config: &TlsCertificatesConfig) -> Result<Self, TlsError> {
+        Ok(Self {
+            certificate: read_file(&config.certificate_path)?,
+            private_key: read_file(&config.private_key_path)?,
+            ca_certificate: config
+                .ca_certificate_path
+                .as_deref()
+                .map(read_file)
+                .transpose()?,
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, TlsError> {
+    std::fs::read(path).map_err(|error| TlsError::Read {
+        path: path.display().to_string(),
+        error,
+    })
+}
+
+impl LoadedCertificates {
+    fn new(files: PemFiles, generation: u64) -> Result<Self, TlsError> {
+        let certificates = parse_certificates(&files.certificate, "the certificate file")?;
+        let private_key = rustls_pemfile::private_key(&mut files.private_key.as_slice())
+            .map_err(|_| TlsError::InvalidPem("the private key file".to_owned()))?
+            .ok_or_else(|| TlsError::MissingPrivateKey("the private key file".to_owned()))?;
+        let ca_roots = match &files.ca_certificate {
+            Some(ca_certificate) => {
+                let mut roots = RootCertStore::empty();
+                for certificate in parse_certificates(ca_certificate, "the CA certificate file")? {
+                    roots.add(certificate)?;
+                }
+                Some(Arc::new(roots))
+            }
+            None => None,
+        };
+
+        let server_config = |alpn_protocols: Vec<Vec<u8>>| -> Result<_, TlsError> {
+            let builder = ServerConfig::builder_with_provider(provider())
+                .with_safe_default_protocol_versions()?;
+            let builder = match &ca_roots {
+                Some(roots) => builder.with_client_cert_verifier(
+                    WebPkiClientVerifier::builder_with_provider(roots.clone(), provider())
+                        .build()?,
+                ),
+                None => builder.with_no_client_auth(),
+            };
+            let mut config =
+                builder.with_single_cert(certificates.clone(), private_key.clone_key())?;
+            config.alpn_protocols = alpn_protocols;
+            Ok(Arc::new(config))
+        };
+        let server = server_config(Vec::new())?;
+        let grpc_server = server_config(vec![ALPN_H2.to_vec()])?;
+
+        let roots = match &ca_roots {
+            Some(roots) => roots.as_ref().clone(),
+            None => public_roots(),
+        };
+        let client = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_client_auth_cert(certificates.clone(), private_key.clone_key())?;
+
+        Ok(Self {
+            files,
+            generation,
+            server,
+            grpc_server,
+            client: Arc::new(client),
+        })
+    }
+}
+
+fn parse_certificates(pem: &[u8], name: &str) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..])
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| TlsError::InvalidPem(name.to_owned()))?;
+    if certificates.is_empty() {
+        return Err(TlsError::MissingCertificate(name.to_owned()));
+    }
+    Ok(certificates)
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::path::Path;
+
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa};
+    use tokio_rustls::rustls::pki_types::CertificateDer;
+
+    use crate::config::TlsCertificatesConfig;
+
+    /// A certificate authority issuing certificates for `localhost`.
+    pub(crate) struct TestAuthority {
+        certificate: Certificate,
+    }
+
+    impl TestAuthority {
+        pub(crate) fn new(name: &str) -> Self {
+            let mut params = CertificateParams::new(Vec::new());
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.distinguished_name.push(DnType::CommonName, name);
+            let certificate = Certificate::from_params(params).unwrap();
+            Self { certificate }
+        }
+
+        /// Returns the certificate of this authority.
+        pub(crate) fn certificate(&self) -> CertificateDer<'static> {
+            self.certificate.serialize_der().unwrap().into()
+        }
+
+        /// Writes a new certificate signed by this authority to `directory`, trusting the
+        /// `trusted` authority, and returns the configuration to read it.
+        pub(crate) fn write_certificates(
+            &self,
+            directory: &Path,
+            trusted: &TestAuthority,
+        ) -> TlsCertificatesConfig {
+            let certificate =
+                Certificate::from_params(CertificateParams::new(vec!["localhost".to_owned()]))
+                    .unwrap();
+            let config = TlsCertificatesConfig {
+                certificate_path: directory.join("certificate.pem"),
+                private_key_path: directory.join("private_key.pem"),
+                ca_certificate_path: Some(directory.join("ca_certificate.pem")),
+            };
+            std::fs::write(
+                &config.certificate_path,
+                certificate
+                    .serialize_pem_with_signer(&self.certificate)
+                    .unwrap(),
+            )
+            .unwrap();
+            std::fs::write(
+                &config.private_key_path,
+                certificate.serialize_private_key_pem(),
+            )
+            .unwrap();
+            std::fs::write(
+                config.ca_certificate_path.as_ref().unwrap(),
+                trusted.certificate.serialize_pem().unwrap(),
+            )
+            .unwrap();
+            config
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::{test_utils::TestAuthority, *};
+
+    /// Loads a new certificate signed by `authority` and trusting `trusted`.
+    fn certificates(
+        directory: &TempDir,
+        authority: &TestAuthority,
+        trusted: &TestAuthority,
+    ) -> TlsCertificates {
+        TlsCertificates::load(authority.write_certificates(directory.path(), trusted)).unwrap()
+    }
+
+    /// Returns the configuration of a client trusting `trusted` without a certificate.
+    fn anonymous_client(trusted: &TestAuthority) -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add(trusted.certificate()).unwrap();
+        let config = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Arc::new(config)
+    }
+
+    /// Returns the certificate that `certificates` present to peers.
+    fn presented(certificates: &TlsCertificates) -> CertificateDer<'static> {
+        parse_certificates(&certificates.current().files.certificate, "the certificate")
+            .unwrap()
+            .remove(0)
+    }
+
+    /// Performs a handshake between `server` and a client with the configuration `client`,
+    /// and returns the server's and the client's ends.
+    async fn handshake(
+        server: &TlsCertificates,
+        client: Arc<ClientConfig>,
+    ) -> (
+        io::Result<server::TlsStream<TcpStream>>,
+        io::Result<client::TlsStream<TcpStream>>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::join!(
+            async {
+                let (stream, _) = listener.accept().await?;
+                server.accept(stream).await
+            },
+            async {
+                let stream = TcpStream::connect(address).await?;
+                connect_with(client, "localhost", stream).await
+            }
+        )
+    }
+
+    /// Checks that data sent by the client reaches the server.
+    async fn assert_connected(
+        server: &mut server::TlsStream<TcpStream>,
+        client: &mut client::TlsStream<TcpStream>,
+    ) {
+        client.write_all(b"ping").await.unwrap();
+        client.flush().await.unwrap();
+        let mut buffer = [0; 4];
+        server.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"ping");
+    }
+
+    #[test]
+    fn embedded_certificate_is_valid() {
+        let certificates = TlsCertificates::embedded();
+        assert_eq!(certificates.generation(), 0);
+        assert!(!certificates.reload().unwrap());
+    }
+
+    #[test]
+    fn invalid_key_is_rejected() {
+        let files = PemFiles {
+            certificate: crate::CERT_PEM.as_bytes().to_vec(),
+            private_key: Vec::new(),
+            ca_certificate: None,
+        };
+        assert!(matches!(
+            LoadedCertificates::new(files, 0),
+            Err(TlsError::MissingPrivateKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_handshake_succeeds() {
+        let authority = TestAuthority::new("authority");
+        let (server_directory, client_directory) =
+            (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let server = certificates(&server_directory, &authority, &authority);
+        let client = certificates(&client_directory, &authority, &authority);
+
+        let (server_stream, client_stream) =
+            handshake(&server, client.current().client.clone()).await;
+        let (mut server_stream, mut client_stream) =
+            (server_stream.unwrap(), client_stream.unwrap());
+        let peer_certificates = server_stream.get_ref().1.peer_certificates().unwrap();
+        assert_eq!(peer_certificates[0], presented(&client));
+        assert_connected(&mut server_stream, &mut client_stream).await;
+    }
+
+    #[tokio::test]
+    async fn clients_without_trusted_certificate_are_rejected() {
+        let authority = TestAuthority::new("authority");
+        let other_authority = TestAuthority::new("other authority");
+        let (server_directory, client_directory) =
+            (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let server = certificates(&server_directory, &authority, &authority);
+
+        // The client trusts the server but doesn't present a certificate.
+        let (server_stream, _) = handshake(&server, anonymous_client(&authority)).await;
+        assert!(server_stream.is_err());
+
+        // The client presents a certificate signed by another authority.
+        let client = certificates(&client_directory, &other_authority, &authority);
+        let (server_stream, _) = handshake(&server, client.current().client.clone()).await;
+        assert!(server_stream.is_err());
+    }
+
+    #[tokio::test]
+    async fn grpc_incoming_drops_rejected_clients() {
+        let authority = TestAuthority::new("authority");
+        let other_authority = TestAuthority::new("other authority");
+        let directories = [
+            TempDir::new().unwrap(),
+            TempDir::new().unwrap(),
+            TempDir::new().unwrap(),
+        ];
+        let server = certificates(&directories[0], &authority, &authority);
+        let untrusted = certificates(&directories[1], &other_authority, &authority);
+        let trusted = certificates(&directories[2], &authority, &authority);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut incoming = Box::pin(server.grpc_incoming(listener));
+
+        let (accepted, _client_stream) = tokio::join!(incoming.next(), async {
+            for config in [
+                anonymous_client(&authority),
+                untrusted.current().client.clone(),
+            ] {
+                let stream = TcpStream::connect(address).await.unwrap();
+                // With TLS 1.3, the server checks the client's certificate after the client
+                // completed the handshake: wait until the server closes the connection.
+                if let Ok(mut stream) = connect_with(config, "localhost", stream).await {
+                    assert!(!matches!(stream.read(&mut [0]).await, Ok(1)));
+                }
+            }
+            let stream = TcpStream::connect(address).await.unwrap();
+            connect_with(trusted.current().client.clone(), "localhost", stream)
+                .await
+                .unwrap()
+        });
+
+        // Only the connection of the trusted client is yielded.
+        let accepted = accepted.unwrap().unwrap();
+        let peer_certificates = accepted.get_ref().1.peer_certificates().unwrap();
+        assert_eq!(peer_certificates[0], presented(&trusted));
+    }
+
+    #[tokio::test]
+    async fn reloaded_certificates_are_used_by_new_connections() {
+        let authority = TestAuthority::new("authority");
+        let new_authority = TestAuthority::new("new authority");
+        let directories = [
+            TempDir::new().unwrap(),
+            TempDir::new().unwrap(),
+            TempDir::new().unwrap(),
+        ];
+        let server = certificates(&directories[0], &authority, &authority);
+        let client = certificates(&directories[1], &authority, &authority);
+        let (server_stream, client_stream) =
+            handshake(&server, client.current().client.clone()).await;
+        let (mut server_stream, mut client_stream) =
+            (server_stream.unwrap(), client_stream.unwrap());
+
+        // The server's certificate is renewed by another authority.
+        new_authority.write_certificates(directories[0].path(), &authority);
+        assert!(server.reload().unwrap());
+        assert_eq!(server.generation(), 1);
+        assert!(!server.reload().unwrap());
+
+        // The established connection is kept.
+        assert_connected(&mut server_stream, &mut client_stream).await;
+
+        // New connections present the new certificate: clients that only trust the previous
+        // authority reject it.
+        let (_, client_stream) = handshake(&server, client.current().client.clone()).await;
+        assert!(client_stream.is_err());
+        let new_client = certificates(&directories[2], &authority, &new_authority);
+        let (server_stream, client_stream) =
+            handshake(&server, new_client.current().client.clone()).await;
+        let (mut server_stream, mut client_stream) =
+            (server_stream.unwrap(), client_stream.unwrap());
+        let server_certificates = client_stream.get_ref().1.peer_certificates().unwrap();
+        assert_eq!(server_certificates[0], presented(&server));
+        assert_connected(&mut server_stream, &mut client_stream).await;
+    }
+}
@@ -22,6 +22,7 @@ async fn client() {
     let options = Options {
         connect_timeout: Some(Duration::from_millis(100)),
         timeout: Some(Duration::from_millis(100)),
+        ..Options::default()
     };
     let channel = create_channel(address.clone(), &options).unwrap();
     let _ = GrpcClient::new(address, channel, retry_delay, max_retries)
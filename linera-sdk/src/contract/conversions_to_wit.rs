@@ -7,6 +7,7 @@ use linera_base::{
     crypto::CryptoHash,
     data_types::{
         Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, TimeDelta,
+        TimerDeadline, Timestamp,
     },
     identifiers::{
         Account, AccountOwner, ApplicationId, ChainId, ChannelName, Destination, MessageId,
@@ -202,6 +203,27 @@ impl From<TimeDelta> for wit_contract_api::TimeDelta {
     }
 }
 
+impl From<Timestamp> for wit_contract_api::Timestamp {
+    fn from(timestamp: Timestamp) -> Self {
+        Self {
+            inner0: timestamp.micros(),
+        }
+    }
+}
+
+impl From<TimerDeadline> for wit_contract_api::TimerDeadline {
+    fn from(deadline: TimerDeadline) -> Self {
+        match deadline {
+            TimerDeadline::Timestamp(timestamp) => {
+                wit_contract_api::TimerDeadline::Timestamp(timestamp.into())
+            }
+            TimerDeadline::BlockHeight(height) => {
+                wit_contract_api::TimerDeadline::BlockHeight(height.into())
+            }
+        }
+    }
+}
+
 impl From<TimeoutConfig> for wit_contract_api::TimeoutConfig {
     fn from(config: TimeoutConfig) -> Self {
         let TimeoutConfig {
@@ -228,6 +250,7 @@ impl From<ApplicationPermissions> for wit_contract_api::ApplicationPermissions {
             change_application_permissions,
             call_service_as_oracle,
             make_http_requests,
+            upgrade_applications,
         } = permissions;
         Self {
             execute_operations: execute_operations
@@ -242,6 +265,7 @@ impl From<ApplicationPermissions> for wit_contract_api::ApplicationPermissions {
                 .map(|app_ids| app_ids.into_iter().map(Into::into).collect()),
             make_http_requests: make_http_requests
                 .map(|app_ids| app_ids.into_iter().map(Into::into).collect()),
+            upgrade_applications: upgrade_applications.into_iter().map(Into::into).collect(),
         }
     }
 }
@@ -55,6 +55,14 @@ macro_rules! contract {
                 )
             }
 
+            fn migrate() {
+                use $crate::util::BlockingWait;
+                $crate::contract::run_async_entrypoint::<$contract, _, _>(
+                    unsafe { &mut CONTRACT },
+                    move |contract| contract.migrate().blocking_wait(),
+                )
+            }
+
             fn execute_operation(operation: Vec<u8>) -> Vec<u8> {
                 use $crate::util::BlockingWait;
                 $crate::contract::run_async_entrypoint::<$contract, _, _>(
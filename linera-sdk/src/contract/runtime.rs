@@ -6,7 +6,8 @@
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, TimerDeadline,
+        Timestamp,
     },
     ensure, http,
     identifiers::{
@@ -296,6 +297,27 @@ where
         bcs::from_bytes(&event).expect("Failed to deserialize event")
     }
 
+    /// Sets a timer that delivers `message` to this application on the current chain once
+    /// the `deadline` is reached. Returns the ID of the timer.
+    ///
+    /// The fee for the timer is charged now. The message is delivered by a later block, which
+    /// the chain's owners propose automatically when they run a chain listener, e.g. a node
+    /// service.
+    pub fn set_timer(&mut self, deadline: TimerDeadline, message: &Application::Message) -> u64 {
+        contract_wit::set_timer(
+            deadline.into(),
+            &bcs::to_bytes(message).expect("Failed to serialize timer message"),
+        )
+    }
+
+    /// Cancels a timer set by this application. Returns `false` if the timer does not exist
+    /// anymore, e.g. because it already fired.
+    ///
+    /// Panics if the timer was set by another application.
+    pub fn cancel_timer(&mut self, timer_id: u64) -> bool {
+        contract_wit::cancel_timer(timer_id)
+    }
+
     /// Queries an application service as an oracle and returns the response.
     ///
     /// Should only be used with queries where it is very likely that all validators will compute
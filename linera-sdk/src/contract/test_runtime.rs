@@ -11,7 +11,8 @@ use std::{
 use linera_base::{
     abi::{ContractAbi, ServiceAbi},
     data_types::{
-        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, Timestamp,
+        Amount, ApplicationPermissions, BlockHeight, Resources, SendMessageRequest, TimerDeadline,
+        Timestamp,
     },
     ensure, http,
     identifiers::{
@@ -62,6 +63,7 @@ where
     outgoing_transfers: HashMap<Account, Amount>,
     created_events: BTreeMap<StreamName, Vec<Vec<u8>>>,
     events: BTreeMap<(ChainId, StreamName, u32), Vec<u8>>,
+    timers: Vec<Option<(TimerDeadline, Vec<u8>)>>,
     claim_requests: Vec<ClaimRequest>,
     expected_service_queries: VecDeque<(ApplicationId, String, String)>,
     expected_http_requests: VecDeque<(http::Request, http::Response)>,
@@ -112,6 +114,7 @@ where
             outgoing_transfers: HashMap::new(),
             created_events: BTreeMap::new(),
             events: BTreeMap::new(),
+            timers: Vec::new(),
             claim_requests: Vec::new(),
             expected_service_queries: VecDeque::new(),
             expected_http_requests: VecDeque::new(),
@@ -840,6 +843,36 @@ where
         bcs::from_bytes(value).expect("Failed to deserialize event value")
     }
 
+    /// Sets a timer that delivers `message` to this application once the `deadline` is
+    /// reached. Returns the ID of the timer.
+    pub fn set_timer(&mut self, deadline: TimerDeadline, message: &Application::Message) -> u64 {
+        let message = bcs::to_bytes(message).expect("Failed to serialize timer message");
+        self.timers.push(Some((deadline, message)));
+        self.timers.len() as u64 - 1
+    }
+
+    /// Cancels a timer set in the test. Returns `false` if the timer was already cancelled.
+    pub fn cancel_timer(&mut self, timer_id: u64) -> bool {
+        let timer = usize::try_from(timer_id)
+            .ok()
+            .and_then(|index| self.timers.get_mut(index))
+            .expect("Timer not found");
+        timer.take().is_some()
+    }
+
+    /// Returns the timers set in the test so far and not cancelled, with their messages.
+    pub fn timers(&self) -> Vec<(TimerDeadline, Application::Message)> {
+        self.timers
+            .iter()
+            .flatten()
+            .map(|(deadline, message)| {
+                let message =
+                    bcs::from_bytes(message).expect("Failed to deserialize timer message");
+                (*deadline, message)
+            })
+            .collect()
+    }
+
     /// Adds an expected `query_service` call`, and the response it should return in the test.
     pub fn add_expected_service_query<A: ServiceAbi + Send>(
         &mut self,
@@ -113,6 +113,18 @@ pub trait Contract: WithContractAbi + ContractAbi + Sized {
     /// created the application.
     async fn instantiate(&mut self, argument: Self::InstantiationArgument);
 
+    /// Migrates the application's state after the application was upgraded to this code.
+    ///
+    /// This is called once on each chain, when the chain applies the upgrade published by the
+    /// creator chain of the application, before any other entry point of the new code on that
+    /// chain. Since the state of each chain is migrated separately, the new code may receive
+    /// messages from chains still running the previous code. Messages that were sent by
+    /// the previous code and are still in flight are then executed by the new code, so the
+    /// [`Contract::Message`] type must remain able to deserialize them.
+    ///
+    /// The default implementation does nothing.
+    async fn migrate(&mut self) {}
+
     /// Applies an operation from the current block.
     ///
     /// Operations are created by users and added to blocks, serving as the starting point for an
@@ -17,11 +17,12 @@ use linera_chain::{
         SignatureAggregator,
     },
     types::{ConfirmedBlock, ConfirmedBlockCertificate},
+    ExecutionOptions,
 };
 use linera_core::worker::WorkerError;
 use linera_execution::{
     system::{Recipient, SystemOperation},
-    Operation,
+    Operation, ResourceProfile,
 };
 
 use super::TestValidator;
@@ -204,22 +205,16 @@ impl BlockBuilder {
         self,
         blobs: &[Blob],
     ) -> Result<ConfirmedBlockCertificate, WorkerError> {
-        let published_blobs = self
-            .block
-            .published_blob_ids()
-            .into_iter()
-            .map(|blob_id| {
-                blobs
-                    .iter()
-                    .find(|blob| blob.id() == blob_id)
-                    .expect("missing published blob")
-                    .clone()
-            })
-            .collect();
-        let (block, _) = self
+        let published_blobs = self.published_blobs(blobs);
+        let (block, _, _) = self
             .validator
             .worker()
-            .stage_block_execution(self.block, None, published_blobs)
+            .stage_block_execution(
+                self.block,
+                None,
+                published_blobs,
+                ExecutionOptions::default(),
+            )
             .await?;
 
         let value = ConfirmedBlock::new(block);
@@ -237,4 +232,35 @@ impl BlockBuilder {
 
         Ok(certificate)
     }
+
+    /// Tries to execute the prepared block without signing it, and returns the profile of the
+    /// resources used by the applications. Returns an error if block execution fails.
+    pub(crate) async fn try_profile(self, blobs: &[Blob]) -> Result<ResourceProfile, WorkerError> {
+        let published_blobs = self.published_blobs(blobs);
+        let options = ExecutionOptions {
+            profile: true,
+            ..ExecutionOptions::default()
+        };
+        let (_, _, report) = self
+            .validator
+            .worker()
+            .stage_block_execution(self.block, None, published_blobs, options)
+            .await?;
+        Ok(report.profile.unwrap_or_default())
+    }
+
+    /// Returns the blobs published by the prepared block, taken from `blobs`.
+    fn published_blobs(&self, blobs: &[Blob]) -> Vec<Blob> {
+        self.block
+            .published_blob_ids()
+            .into_iter()
+            .map(|blob_id| {
+                blobs
+                    .iter()
+                    .find(|blob| blob.id() == blob_id)
+                    .expect("missing published blob")
+                    .clone()
+            })
+            .collect()
+    }
 }
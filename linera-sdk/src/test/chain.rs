@@ -25,7 +25,7 @@ use linera_chain::{types::ConfirmedBlockCertificate, ChainExecutionContext};
 use linera_core::{data_types::ChainInfoQuery, worker::WorkerError};
 use linera_execution::{
     system::{SystemOperation, SystemQuery, SystemResponse},
-    ExecutionError, Operation, Query, QueryOutcome, QueryResponse,
+    ExecutionError, Operation, Query, QueryOutcome, QueryResponse, ResourceProfile,
 };
 use linera_storage::Storage as _;
 use serde::Serialize;
@@ -288,6 +288,32 @@ impl ActiveChain {
         Ok(certificate)
     }
 
+    /// Executes a block after the tip of this microchain without adding it, and returns the
+    /// profile of the resources used by the applications.
+    ///
+    /// The `block_builder` parameter is a closure that should use the [`BlockBuilder`] parameter
+    /// to provide the block's contents. The profile can be written to a file with
+    /// [`ResourceProfile::write_folded`], to be rendered as a flamegraph.
+    pub async fn profile_block(
+        &self,
+        block_builder: impl FnOnce(&mut BlockBuilder),
+    ) -> ResourceProfile {
+        let tip = self.tip.lock().await;
+        let mut block = BlockBuilder::new(
+            self.description.into(),
+            self.key_pair.public().into(),
+            self.epoch().await,
+            tip.as_ref(),
+            self.validator.clone(),
+        );
+
+        block_builder(&mut block);
+
+        Box::pin(block.try_profile(&[]))
+            .await
+            .expect("Failed to execute block.")
+    }
+
     /// Receives all queued messages in all inboxes of this microchain.
     ///
     /// Adds a block to this microchain that receives all queued messages in the microchains
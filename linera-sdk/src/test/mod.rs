@@ -26,7 +26,10 @@ pub use {
         ChainError, ChainExecutionContext,
     },
     linera_core::worker::WorkerError,
-    linera_execution::{system::Recipient, ExecutionError, QueryOutcome, WasmExecutionError},
+    linera_execution::{
+        system::Recipient, ExecutionError, ProfileMetric, QueryOutcome, ResourceProfile,
+        WasmExecutionError,
+    },
 };
 
 #[cfg(with_testing)]
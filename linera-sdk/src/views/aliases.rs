@@ -36,6 +36,11 @@ pub type CustomMapView<K, V> = linera_views::map_view::CustomMapView<ViewStorage
 /// WebAssembly-specific [`ViewStorageContext`].
 pub type CustomSetView<W> = linera_views::set_view::CustomSetView<ViewStorageContext, W>;
 
+/// An alias to [`linera_views::indexed_map_view::IndexedMapView`] that uses the
+/// WebAssembly-specific [`ViewStorageContext`].
+pub type IndexedMapView<K, V> =
+    linera_views::indexed_map_view::IndexedMapView<ViewStorageContext, K, V>;
+
 /// An alias to [`linera_views::log_view::LogView`] that uses the WebAssembly-specific
 /// [`ViewStorageContext`].
 pub type LogView<T> = linera_views::log_view::LogView<ViewStorageContext, T>;
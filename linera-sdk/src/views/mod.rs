@@ -11,14 +11,15 @@ mod system_api;
 pub use linera_views::{
     self,
     common::CustomSerialize,
+    indexed_map_view::IndexedValue,
     views::{RootView, View, ViewError},
 };
 
 pub use self::{
     aliases::{
         ByteCollectionView, ByteMapView, ByteSetView, CollectionView, CustomCollectionView,
-        CustomMapView, CustomSetView, LogView, MapView, QueueView, ReadGuardedView, RegisterView,
-        SetView,
+        CustomMapView, CustomSetView, IndexedMapView, LogView, MapView, QueueView, ReadGuardedView,
+        RegisterView, SetView,
     },
     system_api::{KeyValueStore, ViewStorageContext},
 };
@@ -6,5 +6,6 @@ fn main() {
         with_revm: { feature = "revm" },
         with_testing: { any(test, feature = "test") },
         with_metrics: { all(not(target_arch = "wasm32"), feature = "metrics") },
+        with_wasm_runtime: { any(feature = "wasmer", feature = "wasmtime") },
     };
 }
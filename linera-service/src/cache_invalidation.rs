@@ -0,0 +1,318 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Invalidation of the storage caches of the processes sharing a database.
+//!
+//! The proxy and the shards of a validator write to the same database. Each of them sends
+//! the keys written through its storage cache to the other ones over TCP, one JSON message
+//! per line, and makes its caches forget the keys it receives. When a connection is
+//! (re)opened, the receiving process forgets all its cached entries, since the writes made
+//! while it was closed are unknown.
+//!
+//! If the certificates of the internal network authenticate their clients, the connections
+//! use mutual TLS. Otherwise, the listener must be bound to a loopback or private address.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use linera_rpc::tls::TlsCertificates;
+use linera_views::lru_caching::{InvalidationChannel, InvalidationMessage};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncRead, AsyncWrite, AsyncWriteExt as _, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+};
+use tracing::{debug, warn};
+
+/// The number of messages buffered for each cache and each peer.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// How long to wait before connecting to a peer again.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The options to exchange the keys written to storage with the other processes sharing
+/// the database.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct CacheInvalidationConfig {
+    /// The address on which to receive the keys written by the other processes sharing the
+    /// database, so that the storage cache forgets them. Must be a loopback or private
+    /// address, unless the internal network uses TLS certificates with a CA certificate.
+    #[arg(long)]
+    pub cache_invalidation_address: Option<SocketAddr>,
+
+    /// The address of another process sharing the database, to send it the keys written
+    /// by this one. Can be repeated.
+    #[arg(long = "cache-invalidation-peer")]
+    pub cache_invalidation_peers: Vec<String>,
+}
+
+/// A line sent to a peer.
+#[derive(Debug, Serialize, Deserialize)]
+enum Frame {
+    /// Keys were written.
+    Written(InvalidationMessage),
+    /// Some writes were not sent.
+    Reset,
+}
+
+impl CacheInvalidationConfig {
+    /// Starts exchanging the keys written to storage with the peers, and returns the
+    /// channel for the storage caches of this process, if configured.
+    ///
+    /// Caching missing keys requires receiving the writes of the other processes, since
+    /// the storage of a validator is always shared by its proxy and shards.
+    ///
+    /// The connections use the `certificates` of the internal network if they authenticate
+    /// their clients. Otherwise, anyone reaching the listener could make the caches serve
+    /// stale values, so it must be bound to a loopback or private address.
+    pub async fn start(
+        &self,
+        cache_missing_keys: bool,
+        certificates: Option<TlsCertificates>,
+    ) -> Result<Option<InvalidationChannel>> {
+        let Some(address) = self.cache_invalidation_address else {
+            if cache_missing_keys {
+                bail!(
+                    "`--cache-missing-keys` requires `--cache-invalidation-address`, so that \
+                     the other processes sharing the database can report their writes"
+                );
+            }
+            if !self.cache_invalidation_peers.is_empty() {
+                bail!("`--cache-invalidation-peer` requires `--cache-invalidation-address`");
+            }
+            return Ok(None);
+        };
+        let certificates = certificates.filter(TlsCertificates::authenticates_clients);
+        if certificates.is_none() && !is_internal(address.ip()) {
+            bail!(
+                "`--cache-invalidation-address` must be a loopback or private address, unless \
+                 the internal network uses TLS certificates with a CA certificate"
+            );
+        }
+        let channel = InvalidationChannel::new(CHANNEL_CAPACITY);
+        let listener = TcpListener::bind(address).await?;
+        tokio::spawn(receive(listener, channel.clone(), certificates.clone()));
+        for peer in &self.cache_invalidation_peers {
+            tokio::spawn(send(peer.clone(), channel.outgoing(), certificates.clone()));
+        }
+        Ok(Some(channel))
+    }
+}
+
+/// Returns whether `ip` can only be reached from the local host or a private network.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        // Loopback, or unique local addresses (`fc00::/7`).
+        IpAddr::V6(ip) => ip.is_loopback() || ip.segments()[0] & 0xfe00 == 0xfc00,
+    }
+}
+
+/// Returns the host name of `peer`, without its port, to verify its certificate.
+fn host_name(peer: &str) -> &str {
+    let host = peer.rsplit_once(':').map_or(peer, |(host, _port)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+/// Delivers the messages of the peers connecting to `listener`.
+async fn receive(
+    listener: TcpListener,
+    channel: InvalidationChannel,
+    certificates: Option<TlsCertificates>,
+) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(error) => {
+                warn!("Failed to accept a cache invalidation connection: {error}");
+                continue;
+            }
+        };
+        let channel = channel.clone();
+        let certificates = certificates.clone();
+        tokio::spawn(async move {
+            match certificates {
+                Some(certificates) => {
+                    match certificates.accept(stream).await {
+                        Ok(stream) => deliver(stream, peer, channel).await,
+                        Err(error) => {
+                            warn!("TLS handshake with the cache invalidation peer {peer} failed: {error}")
+                        }
+                    }
+                }
+                None => deliver(stream, peer, channel).await,
+            }
+        });
+    }
+}
+
+/// Delivers the messages read from `stream` until the connection is closed.
+async fn deliver(stream: impl AsyncRead + Unpin, peer: SocketAddr, channel: InvalidationChannel) {
+    // The writes made while the peer was not connected are unknown.
+    channel.reset();
+    let mut lines = BufReader::new(stream).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => return,
+            Err(error) => {
+                warn!("Lost the cache invalidation connection from {peer}: {error}");
+                return;
+            }
+        };
+        match serde_json::from_str(&line) {
+            Ok(Frame::Written(message)) => channel.deliver(message),
+            Ok(Frame::Reset) => channel.reset(),
+            Err(error) => {
+                warn!("Invalid cache invalidation message from {peer}: {error}");
+                channel.reset();
+                return;
+            }
+        }
+    }
+}
+
+/// Sends the `outgoing` messages to `peer`, connecting again whenever the connection fails.
+async fn send(
+    peer: String,
+    outgoing: broadcast::Receiver<Arc<InvalidationMessage>>,
+    certificates: Option<TlsCertificates>,
+) {
+    loop {
+        // Subscribe before connecting: the peer forgets all its entries once connected, so
+        // the older messages are obsolete, but none of the later ones may be missed.
+        let messages = outgoing.resubscribe();
+        let stream = match TcpStream::connect(&peer).await {
+            Ok(stream) => stream,
+            Err(error) => {
+                debug!("Failed to connect to the cache invalidation peer {peer}: {error}");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        let result = match &certificates {
+            Some(certificates) => match certificates.connect(host_name(&peer), stream).await {
+                Ok(stream) => forward(stream, messages).await,
+                Err(error) => Err(error),
+            },
+            None => forward(stream, messages).await,
+        };
+        match result {
+            Ok(()) => return,
+            Err(error) => {
+                warn!("Lost the cache invalidation connection to {peer}: {error}");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Writes the `messages` to `stream`, until the channel is closed or writing fails.
+async fn forward(
+    mut stream: impl AsyncWrite + Unpin,
+    mut messages: broadcast::Receiver<Arc<InvalidationMessage>>,
+) -> std::io::Result<()> {
+    loop {
+        let frame = match messages.recv().await {
+            Ok(message) => Frame::Written((*message).clone()),
+            Err(broadcast::error::RecvError::Lagged(_)) => Frame::Reset,
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        let mut line = serde_json::to_vec(&frame).expect("frames can be serialized");
+        line.push(b'\n');
+        stream.write_all(&line).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_base::port::get_free_endpoint;
+    use linera_views::{
+        batch::Batch,
+        lru_caching::{LruCachingStore, StorageCacheConfig, DEFAULT_STORAGE_CACHE_CONFIG},
+        memory::MemoryStore,
+        store::{ReadableKeyValueStore as _, TestKeyValueStore as _, WritableKeyValueStore as _},
+    };
+
+    use super::*;
+
+    fn cache_config(channel: Option<InvalidationChannel>) -> StorageCacheConfig {
+        StorageCacheConfig {
+            cache_missing_keys: true,
+            invalidation_channel: channel,
+            ..DEFAULT_STORAGE_CACHE_CONFIG
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_keys_require_invalidations() {
+        let config = CacheInvalidationConfig::default();
+        assert!(config.start(true, None).await.is_err());
+        assert!(config.start(false, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_public_addresses_require_tls() -> Result<()> {
+        for address in ["0.0.0.0:0", "8.8.8.8:0", "[::]:0"] {
+            let config = CacheInvalidationConfig {
+                cache_invalidation_address: Some(address.parse()?),
+                cache_invalidation_peers: Vec::new(),
+            };
+            assert!(config.start(true, None).await.is_err());
+            // Certificates without a CA do not authenticate the peers.
+            let certificates = TlsCertificates::embedded();
+            assert!(config.start(true, Some(certificates)).await.is_err());
+        }
+        assert!(is_internal("10.1.2.3".parse()?));
+        assert!(is_internal("fd00::1".parse()?));
+        assert_eq!(host_name("shard-0:19100"), "shard-0");
+        assert_eq!(host_name("[::1]:19100"), "::1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_sent_to_peers() -> Result<()> {
+        let reader_address: SocketAddr = get_free_endpoint().await?.parse()?;
+        let reader_config = CacheInvalidationConfig {
+            cache_invalidation_address: Some(reader_address),
+            cache_invalidation_peers: Vec::new(),
+        };
+        let writer_config = CacheInvalidationConfig {
+            cache_invalidation_address: Some("127.0.0.1:0".parse()?),
+            cache_invalidation_peers: vec![reader_address.to_string()],
+        };
+        let store = MemoryStore::new_test_store().await?;
+        let reader = LruCachingStore::new(
+            store.clone(),
+            cache_config(reader_config.start(true, None).await?),
+        );
+        let writer =
+            LruCachingStore::new(store, cache_config(writer_config.start(true, None).await?));
+
+        assert_eq!(reader.read_value_bytes(b"key").await?, None);
+        // Wait for the writer to connect: the reader then forgets its entries.
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(b"key".to_vec(), b"value".to_vec());
+        writer.write_batch(batch).await?;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while reader.read_value_bytes(b"key").await?.is_none() {
+            assert!(tokio::time::Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Once connected, the writes are forwarded.
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(b"key".to_vec(), b"new value".to_vec());
+        writer.write_batch(batch).await?;
+        while reader.read_value_bytes(b"key").await? != Some(b"new value".to_vec()) {
+            assert!(tokio::time::Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        Ok(())
+    }
+}
@@ -23,7 +23,7 @@ use linera_base::{
     command::{resolve_binary, CommandExt},
     crypto::CryptoHash,
     data_types::{Amount, Bytecode, Epoch},
-    identifiers::{Account, AccountOwner, ApplicationId, ChainId, MessageId, ModuleId},
+    identifiers::{Account, AccountOwner, ApplicationId, ChainId, MessageId, ModuleId, StreamId},
     vm::VmRuntime,
 };
 use linera_client::{client_options::ResourceControlPolicyConfig, wallet::Wallet};
@@ -1287,7 +1287,30 @@ impl NodeService {
         &self,
         chain_id: ChainId,
     ) -> Result<impl Stream<Item = Result<Notification>>> {
-        let query = format!("subscription {{ notifications(chainId: \"{chain_id}\") }}",);
+        self.subscribe_to_notifications(chain_id, None).await
+    }
+
+    /// Subscribes to the node service and returns a stream of notifications about a chain,
+    /// where new events are only notified for the given streams.
+    pub async fn notifications_for_streams(
+        &self,
+        chain_id: ChainId,
+        stream_ids: &[StreamId],
+    ) -> Result<impl Stream<Item = Result<Notification>>> {
+        self.subscribe_to_notifications(chain_id, Some(stream_ids))
+            .await
+    }
+
+    async fn subscribe_to_notifications(
+        &self,
+        chain_id: ChainId,
+        stream_ids: Option<&[StreamId]>,
+    ) -> Result<impl Stream<Item = Result<Notification>>> {
+        let stream_ids = stream_ids.map_or_else(String::new, |stream_ids| {
+            format!(", streamIds: {}", stream_ids.to_vec().to_value())
+        });
+        let query =
+            format!("subscription {{ notifications(chainId: \"{chain_id}\"{stream_ids}) }}");
         let url = format!("ws://localhost:{}/ws", self.port);
         let mut request = url.into_client_request()?;
         request.headers_mut().insert(
@@ -1437,6 +1460,48 @@ impl<A> ApplicationWrapper<A> {
         self.run_graphql_query(&format!("mutation {{ {mutation} }}"))
             .await
     }
+
+    /// Sends a request to the Ethereum JSON-RPC endpoint of an EVM application, and returns
+    /// its result.
+    pub async fn ethereum_request(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response: Value = reqwest_client()
+            .post(format!("{}/ethereum", self.uri))
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("ethereum_request: failed to post method={method}"))?
+            .json()
+            .await
+            .context("invalid JSON")?;
+        if let Some(error) = response.get("error") {
+            bail!("Ethereum request \"{method}\" failed: {error}");
+        }
+        Ok(response["result"].clone())
+    }
+
+    /// Profiles the operations scheduled by a `mutation` without executing them, and returns
+    /// the profile of the `metric` resource in the folded stacks format.
+    pub async fn profile(&self, mutation: impl AsRef<str>, metric: &str) -> Result<String> {
+        let mutation = mutation.as_ref();
+        let query = json!({ "query": format!("mutation {{ {mutation} }}") });
+        let response = reqwest_client()
+            .post(format!("{}/profile", self.uri))
+            .query(&[("metric", metric)])
+            .json(&query)
+            .send()
+            .await
+            .with_context(|| format!("profile: failed to post mutation={mutation}"))?;
+        anyhow::ensure!(
+            response.status().is_success(),
+            "Profiling \"{mutation}\" failed: {}",
+            response
+                .text()
+                .await
+                .unwrap_or_else(|error| format!("Could not get response text: {error}"))
+        );
+        Ok(response.text().await?)
+    }
 }
 
 impl<A> From<String> for ApplicationWrapper<A> {
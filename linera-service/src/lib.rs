@@ -6,11 +6,14 @@
 
 #![deny(clippy::large_futures)]
 
+pub mod cache_invalidation;
 pub mod cli_wrappers;
 pub mod node_service;
 pub mod project;
 #[cfg(with_metrics)]
 pub mod prometheus_server;
+pub mod shard_assignment;
 pub mod storage;
+pub mod tls;
 pub mod util;
 pub mod wallet;
@@ -5,7 +5,7 @@ use std::{borrow::Cow, num::NonZeroU16, path::PathBuf};
 
 use chrono::{DateTime, Utc};
 use linera_base::{
-    crypto::{AccountPublicKey, CryptoHash, ValidatorPublicKey},
+    crypto::{AccountPublicKey, CryptoHash, SignatureScheme, ValidatorPublicKey},
     data_types::Amount,
     identifiers::{Account, AccountOwner, ApplicationId, ChainId, MessageId, ModuleId},
     time::Duration,
@@ -292,6 +292,10 @@ pub enum ClientCommand {
         #[arg(long)]
         http_request: Option<Amount>,
 
+        /// Set the price for setting a timer.
+        #[arg(long)]
+        timer: Option<Amount>,
+
         /// Set the maximum amount of fuel per block.
         #[arg(long)]
         maximum_fuel_per_block: Option<u64>,
@@ -337,6 +341,10 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_http_response_bytes: Option<u64>,
 
+        /// Set the maximum size in bytes of the payload of a timer.
+        #[arg(long)]
+        maximum_timer_payload_bytes: Option<u64>,
+
         /// Set the maximum amount of time allowed to wait for an HTTP response.
         #[arg(long)]
         http_request_timeout_ms: Option<u64>,
@@ -509,6 +517,10 @@ pub enum ClientCommand {
         #[arg(long)]
         http_request_price: Option<Amount>,
 
+        /// Set the price for setting a timer.
+        #[arg(long)]
+        timer_price: Option<Amount>,
+
         /// Set the maximum amount of fuel per block.
         /// (This will overwrite value from `--policy-config`)
         #[arg(long)]
@@ -563,6 +575,10 @@ pub enum ClientCommand {
         #[arg(long)]
         maximum_http_response_bytes: Option<u64>,
 
+        /// Set the maximum size in bytes of the payload of a timer.
+        #[arg(long)]
+        maximum_timer_payload_bytes: Option<u64>,
+
         /// Set the maximum amount of time allowed to wait for an HTTP response.
         #[arg(long)]
         http_request_timeout_ms: Option<u64>,
@@ -691,6 +707,24 @@ pub enum ClientCommand {
         required_application_ids: Option<Vec<ApplicationId>>,
     },
 
+    /// Upgrade an application to the code of another module, and run its migration.
+    ///
+    /// Only the chain that created the application can upgrade it, if its application
+    /// permissions allow it. Other chains apply the upgrade when they process their inbox. The
+    /// application keeps its ID and its state, and messages to it that are still in flight are
+    /// executed by the new code.
+    UpgradeApplication {
+        /// The ID of the application to upgrade.
+        application_id: ApplicationId,
+
+        /// The module ID of the new code.
+        module_id: ModuleId,
+
+        /// The chain that created the application, if it is not the default chain of the
+        /// wallet.
+        chain_id: Option<ChainId>,
+    },
+
     /// Create an application, and publish the required module.
     PublishAndCreate {
         /// Path to the Wasm file for the application "contract" bytecode.
@@ -729,7 +763,18 @@ pub enum ClientCommand {
     },
 
     /// Create an unassigned key pair.
-    Keygen,
+    Keygen {
+        /// Derive the key pair with this index from the mnemonic of the wallet, e.g. to
+        /// restore it. By default, wallets created with `--mnemonic` derive the next unused
+        /// index, and other wallets generate a random key pair.
+        #[arg(long)]
+        index: Option<u32>,
+
+        /// The signature scheme of the key pair. Only Ed25519 keys can be generated at
+        /// random: other schemes require a wallet created with `--mnemonic`.
+        #[arg(long, default_value = "ed25519")]
+        scheme: SignatureScheme,
+    },
 
     /// Link an owner with a key pair in the wallet to a chain that was created for that owner.
     Assign {
@@ -743,6 +788,16 @@ pub enum ClientCommand {
         message_id: MessageId,
     },
 
+    /// Run a signer daemon that signs block proposals with the keys of the wallet.
+    ///
+    /// Clients started with `--signer-socket` then never need access to the secret keys. Keys
+    /// added to the wallet later are only served after the daemon is restarted.
+    SignerDaemon {
+        /// The path of the Unix socket to listen on.
+        #[arg(long)]
+        socket: PathBuf,
+    },
+
     /// Retry a block we unsuccessfully tried to propose earlier.
     ///
     /// As long as a block is pending most other commands will fail, since it is unsafe to propose
@@ -752,6 +807,30 @@ pub enum ClientCommand {
         chain_id: Option<ChainId>,
     },
 
+    /// Re-execute a confirmed block from the local storage and compare the outcome with the
+    /// recorded one.
+    ///
+    /// The earlier blocks of the chain are executed first, in a temporary storage. The traces
+    /// of the execution of the block's transactions and the differences with the recorded
+    /// outcome are printed as JSON.
+    ReplayBlock {
+        /// The hash of the confirmed block.
+        hash: CryptoHash,
+    },
+
+    /// Execute a batch of system and user operations atomically in a single block.
+    ///
+    /// The batch is read from a JSON file with a list of `operations` and an optional list of
+    /// `preconditions`, e.g. the expected height of the block or the expected balance of an
+    /// account. If a precondition does not hold, no block is proposed.
+    Batch {
+        /// Path to the JSON file with the batch.
+        path: PathBuf,
+        /// The chain that executes the batch. If not specified, the wallet's default chain is
+        /// used.
+        chain_id: Option<ChainId>,
+    },
+
     /// Show the contents of the wallet.
     #[command(subcommand)]
     Wallet(WalletCommand),
@@ -816,11 +895,15 @@ impl ClientCommand {
             | ClientCommand::PublishDataBlob { .. }
             | ClientCommand::ReadDataBlob { .. }
             | ClientCommand::CreateApplication { .. }
+            | ClientCommand::UpgradeApplication { .. }
             | ClientCommand::PublishAndCreate { .. }
-            | ClientCommand::Keygen
+            | ClientCommand::Keygen { .. }
             | ClientCommand::Assign { .. }
+            | ClientCommand::SignerDaemon { .. }
             | ClientCommand::Wallet { .. }
-            | ClientCommand::RetryPendingBlock { .. } => "client".into(),
+            | ClientCommand::RetryPendingBlock { .. }
+            | ClientCommand::ReplayBlock { .. }
+            | ClientCommand::Batch { .. } => "client".into(),
             #[cfg(feature = "benchmark")]
             ClientCommand::Benchmark { .. } => "benchmark".into(),
             ClientCommand::Net { .. } => "net".into(),
@@ -861,6 +944,42 @@ pub enum DatabaseToolCommand {
 
     /// List the chain IDs in the database
     ListChainIds,
+
+    /// Write every key-value pair of the namespace into a checksummed snapshot archive.
+    /// Nothing should write to the namespace while the snapshot is taken.
+    Snapshot {
+        /// The file to write the snapshot to
+        path: PathBuf,
+    },
+
+    /// Restore a snapshot archive into the namespace, which must not exist yet. The
+    /// snapshot can be taken from any storage backend.
+    Restore {
+        /// The snapshot file to restore
+        path: PathBuf,
+    },
+
+    /// Copy the namespace into another storage, one root key at a time. Every root key
+    /// is verified after it is copied, and an interrupted migration can be resumed. Writes
+    /// made after a root key is copied are only carried over if the namespace is used
+    /// through a `mirroredrocksdbscylladb` storage meanwhile.
+    Migrate {
+        /// The storage to copy the namespace into, in the same format as `--storage`
+        #[arg(long = "target-storage")]
+        target_storage_config: String,
+
+        /// The file recording the progress, to resume an interrupted migration
+        #[arg(long)]
+        progress: Option<PathBuf>,
+
+        /// The maximal number of key-value pairs written in a single batch
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+
+        /// Do not compare the hashes of every root key in both storages after copying it
+        #[arg(long)]
+        skip_verification: bool,
+    },
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -1003,6 +1122,13 @@ pub enum WalletCommand {
         /// TESTING ONLY.
         #[arg(long)]
         testing_prng_seed: Option<u64>,
+
+        /// Derive the keys of this wallet from a BIP-39 mnemonic phrase, so that they can be
+        /// restored from it. The phrase is read from the `LINERA_WALLET_MNEMONIC` environment
+        /// variable if it is set; otherwise a new one is generated and printed. The wallet is
+        /// encrypted with a passphrase, read from `LINERA_WALLET_PASSPHRASE` or prompted for.
+        #[arg(long)]
+        mnemonic: bool,
     },
 
     /// Request a new chain from a faucet and add it to the wallet.
@@ -1023,11 +1149,30 @@ pub enum WalletCommand {
     },
 
     /// Forgets the specified chain's keys. The chain will still be followed by the
-    /// wallet.
+    /// wallet, and its owner is kept for use with an external signer.
     ForgetKeys { chain_id: ChainId },
 
     /// Forgets the specified chain, including the associated key pair.
     ForgetChain { chain_id: ChainId },
+
+    /// Encrypt the secret keys of the wallet with a passphrase.
+    ///
+    /// The passphrase is read from `LINERA_WALLET_PASSPHRASE` if set, or prompted for.
+    Encrypt,
+
+    /// Decrypt the secret keys of the wallet and store them in the clear again.
+    Decrypt,
+
+    /// Change the passphrase of an encrypted wallet.
+    ///
+    /// The new passphrase is read from `LINERA_WALLET_NEW_PASSPHRASE` if set, or prompted for.
+    ChangePassphrase,
+
+    /// Unlock an encrypted wallet for the current shell session.
+    ///
+    /// This prints a command setting `LINERA_WALLET_KEY`, to be used as
+    /// `eval $(linera wallet unlock)`. Later commands then don't ask for the passphrase.
+    Unlock,
 }
 
 #[derive(Clone, clap::Parser)]
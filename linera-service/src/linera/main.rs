@@ -8,6 +8,7 @@
 use std::{
     collections::{BTreeSet, HashMap},
     env,
+    io::IsTerminal as _,
     path::PathBuf,
     process,
     sync::Arc,
@@ -21,7 +22,9 @@ use colored::Colorize;
 use command::{ClientCommand, DatabaseToolCommand, NetCommand, ProjectCommand, WalletCommand};
 use futures::{lock::Mutex, FutureExt as _, StreamExt};
 use linera_base::{
-    crypto::{AccountSecretKey, CryptoHash, CryptoRng, Ed25519SecretKey},
+    crypto::{
+        hd::Mnemonic, AccountSecretKey, CryptoHash, CryptoRng, Ed25519SecretKey, SignatureScheme,
+    },
     data_types::{ApplicationPermissions, Timestamp},
     identifiers::{AccountOwner, ChainDescription, ChainId},
     listen_for_shutdown_signals,
@@ -32,11 +35,18 @@ use linera_client::{
     client_context::ClientContext,
     client_options::ClientContextOptions,
     config::{CommitteeConfig, GenesisConfig, WalletState},
+    keystore::KeystoreKey,
     persistent::{self, Persist},
+    signer::SignerDaemon,
     wallet::{UserChain, Wallet},
 };
 use linera_core::{
-    data_types::ClientOutcome, node::ValidatorNodeProvider, worker::Reason, JoinSetExt as _,
+    data_types::{ClientOutcome, OperationBatch},
+    node::ValidatorNodeProvider,
+    replay,
+    signer::InMemorySigner,
+    worker::Reason,
+    JoinSetExt as _,
 };
 use linera_execution::{
     committee::{Committee, ValidatorState},
@@ -53,12 +63,16 @@ use linera_service::{
 use linera_storage::{DbStorage, Storage};
 use linera_views::{
     lru_caching::StorageCacheConfig,
+    memory::{MemoryStore, MemoryStoreConfig},
+    migration::{migrate_namespace, MigrationOptions, MigrationProgress, MigrationSummary},
+    snapshot,
     store::{CommonStoreConfig, KeyValueStore},
 };
 use serde_json::Value;
 use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn, Instrument as _};
+use zeroize::Zeroizing;
 
 mod command;
 mod net_up_utils;
@@ -132,7 +146,7 @@ impl Runnable for Job {
                 let (new_owner, key_pair) = match owner {
                     Some(owner) => (owner, None),
                     None => {
-                        let key_pair = context.wallet.generate_key_pair();
+                        let key_pair = context.wallet.generate_key_pair()?;
                         (key_pair.public().into(), Some(key_pair))
                     }
                 };
@@ -604,6 +618,7 @@ impl Runnable for Job {
                                     message_byte,
                                     service_as_oracle_query,
                                     http_request,
+                                    timer,
                                     maximum_fuel_per_block,
                                     maximum_service_oracle_execution_ms,
                                     maximum_block_size,
@@ -615,6 +630,7 @@ impl Runnable for Job {
                                     maximum_bytes_written_per_block,
                                     maximum_oracle_response_bytes,
                                     maximum_http_response_bytes,
+                                    maximum_timer_payload_bytes,
                                     http_request_timeout_ms,
                                     http_request_allow_list,
                                 } => {
@@ -648,6 +664,7 @@ impl Runnable for Job {
                                             .unwrap_or(existing_policy.service_as_oracle_query),
                                         http_request: http_request
                                             .unwrap_or(existing_policy.http_request),
+                                        timer: timer.unwrap_or(existing_policy.timer),
                                         maximum_fuel_per_block: maximum_fuel_per_block
                                             .unwrap_or(existing_policy.maximum_fuel_per_block),
                                         maximum_service_oracle_execution_ms:
@@ -678,6 +695,8 @@ impl Runnable for Job {
                                             ),
                                         maximum_http_response_bytes: maximum_http_response_bytes
                                             .unwrap_or(existing_policy.maximum_http_response_bytes),
+                                        maximum_timer_payload_bytes: maximum_timer_payload_bytes
+                                            .unwrap_or(existing_policy.maximum_timer_payload_bytes),
                                         http_request_timeout_ms: http_request_timeout_ms
                                             .unwrap_or(existing_policy.http_request_timeout_ms),
                                         http_request_allow_list: http_request_allow_list
@@ -956,6 +975,34 @@ impl Runnable for Job {
                 println!("{}", application_id);
             }
 
+            UpgradeApplication {
+                application_id,
+                module_id,
+                chain_id,
+            } => {
+                let start_time = Instant::now();
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                info!("Upgrading application {application_id} on chain {chain_id}");
+                let chain_client = context.make_chain_client(chain_id)?;
+                context.process_inbox(&chain_client).await?;
+                let certificate = context
+                    .apply_client_command(&chain_client, move |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move {
+                            chain_client
+                                .upgrade_application(application_id, module_id)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to upgrade application")?;
+                info!(
+                    "Application upgraded in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                debug!("{:?}", certificate);
+            }
+
             PublishAndCreate {
                 contract,
                 service,
@@ -1101,6 +1148,62 @@ impl Runnable for Job {
                 );
             }
 
+            Batch { path, chain_id } => {
+                let start_time = Instant::now();
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let batch: OperationBatch = serde_json::from_str(&fs_err::read_to_string(path)?)?;
+                info!(
+                    "Executing a batch of {} operations on chain {}",
+                    batch.operations.len(),
+                    chain_id
+                );
+                let chain_client = context.make_chain_client(chain_id)?;
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let batch = batch.clone();
+                        async move { chain_client.execute_batch(batch, vec![]).await }
+                    })
+                    .await
+                    .context("Failed to execute the batch")?;
+                println!("{}", certificate.hash());
+                info!("Batch executed in {} ms", start_time.elapsed().as_millis());
+            }
+
+            ReplayBlock { hash } => {
+                let start_time = Instant::now();
+                let store_config = MemoryStoreConfig::new(options.max_stream_queries);
+                let namespace = format!("replay_{hash}");
+                let mut replay_storage = DbStorage::<MemoryStore, _>::maybe_create_and_connect(
+                    &store_config,
+                    &namespace,
+                    options.wasm_runtime.with_wasm_default(),
+                )
+                .await?;
+                context
+                    .wallet()
+                    .genesis_config()
+                    .initialize_storage(&mut replay_storage)
+                    .await?;
+                let replay = replay::replay_block(&storage, &replay_storage, hash).await?;
+                let differences = replay.differences();
+                let output = serde_json::json!({
+                    "matches": replay.matches(),
+                    "differences": differences,
+                    "traces": replay.traces,
+                });
+                println!("{}", serde_json::to_string_pretty(&output)?);
+                if replay.matches() {
+                    info!("The replay of block {hash} has the recorded outcome");
+                } else {
+                    warn!(
+                        "The replay of block {hash} has {} differences with the recorded outcome",
+                        differences.len()
+                    );
+                }
+                info!("Block replayed in {} ms", start_time.elapsed().as_millis());
+            }
+
             Wallet(WalletCommand::Init {
                 faucet: Some(faucet_url),
                 with_new_chain: true,
@@ -1108,7 +1211,7 @@ impl Runnable for Job {
                 ..
             }) => {
                 let start_time = Instant::now();
-                let key_pair = context.wallet.generate_key_pair();
+                let key_pair = context.wallet.generate_key_pair()?;
                 let owner = key_pair.public().into();
                 info!(
                     "Requesting a new chain for owner {owner} using the faucet at address \
@@ -1153,7 +1256,7 @@ impl Runnable for Job {
                 set_default,
             }) => {
                 let start_time = Instant::now();
-                let key_pair = context.wallet.generate_key_pair();
+                let key_pair = context.wallet.generate_key_pair()?;
                 let owner = key_pair.public().into();
                 info!(
                     "Requesting a new chain for owner {owner} using the faucet at address \
@@ -1191,7 +1294,7 @@ impl Runnable for Job {
             }
 
             CreateGenesisConfig { .. }
-            | Keygen
+            | Keygen { .. }
             | Net(_)
             | Storage { .. }
             | Wallet(_)
@@ -1327,6 +1430,9 @@ impl ClientOptions {
             max_cache_size: self.max_cache_size,
             max_entry_size: self.max_entry_size,
             max_cache_entries: self.max_cache_entries,
+            cache_missing_keys: false,
+            entry_ttl: None,
+            invalidation_channel: None,
         };
         CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -1372,11 +1478,70 @@ impl ClientOptions {
         Ok(())
     }
 
+    /// Reads the wallet, decrypting its secret keys if it is encrypted. With an external
+    /// signer, the secret keys are not needed and the wallet is left locked.
     async fn wallet(&self) -> Result<WalletState<persistent::File<Wallet>>, Error> {
-        let wallet = persistent::File::read(&self.wallet_path()?)?;
+        let mut wallet = self.locked_wallet()?;
+        #[cfg(unix)]
+        let external_signer = self.inner.signer_socket.is_some();
+        #[cfg(not(unix))]
+        let external_signer = false;
+        if wallet.is_locked() && !external_signer {
+            let key = self.keystore_key(&wallet)?;
+            wallet.unlock(key)?;
+        }
         Ok(WalletState::new(wallet))
     }
 
+    /// Reads the wallet without decrypting its secret keys.
+    fn locked_wallet(&self) -> Result<persistent::File<Wallet>, Error> {
+        Ok(persistent::File::read(&self.wallet_path()?)?)
+    }
+
+    /// Returns the key protecting the secret keys of an encrypted wallet, either from the
+    /// session key printed by `linera wallet unlock` or from the wallet passphrase.
+    fn keystore_key(&self, wallet: &Wallet) -> Result<KeystoreKey, Error> {
+        let suffix = self.suffix();
+        if let Ok(session_key) = env::var(format!("LINERA_WALLET_KEY{suffix}")) {
+            return Ok(session_key.parse()?);
+        }
+        let passphrase = self.read_passphrase(&format!("LINERA_WALLET_PASSPHRASE{suffix}"))?;
+        Ok(wallet.derive_keystore_key(&passphrase)?)
+    }
+
+    /// Reads the wallet passphrase from the environment variable `var`, or prompts for it.
+    fn read_passphrase(&self, var: &str) -> Result<Zeroizing<String>, Error> {
+        if let Ok(passphrase) = env::var(var) {
+            return Ok(Zeroizing::new(passphrase));
+        }
+        ensure!(
+            std::io::stdin().is_terminal(),
+            "The wallet is encrypted: please set {var} or run `linera wallet unlock`"
+        );
+        Ok(Zeroizing::new(rpassword::prompt_password(
+            "Wallet passphrase: ",
+        )?))
+    }
+
+    /// Reads a new wallet passphrase from the environment variable `var`, or prompts for it
+    /// twice.
+    fn read_new_passphrase(&self, var: &str) -> Result<Zeroizing<String>, Error> {
+        if let Ok(passphrase) = env::var(var) {
+            return Ok(Zeroizing::new(passphrase));
+        }
+        ensure!(
+            std::io::stdin().is_terminal(),
+            "Please set {var} to the new wallet passphrase"
+        );
+        let passphrase = Zeroizing::new(rpassword::prompt_password("New wallet passphrase: ")?);
+        let confirmation = Zeroizing::new(rpassword::prompt_password(
+            "Confirm new wallet passphrase: ",
+        )?);
+        ensure!(passphrase == confirmation, "The passphrases do not match");
+        ensure!(!passphrase.is_empty(), "The passphrase must not be empty");
+        Ok(passphrase)
+    }
+
     fn suffix(&self) -> String {
         self.inner
             .with_wallet
@@ -1455,7 +1620,7 @@ impl ClientOptions {
     }
 }
 
-struct DatabaseToolJob<'a>(&'a DatabaseToolCommand);
+struct DatabaseToolJob<'a>(&'a DatabaseToolCommand, CommonStoreConfig);
 
 #[async_trait]
 impl RunnableWithStore for DatabaseToolJob<'_> {
@@ -1542,11 +1707,133 @@ impl RunnableWithStore for DatabaseToolJob<'_> {
                     println!("{}", id);
                 }
             }
+            DatabaseToolCommand::Snapshot { path } => {
+                let file = std::io::BufWriter::new(fs_err::File::create(path)?);
+                let footer = match snapshot::take_snapshot::<S, _>(&config, &namespace, file).await
+                {
+                    Ok(footer) => footer,
+                    Err(error) => {
+                        fs_err::remove_file(path)?;
+                        return Err(error.into());
+                    }
+                };
+                info!(
+                    "Snapshot of namespace {namespace} taken in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                println!(
+                    "{} root keys, {} key-value pairs, checksum {}",
+                    footer.root_keys,
+                    footer.key_values,
+                    hex::encode(footer.checksum)
+                );
+            }
+            DatabaseToolCommand::Restore { path } => {
+                let file = std::io::BufReader::new(fs_err::File::open(path)?);
+                let (header, footer) =
+                    snapshot::restore_snapshot::<S, _>(&config, &namespace, file).await?;
+                info!(
+                    "Snapshot of namespace {} taken from {} at {} restored into namespace \
+                    {namespace} in {} ms",
+                    header.namespace,
+                    header.backend,
+                    header.timestamp,
+                    start_time.elapsed().as_millis()
+                );
+                println!(
+                    "{} root keys, {} key-value pairs, checksum {}",
+                    footer.root_keys,
+                    footer.key_values,
+                    hex::encode(footer.checksum)
+                );
+            }
+            DatabaseToolCommand::Migrate {
+                target_storage_config,
+                progress,
+                batch_size,
+                skip_verification,
+            } => {
+                let target_config = target_storage_config
+                    .parse::<StorageConfigNamespace>()?
+                    .add_common_config(self.1)
+                    .await?;
+                let job = MigrationJob::<S> {
+                    source_config: config,
+                    source_namespace: namespace.clone(),
+                    progress_path: progress.clone(),
+                    options: MigrationOptions {
+                        batch_size: *batch_size,
+                        verify: !skip_verification,
+                        ..MigrationOptions::default()
+                    },
+                };
+                let summary = Box::pin(target_config.run_with_store(job)).await?;
+                info!(
+                    "Namespace {namespace} migrated in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                println!(
+                    "{} root keys and {} key-value pairs copied, {} root keys copied previously",
+                    summary.root_keys, summary.key_values, summary.skipped_root_keys
+                );
+            }
         }
         Ok(0)
     }
 }
 
+/// Copies a namespace of a store of type `S` into the store the job is run with.
+struct MigrationJob<S: KeyValueStore> {
+    source_config: S::Config,
+    source_namespace: String,
+    progress_path: Option<PathBuf>,
+    options: MigrationOptions,
+}
+
+#[async_trait]
+impl<S1> RunnableWithStore for MigrationJob<S1>
+where
+    S1: KeyValueStore + Clone + Send + Sync + 'static,
+    S1::Error: Send + Sync,
+{
+    type Output = MigrationSummary;
+
+    async fn run<S2>(
+        self,
+        config: S2::Config,
+        namespace: String,
+    ) -> Result<Self::Output, anyhow::Error>
+    where
+        S2: KeyValueStore + Clone + Send + Sync + 'static,
+        S2::Error: Send + Sync,
+    {
+        let mut progress = match &self.progress_path {
+            Some(path) if path.exists() => util::read_json(path)?,
+            _ => MigrationProgress::default(),
+        };
+        let save_progress = |progress: &MigrationProgress| -> std::io::Result<()> {
+            let Some(path) = &self.progress_path else {
+                return Ok(());
+            };
+            // Replace the file atomically, so that an interruption cannot corrupt it.
+            let temporary_path = path.with_extension("tmp");
+            fs_err::write(&temporary_path, serde_json::to_vec(progress)?)?;
+            fs_err::rename(temporary_path, path)
+        };
+        let summary = migrate_namespace::<S1, S2, _>(
+            &self.source_config,
+            &self.source_namespace,
+            &config,
+            &namespace,
+            &self.options,
+            &mut progress,
+            save_progress,
+        )
+        .await?;
+        Ok(summary)
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let options = ClientOptions::init();
 
@@ -1639,6 +1926,7 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             message_byte_price,
             service_as_oracle_query_price,
             http_request_price,
+            timer_price,
             maximum_fuel_per_block,
             maximum_service_oracle_execution_ms,
             maximum_block_size,
@@ -1650,6 +1938,7 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             maximum_bytes_written_per_block,
             maximum_oracle_response_bytes,
             maximum_http_response_bytes,
+            maximum_timer_payload_bytes,
             http_request_timeout_ms,
             http_request_allow_list,
             testing_prng_seed,
@@ -1679,6 +1968,7 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 service_as_oracle_query: service_as_oracle_query_price
                     .unwrap_or(existing_policy.service_as_oracle_query),
                 http_request: http_request_price.unwrap_or(existing_policy.http_request),
+                timer: timer_price.unwrap_or(existing_policy.timer),
                 maximum_fuel_per_block: maximum_fuel_per_block
                     .unwrap_or(existing_policy.maximum_fuel_per_block),
                 maximum_service_oracle_execution_ms: maximum_service_oracle_execution_ms
@@ -1700,6 +1990,8 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                     .unwrap_or(existing_policy.maximum_oracle_response_bytes),
                 maximum_http_response_bytes: maximum_http_response_bytes
                     .unwrap_or(existing_policy.maximum_http_response_bytes),
+                maximum_timer_payload_bytes: maximum_timer_payload_bytes
+                    .unwrap_or(existing_policy.maximum_timer_payload_bytes),
                 http_request_timeout_ms: http_request_timeout_ms
                     .unwrap_or(existing_policy.http_request_timeout_ms),
                 http_request_allow_list: http_request_allow_list
@@ -1781,10 +2073,14 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             }
         },
 
-        ClientCommand::Keygen => {
+        ClientCommand::Keygen { index, scheme } => {
             let start_time = Instant::now();
             let mut wallet = options.wallet().await?;
-            let key_pair = wallet.generate_key_pair();
+            let key_pair = match index {
+                Some(index) => wallet.derive_key_pair(*scheme, *index)?,
+                None if *scheme == SignatureScheme::Ed25519 => wallet.generate_key_pair()?,
+                None => wallet.derive_next_key_pair(*scheme)?,
+            };
             let owner = AccountOwner::from(key_pair.public());
             wallet
                 .mutate(|w| w.add_unassigned_key_pair(key_pair))
@@ -1794,6 +2090,18 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
             Ok(0)
         }
 
+        ClientCommand::SignerDaemon { socket } => {
+            let wallet = options.wallet().await?;
+            let signer = Arc::new(InMemorySigner::new(wallet.key_pairs()));
+            let cancellation_token = CancellationToken::new();
+            let child_token = cancellation_token.child_token();
+            tokio::spawn(listen_for_shutdown_signals(cancellation_token));
+            SignerDaemon::bind(socket.clone())?
+                .run(signer, child_token)
+                .await?;
+            Ok(0)
+        }
+
         ClientCommand::Net(net_command) => match net_command {
             #[cfg(feature = "kubernetes")]
             NetCommand::Up {
@@ -1885,7 +2193,8 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
         },
 
         ClientCommand::Storage(command) => {
-            Ok(options.run_with_store(DatabaseToolJob(command)).await?)
+            let job = DatabaseToolJob(command, options.common_config());
+            Ok(options.run_with_store(job).await?)
         }
 
         ClientCommand::Wallet(wallet_command) => match wallet_command {
@@ -1901,14 +2210,18 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 } else if *owned {
                     options.wallet().await?.owned_chain_ids()
                 } else {
-                    options.wallet().await?.chain_ids()
+                    options.locked_wallet()?.chain_ids()
                 };
                 if *short {
                     for chain_id in chain_ids {
                         println!("{chain_id}");
                     }
                 } else {
-                    wallet::pretty_print(&*options.wallet().await?, chain_ids);
+                    let wallet = options.locked_wallet()?;
+                    if wallet.is_locked() {
+                        info!("The wallet is encrypted: secret keys are not shown");
+                    }
+                    wallet::pretty_print(&wallet, chain_ids);
                 }
                 info!("Wallet shown in {} ms", start_time.elapsed().as_millis());
                 Ok(0)
@@ -1974,12 +2287,77 @@ async fn run(options: &ClientOptions) -> Result<i32, Error> {
                 Ok(0)
             }
 
+            WalletCommand::Encrypt => {
+                let start_time = Instant::now();
+                let mut wallet = options.wallet().await?;
+                ensure!(!wallet.is_encrypted(), "The wallet is already encrypted");
+                let suffix = options.suffix();
+                let passphrase =
+                    options.read_new_passphrase(&format!("LINERA_WALLET_PASSPHRASE{suffix}"))?;
+                let mut rng = Box::<dyn CryptoRng>::from(None);
+                wallet
+                    .mutate(|w| w.set_passphrase(&passphrase, &mut rng))
+                    .await??;
+                info!(
+                    "Wallet encrypted in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                Ok(0)
+            }
+
+            WalletCommand::Decrypt => {
+                let start_time = Instant::now();
+                options
+                    .wallet()
+                    .await?
+                    .mutate(|w| w.remove_passphrase())
+                    .await??;
+                info!(
+                    "Wallet decrypted in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                Ok(0)
+            }
+
+            WalletCommand::ChangePassphrase => {
+                let start_time = Instant::now();
+                let mut wallet = options.wallet().await?;
+                ensure!(wallet.is_encrypted(), "The wallet is not encrypted");
+                let suffix = options.suffix();
+                let passphrase = options
+                    .read_new_passphrase(&format!("LINERA_WALLET_NEW_PASSPHRASE{suffix}"))?;
+                let mut rng = Box::<dyn CryptoRng>::from(None);
+                wallet
+                    .mutate(|w| w.set_passphrase(&passphrase, &mut rng))
+                    .await??;
+                info!(
+                    "Wallet passphrase changed in {} ms",
+                    start_time.elapsed().as_millis()
+                );
+                Ok(0)
+            }
+
+            WalletCommand::Unlock => {
+                let wallet = options.locked_wallet()?;
+                ensure!(wallet.is_encrypted(), "The wallet is not encrypted");
+                let suffix = options.suffix();
+                let passphrase =
+                    options.read_passphrase(&format!("LINERA_WALLET_PASSPHRASE{suffix}"))?;
+                let key = wallet.derive_keystore_key(&passphrase)?;
+                println!(
+                    "export LINERA_WALLET_KEY{suffix}={}",
+                    key.to_session_string().as_str()
+                );
+                Ok(0)
+            }
+
             WalletCommand::Init {
                 genesis_config_path,
                 faucet,
                 with_new_chain,
                 with_other_chains,
                 testing_prng_seed,
+                mnemonic,
             } => {
                 let start_time = Instant::now();
                 let genesis_config: GenesisConfig = match (genesis_config_path, faucet) {
@@ -2011,17 +2389,46 @@ Make sure to use a Linera client compatible with this network.
                     }
                     (_, _) => bail!("Either --faucet or --genesis must be specified, but not both"),
                 };
+                let mnemonic = if *mnemonic {
+                    let var = format!("LINERA_WALLET_MNEMONIC{}", options.suffix());
+                    let mnemonic = match env::var(&var) {
+                        Ok(phrase) => Zeroizing::new(phrase).parse::<Mnemonic>()?,
+                        Err(_) => {
+                            let mut rng = Box::<dyn CryptoRng>::from(*testing_prng_seed);
+                            let mnemonic = Mnemonic::generate_from(&mut *rng);
+                            eprintln!(
+                                "Write down the following mnemonic: it is the only way to \
+                                restore the keys of this wallet.\n\n{mnemonic}\n"
+                            );
+                            mnemonic
+                        }
+                    };
+                    // The mnemonic is only stored in the encrypted keystore.
+                    let passphrase = options.read_new_passphrase(&format!(
+                        "LINERA_WALLET_PASSPHRASE{}",
+                        options.suffix()
+                    ))?;
+                    Some((mnemonic, passphrase))
+                } else {
+                    None
+                };
                 let timestamp = genesis_config.timestamp;
                 options
                     .create_wallet(genesis_config, *testing_prng_seed)?
                     .mutate(|wallet| {
+                        if let Some((mnemonic, passphrase)) = &mnemonic {
+                            let mut rng = Box::<dyn CryptoRng>::from(None);
+                            wallet.set_passphrase(passphrase, &mut rng)?;
+                            wallet.set_mnemonic(mnemonic)?;
+                        }
                         wallet.extend(
                             with_other_chains
                                 .iter()
                                 .map(|chain_id| UserChain::make_other(*chain_id, timestamp)),
-                        )
+                        );
+                        Ok::<_, linera_client::Error>(())
                     })
-                    .await?;
+                    .await??;
                 options.initialize_storage().boxed().await?;
                 if *with_new_chain {
                     ensure!(
@@ -100,6 +100,10 @@ impl StorageConfigProvider {
             StorageConfig::ScyllaDb { .. } => Ok(Database::ScyllaDb),
             #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
             StorageConfig::DualRocksDbScyllaDb { .. } => Ok(Database::DualRocksDbScyllaDb),
+            #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+            StorageConfig::MirroredRocksDbScyllaDb { .. } => {
+                anyhow::bail!("Not possible to work with a mirrored RocksDB and ScyllaDB")
+            }
         }
     }
 }
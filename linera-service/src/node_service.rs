@@ -1,21 +1,25 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(with_revm)]
+mod ethereum;
+
 use std::{borrow::Cow, future::IntoFuture, iter, net::SocketAddr, num::NonZeroU16, sync::Arc};
 
 use async_graphql::{
-    futures_util::Stream, resolver_utils::ContainerType, Error, MergedObject, OutputType,
-    ScalarType, Schema, SimpleObject, Subscription,
+    futures_util::Stream, resolver_utils::ContainerType, Error, InputObject, MergedObject,
+    OneofObject, OutputType, ScalarType, Schema, SimpleObject, Subscription,
 };
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
 use axum::{extract::Path, http::StatusCode, response, response::IntoResponse, Extension, Router};
-use futures::{lock::Mutex, Future, FutureExt as _};
+use futures::{future, lock::Mutex, Future, FutureExt as _, StreamExt as _};
 use linera_base::{
     crypto::{CryptoError, CryptoHash},
     data_types::{
-        Amount, ApplicationDescription, ApplicationPermissions, Bytecode, Epoch, TimeDelta,
+        Amount, ApplicationDescription, ApplicationPermissions, BlockHeight, Bytecode, Epoch,
+        TimeDelta,
     },
-    identifiers::{AccountOwner, ApplicationId, ChainId, ModuleId},
+    identifiers::{AccountOwner, ApplicationId, ChainId, ModuleId, StreamId},
     ownership::{ChainOwnership, TimeoutConfig},
     vm::VmRuntime,
     BcsHexParseError,
@@ -26,17 +30,19 @@ use linera_chain::{
 };
 use linera_client::chain_listener::{ChainListener, ChainListenerConfig, ClientContext};
 use linera_core::{
-    client::{ChainClient, ChainClientError},
-    data_types::ClientOutcome,
-    worker::Notification,
+    client::{ChainClient, ChainClientError, MessageRule},
+    data_types::{BatchPrecondition, ClientOutcome, DryRunOutcome, OperationBatch},
+    worker::{Notification, Reason},
 };
 use linera_execution::{
     committee::Committee,
     system::{AdminOperation, Recipient},
-    Operation, Query, QueryOutcome, QueryResponse, SystemOperation,
+    ExecutionStateView, InvalidProfileMetric, Operation, ProfileMetric, Query, QueryOutcome,
+    QueryResponse, SystemOperation,
 };
 use linera_sdk::linera_base_types::BlobContent;
 use linera_storage::Storage;
+use linera_views::proof::ViewProof;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use thiserror::Error as ThisError;
@@ -53,6 +59,46 @@ pub struct Chains {
     pub default: Option<ChainId>,
 }
 
+/// A proof about the execution state of a chain, with the hashes to check it against.
+#[derive(SimpleObject, Clone)]
+pub struct StateProof {
+    /// The proof, leading to the hash of the execution state.
+    pub proof: ViewProof,
+    /// The hash of the execution state after the latest block, as certified in that block.
+    pub state_hash: Option<CryptoHash>,
+    /// The hash of the latest block of the chain.
+    pub block_hash: Option<CryptoHash>,
+    /// The height of the next block of the chain.
+    pub next_block_height: BlockHeight,
+}
+
+/// The value that a state proof is expected to be about.
+#[derive(OneofObject)]
+pub enum ProvenValue {
+    /// The balance of an account owner.
+    Balance(AccountOwner),
+    /// The value at a key in the state of an application.
+    ApplicationState(ApplicationStateKey),
+}
+
+/// A key in the state of an application.
+#[derive(InputObject)]
+pub struct ApplicationStateKey {
+    /// The application.
+    pub application_id: ApplicationId,
+    /// The key in the state of the application.
+    pub key: Vec<u8>,
+}
+
+/// The parameters of a request to profile an application mutation.
+#[derive(Deserialize)]
+struct ProfileParameters {
+    /// The resource to profile, `fuel` by default.
+    metric: Option<String>,
+    /// The owner authenticating the profiled block, if any.
+    signer: Option<AccountOwner>,
+}
+
 /// Our root GraphQL query type.
 pub struct QueryRoot<C> {
     context: Arc<Mutex<C>>,
@@ -82,6 +128,8 @@ enum NodeServiceError {
     UnknownChainId { chain_id: String },
     #[error("malformed chain ID: {0}")]
     InvalidChainId(CryptoError),
+    #[error(transparent)]
+    InvalidProfileMetric(#[from] InvalidProfileMetric),
 }
 
 impl IntoResponse for NodeServiceError {
@@ -102,6 +150,9 @@ impl IntoResponse for NodeServiceError {
                 StatusCode::BAD_REQUEST,
                 vec!["invalid chain ID".to_string()],
             ),
+            NodeServiceError::InvalidProfileMetric(e) => {
+                (StatusCode::BAD_REQUEST, vec![e.to_string()])
+            }
         };
         let tuple = (tuple.0, json!({"error": tuple.1}).to_string());
         tuple.into_response()
@@ -113,13 +164,24 @@ impl<C> SubscriptionRoot<C>
 where
     C: ClientContext,
 {
-    /// Subscribes to notifications from the specified chain.
+    /// Subscribes to notifications from the specified chain. If `stream_ids` is given, new
+    /// events are only notified for these event streams.
     async fn notifications(
         &self,
         chain_id: ChainId,
+        stream_ids: Option<Vec<StreamId>>,
     ) -> Result<impl Stream<Item = Notification>, Error> {
         let client = self.context.lock().await.make_chain_client(chain_id)?;
-        Ok(client.subscribe().await?)
+        let notifications = client.subscribe().await?;
+        Ok(notifications.filter(move |notification| {
+            let keep = match (&notification.reason, &stream_ids) {
+                (Reason::NewEvents { stream_id, .. }, Some(stream_ids)) => {
+                    stream_ids.contains(stream_id)
+                }
+                _ => true,
+            };
+            future::ready(keep)
+        }))
     }
 }
 
@@ -220,6 +282,14 @@ where
         }
     }
 
+    /// Replaces the rules for handling the incoming messages of all chains, checked in order
+    /// before the blanket policy, and returns them.
+    async fn set_message_rules(&self, rules: Vec<MessageRule>) -> Vec<MessageRule> {
+        let context = self.context.lock().await;
+        context.message_policy().set_rules(rules);
+        context.message_policy().rules()
+    }
+
     /// Transfers `amount` units of value from the given owner's account to the recipient.
     /// If no owner is given, try to take the units out of the chain account.
     async fn transfer(
@@ -460,6 +530,7 @@ where
         change_application_permissions: Vec<ApplicationId>,
         call_service_as_oracle: Option<Vec<ApplicationId>>,
         make_http_requests: Option<Vec<ApplicationId>>,
+        upgrade_applications: Option<Vec<ApplicationId>>,
     ) -> Result<CryptoHash, Error> {
         let operation = SystemOperation::ChangeApplicationPermissions(ApplicationPermissions {
             execute_operations,
@@ -468,6 +539,7 @@ where
             change_application_permissions,
             call_service_as_oracle,
             make_http_requests,
+            upgrade_applications: upgrade_applications.unwrap_or_default(),
         });
         self.execute_system_operation(operation, chain_id).await
     }
@@ -572,6 +644,49 @@ where
         })
         .await
     }
+
+    /// Upgrades an application created by this chain to the code of another module, and runs
+    /// its migration, if the chain's application permissions allow it. Other chains apply the
+    /// upgrade when they process their inbox.
+    async fn upgrade_application(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        module_id: ModuleId,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::UpgradeApplication {
+            application_id,
+            module_id,
+        };
+        self.execute_system_operation(operation, chain_id).await
+    }
+
+    /// Executes system and user operations atomically in a single block, and returns the
+    /// hash of the block. If one of the preconditions does not hold for the state of the chain
+    /// before the block, no block is proposed.
+    async fn execute_batch(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+        preconditions: Option<Vec<BatchPrecondition>>,
+    ) -> Result<CryptoHash, Error> {
+        let batch = OperationBatch {
+            preconditions: preconditions.unwrap_or_default(),
+            operations,
+        };
+        self.apply_client_command(&chain_id, move |client| {
+            let batch = batch.clone();
+            async move {
+                let result = client
+                    .execute_batch(batch, vec![])
+                    .await
+                    .map_err(Error::from)
+                    .map(|outcome| outcome.map(|certificate| certificate.hash()));
+                (result, client)
+            }
+        })
+        .await
+    }
 }
 
 #[async_graphql::Object(cache_control(no_cache))]
@@ -612,6 +727,12 @@ where
         })
     }
 
+    /// Returns the rules for handling incoming messages, in the order in which they are
+    /// checked.
+    async fn message_rules(&self) -> Vec<MessageRule> {
+        self.context.lock().await.message_policy().rules()
+    }
+
     async fn block(
         &self,
         hash: Option<CryptoHash>,
@@ -656,12 +777,111 @@ where
         }
     }
 
+    /// Returns a proof of the balance of `owner` on the given chain, or of its absence.
+    async fn balance_proof(
+        &self,
+        chain_id: ChainId,
+        owner: AccountOwner,
+    ) -> Result<StateProof, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let view = client.chain_state_view().await?;
+        let proof = view.execution_state.balance_proof(&owner).await?;
+        Ok(StateProof::new(&view, proof))
+    }
+
+    /// Returns a proof of the value at `key` in the state of an application on the given
+    /// chain, or of its absence.
+    async fn application_state_proof(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        key: Vec<u8>,
+    ) -> Result<StateProof, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let view = client.chain_state_view().await?;
+        let proof = view
+            .execution_state
+            .application_state_proof(application_id, &key)
+            .await?;
+        Ok(StateProof::new(&view, proof))
+    }
+
+    /// Checks that a proof is about the expected value, and leads to the state hash of a
+    /// block. Returns the proven value in hexadecimal, or `null` for a proof of absence.
+    async fn verify_state_proof(
+        &self,
+        proof: ViewProof,
+        state_hash: CryptoHash,
+        value: ProvenValue,
+    ) -> Result<Option<String>, Error> {
+        type View<C> = ExecutionStateView<<<C as ClientContext>::Storage as Storage>::Context>;
+        let path = match value {
+            ProvenValue::Balance(owner) => View::<C>::balance_proof_path(&owner)?,
+            ProvenValue::ApplicationState(ApplicationStateKey {
+                application_id,
+                key,
+            }) => View::<C>::application_state_proof_path(application_id, &key)?,
+        };
+        proof.verify_crypto_hash(&path, "ExecutionStateViewHash", &state_hash)?;
+        Ok(proof.value_bytes().map(hex::encode))
+    }
+
+    /// Executes operations in a new block on top of the given chain's state, without signing
+    /// or committing it, and returns the messages, events, resources and fees of the block,
+    /// or the reason why its execution failed. The block is authenticated by the `signer`,
+    /// if any, whose key is not needed.
+    async fn dry_run(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+        signer: Option<AccountOwner>,
+    ) -> Result<DryRunOutcome, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        Ok(client
+            .dry_run_operations(operations, vec![], signer)
+            .await?)
+    }
+
+    /// Returns the fees that a new block with the given operations would be charged on the
+    /// given chain, authenticated by the `signer` if any. Fails if the execution of the block
+    /// fails.
+    async fn estimate_fees(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+        signer: Option<AccountOwner>,
+    ) -> Result<Amount, Error> {
+        let client = self.context.lock().await.make_chain_client(chain_id)?;
+        let outcome = client
+            .dry_run_operations(operations, vec![], signer)
+            .await?;
+        if let Some(error) = outcome.error {
+            return Err(Error::new(error));
+        }
+        Ok(outcome.fees.unwrap_or_default())
+    }
+
     /// Returns the version information on this node service.
     async fn version(&self) -> linera_version::VersionInfo {
         linera_version::VersionInfo::default()
     }
 }
 
+impl StateProof {
+    fn new<C>(view: &ChainStateView<C>, proof: ViewProof) -> Self
+    where
+        C: linera_views::context::Context + Clone + Send + Sync + 'static,
+    {
+        let tip_state = view.tip_state.get();
+        StateProof {
+            proof,
+            state_hash: *view.execution_state_hash.get(),
+            block_hash: tip_state.block_hash,
+            next_block_height: tip_state.next_block_height,
+        }
+    }
+}
+
 // What follows is a hack to add a chain_id field to `ChainStateView` based on
 // https://async-graphql.github.io/async-graphql/en/merging_objects.html
 
@@ -770,6 +990,9 @@ where
     default_chain: Option<ChainId>,
     storage: C::Storage,
     context: Arc<Mutex<C>>,
+    /// The Ethereum transactions sent through the node service.
+    #[cfg(with_revm)]
+    ethereum_transactions: Arc<std::sync::Mutex<ethereum::TransactionIndex>>,
 }
 
 impl<C> Clone for NodeService<C>
@@ -783,6 +1006,8 @@ where
             default_chain: self.default_chain,
             storage: self.storage.clone(),
             context: Arc::clone(&self.context),
+            #[cfg(with_revm)]
+            ethereum_transactions: Arc::clone(&self.ethereum_transactions),
         }
     }
 }
@@ -805,6 +1030,8 @@ where
             default_chain,
             storage,
             context: Arc::new(Mutex::new(context)),
+            #[cfg(with_revm)]
+            ethereum_transactions: Arc::default(),
         }
     }
 
@@ -832,6 +1059,7 @@ where
         let index_handler = axum::routing::get(util::graphiql).post(Self::index_handler);
         let application_handler =
             axum::routing::get(util::graphiql).post(Self::application_handler);
+        let profile_handler = axum::routing::post(Self::profile_handler);
 
         let app = Router::new()
             .route("/", index_handler)
@@ -839,8 +1067,18 @@ where
                 "/chains/:chain_id/applications/:application_id",
                 application_handler,
             )
+            .route(
+                "/chains/:chain_id/applications/:application_id/profile",
+                profile_handler,
+            )
             .route("/ready", axum::routing::get(|| async { "ready!" }))
-            .route_service("/ws", GraphQLSubscription::new(self.schema()))
+            .route_service("/ws", GraphQLSubscription::new(self.schema()));
+        #[cfg(with_revm)]
+        let app = app.route(
+            "/chains/:chain_id/applications/:application_id/ethereum",
+            axum::routing::post(Self::ethereum_handler),
+        );
+        let app = app
             .layer(Extension(self.clone()))
             // TODO(#551): Provide application authentication.
             .layer(CorsLayer::permissive());
@@ -879,6 +1117,17 @@ where
         }
 
         trace!("Query requested a new block with operations: {operations:?}");
+        let hash = self.execute_operations(chain_id, operations).await?;
+        let response = async_graphql::Response::new(hash.to_value());
+        Ok(serde_json::to_vec(&response)?)
+    }
+
+    /// Executes operations in a new block on the given chain, and returns the block's hash.
+    async fn execute_operations(
+        &self,
+        chain_id: ChainId,
+        operations: Vec<Operation>,
+    ) -> Result<CryptoHash, NodeServiceError> {
         let client = self
             .context
             .lock()
@@ -900,8 +1149,7 @@ where
             })?;
             util::wait_for_next_round(&mut stream, timeout).await;
         };
-        let response = async_graphql::Response::new(hash.to_value());
-        Ok(serde_json::to_vec(&response)?)
+        Ok(hash)
     }
 
     /// Queries a user application, returning the raw [`QueryOutcome`].
@@ -970,4 +1218,42 @@ where
 
         Ok(response)
     }
+
+    /// Executes a GraphQL mutation against an application, and profiles the operations that it
+    /// returns without committing them. The response is the profile of the `metric` resource
+    /// in the folded stacks format of flamegraph tools.
+    async fn profile_handler(
+        Path((chain_id, application_id)): Path<(String, String)>,
+        axum::extract::Query(parameters): axum::extract::Query<ProfileParameters>,
+        service: Extension<Self>,
+        request: String,
+    ) -> Result<String, NodeServiceError> {
+        let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
+        let application_id: ApplicationId = application_id.parse()?;
+        let metric = parameters
+            .metric
+            .as_deref()
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(ProfileMetric::Fuel);
+
+        debug!("Profiling request for application {application_id} on chain {chain_id}");
+        let QueryOutcome { operations, .. } = service
+            .0
+            .query_user_application(application_id, request.into_bytes(), chain_id)
+            .await?;
+        let client = service
+            .0
+            .context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .map_err(|_| NodeServiceError::UnknownChainId {
+                chain_id: chain_id.to_string(),
+            })?;
+        let profile = client
+            .profile_operations(operations, vec![], parameters.signer)
+            .await?;
+        Ok(profile.folded(metric).to_string())
+    }
 }
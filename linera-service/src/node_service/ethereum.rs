@@ -0,0 +1,994 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An Ethereum JSON-RPC endpoint for each EVM application, so that Ethereum libraries such
+//! as ethers or viem can talk to it.
+//!
+//! Calls are answered by the application's service. Gas estimations execute the call as an
+//! operation of the application in a block of the chain of the URL that is not committed, and
+//! add the Linera fuel to the EVM gas. Signed transactions are executed as operations of the
+//! application on that chain, which must be owned by the signer of the transaction and by a
+//! key of the wallet. The nonce of a transaction must be the next block height of that chain,
+//! which is what `eth_getTransactionCount` returns. Logs are decoded from the [`EvmLog`]
+//! events of the application in the confirmed blocks of the chain, and the block numbers are
+//! the heights of these blocks.
+//!
+//! Receipts are only known for the transactions sent through this node service since it
+//! started. The logs of the other transactions of a block refer to a hash derived from the
+//! block hash and the transaction index.
+
+use std::collections::{HashMap, VecDeque};
+
+use alloy::{
+    consensus::{Transaction as _, TxEnvelope},
+    eips::eip2718::Decodable2718 as _,
+    primitives::{keccak256, Address, Bloom, Bytes, B256, U256},
+    rpc::types::TransactionRequest,
+};
+use axum::{extract::Path, Extension};
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::BlockHeight,
+    identifiers::{AccountOwner, ApplicationId, ChainId, GenericApplicationId, StreamId},
+    vm::{evm_log_stream_name, EvmLog, EvmLogFilter, EvmQuery},
+};
+use linera_chain::block::Block;
+use linera_client::chain_listener::ClientContext;
+use linera_core::{
+    client::{ChainClient, ChainClientError},
+    data_types::{BatchPrecondition, ClientOutcome, OperationBatch},
+    node::ValidatorNodeProvider,
+};
+use linera_execution::Operation;
+use linera_storage::Storage;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use super::{NodeService, NodeServiceError};
+use crate::util;
+
+/// The maximal number of blocks searched by a single `eth_getLogs` request.
+const MAX_LOG_BLOCK_RANGE: u64 = 1000;
+
+/// The maximal number of transactions sent through the node service whose receipts are kept.
+const MAX_INDEXED_TRANSACTIONS: usize = 10_000;
+
+/// The JSON-RPC error code of invalid parameters.
+const INVALID_PARAMS: i64 = -32602;
+
+/// The JSON-RPC error code of unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The JSON-RPC error code of invalid requests.
+const INVALID_REQUEST: i64 = -32600;
+
+/// The JSON-RPC error code of failed executions, as used by Ethereum nodes.
+const SERVER_ERROR: i64 = -32000;
+
+/// Returns the Ethereum chain ID of a Linera chain. It is made of the first bytes of the
+/// chain ID, and kept below 2^53 so that JavaScript clients can represent it as a number.
+fn ethereum_chain_id(chain_id: ChainId) -> u64 {
+    let bytes = chain_id.0.as_bytes();
+    let prefix = u64::from_be_bytes(bytes[..8].try_into().expect("a hash has 32 bytes"));
+    prefix & ((1 << 53) - 1)
+}
+
+/// A JSON-RPC request.
+#[derive(Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+}
+
+/// A JSON-RPC error.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl ToString) -> Self {
+        RpcError {
+            code: INVALID_PARAMS,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<NodeServiceError> for RpcError {
+    fn from(error: NodeServiceError) -> Self {
+        RpcError {
+            code: SERVER_ERROR,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The filter of an `eth_getLogs` request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogFilter {
+    from_block: Option<String>,
+    to_block: Option<String>,
+    block_hash: Option<B256>,
+    address: Option<OneOrMany<Address>>,
+    #[serde(default)]
+    topics: Vec<Option<OneOrMany<B256>>>,
+}
+
+/// A value, or a list of accepted values.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrMany::One(one) => vec![one],
+            OneOrMany::Many(many) => many,
+        }
+    }
+}
+
+impl LogFilter {
+    /// Returns the filter on the addresses and topics of the logs.
+    fn log_filter(&self) -> EvmLogFilter {
+        EvmLogFilter {
+            addresses: self
+                .address
+                .clone()
+                .map(OneOrMany::into_vec)
+                .unwrap_or_default(),
+            topics: self
+                .topics
+                .iter()
+                .map(|topics| topics.clone().map(OneOrMany::into_vec))
+                .collect(),
+        }
+    }
+}
+
+/// Where an Ethereum transaction sent through the node service was executed.
+#[derive(Clone, Copy, Debug)]
+struct TransactionLocation {
+    chain_id: ChainId,
+    block_hash: CryptoHash,
+    transaction_index: u32,
+    from: Address,
+    to: Address,
+    transaction_type: u8,
+}
+
+/// The Ethereum transactions sent through the node service, so that their receipts can be
+/// returned and their logs refer to them. The oldest ones are forgotten first.
+#[derive(Default)]
+pub(super) struct TransactionIndex {
+    locations: HashMap<B256, TransactionLocation>,
+    hashes: HashMap<(CryptoHash, u32), B256>,
+    order: VecDeque<B256>,
+}
+
+impl TransactionIndex {
+    fn insert(&mut self, hash: B256, location: TransactionLocation) {
+        if self.locations.contains_key(&hash) {
+            return;
+        }
+        if self.order.len() == MAX_INDEXED_TRANSACTIONS {
+            if let Some(oldest) = self.order.pop_front() {
+                if let Some(old) = self.locations.remove(&oldest) {
+                    self.hashes.remove(&(old.block_hash, old.transaction_index));
+                }
+            }
+        }
+        self.hashes
+            .insert((location.block_hash, location.transaction_index), hash);
+        self.locations.insert(hash, location);
+        self.order.push_back(hash);
+    }
+
+    /// Returns the hash of a transaction of a block: the hash of the Ethereum transaction if
+    /// it was sent through the node service, otherwise a hash derived from its position.
+    fn transaction_hash(&self, block_hash: CryptoHash, transaction_index: u32) -> B256 {
+        self.hashes
+            .get(&(block_hash, transaction_index))
+            .copied()
+            .unwrap_or_else(|| {
+                let mut bytes = block_hash.as_bytes().to_vec();
+                bytes.extend(transaction_index.to_be_bytes());
+                keccak256(bytes)
+            })
+    }
+}
+
+/// A log of an application in a confirmed block.
+struct BlockLog {
+    transaction_index: u32,
+    log_index: u32,
+    log: EvmLog,
+}
+
+impl BlockLog {
+    /// Returns the log in the JSON-RPC format.
+    fn to_json(&self, block: &Block, block_hash: CryptoHash, transaction_hash: B256) -> Value {
+        json!({
+            "address": self.log.address,
+            "topics": self.log.topics,
+            "data": self.log.data,
+            "blockNumber": quantity(block.header.height.0),
+            "blockHash": block_hash.as_bytes(),
+            "transactionHash": transaction_hash,
+            "transactionIndex": quantity(u64::from(self.transaction_index)),
+            "logIndex": quantity(u64::from(self.log_index)),
+            "removed": false,
+        })
+    }
+}
+
+/// Returns the logs of an application in a block, decoded from its [`EvmLog`] events.
+fn block_logs(block: &Block, application_id: ApplicationId) -> Vec<BlockLog> {
+    let stream_id = StreamId {
+        application_id: GenericApplicationId::User(application_id),
+        stream_name: evm_log_stream_name(),
+    };
+    let mut logs = Vec::new();
+    for (transaction_index, events) in (0..).zip(&block.body.events) {
+        for event in events {
+            if event.stream_id != stream_id {
+                continue;
+            }
+            let log = match EvmLog::from_event_value(&event.value) {
+                Ok(Some(log)) => log,
+                // The logs of an older format are skipped, as by the indexer.
+                Ok(None) => continue,
+                Err(error) => {
+                    warn!(
+                        "failed to decode an EVM log of {application_id} at index {} of \
+                         transaction {transaction_index}: {error}",
+                        event.index
+                    );
+                    continue;
+                }
+            };
+            logs.push(BlockLog {
+                transaction_index,
+                log_index: event.index,
+                log,
+            });
+        }
+    }
+    logs
+}
+
+/// Checks that a signed transaction can be executed as an operation of the application on a
+/// chain with the given `owner` and next block height, and returns its signer.
+fn check_transaction(
+    transaction: &TxEnvelope,
+    chain_id: ChainId,
+    application_id: ApplicationId,
+    owner: AccountOwner,
+    next_height: BlockHeight,
+) -> Result<Address, RpcError> {
+    if transaction.chain_id() != Some(ethereum_chain_id(chain_id)) {
+        return Err(RpcError::invalid_params(
+            "the transaction must be signed for the chain ID of this chain",
+        ));
+    }
+    if transaction.to() != Some(application_id.evm_address()) {
+        return Err(RpcError::invalid_params(
+            "the transaction must be sent to the address of this application",
+        ));
+    }
+    if transaction.value() != U256::ZERO {
+        return Err(RpcError::invalid_params(
+            "transactions cannot transfer native tokens",
+        ));
+    }
+    let signer = transaction
+        .signature()
+        .recover_address_from_prehash(&transaction.signature_hash())
+        .map_err(RpcError::invalid_params)?;
+    if owner != AccountOwner::Address20(signer.into_array()) {
+        return Err(RpcError::invalid_params(
+            "the transaction must be signed by the owner of this chain",
+        ));
+    }
+    if transaction.nonce() != next_height.0 {
+        return Err(RpcError::invalid_params(format!(
+            "the nonce must be the next block height of the chain: {next_height}"
+        )));
+    }
+    Ok(signer)
+}
+
+/// Executes an operation in a block at the given height, and returns the hash of the block.
+///
+/// The height is checked again under the lock of the chain client, right before the block is
+/// created, so that a transaction whose nonce is the height is only executed once, even if
+/// it is sent several times concurrently.
+async fn execute_at_height<P, S>(
+    client: &ChainClient<P, S>,
+    operation: Operation,
+    height: BlockHeight,
+) -> Result<CryptoHash, NodeServiceError>
+where
+    P: ValidatorNodeProvider + Sync + 'static,
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    let batch = OperationBatch {
+        preconditions: vec![BatchPrecondition::BlockHeight(height)],
+        operations: vec![operation],
+    };
+    loop {
+        let timeout = match client.execute_batch(batch.clone(), vec![]).await? {
+            ClientOutcome::Committed(certificate) => return Ok(certificate.hash()),
+            ClientOutcome::WaitForTimeout(timeout) => timeout,
+        };
+        let mut stream = client.subscribe().await.map_err(|_| {
+            ChainClientError::InternalError("Could not subscribe to the local node.")
+        })?;
+        util::wait_for_next_round(&mut stream, timeout).await;
+    }
+}
+
+impl<C> NodeService<C>
+where
+    C: ClientContext,
+{
+    /// Handles Ethereum JSON-RPC requests to an EVM application, including batches.
+    pub(super) async fn ethereum_handler(
+        Path((chain_id, application_id)): Path<(String, String)>,
+        service: Extension<Self>,
+        request: String,
+    ) -> Result<String, NodeServiceError> {
+        let chain_id: ChainId = chain_id.parse().map_err(NodeServiceError::InvalidChainId)?;
+        let application_id: ApplicationId = application_id.parse()?;
+        let response = match serde_json::from_str::<Value>(&request)? {
+            Value::Array(requests) => {
+                let mut responses = Vec::new();
+                for request in requests {
+                    responses.push(
+                        service
+                            .0
+                            .handle_ethereum_request(chain_id, application_id, request)
+                            .await,
+                    );
+                }
+                Value::Array(responses)
+            }
+            request => {
+                service
+                    .0
+                    .handle_ethereum_request(chain_id, application_id, request)
+                    .await
+            }
+        };
+        Ok(response.to_string())
+    }
+
+    /// Handles a single JSON-RPC request, and returns its response.
+    async fn handle_ethereum_request(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        request: Value,
+    ) -> Value {
+        let request = match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) => request,
+            Err(error) => {
+                let error = RpcError {
+                    code: INVALID_REQUEST,
+                    message: error.to_string(),
+                };
+                return rpc_response(Value::Null, Err(error));
+            }
+        };
+        debug!(
+            "Processing Ethereum request {} for application {application_id} on chain {chain_id}",
+            request.method
+        );
+        let result = self
+            .ethereum_method(chain_id, application_id, &request.method, request.params)
+            .await;
+        rpc_response(request.id, result)
+    }
+
+    async fn ethereum_method(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        method: &str,
+        params: Vec<Value>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "eth_chainId" => Ok(quantity(ethereum_chain_id(chain_id))),
+            "eth_blockNumber" => {
+                let next_height = self.next_block_height(chain_id).await?;
+                Ok(quantity(next_height.0.saturating_sub(1)))
+            }
+            "eth_getTransactionCount" => Ok(quantity(self.next_block_height(chain_id).await?.0)),
+            "eth_call" => {
+                let (data, _) = call_data(application_id, params)?;
+                let output = self
+                    .query_evm(chain_id, application_id, EvmQuery::Query(data))
+                    .await?;
+                let output =
+                    serde_json::from_slice::<Vec<u8>>(&output).map_err(NodeServiceError::from)?;
+                Ok(json!(Bytes::from(output)))
+            }
+            "eth_estimateGas" => {
+                let (data, signer) = call_data(application_id, params)?;
+                let gas = self
+                    .estimate_gas(chain_id, application_id, data, signer)
+                    .await?;
+                Ok(quantity(gas))
+            }
+            "eth_sendRawTransaction" => {
+                let raw = first_param::<Bytes>(params)?;
+                let hash = self
+                    .send_raw_transaction(chain_id, application_id, &raw)
+                    .await?;
+                Ok(json!(hash))
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = first_param::<B256>(params)?;
+                self.transaction_receipt(chain_id, application_id, hash)
+                    .await
+            }
+            "eth_getLogs" => {
+                let filter = first_param::<LogFilter>(params)?;
+                self.logs(chain_id, application_id, filter).await
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("the method {method} does not exist or is not available"),
+            }),
+        }
+    }
+
+    /// Returns the height of the next block of a chain.
+    async fn next_block_height(&self, chain_id: ChainId) -> Result<BlockHeight, RpcError> {
+        let client = self.chain_client(chain_id).await?;
+        let view = client
+            .chain_state_view()
+            .await
+            .map_err(|error| NodeServiceError::ChainClientError(error.into()))?;
+        Ok(view.tip_state.get().next_block_height)
+    }
+
+    /// Sends a query to the service of an EVM application.
+    async fn query_evm(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        query: EvmQuery,
+    ) -> Result<Vec<u8>, RpcError> {
+        let query = serde_json::to_vec(&query).map_err(NodeServiceError::from)?;
+        let outcome = self
+            .query_user_application(application_id, query, chain_id)
+            .await?;
+        Ok(outcome.response)
+    }
+
+    /// Executes a call as an operation of the application in a block that is not committed,
+    /// and returns the EVM gas and the Linera fuel that it used.
+    async fn estimate_gas(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        data: Vec<u8>,
+        signer: Option<AccountOwner>,
+    ) -> Result<u64, RpcError> {
+        let operation = Operation::User {
+            application_id,
+            bytes: data,
+        };
+        let client = self.chain_client(chain_id).await?;
+        // The profile has both the fuel charged by Linera and the gas used by the EVM.
+        let profile = client
+            .profile_operations(vec![operation], vec![], signer)
+            .await
+            .map_err(|error| RpcError {
+                code: SERVER_ERROR,
+                message: format!("execution failed: {error}"),
+            })?;
+        Ok(profile.total().fuel)
+    }
+
+    /// Executes a signed Ethereum transaction as an operation of the application, and
+    /// returns the hash of the transaction.
+    async fn send_raw_transaction(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        raw: &[u8],
+    ) -> Result<B256, RpcError> {
+        let transaction =
+            TxEnvelope::decode_2718(&mut &raw[..]).map_err(RpcError::invalid_params)?;
+        let client = self.chain_client(chain_id).await?;
+        let owner = client.identity().await.map_err(NodeServiceError::from)?;
+        let next_height = self.next_block_height(chain_id).await?;
+        let signer = check_transaction(&transaction, chain_id, application_id, owner, next_height)?;
+        let operation = Operation::User {
+            application_id,
+            bytes: transaction.input().to_vec(),
+        };
+        let block_hash =
+            execute_at_height(&client, operation.clone(), BlockHeight(transaction.nonce())).await?;
+        let hash = *transaction.tx_hash();
+        debug!("Executed Ethereum transaction {hash} in block {block_hash}");
+        let block = client
+            .read_confirmed_block(block_hash)
+            .await
+            .map_err(NodeServiceError::from)?;
+        let body = &block.block().body;
+        // The incoming messages are executed before the operations.
+        if let Some(position) = body.operations.iter().position(|op| *op == operation) {
+            let location = TransactionLocation {
+                chain_id,
+                block_hash,
+                transaction_index: (body.incoming_bundles.len() + position) as u32,
+                from: signer,
+                to: application_id.evm_address(),
+                transaction_type: transaction.tx_type() as u8,
+            };
+            self.ethereum_transactions
+                .lock()
+                .unwrap()
+                .insert(hash, location);
+        }
+        Ok(hash)
+    }
+
+    /// Returns the receipt of an Ethereum transaction sent through the node service, or
+    /// `null` if it is unknown.
+    async fn transaction_receipt(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        hash: B256,
+    ) -> Result<Value, RpcError> {
+        let location = self
+            .ethereum_transactions
+            .lock()
+            .unwrap()
+            .locations
+            .get(&hash)
+            .copied();
+        let Some(location) = location.filter(|location| {
+            location.chain_id == chain_id && location.to == application_id.evm_address()
+        }) else {
+            return Ok(Value::Null);
+        };
+        let client = self.chain_client(chain_id).await?;
+        let block = client
+            .read_confirmed_block(location.block_hash)
+            .await
+            .map_err(NodeServiceError::from)?;
+        let block = block.block();
+        let mut bloom = Bloom::default();
+        let logs = block_logs(block, application_id)
+            .into_iter()
+            .filter(|log| log.transaction_index == location.transaction_index)
+            .map(|log| {
+                bloom.accrue_raw_log(log.log.address, &log.log.topics);
+                log.to_json(block, location.block_hash, hash)
+            })
+            .collect::<Vec<_>>();
+        // Linera blocks don't record the gas used by each transaction, and only contain the
+        // transactions that succeeded.
+        Ok(json!({
+            "transactionHash": hash,
+            "transactionIndex": quantity(u64::from(location.transaction_index)),
+            "blockHash": location.block_hash.as_bytes(),
+            "blockNumber": quantity(block.header.height.0),
+            "from": location.from,
+            "to": location.to,
+            "cumulativeGasUsed": quantity(0),
+            "gasUsed": quantity(0),
+            "effectiveGasPrice": quantity(0),
+            "contractAddress": Value::Null,
+            "logs": logs,
+            "logsBloom": bloom,
+            "status": quantity(1),
+            "type": quantity(u64::from(location.transaction_type)),
+        }))
+    }
+
+    /// Returns the logs of the application in the confirmed blocks of the chain that match
+    /// the filter.
+    async fn logs(
+        &self,
+        chain_id: ChainId,
+        application_id: ApplicationId,
+        filter: LogFilter,
+    ) -> Result<Value, RpcError> {
+        let client = self.chain_client(chain_id).await?;
+        let block_hashes = {
+            let view = client
+                .chain_state_view()
+                .await
+                .map_err(|error| NodeServiceError::ChainClientError(error.into()))?;
+            let next_height = view.tip_state.get().next_block_height.0;
+            let latest = next_height.saturating_sub(1);
+            let from = block_number(filter.from_block.as_deref(), latest)?;
+            let to = block_number(filter.to_block.as_deref(), latest)?.min(latest);
+            if next_height == 0 || from > to {
+                return Ok(json!([]));
+            }
+            if to - from >= MAX_LOG_BLOCK_RANGE {
+                return Err(RpcError::invalid_params(format!(
+                    "at most {MAX_LOG_BLOCK_RANGE} blocks can be searched at once"
+                )));
+            }
+            view.confirmed_log
+                .read(from as usize..=to as usize)
+                .await
+                .map_err(|error| NodeServiceError::ChainClientError(error.into()))?
+        };
+        let log_filter = filter.log_filter();
+        let mut logs = Vec::new();
+        for block_hash in block_hashes {
+            if filter
+                .block_hash
+                .is_some_and(|hash| hash != *block_hash.as_bytes())
+            {
+                continue;
+            }
+            let block = client
+                .read_confirmed_block(block_hash)
+                .await
+                .map_err(NodeServiceError::from)?;
+            let block = block.block();
+            let transactions = self.ethereum_transactions.lock().unwrap();
+            for log in block_logs(block, application_id) {
+                if log_filter.matches(&log.log) {
+                    let hash = transactions.transaction_hash(block_hash, log.transaction_index);
+                    logs.push(log.to_json(block, block_hash, hash));
+                }
+            }
+        }
+        Ok(Value::Array(logs))
+    }
+
+    async fn chain_client(
+        &self,
+        chain_id: ChainId,
+    ) -> Result<ChainClient<C::ValidatorNodeProvider, C::Storage>, RpcError> {
+        self.context
+            .lock()
+            .await
+            .make_chain_client(chain_id)
+            .map_err(|_| {
+                NodeServiceError::UnknownChainId {
+                    chain_id: chain_id.to_string(),
+                }
+                .into()
+            })
+    }
+}
+
+/// Returns the data and the sender of the transaction in the parameters of `eth_call` or
+/// `eth_estimateGas`.
+fn call_data(
+    application_id: ApplicationId,
+    params: Vec<Value>,
+) -> Result<(Vec<u8>, Option<AccountOwner>), RpcError> {
+    let request = first_param::<TransactionRequest>(params)?;
+    if request
+        .to
+        .is_some_and(|to| to.to() != Some(&application_id.evm_address()))
+    {
+        return Err(RpcError::invalid_params(
+            "the call must be sent to the address of this application",
+        ));
+    }
+    let data = request
+        .input
+        .input()
+        .map(|data| data.to_vec())
+        .unwrap_or_default();
+    let sender = request
+        .from
+        .map(|from| AccountOwner::Address20(from.into_array()));
+    Ok((data, sender))
+}
+
+/// Deserializes the first parameter of a request.
+fn first_param<T: for<'de> Deserialize<'de>>(params: Vec<Value>) -> Result<T, RpcError> {
+    let param = params
+        .into_iter()
+        .next()
+        .ok_or_else(|| RpcError::invalid_params("missing parameter"))?;
+    serde_json::from_value(param).map_err(RpcError::invalid_params)
+}
+
+/// Parses a block number of a filter, defaulting to the latest block.
+fn block_number(tag: Option<&str>, latest: u64) -> Result<u64, RpcError> {
+    match tag {
+        None | Some("latest" | "safe" | "finalized" | "pending") => Ok(latest),
+        Some("earliest") => Ok(0),
+        Some(number) => {
+            let digits = number
+                .strip_prefix("0x")
+                .ok_or_else(|| RpcError::invalid_params("invalid block number"))?;
+            u64::from_str_radix(digits, 16).map_err(RpcError::invalid_params)
+        }
+    }
+}
+
+/// Encodes a number as a JSON-RPC quantity.
+fn quantity(value: u64) -> Value {
+    json!(format!("{value:#x}"))
+}
+
+fn rpc_response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(RpcError { code, message }) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{SignableTransaction as _, TxEip1559},
+        primitives::TxKind,
+    };
+    use alloy_signer::SignerSync as _;
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::{sol, SolCall as _, SolValue as _};
+    use assert_matches::assert_matches;
+    use linera_base::{
+        crypto::{AccountSecretKey, EvmSecretKey},
+        data_types::{Amount, Bytecode, Event},
+        ownership::ChainOwnership,
+        vm::VmRuntime,
+    };
+    use linera_chain::{data_types::BlockExecutionOutcome, test::make_first_block};
+    use linera_core::test_utils::{MemoryStorageBuilder, TestBuilder};
+    use linera_execution::test_utils::solidity::load_solidity_example;
+
+    use super::*;
+
+    fn signed_transaction(
+        signer: &PrivateKeySigner,
+        chain_id: u64,
+        to: Address,
+        nonce: u64,
+    ) -> TxEnvelope {
+        let transaction = TxEip1559 {
+            chain_id,
+            nonce,
+            to: TxKind::Call(to),
+            input: Bytes::from(vec![1, 2, 3]),
+            ..TxEip1559::default()
+        };
+        let signature = signer
+            .sign_hash_sync(&transaction.signature_hash())
+            .unwrap();
+        transaction.into_signed(signature).into()
+    }
+
+    #[test]
+    fn test_transaction_checks() {
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        let application_id = ApplicationId::new(CryptoHash::test_hash("application"));
+        let signer = PrivateKeySigner::from_bytes(&B256::with_last_byte(1)).unwrap();
+        let other_signer = PrivateKeySigner::from_bytes(&B256::with_last_byte(2)).unwrap();
+        let owner = AccountOwner::Address20(signer.address().into_array());
+        let ethereum_chain_id = ethereum_chain_id(chain_id);
+        let to = application_id.evm_address();
+        let height = BlockHeight(3);
+        let check = |transaction: TxEnvelope| {
+            check_transaction(&transaction, chain_id, application_id, owner, height)
+                .map_err(|error| error.message)
+        };
+
+        let transaction = signed_transaction(&signer, ethereum_chain_id, to, 3);
+        assert_eq!(check(transaction).unwrap(), signer.address());
+
+        let transaction = signed_transaction(&signer, ethereum_chain_id + 1, to, 3);
+        assert!(check(transaction).unwrap_err().contains("chain ID"));
+
+        let transaction = signed_transaction(&signer, ethereum_chain_id, Address::ZERO, 3);
+        assert!(check(transaction).unwrap_err().contains("address"));
+
+        let transaction = signed_transaction(&other_signer, ethereum_chain_id, to, 3);
+        assert!(check(transaction).unwrap_err().contains("owner"));
+
+        let transaction = signed_transaction(&signer, ethereum_chain_id, to, 2);
+        assert!(check(transaction).unwrap_err().contains("nonce"));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_replays_are_executed_once() -> anyhow::Result<()> {
+        sol! {
+            struct ConstructorArgs {
+                uint64 initial_value;
+            }
+            function increment(uint64 input);
+        }
+
+        // A chain owned by an Ethereum account, with a counter application.
+        let mut builder = TestBuilder::new(MemoryStorageBuilder::default(), 4, 0).await?;
+        let creator = builder.add_root_chain(1, Amount::from_tokens(10)).await?;
+        let chain_id = creator.chain_id();
+        let key = EvmSecretKey::generate();
+        let signer = PrivateKeySigner::from_signing_key(key.0.clone());
+        let owner = AccountOwner::from(key.public());
+        creator
+            .change_ownership(ChainOwnership::single(owner))
+            .await?;
+        let client = builder
+            .make_client(
+                chain_id,
+                AccountSecretKey::EvmSecp256k1(key),
+                creator.block_hash(),
+                BlockHeight(1),
+            )
+            .await?;
+        let module = load_solidity_example("tests/fixtures/evm_example_counter.sol")?;
+        let module = Bytecode::new(module);
+        let (module_id, _) = client
+            .publish_module(module.clone(), module, VmRuntime::Evm)
+            .await?
+            .unwrap();
+        let argument = ConstructorArgs { initial_value: 0 }.abi_encode();
+        let (application_id, _) = client
+            .create_application(
+                module_id.with_abi::<(), (), Vec<u8>>(),
+                &(),
+                &argument,
+                vec![],
+            )
+            .await?
+            .unwrap();
+
+        let height = client.next_block_height();
+        let transaction = TxEip1559 {
+            chain_id: ethereum_chain_id(chain_id),
+            nonce: height.0,
+            to: TxKind::Call(application_id.evm_address()),
+            input: Bytes::from(incrementCall { input: 1 }.abi_encode()),
+            ..TxEip1559::default()
+        };
+        let signature = signer.sign_hash_sync(&transaction.signature_hash())?;
+        let transaction = TxEnvelope::from(transaction.into_signed(signature));
+        check_transaction(&transaction, chain_id, application_id, owner, height).unwrap();
+        let operation = Operation::User {
+            application_id,
+            bytes: transaction.input().to_vec(),
+        };
+
+        // Sending the transaction twice at once only executes it once.
+        let (first, second) = tokio::join!(
+            execute_at_height(&client, operation.clone(), height),
+            execute_at_height(&client, operation, height),
+        );
+        assert_ne!(first.is_ok(), second.is_ok());
+        assert_matches!(
+            first.and(second),
+            Err(NodeServiceError::ChainClientError(
+                ChainClientError::BatchPreconditionFailed {
+                    precondition: BatchPrecondition::BlockHeight(_),
+                    ..
+                }
+            ))
+        );
+        assert_eq!(client.next_block_height(), height.try_add_one()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_logs() {
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        let application_id = ApplicationId::new(CryptoHash::test_hash("application"));
+        let log = EvmLog {
+            origin: "operation".to_owned(),
+            address: application_id.evm_address(),
+            topics: vec![B256::with_last_byte(7)],
+            data: Bytes::from(vec![42]),
+        };
+        let value = log.to_event_value().unwrap();
+        // Logs written before the encoding had a version are skipped.
+        let old_value = bcs::to_bytes(&log).unwrap();
+        let event = |application_id, index, value: &Vec<u8>| Event {
+            stream_id: StreamId {
+                application_id,
+                stream_name: evm_log_stream_name(),
+            },
+            index,
+            value: value.clone(),
+        };
+        let application = GenericApplicationId::User(application_id);
+        let block = BlockExecutionOutcome {
+            events: vec![
+                vec![event(GenericApplicationId::System, 0, &value)],
+                vec![
+                    event(application, 0, &value),
+                    event(application, 1, &value),
+                    event(application, 2, &old_value),
+                ],
+            ],
+            ..BlockExecutionOutcome::default()
+        }
+        .with(make_first_block(chain_id));
+
+        let logs = block_logs(&block, application_id);
+        let positions = logs
+            .iter()
+            .map(|log| (log.transaction_index, log.log_index))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, [(1, 0), (1, 1)]);
+        assert_eq!(logs[0].log, log);
+
+        // The logs refer to the Ethereum transaction if it is known.
+        let block_hash = CryptoHash::test_hash("block");
+        let mut transactions = TransactionIndex::default();
+        let derived_hash = transactions.transaction_hash(block_hash, 1);
+        let hash = B256::with_last_byte(9);
+        transactions.insert(
+            hash,
+            TransactionLocation {
+                chain_id,
+                block_hash,
+                transaction_index: 1,
+                from: Address::ZERO,
+                to: application_id.evm_address(),
+                transaction_type: 2,
+            },
+        );
+        assert_ne!(derived_hash, hash);
+        assert_eq!(transactions.transaction_hash(block_hash, 1), hash);
+        assert_ne!(transactions.transaction_hash(block_hash, 0), derived_hash);
+
+        let json = logs[1].to_json(&block, block_hash, hash);
+        assert_eq!(json["transactionHash"], json!(hash));
+        assert_eq!(json["blockHash"], json!(block_hash.as_bytes()));
+        assert_eq!(json["blockNumber"], json!("0x0"));
+        assert_eq!(json["transactionIndex"], json!("0x1"));
+        assert_eq!(json["logIndex"], json!("0x1"));
+    }
+
+    #[test]
+    fn test_ethereum_chain_id_is_a_safe_integer() {
+        let chain_id = ChainId(CryptoHash::test_hash("chain"));
+        assert!(ethereum_chain_id(chain_id) < 1 << 53);
+    }
+
+    #[test]
+    fn test_block_numbers() {
+        assert_eq!(block_number(None, 7).ok(), Some(7));
+        assert_eq!(block_number(Some("earliest"), 7).ok(), Some(0));
+        assert_eq!(block_number(Some("0x1f"), 40).ok(), Some(31));
+        assert!(block_number(Some("31"), 40).is_err());
+    }
+
+    #[test]
+    fn test_filter_values() {
+        let filter: LogFilter = serde_json::from_value(json!({
+            "fromBlock": "0x1",
+            "address": "0x0000000000000000000000000000000000000001",
+            "topics": [null, ["0x0000000000000000000000000000000000000000000000000000000000000002"]],
+        }))
+        .unwrap();
+        let filter = filter.log_filter();
+        assert_eq!(filter.addresses, vec![Address::with_last_byte(1)]);
+        assert_eq!(
+            filter.topics,
+            vec![None, Some(vec![B256::with_last_byte(2)])]
+        );
+    }
+}
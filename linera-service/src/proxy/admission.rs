@@ -0,0 +1,418 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Admission control of the requests received by the gRPC proxy.
+//!
+//! Each client IP address, and each chain for each client IP address, gets a token bucket
+//! for every rate-limited method, so that a single noisy client cannot starve the others.
+//! The proxy doesn't check the signatures of the requests, so the tokens of a chain are not
+//! shared between clients: otherwise, anyone could use them up with forged requests.
+//!
+//! Block proposals and certificates are handled in separate lanes: certificates wait for a
+//! free slot in theirs, while proposals are rejected when their lane is full or when
+//! certificates are waiting.
+
+use std::{
+    hash::Hash,
+    net::IpAddr,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use linera_base::identifiers::ChainId;
+use lru::LruCache;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tonic::Status;
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec,
+    std::sync::LazyLock,
+};
+
+#[cfg(with_metrics)]
+static PROXY_REQUEST_REJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "proxy_request_rejected",
+        "Proxy requests rejected by the admission control",
+        &["method_name", "reason"],
+    )
+});
+
+/// The maximal number of token buckets of each kind. Above it, the least recently used ones
+/// are dropped.
+const MAX_TRACKED_BUCKETS: NonZeroUsize = NonZeroUsize::new(100_000).unwrap();
+
+/// The rate limits and the priority lanes of the gRPC proxy.
+#[derive(Clone, Debug, clap::Args)]
+pub struct AdmissionConfig {
+    /// The number of requests per second that each client IP address can send to each of
+    /// `handle_block_proposal`, `upload_blob`, `download_certificates` and `subscribe`. By
+    /// default, client IP addresses are not limited.
+    #[arg(long = "rate-limit-per-ip", env = "LINERA_PROXY_RATE_LIMIT_PER_IP")]
+    pub rate_limit_per_ip: Option<f64>,
+
+    /// The number of requests that each client IP address can send at once above its rate.
+    #[arg(
+        long = "rate-limit-per-ip-burst",
+        default_value = "100",
+        env = "LINERA_PROXY_RATE_LIMIT_PER_IP_BURST"
+    )]
+    pub rate_limit_per_ip_burst: u32,
+
+    /// The number of requests per second that each client IP address can send concerning
+    /// each chain, for `handle_block_proposal` and `subscribe`. By default, chains are not
+    /// limited.
+    #[arg(
+        long = "rate-limit-per-chain",
+        env = "LINERA_PROXY_RATE_LIMIT_PER_CHAIN"
+    )]
+    pub rate_limit_per_chain: Option<f64>,
+
+    /// The number of requests that each client IP address can send concerning each chain at
+    /// once above its rate.
+    #[arg(
+        long = "rate-limit-per-chain-burst",
+        default_value = "20",
+        env = "LINERA_PROXY_RATE_LIMIT_PER_CHAIN_BURST"
+    )]
+    pub rate_limit_per_chain_burst: u32,
+
+    /// The maximal number of block proposals forwarded to the shards at the same time.
+    /// Further proposals are rejected.
+    #[arg(
+        long,
+        default_value = "200",
+        env = "LINERA_PROXY_MAX_CONCURRENT_PROPOSALS"
+    )]
+    pub max_concurrent_proposals: usize,
+
+    /// The maximal number of certificates forwarded to the shards at the same time. Further
+    /// certificates wait, and block proposals are rejected in the meantime.
+    #[arg(
+        long,
+        default_value = "1000",
+        env = "LINERA_PROXY_MAX_CONCURRENT_CERTIFICATES"
+    )]
+    pub max_concurrent_certificates: usize,
+}
+
+/// Why a request was not admitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The client IP address exceeded its rate limit.
+    IpRateLimit,
+    /// The client IP address exceeded its rate limit for a chain concerned by the request.
+    ChainRateLimit,
+    /// The lane of the request is full, or has to yield to certificates.
+    Overloaded,
+}
+
+impl Rejection {
+    fn label(self) -> &'static str {
+        match self {
+            Rejection::IpRateLimit => "ip_rate_limit",
+            Rejection::ChainRateLimit => "chain_rate_limit",
+            Rejection::Overloaded => "overloaded",
+        }
+    }
+
+    /// Records the rejection of a `method_name` request and returns its gRPC status.
+    fn into_status(self, method_name: &str) -> Status {
+        #[cfg(with_metrics)]
+        PROXY_REQUEST_REJECTED
+            .with_label_values(&[method_name, self.label()])
+            .inc();
+        match self {
+            Rejection::IpRateLimit | Rejection::ChainRateLimit => Status::resource_exhausted(
+                format!("{method_name}: rate limit exceeded ({})", self.label()),
+            ),
+            Rejection::Overloaded => Status::resource_exhausted(format!(
+                "{method_name}: the validator is overloaded, try again later"
+            )),
+        }
+    }
+}
+
+/// A token bucket: it holds up to `burst` tokens, and gains `rate` tokens per second.
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(burst: f64, now: Instant) -> Self {
+        Self {
+            tokens: burst,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.updated_at = now;
+    }
+
+    /// Takes a token if there is one.
+    fn try_take(&mut self, rate: f64, burst: f64, now: Instant) -> bool {
+        self.refill(rate, burst, now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// The token buckets of one kind of key, for each rate-limited method.
+///
+/// At most `capacity` buckets are kept. The one dropped to make room for a new one is the
+/// least recently used, i.e. the one that had the most time to refill.
+struct RateLimiter<K: Eq + Hash> {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<LruCache<(&'static str, K), TokenBucket>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    fn new(rate: f64, burst: u32, capacity: NonZeroUsize) -> Self {
+        Self {
+            rate,
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Takes a token from the bucket of `key` for `method_name`.
+    fn try_take(&self, method_name: &'static str, key: K, now: Instant) -> bool {
+        self.buckets
+            .lock()
+            .unwrap()
+            .get_or_insert_mut((method_name, key), || TokenBucket::full(self.burst, now))
+            .try_take(self.rate, self.burst, now)
+    }
+}
+
+/// Decrements the number of waiting certificates when dropped, even if the request is
+/// cancelled while waiting.
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The admission control of the gRPC proxy.
+pub struct AdmissionControl {
+    per_ip: Option<RateLimiter<IpAddr>>,
+    per_chain: Option<RateLimiter<(Option<IpAddr>, ChainId)>>,
+    proposals: Semaphore,
+    certificates: Semaphore,
+    waiting_certificates: AtomicUsize,
+}
+
+impl AdmissionControl {
+    pub fn new(config: &AdmissionConfig) -> Self {
+        Self {
+            per_ip: config.rate_limit_per_ip.map(|rate| {
+                RateLimiter::new(rate, config.rate_limit_per_ip_burst, MAX_TRACKED_BUCKETS)
+            }),
+            per_chain: config.rate_limit_per_chain.map(|rate| {
+                RateLimiter::new(rate, config.rate_limit_per_chain_burst, MAX_TRACKED_BUCKETS)
+            }),
+            proposals: Semaphore::new(config.max_concurrent_proposals),
+            certificates: Semaphore::new(config.max_concurrent_certificates),
+            waiting_certificates: AtomicUsize::new(0),
+        }
+    }
+
+    /// Takes a token for a `method_name` request from the client at `ip`, if known, and for
+    /// each of the `chain_ids` it concerns from that client.
+    #[allow(clippy::result_large_err)]
+    pub fn check_rate_limits(
+        &self,
+        method_name: &'static str,
+        ip: Option<IpAddr>,
+        chain_ids: &[ChainId],
+    ) -> Result<(), Status> {
+        self.check_rate_limits_at(method_name, ip, chain_ids, Instant::now())
+            .map_err(|rejection| rejection.into_status(method_name))
+    }
+
+    fn check_rate_limits_at(
+        &self,
+        method_name: &'static str,
+        ip: Option<IpAddr>,
+        chain_ids: &[ChainId],
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if let (Some(limiter), Some(ip)) = (&self.per_ip, ip) {
+            if !limiter.try_take(method_name, ip, now) {
+                return Err(Rejection::IpRateLimit);
+            }
+        }
+        if let Some(limiter) = &self.per_chain {
+            for chain_id in chain_ids {
+                if !limiter.try_take(method_name, (ip, *chain_id), now) {
+                    return Err(Rejection::ChainRateLimit);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Admits a block proposal into its lane, unless the lane is full or certificates are
+    /// waiting for theirs. The proposal keeps its slot until the permit is dropped.
+    #[allow(clippy::result_large_err)]
+    pub fn admit_proposal(&self, method_name: &str) -> Result<SemaphorePermit<'_>, Status> {
+        self.try_admit_proposal()
+            .map_err(|rejection| rejection.into_status(method_name))
+    }
+
+    fn try_admit_proposal(&self) -> Result<SemaphorePermit<'_>, Rejection> {
+        if self.waiting_certificates.load(Ordering::Acquire) > 0 {
+            return Err(Rejection::Overloaded);
+        }
+        self.proposals
+            .try_acquire()
+            .map_err(|_| Rejection::Overloaded)
+    }
+
+    /// Waits for a slot in the lane of certificates. The certificate keeps its slot until
+    /// the permit is dropped.
+    pub async fn admit_certificate(&self) -> SemaphorePermit<'_> {
+        if let Ok(permit) = self.certificates.try_acquire() {
+            return permit;
+        }
+        self.waiting_certificates.fetch_add(1, Ordering::AcqRel);
+        let _guard = WaitingGuard(&self.waiting_certificates);
+        self.certificates
+            .acquire()
+            .await
+            .expect("the semaphore is never closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use linera_base::crypto::CryptoHash;
+
+    use super::*;
+
+    fn config() -> AdmissionConfig {
+        AdmissionConfig {
+            rate_limit_per_ip: Some(1.0),
+            rate_limit_per_ip_burst: 2,
+            rate_limit_per_chain: Some(1.0),
+            rate_limit_per_chain_burst: 3,
+            max_concurrent_proposals: 1,
+            max_concurrent_certificates: 1,
+        }
+    }
+
+    fn chain_id(index: u8) -> ChainId {
+        ChainId(CryptoHash::test_hash(index.to_string()))
+    }
+
+    #[test]
+    fn limits_each_ip_and_method() {
+        let admission = AdmissionControl::new(&config());
+        let now = Instant::now();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let other_ip = IpAddr::from([127, 0, 0, 2]);
+        for _ in 0..2 {
+            assert!(admission
+                .check_rate_limits_at("upload_blob", Some(ip), &[], now)
+                .is_ok());
+        }
+        assert_eq!(
+            admission.check_rate_limits_at("upload_blob", Some(ip), &[], now),
+            Err(Rejection::IpRateLimit)
+        );
+        assert!(admission
+            .check_rate_limits_at("upload_blob", Some(other_ip), &[], now)
+            .is_ok());
+        assert!(admission
+            .check_rate_limits_at("subscribe", Some(ip), &[], now)
+            .is_ok());
+        let later = now + Duration::from_secs(1);
+        assert!(admission
+            .check_rate_limits_at("upload_blob", Some(ip), &[], later)
+            .is_ok());
+    }
+
+    #[test]
+    fn limits_each_chain_for_each_ip() {
+        let admission = AdmissionControl::new(&AdmissionConfig {
+            rate_limit_per_ip: None,
+            ..config()
+        });
+        let now = Instant::now();
+        let ip = IpAddr::from([10, 0, 0, 1]);
+        let chains = [chain_id(0), chain_id(1)];
+        for _ in 0..3 {
+            assert!(admission
+                .check_rate_limits_at("subscribe", Some(ip), &chains, now)
+                .is_ok());
+        }
+        assert_eq!(
+            admission.check_rate_limits_at("subscribe", Some(ip), &chains[1..], now),
+            Err(Rejection::ChainRateLimit)
+        );
+        assert!(admission
+            .check_rate_limits_at("subscribe", Some(ip), &[chain_id(2)], now)
+            .is_ok());
+        // Other clients still have the tokens of the chain, e.g. if the requests of the
+        // first one were forged.
+        let other_ip = IpAddr::from([10, 0, 0, 2]);
+        assert!(admission
+            .check_rate_limits_at("subscribe", Some(other_ip), &chains, now)
+            .is_ok());
+    }
+
+    #[test]
+    fn drops_least_recently_used_buckets() {
+        let limiter = RateLimiter::new(1.0, 1, NonZeroUsize::new(2).unwrap());
+        let now = Instant::now();
+        assert!(limiter.try_take("subscribe", 0, now));
+        assert!(limiter.try_take("subscribe", 1, now));
+        assert!(!limiter.try_take("subscribe", 0, now));
+        // The bucket of `1` is dropped to make room, and later starts full again.
+        assert!(limiter.try_take("subscribe", 2, now));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+        assert!(!limiter.try_take("subscribe", 0, now));
+        assert!(limiter.try_take("subscribe", 1, now));
+    }
+
+    #[tokio::test]
+    async fn certificates_have_priority_over_proposals() {
+        let admission = AdmissionControl::new(&config());
+        let proposal = admission.try_admit_proposal().unwrap();
+        assert_eq!(
+            admission.try_admit_proposal().err(),
+            Some(Rejection::Overloaded)
+        );
+        drop(proposal);
+
+        let certificate = admission.admit_certificate().await;
+        assert!(admission.try_admit_proposal().is_ok());
+        let waiting = admission.admit_certificate();
+        tokio::pin!(waiting);
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        assert_eq!(
+            admission.try_admit_proposal().err(),
+            Some(Rejection::Overloaded)
+        );
+        drop(certificate);
+        let _certificate = waiting.await;
+        assert!(admission.try_admit_proposal().is_ok());
+    }
+}
@@ -20,11 +20,13 @@ use async_trait::async_trait;
 use futures::{future::BoxFuture, FutureExt as _};
 use linera_base::identifiers::ChainId;
 use linera_client::config::GenesisConfig;
-use linera_core::{notifier::ChannelNotifier, JoinSetExt as _};
+use linera_core::{
+    notifier::ChannelNotifier,
+    worker::{self, Reason},
+    JoinSetExt as _,
+};
 use linera_rpc::{
-    config::{
-        ShardConfig, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
-    },
+    config::{ShardConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig},
     grpc::{
         api::{
             self,
@@ -44,11 +46,11 @@ use linera_rpc::{
 use linera_sdk::{linera_base_types::Blob, views::ViewError};
 use linera_storage::Storage;
 use prost::Message;
-use tokio::{select, task::JoinSet};
+use tokio::{net::TcpListener, select, task::JoinSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tonic::{
-    transport::{Channel, Identity, Server, ServerTlsConfig},
+    transport::{Channel, Server},
     Request, Response, Status,
 };
 use tower::{builder::ServiceBuilder, Layer, Service};
@@ -63,6 +65,10 @@ use {
 
 #[cfg(with_metrics)]
 use crate::prometheus_server;
+use crate::{
+    admission::{AdmissionConfig, AdmissionControl},
+    ProxyCertificates,
+};
 
 #[cfg(with_metrics)]
 static PROXY_REQUEST_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
@@ -153,7 +159,10 @@ struct GrpcProxyInner<S> {
     genesis_config: GenesisConfig,
     worker_connection_pool: GrpcConnectionPool,
     notifier: ChannelNotifier<Result<Notification, Status>>,
-    tls: TlsConfig,
+    /// The subscribers that did not ask for the notifications of new events.
+    notifier_without_events: ChannelNotifier<Result<Notification, Status>>,
+    certificates: ProxyCertificates,
+    admission: AdmissionControl,
     storage: S,
 }
 
@@ -161,13 +170,15 @@ impl<S> GrpcProxy<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
         public_config: ValidatorPublicNetworkConfig,
         internal_config: ValidatorInternalNetworkConfig,
         genesis_config: GenesisConfig,
         connect_timeout: Duration,
         timeout: Duration,
-        tls: TlsConfig,
+        certificates: ProxyCertificates,
+        admission: &AdmissionConfig,
         storage: S,
     ) -> Self {
         Self(Arc::new(GrpcProxyInner {
@@ -176,9 +187,12 @@ where
             genesis_config,
             worker_connection_pool: GrpcConnectionPool::default()
                 .with_connect_timeout(connect_timeout)
-                .with_timeout(timeout),
+                .with_timeout(timeout)
+                .with_certificates(certificates.internal.clone()),
             notifier: ChannelNotifier::default(),
-            tls,
+            notifier_without_events: ChannelNotifier::default(),
+            certificates,
+            admission: AdmissionControl::new(admission),
             storage,
         }))
     }
@@ -218,7 +232,7 @@ where
         &self,
         shard: &ShardConfig,
     ) -> Result<ValidatorWorkerClient<Channel>> {
-        let address = shard.http_address();
+        let address = self.0.internal_config.shard_address(shard);
         let channel = self.0.worker_connection_pool.channel(address)?;
         let client = ValidatorWorkerClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -250,28 +264,61 @@ where
         health_reporter
             .set_serving::<ValidatorNodeServer<GrpcProxy<S>>>()
             .await;
+        let internal_router = Server::builder().add_service(self.as_notifier_service());
+        let internal_address = self.internal_address();
+        let internal_certificates = self.0.certificates.internal.clone();
         let internal_server = join_set.spawn_task(
-            Server::builder()
-                .add_service(self.as_notifier_service())
-                .serve(self.internal_address())
-                .in_current_span(),
+            async move {
+                match internal_certificates {
+                    Some(certificates) => {
+                        let listener = TcpListener::bind(internal_address).await?;
+                        internal_router
+                            .serve_with_incoming(certificates.grpc_incoming(listener))
+                            .await?
+                    }
+                    None => internal_router.serve(internal_address).await?,
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .in_current_span(),
         );
         let reflection_service = tonic_reflection::server::Builder::configure()
             .register_encoded_file_descriptor_set(linera_rpc::FILE_DESCRIPTOR_SET)
             .build_v1()?;
+        let public_router = Server::builder()
+            .layer(
+                ServiceBuilder::new()
+                    .layer(PrometheusMetricsMiddlewareLayer)
+                    .into_inner(),
+            )
+            .accept_http1(true)
+            .add_service(health_service)
+            .add_service(tonic_web::enable(self.as_validator_node()))
+            .add_service(tonic_web::enable(reflection_service));
+        let public_address = self.public_address();
+        let public_certificates = self.0.certificates.public.clone();
         let public_server = join_set.spawn_task(
-            self.public_server()?
-                .layer(
-                    ServiceBuilder::new()
-                        .layer(PrometheusMetricsMiddlewareLayer)
-                        .into_inner(),
-                )
-                .accept_http1(true)
-                .add_service(health_service)
-                .add_service(tonic_web::enable(self.as_validator_node()))
-                .add_service(tonic_web::enable(reflection_service))
-                .serve_with_shutdown(self.public_address(), shutdown_signal.cancelled_owned())
-                .in_current_span(),
+            async move {
+                let shutdown = shutdown_signal.cancelled_owned();
+                match public_certificates {
+                    Some(certificates) => {
+                        let listener = TcpListener::bind(public_address).await?;
+                        public_router
+                            .serve_with_incoming_shutdown(
+                                certificates.grpc_incoming(listener),
+                                shutdown,
+                            )
+                            .await?
+                    }
+                    None => {
+                        public_router
+                            .serve_with_shutdown(public_address, shutdown)
+                            .await?
+                    }
+                }
+                Ok::<_, anyhow::Error>(())
+            }
+            .in_current_span(),
         );
 
         select! {
@@ -281,20 +328,6 @@ where
         Ok(())
     }
 
-    /// Pre-configures the public server with no services attached.
-    /// If a certificate and key are defined, creates a TLS server.
-    fn public_server(&self) -> Result<Server> {
-        match self.0.tls {
-            TlsConfig::Tls => {
-                use linera_rpc::{CERT_PEM, KEY_PEM};
-                let identity = Identity::from_pem(CERT_PEM, KEY_PEM);
-                let tls_config = ServerTlsConfig::new().identity(identity);
-                Ok(Server::builder().tls_config(tls_config)?)
-            }
-            TlsConfig::ClearText => Ok(Server::builder()),
-        }
-    }
-
     async fn worker_client<R>(
         &self,
         request: Request<R>,
@@ -313,6 +346,21 @@ where
         Ok((client, inner))
     }
 
+    /// Applies the rate limits of the client that sent `request` and of the chains it
+    /// concerns.
+    #[allow(clippy::result_large_err)]
+    fn check_rate_limits<R>(
+        &self,
+        method_name: &'static str,
+        request: &Request<R>,
+        chain_ids: &[ChainId],
+    ) -> Result<(), Status> {
+        let ip = request.remote_addr().map(|address| address.ip());
+        self.0
+            .admission
+            .check_rate_limits(method_name, ip, chain_ids)
+    }
+
     #[allow(clippy::result_large_err)]
     fn log_and_return_proxy_request_outcome(
         result: Result<Response<ChainInfoResult>, Status>,
@@ -346,6 +394,8 @@ where
             | ViewError::TryLockError(_)
             | ViewError::InconsistentEntries
             | ViewError::PostLoadValuesError
+            | ViewError::TooManyIndexes(_)
+            | ViewError::UnsupportedSchemaVersion { .. }
             | ViewError::IoError(_) => Status::internal(err.to_string()),
             ViewError::KeyTooLong | ViewError::ArithmeticError(_) => {
                 Status::out_of_range(err.to_string())
@@ -353,6 +403,7 @@ where
             ViewError::NotFound(_)
             | ViewError::BlobsNotFound(_)
             | ViewError::EventsNotFound(_)
+            | ViewError::CertificatePruned { .. }
             | ViewError::CannotAcquireCollectionEntry
             | ViewError::MissingEntries => Status::not_found(err.to_string()),
         };
@@ -373,6 +424,10 @@ where
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let method_name = "handle_block_proposal";
+        let chain_ids = Vec::from_iter(request.get_ref().chain_id());
+        self.check_rate_limits(method_name, &request, &chain_ids)?;
+        let _permit = self.0.admission.admit_proposal(method_name)?;
         let (mut client, inner) = self.worker_client(request).await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_block_proposal(inner).await,
@@ -385,6 +440,7 @@ where
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _permit = self.0.admission.admit_certificate().await;
         let (mut client, inner) = self.worker_client(request).await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_lite_certificate(inner).await,
@@ -397,6 +453,7 @@ where
         &self,
         request: Request<api::HandleConfirmedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _permit = self.0.admission.admit_certificate().await;
         let (mut client, inner) = self.worker_client(request).await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_confirmed_certificate(inner).await,
@@ -409,6 +466,7 @@ where
         &self,
         request: Request<api::HandleValidatedCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _permit = self.0.admission.admit_certificate().await;
         let (mut client, inner) = self.worker_client(request).await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_validated_certificate(inner).await,
@@ -421,6 +479,7 @@ where
         &self,
         request: Request<api::HandleTimeoutCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _permit = self.0.admission.admit_certificate().await;
         let (mut client, inner) = self.worker_client(request).await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_timeout_certificate(inner).await,
@@ -445,18 +504,23 @@ where
         &self,
         request: Request<SubscriptionRequest>,
     ) -> Result<Response<Self::SubscribeStream>, Status> {
-        let subscription_request = request.into_inner();
-        let chain_ids = subscription_request
+        let chain_ids = request
+            .get_ref()
             .chain_ids
-            .into_iter()
+            .iter()
+            .cloned()
             .map(ChainId::try_from)
             .collect::<Result<Vec<ChainId>, _>>()?;
+        self.check_rate_limits("subscribe", &request, &chain_ids)?;
+        // Older clients fail to decode the notifications of new events.
+        let notifier = if request.get_ref().with_events {
+            &self.0.notifier
+        } else {
+            &self.0.notifier_without_events
+        };
         // The empty notification seems to be needed in some cases to force
         // completion of HTTP2 headers.
-        let rx = self
-            .0
-            .notifier
-            .subscribe_with_ack(chain_ids, Ok(Notification::default()));
+        let rx = notifier.subscribe_with_ack(chain_ids, Ok(Notification::default()));
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
 
@@ -479,6 +543,7 @@ where
 
     #[instrument(skip_all, err(Display))]
     async fn upload_blob(&self, request: Request<BlobContent>) -> Result<Response<BlobId>, Status> {
+        self.check_rate_limits("upload_blob", &request, &[])?;
         let content: linera_sdk::linera_base_types::BlobContent =
             request.into_inner().try_into()?;
         let blob = Blob::new(content);
@@ -576,6 +641,7 @@ where
         &self,
         request: Request<CertificatesBatchRequest>,
     ) -> Result<Response<CertificatesBatchResponse>, Status> {
+        self.check_rate_limits("download_certificates", &request, &[])?;
         let hashes: Vec<linera_base::crypto::CryptoHash> = request
             .into_inner()
             .hashes
@@ -655,6 +721,18 @@ where
             .clone()
             .ok_or_else(|| Status::invalid_argument("Missing field: chain_id."))?
             .try_into()?;
+        let is_new_events = matches!(
+            Option::<worker::Notification>::try_from(notification.clone()),
+            Ok(Some(worker::Notification {
+                reason: Reason::NewEvents { .. },
+                ..
+            }))
+        );
+        if !is_new_events {
+            self.0
+                .notifier_without_events
+                .notify_chain(&chain_id, &Ok(notification.clone()));
+        }
         self.0.notifier.notify_chain(&chain_id, &Ok(notification));
         Ok(Response::new(()))
     }
@@ -17,13 +17,17 @@ use linera_rpc::{
         ValidatorPublicNetworkPreConfig,
     },
     simple::{MessageHandler, TransportProtocol},
+    tls::TlsCertificates,
     RpcMessage,
 };
 use linera_sdk::linera_base_types::Blob;
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
 use linera_service::{
+    cache_invalidation::CacheInvalidationConfig,
+    shard_assignment::watch_shard_assignment,
     storage::{Runnable, StorageConfigNamespace},
+    tls::{load_and_watch_certificates, load_internal_certificates},
     util,
 };
 use linera_storage::Storage;
@@ -32,7 +36,9 @@ use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, instrument};
 
+mod admission;
 mod grpc;
+use admission::AdmissionConfig;
 use grpc::GrpcProxy;
 
 /// Options for running the proxy.
@@ -92,12 +98,46 @@ pub struct ProxyOptions {
     #[arg(long, default_value = "1000")]
     pub max_cache_entries: usize,
 
+    /// Whether the storage cache also remembers missing keys. Requires
+    /// `--cache-invalidation-address`, to learn about the writes of the other processes
+    /// sharing the database.
+    #[arg(long)]
+    pub cache_missing_keys: bool,
+
+    /// How long an entry stays in the storage cache, in milliseconds. By default, entries
+    /// only leave the cache when it is full.
+    #[arg(long = "cache-entry-ttl-ms", value_parser = util::parse_millis)]
+    pub cache_entry_ttl: Option<Duration>,
+
     /// Path to the file describing the initial user chains (aka genesis state)
     #[arg(long = "genesis")]
     genesis_config_path: PathBuf,
+
+    /// How often the explicit assignments of chains to shards are read again from the
+    /// server configuration, in milliseconds.
+    #[arg(long = "shard-assignment-refresh-ms",
+          default_value = "5000",
+          value_parser = util::parse_millis,
+          env = "LINERA_PROXY_SHARD_ASSIGNMENT_REFRESH")]
+    shard_assignment_refresh: Duration,
+
+    /// How often the TLS certificates are read again from their files, in milliseconds.
+    #[arg(long = "certificates-refresh-ms",
+          default_value = "60000",
+          value_parser = util::parse_millis,
+          env = "LINERA_PROXY_CERTIFICATES_REFRESH")]
+    certificates_refresh: Duration,
+
+    #[command(flatten)]
+    cache_invalidation_config: CacheInvalidationConfig,
+
+    /// The rate limits and the priority lanes of the gRPC proxy.
+    #[command(flatten)]
+    admission: AdmissionConfig,
 }
 
-/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
+/// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP (possibly with TLS)
+/// or UDP.
 /// The proxy can be configured to have a gRPC ingress and egress, or a combination
 /// of TCP / UDP ingress and egress.
 enum Proxy<S>
@@ -110,9 +150,13 @@ where
 
 struct ProxyContext {
     config: ValidatorServerConfig,
+    config_path: PathBuf,
     genesis_config: GenesisConfig,
     send_timeout: Duration,
     recv_timeout: Duration,
+    shard_assignment_refresh: Duration,
+    certificates_refresh: Duration,
+    admission: AdmissionConfig,
 }
 
 impl ProxyContext {
@@ -121,11 +165,34 @@ impl ProxyContext {
         let genesis_config = util::read_json(&options.genesis_config_path)?;
         Ok(Self {
             config,
+            config_path: options.config_path.clone(),
             send_timeout: options.send_timeout,
             recv_timeout: options.recv_timeout,
             genesis_config,
+            shard_assignment_refresh: options.shard_assignment_refresh,
+            certificates_refresh: options.certificates_refresh,
+            admission: options.admission.clone(),
         })
     }
+
+    /// Returns the certificates of the proxy's public endpoint, if it uses TLS.
+    fn public_certificates(
+        &self,
+        shutdown_signal: &CancellationToken,
+    ) -> Result<Option<TlsCertificates>> {
+        if !self.config.validator.network.protocol.uses_tls() {
+            return Ok(None);
+        }
+        let certificates = match self.config.public_tls.clone() {
+            Some(config) => load_and_watch_certificates(
+                config,
+                self.certificates_refresh,
+                shutdown_signal.clone(),
+            )?,
+            None => TlsCertificates::embedded(),
+        };
+        Ok(Some(certificates))
+    }
 }
 
 #[async_trait]
@@ -138,7 +205,23 @@ impl Runnable for ProxyContext {
     {
         let shutdown_notifier = CancellationToken::new();
         tokio::spawn(listen_for_shutdown_signals(shutdown_notifier.clone()));
-        let proxy = Proxy::from_context(self, storage)?;
+        // The proxy shares the assignment, so it routes requests according to the updates.
+        tokio::spawn(watch_shard_assignment(
+            self.config_path.clone(),
+            self.config.internal_network.shard_assignment.clone(),
+            self.shard_assignment_refresh,
+            |_| {},
+            shutdown_notifier.clone(),
+        ));
+        let certificates = ProxyCertificates {
+            internal: load_internal_certificates(
+                &self.config.internal_network,
+                self.certificates_refresh,
+                shutdown_notifier.clone(),
+            )?,
+            public: self.public_certificates(&shutdown_notifier)?,
+        };
+        let proxy = Proxy::from_context(self, certificates, storage)?;
         match proxy {
             Proxy::Simple(simple_proxy) => simple_proxy.run(shutdown_notifier).await,
             Proxy::Grpc(grpc_proxy) => grpc_proxy.run(shutdown_notifier).await,
@@ -151,21 +234,24 @@ where
     S: Storage + Clone + Send + Sync + 'static,
 {
     /// Constructs and configures the [`Proxy`] given [`ProxyContext`].
-    fn from_context(context: ProxyContext, storage: S) -> Result<Self> {
+    fn from_context(
+        context: ProxyContext,
+        certificates: ProxyCertificates,
+        storage: S,
+    ) -> Result<Self> {
         let internal_protocol = context.config.internal_network.protocol;
         let external_protocol = context.config.validator.network.protocol;
         let proxy = match (internal_protocol, external_protocol) {
-            (NetworkProtocol::Grpc { .. }, NetworkProtocol::Grpc(tls)) => {
-                Self::Grpc(GrpcProxy::new(
-                    context.config.validator.network,
-                    context.config.internal_network,
-                    context.genesis_config,
-                    context.send_timeout,
-                    context.recv_timeout,
-                    tls,
-                    storage,
-                ))
-            }
+            (NetworkProtocol::Grpc(_), NetworkProtocol::Grpc(_)) => Self::Grpc(GrpcProxy::new(
+                context.config.validator.network,
+                context.config.internal_network,
+                context.genesis_config,
+                context.send_timeout,
+                context.recv_timeout,
+                certificates,
+                &context.admission,
+                storage,
+            )),
             (
                 NetworkProtocol::Simple(internal_transport),
                 NetworkProtocol::Simple(public_transport),
@@ -182,6 +268,7 @@ where
                 genesis_config: context.genesis_config,
                 send_timeout: context.send_timeout,
                 recv_timeout: context.recv_timeout,
+                certificates,
                 storage,
             })),
             _ => {
@@ -197,6 +284,15 @@ where
     }
 }
 
+/// The TLS certificates of the proxy, for the protocols that use TLS.
+#[derive(Debug, Clone)]
+pub struct ProxyCertificates {
+    /// The certificates presented to the shards and to the proxy's internal clients.
+    internal: Option<TlsCertificates>,
+    /// The certificates presented to the proxy's public clients.
+    public: Option<TlsCertificates>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SimpleProxy<S>
 where
@@ -207,6 +303,7 @@ where
     genesis_config: GenesisConfig,
     send_timeout: Duration,
     recv_timeout: Duration,
+    certificates: ProxyCertificates,
     storage: S,
 }
 
@@ -241,6 +338,7 @@ where
             message,
             shard.clone(),
             protocol,
+            self.certificates.internal.as_ref(),
             self.send_timeout,
             self.recv_timeout,
         )
@@ -271,9 +369,10 @@ where
             shutdown_signal.clone(),
         );
 
+        let certificates = self.certificates.public.clone();
         self.public_config
             .protocol
-            .spawn_server(address, self, shutdown_signal, &mut join_set)
+            .spawn_server(address, self, certificates, shutdown_signal, &mut join_set)
             .join()
             .await?;
 
@@ -295,10 +394,13 @@ where
         message: RpcMessage,
         shard: ShardConfig,
         protocol: TransportProtocol,
+        certificates: Option<&TlsCertificates>,
         send_timeout: Duration,
         recv_timeout: Duration,
     ) -> Result<Option<RpcMessage>> {
-        let mut connection = protocol.connect((shard.host, shard.port)).await?;
+        let mut connection = protocol
+            .connect(&shard.host, shard.port, certificates)
+            .await?;
         linera_base::time::timer::timeout(send_timeout, connection.send(message)).await??;
         let message = linera_base::time::timer::timeout(recv_timeout, connection.next())
             .await?
@@ -399,10 +501,23 @@ fn main() -> Result<()> {
 
 impl ProxyOptions {
     async fn run(&self) -> Result<()> {
+        let context = ProxyContext::from_options(self)?;
+        // The certificates stay up to date as long as the storage caches are used.
+        let internal_certificates = load_internal_certificates(
+            &context.config.internal_network,
+            self.certificates_refresh,
+            CancellationToken::new(),
+        )?;
         let storage_cache_config = StorageCacheConfig {
             max_cache_size: self.max_cache_size,
             max_entry_size: self.max_entry_size,
             max_cache_entries: self.max_cache_entries,
+            cache_missing_keys: self.cache_missing_keys,
+            entry_ttl: self.cache_entry_ttl,
+            invalidation_channel: self
+                .cache_invalidation_config
+                .start(self.cache_missing_keys, internal_certificates)
+                .await?,
         };
         let common_config = CommonStoreConfig {
             max_concurrent_queries: self.max_concurrent_queries,
@@ -412,7 +527,7 @@ impl ProxyOptions {
         let genesis_config: GenesisConfig = util::read_json(&self.genesis_config_path)?;
         let store_config = self.storage_config.add_common_config(common_config).await?;
         store_config
-            .run_with_storage(&genesis_config, None, ProxyContext::from_options(self)?)
+            .run_with_storage(&genesis_config, None, context)
             .boxed()
             .await?
     }
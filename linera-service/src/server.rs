@@ -11,14 +11,17 @@ use std::{
     time::Duration,
 };
 
-use anyhow::{bail, Context};
+use anyhow::Context;
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
 use linera_base::{
     crypto::{CryptoRng, Ed25519SecretKey},
+    data_types::{TimeDelta, Timestamp},
+    identifiers::ChainId,
     listen_for_shutdown_signals,
 };
 use linera_client::{
+    client_options::RetentionConfig,
     config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
     persistent::{self, Persist},
 };
@@ -26,19 +29,24 @@ use linera_core::{worker::WorkerState, JoinSetExt as _};
 use linera_execution::{WasmRuntime, WithWasmDefault};
 use linera_rpc::{
     config::{
-        CrossChainConfig, NetworkProtocol, NotificationConfig, ShardConfig, ShardId, TlsConfig,
-        ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        CrossChainConfig, NetworkProtocol, NotificationConfig, ShardAssignment, ShardConfig,
+        ShardId, TlsCertificatesConfig, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig,
     },
     grpc, simple,
+    tls::TlsCertificates,
 };
 use linera_sdk::linera_base_types::{AccountSecretKey, ValidatorKeypair};
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
 use linera_service::{
+    cache_invalidation::CacheInvalidationConfig,
+    shard_assignment::watch_shard_assignment,
     storage::{Runnable, StorageConfigNamespace},
+    tls::load_internal_certificates,
     util,
 };
-use linera_storage::Storage;
+use linera_storage::{RetentionPolicy, Storage};
 use linera_views::{lru_caching::StorageCacheConfig, store::CommonStoreConfig};
 use serde::Deserialize;
 use tokio::task::JoinSet;
@@ -47,11 +55,15 @@ use tracing::{error, info};
 
 struct ServerContext {
     server_config: ValidatorServerConfig,
+    server_config_path: PathBuf,
     cross_chain_config: CrossChainConfig,
     notification_config: NotificationConfig,
     shard: Option<usize>,
     grace_period: Duration,
     max_loaded_chains: NonZeroUsize,
+    retention_policy: RetentionPolicy,
+    shard_assignment_refresh: Duration,
+    certificates_refresh: Duration,
 }
 
 impl ServerContext {
@@ -70,15 +82,20 @@ impl ServerContext {
             "Public key: {}",
             self.server_config.validator_secret.public()
         );
+        let network = self.server_config.internal_network.clone();
         let state = WorkerState::new(
             format!("Shard {} @ {}:{}", shard_id, local_ip_addr, shard.port),
             Some(self.server_config.validator_secret.copy()),
             storage,
             self.max_loaded_chains,
         )
+        .with_chain_assignment(move |chain_id, now| {
+            network.chain_assignment(shard_id, chain_id, now)
+        })
         .with_allow_inactive_chains(false)
         .with_allow_messages_from_deprecated_epochs(false)
-        .with_grace_period(self.grace_period);
+        .with_grace_period(self.grace_period)
+        .with_retention_policy(self.retention_policy);
         (state, shard_id, shard.clone())
     }
 
@@ -87,6 +104,7 @@ impl ServerContext {
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
         protocol: simple::TransportProtocol,
+        certificates: Option<TlsCertificates>,
         shutdown_signal: CancellationToken,
     ) -> JoinSet<()>
     where
@@ -117,6 +135,7 @@ impl ServerContext {
                 state,
                 shard_id,
                 cross_chain_config,
+                certificates.clone(),
             )
             .spawn(shutdown_signal.clone(), &mut join_set);
 
@@ -139,6 +158,7 @@ impl ServerContext {
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        certificates: Option<TlsCertificates>,
         shutdown_signal: CancellationToken,
     ) -> JoinSet<()>
     where
@@ -161,6 +181,7 @@ impl ServerContext {
                 self.server_config.internal_network.clone(),
                 self.cross_chain_config.clone(),
                 self.notification_config.clone(),
+                certificates.clone(),
                 shutdown_signal.clone(),
                 &mut join_set,
             );
@@ -219,14 +240,43 @@ impl Runnable for ServerContext {
             }
         };
 
+        let workers = states
+            .iter()
+            .map(|(state, _, _)| state.clone())
+            .collect::<Vec<_>>();
+        tokio::spawn(watch_shard_assignment(
+            self.server_config_path.clone(),
+            self.server_config.internal_network.shard_assignment.clone(),
+            self.shard_assignment_refresh,
+            move |moved| {
+                for worker in &workers {
+                    for chain_id in moved {
+                        let worker = worker.clone();
+                        let chain_id = *chain_id;
+                        tokio::spawn(async move { worker.evict_chain(chain_id).await });
+                    }
+                }
+            },
+            shutdown_notifier.clone(),
+        ));
+
+        let certificates = load_internal_certificates(
+            &self.server_config.internal_network,
+            self.certificates_refresh,
+            shutdown_notifier.clone(),
+        )?;
+
         let mut join_set = match self.server_config.internal_network.protocol {
-            NetworkProtocol::Simple(protocol) => {
-                self.spawn_simple(&listen_address, states, protocol, shutdown_notifier)
+            NetworkProtocol::Simple(protocol) => self.spawn_simple(
+                &listen_address,
+                states,
+                protocol,
+                certificates,
+                shutdown_notifier,
+            ),
+            NetworkProtocol::Grpc(_) => {
+                self.spawn_grpc(&listen_address, states, certificates, shutdown_notifier)
             }
-            NetworkProtocol::Grpc(tls_config) => match tls_config {
-                TlsConfig::ClearText => self.spawn_grpc(&listen_address, states, shutdown_notifier),
-                TlsConfig::Tls => bail!("TLS not supported between proxy and shards."),
-            },
         };
 
         join_set.await_all_tasks().await;
@@ -283,6 +333,14 @@ struct ValidatorOptions {
 
     /// The public name and the port of each of the shards
     shards: Vec<ShardConfig>,
+
+    /// The certificates of the proxy and the shards, if the internal protocol uses TLS.
+    #[serde(default)]
+    internal_tls: Option<TlsCertificatesConfig>,
+
+    /// The certificate of the proxy's public endpoint, if the external protocol uses TLS.
+    #[serde(default)]
+    public_tls: Option<TlsCertificatesConfig>,
 }
 
 fn make_server_config<R: CryptoRng>(
@@ -302,6 +360,8 @@ fn make_server_config<R: CryptoRng>(
         public_key,
         protocol: options.internal_protocol,
         shards: options.shards,
+        shard_assignment: ShardAssignment::default(),
+        tls: options.internal_tls,
         host: options.internal_host,
         port: options.internal_port,
         metrics_port: options.metrics_port,
@@ -317,6 +377,7 @@ fn make_server_config<R: CryptoRng>(
             validator,
             validator_secret: validator_keypair.secret_key,
             internal_network,
+            public_tls: options.public_tls,
         },
     )?)
 }
@@ -359,6 +420,17 @@ enum ServerCommand {
         #[arg(long)]
         wasm_runtime: Option<WasmRuntime>,
 
+        /// A directory where to keep the compiled WebAssembly modules, so that they are not
+        /// compiled again after a restart. By default, they are only cached in memory.
+        #[cfg(with_wasm_runtime)]
+        #[arg(long)]
+        wasm_cache_dir: Option<PathBuf>,
+
+        /// The maximal total size, in bytes, of the compiled modules kept in `--wasm-cache-dir`.
+        #[cfg(with_wasm_runtime)]
+        #[arg(long, default_value_t = linera_execution::DEFAULT_MAX_DISK_CACHE_SIZE)]
+        wasm_cache_max_size: u64,
+
         /// The maximal number of chains loaded in memory at a given time.
         #[arg(long, default_value = "400")]
         max_loaded_chains: NonZeroUsize,
@@ -382,6 +454,40 @@ enum ServerCommand {
         /// The maximal number of entries in the storage cache.
         #[arg(long, default_value = "1000")]
         max_cache_entries: usize,
+
+        /// Whether the storage cache also remembers missing keys. Requires
+        /// `--cache-invalidation-address`, to learn about the writes of the other processes
+        /// sharing the database.
+        #[arg(long)]
+        cache_missing_keys: bool,
+
+        /// How long an entry stays in the storage cache, in milliseconds. By default, entries
+        /// only leave the cache when it is full.
+        #[arg(long = "cache-entry-ttl-ms", value_parser = util::parse_millis)]
+        cache_entry_ttl: Option<Duration>,
+
+        #[command(flatten)]
+        cache_invalidation_config: CacheInvalidationConfig,
+
+        #[command(flatten)]
+        retention_config: RetentionConfig,
+
+        /// How often the explicit assignments of chains to shards are read again from the
+        /// server configuration, in milliseconds.
+        #[arg(
+            long = "shard-assignment-refresh-ms",
+            default_value = "5000",
+            value_parser = util::parse_millis
+        )]
+        shard_assignment_refresh: Duration,
+
+        /// How often the TLS certificates are read again from their files, in milliseconds.
+        #[arg(
+            long = "certificates-refresh-ms",
+            default_value = "60000",
+            value_parser = util::parse_millis
+        )]
+        certificates_refresh: Duration,
     },
 
     /// Act as a trusted third-party and generate all server configurations
@@ -461,6 +567,37 @@ enum ServerCommand {
         #[arg(long)]
         metrics_port: Option<String>,
     },
+
+    /// Assigns a chain to a shard, or removes its explicit assignment.
+    ///
+    /// The proxy and the shards of a running validator pick up the change within
+    /// `--shard-assignment-refresh-ms`: requests for the chain are then sent to the new shard,
+    /// and the previous shard rejects them and drops the chain from its cache once its
+    /// requests are complete. The new shard only serves the chain after the handover delay,
+    /// which must be longer than the refresh interval. No restart is needed.
+    #[command(name = "move-chain")]
+    MoveChain {
+        /// Path to the file containing the server configuration of this Linera validator.
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+
+        /// The chain to move.
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// The shard to assign the chain to. If omitted, the chain is assigned by hashing
+        /// again.
+        #[arg(long)]
+        shard: Option<ShardId>,
+
+        /// How long no shard serves the chain, in milliseconds.
+        #[arg(
+            long = "handover-delay-ms",
+            default_value = "10000",
+            value_parser = util::parse_millis
+        )]
+        handover_delay: Duration,
+    },
 }
 
 fn main() {
@@ -512,7 +649,8 @@ fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
         }
         ServerCommand::Generate { .. }
         | ServerCommand::Initialize { .. }
-        | ServerCommand::EditShards { .. } => "server".into(),
+        | ServerCommand::EditShards { .. }
+        | ServerCommand::MoveChain { .. } => "server".into(),
     }
 }
 
@@ -527,12 +665,22 @@ async fn run(options: ServerOptions) {
             shard,
             grace_period,
             wasm_runtime,
+            #[cfg(with_wasm_runtime)]
+            wasm_cache_dir,
+            #[cfg(with_wasm_runtime)]
+            wasm_cache_max_size,
             max_loaded_chains,
             max_concurrent_queries,
             max_stream_queries,
             max_cache_size,
             max_entry_size,
             max_cache_entries,
+            cache_missing_keys,
+            cache_entry_ttl,
+            cache_invalidation_config,
+            retention_config,
+            shard_assignment_refresh,
+            certificates_refresh,
         } => {
             linera_version::VERSION_INFO.log();
 
@@ -540,20 +688,47 @@ async fn run(options: ServerOptions) {
                 util::read_json(&genesis_config_path).expect("Failed to read initial chain config");
             let server_config: ValidatorServerConfig =
                 util::read_json(&server_config_path).expect("Failed to read server config");
+            // The certificates stay up to date as long as the storage caches are used.
+            let internal_certificates = load_internal_certificates(
+                &server_config.internal_network,
+                certificates_refresh,
+                CancellationToken::new(),
+            )
+            .expect("Failed to load the TLS certificates of the internal network");
 
             let job = ServerContext {
                 server_config,
+                server_config_path,
                 cross_chain_config,
                 notification_config,
                 shard,
                 grace_period,
                 max_loaded_chains,
+                retention_policy: retention_config.into(),
+                shard_assignment_refresh,
+                certificates_refresh,
             };
             let wasm_runtime = wasm_runtime.with_wasm_default();
+            #[cfg(with_wasm_runtime)]
+            if let Some(directory) = wasm_cache_dir {
+                linera_execution::enable_module_disk_cache(
+                    linera_execution::ModuleDiskCacheConfig {
+                        directory,
+                        max_size: wasm_cache_max_size,
+                    },
+                )
+                .expect("Failed to enable the Wasm module disk cache");
+            }
             let storage_cache_config = StorageCacheConfig {
                 max_cache_size,
                 max_entry_size,
                 max_cache_entries,
+                cache_missing_keys,
+                entry_ttl: cache_entry_ttl,
+                invalidation_channel: cache_invalidation_config
+                    .start(cache_missing_keys, internal_certificates)
+                    .await
+                    .expect("Invalid storage cache options"),
             };
             let common_config = CommonStoreConfig {
                 max_concurrent_queries,
@@ -628,6 +803,9 @@ async fn run(options: ServerOptions) {
                 max_cache_size,
                 max_entry_size,
                 max_cache_entries,
+                cache_missing_keys: false,
+                entry_ttl: None,
+                invalidation_channel: None,
             };
             let common_config = CommonStoreConfig {
                 max_concurrent_queries,
@@ -657,11 +835,50 @@ async fn run(options: ServerOptions) {
                     .expect("Failed to read server config");
             let shards = generate_shard_configs(num_shards, host, port, metrics_port)
                 .expect("Failed to generate shard configs");
+            let unassigned = server_config
+                .internal_network
+                .shard_assignment
+                .retain_shards(shards.len());
+            for chain_id in unassigned {
+                info!("Chain {chain_id} was assigned to a removed shard and is now hashed again");
+            }
             server_config.internal_network.shards = shards;
             Persist::persist(&mut server_config)
                 .await
                 .expect("Failed to write updated server config");
         }
+
+        ServerCommand::MoveChain {
+            server_config_path,
+            chain_id,
+            shard,
+            handover_delay,
+        } => {
+            let mut server_config =
+                persistent::File::<ValidatorServerConfig>::read(&server_config_path)
+                    .expect("Failed to read server config");
+            let network = &mut server_config.internal_network;
+            if let Some(shard) = shard {
+                assert!(
+                    shard < network.shards.len(),
+                    "The validator only has {} shards",
+                    network.shards.len()
+                );
+            }
+            let previous_shard = network.get_shard_id(chain_id);
+            let now = Timestamp::now();
+            let until = now.saturating_add(TimeDelta::from_duration(handover_delay));
+            network
+                .shard_assignment
+                .hand_over(chain_id, shard, now, until);
+            info!(
+                "Chain {chain_id} moves from shard {previous_shard} to shard {}",
+                network.get_shard_id(chain_id)
+            );
+            Persist::persist(&mut server_config)
+                .await
+                .expect("Failed to write updated server config");
+        }
     }
 }
 
@@ -755,6 +972,8 @@ mod test {
                         metrics_port: Some(5002),
                     },
                 ],
+                internal_tls: None,
+                public_tls: None,
             }
         );
     }
@@ -0,0 +1,126 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Updates of the shard assignment of a running validator.
+//!
+//! The proxy and the shards of a validator read the explicit assignments of chains to shards
+//! from the server configuration file again at a regular interval. To move a chain to
+//! another shard without restarting the validator, an operator changes its assignment in the
+//! file with `linera-server move-chain`. The move starts a handover: once they read the new
+//! assignment, the proxy routes the chain's requests to the new shard and the shards send
+//! their cross-chain requests for the chain there, while the shard that owned the chain
+//! rejects its requests and drops it from its cache once the pending ones are complete. The
+//! new shard holds the chain's requests back until the handover is over, so that two shards
+//! never serve the chain at the same time, provided that the handover is longer than the
+//! refresh interval.
+
+use std::{path::PathBuf, time::Duration};
+
+use linera_base::identifiers::ChainId;
+use linera_rpc::config::ShardAssignment;
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::util;
+
+/// The part of the server configuration with the shard assignment.
+#[derive(Deserialize)]
+struct ServerConfigAssignment {
+    internal_network: InternalNetworkAssignment,
+}
+
+/// The part of the internal network configuration with the shard assignment.
+#[derive(Deserialize)]
+struct InternalNetworkAssignment {
+    #[serde(default)]
+    shard_assignment: ShardAssignment,
+}
+
+/// Reads the shard assignment from the server configuration at `config_path` every
+/// `interval`, until `shutdown_signal` is cancelled. When it changed, `assignment` is
+/// updated and `on_moved` is called with the chains whose assignment changed.
+pub async fn watch_shard_assignment(
+    config_path: PathBuf,
+    assignment: ShardAssignment,
+    interval: Duration,
+    mut on_moved: impl FnMut(&[ChainId]) + Send,
+    shutdown_signal: CancellationToken,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            () = shutdown_signal.cancelled() => return,
+        }
+        let config: ServerConfigAssignment = match util::read_json(&config_path) {
+            Ok(config) => config,
+            Err(error) => {
+                warn!("Failed to read the shard assignment from {config_path:?}: {error}");
+                continue;
+            }
+        };
+        let moved = assignment.update(&config.internal_network.shard_assignment);
+        if !moved.is_empty() {
+            info!("The shard assignment of {} chains changed", moved.len());
+            on_moved(&moved);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    fn write_assignment(path: &PathBuf, assignment: &ShardAssignment) -> anyhow::Result<()> {
+        let config = serde_json::json!({ "internal_network": { "shard_assignment": assignment } });
+        fs_err::write(path, serde_json::to_vec(&config)?)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch_shard_assignment() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let config_path = directory.path().join("server.json");
+        let chain_id = ChainId::root(0);
+        write_assignment(&config_path, &ShardAssignment::default())?;
+
+        let assignment = ShardAssignment::default();
+        let moved = Arc::new(Mutex::new(Vec::new()));
+        let shutdown_signal = CancellationToken::new();
+        let watcher = tokio::spawn(watch_shard_assignment(
+            config_path.clone(),
+            assignment.clone(),
+            Duration::from_millis(10),
+            {
+                let moved = moved.clone();
+                move |chain_ids: &[ChainId]| moved.lock().unwrap().extend_from_slice(chain_ids)
+            },
+            shutdown_signal.clone(),
+        ));
+
+        // A move in the file is applied to the running assignment.
+        let new_assignment = ShardAssignment::default();
+        new_assignment.set(chain_id, Some(1));
+        write_assignment(&config_path, &new_assignment)?;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+        while moved.lock().unwrap().is_empty() {
+            assert!(tokio::time::Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(assignment.get(&chain_id), Some(1));
+
+        // An invalid file leaves the assignment unchanged.
+        fs_err::write(&config_path, b"not a configuration")?;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(assignment.get(&chain_id), Some(1));
+        assert_eq!(*moved.lock().unwrap(), vec![chain_id]);
+
+        shutdown_signal.cancel();
+        watcher.await?;
+        Ok(())
+    }
+}
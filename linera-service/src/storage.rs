@@ -26,7 +26,7 @@ use {anyhow::bail, linera_views::store::LocalAdminKeyValueStore as _};
 #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
 use {
     linera_storage::ChainStatesFirstAssignment,
-    linera_views::backends::dual::{DualStore, DualStoreConfig},
+    linera_views::backends::dual::{DualStore, DualStoreConfig, MirroringAssignment},
     std::path::Path,
 };
 #[cfg(feature = "rocksdb")]
@@ -63,6 +63,12 @@ pub enum StoreConfig {
         DualStoreConfig<RocksDbStoreConfig, ScyllaDbStoreConfig>,
         String,
     ),
+    /// The RocksDB key value store, with every write mirrored into ScyllaDB
+    #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+    MirroredRocksDbScyllaDb(
+        DualStoreConfig<RocksDbStoreConfig, ScyllaDbStoreConfig>,
+        String,
+    ),
 }
 
 /// The description of a storage implementation.
@@ -106,6 +112,16 @@ pub enum StorageConfig {
         /// The URI for accessing the database
         uri: String,
     },
+    /// The RocksDB description, with every write mirrored into ScyllaDB
+    #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+    MirroredRocksDbScyllaDb {
+        /// The path used
+        path_with_guard: PathWithGuard,
+        /// Whether to use `block_in_place` or `spawn_blocking`.
+        spawn_mode: RocksDbSpawnMode,
+        /// The URI for accessing the database
+        uri: String,
+    },
 }
 
 impl StorageConfig {
@@ -127,7 +143,8 @@ impl StorageConfig {
     pub fn are_chains_shared(&self) -> bool {
         match self {
             #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
-            StorageConfig::DualRocksDbScyllaDb { .. } => false,
+            StorageConfig::DualRocksDbScyllaDb { .. }
+            | StorageConfig::MirroredRocksDbScyllaDb { .. } => false,
             _ => true,
         }
     }
@@ -139,6 +156,11 @@ impl StorageConfig {
                 path_with_guard,
                 spawn_mode: _,
                 uri: _,
+            }
+            | StorageConfig::MirroredRocksDbScyllaDb {
+                path_with_guard,
+                spawn_mode: _,
+                uri: _,
             } => {
                 path_with_guard.path_buf.push(_shard_str);
             }
@@ -169,6 +191,8 @@ const DYNAMO_DB: &str = "dynamodb:";
 const SCYLLA_DB: &str = "scylladb:";
 #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
 const DUAL_ROCKS_DB_SCYLLA_DB: &str = "dualrocksdbscylladb:";
+#[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+const MIRRORED_ROCKS_DB_SCYLLA_DB: &str = "mirroredrocksdbscylladb:";
 
 impl FromStr for StorageConfigNamespace {
     type Err = anyhow::Error;
@@ -328,11 +352,17 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
             });
         }
         #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
-        if let Some(s) = input.strip_prefix(DUAL_ROCKS_DB_SCYLLA_DB) {
+        for (prefix, mirrored) in [
+            (DUAL_ROCKS_DB_SCYLLA_DB, false),
+            (MIRRORED_ROCKS_DB_SCYLLA_DB, true),
+        ] {
+            let Some(s) = input.strip_prefix(prefix) else {
+                continue;
+            };
             let parts = s.split(':').collect::<Vec<_>>();
             if parts.len() != 5 && parts.len() != 6 {
                 bail!(
-                    "For DualRocksDbScyllaDb, the formatting has to be dualrocksdbscylladb:directory:mode:tcp:hostname:port:namespace"
+                    "For DualRocksDbScyllaDb and MirroredRocksDbScyllaDb, the formatting has to be {prefix}directory:mode:tcp:hostname:port:namespace"
                 );
             }
             let path = Path::new(parts[0]);
@@ -353,10 +383,18 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
             let port = NonZeroU16::from_str(port_str)
                 .map_err(|_| anyhow!("Failed to find parse port {port_str} for {s}"))?;
             let uri = format!("{}:{}", &address, port);
-            let storage_config = StorageConfig::DualRocksDbScyllaDb {
-                path_with_guard,
-                spawn_mode,
-                uri,
+            let storage_config = if mirrored {
+                StorageConfig::MirroredRocksDbScyllaDb {
+                    path_with_guard,
+                    spawn_mode,
+                    uri,
+                }
+            } else {
+                StorageConfig::DualRocksDbScyllaDb {
+                    path_with_guard,
+                    spawn_mode,
+                    uri,
+                }
             };
             let namespace = if parts.len() == 5 {
                 DEFAULT_NAMESPACE.to_string()
@@ -378,7 +416,7 @@ example service:tcp:127.0.0.1:7878:table_do_my_test"
         #[cfg(feature = "scylladb")]
         error!("Also available is ScyllaDB");
         #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
-        error!("Also available is DualRocksDbScyllaDb");
+        error!("Also available are DualRocksDbScyllaDb and MirroredRocksDbScyllaDb");
         Err(anyhow!("The input has not matched: {input}"))
     }
 }
@@ -432,6 +470,11 @@ impl StorageConfigNamespace {
                 path_with_guard,
                 spawn_mode,
                 uri,
+            }
+            | StorageConfig::MirroredRocksDbScyllaDb {
+                path_with_guard,
+                spawn_mode,
+                uri,
             } => {
                 let first_config = RocksDbStoreConfig::new(
                     *spawn_mode,
@@ -443,7 +486,14 @@ impl StorageConfigNamespace {
                     first_config,
                     second_config,
                 };
-                Ok(StoreConfig::DualRocksDbScyllaDb(config, namespace))
+                if matches!(
+                    self.storage_config,
+                    StorageConfig::MirroredRocksDbScyllaDb { .. }
+                ) {
+                    Ok(StoreConfig::MirroredRocksDbScyllaDb(config, namespace))
+                } else {
+                    Ok(StoreConfig::DualRocksDbScyllaDb(config, namespace))
+                }
             }
         }
     }
@@ -489,6 +539,21 @@ impl fmt::Display for StorageConfigNamespace {
                     namespace
                 )
             }
+            #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+            StorageConfig::MirroredRocksDbScyllaDb {
+                path_with_guard,
+                spawn_mode,
+                uri,
+            } => {
+                write!(
+                    f,
+                    "mirroredrocksdbscylladb:{}:{}:tcp:{}:{}",
+                    path_with_guard.path_buf.display(),
+                    spawn_mode,
+                    uri,
+                    namespace
+                )
+            }
         }
     }
 }
@@ -577,6 +642,15 @@ impl StoreConfig {
                 .await?;
                 Ok(job.run(storage).await)
             }
+            #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+            StoreConfig::MirroredRocksDbScyllaDb(config, namespace) => {
+                let storage = DbStorage::<
+                    DualStore<RocksDbStore, ScyllaDbStore, MirroringAssignment>,
+                    _,
+                >::connect(&config, &namespace, wasm_runtime)
+                .await?;
+                Ok(job.run(storage).await)
+            }
         }
     }
 
@@ -611,6 +685,12 @@ impl StoreConfig {
                     config, namespace,
                 )
                 .await?),
+            #[cfg(all(feature = "rocksdb", feature = "scylladb"))]
+            StoreConfig::MirroredRocksDbScyllaDb(config, namespace) => Ok(job
+                .run::<DualStore<RocksDbStore, ScyllaDbStore, MirroringAssignment>>(
+                    config, namespace,
+                )
+                .await?),
         }
     }
 
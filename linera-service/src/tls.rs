@@ -0,0 +1,81 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Renewal of the TLS certificates of a running validator.
+//!
+//! The proxy and the shards read their certificates from their files again at a regular
+//! interval. To renew a certificate, e.g. before it expires, an operator replaces the files:
+//! new connections then use the new certificate, and the connections between the proxy and
+//! the shards are opened again with it.
+
+use std::time::Duration;
+
+use anyhow::bail;
+use linera_rpc::{
+    config::{TlsCertificatesConfig, ValidatorInternalNetworkConfig},
+    tls::{TlsCertificates, TlsError},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Reads `certificates` from their files every `interval`, until `shutdown_signal` is
+/// cancelled.
+pub async fn watch_certificates(
+    certificates: TlsCertificates,
+    interval: Duration,
+    shutdown_signal: CancellationToken,
+) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately, and the certificates were just loaded.
+    ticks.tick().await;
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => {}
+            () = shutdown_signal.cancelled() => return,
+        }
+        match certificates.reload() {
+            Ok(true) => info!("Reloaded the TLS certificates {certificates:?}"),
+            Ok(false) => {}
+            Err(error) => warn!("Failed to reload the TLS certificates {certificates:?}: {error}"),
+        }
+    }
+}
+
+/// Loads the certificates of `config` and keeps them up to date until `shutdown_signal` is
+/// cancelled.
+pub fn load_and_watch_certificates(
+    config: TlsCertificatesConfig,
+    interval: Duration,
+    shutdown_signal: CancellationToken,
+) -> Result<TlsCertificates, TlsError> {
+    let certificates = TlsCertificates::load(config)?;
+    tokio::spawn(watch_certificates(
+        certificates.clone(),
+        interval,
+        shutdown_signal,
+    ));
+    Ok(certificates)
+}
+
+/// Loads the certificates of the proxy and the shards if the internal network uses TLS, and
+/// keeps them up to date until `shutdown_signal` is cancelled.
+pub fn load_internal_certificates(
+    network: &ValidatorInternalNetworkConfig,
+    interval: Duration,
+    shutdown_signal: CancellationToken,
+) -> anyhow::Result<Option<TlsCertificates>> {
+    if !network.protocol.uses_tls() {
+        return Ok(None);
+    }
+    let Some(config) = network.tls.clone() else {
+        bail!(
+            "TLS between proxy and shards requires the `tls` certificates of the internal network"
+        );
+    };
+    Ok(Some(load_and_watch_certificates(
+        config,
+        interval,
+        shutdown_signal,
+    )?))
+}
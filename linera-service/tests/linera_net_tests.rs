@@ -28,7 +28,7 @@ use guard::INTEGRATION_TEST_GUARD;
 use linera_base::{
     crypto::CryptoHash,
     data_types::Amount,
-    identifiers::{Account, AccountOwner, ApplicationId, ChainId},
+    identifiers::{Account, AccountOwner, ApplicationId, ChainId, StreamId},
     vm::VmRuntime,
 };
 use linera_chain::data_types::{Medium, Origin};
@@ -338,6 +338,13 @@ impl AmmApp {
 #[cfg_attr(feature = "remote-net", test_case(RemoteNetTestingConfig::new(None) ; "remote_net_grpc"))]
 #[test_log::test(tokio::test)]
 async fn test_evm_end_to_end_counter(config: impl LineraNetConfig) -> Result<()> {
+    use alloy::{
+        consensus::{SignableTransaction as _, TxEip1559, TxEnvelope},
+        eips::eip2718::Encodable2718 as _,
+        primitives::{Bytes, TxKind, B256},
+    };
+    use alloy_signer::SignerSync as _;
+    use alloy_signer_local::PrivateKeySigner;
     use alloy_sol_types::{sol, SolCall, SolValue};
     use linera_base::vm::EvmQuery;
     use linera_execution::test_utils::solidity::{get_evm_contract_path, read_evm_u64_entry};
@@ -405,6 +412,57 @@ async fn test_evm_end_to_end_counter(config: impl LineraNetConfig) -> Result<()>
     let counter_value = read_evm_u64_entry(result);
     assert_eq!(counter_value, original_counter_value + increment);
 
+    // The Ethereum JSON-RPC endpoint estimates the gas of an increment, EVM and Linera fuel.
+    let quantity = |value: Value| -> Result<u64> {
+        let digits = value.as_str().unwrap_or_default().trim_start_matches("0x");
+        Ok(u64::from_str_radix(digits, 16)?)
+    };
+    let address = application_id.forget_abi().evm_address();
+    let input = Bytes::from(incrementCall { input: increment }.abi_encode());
+    let call = json!([{ "to": address, "input": input }]);
+    let gas = quantity(
+        application
+            .ethereum_request("eth_estimateGas", call)
+            .await?,
+    )?;
+    assert!(gas > 21_000);
+
+    // It only executes transactions signed by the owner of the chain.
+    let ethereum_chain_id = quantity(
+        application
+            .ethereum_request("eth_chainId", json!([]))
+            .await?,
+    )?;
+    let nonce = application
+        .ethereum_request("eth_getTransactionCount", json!([]))
+        .await?;
+    let transaction = TxEip1559 {
+        chain_id: ethereum_chain_id,
+        nonce: quantity(nonce)?,
+        to: TxKind::Call(address),
+        input,
+        ..TxEip1559::default()
+    };
+    let signer = PrivateKeySigner::from_bytes(&B256::with_last_byte(1))?;
+    let signature = signer.sign_hash_sync(&transaction.signature_hash())?;
+    let transaction = TxEnvelope::from(transaction.into_signed(signature));
+    let raw = Bytes::from(transaction.encoded_2718());
+    let error = application
+        .ethereum_request("eth_sendRawTransaction", json!([raw]))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("owner of this chain"));
+    let receipt = application
+        .ethereum_request("eth_getTransactionReceipt", json!([transaction.tx_hash()]))
+        .await?;
+    assert_eq!(receipt, Value::Null);
+
+    // The counter emits no logs.
+    let logs = application
+        .ethereum_request("eth_getLogs", json!([{ "fromBlock": "earliest" }]))
+        .await?;
+    assert_eq!(logs, json!([]));
+
     net.ensure_is_running().await?;
     net.terminate().await?;
 
@@ -751,8 +809,17 @@ async fn test_wasm_end_to_end_counter(config: impl LineraNetConfig) -> Result<()
     assert_eq!(counter_value, original_counter_value);
 
     let mutation = format!("increment(value: {increment})");
-    application.mutate(mutation).await?;
+    application.mutate(&mutation).await?;
+
+    let counter_value: u64 = application.query_json("value").await?;
+    assert_eq!(counter_value, original_counter_value + increment);
 
+    // Profiling an increment attributes its fuel to the contract, without executing it.
+    let profile = application.profile(&mutation, "fuel").await?;
+    let frame = format!("{}::execute_operation", application_id.forget_abi());
+    assert!(!profile.is_empty());
+    assert!(profile.lines().all(|line| line.starts_with(&frame)));
+    assert!(profile.contains("linera:app/contract-entrypoints#execute-operation "));
     let counter_value: u64 = application.query_json("value").await?;
     assert_eq!(counter_value, original_counter_value + increment);
 
@@ -927,6 +994,15 @@ async fn test_wasm_end_to_end_social_user_pub_sub(config: impl LineraNetConfig)
     }
 
     let mut notifications = Box::pin(node_service2.notifications(chain2).await?);
+    let posts_stream_id = StreamId {
+        application_id: application_id.forget_abi().into(),
+        stream_name: b"posts".into(),
+    };
+    let mut event_notifications = Box::pin(
+        node_service1
+            .notifications_for_streams(chain1, &[posts_stream_id.clone()])
+            .await?,
+    );
 
     let app1 = node_service1
         .make_application(&chain1, &application_id)
@@ -956,6 +1032,23 @@ async fn test_wasm_end_to_end_social_user_pub_sub(config: impl LineraNetConfig)
         tracing::warn!("Waiting to confirm post: {}", response);
     }
 
+    // The post was emitted as an event, and notified to the subscribers of its stream.
+    loop {
+        let result = linera_base::time::timer::timeout(
+            deadline - Instant::now(),
+            event_notifications.next(),
+        )
+        .await?;
+        let Some(notification) = result.transpose()? else {
+            anyhow::bail!("The notification stream ended before the post was notified");
+        };
+        if let Reason::NewEvents { stream_id, indices } = notification.reason {
+            assert_eq!(stream_id, posts_stream_id);
+            assert_eq!(indices, [0]);
+            break;
+        }
+    }
+
     node_service1.ensure_is_running()?;
     node_service2.ensure_is_running()?;
 
@@ -3453,7 +3546,7 @@ async fn test_end_to_end_repeated_transfers(config: impl LineraNetConfig) -> Res
                     }
                     break hash;
                 }
-                reason @ Reason::NewRound { .. } => {
+                reason @ (Reason::NewRound { .. } | Reason::NewEvents { .. }) => {
                     panic!("Unexpected notification about transfer #{i} {reason:?}")
                 }
             }
@@ -410,6 +410,7 @@ impl ServiceStoreClientInternal {
 
 impl AdminKeyValueStore for ServiceStoreClientInternal {
     type Config = ServiceStoreInternalConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = true;
 
     fn get_name() -> String {
         "service store".to_string()
@@ -9,7 +9,7 @@ use async_trait::async_trait;
 use dashmap::DashMap;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::{Blob, Epoch, TimeDelta, Timestamp},
+    data_types::{Blob, BlockHeight, Epoch, TimeDelta, Timestamp},
     identifiers::{ApplicationId, BlobId, ChainId, EventId},
 };
 use linera_chain::{
@@ -42,7 +42,7 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
-use crate::{ChainRuntimeContext, Clock, Storage};
+use crate::{ChainRuntimeContext, Clock, RetentionPolicy, Storage};
 
 /// The metric counting how often a blob is tested for existence from storage
 #[cfg(with_metrics)]
@@ -206,6 +206,19 @@ pub static WRITE_EVENT_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
     )
 });
 
+/// The metric counting how many certificates were pruned from storage.
+#[cfg(with_metrics)]
+static PRUNE_CERTIFICATE_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec(
+        "prune_certificate",
+        "The metric counting how many certificates were pruned from storage",
+        &[],
+    )
+});
+
+/// The maximal number of certificates deleted in a single batch when pruning.
+const PRUNING_BATCH_SIZE: usize = 1000;
+
 trait BatchExt {
     fn add_blob(&mut self, blob: &Blob) -> Result<(), ViewError>;
 
@@ -275,6 +288,10 @@ enum BaseKey {
     BlobState(BlobId),
     Event(EventId),
     BlockExporterState(u32),
+    /// The height below which the certificates of a chain were pruned.
+    PrunedHeight(ChainId),
+    /// The chain and height of a pruned certificate.
+    PrunedCertificate(CryptoHash),
 }
 
 const INDEX_CHAIN_ID: u8 = 0;
@@ -519,8 +536,13 @@ where
         let maybe_value = self.store.read_value::<ConfirmedBlock>(&block_key).await?;
         #[cfg(with_metrics)]
         READ_CONFIRMED_BLOCK_COUNTER.with_label_values(&[]).inc();
-        let value = maybe_value.ok_or_else(|| ViewError::not_found("value for hash", hash))?;
-        Ok(value)
+        if let Some(value) = maybe_value {
+            return Ok(value);
+        }
+        Err(self
+            .pruned_certificate_error(hash)
+            .await?
+            .unwrap_or_else(|| ViewError::not_found("value for hash", hash)))
     }
 
     async fn read_blob(&self, blob_id: BlobId) -> Result<Blob, ViewError> {
@@ -596,12 +618,22 @@ where
     ) -> Result<Vec<ConfirmedBlock>, ViewError> {
         let mut hash = Some(from);
         let mut values = Vec::new();
+        let mut pruned_height = None;
         for _ in 0..limit {
             let Some(next_hash) = hash else {
                 break;
             };
             let value = self.read_confirmed_block(next_hash).await?;
-            hash = value.block().header.previous_block_hash;
+            let header = &value.block().header;
+            if pruned_height.is_none() {
+                pruned_height = Some(self.pruned_height(header.chain_id).await?);
+            }
+            // The blocks below the pruned height were deleted.
+            hash = header.previous_block_hash.filter(|_| {
+                pruned_height
+                    .flatten()
+                    .is_none_or(|pruned| header.height > pruned)
+            });
             values.push(value);
         }
         Ok(values)
@@ -749,6 +781,11 @@ where
             READ_CERTIFICATE_COUNTER.with_label_values(&[]).inc();
         }
         let values = values?;
+        if values[0].is_none() {
+            if let Some(error) = self.pruned_certificate_error(hash).await? {
+                return Err(error);
+            }
+        }
         Self::deserialize_certificate(&values, hash)
     }
 
@@ -769,6 +806,11 @@ where
         let values = values?;
         let mut certificates = Vec::new();
         for (pair, hash) in values.chunks_exact(2).zip(hashes) {
+            if pair[0].is_none() {
+                if let Some(error) = self.pruned_certificate_error(hash).await? {
+                    return Err(error);
+                }
+            }
             let certificate = Self::deserialize_certificate(pair, hash)?;
             certificates.push(certificate);
         }
@@ -794,6 +836,79 @@ where
         self.write_batch(batch).await
     }
 
+    async fn prune_chain(
+        &self,
+        chain_id: ChainId,
+        policy: &RetentionPolicy,
+    ) -> Result<Option<BlockHeight>, ViewError> {
+        let pruned_height = self.pruned_height(chain_id).await?;
+        if policy.is_archival() {
+            return Ok(pruned_height);
+        }
+        // We only read the chain state here, so this cannot corrupt it.
+        let chain = self.load_chain(chain_id).await?;
+        let next_height = chain.tip_state.get().next_block_height;
+        let Ok(latest_height) = next_height.try_sub_one() else {
+            return Ok(pruned_height);
+        };
+        // Keep the latest block, and the blocks whose messages may still be requested.
+        let mut first_kept = latest_height.min(policy.first_height_kept_by_count(next_height));
+        if let Some((height, _)) = chain.outbox_counters.get().first_key_value() {
+            first_kept = first_kept.min(*height);
+        }
+        let start = pruned_height.unwrap_or(BlockHeight::ZERO);
+        if first_kept <= start {
+            return Ok(pruned_height);
+        }
+        let mut hashes = chain
+            .confirmed_log
+            .read(usize::try_from(start)?..usize::try_from(first_kept)?)
+            .await?;
+        if let Some(keep_since) = policy.keep_since() {
+            // Block timestamps are non-decreasing along a chain, so we can search for the
+            // first block that is recent enough.
+            let (mut low, mut high) = (0, hashes.len());
+            while low < high {
+                let middle = low + (high - low) / 2;
+                let block = self.read_confirmed_block(hashes[middle]).await?;
+                if block.block().header.timestamp < keep_since {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+            hashes.truncate(low);
+        }
+        if hashes.is_empty() {
+            return Ok(pruned_height);
+        }
+        let pruned_height_key = bcs::to_bytes(&BaseKey::PrunedHeight(chain_id))?;
+        let mut height = start;
+        for chunk in hashes.chunks(PRUNING_BATCH_SIZE) {
+            let mut batch = Batch::new();
+            for key in Self::get_keys_for_certificates(chunk)? {
+                batch.delete_key(key);
+            }
+            for hash in chunk {
+                let tombstone_key = bcs::to_bytes(&BaseKey::PrunedCertificate(*hash))?;
+                batch.put_key_value(tombstone_key, &(chain_id, height))?;
+                height.try_add_assign_one()?;
+            }
+            batch.put_key_value(pruned_height_key.clone(), &height)?;
+            self.write_batch(batch).await?;
+            #[cfg(with_metrics)]
+            PRUNE_CERTIFICATE_COUNTER
+                .with_label_values(&[])
+                .inc_by(chunk.len() as u64);
+        }
+        Ok(Some(height))
+    }
+
+    async fn pruned_height(&self, chain_id: ChainId) -> Result<Option<BlockHeight>, ViewError> {
+        let key = bcs::to_bytes(&BaseKey::PrunedHeight(chain_id))?;
+        Ok(self.store.read_value::<BlockHeight>(&key).await?)
+    }
+
     fn wasm_runtime(&self) -> Option<WasmRuntime> {
         self.wasm_runtime
     }
@@ -825,6 +940,25 @@ where
             .collect::<Result<_, _>>()?)
     }
 
+    /// Returns the error to report for a missing certificate, if it was pruned.
+    async fn pruned_certificate_error(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<Option<ViewError>, ViewError> {
+        let key = bcs::to_bytes(&BaseKey::PrunedCertificate(hash))?;
+        let maybe_location = self
+            .store
+            .read_value::<(ChainId, BlockHeight)>(&key)
+            .await?;
+        Ok(
+            maybe_location.map(|(chain_id, height)| ViewError::CertificatePruned {
+                hash,
+                chain_id,
+                height,
+            }),
+        )
+    }
+
     fn deserialize_certificate(
         pair: &[Option<Vec<u8>>],
         hash: CryptoHash,
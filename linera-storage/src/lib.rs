@@ -6,6 +6,7 @@
 #![deny(clippy::large_futures)]
 
 mod db_storage;
+mod pruning;
 
 use std::sync::Arc;
 
@@ -43,11 +44,14 @@ use linera_views::{
 
 #[cfg(with_testing)]
 pub use crate::db_storage::TestClock;
-pub use crate::db_storage::{ChainStatesFirstAssignment, DbStorage, WallClock};
 #[cfg(with_metrics)]
 pub use crate::db_storage::{
     READ_CERTIFICATE_COUNTER, READ_CONFIRMED_BLOCK_COUNTER, WRITE_CERTIFICATE_COUNTER,
 };
+pub use crate::{
+    db_storage::{ChainStatesFirstAssignment, DbStorage, WallClock},
+    pruning::RetentionPolicy,
+};
 
 /// The default namespace to be used when none is specified
 pub const DEFAULT_NAMESPACE: &str = "table_linera";
@@ -102,6 +106,8 @@ pub trait Storage: Sized {
     async fn read_blob_states(&self, blob_ids: &[BlobId]) -> Result<Vec<BlobState>, ViewError>;
 
     /// Reads the hashed certificate values in descending order from the given hash.
+    ///
+    /// Stops early if it reaches a block that was pruned.
     async fn read_confirmed_blocks_downward(
         &self,
         from: CryptoHash,
@@ -171,6 +177,23 @@ pub trait Storage: Sized {
         events: impl IntoIterator<Item = (EventId, Vec<u8>)> + Send,
     ) -> Result<(), ViewError>;
 
+    /// Deletes the confirmed blocks and certificates of the given chain that are not kept by
+    /// the retention `policy`. Returns the height below which the chain's blocks are now
+    /// pruned, if any.
+    ///
+    /// Reading a pruned certificate or block afterwards fails with
+    /// [`ViewError::CertificatePruned`]. Blobs and events are never pruned, since they can be
+    /// shared by several chains.
+    async fn prune_chain(
+        &self,
+        chain_id: ChainId,
+        policy: &RetentionPolicy,
+    ) -> Result<Option<BlockHeight>, ViewError>;
+
+    /// Returns the height below which the confirmed blocks and certificates of the given
+    /// chain were pruned, if any.
+    async fn pruned_height(&self, chain_id: ChainId) -> Result<Option<BlockHeight>, ViewError>;
+
     /// Initializes a chain in a simple way (used for testing and to create a genesis state).
     ///
     /// # Notes
@@ -0,0 +1,88 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The retention policy of nodes that do not keep the full history of their chains.
+
+use linera_base::data_types::{BlockHeight, Timestamp};
+use serde::{Deserialize, Serialize};
+
+/// Which confirmed blocks and certificates of a chain a node keeps.
+///
+/// A block is kept if any of the criteria that are set keeps it. The latest block of a chain,
+/// and every block with outgoing messages that were not delivered yet, are always kept. The
+/// default policy keeps everything, i.e. the node is archival.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Keep the blocks at the last that many heights of each chain.
+    keep_last_heights: Option<u64>,
+    /// Keep the blocks with a timestamp at or after this one.
+    keep_since: Option<Timestamp>,
+}
+
+impl RetentionPolicy {
+    /// Returns a policy that keeps every block.
+    pub fn archival() -> Self {
+        Self::default()
+    }
+
+    /// Returns this policy, also keeping the blocks at the last `heights` heights of a chain.
+    pub fn with_keep_last_heights(mut self, heights: u64) -> Self {
+        self.keep_last_heights = Some(heights);
+        self
+    }
+
+    /// Returns this policy, also keeping the blocks with a timestamp at or after `timestamp`.
+    pub fn with_keep_since(mut self, timestamp: Timestamp) -> Self {
+        self.keep_since = Some(timestamp);
+        self
+    }
+
+    /// Returns whether this policy keeps every block.
+    pub fn is_archival(&self) -> bool {
+        self.keep_last_heights.is_none() && self.keep_since.is_none()
+    }
+
+    /// Returns the number of heights that are kept at the end of a chain, if set.
+    pub fn keep_last_heights(&self) -> Option<u64> {
+        self.keep_last_heights
+    }
+
+    /// Returns the timestamp from which blocks are kept, if set.
+    pub fn keep_since(&self) -> Option<Timestamp> {
+        self.keep_since
+    }
+
+    /// Returns the lowest height kept by the `keep_last_heights` criterion, for a chain with
+    /// the given next block height. If that criterion is not set, this returns the next block
+    /// height, i.e. it keeps nothing on its own.
+    pub(crate) fn first_height_kept_by_count(&self, next_height: BlockHeight) -> BlockHeight {
+        match self.keep_last_heights {
+            Some(heights) => BlockHeight(next_height.0.saturating_sub(heights)),
+            None => next_height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_height_kept_by_count() {
+        let policy = RetentionPolicy::archival().with_keep_last_heights(10);
+        assert_eq!(
+            policy.first_height_kept_by_count(BlockHeight(25)),
+            BlockHeight(15)
+        );
+        assert_eq!(
+            policy.first_height_kept_by_count(BlockHeight(5)),
+            BlockHeight(0)
+        );
+        let policy = RetentionPolicy::archival().with_keep_since(Timestamp::from(1000));
+        assert!(!policy.is_archival());
+        assert_eq!(
+            policy.first_height_kept_by_count(BlockHeight(25)),
+            BlockHeight(25)
+        );
+    }
+}
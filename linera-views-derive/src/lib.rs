@@ -7,8 +7,8 @@ use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, parse_quote, punctuated::Punctuated, Attribute, ItemStruct, Lit, LitStr,
-    MetaNameValue, Token, Type, TypePath, WhereClause,
+    ext::IdentExt as _, parse_macro_input, parse_quote, punctuated::Punctuated, Attribute,
+    ItemStruct, Lit, LitStr, MetaNameValue, Token, Type, TypePath, WhereClause,
 };
 
 fn get_seq_parameter(generics: syn::Generics) -> Vec<syn::Ident> {
@@ -71,6 +71,17 @@ fn context_and_constraints(
     (context, constraints)
 }
 
+/// Returns the schema version set with `#[view(version = "N")]`, if any.
+fn schema_version(attributes: &[Attribute]) -> Option<syn::LitInt> {
+    custom_attribute(attributes, "version").map(|version| {
+        version
+            .value()
+            .parse::<u32>()
+            .expect("Invalid schema version: expected an integer");
+        syn::LitInt::new(&version.value(), version.span())
+    })
+}
+
 /// Returns an empty [`WhereClause`].
 fn empty_where_clause() -> WhereClause {
     WhereClause {
@@ -161,6 +172,27 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
         quote! {}
     };
 
+    let load_quote = if root && schema_version(&input.attrs).is_some() {
+        quote! {
+            #[allow(unused_imports)]
+            use linera_views::context::Context as _;
+            #load_metrics
+            linera_views::schema::load_versioned(context).await
+        }
+    } else {
+        quote! {
+            use linera_views::context::Context as _;
+            #load_metrics
+            if Self::NUM_INIT_KEYS == 0 {
+                Self::post_load(context, &[])
+            } else {
+                let keys = Self::pre_load(&context)?;
+                let values = context.read_multi_values_bytes(keys).await?;
+                Self::post_load(context, &values)
+            }
+        }
+    };
+
     quote! {
         #[linera_views::async_trait]
         impl #impl_generics linera_views::views::View<#context> for #struct_name #type_generics
@@ -188,15 +220,7 @@ fn generate_view_code(input: ItemStruct, root: bool) -> TokenStream2 {
             }
 
             async fn load(context: #context) -> Result<Self, linera_views::views::ViewError> {
-                use linera_views::context::Context as _;
-                #load_metrics
-                if Self::NUM_INIT_KEYS == 0 {
-                    Self::post_load(context, &[])
-                } else {
-                    let keys = Self::pre_load(&context)?;
-                    let values = context.read_multi_values_bytes(keys).await?;
-                    Self::post_load(context, &values)
-                }
+                #load_quote
             }
 
 
@@ -236,6 +260,14 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
         .predicates
         .extend(context_constraints.predicates);
 
+    let save_version = schema_version(&input.attrs).map(|version| {
+        quote! {
+            if !batch.is_empty() {
+                linera_views::schema::write_schema_version(self.context(), #version, &mut batch)?;
+            }
+        }
+    });
+
     let mut flushes = Vec::new();
     let mut deletes = Vec::new();
     for e in input.fields {
@@ -267,6 +299,7 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
                 #increment_counter
                 let mut batch = Batch::new();
                 #(#flushes)*
+                #save_version
                 if !batch.is_empty() {
                     self.context().write_batch(batch).await?;
                 }
@@ -276,6 +309,44 @@ fn generate_save_delete_view_code(input: ItemStruct) -> TokenStream2 {
     }
 }
 
+fn generate_versioned_view_code(input: ItemStruct) -> TokenStream2 {
+    let Some(version) = schema_version(&input.attrs) else {
+        return quote! {};
+    };
+    let struct_name = input.ident;
+    let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
+    let template_vect = get_seq_parameter(input.generics.clone());
+
+    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+
+    let mut where_clause = maybe_where_clause
+        .cloned()
+        .unwrap_or_else(empty_where_clause);
+    where_clause
+        .predicates
+        .extend(context_constraints.predicates);
+
+    let migrations = match custom_attribute(&input.attrs, "migrations") {
+        Some(path) => {
+            let path: syn::Path = path.parse().expect("Invalid migrations function");
+            quote! { #path() }
+        }
+        None => quote! { linera_views::schema::SchemaMigrations::new() },
+    };
+
+    quote! {
+        impl #impl_generics linera_views::schema::VersionedView<#context> for #struct_name #type_generics
+        #where_clause
+        {
+            const SCHEMA_VERSION: u32 = #version;
+
+            fn schema_migrations() -> linera_views::schema::SchemaMigrations<Self> {
+                #migrations
+            }
+        }
+    }
+}
+
 fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     let struct_name = input.ident;
     let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
@@ -324,6 +395,45 @@ fn generate_hash_view_code(input: ItemStruct) -> TokenStream2 {
     }
 }
 
+fn generate_field_hashes_code(input: ItemStruct) -> TokenStream2 {
+    let struct_name = input.ident;
+    let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
+    let template_vect = get_seq_parameter(input.generics.clone());
+
+    let (context, context_constraints) = context_and_constraints(&input.attrs, &template_vect);
+
+    let mut where_clause = maybe_where_clause
+        .cloned()
+        .unwrap_or_else(empty_where_clause);
+    where_clause
+        .predicates
+        .extend(context_constraints.predicates);
+
+    let mut field_names = Vec::new();
+    let mut field_hashes = Vec::new();
+    for field in input.fields {
+        let name = field.ident.unwrap();
+        field_names.push(name.unraw().to_string());
+        field_hashes.push(quote! {
+            linera_views::common::HasherOutput::clone_from_slice(self.#name.hash().await?.as_ref())
+        });
+    }
+
+    quote! {
+        #[linera_views::async_trait]
+        impl #impl_generics linera_views::views::HashableFields<#context> for #struct_name #type_generics
+        #where_clause
+        {
+            const FIELD_NAMES: &'static [&'static str] = &[#(#field_names),*];
+
+            async fn field_hashes(&self) -> Result<Vec<linera_views::common::HasherOutput>, linera_views::views::ViewError> {
+                use linera_views::views::HashableView as _;
+                Ok(vec![#(#field_hashes),*])
+            }
+        }
+    }
+}
+
 fn generate_crypto_hash_code(input: ItemStruct) -> TokenStream2 {
     let struct_name = input.ident;
     let (impl_generics, type_generics, maybe_where_clause) = input.generics.split_for_impl();
@@ -415,7 +525,8 @@ pub fn derive_view(input: TokenStream) -> TokenStream {
 pub fn derive_hash_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), false);
-    stream.extend(generate_hash_view_code(input));
+    stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input));
     stream.into()
 }
 
@@ -423,7 +534,8 @@ pub fn derive_hash_view(input: TokenStream) -> TokenStream {
 pub fn derive_root_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), true);
-    stream.extend(generate_save_delete_view_code(input));
+    stream.extend(generate_save_delete_view_code(input.clone()));
+    stream.extend(generate_versioned_view_code(input));
     stream.into()
 }
 
@@ -432,6 +544,7 @@ pub fn derive_crypto_hash_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), false);
     stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input.clone()));
     stream.extend(generate_crypto_hash_code(input));
     stream.into()
 }
@@ -442,7 +555,9 @@ pub fn derive_crypto_hash_root_view(input: TokenStream) -> TokenStream {
     let mut stream = generate_view_code(input.clone(), true);
     stream.extend(generate_save_delete_view_code(input.clone()));
     stream.extend(generate_hash_view_code(input.clone()));
-    stream.extend(generate_crypto_hash_code(input));
+    stream.extend(generate_field_hashes_code(input.clone()));
+    stream.extend(generate_crypto_hash_code(input.clone()));
+    stream.extend(generate_versioned_view_code(input));
     stream.into()
 }
 
@@ -452,7 +567,9 @@ pub fn derive_hashable_root_view(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
     let mut stream = generate_view_code(input.clone(), true);
     stream.extend(generate_save_delete_view_code(input.clone()));
-    stream.extend(generate_hash_view_code(input));
+    stream.extend(generate_hash_view_code(input.clone()));
+    stream.extend(generate_field_hashes_code(input.clone()));
+    stream.extend(generate_versioned_view_code(input));
     stream.into()
 }
 
@@ -506,6 +623,33 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_generate_field_hashes_code() {
+        for context in SpecificContextInfo::test_cases() {
+            let input = context.test_view_input();
+            insta::assert_snapshot!(
+                format!("test_generate_field_hashes_code_{}", context.name),
+                pretty(generate_field_hashes_code(input))
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_versioned_view_code() {
+        for context in SpecificContextInfo::test_cases() {
+            let mut input = context.test_view_input();
+            assert!(generate_versioned_view_code(input.clone()).is_empty());
+            input.attrs.push(parse_quote! { #[view(version = "3")] });
+            input
+                .attrs
+                .push(parse_quote! { #[view(migrations = "TestView::migrations")] });
+            insta::assert_snapshot!(
+                format!("test_generate_versioned_view_code_{}", context.name),
+                pretty(generate_versioned_view_code(input))
+            );
+        }
+    }
+
     #[test]
     fn test_generate_save_delete_view_code() {
         for context in SpecificContextInfo::test_cases() {
@@ -32,6 +32,8 @@ pub enum StoreInUse {
     First,
     /// The second store.
     Second,
+    /// Both stores: reads are served by the first store and writes are applied to both.
+    Both,
 }
 
 /// The trait for a (static) root key assignment.
@@ -40,6 +42,16 @@ pub trait DualStoreRootKeyAssignment {
     fn assigned_store(root_key: &[u8]) -> Result<StoreInUse, bcs::Error>;
 }
 
+/// Mirrors every root key of the first store into the second one, e.g. to keep a copy of
+/// the data in sync while it is migrated to the second store.
+pub struct MirroringAssignment;
+
+impl DualStoreRootKeyAssignment for MirroringAssignment {
+    fn assigned_store(_root_key: &[u8]) -> Result<StoreInUse, bcs::Error> {
+        Ok(StoreInUse::Both)
+    }
+}
+
 /// A store made of two existing stores.
 #[derive(Clone)]
 pub struct DualStore<S1, S2, A> {
@@ -79,14 +91,14 @@ where
 
     fn max_stream_queries(&self) -> usize {
         match self.store_in_use {
-            StoreInUse::First => self.first_store.max_stream_queries(),
+            StoreInUse::First | StoreInUse::Both => self.first_store.max_stream_queries(),
             StoreInUse::Second => self.second_store.max_stream_queries(),
         }
     }
 
     async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => self
+            StoreInUse::First | StoreInUse::Both => self
                 .first_store
                 .read_value_bytes(key)
                 .await
@@ -102,7 +114,7 @@ where
 
     async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => self
+            StoreInUse::First | StoreInUse::Both => self
                 .first_store
                 .contains_key(key)
                 .await
@@ -118,7 +130,7 @@ where
 
     async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => self
+            StoreInUse::First | StoreInUse::Both => self
                 .first_store
                 .contains_keys(keys)
                 .await
@@ -137,7 +149,7 @@ where
         keys: Vec<Vec<u8>>,
     ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => self
+            StoreInUse::First | StoreInUse::Both => self
                 .first_store
                 .read_multi_values_bytes(keys)
                 .await
@@ -153,7 +165,7 @@ where
 
     async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => DualStoreKeys::First(
+            StoreInUse::First | StoreInUse::Both => DualStoreKeys::First(
                 self.first_store
                     .find_keys_by_prefix(key_prefix)
                     .await
@@ -174,7 +186,7 @@ where
         key_prefix: &[u8],
     ) -> Result<Self::KeyValues, Self::Error> {
         let result = match self.store_in_use {
-            StoreInUse::First => DualStoreKeyValues::First(
+            StoreInUse::First | StoreInUse::Both => DualStoreKeyValues::First(
                 self.first_store
                     .find_key_values_by_prefix(key_prefix)
                     .await
@@ -211,6 +223,16 @@ where
                 .write_batch(batch)
                 .await
                 .map_err(DualStoreError::Second)?,
+            StoreInUse::Both => {
+                self.first_store
+                    .write_batch(batch.clone())
+                    .await
+                    .map_err(DualStoreError::First)?;
+                self.second_store
+                    .write_batch(batch)
+                    .await
+                    .map_err(DualStoreError::Second)?;
+            }
         }
         Ok(())
     }
@@ -227,6 +249,16 @@ where
                 .clear_journal()
                 .await
                 .map_err(DualStoreError::Second)?,
+            StoreInUse::Both => {
+                self.first_store
+                    .clear_journal()
+                    .await
+                    .map_err(DualStoreError::First)?;
+                self.second_store
+                    .clear_journal()
+                    .await
+                    .map_err(DualStoreError::Second)?;
+            }
         }
         Ok(())
     }
@@ -239,6 +271,7 @@ where
     A: DualStoreRootKeyAssignment + Send + Sync,
 {
     type Config = DualStoreConfig<S1::Config, S2::Config>;
+    const ROOT_KEYS_ARE_PREFIXES: bool = S1::ROOT_KEYS_ARE_PREFIXES || S2::ROOT_KEYS_ARE_PREFIXES;
 
     fn get_name() -> String {
         format!("dual {} and {}", S1::get_name(), S2::get_name())
@@ -308,6 +341,9 @@ where
                 .await
                 .map_err(DualStoreError::Second)?,
         );
+        // Root keys in use by both stores are listed by both.
+        root_keys.sort();
+        root_keys.dedup();
         Ok(root_keys)
     }
 
@@ -340,6 +340,7 @@ impl DynamoDbStoreInternalConfig {
 
 impl AdminKeyValueStore for DynamoDbStoreInternal {
     type Config = DynamoDbStoreInternalConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = false;
 
     fn get_name() -> String {
         "dynamodb internal".to_string()
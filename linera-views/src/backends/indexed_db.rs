@@ -268,6 +268,7 @@ impl LocalWritableKeyValueStore for IndexedDbStore {
 
 impl LocalAdminKeyValueStore for IndexedDbStore {
     type Config = IndexedDbStoreConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = true;
 
     fn get_name() -> String {
         "indexed db".to_string()
@@ -182,6 +182,7 @@ where
     K: AdminKeyValueStore + Send + Sync,
 {
     type Config = K::Config;
+    const ROOT_KEYS_ARE_PREFIXES: bool = K::ROOT_KEYS_ARE_PREFIXES;
 
     fn get_name() -> String {
         format!("journaling {}", K::get_name())
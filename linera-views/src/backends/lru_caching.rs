@@ -2,19 +2,30 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Add LRU (least recently used) caching to a given store.
+//!
+//! When several processes write to the same namespace, e.g. the proxy and the shards of a
+//! validator sharing a ScyllaDB database, their caches tell each other which keys they wrote
+//! through an [`InvalidationChannel`]. Each process then forgets the keys written by the
+//! others, so that it never serves data older than the latest write it was told about.
 
-#[cfg(with_metrics)]
-use std::sync::LazyLock;
 use std::{
     collections::{btree_map, hash_map::RandomState, BTreeMap},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
 
+use linera_base::time::{Duration, Instant};
 use linked_hash_map::LinkedHashMap;
 use serde::{Deserialize, Serialize};
-#[cfg(with_metrics)]
-use {linera_base::prometheus_util::register_int_counter_vec, prometheus::IntCounterVec};
+use tokio::sync::broadcast;
 
+#[cfg(with_metrics)]
+use crate::metrics::{
+    CONTAINS_KEY_CACHE_HIT_COUNT, CONTAINS_KEY_CACHE_MISS_COUNT, READ_VALUE_CACHE_HIT_COUNT,
+    READ_VALUE_CACHE_MISS_COUNT, STORAGE_CACHE_EVICTION_COUNT,
+};
 use crate::{
     batch::{Batch, WriteOperation},
     common::get_interval,
@@ -23,46 +34,6 @@ use crate::{
 #[cfg(with_testing)]
 use crate::{memory::MemoryStore, store::TestKeyValueStore};
 
-#[cfg(with_metrics)]
-/// The total number of cache read value misses
-static READ_VALUE_CACHE_MISS_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec(
-        "num_read_value_cache_miss",
-        "Number of read value cache misses",
-        &[],
-    )
-});
-
-#[cfg(with_metrics)]
-/// The total number of read value cache hits
-static READ_VALUE_CACHE_HIT_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec(
-        "num_read_value_cache_hits",
-        "Number of read value cache hits",
-        &[],
-    )
-});
-
-#[cfg(with_metrics)]
-/// The total number of contains key cache misses
-static CONTAINS_KEY_CACHE_MISS_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec(
-        "num_contains_key_cache_miss",
-        "Number of contains key cache misses",
-        &[],
-    )
-});
-
-#[cfg(with_metrics)]
-/// The total number of contains key cache hits
-static CONTAINS_KEY_CACHE_HIT_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
-    register_int_counter_vec(
-        "num_contains_key_cache_hit",
-        "Number of contains key cache hits",
-        &[],
-    )
-});
-
 /// The parametrization of the cache
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StorageCacheConfig {
@@ -72,6 +43,18 @@ pub struct StorageCacheConfig {
     pub max_entry_size: usize,
     /// The maximum number of entries in the cache.
     pub max_cache_entries: usize,
+    /// Whether to cache the absence of keys even without exclusive access to the store. This
+    /// is only safe if all the other writers of the store publish their writes on the
+    /// invalidation channel, or if the time to live of the entries is short.
+    #[serde(default)]
+    pub cache_missing_keys: bool,
+    /// How long an entry stays in the cache, if limited.
+    #[serde(default)]
+    pub entry_ttl: Option<Duration>,
+    /// The channel through which the caches of the processes sharing the store forget the
+    /// keys written by each other.
+    #[serde(skip)]
+    pub invalidation_channel: Option<InvalidationChannel>,
 }
 
 /// The maximum number of entries in the cache.
@@ -81,8 +64,95 @@ pub const DEFAULT_STORAGE_CACHE_CONFIG: StorageCacheConfig = StorageCacheConfig
     max_cache_size: 10000000,
     max_entry_size: 1000000,
     max_cache_entries: 1000,
+    cache_missing_keys: false,
+    entry_ttl: None,
+    invalidation_channel: None,
 };
 
+/// Keys written to a store, that the caches of the other processes must forget.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Invalidation {
+    /// A key was written or deleted.
+    Key(Vec<u8>),
+    /// All the keys with this prefix were deleted.
+    Prefix(Vec<u8>),
+}
+
+/// The keys written to a store by one batch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidationMessage {
+    /// The root key of the store that was written.
+    pub root_key: Vec<u8>,
+    /// The written keys, relative to the root key.
+    pub invalidations: Vec<Invalidation>,
+}
+
+/// A channel between the caches of the processes sharing a namespace.
+///
+/// The channel itself connects the caches of one process. It is bridged to the other
+/// processes by forwarding the messages received from [`InvalidationChannel::outgoing`],
+/// and by passing the messages from the other processes to [`InvalidationChannel::deliver`].
+/// A cache that misses messages because it lags behind forgets all its entries, and so do
+/// all the caches after [`InvalidationChannel::reset`], e.g. when the bridge to another
+/// process was interrupted.
+#[derive(Clone, Debug)]
+pub struct InvalidationChannel {
+    /// The notices for the caches of this process.
+    caches: broadcast::Sender<Notice>,
+    /// The messages published by the caches of this process.
+    outgoing: broadcast::Sender<Arc<InvalidationMessage>>,
+}
+
+impl InvalidationChannel {
+    /// Creates a channel buffering up to `capacity` messages for each receiver. The
+    /// capacity must be positive.
+    pub fn new(capacity: usize) -> Self {
+        let (caches, _) = broadcast::channel(capacity);
+        let (outgoing, _) = broadcast::channel(capacity);
+        Self { caches, outgoing }
+    }
+
+    /// Delivers a message published by a cache of another process.
+    pub fn deliver(&self, message: InvalidationMessage) {
+        // Sending only fails if there is no receiver, i.e. no cache to invalidate.
+        let _ = self.caches.send(Notice::Written(None, Arc::new(message)));
+    }
+
+    /// Makes all the caches of this process forget their entries, because some writes of
+    /// another process may be unknown.
+    pub fn reset(&self) {
+        let _ = self.caches.send(Notice::Reset);
+    }
+
+    /// Returns a receiver of the messages published by the caches of this process, to
+    /// forward to the other processes.
+    pub fn outgoing(&self) -> broadcast::Receiver<Arc<InvalidationMessage>> {
+        self.outgoing.subscribe()
+    }
+
+    /// Publishes the keys written by the cache `origin` of this process.
+    fn publish(&self, origin: u64, message: InvalidationMessage) {
+        let message = Arc::new(message);
+        let _ = self
+            .caches
+            .send(Notice::Written(Some(origin), message.clone()));
+        let _ = self.outgoing.send(message);
+    }
+}
+
+/// What the caches of a process are told through an [`InvalidationChannel`].
+#[derive(Clone, Debug)]
+enum Notice {
+    /// Keys were written, by the cache with the given identifier if it belongs to this
+    /// process.
+    Written(Option<u64>, Arc<InvalidationMessage>),
+    /// Some writes are unknown.
+    Reset,
+}
+
+/// The identifier of the next cache created by this process.
+static NEXT_CACHE_ID: AtomicU64 = AtomicU64::new(0);
+
 enum CacheEntry {
     DoesNotExist,
     Exists,
@@ -98,77 +168,240 @@ impl CacheEntry {
     }
 }
 
+/// The position of an entry in the LRU queue.
+struct QueueEntry {
+    /// The size of the key and value.
+    size: usize,
+    /// When the entry must be removed, if it has a time to live.
+    expires_at: Option<Instant>,
+}
+
 /// Stores the data for simple `read_values` queries.
 ///
 /// This data structure is inspired by the crate `lru-cache` but was modified to support
 /// range deletions.
 struct LruPrefixCache {
     map: BTreeMap<Vec<u8>, CacheEntry>,
-    queue: LinkedHashMap<Vec<u8>, usize, RandomState>,
+    queue: LinkedHashMap<Vec<u8>, QueueEntry, RandomState>,
     storage_cache_config: StorageCacheConfig,
     total_size: usize,
     /// Whether we have exclusive R/W access to the keys under the root key of the store.
     has_exclusive_access: bool,
+    /// The identifier of the cache among the ones of this process.
+    id: u64,
+    /// The root key of the store, which selects the invalidations concerning the cache.
+    root_key: Vec<u8>,
+    /// The invalidations published by the other caches, if there is a channel.
+    invalidations: Option<broadcast::Receiver<Notice>>,
+    /// The number of times invalidations were applied to the cache.
+    invalidation_epoch: u64,
 }
 
 impl LruPrefixCache {
     /// Creates an `LruPrefixCache`.
-    pub fn new(storage_cache_config: StorageCacheConfig) -> Self {
+    pub fn new(storage_cache_config: StorageCacheConfig, root_key: Vec<u8>) -> Self {
+        let invalidations = storage_cache_config
+            .invalidation_channel
+            .as_ref()
+            .map(|channel| channel.caches.subscribe());
         Self {
             map: BTreeMap::new(),
             queue: LinkedHashMap::new(),
             storage_cache_config,
             total_size: 0,
             has_exclusive_access: false,
+            id: NEXT_CACHE_ID.fetch_add(1, Ordering::Relaxed),
+            root_key,
+            invalidations,
+            invalidation_epoch: 0,
         }
     }
 
+    /// Whether the absence of a key can be cached.
+    fn caches_missing_keys(&self) -> bool {
+        self.has_exclusive_access || self.storage_cache_config.cache_missing_keys
+    }
+
     /// Trim the cache so that it fits within the constraints.
     fn trim_cache(&mut self) {
         while self.total_size > self.storage_cache_config.max_cache_size
             || self.queue.len() > self.storage_cache_config.max_cache_entries
         {
-            let Some((key, key_value_size)) = self.queue.pop_front() else {
+            let Some((key, queue_entry)) = self.queue.pop_front() else {
                 break;
             };
             self.map.remove(&key);
-            self.total_size -= key_value_size;
+            self.total_size -= queue_entry.size;
+            #[cfg(with_metrics)]
+            STORAGE_CACHE_EVICTION_COUNT
+                .with_label_values(&["size"])
+                .inc();
+        }
+    }
+
+    /// Removes an entry from the cache. Returns whether it was present.
+    fn forget(&mut self, key: &[u8]) -> bool {
+        let Some(queue_entry) = self.queue.remove(key) else {
+            return false;
+        };
+        self.map.remove(key);
+        self.total_size -= queue_entry.size;
+        true
+    }
+
+    /// Removes the entries whose key matches the prefix. Returns how many there were.
+    fn forget_prefix(&mut self, key_prefix: &[u8]) -> usize {
+        let keys = self
+            .map
+            .range(get_interval(key_prefix.to_vec()))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        for key in &keys {
+            self.forget(key);
+        }
+        keys.len()
+    }
+
+    /// Removes the entry if its time to live is over.
+    fn remove_if_expired(&mut self, key: &[u8]) {
+        let expired = self
+            .queue
+            .get(key)
+            .and_then(|queue_entry| queue_entry.expires_at)
+            .is_some_and(|expires_at| expires_at <= Instant::now());
+        if expired {
+            self.forget(key);
+            #[cfg(with_metrics)]
+            STORAGE_CACHE_EVICTION_COUNT
+                .with_label_values(&["expired"])
+                .inc();
+        }
+    }
+
+    /// Removes the entries written by the other caches since the last call.
+    fn apply_invalidations(&mut self) {
+        let Some(receiver) = &mut self.invalidations else {
+            return;
+        };
+        let mut messages = Vec::new();
+        let mut reset = false;
+        loop {
+            match receiver.try_recv() {
+                Ok(Notice::Written(origin, message)) => {
+                    if origin != Some(self.id) && message.root_key == self.root_key {
+                        messages.push(message);
+                    }
+                }
+                Ok(Notice::Reset) | Err(broadcast::error::TryRecvError::Lagged(_)) => reset = true,
+                Err(broadcast::error::TryRecvError::Empty)
+                | Err(broadcast::error::TryRecvError::Closed) => break,
+            }
+        }
+        if reset {
+            // Some writes are unknown: no entry can be trusted.
+            #[cfg(with_metrics)]
+            STORAGE_CACHE_EVICTION_COUNT
+                .with_label_values(&["invalidated"])
+                .inc_by(self.queue.len() as u64);
+            self.map.clear();
+            self.queue.clear();
+            self.total_size = 0;
+            self.invalidation_epoch += 1;
+            return;
+        }
+        for message in messages {
+            self.invalidation_epoch += 1;
+            for invalidation in &message.invalidations {
+                let _count = match invalidation {
+                    Invalidation::Key(key) => usize::from(self.forget(key)),
+                    Invalidation::Prefix(key_prefix) => self.forget_prefix(key_prefix),
+                };
+                #[cfg(with_metrics)]
+                STORAGE_CACHE_EVICTION_COUNT
+                    .with_label_values(&["invalidated"])
+                    .inc_by(_count as u64);
+            }
+        }
+    }
+
+    /// Returns the number of times invalidations were applied so far. A value read from
+    /// storage is only cached if no invalidation was applied during the read, since the
+    /// value may predate the invalidated write.
+    pub fn invalidation_epoch(&self) -> u64 {
+        self.invalidation_epoch
+    }
+
+    /// Returns the message announcing the keys written by a batch, if there is an
+    /// invalidation channel.
+    pub fn invalidation_message(&self, batch: &Batch) -> Option<InvalidationMessage> {
+        self.storage_cache_config.invalidation_channel.as_ref()?;
+        let invalidations = batch
+            .operations
+            .iter()
+            .map(|operation| match operation {
+                WriteOperation::Put { key, .. } | WriteOperation::Delete { key } => {
+                    Invalidation::Key(key.clone())
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    Invalidation::Prefix(key_prefix.clone())
+                }
+            })
+            .collect();
+        Some(InvalidationMessage {
+            root_key: self.root_key.clone(),
+            invalidations,
+        })
+    }
+
+    /// Publishes a message on the invalidation channel, if any.
+    pub fn publish(&self, message: InvalidationMessage) {
+        if let Some(channel) = &self.storage_cache_config.invalidation_channel {
+            channel.publish(self.id, message);
         }
     }
 
     /// Inserts an entry into the cache.
     pub fn insert(&mut self, key: Vec<u8>, cache_entry: CacheEntry) {
         let key_value_size = key.len() + cache_entry.size();
-        if (matches!(cache_entry, CacheEntry::DoesNotExist) && !self.has_exclusive_access)
+        if (matches!(cache_entry, CacheEntry::DoesNotExist) && !self.caches_missing_keys())
             || key_value_size > self.storage_cache_config.max_entry_size
         {
             // Just forget about the entry.
-            if let Some(old_key_value_size) = self.queue.remove(&key) {
-                self.total_size -= old_key_value_size;
-                self.map.remove(&key);
-            };
+            self.forget(&key);
             return;
         }
+        let queue_entry = QueueEntry {
+            size: key_value_size,
+            expires_at: self
+                .storage_cache_config
+                .entry_ttl
+                .map(|ttl| Instant::now() + ttl),
+        };
         match self.map.entry(key.clone()) {
             btree_map::Entry::Occupied(mut entry) => {
                 entry.insert(cache_entry);
                 // Put it on first position for LRU
-                let old_key_value_size = self.queue.remove(&key).expect("old_key_value_size");
-                self.total_size -= old_key_value_size;
-                self.queue.insert(key, key_value_size);
+                let old_queue_entry = self.queue.remove(&key).expect("old_key_value_size");
+                self.total_size -= old_queue_entry.size;
+                self.queue.insert(key, queue_entry);
                 self.total_size += key_value_size;
             }
             btree_map::Entry::Vacant(entry) => {
                 entry.insert(cache_entry);
-                self.queue.insert(key, key_value_size);
+                self.queue.insert(key, queue_entry);
                 self.total_size += key_value_size;
             }
         }
         self.trim_cache();
     }
 
-    /// Inserts a read_value entry into the cache.
-    pub fn insert_read_value(&mut self, key: Vec<u8>, value: &Option<Vec<u8>>) {
+    /// Inserts a read_value entry into the cache, read from storage at the given
+    /// invalidation epoch.
+    pub fn insert_read_value(&mut self, key: Vec<u8>, value: &Option<Vec<u8>>, epoch: u64) {
+        self.apply_invalidations();
+        if epoch != self.invalidation_epoch {
+            return;
+        }
         let cache_entry = match value {
             None => CacheEntry::DoesNotExist,
             Some(vec) => CacheEntry::Value(vec.to_vec()),
@@ -176,8 +409,13 @@ impl LruPrefixCache {
         self.insert(key, cache_entry)
     }
 
-    /// Inserts a read_value entry into the cache.
-    pub fn insert_contains_key(&mut self, key: Vec<u8>, result: bool) {
+    /// Inserts a contains_key entry into the cache, read from storage at the given
+    /// invalidation epoch.
+    pub fn insert_contains_key(&mut self, key: Vec<u8>, result: bool, epoch: u64) {
+        self.apply_invalidations();
+        if epoch != self.invalidation_epoch {
+            return;
+        }
         let cache_entry = match result {
             false => CacheEntry::DoesNotExist,
             true => CacheEntry::Exists,
@@ -188,25 +426,15 @@ impl LruPrefixCache {
     /// Marks cached keys that match the prefix as deleted. Importantly, this does not
     /// create new entries in the cache.
     pub fn delete_prefix(&mut self, key_prefix: &[u8]) {
-        if self.has_exclusive_access {
+        if self.caches_missing_keys() {
             for (key, value) in self.map.range_mut(get_interval(key_prefix.to_vec())) {
-                *self.queue.get_mut(key).unwrap() = key.len();
+                self.queue.get_mut(key).unwrap().size = key.len();
                 self.total_size -= value.size();
                 *value = CacheEntry::DoesNotExist;
             }
         } else {
             // Just forget about the entries.
-            let mut keys = Vec::new();
-            for (key, _) in self.map.range(get_interval(key_prefix.to_vec())) {
-                keys.push(key.to_vec());
-            }
-            for key in keys {
-                self.map.remove(&key);
-                let Some(key_value_size) = self.queue.remove(&key) else {
-                    unreachable!("The key should be in the queue");
-                };
-                self.total_size -= key_value_size;
-            }
+            self.forget_prefix(key_prefix);
         }
     }
 
@@ -214,6 +442,8 @@ impl LruPrefixCache {
     /// database. If `None` is returned, the entry might exist in the database but is
     /// not in the cache.
     pub fn query_read_value(&mut self, key: &[u8]) -> Option<Option<Vec<u8>>> {
+        self.apply_invalidations();
+        self.remove_if_expired(key);
         let result = match self.map.get(key) {
             None => None,
             Some(entry) => match entry {
@@ -224,8 +454,8 @@ impl LruPrefixCache {
         };
         if result.is_some() {
             // Put back the key on top
-            let key_value_size = self.queue.remove(key).expect("key_value_size");
-            self.queue.insert(key.to_vec(), key_value_size);
+            let queue_entry = self.queue.remove(key).expect("key_value_size");
+            self.queue.insert(key.to_vec(), queue_entry);
         }
         result
     }
@@ -233,14 +463,16 @@ impl LruPrefixCache {
     /// Returns `Some(true)` or `Some(false)` if we know that the entry does or does not
     /// exist in the database. Returns `None` if that information is not in the cache.
     pub fn query_contains_key(&mut self, key: &[u8]) -> Option<bool> {
+        self.apply_invalidations();
+        self.remove_if_expired(key);
         let result = self
             .map
             .get(key)
             .map(|entry| !matches!(entry, CacheEntry::DoesNotExist));
         if result.is_some() {
             // Put back the key on top
-            let key_value_size = self.queue.remove(key).expect("key_value_size");
-            self.queue.insert(key.to_vec(), key_value_size);
+            let queue_entry = self.queue.remove(key).expect("key_value_size");
+            self.queue.insert(key.to_vec(), queue_entry);
         }
         result
     }
@@ -280,19 +512,20 @@ where
             return self.store.read_value_bytes(key).await;
         };
         // First inquiring in the read_value_bytes LRU
-        {
+        let epoch = {
             let mut cache = cache.lock().unwrap();
             if let Some(value) = cache.query_read_value(key) {
                 #[cfg(with_metrics)]
                 READ_VALUE_CACHE_HIT_COUNT.with_label_values(&[]).inc();
                 return Ok(value);
             }
-        }
+            cache.invalidation_epoch()
+        };
         #[cfg(with_metrics)]
         READ_VALUE_CACHE_MISS_COUNT.with_label_values(&[]).inc();
         let value = self.store.read_value_bytes(key).await?;
         let mut cache = cache.lock().unwrap();
-        cache.insert_read_value(key.to_vec(), &value);
+        cache.insert_read_value(key.to_vec(), &value, epoch);
         Ok(value)
     }
 
@@ -300,19 +533,20 @@ where
         let Some(cache) = &self.cache else {
             return self.store.contains_key(key).await;
         };
-        {
+        let epoch = {
             let mut cache = cache.lock().unwrap();
             if let Some(value) = cache.query_contains_key(key) {
                 #[cfg(with_metrics)]
                 CONTAINS_KEY_CACHE_HIT_COUNT.with_label_values(&[]).inc();
                 return Ok(value);
             }
-        }
+            cache.invalidation_epoch()
+        };
         #[cfg(with_metrics)]
         CONTAINS_KEY_CACHE_MISS_COUNT.with_label_values(&[]).inc();
         let result = self.store.contains_key(key).await?;
         let mut cache = cache.lock().unwrap();
-        cache.insert_contains_key(key.to_vec(), result);
+        cache.insert_contains_key(key.to_vec(), result, epoch);
         Ok(result)
     }
 
@@ -324,7 +558,7 @@ where
         let mut results = vec![false; size];
         let mut indices = Vec::new();
         let mut key_requests = Vec::new();
-        {
+        let epoch = {
             let mut cache = cache.lock().unwrap();
             for i in 0..size {
                 if let Some(value) = cache.query_contains_key(&keys[i]) {
@@ -338,13 +572,14 @@ where
                     key_requests.push(keys[i].clone());
                 }
             }
-        }
+            cache.invalidation_epoch()
+        };
         if !key_requests.is_empty() {
             let key_results = self.store.contains_keys(key_requests.clone()).await?;
             let mut cache = cache.lock().unwrap();
             for ((index, result), key) in indices.into_iter().zip(key_results).zip(key_requests) {
                 results[index] = result;
-                cache.insert_contains_key(key, result);
+                cache.insert_contains_key(key, result, epoch);
             }
         }
         Ok(results)
@@ -361,7 +596,7 @@ where
         let mut result = Vec::with_capacity(keys.len());
         let mut cache_miss_indices = Vec::new();
         let mut miss_keys = Vec::new();
-        {
+        let epoch = {
             let mut cache = cache.lock().unwrap();
             for (i, key) in keys.into_iter().enumerate() {
                 if let Some(value) = cache.query_read_value(&key) {
@@ -376,7 +611,8 @@ where
                     miss_keys.push(key);
                 }
             }
-        }
+            cache.invalidation_epoch()
+        };
         if !miss_keys.is_empty() {
             let values = self
                 .store
@@ -387,7 +623,7 @@ where
                 .into_iter()
                 .zip(miss_keys.into_iter().zip(values))
             {
-                cache.insert_read_value(key, &value);
+                cache.insert_read_value(key, &value, epoch);
                 result[i] = value;
             }
         }
@@ -418,7 +654,7 @@ where
             return self.store.write_batch(batch).await;
         };
 
-        {
+        let message = {
             let mut cache = cache.lock().unwrap();
             for operation in &batch.operations {
                 match operation {
@@ -435,8 +671,14 @@ where
                     }
                 }
             }
+            cache.invalidation_message(&batch)
+        };
+        self.store.write_batch(batch).await?;
+        // The other processes must only forget the keys once they are written.
+        if let Some(message) = message {
+            cache.lock().unwrap().publish(message);
         }
-        self.store.write_batch(batch).await
+        Ok(())
     }
 
     async fn clear_journal(&self) -> Result<(), Self::Error> {
@@ -458,6 +700,7 @@ where
     K: AdminKeyValueStore + Send + Sync,
 {
     type Config = LruCachingConfig<K::Config>;
+    const ROOT_KEYS_ARE_PREFIXES: bool = K::ROOT_KEYS_ARE_PREFIXES;
 
     fn get_name() -> String {
         format!("lru caching {}", K::get_name())
@@ -473,7 +716,8 @@ where
 
     fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, Self::Error> {
         let store = self.store.clone_with_root_key(root_key)?;
-        let store = LruCachingStore::new(store, self.storage_cache_config());
+        let store =
+            LruCachingStore::new_with_root_key(store, self.storage_cache_config(), root_key);
         store.enable_exclusive_access();
         Ok(store)
     }
@@ -524,12 +768,23 @@ where
 impl<K> LruCachingStore<K> {
     /// Creates a new key-value store that provides LRU caching at top of the given store.
     pub fn new(store: K, storage_cache_config: StorageCacheConfig) -> Self {
+        Self::new_with_root_key(store, storage_cache_config, &[])
+    }
+
+    /// Creates a new key-value store that provides LRU caching at top of the given store,
+    /// whose keys are under `root_key`.
+    fn new_with_root_key(
+        store: K,
+        storage_cache_config: StorageCacheConfig,
+        root_key: &[u8],
+    ) -> Self {
         let cache = {
             if storage_cache_config.max_cache_entries == 0 {
                 None
             } else {
                 Some(Arc::new(Mutex::new(LruPrefixCache::new(
                     storage_cache_config,
+                    root_key.to_vec(),
                 ))))
             }
         };
@@ -543,6 +798,9 @@ impl<K> LruCachingStore<K> {
                 max_cache_size: 0,
                 max_entry_size: 0,
                 max_cache_entries: 0,
+                cache_missing_keys: false,
+                entry_ttl: None,
+                invalidation_channel: None,
             },
             Some(cache) => {
                 let cache = cache.lock().unwrap();
@@ -305,6 +305,7 @@ impl MemoryStore {
 
 impl AdminKeyValueStore for MemoryStore {
     type Config = MemoryStoreConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = false;
 
     fn get_name() -> String {
         "memory".to_string()
@@ -441,6 +441,7 @@ where
     K: AdminKeyValueStore + Send + Sync,
 {
     type Config = K::Config;
+    const ROOT_KEYS_ARE_PREFIXES: bool = K::ROOT_KEYS_ARE_PREFIXES;
 
     fn get_name() -> String {
         K::get_name()
@@ -474,6 +474,7 @@ impl WritableKeyValueStore for RocksDbStoreInternal {
 
 impl AdminKeyValueStore for RocksDbStoreInternal {
     type Config = RocksDbStoreInternalConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = true;
 
     fn get_name() -> String {
         "rocksdb internal".to_string()
@@ -636,6 +636,7 @@ pub struct ScyllaDbStoreInternalConfig {
 
 impl AdminKeyValueStore for ScyllaDbStoreInternal {
     type Config = ScyllaDbStoreInternalConfig;
+    const ROOT_KEYS_ARE_PREFIXES: bool = false;
 
     fn get_name() -> String {
         "scylladb internal".to_string()
@@ -284,6 +284,7 @@ where
     K::Error: 'static,
 {
     type Config = K::Config;
+    const ROOT_KEYS_ARE_PREFIXES: bool = K::ROOT_KEYS_ARE_PREFIXES;
 
     fn get_name() -> String {
         format!("value splitting {}", K::get_name())
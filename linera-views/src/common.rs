@@ -303,6 +303,19 @@ impl CustomSerialize for u128 {
     }
 }
 
+impl CustomSerialize for u64 {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn from_custom_bytes(bytes: &[u8]) -> Result<Self, ViewError> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| bcs::Error::Custom("expected 8 bytes".to_string()))?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+}
+
 /// This computes the offset of the BCS serialization of a vector.
 /// The formula that should be satisfied is
 /// `serialized_size(vec![v_1, ...., v_n]) = get_uleb128_size(n)`
@@ -350,6 +363,24 @@ mod tests {
             assert_eq!(val2, val_ret2);
         }
     }
+
+    #[test]
+    fn test_ordering_serialization_u64() {
+        let mut rng = crate::random::make_deterministic_rng();
+        let mut set = BTreeSet::new();
+        for _ in 0..1000 {
+            set.insert(rng.gen::<u64>());
+        }
+        set.extend([0, u64::MAX]);
+        let vec = set.into_iter().collect::<Vec<_>>();
+        for pair in vec.windows(2) {
+            let bytes1 = pair[0].to_custom_bytes().unwrap();
+            let bytes2 = pair[1].to_custom_bytes().unwrap();
+            assert!(bytes1 < bytes2);
+            assert_eq!(u64::from_custom_bytes(&bytes1).unwrap(), pair[0]);
+            assert_eq!(u64::from_custom_bytes(&bytes2).unwrap(), pair[1]);
+        }
+    }
 }
 
 #[test]
@@ -77,6 +77,18 @@ pub mod views;
 /// Backend implementing the [`crate::store::KeyValueStore`] trait.
 pub mod backends;
 
+/// Snapshots of namespaces in a portable archive format.
+pub mod snapshot;
+
+/// Migration of namespaces between different stores.
+pub mod migration;
+
+/// Proofs that views hold given values, under their hashes.
+pub mod proof;
+
+/// Schema versions of root views and migrations of their stored data.
+pub mod schema;
+
 /// Support for metrics.
 #[cfg(with_metrics)]
 pub mod metrics;
@@ -104,8 +116,8 @@ pub use backends::rocks_db;
 pub use backends::scylla_db;
 pub use backends::{journaling, lru_caching, memory, value_splitting};
 pub use views::{
-    bucket_queue_view, collection_view, hashable_wrapper, key_value_store_view, log_view, map_view,
-    queue_view, reentrant_collection_view, register_view, set_view,
+    bucket_queue_view, collection_view, hashable_wrapper, indexed_map_view, key_value_store_view,
+    log_view, map_view, queue_view, reentrant_collection_view, register_view, set_view,
 };
 /// Re-exports used by the derive macros of this library.
 #[doc(hidden)]
@@ -44,3 +44,50 @@ pub static SAVE_VIEW_COUNTER: LazyLock<IntCounterVec> = LazyLock::new(|| {
         &["type", "base_key"],
     )
 });
+
+/// The number of values read from the storage cache.
+pub static READ_VALUE_CACHE_HIT_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_read_value_cache_hits",
+        "Number of read value cache hits",
+        &[],
+    )
+});
+
+/// The number of values read from storage because they were not in the storage cache.
+pub static READ_VALUE_CACHE_MISS_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_read_value_cache_miss",
+        "Number of read value cache misses",
+        &[],
+    )
+});
+
+/// The number of key lookups answered by the storage cache.
+pub static CONTAINS_KEY_CACHE_HIT_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_contains_key_cache_hit",
+        "Number of contains key cache hits",
+        &[],
+    )
+});
+
+/// The number of key lookups sent to storage because they were not in the storage cache.
+pub static CONTAINS_KEY_CACHE_MISS_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_contains_key_cache_miss",
+        "Number of contains key cache misses",
+        &[],
+    )
+});
+
+/// The number of entries removed from the storage cache, by reason: `size` when the cache is
+/// full, `expired` when their time to live is over, and `invalidated` when another process
+/// wrote them.
+pub static STORAGE_CACHE_EVICTION_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_storage_cache_evictions",
+        "Number of entries removed from the storage cache",
+        &["reason"],
+    )
+});
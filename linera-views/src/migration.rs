@@ -0,0 +1,339 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Copies a namespace from one [`KeyValueStore`] to another, e.g. to move a validator to a
+//! different storage backend.
+//!
+//! The namespace is copied one root key at a time, in batches. After a root key is copied,
+//! the SHA3-256 hashes of its key-value pairs in both stores are compared, and the root key
+//! is recorded in the [`MigrationProgress`], so that an interrupted migration can be resumed
+//! without copying it again. As in [snapshots](crate::snapshot), the key-value pairs of a
+//! root key extending another one are only copied with the longest root key.
+//!
+//! A migration can run while the source is in use. Writes made to a root key after it was
+//! copied are only carried over if the source is accessed through a
+//! [`DualStore`](crate::backends::dual::DualStore) with the
+//! [`MirroringAssignment`](crate::backends::dual::MirroringAssignment), which writes to both
+//! stores. Root keys whose copy is modified concurrently fail the verification and are
+//! copied again.
+
+use std::collections::{BTreeSet, HashSet};
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest as _, Sha3_256};
+use thiserror::Error;
+
+use crate::{
+    batch::Batch,
+    snapshot::{list_keys_outside, nested_root_key_suffixes},
+    store::KeyValueStore,
+    views::ViewError,
+};
+
+/// An error while migrating a namespace.
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    /// An error of one of the key-value stores.
+    #[error(transparent)]
+    View(#[from] ViewError),
+
+    /// The progress could not be saved.
+    #[error("failed to save the migration progress: {0}")]
+    Progress(#[source] std::io::Error),
+
+    /// The progress was recorded for a different migration.
+    #[error(
+        "the migration progress is for {source_namespace} into {target_namespace}, \
+        not {expected_source} into {expected_target}"
+    )]
+    ProgressMismatch {
+        /// The source namespace of the recorded progress.
+        source_namespace: String,
+        /// The target namespace of the recorded progress.
+        target_namespace: String,
+        /// The source namespace of this migration.
+        expected_source: String,
+        /// The target namespace of this migration.
+        expected_target: String,
+    },
+
+    /// A root key still differed between the stores after the last attempt to copy it.
+    #[error("root key {0:?} differs between the source and the target after copying it")]
+    VerificationFailed(Vec<u8>),
+}
+
+/// The options of a migration.
+#[derive(Clone, Debug)]
+pub struct MigrationOptions {
+    /// The maximal number of key-value pairs written in a single batch.
+    pub batch_size: usize,
+    /// Whether to compare the hashes of every root key in both stores after copying it.
+    pub verify: bool,
+    /// How many times a root key is copied before giving up, if its verification fails.
+    pub max_attempts: u32,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            verify: true,
+            max_attempts: 3,
+        }
+    }
+}
+
+/// A root key that was copied to the target store.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigratedRootKey {
+    /// The root key.
+    pub root_key: Vec<u8>,
+    /// The number of key-value pairs copied.
+    pub key_values: u64,
+    /// The SHA3-256 hash of the key-value pairs, if they were verified.
+    pub hash: Option<[u8; 32]>,
+}
+
+/// The progress of a migration, which can be saved to resume it after an interruption.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationProgress {
+    /// The namespace being copied.
+    pub source_namespace: String,
+    /// The namespace being copied into.
+    pub target_namespace: String,
+    /// The root keys that were already copied.
+    pub completed: Vec<MigratedRootKey>,
+}
+
+/// The outcome of a migration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationSummary {
+    /// The number of root keys copied by this run.
+    pub root_keys: u64,
+    /// The number of root keys skipped because an earlier run copied them.
+    pub skipped_root_keys: u64,
+    /// The number of key-value pairs copied by this run.
+    pub key_values: u64,
+}
+
+/// Copies the `source_namespace` of a store of type `S1` into the `target_namespace` of a
+/// store of type `S2`, which is created if needed.
+///
+/// Root keys listed in `progress` are skipped. Every newly copied root key is added to
+/// `progress`, which is then passed to `save_progress`.
+pub async fn migrate_namespace<S1, S2, F>(
+    source_config: &S1::Config,
+    source_namespace: &str,
+    target_config: &S2::Config,
+    target_namespace: &str,
+    options: &MigrationOptions,
+    progress: &mut MigrationProgress,
+    mut save_progress: F,
+) -> Result<MigrationSummary, MigrationError>
+where
+    S1: KeyValueStore,
+    S2: KeyValueStore,
+    F: FnMut(&MigrationProgress) -> std::io::Result<()> + Send,
+{
+    if progress.completed.is_empty() {
+        progress.source_namespace = source_namespace.to_string();
+        progress.target_namespace = target_namespace.to_string();
+    } else if progress.source_namespace != source_namespace
+        || progress.target_namespace != target_namespace
+    {
+        return Err(MigrationError::ProgressMismatch {
+            source_namespace: progress.source_namespace.clone(),
+            target_namespace: progress.target_namespace.clone(),
+            expected_source: source_namespace.to_string(),
+            expected_target: target_namespace.to_string(),
+        });
+    }
+    if !S2::exists(target_config, target_namespace)
+        .await
+        .map_err(ViewError::from)?
+    {
+        S2::create(target_config, target_namespace)
+            .await
+            .map_err(ViewError::from)?;
+    }
+    let source = S1::connect(source_config, source_namespace)
+        .await
+        .map_err(ViewError::from)?;
+    let target = S2::connect(target_config, target_namespace)
+        .await
+        .map_err(ViewError::from)?;
+    let completed = progress
+        .completed
+        .iter()
+        .map(|migrated| migrated.root_key.clone())
+        .collect::<BTreeSet<_>>();
+    let mut root_keys = S1::list_root_keys(source_config, source_namespace)
+        .await
+        .map_err(ViewError::from)?;
+    root_keys.sort();
+    root_keys.dedup();
+    let mut summary = MigrationSummary::default();
+    for (index, root_key) in root_keys.iter().enumerate() {
+        if completed.contains(root_key) {
+            summary.skipped_root_keys += 1;
+            continue;
+        }
+        let source = RootKeyStore {
+            store: source
+                .clone_with_root_key(root_key)
+                .map_err(ViewError::from)?,
+            nested_suffixes: nested_root_key_suffixes::<S1>(&root_keys, index),
+        };
+        let target = RootKeyStore {
+            store: target
+                .clone_with_root_key(root_key)
+                .map_err(ViewError::from)?,
+            nested_suffixes: nested_root_key_suffixes::<S2>(&root_keys, index),
+        };
+        let migrated = migrate_root_key(&source, &target, root_key.clone(), options).await?;
+        summary.root_keys += 1;
+        summary.key_values += migrated.key_values;
+        progress.completed.push(migrated);
+        save_progress(progress).map_err(MigrationError::Progress)?;
+    }
+    Ok(summary)
+}
+
+/// A store cloned with a root key, and the suffixes of the root keys nested in it, whose
+/// key-value pairs are left out.
+struct RootKeyStore<S> {
+    store: S,
+    nested_suffixes: Vec<Vec<u8>>,
+}
+
+impl<S: KeyValueStore> RootKeyStore<S> {
+    /// Lists the keys of this root key, without those of nested root keys.
+    async fn list_keys(&self) -> Result<Vec<Vec<u8>>, ViewError> {
+        list_keys_outside(&self.store, &self.nested_suffixes).await
+    }
+}
+
+/// Copies the content of one root key, until the hashes match or we run out of attempts.
+async fn migrate_root_key<S1, S2>(
+    source: &RootKeyStore<S1>,
+    target: &RootKeyStore<S2>,
+    root_key: Vec<u8>,
+    options: &MigrationOptions,
+) -> Result<MigratedRootKey, MigrationError>
+where
+    S1: KeyValueStore,
+    S2: KeyValueStore,
+{
+    for attempt in 1..=options.max_attempts.max(1) {
+        let key_values = copy_root_key(source, target, options.batch_size).await?;
+        if !options.verify {
+            return Ok(MigratedRootKey {
+                root_key,
+                key_values,
+                hash: None,
+            });
+        }
+        let source_hash = hash_root_key(source, options.batch_size).await?;
+        if source_hash == hash_root_key(target, options.batch_size).await? {
+            return Ok(MigratedRootKey {
+                root_key,
+                key_values,
+                hash: Some(source_hash),
+            });
+        }
+        tracing::warn!(
+            "Root key {root_key:?} changed while it was copied (attempt {attempt} of {})",
+            options.max_attempts
+        );
+    }
+    Err(MigrationError::VerificationFailed(root_key))
+}
+
+/// Replaces the content of `target` by that of `source`, and returns the number of
+/// key-value pairs copied. Only the keys are loaded at once: the values are copied one
+/// batch at a time.
+async fn copy_root_key<S1, S2>(
+    source: &RootKeyStore<S1>,
+    target: &RootKeyStore<S2>,
+    batch_size: usize,
+) -> Result<u64, MigrationError>
+where
+    S1: KeyValueStore,
+    S2: KeyValueStore,
+{
+    let batch_size = batch_size.max(1);
+    let keys = source.list_keys().await?;
+    // Remove what an earlier, interrupted attempt may have left. Other root keys sharing a
+    // prefix with this one are left alone.
+    let source_keys = keys.iter().collect::<HashSet<_>>();
+    let stale_keys = target
+        .list_keys()
+        .await?
+        .into_iter()
+        .filter(|key| !source_keys.contains(key))
+        .collect::<Vec<_>>();
+    for stale_keys in stale_keys.chunks(batch_size) {
+        let mut batch = Batch::new();
+        for key in stale_keys {
+            batch.delete_key(key.clone());
+        }
+        target
+            .store
+            .write_batch(batch)
+            .await
+            .map_err(ViewError::from)?;
+    }
+    let mut key_values = 0;
+    for keys in keys.chunks(batch_size) {
+        let values = source
+            .store
+            .read_multi_values_bytes(keys.to_vec())
+            .await
+            .map_err(ViewError::from)?;
+        let mut batch = Batch::new();
+        for (key, value) in keys.iter().zip(values) {
+            // The key was deleted after it was listed: the verification will tell.
+            let Some(value) = value else {
+                continue;
+            };
+            batch.put_key_value_bytes(key.clone(), value);
+            key_values += 1;
+        }
+        if !batch.is_empty() {
+            target
+                .store
+                .write_batch(batch)
+                .await
+                .map_err(ViewError::from)?;
+        }
+    }
+    Ok(key_values)
+}
+
+/// Computes the SHA3-256 hash of the key-value pairs of a root key, in the order of the
+/// keys, reading `batch_size` values at a time.
+async fn hash_root_key<S: KeyValueStore>(
+    store: &RootKeyStore<S>,
+    batch_size: usize,
+) -> Result<[u8; 32], MigrationError> {
+    let mut keys = store.list_keys().await?;
+    keys.sort_unstable();
+    let mut hasher = Sha3_256::new();
+    for keys in keys.chunks(batch_size.max(1)) {
+        let values = store
+            .store
+            .read_multi_values_bytes(keys.to_vec())
+            .await
+            .map_err(ViewError::from)?;
+        for (key, value) in keys.iter().zip(values) {
+            let Some(value) = value else {
+                continue;
+            };
+            hasher.update((key.len() as u64).to_le_bytes());
+            hasher.update(key);
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(&value);
+        }
+    }
+    Ok(hasher.finalize().into())
+}
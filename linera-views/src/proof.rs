@@ -0,0 +1,378 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proofs that a view holds a given value, under the hash of an enclosing view.
+//!
+//! A [`ViewProof`] starts from a [`ProofLeaf`], i.e. the content of the view holding the
+//! value, and goes up through a sequence of [`ProofStep`]s, each giving the hashes of the
+//! siblings of the current view in its parent. Since the hashes of views are computed over all
+//! their entries, a leaf contains every entry of its map, and a step the hashes of every other
+//! entry of its collection. Checking a proof needs no access to storage.
+//!
+//! The proofs rebuild the hashes of views exactly, and these hash keys and values without
+//! length prefixes. Hence the bytes of an entry can be split differently between its key and
+//! its value without changing the hash: a valid proof only shows that the concatenation of
+//! the entries is under the hash. Checking it against the expected [`ProofPath`] makes sure
+//! that it is about the right field and key, and [`ViewProof::value`] then rejects values
+//! that do not deserialize exactly.
+
+use linera_base::{crypto::CryptoHash, doc_scalar};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest as _, Keccak256, Sha3_256};
+use thiserror::Error;
+
+use crate::common::HasherOutput;
+
+/// An error when checking a [`ViewProof`].
+#[derive(Debug, Error)]
+pub enum ProofError {
+    /// The entries of a proof are not strictly ordered by key.
+    #[error("the entries of the proof are not strictly ordered by key")]
+    UnorderedEntries,
+
+    /// The proven key also appears among the other entries.
+    #[error("the proven key appears twice in the proof")]
+    DuplicateKey,
+
+    /// The position of a field is out of bounds.
+    #[error("field index {0} is out of bounds")]
+    InvalidFieldIndex(u32),
+
+    /// A view has too many entries.
+    #[error("too many entries in the proof")]
+    TooManyEntries,
+
+    /// The proof does not lead to the expected hash.
+    #[error("the proof does not match the expected hash")]
+    HashMismatch,
+
+    /// The proof is about a value at another location than the expected one.
+    #[error("the proof is about {actual:?} instead of {expected:?}")]
+    PathMismatch {
+        /// The expected location of the value.
+        expected: ProofPath,
+        /// The location of the value in the proof.
+        actual: ProofPath,
+    },
+
+    /// The proven value could not be deserialized.
+    #[error("invalid value in the proof: {0}")]
+    InvalidValue(#[from] bcs::Error),
+}
+
+/// The content of the view holding the proven value.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofLeaf {
+    /// The value of a `RegisterView`, serialized with BCS.
+    Register(Vec<u8>),
+    /// An entry of a `MapView` or `KeyValueStoreView`, or its absence.
+    Entry {
+        /// The serialized key.
+        key: Vec<u8>,
+        /// The serialized value, or `None` if the map has no such key.
+        value: Option<Vec<u8>>,
+        /// All the other entries of the map, ordered by key.
+        others: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+}
+
+/// How the hash of a view enters the hash of its parent.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofStep {
+    /// The view is a field of a struct deriving `HashableView`.
+    Field {
+        /// The position of the view among the fields.
+        index: u32,
+        /// The hashes of the other fields, in order.
+        others: Vec<[u8; 32]>,
+    },
+    /// The view is an entry of a `CollectionView` or `ReentrantCollectionView`.
+    CollectionEntry {
+        /// The serialized key of the entry.
+        key: Vec<u8>,
+        /// The hashes of all the other entries, ordered by key.
+        others: Vec<(Vec<u8>, [u8; 32])>,
+    },
+}
+
+/// One step from a view down to one of its subviews.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathStep {
+    /// The field of a struct deriving `HashableView` at the given position.
+    Field(u32),
+    /// The entry of a `CollectionView` or `ReentrantCollectionView` with the given
+    /// serialized key.
+    CollectionEntry(Vec<u8>),
+}
+
+/// The location of a proven value within the enclosing view.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofPath {
+    /// The steps from the enclosing view down to the view holding the value, outermost
+    /// first.
+    pub steps: Vec<PathStep>,
+    /// The serialized key of the value, if the view holding it is a map.
+    pub key: Option<Vec<u8>>,
+}
+
+impl ProofPath {
+    /// The location of the value of a register.
+    pub fn register() -> Self {
+        ProofPath::default()
+    }
+
+    /// The location of the value at `key` in a map.
+    pub fn entry(key: Vec<u8>) -> Self {
+        ProofPath {
+            steps: Vec::new(),
+            key: Some(key),
+        }
+    }
+
+    /// Returns the same location, within the field at `index` of the parent struct.
+    pub fn in_field(mut self, index: usize) -> Self {
+        self.steps.insert(0, PathStep::Field(index as u32));
+        self
+    }
+
+    /// Returns the same location, within the entry at `key` of the parent collection.
+    pub fn in_collection_entry(mut self, key: Vec<u8>) -> Self {
+        self.steps.insert(0, PathStep::CollectionEntry(key));
+        self
+    }
+}
+
+/// A proof that a view holds a given value, under the hash of an enclosing view.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewProof {
+    /// The view holding the value.
+    pub leaf: ProofLeaf,
+    /// The steps from the leaf up to the enclosing view, innermost first.
+    pub steps: Vec<ProofStep>,
+}
+
+doc_scalar!(
+    ViewProof,
+    "A proof that a view holds a given value, under the hash of an enclosing view"
+);
+
+impl ViewProof {
+    /// Creates a proof about the value of a register, serialized with BCS.
+    pub fn register(value: Vec<u8>) -> Self {
+        ViewProof {
+            leaf: ProofLeaf::Register(value),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Creates a proof about `key` from all the entries of a map, ordered by key.
+    pub fn entry(key: Vec<u8>, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        let mut value = None;
+        let others = entries
+            .into_iter()
+            .filter_map(|(entry_key, entry_value)| {
+                if entry_key == key {
+                    value = Some(entry_value);
+                    None
+                } else {
+                    Some((entry_key, entry_value))
+                }
+            })
+            .collect();
+        ViewProof {
+            leaf: ProofLeaf::Entry { key, value, others },
+            steps: Vec::new(),
+        }
+    }
+
+    /// Extends the proof to the parent struct of the view, given the hashes of all the
+    /// fields of that struct.
+    pub fn in_field(mut self, index: usize, field_hashes: Vec<HasherOutput>) -> Self {
+        let others = field_hashes
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| *position != index)
+            .map(|(_, hash)| hash.into())
+            .collect();
+        self.steps.push(ProofStep::Field {
+            index: index as u32,
+            others,
+        });
+        self
+    }
+
+    /// Extends the proof to the parent collection of the view, given the hashes of all the
+    /// entries of that collection, ordered by key.
+    pub fn in_collection_entry(
+        mut self,
+        key: Vec<u8>,
+        entry_hashes: Vec<(Vec<u8>, HasherOutput)>,
+    ) -> Self {
+        let others = entry_hashes
+            .into_iter()
+            .filter(|(entry_key, _)| *entry_key != key)
+            .map(|(entry_key, hash)| (entry_key, hash.into()))
+            .collect();
+        self.steps.push(ProofStep::CollectionEntry { key, others });
+        self
+    }
+
+    /// Returns the location of the proven value within the enclosing view.
+    pub fn path(&self) -> ProofPath {
+        let key = match &self.leaf {
+            ProofLeaf::Register(_) => None,
+            ProofLeaf::Entry { key, .. } => Some(key.clone()),
+        };
+        let steps = self
+            .steps
+            .iter()
+            .rev()
+            .map(|step| match step {
+                ProofStep::Field { index, .. } => PathStep::Field(*index),
+                ProofStep::CollectionEntry { key, .. } => PathStep::CollectionEntry(key.clone()),
+            })
+            .collect();
+        ProofPath { steps, key }
+    }
+
+    /// Returns the serialized proven value, or `None` for a proof of absence.
+    pub fn value_bytes(&self) -> Option<&[u8]> {
+        match &self.leaf {
+            ProofLeaf::Register(value) => Some(value),
+            ProofLeaf::Entry { value, .. } => value.as_deref(),
+        }
+    }
+
+    /// Deserializes the proven value, or returns `None` for a proof of absence.
+    pub fn value<T: DeserializeOwned>(&self) -> Result<Option<T>, ProofError> {
+        Ok(self.value_bytes().map(bcs::from_bytes).transpose()?)
+    }
+
+    /// Computes the hash of the enclosing view that the proof leads to.
+    pub fn root_hash(&self) -> Result<HasherOutput, ProofError> {
+        let mut hash = self.leaf.hash()?;
+        for step in &self.steps {
+            hash = step.hash(hash)?;
+        }
+        Ok(hash)
+    }
+
+    /// Checks that the proof is about the value at `path`, and leads to the given hash of
+    /// the enclosing view.
+    pub fn verify(&self, path: &ProofPath, root_hash: &HasherOutput) -> Result<(), ProofError> {
+        self.check_path(path)?;
+        if self.root_hash()? != *root_hash {
+            return Err(ProofError::HashMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks that the proof is about the value at `path`, and leads to the given
+    /// [`CryptoHash`] of the enclosing view, as computed by a view deriving `CryptoHashView`
+    /// or `CryptoHashRootView`. The `type_name` is the name of that view, followed by
+    /// `Hash`, e.g. `ExecutionStateViewHash`.
+    pub fn verify_crypto_hash(
+        &self,
+        path: &ProofPath,
+        type_name: &str,
+        crypto_hash: &CryptoHash,
+    ) -> Result<(), ProofError> {
+        self.check_path(path)?;
+        let root_hash = self.root_hash()?;
+        let mut hasher = Keccak256::new();
+        hasher.update(format!("{type_name}::"));
+        hasher.update(root_hash);
+        if hasher.finalize().as_slice() != crypto_hash.as_bytes().as_slice() {
+            return Err(ProofError::HashMismatch);
+        }
+        Ok(())
+    }
+
+    fn check_path(&self, expected: &ProofPath) -> Result<(), ProofError> {
+        let actual = self.path();
+        if actual != *expected {
+            return Err(ProofError::PathMismatch {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl ProofLeaf {
+    /// Computes the hash of the view, as `HashableView::hash` does.
+    fn hash(&self) -> Result<HasherOutput, ProofError> {
+        let mut hasher = Sha3_256::new();
+        match self {
+            ProofLeaf::Register(value) => hasher.update(value),
+            ProofLeaf::Entry { key, value, others } => {
+                let entries = insert_entry(key, value.as_ref(), others)?;
+                let count = u32::try_from(entries.len()).map_err(|_| ProofError::TooManyEntries)?;
+                for (key, value) in entries {
+                    hasher.update(key);
+                    hasher.update(value);
+                }
+                hasher.update(bcs::to_bytes(&count)?);
+            }
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+impl ProofStep {
+    /// Computes the hash of the parent view, given the hash of the view.
+    fn hash(&self, hash: HasherOutput) -> Result<HasherOutput, ProofError> {
+        let mut hasher = Sha3_256::new();
+        match self {
+            ProofStep::Field { index, others } => {
+                let position = *index as usize;
+                if position > others.len() {
+                    return Err(ProofError::InvalidFieldIndex(*index));
+                }
+                for other in &others[..position] {
+                    hasher.update(other);
+                }
+                hasher.update(hash);
+                for other in &others[position..] {
+                    hasher.update(other);
+                }
+            }
+            ProofStep::CollectionEntry { key, others } => {
+                let hash: [u8; 32] = hash.into();
+                let entries = insert_entry(key, Some(&hash), others)?;
+                let count = u32::try_from(entries.len()).map_err(|_| ProofError::TooManyEntries)?;
+                hasher.update(bcs::to_bytes(&count)?);
+                for (key, hash) in entries {
+                    hasher.update(key);
+                    hasher.update(hash);
+                }
+            }
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+/// Merges the proven entry, if present, into the other entries, after checking that they
+/// are strictly ordered by key.
+fn insert_entry<'a, T>(
+    key: &'a [u8],
+    value: Option<&'a T>,
+    others: &'a [(Vec<u8>, T)],
+) -> Result<Vec<(&'a [u8], &'a T)>, ProofError> {
+    if others.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+        return Err(ProofError::UnorderedEntries);
+    }
+    let position = match others.binary_search_by(|(other, _)| other.as_slice().cmp(key)) {
+        Ok(_) => return Err(ProofError::DuplicateKey),
+        Err(position) => position,
+    };
+    let mut entries = others
+        .iter()
+        .map(|(key, value)| (key.as_slice(), value))
+        .collect::<Vec<_>>();
+    if let Some(value) = value {
+        entries.insert(position, (key, value));
+    }
+    Ok(entries)
+}
@@ -0,0 +1,292 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Schema versions of root views, and the migrations of their stored data.
+//!
+//! A root view deriving `RootView` or `CryptoHashRootView` with a `#[view(version = "N")]`
+//! attribute stores its schema version next to its fields, every time it is saved. When the
+//! view is loaded from data with an older version, the migrations registered for the
+//! intermediate versions are applied in order:
+//! * an eager migration rewrites the stored key-value pairs, and is written to storage
+//!   immediately, before the view is loaded;
+//! * a lazy migration modifies the loaded view in memory, and is only written to storage
+//!   with the next save of the view.
+//!
+//! The migrations are registered with a `#[view(migrations = "path::to::function")]`
+//! attribute, naming a function that returns the [`SchemaMigrations`] of the view. Data
+//! stored before a version was attached to the view has version 0.
+//!
+//! ```rust
+//! # tokio_test::block_on(async {
+//! use linera_views::{
+//!     batch::Batch,
+//!     context::{Context, MemoryContext},
+//!     register_view::RegisterView,
+//!     schema::{self, SchemaMigrations},
+//!     views::{RootView, View},
+//! };
+//!
+//! #[derive(RootView)]
+//! #[view(version = "1")]
+//! #[view(migrations = "Counter::migrations")]
+//! struct Counter<C> {
+//!     value: RegisterView<C, u64>,
+//! }
+//!
+//! impl<C> Counter<C> {
+//!     fn migrations() -> SchemaMigrations<Self> {
+//!         SchemaMigrations::new().eager(0, |key_values, batch| {
+//!             // Version 0 stored the counter as a `u32`.
+//!             for (key, value) in key_values {
+//!                 let value = bcs::from_bytes::<u32>(value)?;
+//!                 batch.put_key_value(key.clone(), &u64::from(value))?;
+//!             }
+//!             Ok(())
+//!         })
+//!     }
+//! }
+//!
+//! let context = MemoryContext::new_for_testing(());
+//! let mut batch = Batch::new();
+//! let key = [context.base_key(), schema::field_key_prefix(0)].concat();
+//! batch.put_key_value(key, &7u32).unwrap();
+//! context.write_batch(batch).await.unwrap();
+//! let counter = Counter::load(context).await.unwrap();
+//! assert_eq!(*counter.value.get(), 7);
+//! # })
+//! ```
+
+use std::collections::BTreeMap;
+
+use futures::future::BoxFuture;
+
+use crate::{
+    batch::{Batch, WriteOperation},
+    context::Context,
+    store::{KeyIterable as _, KeyValueIterable as _},
+    views::{View, ViewError, MIN_VIEW_TAG},
+};
+
+/// The tag of the key storing the schema version of a view, under its base key. It is
+/// below [`MIN_VIEW_TAG`], so it never collides with the keys of the fields.
+const SCHEMA_VERSION_TAG: u8 = 0;
+
+/// A migration of the key-value pairs stored under the base key of a view, from one
+/// version to the next. The keys are relative to the base key, both in the pairs read and
+/// in the batch written.
+pub type StorageMigration = fn(&[(Vec<u8>, Vec<u8>)], &mut Batch) -> Result<(), ViewError>;
+
+/// A migration of a loaded view, from one version to the next.
+pub type ViewMigration<V> = for<'a> fn(&'a mut V) -> BoxFuture<'a, Result<(), ViewError>>;
+
+/// A registered migration.
+enum SchemaMigration<V> {
+    Eager(StorageMigration),
+    Lazy(ViewMigration<V>),
+}
+
+/// The migrations of a view from its older schema versions, indexed by the version they
+/// migrate from.
+pub struct SchemaMigrations<V> {
+    migrations: BTreeMap<u32, SchemaMigration<V>>,
+}
+
+impl<V> Default for SchemaMigrations<V> {
+    fn default() -> Self {
+        Self {
+            migrations: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> SchemaMigrations<V> {
+    /// Creates an empty set of migrations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the migration from `version` to `version + 1` of the stored data, which
+    /// is written to storage when the view is loaded.
+    pub fn eager(mut self, version: u32, migration: StorageMigration) -> Self {
+        self.migrations
+            .insert(version, SchemaMigration::Eager(migration));
+        self
+    }
+
+    /// Registers the migration from `version` to `version + 1` of the loaded view, which
+    /// is written to storage with the next save of the view.
+    pub fn lazy(mut self, version: u32, migration: ViewMigration<V>) -> Self {
+        self.migrations
+            .insert(version, SchemaMigration::Lazy(migration));
+        self
+    }
+}
+
+/// A view with a schema version. This is implemented by deriving `RootView` or
+/// `CryptoHashRootView` with a `#[view(version = "N")]` attribute.
+pub trait VersionedView<C>: View<C> {
+    /// The current schema version of the view.
+    const SCHEMA_VERSION: u32;
+
+    /// Returns the migrations from the older schema versions.
+    fn schema_migrations() -> SchemaMigrations<Self>;
+}
+
+/// Returns the prefix of the keys of the field at position `index` of a derived view,
+/// relative to the base key of the view.
+pub fn field_key_prefix(index: usize) -> Vec<u8> {
+    let mut key = vec![MIN_VIEW_TAG];
+    // The derive macros serialize the position as an `i32`.
+    key.extend_from_slice(&(index as i32).to_le_bytes());
+    key
+}
+
+/// Reads the schema version stored for the view at the base key of `context`. Returns
+/// `None` if nothing is stored there.
+pub async fn read_schema_version<C>(context: &C) -> Result<Option<u32>, ViewError>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+{
+    let key = context.base_tag(SCHEMA_VERSION_TAG);
+    let value = context.read_value_bytes(&key).await?;
+    stored_version(context, value, &[]).await
+}
+
+/// Adds the current schema version of the view at the base key of `context` to a batch
+/// that saves it.
+pub fn write_schema_version<C: Context>(
+    context: &C,
+    version: u32,
+    batch: &mut Batch,
+) -> Result<(), ViewError> {
+    batch.put_key_value(context.base_tag(SCHEMA_VERSION_TAG), &version)?;
+    Ok(())
+}
+
+/// Loads a versioned view, after applying the migrations needed by the stored data.
+pub async fn load_versioned<C, V>(context: C) -> Result<V, ViewError>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    V: VersionedView<C> + Send,
+{
+    let mut keys = vec![context.base_tag(SCHEMA_VERSION_TAG)];
+    keys.extend(V::pre_load(&context)?);
+    let mut values = context.read_multi_values_bytes(keys).await?;
+    let stored = stored_version(&context, values.remove(0), &values)
+        .await?
+        .unwrap_or(V::SCHEMA_VERSION);
+    if stored == V::SCHEMA_VERSION {
+        return V::post_load(context, &values);
+    }
+    let unsupported = ViewError::UnsupportedSchemaVersion {
+        stored,
+        current: V::SCHEMA_VERSION,
+    };
+    if stored > V::SCHEMA_VERSION {
+        return Err(unsupported);
+    }
+    let migrations = V::schema_migrations();
+    let mut view = None;
+    for version in stored..V::SCHEMA_VERSION {
+        match migrations.migrations.get(&version) {
+            None => return Err(unsupported),
+            Some(SchemaMigration::Eager(migration)) => {
+                let mut batch = Batch::new();
+                if let Some(mut view) = view.take() {
+                    // Lazy migrations must be written before the stored data is migrated.
+                    V::flush(&mut view, &mut batch)?;
+                    write_schema_version(&context, version, &mut batch)?;
+                    context.write_batch(batch).await?;
+                    batch = Batch::new();
+                }
+                migrate_storage(&context, *migration, &mut batch).await?;
+                write_schema_version(&context, version + 1, &mut batch)?;
+                context.write_batch(batch).await?;
+            }
+            Some(SchemaMigration::Lazy(migration)) => {
+                if view.is_none() {
+                    view = Some(load_unversioned::<C, V>(context.clone()).await?);
+                }
+                migration(view.as_mut().expect("the view was just loaded")).await?;
+            }
+        }
+    }
+    match view {
+        Some(view) => Ok(view),
+        None => load_unversioned(context).await,
+    }
+}
+
+/// Returns the schema version stored in `value`. If there is none, data stored under the
+/// base key predates versioning, i.e. has version 0.
+///
+/// The `preloaded` values of the view's initial keys, if already read, are enough to find
+/// such data in most cases. Otherwise, the keys under the base key are listed: this only
+/// happens if the view looks empty.
+async fn stored_version<C>(
+    context: &C,
+    value: Option<Vec<u8>>,
+    preloaded: &[Option<Vec<u8>>],
+) -> Result<Option<u32>, ViewError>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+{
+    if let Some(bytes) = value {
+        return Ok(Some(bcs::from_bytes(&bytes)?));
+    }
+    if preloaded.iter().any(Option::is_some) {
+        return Ok(Some(0));
+    }
+    let keys = context.find_keys_by_prefix(&context.base_key()).await?;
+    Ok(keys.iterator().next().is_some().then_some(0))
+}
+
+/// Loads a view without looking at its schema version.
+async fn load_unversioned<C, V>(context: C) -> Result<V, ViewError>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+    V: View<C>,
+{
+    let keys = V::pre_load(&context)?;
+    let values = context.read_multi_values_bytes(keys).await?;
+    V::post_load(context, &values)
+}
+
+/// Adds to `batch` the migration of the stored data under the base key of `context`.
+async fn migrate_storage<C>(
+    context: &C,
+    migration: StorageMigration,
+    batch: &mut Batch,
+) -> Result<(), ViewError>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+{
+    let base_key = context.base_key();
+    let key_values = context
+        .find_key_values_by_prefix(&base_key)
+        .await?
+        .into_iterator_owned()
+        .filter(|entry| !matches!(entry, Ok((key, _)) if key == &[SCHEMA_VERSION_TAG]))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut relative_batch = Batch::new();
+    migration(&key_values, &mut relative_batch)?;
+    let prefixed = |key: Vec<u8>| [base_key.as_slice(), &key].concat();
+    for operation in relative_batch.operations {
+        batch.operations.push(match operation {
+            WriteOperation::Delete { key } => WriteOperation::Delete { key: prefixed(key) },
+            WriteOperation::DeletePrefix { key_prefix } => WriteOperation::DeletePrefix {
+                key_prefix: prefixed(key_prefix),
+            },
+            WriteOperation::Put { key, value } => WriteOperation::Put {
+                key: prefixed(key),
+                value,
+            },
+        });
+    }
+    Ok(())
+}
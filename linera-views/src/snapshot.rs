@@ -0,0 +1,399 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshots of a namespace in a portable archive, which can be restored into any
+//! [`KeyValueStore`].
+//!
+//! An archive starts with [`SNAPSHOT_MAGIC`], followed by a sequence of BCS-encoded
+//! records, each prefixed by its length as a little-endian `u32`:
+//! * a [`SnapshotHeader`],
+//! * for every root key of the namespace, the root key followed by its key-value pairs, in
+//!   chunks,
+//! * a [`SnapshotFooter`] with the number of entries and the SHA3-256 checksum of all the
+//!   preceding bytes.
+//!
+//! In stores where root keys are key prefixes, the key-value pairs of a root key are also
+//! found under the shorter root keys it extends: they are only recorded once, under the
+//! longest root key.
+//!
+//! The key-value stores offer no isolation between reads and writes, so a snapshot is only
+//! consistent if nothing writes to the namespace while it is taken.
+
+use std::io::{self, Read, Write};
+
+use linera_base::data_types::Timestamp;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest as _, Sha3_256};
+use thiserror::Error;
+
+use crate::{
+    batch::Batch,
+    store::{KeyIterable as _, KeyValueStore},
+    views::ViewError,
+};
+
+/// The bytes every snapshot archive starts with.
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"LNRSNAP\0";
+
+/// The version of the archive format produced by this module.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// The maximal number of key-value pairs in a chunk of the archive. Each chunk is written
+/// in a single batch when restoring.
+const MAX_CHUNK_ENTRIES: usize = 1000;
+
+/// The size in bytes above which a chunk of the archive is closed.
+const MAX_CHUNK_BYTES: usize = 4 << 20;
+
+/// An error while taking or restoring a snapshot.
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    /// An error of the key-value store.
+    #[error(transparent)]
+    View(#[from] ViewError),
+
+    /// An I/O error on the archive.
+    #[error("I/O error on the snapshot archive: {0}")]
+    Io(#[from] io::Error),
+
+    /// The archive could not be encoded or decoded.
+    #[error("invalid snapshot record: {0}")]
+    Bcs(#[from] bcs::Error),
+
+    /// The data is not a snapshot archive.
+    #[error("not a snapshot archive")]
+    NotASnapshot,
+
+    /// The archive was produced by an incompatible version.
+    #[error("unsupported snapshot version: {0}")]
+    UnsupportedVersion(u32),
+
+    /// The records of the archive are not in the expected order.
+    #[error("malformed snapshot archive: {0}")]
+    Malformed(&'static str),
+
+    /// The archive is corrupted.
+    #[error("snapshot checksum mismatch: the archive is corrupted")]
+    ChecksumMismatch,
+
+    /// The namespace to restore into already exists.
+    #[error("namespace {0} already exists")]
+    NamespaceExists(String),
+}
+
+/// The metadata at the start of a snapshot archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotHeader {
+    /// The version of the archive format.
+    pub version: u32,
+    /// The namespace the snapshot was taken from.
+    pub namespace: String,
+    /// The name of the store the snapshot was taken from.
+    pub backend: String,
+    /// When the snapshot was started.
+    pub timestamp: Timestamp,
+}
+
+/// The summary at the end of a snapshot archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotFooter {
+    /// The number of root keys in the snapshot.
+    pub root_keys: u64,
+    /// The total number of key-value pairs in the snapshot.
+    pub key_values: u64,
+    /// The SHA3-256 checksum of the archive up to this footer.
+    pub checksum: [u8; 32],
+}
+
+/// A record of a snapshot archive.
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Header(SnapshotHeader),
+    RootKey(Vec<u8>),
+    KeyValues(Vec<(Vec<u8>, Vec<u8>)>),
+    Footer(SnapshotFooter),
+}
+
+/// Writes length-prefixed records, keeping track of the checksum.
+struct ArchiveWriter<W> {
+    writer: W,
+    hasher: Sha3_256,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn new(mut writer: W) -> Result<Self, SnapshotError> {
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        let mut hasher = Sha3_256::new();
+        hasher.update(SNAPSHOT_MAGIC);
+        Ok(Self { writer, hasher })
+    }
+
+    fn write_record(&mut self, record: &Record) -> Result<(), SnapshotError> {
+        let bytes = bcs::to_bytes(record)?;
+        let length = u32::try_from(bytes.len())
+            .map_err(|_| SnapshotError::Malformed("record too large"))?
+            .to_le_bytes();
+        self.hasher.update(length);
+        self.hasher.update(&bytes);
+        self.writer.write_all(&length)?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    fn checksum(&self) -> [u8; 32] {
+        self.hasher.clone().finalize().into()
+    }
+}
+
+/// Reads length-prefixed records, keeping track of the checksum.
+struct ArchiveReader<R> {
+    reader: R,
+    hasher: Sha3_256,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    fn new(mut reader: R) -> Result<Self, SnapshotError> {
+        let mut magic = [0u8; SNAPSHOT_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| SnapshotError::NotASnapshot)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let mut hasher = Sha3_256::new();
+        hasher.update(magic);
+        Ok(Self { reader, hasher })
+    }
+
+    /// Reads the next record, and returns it with the checksum of the archive before it.
+    fn read_record(&mut self) -> Result<(Record, [u8; 32]), SnapshotError> {
+        let checksum = self.hasher.clone().finalize().into();
+        let mut length = [0u8; 4];
+        self.reader.read_exact(&mut length)?;
+        let expected = u32::from_le_bytes(length) as u64;
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(expected).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != expected {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        self.hasher.update(length);
+        self.hasher.update(&bytes);
+        Ok((bcs::from_bytes(&bytes)?, checksum))
+    }
+}
+
+/// Writes a snapshot of the given `namespace` into `writer`, and returns its footer.
+pub async fn take_snapshot<S, W>(
+    config: &S::Config,
+    namespace: &str,
+    writer: W,
+) -> Result<SnapshotFooter, SnapshotError>
+where
+    S: KeyValueStore,
+    W: Write + Send,
+{
+    let mut archive = ArchiveWriter::new(writer)?;
+    archive.write_record(&Record::Header(SnapshotHeader {
+        version: SNAPSHOT_VERSION,
+        namespace: namespace.to_string(),
+        backend: S::get_name(),
+        timestamp: Timestamp::now(),
+    }))?;
+    let store = S::connect(config, namespace)
+        .await
+        .map_err(ViewError::from)?;
+    let mut root_keys = S::list_root_keys(config, namespace)
+        .await
+        .map_err(ViewError::from)?;
+    root_keys.sort();
+    root_keys.dedup();
+    let mut key_values = 0;
+    for (index, root_key) in root_keys.iter().enumerate() {
+        archive.write_record(&Record::RootKey(root_key.clone()))?;
+        let store = store
+            .clone_with_root_key(root_key)
+            .map_err(ViewError::from)?;
+        let nested_suffixes = nested_root_key_suffixes::<S>(&root_keys, index);
+        // Only the keys are loaded at once: the values are read one chunk at a time.
+        let keys = list_keys_outside(&store, &nested_suffixes).await?;
+        for keys in keys.chunks(MAX_CHUNK_ENTRIES) {
+            let values = store
+                .read_multi_values_bytes(keys.to_vec())
+                .await
+                .map_err(ViewError::from)?;
+            let mut chunk = Vec::new();
+            let mut chunk_bytes = 0;
+            for (key, value) in keys.iter().zip(values) {
+                // The key was deleted after it was listed.
+                let Some(value) = value else {
+                    continue;
+                };
+                chunk_bytes += key.len() + value.len();
+                chunk.push((key.clone(), value));
+                key_values += 1;
+                if chunk_bytes >= MAX_CHUNK_BYTES {
+                    archive.write_record(&Record::KeyValues(std::mem::take(&mut chunk)))?;
+                    chunk_bytes = 0;
+                }
+            }
+            if !chunk.is_empty() {
+                archive.write_record(&Record::KeyValues(chunk))?;
+            }
+        }
+    }
+    let footer = SnapshotFooter {
+        root_keys: root_keys.len() as u64,
+        key_values,
+        checksum: archive.checksum(),
+    };
+    archive.write_record(&Record::Footer(footer.clone()))?;
+    archive.writer.flush()?;
+    Ok(footer)
+}
+
+/// Returns the suffixes by which the root keys nested in `sorted_root_keys[index]` extend
+/// it, if root keys are key prefixes in `S`. The keys starting with one of them belong to
+/// a nested root key, and are read from there instead.
+///
+/// Only the shortest suffixes are kept, so that none of them is a prefix of another.
+pub(crate) fn nested_root_key_suffixes<S: KeyValueStore>(
+    sorted_root_keys: &[Vec<u8>],
+    index: usize,
+) -> Vec<Vec<u8>> {
+    let mut suffixes = Vec::<Vec<u8>>::new();
+    if !S::ROOT_KEYS_ARE_PREFIXES {
+        return suffixes;
+    }
+    let root_key = &sorted_root_keys[index];
+    for nested in sorted_root_keys[index + 1..]
+        .iter()
+        .take_while(|nested| nested.starts_with(root_key))
+    {
+        let suffix = &nested[root_key.len()..];
+        if suffixes
+            .last()
+            .is_none_or(|shorter| !suffix.starts_with(shorter))
+        {
+            suffixes.push(suffix.to_vec());
+        }
+    }
+    suffixes
+}
+
+/// Lists the keys of `store`, except those starting with one of the sorted
+/// `nested_suffixes`, none of which is a prefix of another.
+pub(crate) async fn list_keys_outside<S: KeyValueStore>(
+    store: &S,
+    nested_suffixes: &[Vec<u8>],
+) -> Result<Vec<Vec<u8>>, ViewError> {
+    let keys = store
+        .find_keys_by_prefix(&[])
+        .await
+        .map_err(ViewError::from)?;
+    let mut own_keys = Vec::new();
+    for key in keys.iterator() {
+        let key = key.map_err(ViewError::from)?;
+        // Only the greatest suffix not above the key can be a prefix of it.
+        let index = nested_suffixes.partition_point(|suffix| suffix.as_slice() <= key);
+        if index > 0 && key.starts_with(&nested_suffixes[index - 1]) {
+            continue;
+        }
+        own_keys.push(key.to_vec());
+    }
+    Ok(own_keys)
+}
+
+/// Restores the snapshot read from `reader` into a new `namespace`, and returns the header
+/// and footer of the archive.
+///
+/// The namespace must not exist yet. If the archive turns out to be corrupted, the
+/// namespace is deleted again.
+pub async fn restore_snapshot<S, R>(
+    config: &S::Config,
+    namespace: &str,
+    reader: R,
+) -> Result<(SnapshotHeader, SnapshotFooter), SnapshotError>
+where
+    S: KeyValueStore,
+    R: Read + Send,
+{
+    let mut archive = ArchiveReader::new(reader)?;
+    let header = match archive.read_record()? {
+        (Record::Header(header), _) => header,
+        _ => return Err(SnapshotError::Malformed("missing header")),
+    };
+    if header.version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(header.version));
+    }
+    if S::exists(config, namespace)
+        .await
+        .map_err(ViewError::from)?
+    {
+        return Err(SnapshotError::NamespaceExists(namespace.to_string()));
+    }
+    S::create(config, namespace)
+        .await
+        .map_err(ViewError::from)?;
+    match restore_records::<S, R>(config, namespace, &mut archive).await {
+        Ok(footer) => Ok((header, footer)),
+        Err(error) => {
+            if let Err(delete_error) = S::delete(config, namespace).await {
+                tracing::warn!(
+                    "Failed to delete namespace {namespace} after a failed restore: \
+                    {delete_error}"
+                );
+            }
+            Err(error)
+        }
+    }
+}
+
+async fn restore_records<S, R>(
+    config: &S::Config,
+    namespace: &str,
+    archive: &mut ArchiveReader<R>,
+) -> Result<SnapshotFooter, SnapshotError>
+where
+    S: KeyValueStore,
+    R: Read + Send,
+{
+    let base_store = S::connect(config, namespace)
+        .await
+        .map_err(ViewError::from)?;
+    let mut store = None;
+    let mut root_keys = 0;
+    let mut key_values = 0;
+    loop {
+        match archive.read_record()? {
+            (Record::RootKey(root_key), _) => {
+                store = Some(
+                    base_store
+                        .clone_with_root_key(&root_key)
+                        .map_err(ViewError::from)?,
+                );
+                root_keys += 1;
+            }
+            (Record::KeyValues(chunk), _) => {
+                let store = store.as_ref().ok_or(SnapshotError::Malformed(
+                    "key-value pairs without a root key",
+                ))?;
+                key_values += chunk.len() as u64;
+                let mut batch = Batch::new();
+                for (key, value) in chunk {
+                    batch.put_key_value_bytes(key, value);
+                }
+                store.write_batch(batch).await.map_err(ViewError::from)?;
+            }
+            (Record::Footer(footer), checksum) => {
+                if footer.checksum != checksum {
+                    return Err(SnapshotError::ChecksumMismatch);
+                }
+                if footer.root_keys != root_keys || footer.key_values != key_values {
+                    return Err(SnapshotError::Malformed("wrong number of entries"));
+                }
+                return Ok(footer);
+            }
+            (Record::Header(_), _) => return Err(SnapshotError::Malformed("duplicate header")),
+        }
+    }
+}
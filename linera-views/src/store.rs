@@ -168,6 +168,10 @@ pub trait LocalWritableKeyValueStore: WithError {
 pub trait LocalAdminKeyValueStore: WithError + Sized {
     /// The configuration needed to interact with a new store.
     type Config: Send + Sync;
+    /// Whether root keys are prefixes of the keys in the underlying storage. In that case,
+    /// the keys of a root key are also found under every root key that is a prefix of it,
+    /// e.g. all keys are found under the empty root key.
+    const ROOT_KEYS_ARE_PREFIXES: bool;
     /// The name of this class of stores
     fn get_name() -> String;
 
@@ -12,15 +12,21 @@ use std::collections::{BTreeMap, BTreeSet, HashSet};
 use rand::{seq::SliceRandom, Rng};
 
 use crate::{
+    backends::dual::{DualStore, MirroringAssignment},
     batch::{
         Batch, WriteOperation,
         WriteOperation::{Delete, Put},
     },
+    context::Context,
+    migration::{migrate_namespace, MigrationError, MigrationOptions, MigrationProgress},
     random::{generate_test_namespace, make_deterministic_rng, make_nondeterministic_rng},
+    schema::{read_schema_version, VersionedView},
+    snapshot::{restore_snapshot, take_snapshot, SnapshotError},
     store::{
         KeyIterable, KeyValueIterable, LocalKeyValueStore, LocalRestrictedKeyValueStore,
         TestKeyValueStore,
     },
+    views::{RootView, ViewError},
 };
 
 /// Returns a random key prefix used for tests
@@ -862,3 +868,378 @@ pub async fn access_admin_test<S: TestKeyValueStore>() {
     exclusive_access_admin_test::<S>(true).await;
     exclusive_access_admin_test::<S>(false).await;
 }
+
+/// Reads all the key-value pairs of a namespace, for every root key. If root keys are key
+/// prefixes in `S`, each pair is only reported under the longest root key it is found under.
+async fn read_namespace<S: TestKeyValueStore>(
+    config: &S::Config,
+    namespace: &str,
+) -> BTreeMap<(Vec<u8>, Vec<u8>), Vec<u8>> {
+    let store = S::connect(config, namespace).await.expect("store");
+    let mut root_keys = S::list_root_keys(config, namespace)
+        .await
+        .expect("root keys");
+    root_keys.sort();
+    root_keys.dedup();
+    // Nested root keys come after the ones they extend, and replace their entries.
+    let mut key_values = BTreeMap::new();
+    for root_key in root_keys {
+        let store = store.clone_with_root_key(&root_key).expect("store");
+        let entries = store
+            .find_key_values_by_prefix(&[])
+            .await
+            .expect("key values");
+        for entry in entries.iterator() {
+            let (key, value) = entry.expect("key value");
+            let full_key = if S::ROOT_KEYS_ARE_PREFIXES {
+                [root_key.as_slice(), key].concat()
+            } else {
+                bcs::to_bytes(&(&root_key, key)).expect("full key")
+            };
+            key_values.insert(full_key, ((root_key.clone(), key.to_vec()), value.to_vec()));
+        }
+    }
+    key_values.into_values().collect()
+}
+
+/// Writes random key-value pairs under a few root keys of a namespace, including the
+/// empty root key and a root key extending another one.
+async fn write_random_root_keys<S: TestKeyValueStore>(config: &S::Config, namespace: &str) {
+    let mut rng = make_deterministic_rng();
+    let store = S::connect(config, namespace).await.expect("store");
+    let mut root_key = Vec::new();
+    for i in 0..5 {
+        root_key = match i {
+            0 => Vec::new(),
+            4 => get_random_byte_vector(&mut rng, &root_key, 2),
+            _ => get_random_byte_vector(&mut rng, &[], 4),
+        };
+        let store = store.clone_with_root_key(&root_key).expect("cloned store");
+        let mut batch = Batch::new();
+        for (key, value) in get_random_key_values(&mut rng, 10 * (i + 1)) {
+            batch.put_key_value_bytes(key, value);
+        }
+        store.write_batch(batch).await.expect("write batch");
+    }
+}
+
+/// Tests that a snapshot of a namespace restores the same key-value pairs, and that
+/// corrupted snapshots are rejected.
+pub async fn snapshot_admin_test<S: TestKeyValueStore>() {
+    let config = S::new_test_config().await.expect("config");
+    let namespace = generate_test_namespace();
+    S::create(&config, &namespace).await.expect("creation");
+    write_random_root_keys::<S>(&config, &namespace).await;
+    let key_values = read_namespace::<S>(&config, &namespace).await;
+
+    let mut archive = Vec::new();
+    let footer = take_snapshot::<S, _>(&config, &namespace, &mut archive)
+        .await
+        .expect("snapshot");
+    assert_eq!(footer.key_values, key_values.len() as u64);
+
+    let restored_namespace = generate_test_namespace();
+    let (header, restored_footer) =
+        restore_snapshot::<S, _>(&config, &restored_namespace, archive.as_slice())
+            .await
+            .expect("restore");
+    assert_eq!(header.namespace, namespace);
+    assert_eq!(restored_footer, footer);
+    assert_eq!(
+        read_namespace::<S>(&config, &restored_namespace).await,
+        key_values
+    );
+    assert!(matches!(
+        restore_snapshot::<S, _>(&config, &restored_namespace, archive.as_slice()).await,
+        Err(SnapshotError::NamespaceExists(_))
+    ));
+
+    let corrupted_namespace = generate_test_namespace();
+    let last = archive.len() - 1;
+    archive[last] ^= 1;
+    assert!(
+        restore_snapshot::<S, _>(&config, &corrupted_namespace, archive.as_slice())
+            .await
+            .is_err()
+    );
+    assert!(!S::exists(&config, &corrupted_namespace)
+        .await
+        .expect("exists"));
+}
+
+/// Tests that a snapshot taken in a store of type `S1` restores the same key-value pairs
+/// in a store of type `S2`.
+pub async fn snapshot_between_stores_test<S1: TestKeyValueStore, S2: TestKeyValueStore>() {
+    let source_config = S1::new_test_config().await.expect("config");
+    let namespace = generate_test_namespace();
+    S1::create(&source_config, &namespace)
+        .await
+        .expect("creation");
+    write_random_root_keys::<S1>(&source_config, &namespace).await;
+    let key_values = read_namespace::<S1>(&source_config, &namespace).await;
+
+    let mut archive = Vec::new();
+    let footer = take_snapshot::<S1, _>(&source_config, &namespace, &mut archive)
+        .await
+        .expect("snapshot");
+    assert_eq!(footer.key_values, key_values.len() as u64);
+
+    let target_config = S2::new_test_config().await.expect("config");
+    let restored_namespace = generate_test_namespace();
+    let (header, restored_footer) =
+        restore_snapshot::<S2, _>(&target_config, &restored_namespace, archive.as_slice())
+            .await
+            .expect("restore");
+    assert_eq!(header.backend, S1::get_name());
+    assert_eq!(restored_footer, footer);
+    assert_eq!(
+        read_namespace::<S2>(&target_config, &restored_namespace).await,
+        key_values
+    );
+}
+
+/// Tests that a migration copies every root key, and that an interrupted migration is
+/// resumed without copying the completed root keys again.
+pub async fn migration_admin_test<S: TestKeyValueStore>() {
+    let config = S::new_test_config().await.expect("config");
+    let namespace = generate_test_namespace();
+    S::create(&config, &namespace).await.expect("creation");
+    write_random_root_keys::<S>(&config, &namespace).await;
+    let key_values = read_namespace::<S>(&config, &namespace).await;
+    let root_keys = key_values
+        .keys()
+        .map(|(root_key, _)| root_key.clone())
+        .collect::<BTreeSet<_>>();
+    let options = MigrationOptions {
+        batch_size: 7,
+        ..MigrationOptions::default()
+    };
+
+    // Interrupt the migration when saving the progress for the third time.
+    let target_namespace = generate_test_namespace();
+    let mut progress = MigrationProgress::default();
+    let mut saves = 0;
+    let result = migrate_namespace::<S, S, _>(
+        &config,
+        &namespace,
+        &config,
+        &target_namespace,
+        &options,
+        &mut progress,
+        |_| {
+            saves += 1;
+            if saves == 3 {
+                return Err(std::io::Error::other("interrupted"));
+            }
+            Ok(())
+        },
+    )
+    .await;
+    assert!(matches!(result, Err(MigrationError::Progress(_))));
+    assert_eq!(progress.completed.len(), 3);
+    assert!(progress
+        .completed
+        .iter()
+        .all(|migrated| migrated.hash.is_some()));
+
+    // Leave a stale entry under a root key that was not copied yet.
+    let pending = root_keys
+        .iter()
+        .find(|root_key| {
+            progress
+                .completed
+                .iter()
+                .all(|migrated| &migrated.root_key != *root_key)
+        })
+        .expect("pending root key");
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![255; 3], vec![1]);
+    S::connect(&config, &target_namespace)
+        .await
+        .expect("store")
+        .clone_with_root_key(pending)
+        .expect("cloned store")
+        .write_batch(batch)
+        .await
+        .expect("write batch");
+
+    let summary = migrate_namespace::<S, S, _>(
+        &config,
+        &namespace,
+        &config,
+        &target_namespace,
+        &options,
+        &mut progress,
+        |_| Ok(()),
+    )
+    .await
+    .expect("migration");
+    assert_eq!(summary.root_keys, 2);
+    assert_eq!(summary.skipped_root_keys, 3);
+    assert_eq!(progress.completed.len(), root_keys.len());
+    assert_eq!(
+        progress
+            .completed
+            .iter()
+            .map(|migrated| migrated.key_values)
+            .sum::<u64>(),
+        key_values.len() as u64
+    );
+    assert_eq!(
+        read_namespace::<S>(&config, &target_namespace).await,
+        key_values
+    );
+
+    // The progress of another migration is rejected.
+    assert!(matches!(
+        migrate_namespace::<S, S, _>(
+            &config,
+            &namespace,
+            &config,
+            &generate_test_namespace(),
+            &options,
+            &mut progress,
+            |_| Ok(()),
+        )
+        .await,
+        Err(MigrationError::ProgressMismatch { .. })
+    ));
+}
+
+/// Tests a migration from a store of type `S1` to one of type `S2` while the namespace is
+/// written to through a [`DualStore`] mirroring it into both stores: the writes to root
+/// keys copied before them are carried over too.
+pub async fn mirrored_migration_test<S1: TestKeyValueStore, S2: TestKeyValueStore>() {
+    use crate::store::{
+        AdminKeyValueStore as _, ReadableKeyValueStore as _, WritableKeyValueStore as _,
+    };
+
+    let config = DualStore::<S1, S2, MirroringAssignment>::new_test_config()
+        .await
+        .expect("config");
+    let namespace = generate_test_namespace();
+    S1::create(&config.first_config, &namespace)
+        .await
+        .expect("creation");
+    write_random_root_keys::<S1>(&config.first_config, &namespace).await;
+    let mut root_keys = S1::list_root_keys(&config.first_config, &namespace)
+        .await
+        .expect("root keys");
+    root_keys.sort();
+    root_keys.dedup();
+    let options = MigrationOptions {
+        batch_size: 7,
+        ..MigrationOptions::default()
+    };
+
+    // Interrupt the migration after copying two root keys.
+    let mut progress = MigrationProgress::default();
+    let mut saves = 0;
+    let result = migrate_namespace::<S1, S2, _>(
+        &config.first_config,
+        &namespace,
+        &config.second_config,
+        &namespace,
+        &options,
+        &mut progress,
+        |_| {
+            saves += 1;
+            if saves == 2 {
+                return Err(std::io::Error::other("interrupted"));
+            }
+            Ok(())
+        },
+    )
+    .await;
+    assert!(matches!(result, Err(MigrationError::Progress(_))));
+
+    let store = DualStore::<S1, S2, MirroringAssignment>::connect(&config, &namespace)
+        .await
+        .expect("store");
+    for root_key in &root_keys {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![254; 3], root_key.clone());
+        store
+            .clone_with_root_key(root_key)
+            .expect("cloned store")
+            .write_batch(batch)
+            .await
+            .expect("write batch");
+    }
+
+    migrate_namespace::<S1, S2, _>(
+        &config.first_config,
+        &namespace,
+        &config.second_config,
+        &namespace,
+        &options,
+        &mut progress,
+        |_| Ok(()),
+    )
+    .await
+    .expect("migration");
+    assert_eq!(
+        read_namespace::<S2>(&config.second_config, &namespace).await,
+        read_namespace::<S1>(&config.first_config, &namespace).await
+    );
+    for root_key in &root_keys {
+        let value = store
+            .clone_with_root_key(root_key)
+            .expect("cloned store")
+            .read_value_bytes(&[254; 3])
+            .await
+            .expect("read value");
+        assert_eq!(value.as_ref(), Some(root_key));
+    }
+    // The root keys present in both stores are only listed once.
+    assert_eq!(
+        DualStore::<S1, S2, MirroringAssignment>::list_root_keys(&config, &namespace)
+            .await
+            .expect("root keys"),
+        root_keys
+    );
+}
+
+/// Reads the key-value pairs stored for the view at the base key of `context`, relative to
+/// that key. This is how fixtures of the data of a root view are recorded, before changing
+/// its schema.
+pub async fn read_view_fixture<C>(context: &C) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ViewError>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+{
+    Ok(context
+        .find_key_values_by_prefix(&context.base_key())
+        .await?
+        .into_iterator_owned()
+        .collect::<Result<_, _>>()?)
+}
+
+/// Writes a fixture recorded with [`read_view_fixture`] at the base key of `context`, and
+/// checks that the root view `V` still loads from it, and again after being saved. Returns
+/// the loaded view, so that its content can be checked.
+pub async fn check_fixture_loads<C, V>(
+    context: C,
+    fixture: &[(Vec<u8>, Vec<u8>)],
+) -> Result<V, ViewError>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    V: VersionedView<C> + RootView<C> + Send,
+{
+    let base_key = context.base_key();
+    let mut batch = Batch::new();
+    for (key, value) in fixture {
+        batch.put_key_value_bytes([base_key.as_slice(), key].concat(), value.clone());
+    }
+    context.write_batch(batch).await?;
+    let mut view = V::load(context.clone()).await?;
+    view.save().await?;
+    let version = read_schema_version(&context).await?;
+    assert_eq!(
+        version,
+        Some(V::SCHEMA_VERSION),
+        "the fixture was not migrated to the current schema version"
+    );
+    V::load(context).await
+}
@@ -27,6 +27,7 @@ use crate::{
     common::{CustomSerialize, HasherOutput, Update},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    proof::ViewProof,
     store::KeyIterable,
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
@@ -656,12 +657,31 @@ where
         #[cfg(with_metrics)]
         let _hash_latency = COLLECTION_VIEW_HASH_RUNTIME.measure_latency();
         let mut hasher = sha3::Sha3_256::default();
-        let keys = self.keys().await?;
-        let count = keys.len() as u32;
+        let entry_hashes = self.entry_hashes().await?;
+        let count = entry_hashes.len() as u32;
         hasher.update_with_bcs_bytes(&count)?;
+        for (key, hash) in entry_hashes {
+            hasher.update_with_bytes(&key)?;
+            hasher.write_all(hash.as_ref())?;
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+impl<C, W> ByteCollectionView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: HashableView<C> + Send + Sync + 'static,
+{
+    /// Returns the keys of the collection, with the hashes of their entries.
+    async fn entry_hashes(
+        &self,
+    ) -> Result<Vec<(Vec<u8>, <W::Hasher as Hasher>::Output)>, ViewError> {
+        let keys = self.keys().await?;
         let updates = self.updates.read().await;
+        let mut entry_hashes = Vec::with_capacity(keys.len());
         for key in keys {
-            hasher.update_with_bytes(&key)?;
             let hash = match updates.get(&key) {
                 Some(entry) => {
                     let Update::Set(view) = entry else {
@@ -676,9 +696,43 @@ where
                     view.hash().await?
                 }
             };
-            hasher.write_all(hash.as_ref())?;
+            entry_hashes.push((key, hash));
         }
-        Ok(hasher.finalize())
+        Ok(entry_hashes)
+    }
+
+    /// Extends a proof about the subview at `short_key` to this collection.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::collection_view::ByteCollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view: ByteCollectionView<_, RegisterView<_, u32>> =
+    ///     ByteCollectionView::load(context).await.unwrap();
+    /// view.load_entry_mut(&[0, 1]).await.unwrap().set(42);
+    /// let subview = view.load_entry_or_insert(&[0, 1]).await.unwrap();
+    /// let proof = subview.proof().unwrap();
+    /// let proof = view.extend_proof(&[0, 1], proof).await.unwrap();
+    /// let path = ProofPath::register().in_collection_entry(vec![0, 1]);
+    /// proof.verify(&path, &view.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<u32>().unwrap(), Some(42));
+    /// # })
+    /// ```
+    pub async fn extend_proof(
+        &self,
+        short_key: &[u8],
+        proof: ViewProof,
+    ) -> Result<ViewProof, ViewError>
+    where
+        W: HashableView<C, Hasher = sha3::Sha3_256>,
+    {
+        let entry_hashes = self.entry_hashes().await?;
+        if !entry_hashes.iter().any(|(key, _)| key == short_key) {
+            return Err(ViewError::not_found("collection entry for key", short_key));
+        }
+        Ok(proof.in_collection_entry(short_key.to_vec(), entry_hashes))
     }
 }
 
@@ -1063,6 +1117,41 @@ where
     }
 }
 
+impl<C, I, W> CollectionView<C, I, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Clone + Send + Sync + Serialize + DeserializeOwned,
+    W: HashableView<C, Hasher = sha3::Sha3_256> + Send + Sync + 'static,
+{
+    /// Extends a proof about the subview at `index` to this collection.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::collection_view::CollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view: CollectionView<_, u64, RegisterView<_, u32>> =
+    ///     CollectionView::load(context).await.unwrap();
+    /// view.load_entry_mut(&23).await.unwrap().set(42);
+    /// let proof = view.load_entry_or_insert(&23).await.unwrap().proof().unwrap();
+    /// let proof = view.extend_proof(&23, proof).await.unwrap();
+    /// let path = ProofPath::register().in_collection_entry(bcs::to_bytes(&23u64).unwrap());
+    /// proof.verify(&path, &view.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<u32>().unwrap(), Some(42));
+    /// # })
+    /// ```
+    pub async fn extend_proof<Q>(&self, index: &Q, proof: ViewProof) -> Result<ViewProof, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        self.collection.extend_proof(&short_key, proof).await
+    }
+}
+
 /// A map view that serializes the indices.
 #[derive(Debug)]
 pub struct CustomCollectionView<C, I, W> {
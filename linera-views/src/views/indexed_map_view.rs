@@ -0,0 +1,723 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `IndexedMapView` implements a map with secondary indexes.
+//!
+//! The values of the map declare, through the [`IndexedValue`] trait, the keys under which
+//! each secondary index finds them. The indexes are updated together with the entries of the
+//! map, so that flushing the view writes both in the same [`Batch`]. The keys of an index are
+//! ordered lexicographically, which allows exact, prefix and range queries on them.
+//!
+//! ```rust
+//! # tokio_test::block_on(async {
+//! use linera_views::{
+//!     context::MemoryContext,
+//!     indexed_map_view::{IndexedMapView, IndexedValue},
+//!     views::View,
+//! };
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! struct Account {
+//!     owner: String,
+//!     balance: u64,
+//! }
+//!
+//! impl IndexedValue for Account {
+//!     const INDEXES: &'static [&'static str] = &["owner", "balance"];
+//!
+//!     fn index_keys(&self, index: usize) -> Vec<Vec<u8>> {
+//!         match index {
+//!             0 => vec![self.owner.as_bytes().to_vec()],
+//!             // Big-endian, so that the order of the keys is the order of the numbers.
+//!             _ => vec![self.balance.to_be_bytes().to_vec()],
+//!         }
+//!     }
+//! }
+//!
+//! let context = MemoryContext::new_for_testing(());
+//! let mut accounts: IndexedMapView<_, u32, Account> = IndexedMapView::load(context).await.unwrap();
+//! let alice = Account { owner: "alice".into(), balance: 10 };
+//! let bob = Account { owner: "bob".into(), balance: 20 };
+//! accounts.insert(&1, alice.clone()).await.unwrap();
+//! accounts.insert(&2, bob.clone()).await.unwrap();
+//! assert_eq!(
+//!     accounts.find_by_index("owner", b"bob").await.unwrap(),
+//!     vec![(2, bob)]
+//! );
+//! let low = 0u64.to_be_bytes().to_vec();
+//! let high = 15u64.to_be_bytes().to_vec();
+//! assert_eq!(
+//!     accounts.find_by_index_range("balance", low..high).await.unwrap(),
+//!     vec![(1, alice)]
+//! );
+//! # })
+//! ```
+
+use std::{
+    borrow::Borrow,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+};
+
+use async_trait::async_trait;
+use linera_base::ensure;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    batch::Batch,
+    context::Context,
+    map_view::ByteMapView,
+    views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
+};
+
+/// A value stored in an [`IndexedMapView`], with the keys under which the secondary indexes
+/// of the map find it.
+pub trait IndexedValue {
+    /// The names of the secondary indexes, in the order of their positions. There can be at
+    /// most 256 of them.
+    const INDEXES: &'static [&'static str];
+
+    /// Returns the keys of the value in the secondary index at position `index`. A value may
+    /// have no key, or several keys, in an index. The keys are compared lexicographically,
+    /// so numbers should be encoded in big-endian for range queries to follow their order.
+    fn index_keys(&self, index: usize) -> Vec<Vec<u8>>;
+}
+
+/// Key tags to create the sub-keys of an `IndexedMapView` on top of the base key.
+#[repr(u8)]
+enum KeyTag {
+    /// Prefix for the entries of the map.
+    Entries = MIN_VIEW_TAG,
+    /// Prefix for the entries of the secondary indexes.
+    Indexes,
+}
+
+/// A map view whose entries can also be looked up by the keys of secondary indexes. The
+/// ordering of the entries is determined by the serialization of the context.
+///
+/// An entry of the index at position `p` is stored under
+/// `[p] ++ escape(index_key) ++ [0, 0] ++ short_key`, where `short_key` is the key of the
+/// entry of the map, and `escape` replaces each zero byte with `[0, 255]`. Unlike a length
+/// prefix, this delimits the index key while keeping the lexicographic order of the index
+/// keys, which prefix and range queries rely on.
+#[derive(Debug)]
+pub struct IndexedMapView<C, I, V> {
+    context: C,
+    entries: ByteMapView<C, V>,
+    indexes: ByteMapView<C, ()>,
+    _phantom: PhantomData<I>,
+}
+
+/// The two bytes that end an escaped index key.
+const INDEX_KEY_END: [u8; 2] = [0, 0];
+/// The byte following a zero byte of an index key, once escaped.
+const ESCAPED_ZERO: u8 = 255;
+
+/// Appends `index_key` to `key`, escaping its zero bytes.
+fn escape_index_key(key: &mut Vec<u8>, index_key: &[u8]) {
+    for byte in index_key {
+        key.push(*byte);
+        if *byte == 0 {
+            key.push(ESCAPED_ZERO);
+        }
+    }
+}
+
+/// Splits the rest of an escaped index key, up to its end, from the short key that follows
+/// it. Returns the unescaped rest of the index key and the short key.
+fn split_index_key(key: &[u8]) -> Result<(Vec<u8>, &[u8]), ViewError> {
+    let mut index_key = Vec::new();
+    let mut rest = key;
+    loop {
+        match rest {
+            [0, 0, short_key @ ..] => return Ok((index_key, short_key)),
+            [0, ESCAPED_ZERO, tail @ ..] => {
+                index_key.push(0);
+                rest = tail;
+            }
+            [byte, tail @ ..] if *byte != 0 => {
+                index_key.push(*byte);
+                rest = tail;
+            }
+            _ => return Err(ViewError::InconsistentEntries),
+        }
+    }
+}
+
+#[async_trait]
+impl<C, I, V> View<C> for IndexedMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync,
+    V: Send + Sync + Serialize,
+{
+    const NUM_INIT_KEYS: usize =
+        ByteMapView::<C, V>::NUM_INIT_KEYS + ByteMapView::<C, ()>::NUM_INIT_KEYS;
+
+    fn context(&self) -> &C {
+        &self.context
+    }
+
+    fn pre_load(context: &C) -> Result<Vec<Vec<u8>>, ViewError> {
+        let mut keys = ByteMapView::<C, V>::pre_load(&Self::entries_context(context))?;
+        keys.extend(ByteMapView::<C, ()>::pre_load(&Self::indexes_context(
+            context,
+        ))?);
+        Ok(keys)
+    }
+
+    fn post_load(context: C, values: &[Option<Vec<u8>>]) -> Result<Self, ViewError> {
+        let split = ByteMapView::<C, V>::NUM_INIT_KEYS;
+        let entries = ByteMapView::post_load(
+            Self::entries_context(&context),
+            values.get(..split).ok_or(ViewError::PostLoadValuesError)?,
+        )?;
+        let indexes = ByteMapView::post_load(
+            Self::indexes_context(&context),
+            values.get(split..).ok_or(ViewError::PostLoadValuesError)?,
+        )?;
+        Ok(IndexedMapView {
+            context,
+            entries,
+            indexes,
+            _phantom: PhantomData,
+        })
+    }
+
+    async fn load(context: C) -> Result<Self, ViewError> {
+        let keys = Self::pre_load(&context)?;
+        let values = context.read_multi_values_bytes(keys).await?;
+        Self::post_load(context, &values)
+    }
+
+    fn rollback(&mut self) {
+        self.entries.rollback();
+        self.indexes.rollback();
+    }
+
+    async fn has_pending_changes(&self) -> bool {
+        self.entries.has_pending_changes().await || self.indexes.has_pending_changes().await
+    }
+
+    fn flush(&mut self, batch: &mut Batch) -> Result<bool, ViewError> {
+        let delete_entries = self.entries.flush(batch)?;
+        let delete_indexes = self.indexes.flush(batch)?;
+        Ok(delete_entries && delete_indexes)
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.indexes.clear();
+    }
+}
+
+impl<C, I, V> ClonableView<C> for IndexedMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync,
+    V: Clone + Send + Sync + Serialize,
+{
+    fn clone_unchecked(&mut self) -> Result<Self, ViewError> {
+        Ok(IndexedMapView {
+            context: self.context.clone(),
+            entries: self.entries.clone_unchecked()?,
+            indexes: self.indexes.clone_unchecked()?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<C, I, V> IndexedMapView<C, I, V>
+where
+    C: Context,
+{
+    /// Returns the context of the entries of the map.
+    fn entries_context(context: &C) -> C {
+        context.clone_with_base_key(context.base_tag(KeyTag::Entries as u8))
+    }
+
+    /// Returns the context of the entries of the secondary indexes.
+    fn indexes_context(context: &C) -> C {
+        context.clone_with_base_key(context.base_tag(KeyTag::Indexes as u8))
+    }
+}
+
+impl<C, I, V> IndexedMapView<C, I, V>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+    I: Serialize,
+    V: IndexedValue + Clone + DeserializeOwned + 'static,
+{
+    /// Inserts or resets a value at an index, and updates the secondary indexes.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::indexed_map_view::{IndexedMapView, IndexedValue};
+    /// # use linera_views::views::View;
+    /// # #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    /// # struct Name(String);
+    /// # impl IndexedValue for Name {
+    /// #     const INDEXES: &'static [&'static str] = &["name"];
+    /// #     fn index_keys(&self, _index: usize) -> Vec<Vec<u8>> {
+    /// #         vec![self.0.as_bytes().to_vec()]
+    /// #     }
+    /// # }
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: IndexedMapView<_, u32, Name> = IndexedMapView::load(context).await.unwrap();
+    /// map.insert(&7, Name("alice".into())).await.unwrap();
+    /// map.insert(&7, Name("bob".into())).await.unwrap();
+    /// assert!(map.find_by_index("name", b"alice").await.unwrap().is_empty());
+    /// assert_eq!(map.find_by_index("name", b"bob").await.unwrap().len(), 1);
+    /// # })
+    /// ```
+    pub async fn insert<Q>(&mut self, index: &Q, value: V) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        if let Some(old_value) = self.entries.get(&short_key).await? {
+            self.remove_index_entries(&short_key, &old_value)?;
+        }
+        for (position, index_key) in Self::index_entries(&value)? {
+            let key = Self::index_entry_key(position, &index_key, &short_key);
+            self.indexes.insert(key, ());
+        }
+        self.entries.insert(short_key, value);
+        Ok(())
+    }
+
+    /// Removes a value and its entries in the secondary indexes. If absent then the
+    /// operation does nothing.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::indexed_map_view::{IndexedMapView, IndexedValue};
+    /// # use linera_views::views::View;
+    /// # #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    /// # struct Name(String);
+    /// # impl IndexedValue for Name {
+    /// #     const INDEXES: &'static [&'static str] = &["name"];
+    /// #     fn index_keys(&self, _index: usize) -> Vec<Vec<u8>> {
+    /// #         vec![self.0.as_bytes().to_vec()]
+    /// #     }
+    /// # }
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: IndexedMapView<_, u32, Name> = IndexedMapView::load(context).await.unwrap();
+    /// map.insert(&7, Name("alice".into())).await.unwrap();
+    /// map.remove(&7).await.unwrap();
+    /// assert!(map.get(&7).await.unwrap().is_none());
+    /// assert!(map.find_by_index("name", b"alice").await.unwrap().is_empty());
+    /// # })
+    /// ```
+    pub async fn remove<Q>(&mut self, index: &Q) -> Result<(), ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        if let Some(old_value) = self.entries.get(&short_key).await? {
+            self.remove_index_entries(&short_key, &old_value)?;
+            self.entries.remove(short_key);
+        }
+        Ok(())
+    }
+
+    /// Reads the value at the given index, if any.
+    pub async fn get<Q>(&self, index: &Q) -> Result<Option<V>, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        self.entries.get(&short_key).await
+    }
+
+    /// Returns `true` if the map contains a value for the specified index.
+    pub async fn contains_key<Q>(&self, index: &Q) -> Result<bool, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        self.entries.contains_key(&short_key).await
+    }
+
+    /// Obtains the extra data.
+    pub fn extra(&self) -> &C::Extra {
+        self.entries.extra()
+    }
+
+    /// Removes the entries of `value`, stored at `short_key`, from the secondary indexes.
+    fn remove_index_entries(&mut self, short_key: &[u8], value: &V) -> Result<(), ViewError> {
+        for (position, index_key) in Self::index_entries(value)? {
+            let key = Self::index_entry_key(position, &index_key, short_key);
+            self.indexes.remove(key);
+        }
+        Ok(())
+    }
+
+    /// Returns the positions of the secondary indexes and the keys of `value` in them.
+    fn index_entries(value: &V) -> Result<Vec<(u8, Vec<u8>)>, ViewError> {
+        ensure!(
+            V::INDEXES.len() <= 256,
+            ViewError::TooManyIndexes(V::INDEXES.len())
+        );
+        let mut entries = Vec::new();
+        for (index, position) in (0..V::INDEXES.len()).zip(0..=u8::MAX) {
+            for index_key in value.index_keys(index) {
+                entries.push((position, index_key));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Returns the key of an entry of the index at `position`.
+    fn index_entry_key(position: u8, index_key: &[u8], short_key: &[u8]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(3 + index_key.len() + short_key.len());
+        key.push(position);
+        escape_index_key(&mut key, index_key);
+        key.extend_from_slice(&INDEX_KEY_END);
+        key.extend_from_slice(short_key);
+        key
+    }
+}
+
+impl<C, I, V> IndexedMapView<C, I, V>
+where
+    C: Context + Sync,
+    ViewError: From<C::Error>,
+    I: Send + DeserializeOwned,
+    V: IndexedValue + Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    /// Returns the list of indices in the map. The order is determined by serialization.
+    pub async fn indices(&self) -> Result<Vec<I>, ViewError> {
+        let mut indices = Vec::new();
+        self.entries
+            .for_each_key(
+                |key| {
+                    indices.push(C::deserialize_value(key)?);
+                    Ok(())
+                },
+                Vec::new(),
+            )
+            .await?;
+        Ok(indices)
+    }
+
+    /// Returns the list of indices and values of the map, in the order determined by
+    /// serialization.
+    pub async fn index_values(&self) -> Result<Vec<(I, V)>, ViewError> {
+        let mut index_values = Vec::new();
+        for (key, value) in self.entries.key_values().await? {
+            index_values.push((C::deserialize_value(&key)?, value));
+        }
+        Ok(index_values)
+    }
+
+    /// Obtains the number of entries in the map.
+    pub async fn count(&self) -> Result<usize, ViewError> {
+        self.entries.count().await
+    }
+
+    /// Returns the entries whose key in the secondary index `index` is `index_key`, ordered
+    /// by their index in the map.
+    pub async fn find_by_index(
+        &self,
+        index: &str,
+        index_key: &[u8],
+    ) -> Result<Vec<(I, V)>, ViewError> {
+        let key_length = index_key.len();
+        self.find_while(index, index_key, |key| Some(key.len() == key_length))
+            .await
+    }
+
+    /// Returns the entries with a key in the secondary index `index` that starts with
+    /// `prefix`, ordered by their key in the index.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::context::MemoryContext;
+    /// # use linera_views::indexed_map_view::{IndexedMapView, IndexedValue};
+    /// # use linera_views::views::View;
+    /// # #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    /// # struct Name(String);
+    /// # impl IndexedValue for Name {
+    /// #     const INDEXES: &'static [&'static str] = &["name"];
+    /// #     fn index_keys(&self, _index: usize) -> Vec<Vec<u8>> {
+    /// #         vec![self.0.as_bytes().to_vec()]
+    /// #     }
+    /// # }
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: IndexedMapView<_, u32, Name> = IndexedMapView::load(context).await.unwrap();
+    /// map.insert(&1, Name("carol".into())).await.unwrap();
+    /// map.insert(&2, Name("alice".into())).await.unwrap();
+    /// map.insert(&3, Name("alan".into())).await.unwrap();
+    /// let entries = map.find_by_index_prefix("name", b"al").await.unwrap();
+    /// assert_eq!(
+    ///     entries,
+    ///     vec![(3, Name("alan".into())), (2, Name("alice".into()))]
+    /// );
+    /// # })
+    /// ```
+    pub async fn find_by_index_prefix(
+        &self,
+        index: &str,
+        prefix: &[u8],
+    ) -> Result<Vec<(I, V)>, ViewError> {
+        self.find_while(index, prefix, |_| Some(true)).await
+    }
+
+    /// Returns the entries with a key in the secondary index `index` within `range`,
+    /// ordered by their key in the index. Only the keys starting with the common prefix of
+    /// the bounds of the range are scanned, up to the end of the range.
+    pub async fn find_by_index_range<R>(
+        &self,
+        index: &str,
+        range: R,
+    ) -> Result<Vec<(I, V)>, ViewError>
+    where
+        R: RangeBounds<Vec<u8>> + Send + Sync,
+    {
+        let prefix = match (range.start_bound(), range.end_bound()) {
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) => {
+                let length = start
+                    .iter()
+                    .zip(end)
+                    .take_while(|(start_byte, end_byte)| start_byte == end_byte)
+                    .count();
+                start[..length].to_vec()
+            }
+            _ => Vec::new(),
+        };
+        self.find_while(index, &prefix, |key| {
+            let key = key.to_vec();
+            let after_end = match range.end_bound() {
+                Bound::Included(end) => key > *end,
+                Bound::Excluded(end) => key >= *end,
+                Bound::Unbounded => false,
+            };
+            if after_end {
+                return None;
+            }
+            Some(match range.start_bound() {
+                Bound::Included(start) => key >= *start,
+                Bound::Excluded(start) => key > *start,
+                Bound::Unbounded => true,
+            })
+        })
+        .await
+    }
+
+    /// Returns the entries with a key in the secondary index `index` that starts with
+    /// `prefix` and is accepted by `accept`, ordered by their key in the index. The scan
+    /// stops at the first key for which `accept` returns `None`.
+    async fn find_while<F>(
+        &self,
+        index: &str,
+        prefix: &[u8],
+        mut accept: F,
+    ) -> Result<Vec<(I, V)>, ViewError>
+    where
+        F: FnMut(&[u8]) -> Option<bool> + Send,
+    {
+        ensure!(
+            V::INDEXES.len() <= 256,
+            ViewError::TooManyIndexes(V::INDEXES.len())
+        );
+        let position = V::INDEXES
+            .iter()
+            .position(|name| *name == index)
+            .ok_or_else(|| ViewError::not_found("secondary index", index))?;
+        let mut key_prefix = vec![position as u8];
+        escape_index_key(&mut key_prefix, prefix);
+        let mut short_keys = Vec::new();
+        self.indexes
+            .for_each_key_while(
+                |key| {
+                    let (index_suffix, short_key) = split_index_key(key)?;
+                    let index_key = [prefix, &index_suffix].concat();
+                    match accept(&index_key) {
+                        None => return Ok(false),
+                        Some(true) => short_keys.push(short_key.to_vec()),
+                        Some(false) => {}
+                    }
+                    Ok(true)
+                },
+                key_prefix,
+            )
+            .await?;
+        let values = self.entries.multi_get(short_keys.clone()).await?;
+        let mut entries = Vec::new();
+        for (short_key, value) in short_keys.into_iter().zip(values) {
+            let value = value.ok_or(ViewError::InconsistentEntries)?;
+            entries.push((C::deserialize_value(&short_key)?, value));
+        }
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl<C, I, V> HashableView<C> for IndexedMapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize + DeserializeOwned,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    type Hasher = sha3::Sha3_256;
+
+    // The secondary indexes are derived from the entries, so only the entries are hashed.
+    async fn hash_mut(&mut self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        self.entries.hash_mut().await
+    }
+
+    async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError> {
+        self.entries.hash().await
+    }
+}
+
+mod graphql {
+    use std::borrow::Cow;
+
+    use super::{IndexedMapView, IndexedValue};
+    use crate::{
+        context::Context,
+        graphql::{hash_name, mangle, Entry, MapInput},
+    };
+
+    impl<C: Send + Sync, I: async_graphql::OutputType, V: async_graphql::OutputType>
+        async_graphql::TypeName for IndexedMapView<C, I, V>
+    {
+        fn type_name() -> Cow<'static, str> {
+            format!(
+                "IndexedMapView_{}_{}_{:08x}",
+                mangle(I::type_name()),
+                mangle(V::type_name()),
+                hash_name::<(I, V)>(),
+            )
+            .into()
+        }
+    }
+
+    #[async_graphql::Object(cache_control(no_cache), name_type)]
+    impl<C, I, V> IndexedMapView<C, I, V>
+    where
+        C: Context + Send + Sync,
+        I: async_graphql::OutputType
+            + async_graphql::InputType
+            + serde::ser::Serialize
+            + serde::de::DeserializeOwned
+            + std::fmt::Debug
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        V: async_graphql::OutputType
+            + IndexedValue
+            + serde::ser::Serialize
+            + serde::de::DeserializeOwned
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+    {
+        async fn keys(&self, count: Option<usize>) -> Result<Vec<I>, async_graphql::Error> {
+            let indices = self.indices().await?;
+            let it = indices.iter().cloned();
+            Ok(if let Some(count) = count {
+                it.take(count).collect()
+            } else {
+                it.collect()
+            })
+        }
+
+        async fn entry(&self, key: I) -> Result<Entry<I, Option<V>>, async_graphql::Error> {
+            Ok(Entry {
+                value: self.get(&key).await?,
+                key,
+            })
+        }
+
+        async fn entries(
+            &self,
+            input: Option<MapInput<I>>,
+        ) -> Result<Vec<Entry<I, Option<V>>>, async_graphql::Error> {
+            let keys = input
+                .and_then(|input| input.filters)
+                .and_then(|filters| filters.keys);
+            let keys = if let Some(keys) = keys {
+                keys
+            } else {
+                self.indices().await?
+            };
+
+            let mut values = vec![];
+            for key in keys {
+                values.push(Entry {
+                    value: self.get(&key).await?,
+                    key,
+                })
+            }
+
+            Ok(values)
+        }
+
+        /// The entries with the given key in the secondary index `index`.
+        async fn by_index(
+            &self,
+            index: String,
+            key: Vec<u8>,
+        ) -> Result<Vec<Entry<I, Option<V>>>, async_graphql::Error> {
+            Ok(into_entries(self.find_by_index(&index, &key).await?))
+        }
+
+        /// The entries with a key in the secondary index `index` starting with `prefix`.
+        async fn by_index_prefix(
+            &self,
+            index: String,
+            prefix: Vec<u8>,
+        ) -> Result<Vec<Entry<I, Option<V>>>, async_graphql::Error> {
+            Ok(into_entries(
+                self.find_by_index_prefix(&index, &prefix).await?,
+            ))
+        }
+
+        /// The entries with a key in the secondary index `index` from `start`, included, to
+        /// `end`, excluded.
+        async fn by_index_range(
+            &self,
+            index: String,
+            start: Option<Vec<u8>>,
+            end: Option<Vec<u8>>,
+        ) -> Result<Vec<Entry<I, Option<V>>>, async_graphql::Error> {
+            use std::ops::Bound;
+
+            let start = start.map_or(Bound::Unbounded, Bound::Included);
+            let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+            Ok(into_entries(
+                self.find_by_index_range(&index, (start, end)).await?,
+            ))
+        }
+    }
+
+    fn into_entries<I, V>(index_values: Vec<(I, V)>) -> Vec<Entry<I, Option<V>>>
+    where
+        I: async_graphql::OutputType,
+        V: async_graphql::OutputType,
+    {
+        index_values
+            .into_iter()
+            .map(|(key, value)| Entry {
+                key,
+                value: Some(value),
+            })
+            .collect()
+    }
+}
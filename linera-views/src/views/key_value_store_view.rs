@@ -36,6 +36,7 @@ use crate::{
     },
     context::Context,
     map_view::ByteMapView,
+    proof::ViewProof,
     store::{KeyIterable, KeyValueIterable},
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
@@ -1138,6 +1139,32 @@ where
         hasher.update_with_bcs_bytes(&count)?;
         Ok(hasher.finalize())
     }
+
+    /// Returns a proof of the value at `index`, or of its absence.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::key_value_store_view::KeyValueStoreView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view = KeyValueStoreView::load(context).await.unwrap();
+    /// view.insert(vec![0, 1], vec![42]).await.unwrap();
+    /// view.insert(vec![0, 2], vec![43]).await.unwrap();
+    /// let proof = view.proof(&[0, 1]).await.unwrap();
+    /// let path = ProofPath::entry(vec![0, 1]);
+    /// proof.verify(&path, &view.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value_bytes(), Some(&[42][..]));
+    /// # })
+    /// ```
+    pub async fn proof(&self, index: &[u8]) -> Result<ViewProof, ViewError> {
+        let mut entries = Vec::new();
+        self.for_each_index_value(|key, value| {
+            entries.push((key.to_vec(), value.to_vec()));
+            Ok(())
+        })
+        .await?;
+        Ok(ViewProof::entry(index.to_vec(), entries))
+    }
 }
 
 #[async_trait]
@@ -56,6 +56,7 @@ use crate::{
     },
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    proof::ViewProof,
     store::{KeyIterable, KeyValueIterable},
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
@@ -942,6 +943,46 @@ where
     }
 }
 
+impl<C, V> ByteMapView<C, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    /// Returns a proof of the value at `short_key`, or of its absence.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::map_view::ByteMapView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map = ByteMapView::load(context).await.unwrap();
+    /// map.insert(vec![0, 1], String::from("Hello"));
+    /// map.insert(vec![0, 2], String::from("Bonjour"));
+    /// let proof = map.proof(&[0, 1]).await.unwrap();
+    /// let path = ProofPath::entry(vec![0, 1]);
+    /// proof.verify(&path, &map.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<String>().unwrap(), Some(String::from("Hello")));
+    /// let proof = map.proof(&[0, 3]).await.unwrap();
+    /// let path = ProofPath::entry(vec![0, 3]);
+    /// proof.verify(&path, &map.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<String>().unwrap(), None);
+    /// # })
+    /// ```
+    pub async fn proof(&self, short_key: &[u8]) -> Result<ViewProof, ViewError> {
+        let mut entries = Vec::new();
+        self.for_each_key_value_or_bytes(
+            |index, value| {
+                entries.push((index.to_vec(), value.into_bytes()?));
+                Ok(())
+            },
+            Vec::new(),
+        )
+        .await?;
+        Ok(ViewProof::entry(short_key.to_vec(), entries))
+    }
+}
+
 /// A `View` that has a type for keys. The ordering of the entries
 /// is determined by the serialization of the context.
 #[derive(Debug)]
@@ -1438,6 +1479,38 @@ where
     }
 }
 
+impl<C, I, V> MapView<C, I, V>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize + DeserializeOwned,
+    V: Clone + Send + Sync + Serialize + DeserializeOwned + 'static,
+{
+    /// Returns a proof of the value at `index`, or of its absence.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::map_view::MapView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut map: MapView<_, u32, _> = MapView::load(context).await.unwrap();
+    /// map.insert(&(37 as u32), String::from("Hello")).unwrap();
+    /// let proof = map.proof(&(37 as u32)).await.unwrap();
+    /// let path = ProofPath::entry(bcs::to_bytes(&(37 as u32)).unwrap());
+    /// proof.verify(&path, &map.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<String>().unwrap(), Some(String::from("Hello")));
+    /// # })
+    /// ```
+    pub async fn proof<Q>(&self, index: &Q) -> Result<ViewProof, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        self.map.proof(&short_key).await
+    }
+}
+
 /// A map view that uses custom serialization
 #[derive(Debug)]
 pub struct CustomMapView<C, I, V> {
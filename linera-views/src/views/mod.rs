@@ -6,8 +6,8 @@ use std::{fmt::Debug, io::Write};
 use async_trait::async_trait;
 use linera_base::{
     crypto::CryptoHash,
-    data_types::ArithmeticError,
-    identifiers::{BlobId, EventId},
+    data_types::{ArithmeticError, BlockHeight},
+    identifiers::{BlobId, ChainId, EventId},
 };
 pub use linera_views_derive::{
     ClonableView, CryptoHashRootView, CryptoHashView, HashableView, RootView, View,
@@ -39,6 +39,9 @@ pub mod map_view;
 /// The `SetView` implements a set with ordered entries.
 pub mod set_view;
 
+/// The `IndexedMapView` implements a map with secondary indexes on its values.
+pub mod indexed_map_view;
+
 /// The `CollectionView` implements a map structure whose keys are ordered and the values are views.
 pub mod collection_view;
 
@@ -139,6 +142,10 @@ pub enum ViewError {
     #[error("The key must not be too long")]
     KeyTooLong,
 
+    /// An `IndexedMapView` supports at most 256 secondary indexes.
+    #[error("Too many secondary indexes: {0}, but at most 256 are supported")]
+    TooManyIndexes(usize),
+
     /// The entry does not exist in memory
     // FIXME(#148): This belongs to a future `linera_storage::StoreError`.
     #[error("Entry does not exist in memory: {0}")]
@@ -167,6 +174,26 @@ pub enum ViewError {
     /// Some events were not found.
     #[error("Events not found: {0:?}")]
     EventsNotFound(Vec<EventId>),
+
+    /// The certificate was deleted according to the node's retention policy.
+    #[error("Certificate {hash} of chain {chain_id} at height {height} was pruned")]
+    CertificatePruned {
+        /// The hash of the certificate.
+        hash: CryptoHash,
+        /// The chain of the certificate.
+        chain_id: ChainId,
+        /// The height of the certificate.
+        height: BlockHeight,
+    },
+
+    /// The stored data of a view cannot be migrated to its current schema version.
+    #[error("Cannot migrate the stored schema version {stored} to version {current}")]
+    UnsupportedSchemaVersion {
+        /// The schema version of the stored data.
+        stored: u32,
+        /// The schema version of the view type.
+        current: u32,
+    },
 }
 
 impl ViewError {
@@ -197,6 +224,25 @@ pub trait HashableView<C>: View<C> {
     async fn hash(&self) -> Result<<Self::Hasher as Hasher>::Output, ViewError>;
 }
 
+/// A view deriving `HashableView`, whose hash is computed from the hashes of its fields.
+#[async_trait]
+pub trait HashableFields<C>: HashableView<C> {
+    /// The names of the fields, in the order in which they are hashed.
+    const FIELD_NAMES: &'static [&'static str];
+
+    /// Returns the position of the field `name` in the hash of the view.
+    fn field_index(name: &str) -> Result<usize, ViewError> {
+        Self::FIELD_NAMES
+            .iter()
+            .position(|field_name| *field_name == name)
+            .ok_or_else(|| ViewError::not_found("field", name))
+    }
+
+    /// Returns the hashes of all the fields, in order, e.g. to extend a proof about one of
+    /// them with `ViewProof::in_field`.
+    async fn field_hashes(&self) -> Result<Vec<HasherOutput>, ViewError>;
+}
+
 /// The requirement for the hasher type in [`HashableView`].
 pub trait Hasher: Default + Write + Send + Sync + 'static {
     /// The output type.
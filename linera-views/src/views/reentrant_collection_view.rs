@@ -28,6 +28,7 @@ use crate::{
     common::{CustomSerialize, HasherOutput, Update},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    proof::ViewProof,
     store::KeyIterable,
     views::{ClonableView, HashableView, Hasher, View, ViewError, MIN_VIEW_TAG},
 };
@@ -1018,9 +1019,28 @@ where
         #[cfg(with_metrics)]
         let _hash_latency = REENTRANT_COLLECTION_VIEW_HASH_RUNTIME.measure_latency();
         let mut hasher = sha3::Sha3_256::default();
-        let keys = self.keys().await?;
-        let count = keys.len() as u32;
+        let entry_hashes = self.entry_hashes().await?;
+        let count = entry_hashes.len() as u32;
         hasher.update_with_bcs_bytes(&count)?;
+        for (key, hash) in entry_hashes {
+            hasher.update_with_bytes(&key)?;
+            hasher.write_all(hash.as_ref())?;
+        }
+        Ok(hasher.finalize())
+    }
+}
+
+impl<C, W> ReentrantByteCollectionView<C, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    W: HashableView<C> + Send + Sync + 'static,
+{
+    /// Returns the keys of the collection, with the hashes of their entries.
+    async fn entry_hashes(
+        &self,
+    ) -> Result<Vec<(Vec<u8>, <W::Hasher as Hasher>::Output)>, ViewError> {
+        let keys = self.keys().await?;
         let mut cached_entries_result = Vec::new();
         {
             let cached_entries = self.cached_entries.lock().unwrap();
@@ -1028,20 +1048,20 @@ where
                 cached_entries_result.push(cached_entries.get(key).cloned());
             }
         }
+        let mut entry_hashes = Vec::with_capacity(keys.len());
         for (key, cached_entry) in keys.into_iter().zip(cached_entries_result) {
-            hasher.update_with_bytes(&key)?;
             let hash = if let Some(entry) = self.updates.get(&key) {
                 let Update::Set(view) = entry else {
                     unreachable!();
                 };
                 let view = view
                     .try_read_arc()
-                    .ok_or_else(|| ViewError::TryLockError(key))?;
+                    .ok_or_else(|| ViewError::TryLockError(key.clone()))?;
                 view.hash().await?
             } else if let Some(view) = cached_entry {
                 let view = view
                     .try_read_arc()
-                    .ok_or_else(|| ViewError::TryLockError(key))?;
+                    .ok_or_else(|| ViewError::TryLockError(key.clone()))?;
                 view.hash().await?
             } else {
                 let key = self.context.base_tag_index(KeyTag::Subview as u8, &key);
@@ -1049,9 +1069,45 @@ where
                 let view = W::load(context).await?;
                 view.hash().await?
             };
-            hasher.write_all(hash.as_ref())?;
+            entry_hashes.push((key, hash));
         }
-        Ok(hasher.finalize())
+        Ok(entry_hashes)
+    }
+
+    /// Extends a proof about the subview at `short_key` to this collection.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::reentrant_collection_view::ReentrantByteCollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view: ReentrantByteCollectionView<_, RegisterView<_, u32>> =
+    ///     ReentrantByteCollectionView::load(context).await.unwrap();
+    /// view.try_load_entry_mut(&[0, 1]).await.unwrap().set(42);
+    /// let proof = {
+    ///     let subview = view.try_load_entry(&[0, 1]).await.unwrap().unwrap();
+    ///     subview.proof().unwrap()
+    /// };
+    /// let proof = view.extend_proof(&[0, 1], proof).await.unwrap();
+    /// let path = ProofPath::register().in_collection_entry(vec![0, 1]);
+    /// proof.verify(&path, &view.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<u32>().unwrap(), Some(42));
+    /// # })
+    /// ```
+    pub async fn extend_proof(
+        &self,
+        short_key: &[u8],
+        proof: ViewProof,
+    ) -> Result<ViewProof, ViewError>
+    where
+        W: HashableView<C, Hasher = sha3::Sha3_256>,
+    {
+        let entry_hashes = self.entry_hashes().await?;
+        if !entry_hashes.iter().any(|(key, _)| key == short_key) {
+            return Err(ViewError::not_found("collection entry for key", short_key));
+        }
+        Ok(proof.in_collection_entry(short_key.to_vec(), entry_hashes))
     }
 }
 
@@ -1574,6 +1630,41 @@ where
     }
 }
 
+impl<C, I, W> ReentrantCollectionView<C, I, W>
+where
+    C: Context + Send + Sync,
+    ViewError: From<C::Error>,
+    I: Send + Sync + Serialize + DeserializeOwned,
+    W: HashableView<C, Hasher = sha3::Sha3_256> + Send + Sync + 'static,
+{
+    /// Extends a proof about the subview at `index` to this collection.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::reentrant_collection_view::ReentrantCollectionView;
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut view: ReentrantCollectionView<_, u64, RegisterView<_, u32>> =
+    ///     ReentrantCollectionView::load(context).await.unwrap();
+    /// view.try_load_entry_mut(&23).await.unwrap().set(42);
+    /// let proof = view.try_load_entry(&23).await.unwrap().unwrap().proof().unwrap();
+    /// let proof = view.extend_proof(&23, proof).await.unwrap();
+    /// let path = ProofPath::register().in_collection_entry(bcs::to_bytes(&23u64).unwrap());
+    /// proof.verify(&path, &view.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<u32>().unwrap(), Some(42));
+    /// # })
+    /// ```
+    pub async fn extend_proof<Q>(&self, index: &Q, proof: ViewProof) -> Result<ViewProof, ViewError>
+    where
+        I: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let short_key = C::derive_short_key(index)?;
+        self.collection.extend_proof(&short_key, proof).await
+    }
+}
+
 /// A view that supports accessing a collection of views of the same kind, indexed by an ordered key,
 /// possibly several subviews at a time.
 #[derive(Debug)]
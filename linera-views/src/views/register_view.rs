@@ -19,6 +19,7 @@ use crate::{
     common::{from_bytes_option_or_default, HasherOutput},
     context::Context,
     hashable_wrapper::WrappedHashableContainerView,
+    proof::ViewProof,
     views::{ClonableView, HashableView, Hasher, View, ViewError},
 };
 
@@ -209,6 +210,24 @@ where
         hasher.update_with_bcs_bytes(self.get())?;
         Ok(hasher.finalize())
     }
+
+    /// Returns a proof of the value in the register.
+    /// ```rust
+    /// # tokio_test::block_on(async {
+    /// # use linera_views::{context::MemoryContext, proof::ProofPath};
+    /// # use linera_views::register_view::RegisterView;
+    /// # use linera_views::views::{HashableView, View};
+    /// # let context = MemoryContext::new_for_testing(());
+    /// let mut register: RegisterView<_, u32> = RegisterView::load(context).await.unwrap();
+    /// register.set(5);
+    /// let proof = register.proof().unwrap();
+    /// proof.verify(&ProofPath::register(), &register.hash().await.unwrap()).unwrap();
+    /// assert_eq!(proof.value::<u32>().unwrap(), Some(5));
+    /// # })
+    /// ```
+    pub fn proof(&self) -> Result<ViewProof, ViewError> {
+        Ok(ViewProof::register(bcs::to_bytes(self.get())?))
+    }
 }
 
 #[async_trait]
@@ -12,7 +12,10 @@ use linera_views::scylla_db::ScyllaDbStore;
 use linera_views::{
     memory::MemoryStore,
     store::TestKeyValueStore,
-    test_utils::{namespace_admin_test, root_key_admin_test},
+    test_utils::{
+        migration_admin_test, mirrored_migration_test, namespace_admin_test, root_key_admin_test,
+        snapshot_admin_test, snapshot_between_stores_test,
+    },
 };
 use test_case::test_case;
 
@@ -33,3 +36,47 @@ async fn namespace_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomDat
 async fn root_key_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomData<K>) {
     root_key_admin_test::<K>().await;
 }
+
+#[test_case(PhantomData::<MemoryStore>; "MemoryStore")]
+#[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbStore>; "RocksDbStore"))]
+#[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbStore>; "DynamoDbStore"))]
+#[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbStore>; "ScyllaDbStore"))]
+#[tokio::test]
+async fn snapshot_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomData<K>) {
+    snapshot_admin_test::<K>().await;
+}
+
+#[test_case(PhantomData::<(MemoryStore, MemoryStore)>; "MemoryStore to MemoryStore")]
+#[cfg_attr(with_rocksdb, test_case(PhantomData::<(MemoryStore, RocksDbStore)>; "MemoryStore to RocksDbStore"))]
+#[cfg_attr(with_rocksdb, test_case(PhantomData::<(RocksDbStore, MemoryStore)>; "RocksDbStore to MemoryStore"))]
+#[cfg_attr(with_scylladb, test_case(PhantomData::<(MemoryStore, ScyllaDbStore)>; "MemoryStore to ScyllaDbStore"))]
+#[cfg_attr(all(with_rocksdb, with_scylladb), test_case(PhantomData::<(RocksDbStore, ScyllaDbStore)>; "RocksDbStore to ScyllaDbStore"))]
+#[tokio::test]
+async fn snapshot_between_stores_test_cases<K1, K2>(_view_type: PhantomData<(K1, K2)>)
+where
+    K1: TestKeyValueStore,
+    K2: TestKeyValueStore,
+{
+    snapshot_between_stores_test::<K1, K2>().await;
+}
+
+#[test_case(PhantomData::<MemoryStore>; "MemoryStore")]
+#[cfg_attr(with_rocksdb, test_case(PhantomData::<RocksDbStore>; "RocksDbStore"))]
+#[cfg_attr(with_dynamodb, test_case(PhantomData::<DynamoDbStore>; "DynamoDbStore"))]
+#[cfg_attr(with_scylladb, test_case(PhantomData::<ScyllaDbStore>; "ScyllaDbStore"))]
+#[tokio::test]
+async fn migration_admin_test_cases<K: TestKeyValueStore>(_view_type: PhantomData<K>) {
+    migration_admin_test::<K>().await;
+}
+
+#[cfg(with_rocksdb)]
+#[test_case(PhantomData::<(MemoryStore, RocksDbStore)>; "MemoryStore to RocksDbStore")]
+#[cfg_attr(with_scylladb, test_case(PhantomData::<(RocksDbStore, ScyllaDbStore)>; "RocksDbStore to ScyllaDbStore"))]
+#[tokio::test]
+async fn mirrored_migration_test_cases<K1, K2>(_view_type: PhantomData<(K1, K2)>)
+where
+    K1: TestKeyValueStore,
+    K2: TestKeyValueStore,
+{
+    mirrored_migration_test::<K1, K2>().await;
+}
@@ -0,0 +1,206 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use linera_views::{
+    batch::Batch,
+    context::{Context, MemoryContext},
+    indexed_map_view::{IndexedMapView, IndexedValue},
+    map_view::MapView,
+    views::{HashableView, View, ViewError},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Order {
+    customer: String,
+    tags: Vec<String>,
+    amount: u64,
+}
+
+impl IndexedValue for Order {
+    const INDEXES: &'static [&'static str] = &["customer", "tag", "amount"];
+
+    fn index_keys(&self, index: usize) -> Vec<Vec<u8>> {
+        match index {
+            0 => vec![self.customer.as_bytes().to_vec()],
+            1 => self
+                .tags
+                .iter()
+                .map(|tag| tag.as_bytes().to_vec())
+                .collect(),
+            _ => vec![self.amount.to_be_bytes().to_vec()],
+        }
+    }
+}
+
+fn order(customer: &str, tags: &[&str], amount: u64) -> Order {
+    Order {
+        customer: customer.to_string(),
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        amount,
+    }
+}
+
+fn amount(amount: u64) -> Vec<u8> {
+    amount.to_be_bytes().to_vec()
+}
+
+type Orders = IndexedMapView<MemoryContext<()>, u32, Order>;
+
+#[tokio::test]
+async fn test_indexes_are_saved_with_the_entries() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut orders = Orders::load(context.clone()).await?;
+    orders
+        .insert(&1, order("alice", &["red", "big"], 300))
+        .await?;
+    orders.insert(&2, order("bob", &["red"], 100)).await?;
+    orders.insert(&3, order("alice", &[], 200)).await?;
+    let mut batch = Batch::new();
+    orders.flush(&mut batch)?;
+    context.write_batch(batch).await?;
+
+    let mut orders = Orders::load(context.clone()).await?;
+    let by_customer = orders.find_by_index("customer", b"alice").await?;
+    assert_eq!(
+        by_customer.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 3]
+    );
+    let by_tag = orders.find_by_index("tag", b"red").await?;
+    assert_eq!(
+        by_tag.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    let by_amount = orders
+        .find_by_index_range("amount", amount(150)..=amount(300))
+        .await?;
+    assert_eq!(
+        by_amount.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![3, 1]
+    );
+
+    // Replacing and removing entries updates the indexes in the same batch.
+    orders.insert(&2, order("carol", &["blue"], 100)).await?;
+    orders.remove(&1).await?;
+    let mut batch = Batch::new();
+    orders.flush(&mut batch)?;
+    context.write_batch(batch).await?;
+
+    let orders = Orders::load(context).await?;
+    assert!(orders.find_by_index("customer", b"bob").await?.is_empty());
+    assert_eq!(
+        orders.find_by_index("tag", b"blue").await?,
+        vec![(2, order("carol", &["blue"], 100))]
+    );
+    assert!(orders.find_by_index("tag", b"red").await?.is_empty());
+    assert_eq!(orders.indices().await?, vec![2, 3]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_lookups() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut orders = Orders::load(context).await?;
+    orders.insert(&1, order("al", &[], 1)).await?;
+    orders.insert(&2, order("alice", &[], 2)).await?;
+    orders.insert(&3, order("bob", &[], 3)).await?;
+
+    // An exact lookup does not return the keys that only start with the given one.
+    let exact = orders.find_by_index("customer", b"al").await?;
+    assert_eq!(exact, vec![(1, order("al", &[], 1))]);
+    let prefixed = orders.find_by_index_prefix("customer", b"al").await?;
+    assert_eq!(
+        prefixed.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    let range = orders
+        .find_by_index_range("customer", b"alice".to_vec()..)
+        .await?;
+    assert_eq!(
+        range.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+    assert!(orders.find_by_index("unknown", b"al").await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_index_keys_are_delimited_from_map_keys() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut orders = IndexedMapView::<_, String, Order>::load(context.clone()).await?;
+    // Without a delimiter, both entries of the customer index would be stored under
+    // `"x" ++ [2, 1, b'c']`: the index key `"x"` followed by the serialized `"\u{1}c"`, and
+    // the index key `"x\u{2}"` followed by the serialized `"c"`.
+    let first = order("x", &[], 1);
+    let second = order("x\u{2}", &[], 2);
+    orders.insert("\u{1}c", first.clone()).await?;
+    orders.insert("c", second.clone()).await?;
+    let mut batch = Batch::new();
+    orders.flush(&mut batch)?;
+    context.write_batch(batch).await?;
+
+    let mut orders = IndexedMapView::<_, String, Order>::load(context).await?;
+    assert_eq!(
+        orders.find_by_index("customer", b"x").await?,
+        vec![("\u{1}c".to_string(), first)]
+    );
+    assert_eq!(
+        orders.find_by_index("customer", b"x\x02").await?,
+        vec![("c".to_string(), second.clone())]
+    );
+    assert_eq!(
+        orders.find_by_index_prefix("customer", b"x").await?.len(),
+        2
+    );
+    orders.remove("\u{1}c").await?;
+    assert_eq!(
+        orders.find_by_index("customer", b"x\x02").await?,
+        vec![("c".to_string(), second)]
+    );
+    assert!(orders.find_by_index("customer", b"x").await?.is_empty());
+    Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Unindexable;
+
+impl IndexedValue for Unindexable {
+    const INDEXES: &'static [&'static str] = &["index"; 257];
+
+    fn index_keys(&self, _index: usize) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+}
+
+#[tokio::test]
+async fn test_too_many_indexes() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut map = IndexedMapView::<_, u32, Unindexable>::load(context).await?;
+    assert!(matches!(
+        map.insert(&1, Unindexable).await,
+        Err(ViewError::TooManyIndexes(257))
+    ));
+    assert!(matches!(
+        map.find_by_index("index", b"").await,
+        Err(ViewError::TooManyIndexes(257))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_indexes_do_not_change_the_hash() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut orders = Orders::load(context).await?;
+    let context = MemoryContext::new_for_testing(());
+    let mut plain = MapView::<_, u32, Order>::load(context).await?;
+    for (id, customer) in [(1, "alice"), (2, "bob")] {
+        orders.insert(&id, order(customer, &["red"], 10)).await?;
+        plain.insert(&id, order(customer, &["red"], 10))?;
+    }
+    assert_eq!(orders.hash().await?, plain.hash().await?);
+    orders.clear();
+    assert!(orders.find_by_index("tag", b"red").await?.is_empty());
+    assert_eq!(orders.count().await?, 0);
+    Ok(())
+}
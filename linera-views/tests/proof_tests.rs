@@ -0,0 +1,195 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use linera_views::{
+    collection_view::CollectionView,
+    context::MemoryContext,
+    map_view::MapView,
+    proof::{ProofError, ProofLeaf, ProofPath, ProofStep, ViewProof},
+    register_view::RegisterView,
+    views::{CryptoHashView, HashableFields as _, HashableView, View},
+};
+use linera_views_derive::CryptoHashRootView;
+
+#[derive(CryptoHashRootView)]
+struct ProofTestView<C> {
+    pub register: RegisterView<C, u64>,
+    pub maps: CollectionView<C, u8, MapView<C, String, u64>>,
+}
+
+type TestView = ProofTestView<MemoryContext<()>>;
+
+async fn populated_view() -> Result<TestView> {
+    let context = MemoryContext::new_for_testing(());
+    let mut view = ProofTestView::load(context).await?;
+    view.register.set(7);
+    for index in 0..3u8 {
+        let map = view.maps.load_entry_mut(&index).await?;
+        for key in ["alice", "bob", "carol"] {
+            map.insert(key, u64::from(index) * 100 + key.len() as u64)?;
+        }
+    }
+    Ok(view)
+}
+
+/// Returns the location of the value at `key` in the map at `index`.
+fn map_entry_path(index: u8, key: &str) -> Result<ProofPath> {
+    Ok(ProofPath::entry(bcs::to_bytes(key)?)
+        .in_collection_entry(bcs::to_bytes(&index)?)
+        .in_field(TestView::field_index("maps")?))
+}
+
+/// Returns a proof of the value at `key` in the map at `index`.
+async fn map_entry_proof(view: &TestView, index: u8, key: &str) -> Result<ViewProof> {
+    let map = view.maps.try_load_entry(&index).await?.unwrap();
+    let proof = map.proof(key).await?;
+    drop(map);
+    Ok(view
+        .maps
+        .extend_proof(&index, proof)
+        .await?
+        .in_field(TestView::field_index("maps")?, view.field_hashes().await?))
+}
+
+#[tokio::test]
+async fn test_register_proof() -> Result<()> {
+    let view = populated_view().await?;
+    let proof = view.register.proof()?.in_field(
+        TestView::field_index("register")?,
+        view.field_hashes().await?,
+    );
+    let path = ProofPath::register().in_field(TestView::field_index("register")?);
+    assert_eq!(proof.path(), path);
+    proof.verify(&path, &view.hash().await?)?;
+    proof.verify_crypto_hash(&path, "ProofTestViewHash", &view.crypto_hash().await?)?;
+    assert_eq!(proof.value::<u64>()?, Some(7));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nested_map_proof() -> Result<()> {
+    let view = populated_view().await?;
+    for (key, expected) in [("bob", Some(103)), ("dave", None)] {
+        let proof = map_entry_proof(&view, 1, key).await?;
+        let path = map_entry_path(1, key)?;
+        proof.verify(&path, &view.hash().await?)?;
+        proof.verify_crypto_hash(&path, "ProofTestViewHash", &view.crypto_hash().await?)?;
+        assert_eq!(proof.value::<u64>()?, expected);
+    }
+    assert!(view
+        .maps
+        .extend_proof(&5, view.register.proof()?)
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_proofs_about_other_values_are_rejected() -> Result<()> {
+    let view = populated_view().await?;
+    let hash = view.hash().await?;
+    let proof = map_entry_proof(&view, 2, "alice").await?;
+
+    // A valid proof about another key of the same map.
+    assert!(matches!(
+        proof.verify(&map_entry_path(2, "bob")?, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    // A valid proof about the same key in another map.
+    assert!(matches!(
+        proof.verify(&map_entry_path(1, "alice")?, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    // A valid proof about another field.
+    let register_path = ProofPath::register().in_field(TestView::field_index("register")?);
+    assert!(matches!(
+        proof.verify(&register_path, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    let register_proof = view.register.proof()?.in_field(
+        TestView::field_index("register")?,
+        view.field_hashes().await?,
+    );
+    assert!(matches!(
+        register_proof.verify(&map_entry_path(2, "alice")?, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_proofs_are_rejected() -> Result<()> {
+    let view = populated_view().await?;
+    let proof = map_entry_proof(&view, 2, "alice").await?;
+    let path = map_entry_path(2, "alice")?;
+    let hash = view.hash().await?;
+    proof.verify(&path, &hash)?;
+
+    let mut tampered = proof.clone();
+    let ProofLeaf::Entry { value, .. } = &mut tampered.leaf else {
+        panic!("expected a map entry");
+    };
+    *value = Some(bcs::to_bytes(&1_000_000u64)?);
+    assert!(matches!(
+        tampered.verify(&path, &hash),
+        Err(ProofError::HashMismatch)
+    ));
+
+    let mut tampered = proof.clone();
+    let ProofLeaf::Entry { value, .. } = &mut tampered.leaf else {
+        panic!("expected a map entry");
+    };
+    *value = None;
+    assert!(matches!(
+        tampered.verify(&path, &hash),
+        Err(ProofError::HashMismatch)
+    ));
+
+    let mut tampered = proof.clone();
+    let ProofLeaf::Entry { others, .. } = &mut tampered.leaf else {
+        panic!("expected a map entry");
+    };
+    others.reverse();
+    assert!(matches!(
+        tampered.verify(&path, &hash),
+        Err(ProofError::UnorderedEntries)
+    ));
+
+    // Entries are hashed without length prefixes, as in the views, so moving bytes from the
+    // value to the key keeps the hash. The proof is then about another key, and its value no
+    // longer deserializes.
+    let mut tampered = proof.clone();
+    let ProofLeaf::Entry { key, value, .. } = &mut tampered.leaf else {
+        panic!("expected a map entry");
+    };
+    let value = value.as_mut().unwrap();
+    key.push(value.remove(0));
+    let tampered_key = key.clone();
+    assert!(matches!(
+        tampered.verify(&path, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    assert!(bcs::from_bytes::<String>(&tampered_key).is_err());
+    let tampered_path = ProofPath::entry(tampered_key)
+        .in_collection_entry(bcs::to_bytes(&2u8)?)
+        .in_field(TestView::field_index("maps")?);
+    tampered.verify(&tampered_path, &hash)?;
+    assert!(tampered.value::<u64>().is_err());
+
+    let mut tampered = proof.clone();
+    let ProofStep::Field { index, .. } = &mut tampered.steps[1] else {
+        panic!("expected a field step");
+    };
+    *index = TestView::field_index("register")? as u32;
+    assert!(matches!(
+        tampered.verify(&path, &hash),
+        Err(ProofError::PathMismatch { .. })
+    ));
+    let tampered_path = tampered.path();
+    assert!(matches!(
+        tampered.verify(&tampered_path, &hash),
+        Err(ProofError::HashMismatch)
+    ));
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use linera_views::{
+    batch::Batch,
+    context::{Context, MemoryContext},
+    map_view::MapView,
+    register_view::RegisterView,
+    schema::{self, read_schema_version, SchemaMigrations},
+    test_utils::{check_fixture_loads, read_view_fixture},
+    views::{RootView, View, ViewError},
+};
+
+/// The first version of the view, before versioning.
+#[derive(RootView)]
+struct CounterV0<C> {
+    count: RegisterView<C, u32>,
+    names: MapView<C, u32, String>,
+}
+
+/// The current version of the view: `count` became a `u64` in version 1, and `total` was
+/// added in version 2.
+#[derive(RootView)]
+#[view(version = "2")]
+#[view(migrations = "CounterV2::migrations")]
+struct CounterV2<C> {
+    count: RegisterView<C, u64>,
+    names: MapView<C, u32, String>,
+    total: RegisterView<C, u64>,
+}
+
+impl<C> CounterV2<C>
+where
+    C: Context + Send + Sync + Clone + 'static,
+{
+    fn migrations() -> SchemaMigrations<Self> {
+        SchemaMigrations::new()
+            .eager(0, |key_values, batch| {
+                let count_key = schema::field_key_prefix(0);
+                for (key, value) in key_values {
+                    if *key == count_key {
+                        let count = bcs::from_bytes::<u32>(value)?;
+                        batch.put_key_value(key.clone(), &u64::from(count))?;
+                    }
+                }
+                Ok(())
+            })
+            .lazy(1, Self::compute_total)
+    }
+
+    fn compute_total(&mut self) -> BoxFuture<'_, Result<(), ViewError>> {
+        Box::pin(async move {
+            let total = *self.count.get() + self.names.count().await? as u64;
+            self.total.set(total);
+            Ok(())
+        })
+    }
+}
+
+async fn v0_fixture() -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let context = MemoryContext::new_for_testing(());
+    let mut view = CounterV0::load(context.clone()).await?;
+    view.count.set(40);
+    view.names.insert(&1, "alice".to_string())?;
+    view.names.insert(&2, "bob".to_string())?;
+    view.save().await?;
+    Ok(read_view_fixture(&context).await?)
+}
+
+#[tokio::test]
+async fn test_migrate_unversioned_fixture() -> Result<()> {
+    let fixture = v0_fixture().await?;
+    let context = MemoryContext::new_for_testing(());
+    let view: CounterV2<_> = check_fixture_loads(context.clone(), &fixture).await?;
+    assert_eq!(*view.count.get(), 40);
+    assert_eq!(*view.total.get(), 42);
+    assert_eq!(view.names.get(&2).await?, Some("bob".to_string()));
+    assert_eq!(read_schema_version(&context).await?, Some(2));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lazy_migration_is_saved_with_the_view() -> Result<()> {
+    let fixture = v0_fixture().await?;
+    let context = MemoryContext::new_for_testing(());
+    let mut batch = Batch::new();
+    for (key, value) in fixture {
+        batch.put_key_value_bytes([context.base_key(), key].concat(), value);
+    }
+    context.write_batch(batch).await?;
+
+    // The eager migration is written when loading, the lazy one only when saving.
+    let mut view = CounterV2::load(context.clone()).await?;
+    assert_eq!(*view.total.get(), 42);
+    assert_eq!(read_schema_version(&context).await?, Some(1));
+    view.save().await?;
+    assert_eq!(read_schema_version(&context).await?, Some(2));
+    let view = CounterV2::load(context).await?;
+    assert_eq!(*view.total.get(), 42);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_new_views_have_the_current_version() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut view = CounterV2::load(context.clone()).await?;
+    assert_eq!(read_schema_version(&context).await?, None);
+    view.count.set(3);
+    view.save().await?;
+    assert_eq!(read_schema_version(&context).await?, Some(2));
+    let view = CounterV2::load(context).await?;
+    assert_eq!(*view.count.get(), 3);
+    assert_eq!(*view.total.get(), 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsupported_schema_versions() -> Result<()> {
+    let context = MemoryContext::new_for_testing(());
+    let mut batch = Batch::new();
+    schema::write_schema_version(&context, 3, &mut batch)?;
+    context.write_batch(batch).await?;
+    assert!(matches!(
+        CounterV2::load(context).await,
+        Err(ViewError::UnsupportedSchemaVersion {
+            stored: 3,
+            current: 2
+        })
+    ));
+
+    // Data of version 1 cannot be loaded as a view without migrations.
+    #[allow(dead_code)]
+    #[derive(RootView)]
+    #[view(version = "2")]
+    struct Unmigrated<C> {
+        count: RegisterView<C, u64>,
+    }
+
+    let context = MemoryContext::new_for_testing(());
+    let mut batch = Batch::new();
+    schema::write_schema_version(&context, 1, &mut batch)?;
+    context.write_batch(batch).await?;
+    assert!(matches!(
+        Unmigrated::load(context).await,
+        Err(ViewError::UnsupportedSchemaVersion {
+            stored: 1,
+            current: 2
+        })
+    ));
+    Ok(())
+}
@@ -5,9 +5,12 @@ use linera_views::{
     batch::Batch,
     context::{Context as _, MemoryContext},
     key_value_store_view::ViewContainer,
+    lru_caching::{
+        InvalidationChannel, LruCachingStore, StorageCacheConfig, DEFAULT_STORAGE_CACHE_CONFIG,
+    },
     memory::MemoryStore,
     random::make_deterministic_rng,
-    store::TestKeyValueStore as _,
+    store::{ReadableKeyValueStore as _, TestKeyValueStore as _, WritableKeyValueStore as _},
     test_utils::{
         big_read_multi_values, get_random_test_scenarios, run_big_write_read, run_reads,
         run_writes_from_blank, run_writes_from_state,
@@ -345,3 +348,60 @@ async fn test_scylladb_access() {
 async fn test_dynamodb_access() {
     access_admin_test::<linera_views::dynamo_db::DynamoDbStore>().await
 }
+
+fn cache_config(channel: Option<&InvalidationChannel>) -> StorageCacheConfig {
+    StorageCacheConfig {
+        cache_missing_keys: true,
+        invalidation_channel: channel.cloned(),
+        ..DEFAULT_STORAGE_CACHE_CONFIG
+    }
+}
+
+async fn put(store: &LruCachingStore<MemoryStore>, key: &[u8], value: &[u8]) {
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(key.to_vec(), value.to_vec());
+    store.write_batch(batch).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_lru_caching_invalidations() {
+    let store = MemoryStore::new_test_store().await.unwrap();
+    let channel = InvalidationChannel::new(16);
+    let writer = LruCachingStore::new(store.clone(), cache_config(Some(&channel)));
+    let reader = LruCachingStore::new(store.clone(), cache_config(Some(&channel)));
+    let unaware = LruCachingStore::new(store.clone(), cache_config(None));
+
+    // The missing keys are cached, and only the caches on the channel learn about writes.
+    assert_eq!(reader.read_value_bytes(b"key").await.unwrap(), None);
+    assert!(!unaware.contains_key(b"key").await.unwrap());
+    put(&writer, b"key", b"value").await;
+    assert_eq!(
+        reader.read_value_bytes(b"key").await.unwrap(),
+        Some(b"value".to_vec())
+    );
+    assert!(!unaware.contains_key(b"key").await.unwrap());
+
+    // After a reset, the caches forget the keys written without their knowledge.
+    assert!(!reader.contains_key(b"other key").await.unwrap());
+    put(&unaware, b"other key", b"value").await;
+    assert!(!reader.contains_key(b"other key").await.unwrap());
+    channel.reset();
+    assert!(reader.contains_key(b"other key").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_lru_caching_time_to_live() {
+    let store = MemoryStore::new_test_store().await.unwrap();
+    let config = StorageCacheConfig {
+        entry_ttl: Some(std::time::Duration::ZERO),
+        ..cache_config(None)
+    };
+    let cached = LruCachingStore::new(store.clone(), config);
+    let other = LruCachingStore::new(store, cache_config(None));
+    assert_eq!(cached.read_value_bytes(b"key").await.unwrap(), None);
+    put(&other, b"key", b"value").await;
+    assert_eq!(
+        cached.read_value_bytes(b"key").await.unwrap(),
+        Some(b"value".to_vec())
+    );
+}